@@ -0,0 +1,95 @@
+//! Compression of output files and transparent decompression of inputs.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{self, Read, Write};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression applied to output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Extension appended to the names of compressed files.
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+}
+
+/// A writer compressing what is written to it, if compression is enabled.
+///
+/// [`Encoder::finish`] must be called to write the end of the compressed
+/// stream.
+pub(crate) enum Encoder<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    pub(crate) fn new(writer: W, compression: Option<Compression>) -> io::Result<Self> {
+        Ok(match compression {
+            None => Encoder::Plain(writer),
+            Some(Compression::Gzip) => {
+                Encoder::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+            }
+            Some(Compression::Zstd) => Encoder::Zstd(zstd::Encoder::new(writer, 0)?),
+        })
+    }
+
+    /// Finish the compressed stream and return the underlying writer.
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::Plain(writer) => Ok(writer),
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Plain(writer) => writer.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(writer) => writer.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// `content` compressed with `compression`, or unchanged without it.
+pub(crate) fn compress(content: &[u8], compression: Option<Compression>) -> io::Result<Vec<u8>> {
+    let mut encoder = Encoder::new(Vec::new(), compression)?;
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+/// `content` decompressed if it starts like gzip or zstd data, otherwise
+/// unchanged.
+pub(crate) fn decompress(content: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    if content.starts_with(GZIP_MAGIC) {
+        GzDecoder::new(content.as_slice()).read_to_end(&mut decompressed)?;
+    } else if content.starts_with(ZSTD_MAGIC) {
+        zstd::Decoder::new(content.as_slice())?.read_to_end(&mut decompressed)?;
+    } else {
+        return Ok(content);
+    }
+    Ok(decompressed)
+}
@@ -0,0 +1,586 @@
+//! Interactive terminal explorer for `rustex explore`.
+//!
+//! Shows the module tree of a project on the left and the selected element,
+//! with the elements it references and is referenced by, on the right.
+//! References can be followed and retraced like links in a browser.
+
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use rustex_core::{CodeElement, ElementRef, ElementType, ProjectIndex};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const HELP: &str = "↑↓ move  ←→ collapse/expand  Tab references  Enter follow  \
+                    Backspace back  / search  n next  q quit";
+
+/// Explore `index` until the user quits.
+pub(crate) fn run(index: ProjectIndex) -> Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = Explorer::new(&index).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Tree,
+    References,
+}
+
+/// A line of the module tree.
+enum Row {
+    Module { key: String, depth: usize },
+    Element { id: String, depth: usize },
+}
+
+/// An element referenced by, or referencing, the selected element.
+struct Reference {
+    id: String,
+    outgoing: bool,
+}
+
+struct Explorer<'a> {
+    index: &'a ProjectIndex,
+    /// Element IDs of each module in source order, keyed by module path with
+    /// `crate` replaced by the crate name, so workspace crates stay apart
+    modules: BTreeMap<String, Vec<&'a str>>,
+    /// IDs of the `mod` items, shown as the modules they declare
+    module_items: HashMap<String, &'a str>,
+    expanded: HashSet<String>,
+    rows: Vec<Row>,
+    tree_state: ListState,
+    references: Vec<Reference>,
+    reference_state: ListState,
+    focus: Focus,
+    /// Elements left by following references, most recent last
+    history: Vec<String>,
+    /// Search text while it is being typed
+    search_input: Option<String>,
+    search_matches: Vec<String>,
+    search_position: usize,
+    message: Option<String>,
+}
+
+impl<'a> Explorer<'a> {
+    fn new(index: &'a ProjectIndex) -> Self {
+        let mut elements: Vec<(String, &'a CodeElement, &'a std::path::Path)> = index
+            .ast()
+            .files
+            .iter()
+            .flat_map(|file| {
+                file.elements.iter().map(move |element| {
+                    (module_key(element), element, file.relative_path.as_path())
+                })
+            })
+            .collect();
+        elements.sort_by(|(key_a, a, file_a), (key_b, b, file_b)| {
+            (key_a, file_a, a.location.line_start).cmp(&(key_b, file_b, b.location.line_start))
+        });
+
+        let mut modules: BTreeMap<String, Vec<&'a str>> = BTreeMap::new();
+        let mut module_items = HashMap::new();
+        for (key, element, _) in elements {
+            for ancestor in ancestors(&key) {
+                modules.entry(ancestor.to_string()).or_default();
+            }
+            if element.element_type == ElementType::Module {
+                let module = format!("{}::{}", key, element.name);
+                modules.entry(module.clone()).or_default();
+                module_items.insert(module, element.id.as_str());
+            } else {
+                modules.entry(key).or_default().push(element.id.as_str());
+            }
+        }
+        // Crate roots start expanded.
+        let expanded = modules
+            .keys()
+            .filter(|key| !key.contains("::"))
+            .cloned()
+            .collect();
+
+        let mut explorer = Self {
+            index,
+            modules,
+            module_items,
+            expanded,
+            rows: Vec::new(),
+            tree_state: ListState::default(),
+            references: Vec::new(),
+            reference_state: ListState::default(),
+            focus: Focus::Tree,
+            history: Vec::new(),
+            search_input: None,
+            search_matches: Vec::new(),
+            search_position: 0,
+            message: None,
+        };
+        explorer.rebuild_rows();
+        explorer
+            .tree_state
+            .select((!explorer.rows.is_empty()).then_some(0));
+        explorer.update_references();
+        explorer
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Apply a key press, returning whether to keep exploring.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.message = None;
+        if let Some(input) = &mut self.search_input {
+            match key.code {
+                KeyCode::Esc => self.search_input = None,
+                KeyCode::Enter => {
+                    let query = std::mem::take(input);
+                    self.search_input = None;
+                    self.search(&query);
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Esc if self.focus == Focus::Tree => return false,
+            KeyCode::Esc => self.focus = Focus::Tree,
+            KeyCode::Tab if self.focus == Focus::References => self.focus = Focus::Tree,
+            KeyCode::Tab if !self.references.is_empty() => {
+                self.focus = Focus::References;
+                if self.reference_state.selected().is_none() {
+                    self.reference_state.select(Some(0));
+                }
+            }
+            KeyCode::Backspace | KeyCode::Char('b') => match self.history.pop() {
+                Some(id) => self.select_element(&id),
+                None => self.message = Some("No earlier element".to_string()),
+            },
+            KeyCode::Char('/') => self.search_input = Some(String::new()),
+            KeyCode::Char('n') => self.next_match(),
+            _ if self.focus == Focus::References => self.handle_reference_key(key.code),
+            _ => self.handle_tree_key(key.code),
+        }
+        true
+    }
+
+    fn handle_tree_key(&mut self, code: KeyCode) {
+        let selected = self.tree_state.selected();
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::Home => self.move_selection(isize::MIN / 2),
+            KeyCode::End => self.move_selection(isize::MAX / 2),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => {
+                if let Some(Row::Module { key, .. }) = selected.and_then(|row| self.rows.get(row)) {
+                    let key = key.clone();
+                    self.expanded.insert(key);
+                    self.rebuild_rows();
+                }
+            }
+            // Collapse the selected module, or move to the enclosing one.
+            KeyCode::Left | KeyCode::Char('h') => {
+                let parent = match selected.and_then(|row| self.rows.get(row)) {
+                    Some(Row::Module { key, .. }) if self.expanded.contains(key) => {
+                        let key = key.clone();
+                        self.expanded.remove(&key);
+                        self.rebuild_rows();
+                        return;
+                    }
+                    Some(Row::Module { key, .. }) => {
+                        key.rsplit_once("::").map(|(parent, _)| parent.to_string())
+                    }
+                    Some(Row::Element { id, .. }) => self
+                        .index
+                        .element(id)
+                        .map(|found| module_key(found.element)),
+                    None => None,
+                };
+                if let Some(row) = parent.and_then(|key| self.module_row(&key)) {
+                    self.tree_state.select(Some(row));
+                    self.update_references();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_reference_key(&mut self, code: KeyCode) {
+        let len = self.references.len();
+        let selected = self.reference_state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self
+                .reference_state
+                .select(Some(selected.saturating_sub(1))),
+            KeyCode::Down | KeyCode::Char('j') => self
+                .reference_state
+                .select(Some((selected + 1).min(len.saturating_sub(1)))),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                let Some(target) = self.references.get(selected).map(|r| r.id.clone()) else {
+                    return;
+                };
+                if self.index.element(&target).is_none() {
+                    self.message = Some(format!("`{}` isn't part of the extraction", target));
+                    return;
+                }
+                if let Some(current) = self.selected_element() {
+                    self.history.push(current.element.id.clone());
+                }
+                self.select_element(&target);
+                self.focus = Focus::Tree;
+            }
+            _ => {}
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let current = self.tree_state.selected().unwrap_or(0) as isize;
+        let last = self.rows.len() as isize - 1;
+        self.tree_state
+            .select(Some((current + delta).clamp(0, last) as usize));
+        self.update_references();
+    }
+
+    /// Rebuild the visible rows, keeping the selected row where possible.
+    fn rebuild_rows(&mut self) {
+        let selected = self
+            .tree_state
+            .selected()
+            .and_then(|row| self.rows.get(row));
+        let selected_key = selected.map(|row| match row {
+            Row::Module { key, .. } => key.clone(),
+            Row::Element { id, .. } => id.clone(),
+        });
+
+        let mut rows = Vec::new();
+        for (key, ids) in &self.modules {
+            let visible = ancestors(key)
+                .filter(|ancestor| ancestor != key)
+                .all(|ancestor| self.expanded.contains(ancestor));
+            if !visible {
+                continue;
+            }
+            let depth = key.matches("::").count();
+            rows.push(Row::Module {
+                key: key.clone(),
+                depth,
+            });
+            if self.expanded.contains(key) {
+                for id in ids {
+                    let nesting = self
+                        .index
+                        .element(id)
+                        .map_or(0, |found| found.element.hierarchy.nesting_level as usize);
+                    rows.push(Row::Element {
+                        id: id.to_string(),
+                        depth: depth + 1 + nesting,
+                    });
+                }
+            }
+        }
+        self.rows = rows;
+
+        let row = selected_key.and_then(|selected| {
+            self.rows.iter().position(|row| match row {
+                Row::Module { key, .. } => *key == selected,
+                Row::Element { id, .. } => *id == selected,
+            })
+        });
+        if let Some(row) = row {
+            self.tree_state.select(Some(row));
+        }
+        self.update_references();
+    }
+
+    fn module_row(&self, module: &str) -> Option<usize> {
+        self.rows
+            .iter()
+            .position(|row| matches!(row, Row::Module { key, .. } if key == module))
+    }
+
+    /// The selected element, or the `mod` item of the selected module.
+    fn selected_element(&self) -> Option<ElementRef<'a>> {
+        match self.rows.get(self.tree_state.selected()?)? {
+            Row::Element { id, .. } => self.index.element(id),
+            Row::Module { key, .. } => self.index.element(self.module_items.get(key)?),
+        }
+    }
+
+    /// Show the element with `id` in the tree and select it.
+    fn select_element(&mut self, id: &str) {
+        let Some(found) = self.index.element(id) else {
+            return;
+        };
+        let key = module_key(found.element);
+        for ancestor in ancestors(&key) {
+            self.expanded.insert(ancestor.to_string());
+        }
+        self.rebuild_rows();
+        let row = self.rows.iter().position(|row| match row {
+            Row::Element { id: row_id, .. } => row_id == id,
+            Row::Module { key, .. } => self.module_items.get(key) == Some(&id),
+        });
+        self.tree_state.select(row);
+        self.update_references();
+    }
+
+    fn update_references(&mut self) {
+        self.references.clear();
+        self.reference_state.select(None);
+        let Some(found) = self.selected_element() else {
+            return;
+        };
+        let graph = &self.index.ast().reference_graph;
+        let id = &found.element.id;
+        for (ids, outgoing) in [
+            (graph.references.get(id), true),
+            (graph.referenced_by.get(id), false),
+        ] {
+            for id in ids.into_iter().flatten() {
+                self.references.push(Reference {
+                    id: id.clone(),
+                    outgoing,
+                });
+            }
+        }
+    }
+
+    fn search(&mut self, query: &str) {
+        let query = query.to_lowercase();
+        self.search_matches = self
+            .modules
+            .values()
+            .flatten()
+            .chain(self.module_items.values())
+            .filter(|id| {
+                self.index
+                    .element(id)
+                    .is_some_and(|found| found.element.name.to_lowercase().contains(&query))
+            })
+            .map(|id| id.to_string())
+            .collect();
+        self.search_position = 0;
+        match self.search_matches.first().cloned() {
+            Some(id) => self.select_element(&id),
+            None => self.message = Some(format!("No element named like `{}`", query)),
+        }
+    }
+
+    fn next_match(&mut self) {
+        if self.search_matches.is_empty() {
+            self.message = Some("No search results".to_string());
+            return;
+        }
+        self.search_position = (self.search_position + 1) % self.search_matches.len();
+        let id = self.search_matches[self.search_position].clone();
+        self.select_element(&id);
+        self.message = Some(format!(
+            "Match {} of {}",
+            self.search_position + 1,
+            self.search_matches.len()
+        ));
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [tree, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+        let [details, references] =
+            Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(right);
+
+        self.draw_tree(frame, tree);
+        self.draw_details(frame, details);
+        self.draw_references(frame, references);
+
+        let status_line = match (&self.search_input, &self.message) {
+            (Some(input), _) => Line::from(format!("/{}", input)),
+            (None, Some(message)) => Line::from(message.as_str()),
+            (None, None) => Line::from(HELP).dim(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn draw_tree(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Module { key, depth } => {
+                    let marker = if self.expanded.contains(key) {
+                        "▾"
+                    } else {
+                        "▸"
+                    };
+                    let name = key.rsplit("::").next().unwrap_or(key);
+                    ListItem::new(format!("{}{} {}", "  ".repeat(*depth), marker, name)).bold()
+                }
+                Row::Element { id, depth } => {
+                    let label = self.index.element(id).map_or_else(
+                        || id.clone(),
+                        |found| {
+                            let kind = found.element.element_type.filter_name();
+                            format!("{} {}", kind, found.element.name)
+                        },
+                    );
+                    ListItem::new(format!("{}{}", "  ".repeat(*depth), label))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(focused_block(" Modules ", self.focus == Focus::Tree))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.tree_state);
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let lines = match self
+            .tree_state
+            .selected()
+            .and_then(|row| self.rows.get(row))
+        {
+            Some(Row::Module { key, .. }) => {
+                let mut lines = match self.selected_element() {
+                    Some(found) => element_details(found),
+                    None => vec![Line::from(key.as_str()).bold()],
+                };
+                lines.push(Line::default());
+                lines.push(Line::from(format!("{} elements", self.modules[key].len())));
+                lines
+            }
+            Some(Row::Element { id, .. }) => match self.index.element(id) {
+                Some(found) => element_details(found),
+                None => vec![Line::from(id.as_str())],
+            },
+            None => vec![Line::from("No elements")],
+        };
+        let details = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" Element "));
+        frame.render_widget(details, area);
+    }
+
+    fn draw_references(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .references
+            .iter()
+            .map(|reference| {
+                let arrow = if reference.outgoing { "→" } else { "←" };
+                let target = match self.index.element(&reference.id) {
+                    Some(found) => format!(
+                        "{}  {}:{}",
+                        found.element.hierarchy.qualified_name,
+                        found.file.relative_path.display(),
+                        found.element.location.line_start
+                    ),
+                    None => reference.id.clone(),
+                };
+                ListItem::new(format!("{} {}", arrow, target))
+            })
+            .collect();
+        let title = " References (→ uses, ← used by) ";
+        let list = List::new(items)
+            .block(focused_block(title, self.focus == Focus::References))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.reference_state);
+    }
+}
+
+fn element_details(found: ElementRef<'_>) -> Vec<Line<'static>> {
+    let element = found.element;
+    let location = &element.location;
+    let mut lines = vec![
+        Line::from(element.hierarchy.qualified_name.clone()).bold(),
+        Line::from(format!(
+            "{} · {} · {}:{}-{}",
+            element.element_type.filter_name(),
+            element.visibility,
+            found.file.relative_path.display(),
+            location.line_start,
+            location.line_end
+        ))
+        .dim(),
+    ];
+    if let Some(signature) = &element.signature {
+        lines.push(Line::default());
+        lines.push(Line::from(signature.clone()));
+    }
+    let mut measures = Vec::new();
+    if let Some(metrics) = &element.complexity_metrics {
+        measures.push(format!("cyclomatic {}", metrics.cyclomatic));
+        measures.push(format!("cognitive {}", metrics.cognitive));
+    } else if let Some(complexity) = element.complexity {
+        measures.push(format!("complexity {}", complexity));
+    }
+    measures.push(format!(
+        "{} lines",
+        location.line_end + 1 - location.line_start.max(1)
+    ));
+    lines.push(Line::default());
+    lines.push(Line::from(vec![
+        Span::from("Complexity: ").bold(),
+        Span::from(measures.join(", ")),
+    ]));
+    if !element.doc_comments.is_empty() {
+        lines.push(Line::default());
+        lines.extend(
+            element
+                .doc_comments
+                .iter()
+                .map(|line| Line::from(line.trim().to_string())),
+        );
+    }
+    lines
+}
+
+fn focused_block(title: &str, focused: bool) -> Block<'_> {
+    let block = Block::bordered().title(title);
+    if focused {
+        block.border_style(Style::new().bold())
+    } else {
+        block
+    }
+}
+
+/// The module tree key of an element: its module path with `crate` replaced
+/// by the crate name from its ID.
+fn module_key(element: &CodeElement) -> String {
+    let crate_name = element
+        .id
+        .rsplit_once("::")
+        .map_or("crate", |(crate_name, _)| crate_name);
+    let module_path = &element.hierarchy.module_path;
+    match module_path.strip_prefix("crate") {
+        Some(rest) => format!("{}{}", crate_name, rest),
+        None => module_path.clone(),
+    }
+}
+
+/// `a`, `a::b` and `a::b::c` for `a::b::c`.
+fn ancestors(key: &str) -> impl Iterator<Item = &str> {
+    key.match_indices("::")
+        .map(|(end, _)| &key[..end])
+        .chain(std::iter::once(key))
+}
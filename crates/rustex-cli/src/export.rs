@@ -0,0 +1,125 @@
+//! Export of RAG chunks to vector databases, for `rustex export rag`.
+
+use crate::compression::decompress;
+use crate::{rag_formatter, CliVectorSink};
+use anyhow::{Context, Result};
+use rustex_core::{AstExtractor, ExtractorConfig};
+use rustex_export::{export_chunks, ChromaSink, PgVectorSink, QdrantSink, VectorSink};
+use rustex_formats::{KeywordCorpus, RagChunk, RagDocument, RemovedChunk};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Settings of `rustex export rag` besides the extraction configuration.
+pub(crate) struct RagExport {
+    pub sink: CliVectorSink,
+    pub url: String,
+    pub collection: Option<String>,
+    pub api_key_env: Option<String>,
+    pub input: Option<PathBuf>,
+    pub batch_size: usize,
+}
+
+/// Upsert the RAG chunks of the project at `project_path`, or of the RAG
+/// output in `export.input`, into a vector database. Chunks are embedded as
+/// `config.embeddings` sets unless they all have embeddings already.
+pub(crate) async fn export_rag(
+    config: ExtractorConfig,
+    project_path: PathBuf,
+    export: RagExport,
+) -> Result<()> {
+    let formatter = rag_formatter(&config)?;
+    let (project, mut chunks) = match &export.input {
+        Some(input) => load_rag_chunks(input)?,
+        None => {
+            let ast_data = AstExtractor::new(config.clone(), project_path).extract_project()?;
+            let corpus = KeywordCorpus::from_files(&ast_data.files);
+            let mut chunks = Vec::new();
+            for file in &ast_data.files {
+                chunks.extend(formatter.chunks_for_file(file, &corpus)?);
+            }
+            (ast_data.project.name, chunks)
+        }
+    };
+    if chunks.iter().any(|chunk| chunk.embedding.is_none()) {
+        info!("Embedding {} chunks", chunks.len());
+        formatter.embed_chunks(&mut chunks)?;
+    }
+
+    let collection = export
+        .collection
+        .unwrap_or_else(|| collection_name(&project));
+    let api_key = export
+        .api_key_env
+        .map(|name| {
+            std::env::var(&name)
+                .with_context(|| format!("Environment variable {} is not set", name))
+        })
+        .transpose()?;
+    let sink: Box<dyn VectorSink> = match export.sink {
+        CliVectorSink::Qdrant => Box::new(QdrantSink::new(&export.url, &collection, api_key)?),
+        CliVectorSink::Chroma => Box::new(ChromaSink::new(&export.url, &collection, api_key)?),
+        CliVectorSink::Pgvector => Box::new(PgVectorSink::connect(&export.url, &collection).await?),
+    };
+    let exported = export_chunks(sink.as_ref(), &chunks, export.batch_size).await?;
+    println!(
+        "✓ Exported {} chunks to {} collection {}",
+        exported,
+        sink.name(),
+        collection
+    );
+    Ok(())
+}
+
+/// The chunks of `rustex extract --format rag` or `--format rag-jsonl`
+/// output, with the project name from the RAG metadata, or the file name
+/// when there is none.
+fn load_rag_chunks(path: &Path) -> Result<(String, Vec<RagChunk>)> {
+    let content = std::fs::read(path)
+        .and_then(decompress)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if let Ok(document) = serde_json::from_slice::<RagDocument>(&content) {
+        return Ok((document.metadata.project_name, document.chunks));
+    }
+
+    let content = String::from_utf8(content)
+        .with_context(|| format!("{} is not RAG output", path.display()))?;
+    let chunks = content
+        .lines()
+        .enumerate()
+        // Lines of chunks removed since a `--since` manifest have nothing to upsert.
+        .filter(|(_, line)| {
+            !line.trim().is_empty() && serde_json::from_str::<RemovedChunk>(line).is_err()
+        })
+        .map(|(index, line)| {
+            serde_json::from_str(line).with_context(|| {
+                format!(
+                    "Line {} of {} is not a RAG chunk",
+                    index + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect::<Result<Vec<RagChunk>>>()?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or("rustex");
+    Ok((name.to_string(), chunks))
+}
+
+/// A collection name made of the letters and digits of `project`, valid as
+/// a Qdrant or Chroma collection and a PostgreSQL table.
+fn collection_name(project: &str) -> String {
+    let name: String = project
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_chunks", name)
+}
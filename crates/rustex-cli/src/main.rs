@@ -2,11 +2,48 @@
 //!
 //! Command-line interface for Rust AST extraction.
 
+mod compression;
+mod explore;
+mod export;
+mod mcp;
+mod serve;
+
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use rustex_core::{AstExtractor, ConfigUseCase, ExtractorConfig, OutputFormat};
+use compression::{compress, decompress, Compression, Encoder};
+use rustex_core::{
+    check_thresholds, diagnose_config_str_with_schemas, fit_to_budget, split_project, stable_hash,
+    AstExtractor, ConfigDiagnostic, ConfigLayers, ConfigUseCase, CsvTable, DependencyGraph,
+    DependencyKind, DiagnosticSeverity, Diagram, ElementRef, ElementType, ExtractionReport,
+    ExtractorConfig, FileAst, Hotspot, IncrementalExtractor, MarkdownSection, MetricsReport,
+    OutputFormat, PackageRole, ProjectAst, ProjectIndex, Query, RagChunkStrategy, SplitStrategy,
+    TextField, TextMatch, ThresholdConfig, TruncationPolicy,
+};
+use rustex_formats::{
+    doc_coverage, embedding_provider, format_as_anthropic_fine_tuning, format_as_csv,
+    format_as_dot, format_as_hf_dataset, format_as_langchain, format_as_llamaindex,
+    format_as_markdown, format_as_mdbook, format_as_mermaid, format_as_message_pack,
+    format_as_openai_fine_tuning, format_as_parquet, format_as_protobuf, format_as_scip,
+    format_coverage_badge, format_coverage_markdown, format_dependency_graph, format_project_ast,
+    format_with_template, output_schema, parse_message_pack, summarizer, ChunkDeduplicator,
+    KeywordCorpus, RagChunk, RagConfig, RagDocument, RagFormatter, RagManifest, RemovedChunk,
+};
+use rustex_plugins::builtin::api_stability::{is_url, load_baseline, ApiStabilityConfig};
+use rustex_plugins::builtin::rustsec::{RustSecAuditConfig, Vulnerability};
+use rustex_plugins::core::manager::PluginManagerConfig;
+use rustex_plugins::{
+    install_plugin, register_builtin_plugins, register_installed_plugins, uninstall_plugin,
+    CountingAllocator, PluginInfo, PluginManager, RustSecAuditor,
+};
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
+
+// Counts allocations for plugin profiles, once `run_plugins` enables it.
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 #[derive(Parser)]
 #[command(name = "rustex")]
@@ -29,18 +66,51 @@ struct Cli {
     config: Option<PathBuf>,
 }
 
+// Parsed once per run, so the size of the largest subcommand doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Extract AST from Rust project
     Extract {
-        /// Output format
-        #[arg(short, long, value_enum, default_value = "json")]
-        format: CliOutputFormat,
+        /// Output formats, comma-separated; several formats need
+        /// --output-dir [default: json]
+        #[arg(short, long, value_enum, value_delimiter = ',')]
+        format: Vec<CliOutputFormat>,
 
         /// Output file path
         #[arg(short, long)]
         output: Option<PathBuf>,
 
+        /// Write one file per output format to this directory, extracting
+        /// the project once
+        #[arg(long, conflicts_with = "output")]
+        output_dir: Option<PathBuf>,
+
+        /// Split the output into many files plus a manifest.json index:
+        /// per-file, per-module or max-size=<size> (e.g. max-size=10MB)
+        #[arg(long, requires = "output_dir", value_parser = parse_split_strategy)]
+        split_output: Option<SplitStrategy>,
+
+        /// Write the Markdown output to --output-dir as an mdBook, with a
+        /// SUMMARY.md and one chapter per module
+        #[arg(long, requires = "output_dir", conflicts_with_all = ["split_output", "compress"])]
+        mdbook: bool,
+
+        /// Compress the output; files in --output-dir get a .gz or .zst
+        /// extension
+        #[arg(long, value_enum)]
+        compress: Option<Compression>,
+
+        /// Extract only this file, which needn't be part of a Cargo project,
+        /// and output its JSON
+        #[arg(long, conflicts_with_all = ["output_dir", "stdin"])]
+        file: Option<PathBuf>,
+
+        /// Extract Rust source read from stdin as a crate root and output its
+        /// JSON
+        #[arg(long, conflicts_with = "output_dir")]
+        stdin: bool,
+
         /// Include documentation
         #[arg(long)]
         include_docs: bool,
@@ -53,9 +123,145 @@ enum Commands {
         #[arg(long)]
         parse_deps: bool,
 
-        /// Maximum file size in bytes
-        #[arg(long, default_value = "10485760")] // 10MB
-        max_file_size: usize,
+        /// Expand macros and include generated items
+        #[arg(long)]
+        expand_macros: bool,
+
+        /// Don't skip files ignored by .gitignore and .ignore
+        #[arg(long)]
+        no_ignore: bool,
+
+        /// Simulate this feature set and skip items disabled by #[cfg]
+        #[arg(long, value_delimiter = ',', num_args = 0..)]
+        cfg_features: Option<Vec<String>>,
+
+        /// Maximum file size in bytes [default: 10485760]
+        #[arg(long)]
+        max_file_size: Option<usize>,
+
+        /// Extract signatures and docs from files over --max-file-size
+        /// instead of skipping them
+        #[arg(long)]
+        partial_oversize_files: bool,
+
+        /// Fail if any file is skipped or only partly extracted
+        #[arg(long)]
+        strict: bool,
+
+        /// Limit the output to about this many tokens, at four bytes per token
+        #[arg(long)]
+        max_output_tokens: Option<usize>,
+
+        /// Limit the output to this many bytes
+        #[arg(long)]
+        max_output_bytes: Option<usize>,
+
+        /// How output over the limit is reduced [default: drop-bodies-first]
+        #[arg(long, value_enum)]
+        truncation_policy: Option<CliTruncationPolicy>,
+
+        /// Diagrams of the mermaid and dot formats, comma-separated
+        /// [default: all]; Markdown output embeds these diagrams
+        #[arg(long, value_enum, value_delimiter = ',')]
+        diagram: Vec<CliDiagram>,
+
+        /// Table of the csv and parquet formats [default: elements];
+        /// --output-dir writes every table
+        #[arg(long, value_enum)]
+        csv_table: Option<CliCsvTable>,
+
+        /// Tera template rendered by the template format
+        #[arg(long)]
+        template: Option<PathBuf>,
+
+        /// Sections of the markdown format, comma-separated [default: all]
+        #[arg(long, value_enum, value_delimiter = ',')]
+        markdown_sections: Vec<CliMarkdownSection>,
+
+        /// Sections left out of the markdown format, comma-separated
+        #[arg(long, value_enum, value_delimiter = ',')]
+        markdown_exclude_sections: Vec<CliMarkdownSection>,
+
+        /// Level of the markdown title heading, to embed the output in
+        /// another document [default: 1]
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=3))]
+        heading_level: Option<u8>,
+
+        /// Leave the table of contents out of markdown output
+        #[arg(long)]
+        no_toc: bool,
+
+        /// Don't link markdown elements to the elements they use and are
+        /// used by
+        #[arg(long)]
+        no_cross_links: bool,
+
+        /// Base URL of the source files that markdown locations link to,
+        /// e.g. https://github.com/owner/repo/blob/main
+        #[arg(long)]
+        repo_url: Option<String>,
+
+        /// Embed the chunks of the rag, rag-jsonl and llamaindex formats
+        #[arg(long)]
+        embed: bool,
+
+        /// Base URL of the OpenAI-compatible API to embed with
+        /// [default: https://api.openai.com/v1]
+        #[arg(long)]
+        embedding_url: Option<String>,
+
+        /// Embedding model requested from the API [default: text-embedding-3-small]
+        #[arg(long)]
+        embedding_model: Option<String>,
+
+        /// Directory of a local BERT-style model to embed with instead of the
+        /// API; needs the embeddings-candle feature
+        #[arg(long)]
+        embedding_model_dir: Option<PathBuf>,
+
+        /// Summarize the complex elements without docs in the chunks of the
+        /// rag and rag-jsonl formats with a language model
+        #[arg(long)]
+        summarize: bool,
+
+        /// How the rag formats split elements into chunks
+        /// [default: per-element]
+        #[arg(long, value_enum)]
+        chunk_strategy: Option<CliChunkStrategy>,
+
+        /// Overlap between the chunks of a split element, in tokens
+        /// [default: 50]
+        #[arg(long)]
+        chunk_overlap_tokens: Option<usize>,
+
+        /// Lines of source around each RAG chunk to add to its context
+        /// [default: 0]
+        #[arg(long)]
+        context_radius_lines: Option<usize>,
+
+        /// Leave the headers of enclosing impls, traits and modules out of
+        /// the context of RAG chunks
+        #[arg(long)]
+        no_parent_signature: bool,
+
+        /// RAG manifest of an earlier run; the rag and rag-jsonl formats only
+        /// output the chunks added or changed since, and the removed ones
+        #[arg(long)]
+        since: Option<PathBuf>,
+
+        /// Write the RAG manifest of the chunks, for a later --since
+        #[arg(long)]
+        rag_manifest: Option<PathBuf>,
+
+        /// Fraction of training examples in the validation split of the
+        /// hf-dataset format [default: 0.1]
+        #[arg(long)]
+        validation_split: Option<f64>,
+
+        /// Fraction of training examples in the test split of the hf-dataset
+        /// format [default: 0.1]
+        #[arg(long)]
+        test_split: Option<f64>,
 
         /// Files to include (glob patterns)
         #[arg(long, value_delimiter = ',')]
@@ -65,22 +271,205 @@ enum Commands {
         #[arg(long, value_delimiter = ',')]
         exclude: Vec<String>,
 
-        /// Enable plugins
+        /// Only extract elements with these visibilities
+        /// (pub, pub(crate), pub(super), pub(in path), private)
+        #[arg(long, value_delimiter = ',')]
+        visibility: Vec<String>,
+
+        /// Skip build scripts (build.rs)
+        #[arg(long)]
+        exclude_build_scripts: bool,
+
+        /// Enable plugins (see `rustex plugins list`)
         #[arg(long, value_delimiter = ',')]
         plugins: Vec<String>,
 
+        /// Write a report of each plugin's time, allocations and output size
+        /// to stderr
+        #[arg(long, value_enum, conflicts_with_all = ["output_dir", "file", "stdin"])]
+        plugin_profile: Option<PluginProfileFormat>,
+
+        /// Only extract these workspace crates
+        #[arg(long = "crate", value_delimiter = ',')]
+        crates: Vec<String>,
+
+        /// Number of parallel parsing jobs (defaults to all available cores)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Pretty print JSON output
+        #[arg(long)]
+        pretty: bool,
+    },
+
+    /// Re-extract the project whenever its files change
+    Watch {
+        /// Output format [default: json]
+        #[arg(short, long, value_enum)]
+        format: Option<CliOutputFormat>,
+
+        /// Output file path, rewritten after each change
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Include documentation
+        #[arg(long)]
+        include_docs: bool,
+
+        /// Include private items
+        #[arg(long)]
+        include_private: bool,
+
+        /// Files to include (glob patterns)
+        #[arg(long, value_delimiter = ',')]
+        include: Vec<String>,
+
+        /// Files to exclude (glob patterns)
+        #[arg(long, value_delimiter = ',')]
+        exclude: Vec<String>,
+
+        /// Only extract these workspace crates
+        #[arg(long = "crate", value_delimiter = ',')]
+        crates: Vec<String>,
+
+        /// Number of parallel parsing jobs (defaults to all available cores)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
         /// Pretty print JSON output
         #[arg(long)]
         pretty: bool,
+
+        /// Shell command to run after each update
+        #[arg(long)]
+        exec: Option<String>,
+
+        /// Milliseconds to wait for changes to settle before re-extracting
+        #[arg(long, default_value = "200")]
+        debounce: u64,
+    },
+
+    /// Serve the extracted project over a local HTTP API
+    Serve {
+        /// Serve this earlier `rustex extract` JSON output instead of
+        /// extracting the project
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: std::net::IpAddr,
+
+        /// Port to listen on
+        #[arg(long, default_value = "7878")]
+        port: u16,
+
+        /// Include private items when extracting
+        #[arg(long, conflicts_with = "input")]
+        include_private: bool,
+    },
+
+    /// Run a Model Context Protocol server on stdio for LLM agents
+    Mcp {
+        /// Serve this earlier `rustex extract` JSON output instead of
+        /// extracting the project
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Include private items when extracting
+        #[arg(long, conflicts_with = "input")]
+        include_private: bool,
+    },
+
+    /// Browse modules, elements and their references interactively
+    Explore {
+        /// Explore this earlier `rustex extract` output instead of
+        /// extracting the project
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Include private items when extracting
+        #[arg(long, conflicts_with = "input")]
+        include_private: bool,
+    },
+
+    /// Query the elements of an earlier `rustex extract` JSON output
+    Query {
+        /// JSON output of `rustex extract`
+        input: PathBuf,
+
+        /// Query such as "functions where complexity > 15 and visibility = pub"
+        #[arg(short, long)]
+        select: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: CliQueryFormat,
+
+        /// Output file path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Search element names, signatures and documentation
+    Search {
+        /// Text to search for, ignoring case
+        pattern: String,
+
+        /// Treat the pattern as a regular expression
+        #[arg(short = 'e', long)]
+        regex: bool,
+
+        /// Match case
+        #[arg(short = 's', long)]
+        case_sensitive: bool,
+
+        /// Only search elements of these types, such as `function` or `struct`
+        #[arg(short = 't', long = "type", value_delimiter = ',')]
+        element_types: Vec<String>,
+
+        /// Show this many lines of source around each match
+        #[arg(short = 'C', long, default_value = "0")]
+        context: usize,
+
+        /// Maximum number of results
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: CliSearchFormat,
+
+        /// Search this earlier `rustex extract` JSON output instead of
+        /// extracting the project
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Include private items when extracting
+        #[arg(long, conflicts_with = "input")]
+        include_private: bool,
+
+        /// Output file path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 
-    /// Analyze project dependencies
+    /// Analyze project dependencies from Cargo.lock
     Deps {
-        /// Visualize dependencies
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: CliDepsFormat,
+
+        /// Output the dependency graph in Graphviz DOT format
         #[arg(long)]
         visualize: bool,
 
-        /// Output file for visualization
+        /// List the packages RustSec advisories apply to, using the
+        /// `rustsec-audit` plugin settings
+        #[arg(long, conflicts_with = "visualize")]
+        audit: bool,
+
+        /// Output file path
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
@@ -95,54 +484,299 @@ enum Commands {
         #[arg(long)]
         loc: bool,
 
+        /// List deprecated items and the elements still using them
+        #[arg(long)]
+        deprecated: bool,
+
+        /// With --deprecated, fail if deprecated items have callers missing
+        /// from this earlier `rustex extract` JSON output
+        #[arg(long, requires = "deprecated")]
+        baseline: Option<PathBuf>,
+
+        /// Report lifetimes, reference parameters, Arc/Rc/RefCell use and
+        /// `.clone()` density, listing the files that clone most
+        #[arg(long, conflicts_with = "deprecated")]
+        ownership: bool,
+
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: CliMetricsFormat,
+
+        /// Number of largest and most complex elements to list
+        #[arg(long, default_value = "10")]
+        top: usize,
+
         /// Output file for metrics
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
 
+    /// Fail when the project exceeds the quality thresholds of the
+    /// `[thresholds]` config section or the flags below
+    Check {
+        /// Maximum cyclomatic complexity of a function
+        #[arg(long)]
+        max_cyclomatic: Option<u32>,
+
+        /// Maximum cognitive complexity of a function
+        #[arg(long)]
+        max_cognitive: Option<u32>,
+
+        /// Maximum number of lines of a function
+        #[arg(long)]
+        max_function_lines: Option<usize>,
+
+        /// Maximum lines of code of a file
+        #[arg(long)]
+        max_file_lines: Option<usize>,
+
+        /// Minimum percentage of documented public items
+        #[arg(long)]
+        min_doc_coverage: Option<f64>,
+
+        /// Maximum number of `unsafe` blocks, functions, impls and traits
+        #[arg(long)]
+        max_unsafe: Option<usize>,
+
+        /// Maximum number of vulnerable dependencies, audited against the
+        /// RustSec advisory database
+        #[arg(long)]
+        max_vulnerabilities: Option<usize>,
+
+        /// Report format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: CliCheckFormat,
+
+        /// Check this earlier `rustex extract` JSON output instead of
+        /// extracting the project
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Include private items when extracting
+        #[arg(long, conflicts_with = "input")]
+        include_private: bool,
+
+        /// Output file for the report
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Report the documentation coverage of public items per module and
+    /// element type, failing below a minimum coverage
+    Coverage {
+        /// Report format; `badge` is a shields.io endpoint badge
+        #[arg(short, long, value_enum, default_value = "markdown")]
+        format: CliCoverageFormat,
+
+        /// Fail if the coverage is below this percentage; defaults to
+        /// `min_doc_coverage` of the `[thresholds]` config section
+        #[arg(long)]
+        min_coverage: Option<f64>,
+
+        /// Gate on, and show in the badge, the coverage weighted by the
+        /// quality of the docs instead of the share of documented items
+        #[arg(long)]
+        weighted: bool,
+
+        /// Report on this earlier `rustex extract` JSON output instead of
+        /// extracting the project
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Include private items when extracting
+        #[arg(long, conflicts_with = "input")]
+        include_private: bool,
+
+        /// Output file for the report
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export extracted data to other systems
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+
     /// Configuration management
     Config {
         #[command(subcommand)]
         action: ConfigAction,
     },
-}
 
-#[derive(Subcommand)]
-enum ConfigAction {
-    /// Initialize a new configuration file
-    Init {
-        /// Force overwrite existing config
-        #[arg(long)]
-        force: bool,
+    /// List, install, inspect, enable and disable plugins
+    Plugins {
+        #[command(subcommand)]
+        action: PluginsAction,
+    },
 
-        /// Use case template to generate
-        #[arg(long, value_enum)]
-        template: Option<CliConfigUseCase>,
+    /// Compare two JSON extractions of a project
+    Diff {
+        /// Earlier extraction (JSON output of `rustex extract`)
+        old: PathBuf,
 
-        /// Configuration file path
-        #[arg(short, long, default_value = "rustex.toml")]
-        output: PathBuf,
-    },
+        /// Later extraction (JSON output of `rustex extract`)
+        new: PathBuf,
 
-    /// Validate configuration file
-    Validate {
-        /// Configuration file path
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "json")]
+        format: CliReportFormat,
+
+        /// Output file path
         #[arg(short, long)]
-        file: Option<PathBuf>,
+        output: Option<PathBuf>,
+
+        /// Pretty print JSON output
+        #[arg(long)]
+        pretty: bool,
     },
 
-    /// Show current configuration
-    Show {
-        /// Configuration file path
+    /// Render a saved extraction in another output format without
+    /// extracting the project again
+    Convert {
+        /// Extraction to convert (JSON or MessagePack, optionally compressed)
+        input: PathBuf,
+
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: CliOutputFormat,
+
+        /// Output file path
         #[arg(short, long)]
-        file: Option<PathBuf>,
+        output: Option<PathBuf>,
+
+        /// Directory to write the dataset of the hf-dataset format to
+        #[arg(long, conflicts_with = "output")]
+        output_dir: Option<PathBuf>,
+
+        /// Tera template rendered by the template format
+        #[arg(long)]
+        template: Option<PathBuf>,
+
+        /// Pretty print JSON output
+        #[arg(long)]
+        pretty: bool,
     },
 
-    /// Generate example configuration for different use cases
-    Template {
-        /// Use case to generate template for
-        #[arg(value_enum)]
-        use_case: CliConfigUseCase,
+    /// Print the JSON Schema of an output format, for validating output
+    /// and generating code against it
+    Schema {
+        /// Output format to describe; line-oriented formats get the schema of a line
+        #[arg(short, long, value_enum, default_value = "json")]
+        format: CliOutputFormat,
+
+        /// Output file path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check public API changes against a baseline for semver compatibility
+    SemverCheck {
+        /// Git revision (tag, branch or commit), or JSON extraction or its URL, to compare
+        /// against; defaults to the `baseline` of the `api-stability` plugin settings
+        #[arg(long)]
+        baseline: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "markdown")]
+        format: CliReportFormat,
+
+        /// Output file path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportTarget {
+    /// Upsert the project's RAG chunks into a vector database, by chunk ID;
+    /// chunks are embedded as set in the `[embeddings]` config section
+    Rag {
+        /// Vector database to export to
+        #[arg(long, value_enum)]
+        sink: CliVectorSink,
+
+        /// URL of the database, e.g. http://localhost:6333 for Qdrant,
+        /// http://localhost:8000 for Chroma or postgres://localhost/rag for
+        /// pgvector
+        #[arg(long)]
+        url: String,
+
+        /// Collection, or table for pgvector [default: <project>_chunks]
+        #[arg(long)]
+        collection: Option<String>,
+
+        /// Environment variable holding the API key of Qdrant or Chroma
+        #[arg(long)]
+        api_key_env: Option<String>,
+
+        /// Export the chunks of this earlier `rustex extract --format rag`
+        /// or `rag-jsonl` output instead of extracting the project
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Chunks upserted per request
+        #[arg(long, default_value_t = 100)]
+        batch_size: usize,
+
+        /// Include private items when extracting
+        #[arg(long, conflicts_with = "input")]
+        include_private: bool,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliVectorSink {
+    Qdrant,
+    Pgvector,
+    Chroma,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Initialize a new configuration file
+    Init {
+        /// Force overwrite existing config
+        #[arg(long)]
+        force: bool,
+
+        /// Use case template to generate
+        #[arg(long, value_enum)]
+        template: Option<CliConfigUseCase>,
+
+        /// Configuration file path
+        #[arg(short, long, default_value = "rustex.toml")]
+        output: PathBuf,
+    },
+
+    /// Validate configuration file
+    Validate {
+        /// Configuration file path
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Output format of the diagnostics
+        #[arg(long, value_enum, default_value = "text")]
+        format: CliDiagnosticFormat,
+    },
+
+    /// Show current configuration
+    Show {
+        /// Configuration file path
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Show the configuration merged from defaults, config files and
+        /// environment variables, with the source of each setting
+        #[arg(long)]
+        resolved: bool,
+    },
+
+    /// Generate example configuration for different use cases
+    Template {
+        /// Use case to generate template for
+        #[arg(value_enum)]
+        use_case: CliConfigUseCase,
 
         /// Output file path
         #[arg(short, long, default_value = "rustex-template.toml")]
@@ -150,6 +784,53 @@ enum ConfigAction {
     },
 }
 
+#[derive(Subcommand)]
+enum PluginsAction {
+    /// List available plugins and whether they are enabled
+    List,
+
+    /// Show the details of a plugin
+    Info {
+        /// Plugin name
+        name: String,
+    },
+
+    /// Enable a plugin in the project configuration file
+    Enable {
+        /// Plugin name
+        name: String,
+    },
+
+    /// Disable a plugin in the project configuration file
+    Disable {
+        /// Plugin name
+        name: String,
+    },
+
+    /// Remove a plugin from the enabled and disabled plugins of the project
+    /// configuration file, so it falls back to its default
+    Reset {
+        /// Plugin name
+        name: String,
+    },
+
+    /// Install an external plugin from a directory with a `rustex-plugin.toml`
+    Install {
+        /// Directory of the plugin
+        path: PathBuf,
+
+        /// Replace an installed plugin of the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove an installed external plugin
+    Uninstall {
+        /// Plugin name
+        name: String,
+    },
+}
+
 #[derive(Debug, clap::ValueEnum, Clone, Copy)]
 enum CliConfigUseCase {
     Documentation,
@@ -172,10 +853,190 @@ impl From<CliConfigUseCase> for ConfigUseCase {
 #[derive(clap::ValueEnum, Clone)]
 enum CliOutputFormat {
     Json,
+    #[value(alias = "messagepack")]
     MessagePack,
     Markdown,
+    #[value(alias = "graphql")]
     GraphQL,
     Rag,
+    Jsonl,
+    RagJsonl,
+    #[value(name = "langchain")]
+    LangChain,
+    #[value(name = "llamaindex")]
+    LlamaIndex,
+    HfDataset,
+    #[value(name = "openai-finetune")]
+    OpenAiFineTune,
+    #[value(name = "anthropic-finetune")]
+    AnthropicFineTune,
+    Mermaid,
+    Dot,
+    Csv,
+    Parquet,
+    Turtle,
+    #[value(alias = "ntriples")]
+    NTriples,
+    Cypher,
+    Protobuf,
+    Scip,
+    Ctags,
+    Etags,
+    Overview,
+    Sarif,
+    Template,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliDiagram {
+    CrateDependencies,
+    ModuleHierarchy,
+    ModuleImports,
+    StructRelationships,
+    CallGraph,
+}
+
+impl From<CliDiagram> for Diagram {
+    fn from(cli_diagram: CliDiagram) -> Self {
+        match cli_diagram {
+            CliDiagram::CrateDependencies => Diagram::CrateDependencies,
+            CliDiagram::ModuleHierarchy => Diagram::ModuleHierarchy,
+            CliDiagram::ModuleImports => Diagram::ModuleImports,
+            CliDiagram::StructRelationships => Diagram::StructRelationships,
+            CliDiagram::CallGraph => Diagram::CallGraph,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliDepsFormat {
+    Table,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliQueryFormat {
+    Table,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliSearchFormat {
+    Text,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliCheckFormat {
+    Text,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliCoverageFormat {
+    Markdown,
+    Json,
+    Badge,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliMetricsFormat {
+    Text,
+    Json,
+    Markdown,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliDiagnosticFormat {
+    Text,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliTruncationPolicy {
+    DropBodiesFirst,
+    DropPrivateFirst,
+    PerFileProportional,
+}
+
+impl From<CliTruncationPolicy> for TruncationPolicy {
+    fn from(cli_policy: CliTruncationPolicy) -> Self {
+        match cli_policy {
+            CliTruncationPolicy::DropBodiesFirst => TruncationPolicy::DropBodiesFirst,
+            CliTruncationPolicy::DropPrivateFirst => TruncationPolicy::DropPrivateFirst,
+            CliTruncationPolicy::PerFileProportional => TruncationPolicy::PerFileProportional,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum PluginProfileFormat {
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliCsvTable {
+    Elements,
+    Files,
+    Metrics,
+}
+
+impl From<CliCsvTable> for CsvTable {
+    fn from(cli_table: CliCsvTable) -> Self {
+        match cli_table {
+            CliCsvTable::Elements => CsvTable::Elements,
+            CliCsvTable::Files => CsvTable::Files,
+            CliCsvTable::Metrics => CsvTable::Metrics,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliChunkStrategy {
+    PerElement,
+    TokenBudget,
+    SlidingWindow,
+    Semantic,
+}
+
+impl From<CliChunkStrategy> for RagChunkStrategy {
+    fn from(cli_strategy: CliChunkStrategy) -> Self {
+        match cli_strategy {
+            CliChunkStrategy::PerElement => RagChunkStrategy::PerElement,
+            CliChunkStrategy::TokenBudget => RagChunkStrategy::TokenBudget,
+            CliChunkStrategy::SlidingWindow => RagChunkStrategy::SlidingWindow,
+            CliChunkStrategy::Semantic => RagChunkStrategy::Semantic,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliMarkdownSection {
+    Metrics,
+    PluginResults,
+    TechDebt,
+    Diagrams,
+    TraitImplementations,
+    Files,
+}
+
+impl From<CliMarkdownSection> for MarkdownSection {
+    fn from(cli_section: CliMarkdownSection) -> Self {
+        match cli_section {
+            CliMarkdownSection::Metrics => MarkdownSection::Metrics,
+            CliMarkdownSection::PluginResults => MarkdownSection::PluginResults,
+            CliMarkdownSection::TechDebt => MarkdownSection::TechDebt,
+            CliMarkdownSection::Diagrams => MarkdownSection::Diagrams,
+            CliMarkdownSection::TraitImplementations => MarkdownSection::TraitImplementations,
+            CliMarkdownSection::Files => MarkdownSection::Files,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CliReportFormat {
+    Json,
+    Markdown,
 }
 
 impl From<CliOutputFormat> for OutputFormat {
@@ -186,6 +1047,27 @@ impl From<CliOutputFormat> for OutputFormat {
             CliOutputFormat::Markdown => OutputFormat::Markdown,
             CliOutputFormat::GraphQL => OutputFormat::GraphQL,
             CliOutputFormat::Rag => OutputFormat::Rag,
+            CliOutputFormat::Jsonl => OutputFormat::Jsonl,
+            CliOutputFormat::RagJsonl => OutputFormat::RagJsonl,
+            CliOutputFormat::LangChain => OutputFormat::LangChain,
+            CliOutputFormat::LlamaIndex => OutputFormat::LlamaIndex,
+            CliOutputFormat::HfDataset => OutputFormat::HfDataset,
+            CliOutputFormat::OpenAiFineTune => OutputFormat::OpenAiFineTune,
+            CliOutputFormat::AnthropicFineTune => OutputFormat::AnthropicFineTune,
+            CliOutputFormat::Mermaid => OutputFormat::Mermaid,
+            CliOutputFormat::Dot => OutputFormat::Dot,
+            CliOutputFormat::Csv => OutputFormat::Csv,
+            CliOutputFormat::Parquet => OutputFormat::Parquet,
+            CliOutputFormat::Turtle => OutputFormat::Turtle,
+            CliOutputFormat::NTriples => OutputFormat::NTriples,
+            CliOutputFormat::Cypher => OutputFormat::Cypher,
+            CliOutputFormat::Protobuf => OutputFormat::Protobuf,
+            CliOutputFormat::Scip => OutputFormat::Scip,
+            CliOutputFormat::Ctags => OutputFormat::Ctags,
+            CliOutputFormat::Etags => OutputFormat::Etags,
+            CliOutputFormat::Overview => OutputFormat::Overview,
+            CliOutputFormat::Sarif => OutputFormat::Sarif,
+            CliOutputFormat::Template => OutputFormat::Template,
         }
     }
 }
@@ -196,23 +1078,96 @@ async fn main() -> Result<()> {
 
     // Initialize logging
     let log_level = if cli.verbose { "debug" } else { "info" };
-    tracing_subscriber::fmt()
-        .with_env_filter(format!("rustex={}", log_level))
-        .init();
+    let subscriber = tracing_subscriber::fmt().with_env_filter(format!("rustex={}", log_level));
+    // The MCP server owns stdout for protocol messages, and editors read
+    // single-file extractions from it.
+    let stdout_is_data = match &cli.command {
+        Commands::Mcp { .. } => true,
+        Commands::Extract { file, stdin, .. } => file.is_some() || *stdin,
+        _ => false,
+    };
+    if stdout_is_data {
+        subscriber.with_writer(std::io::stderr).init();
+    } else {
+        subscriber.init();
+    }
 
     match cli.command {
         Commands::Extract {
             format,
             output,
+            output_dir,
+            split_output,
+            mdbook,
+            compress,
+            file,
+            stdin,
             include_docs,
             include_private,
             parse_deps,
+            expand_macros,
+            no_ignore,
+            cfg_features,
             max_file_size,
+            partial_oversize_files,
+            strict,
+            max_output_tokens,
+            max_output_bytes,
+            truncation_policy,
+            diagram,
+            csv_table,
+            template,
+            markdown_sections,
+            markdown_exclude_sections,
+            heading_level,
+            no_toc,
+            no_cross_links,
+            repo_url,
+            embed,
+            embedding_url,
+            embedding_model,
+            embedding_model_dir,
+            summarize,
+            chunk_strategy,
+            chunk_overlap_tokens,
+            context_radius_lines,
+            no_parent_signature,
+            since,
+            rag_manifest,
+            validation_split,
+            test_split,
             include,
             exclude,
+            visibility,
+            exclude_build_scripts,
             plugins,
+            plugin_profile,
+            crates,
+            jobs,
             pretty,
         } => {
+            let formats: Vec<OutputFormat> = format.into_iter().map(Into::into).collect();
+            if formats.len() > 1 && output_dir.is_none() {
+                anyhow::bail!("Several output formats need --output-dir to write one file each");
+            }
+            // Clap drops `requires = "output_dir"` when --output is given,
+            // as --output-dir conflicts with it.
+            if split_output.is_some() && output_dir.is_none() {
+                anyhow::bail!("--split-output writes many files; give --output-dir");
+            }
+            if mdbook && output_dir.is_none() {
+                anyhow::bail!("--mdbook writes a directory; give --output-dir");
+            }
+            if formats.len() > 1 && split_output.is_some() {
+                anyhow::bail!("--split-output writes a single output format");
+            }
+            let hf_dataset = formats
+                .iter()
+                .any(|format| matches!(format, OutputFormat::HfDataset));
+            if hf_dataset && output_dir.is_none() {
+                anyhow::bail!("--format hf-dataset writes a directory; give --output-dir");
+            }
+
             // Load base configuration
             let mut config = load_config(&cli.config, &cli.path)?;
 
@@ -220,45 +1175,382 @@ async fn main() -> Result<()> {
             override_config_with_cli_args(
                 &mut config,
                 CliOverrides {
-                    format: format.into(),
+                    format: formats.first().cloned(),
                     include_docs,
                     include_private,
                     parse_deps,
+                    expand_macros,
+                    no_ignore,
+                    cfg_features,
                     max_file_size,
+                    partial_oversize_files,
+                    strict,
+                    max_output_tokens,
+                    max_output_bytes,
+                    truncation_policy: truncation_policy.map(Into::into),
+                    diagrams: diagram.into_iter().map(Into::into).collect(),
+                    csv_table: csv_table.map(Into::into),
+                    template,
+                    markdown_sections: markdown_sections.into_iter().map(Into::into).collect(),
+                    markdown_exclude_sections: markdown_exclude_sections
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                    heading_level: heading_level.map(usize::from),
+                    no_toc,
+                    no_cross_links,
+                    repo_url,
+                    embed,
+                    embedding_url,
+                    embedding_model,
+                    embedding_model_dir,
+                    summarize,
+                    chunk_strategy: chunk_strategy.map(Into::into),
+                    chunk_overlap_tokens,
+                    context_radius_lines,
+                    no_parent_signature,
+                    since,
+                    rag_manifest,
+                    validation_split,
+                    test_split,
                     include_patterns: include,
                     exclude_patterns: exclude,
+                    visibility,
+                    exclude_build_scripts,
                     plugins,
+                    crates,
+                    jobs,
+                },
+            );
+
+            let source = match file {
+                Some(file) => Some(SingleFileSource::File(file)),
+                None => stdin.then_some(SingleFileSource::Stdin),
+            };
+            if let Some(source) = source {
+                if formats
+                    .iter()
+                    .any(|format| !matches!(format, OutputFormat::Json))
+                {
+                    anyhow::bail!("--file and --stdin only output JSON");
+                }
+                extract_single_file(cli.path, config, source, output, compress, pretty)?;
+                return Ok(());
+            }
+            if let (true, Some(output_dir)) = (mdbook, &output_dir) {
+                if formats
+                    .iter()
+                    .any(|format| !matches!(format, OutputFormat::Markdown))
+                {
+                    anyhow::bail!("--mdbook only outputs Markdown");
+                }
+                extract_to_mdbook(cli.path, config, output_dir, plugin_profile).await?;
+                return Ok(());
+            }
+
+            match output_dir {
+                Some(output_dir) => {
+                    let options = DirectoryOptions {
+                        split: split_output,
+                        compression: compress,
+                        pretty,
+                        plugin_profile,
+                    };
+                    extract_to_directory(cli.path, config, formats, &output_dir, options).await?
+                }
+                None => {
+                    extract_command(cli.path, config, output, compress, pretty, plugin_profile)
+                        .await?
+                }
+            }
+        }
+        Commands::Watch {
+            format,
+            output,
+            include_docs,
+            include_private,
+            include,
+            exclude,
+            crates,
+            jobs,
+            pretty,
+            exec,
+            debounce,
+        } => {
+            let mut config = load_config(&cli.config, &cli.path)?;
+            override_config_with_cli_args(
+                &mut config,
+                CliOverrides {
+                    format: format.map(Into::into),
+                    include_docs,
+                    include_private,
+                    include_patterns: include,
+                    exclude_patterns: exclude,
+                    crates,
+                    jobs,
+                    ..CliOverrides::default()
                 },
             );
 
-            extract_command(cli.path, config, output, pretty).await?;
+            let options = WatchOptions {
+                output,
+                pretty,
+                exec,
+                debounce: std::time::Duration::from_millis(debounce),
+            };
+            watch_command(cli.path, config, options).await?;
+        }
+        Commands::Serve {
+            input,
+            host,
+            port,
+            include_private,
+        } => {
+            // Chunks are made as by `extract --format rag`.
+            let rag_formatter = rag_formatter(&load_config(&cli.config, &cli.path)?)?;
+            let ast_data = load_or_extract(&cli.config, cli.path, input, include_private)?;
+            let index = ProjectIndex::new(ast_data);
+            serve::serve(index, rag_formatter, (host, port).into()).await?;
+        }
+        Commands::Mcp {
+            input,
+            include_private,
+        } => {
+            let ast_data = load_or_extract(&cli.config, cli.path, input, include_private)?;
+            mcp::serve(ProjectIndex::new(ast_data)).await?;
+        }
+        Commands::Explore {
+            input,
+            include_private,
+        } => {
+            let ast_data = load_or_extract(&cli.config, cli.path, input, include_private)?;
+            explore::run(ProjectIndex::new(ast_data))?;
+        }
+        Commands::Query {
+            input,
+            select,
+            format,
+            output,
+        } => {
+            query_command(&input, &select, format, output)?;
+        }
+        Commands::Search {
+            pattern,
+            regex,
+            case_sensitive,
+            element_types,
+            context,
+            limit,
+            format,
+            input,
+            include_private,
+            output,
+        } => {
+            let pattern = if regex {
+                pattern
+            } else {
+                regex::escape(&pattern)
+            };
+            let pattern = regex::RegexBuilder::new(&pattern)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .context("Invalid search pattern")?;
+            for filter in &element_types {
+                if !ElementType::is_valid_filter(filter) {
+                    anyhow::bail!("Unknown element type `{}`", filter);
+                }
+            }
+            let ast_data = load_or_extract(&cli.config, cli.path, input, include_private)?;
+            let search = SearchOptions {
+                element_types,
+                context,
+                limit,
+                format,
+            };
+            search_command(&ProjectIndex::new(ast_data), &pattern, &search, output)?;
         }
-        Commands::Deps { visualize, output } => {
-            deps_command(cli.path, visualize, output).await?;
+        Commands::Deps {
+            format,
+            visualize,
+            audit,
+            output,
+        } => {
+            let auditor = if audit {
+                let config = load_config(&cli.config, &cli.path)?;
+                let settings = config.plugins.config.get("rustsec-audit").cloned();
+                let settings: RustSecAuditConfig = match settings {
+                    Some(settings) => serde_json::from_value(settings)
+                        .context("Invalid rustsec-audit plugin settings")?,
+                    None => RustSecAuditConfig::default(),
+                };
+                Some(RustSecAuditor::with_config(settings))
+            } else {
+                None
+            };
+            deps_command(cli.path, format, visualize, auditor, output).await?;
         }
         Commands::Metrics {
             complexity,
             loc,
+            deprecated,
+            baseline,
+            ownership,
+            format,
+            top,
+            output,
+        } => {
+            if deprecated {
+                let config = load_config(&cli.config, &cli.path)?;
+                deprecations_command(cli.path, config, baseline.as_deref(), output).await?;
+            } else if ownership {
+                let config = load_config(&cli.config, &cli.path)?;
+                ownership_command(cli.path, config, output).await?;
+            } else {
+                let config = load_config(&cli.config, &cli.path)?;
+                let sections = MetricsSections {
+                    // Without a section flag, every section is reported.
+                    loc: loc || !complexity,
+                    complexity: complexity || !loc,
+                };
+                metrics_command(cli.path, config, sections, format, top, output).await?;
+            }
+        }
+        Commands::Check {
+            max_cyclomatic,
+            max_cognitive,
+            max_function_lines,
+            max_file_lines,
+            min_doc_coverage,
+            max_unsafe,
+            max_vulnerabilities,
+            format,
+            input,
+            include_private,
+            output,
+        } => {
+            let mut config = load_config(&cli.config, &cli.path)?;
+            config.include_private |= include_private;
+            config.thresholds.merge_with(ThresholdConfig {
+                max_cyclomatic,
+                max_cognitive,
+                max_function_lines,
+                max_file_lines,
+                min_doc_coverage,
+                max_unsafe,
+                max_vulnerabilities,
+            });
+            check_command(cli.path, config, input, format, output).await?;
+        }
+        Commands::Coverage {
+            format,
+            min_coverage,
+            weighted,
+            input,
+            include_private,
             output,
         } => {
-            metrics_command(cli.path, complexity, loc, output).await?;
+            let mut config = load_config(&cli.config, &cli.path)?;
+            config.include_private |= include_private;
+            config.validate()?;
+            let min_coverage = min_coverage.or(config.thresholds.min_doc_coverage);
+            let ast_data = match input {
+                Some(input) => load_project_ast(&input)?,
+                None => AstExtractor::new(config, cli.path).extract_project()?,
+            };
+            coverage_command(&ast_data, format, min_coverage, weighted, output)?;
+        }
+        Commands::Export {
+            target:
+                ExportTarget::Rag {
+                    sink,
+                    url,
+                    collection,
+                    api_key_env,
+                    input,
+                    batch_size,
+                    include_private,
+                },
+        } => {
+            let mut config = load_config(&cli.config, &cli.path)?;
+            config.include_private |= include_private;
+            config.embeddings.enabled = true;
+            config.validate()?;
+            let export = export::RagExport {
+                sink,
+                url,
+                collection,
+                api_key_env,
+                input,
+                batch_size,
+            };
+            export::export_rag(config, cli.path, export).await?;
         }
         Commands::Config { action } => {
-            config_command(action, cli.config.as_ref()).await?;
+            config_command(action, cli.config.as_ref(), &cli.path).await?;
         }
-    }
-
-    Ok(())
-}
-
-async fn extract_command(
-    project_path: PathBuf,
-    config: ExtractorConfig,
-    output: Option<PathBuf>,
-    pretty: bool,
-) -> Result<()> {
-    info!("Starting AST extraction for project at {:?}", project_path);
-
+        Commands::Plugins { action } => {
+            plugins_command(action, &cli.config, &cli.path)?;
+        }
+        Commands::Diff {
+            old,
+            new,
+            format,
+            output,
+            pretty,
+        } => {
+            diff_command(&old, &new, format, output, pretty).await?;
+        }
+        Commands::Convert {
+            input,
+            format,
+            output,
+            output_dir,
+            template,
+            pretty,
+        } => {
+            let mut config = load_config(&cli.config, &cli.path)?;
+            config.output_format = format.into();
+            if let Some(template) = template {
+                config.template = Some(template);
+            }
+            match output_dir {
+                Some(output_dir) => {
+                    if !matches!(config.output_format, OutputFormat::HfDataset) {
+                        anyhow::bail!("--output-dir only applies to --format hf-dataset");
+                    }
+                    let ast_data = load_project_ast(&input)?;
+                    let formatter = rag_formatter(&config)?;
+                    write_hf_dataset(&ast_data, &config, &formatter, &output_dir)?;
+                }
+                None => convert_command(&input, &config, output, pretty)?,
+            }
+        }
+        Commands::Schema { format, output } => {
+            write_output(output, &output_schema(&format.into())?)?;
+        }
+        Commands::SemverCheck {
+            baseline,
+            format,
+            output,
+        } => {
+            let config = load_config(&cli.config, &cli.path)?;
+            semver_check_command(cli.path, config, baseline, format, output).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn extract_command(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    output: Option<PathBuf>,
+    compression: Option<Compression>,
+    pretty: bool,
+    plugin_profile: Option<PluginProfileFormat>,
+) -> Result<()> {
+    info!("Starting AST extraction for project at {:?}", project_path);
+
     // Validate configuration
     if let Err(e) = config.validate() {
         error!("Configuration validation failed: {}", e);
@@ -267,104 +1559,1897 @@ async fn extract_command(
 
     let extractor = AstExtractor::new(config.clone(), project_path);
 
+    // Line-based formats are written as files are extracted.
+    if matches!(
+        config.output_format,
+        OutputFormat::Jsonl | OutputFormat::RagJsonl
+    ) {
+        let plugins = enabled_plugins(&config)?;
+        if !plugins.is_empty() {
+            anyhow::bail!(
+                "Plugins run on the whole project, but jsonl and rag-jsonl output is streamed \
+                 file by file; give --output-dir or disable {}",
+                plugins.join(", ")
+            );
+        }
+        return stream_jsonl_output(
+            &extractor,
+            &config.output_format,
+            output,
+            compression,
+            config.output_byte_limit(),
+        );
+    }
+
     // Show progress bar
     let pb = indicatif::ProgressBar::new_spinner();
     pb.set_message("Extracting AST...");
     pb.enable_steady_tick(std::time::Duration::from_millis(100));
 
     match extractor.extract_project() {
-        Ok(ast_data) => {
+        Ok(mut ast_data) => {
             pb.finish_with_message("✓ AST extraction completed");
 
-            let output_content = match config.output_format {
-                OutputFormat::Json => {
-                    if pretty {
-                        serde_json::to_string_pretty(&ast_data)?
-                    } else {
-                        serde_json::to_string(&ast_data)?
-                    }
+            run_plugins(&mut ast_data, &config, plugin_profile).await?;
+            let rag = LazyRagFormatter::default();
+            let output_content = render_project(&mut ast_data, &config, &rag, pretty)?;
+            write_output_bytes(output, &compress(&output_content, compression)?)?;
+            if let (OutputFormat::Rag, Some(_)) = (&config.output_format, &config.rag.manifest) {
+                write_rag_manifest(&config, &rag.get(&config)?.manifest(&ast_data)?)?;
+            }
+
+            // Print summary
+            print_extraction_summary(&ast_data);
+        }
+        Err(e) => {
+            pb.finish_with_message("✗ AST extraction failed");
+
+            // Provide more helpful error messages based on error type
+            match &e {
+                rustex_core::RustExError::PartialFailure {
+                    failed_count,
+                    total_count,
+                    ..
+                } => {
+                    error!(
+                        "Partial extraction failure: {}/{} files failed",
+                        failed_count, total_count
+                    );
+                    error!("Consider checking file permissions, syntax errors, or adjusting file size limits");
+                }
+                rustex_core::RustExError::Strict { report } => {
+                    error!("{}", e);
+                    print_extraction_report(report);
+                }
+                rustex_core::RustExError::InvalidProjectRoot { path } => {
+                    error!("Invalid project root: {:?}", path);
+                    error!("Make sure the path exists and contains a valid Rust project");
                 }
-                OutputFormat::Markdown => generate_markdown_output(&ast_data)?,
                 _ => {
-                    error!("Output format not yet implemented");
-                    return Ok(());
+                    error!("Extraction failed: {}", e);
                 }
+            }
+
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// A plugin manager with the built-in and installed plugins, enabled as
+/// `config` says.
+fn plugin_manager(config: &ExtractorConfig) -> Result<PluginManager> {
+    let mut manager = PluginManager::with_config(PluginManagerConfig::from(config));
+    register_builtin_plugins(&mut manager)?;
+    register_installed_plugins(&mut manager);
+    Ok(manager)
+}
+
+/// Names of the plugins `config` enables, explicitly or by default.
+fn enabled_plugins(config: &ExtractorConfig) -> Result<Vec<String>> {
+    let manager = plugin_manager(config)?;
+    let mut plugins = manager.list_plugins();
+    plugins.retain(|name| manager.is_plugin_enabled(name));
+    Ok(plugins)
+}
+
+/// Run the enabled built-in and installed plugins on `ast_data`, merging
+/// their output into it, and log their profiles or report them to stderr.
+async fn run_plugins(
+    ast_data: &mut ProjectAst,
+    config: &ExtractorConfig,
+    profile_format: Option<PluginProfileFormat>,
+) -> Result<()> {
+    // Counting allocations slows every allocation down, so it's only done
+    // when the profiles are reported.
+    if profile_format.is_some() || tracing::enabled!(tracing::Level::DEBUG) {
+        CountingAllocator::enable();
+    }
+    let mut manager = plugin_manager(config)?;
+    manager.run_on_project(ast_data, config).await?;
+
+    let profiles = manager.get_profiles();
+    for profile in &profiles {
+        debug!(
+            "Plugin '{}': {} executions ({} failed) in {:.1} ms, ~{} allocations ({} bytes), \
+             {} bytes of output",
+            profile.name,
+            profile.executions,
+            profile.failures,
+            profile.wall_time_ms,
+            profile.allocations,
+            profile.allocated_bytes,
+            profile.output_bytes
+        );
+    }
+    if let Some(PluginProfileFormat::Json) = profile_format {
+        eprintln!("{}", serde_json::to_string_pretty(&profiles)?);
+    }
+    Ok(())
+}
+
+/// Where `rustex extract --file` or `--stdin` reads its source.
+enum SingleFileSource {
+    File(PathBuf),
+    Stdin,
+}
+
+/// Extract one file without the rest of its project and write its JSON.
+fn extract_single_file(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    source: SingleFileSource,
+    output: Option<PathBuf>,
+    compression: Option<Compression>,
+    pretty: bool,
+) -> Result<()> {
+    config.validate()?;
+    let extractor = AstExtractor::new(config, project_path);
+    let file_ast = match source {
+        SingleFileSource::File(path) => extractor.extract_single_file(&path)?,
+        SingleFileSource::Stdin => {
+            let mut source = String::new();
+            std::io::stdin()
+                .read_to_string(&mut source)
+                .context("Failed to read stdin")?;
+            extractor.extract_source(Path::new("<stdin>"), &source)?
+        }
+    };
+    let content = if pretty {
+        serde_json::to_vec_pretty(&file_ast)?
+    } else {
+        serde_json::to_vec(&file_ast)?
+    };
+    write_output_bytes(output, &compress(&content, compression)?)
+}
+
+/// Settings of `rustex extract --output-dir` besides the formats.
+struct DirectoryOptions {
+    split: Option<SplitStrategy>,
+    compression: Option<Compression>,
+    pretty: bool,
+    plugin_profile: Option<PluginProfileFormat>,
+}
+
+/// Extract the project once, run the enabled plugins on it and write it to
+/// `output_dir` in each of `formats`, or the configured format if none is
+/// given, split into parts when a split strategy is given.
+async fn extract_to_directory(
+    project_path: PathBuf,
+    mut config: ExtractorConfig,
+    formats: Vec<OutputFormat>,
+    output_dir: &Path,
+    options: DirectoryOptions,
+) -> Result<()> {
+    let DirectoryOptions {
+        split,
+        compression,
+        pretty,
+        plugin_profile,
+    } = options;
+    config.validate()?;
+    let formats = if formats.is_empty() {
+        vec![config.output_format.clone()]
+    } else {
+        formats
+    };
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+
+    let mut ast_data = AstExtractor::new(config.clone(), project_path).extract_project()?;
+    run_plugins(&mut ast_data, &config, plugin_profile).await?;
+    // Shared by the formats, as its settings don't depend on the format.
+    let rag = LazyRagFormatter::default();
+    for format in formats {
+        if let OutputFormat::HfDataset = format {
+            write_hf_dataset(&ast_data, &config, rag.get(&config)?, output_dir)?;
+            continue;
+        }
+        if let Some(strategy) = split {
+            config.output_format = format;
+            write_split_output(
+                &ast_data,
+                &config,
+                &rag,
+                strategy,
+                output_dir,
+                compression,
+                pretty,
+            )?;
+            continue;
+        }
+        config.output_format = format;
+        // CSV and Parquet output get a file per table.
+        let tables = match config.output_format {
+            OutputFormat::Csv | OutputFormat::Parquet => CsvTable::ALL.to_vec(),
+            _ => vec![config.csv_table],
+        };
+        for table in tables {
+            config.csv_table = table;
+            let path = output_dir.join(compressed_name(output_file_name(&config), compression));
+            // Each format is fitted to the output budget on its own.
+            let content = render_project(&mut ast_data.clone(), &config, &rag, pretty)?;
+            write_file(&path, &content, compression)?;
+            println!("✓ Output written to {}", path.display());
+        }
+    }
+
+    print_extraction_summary(&ast_data);
+    Ok(())
+}
+
+/// Write the training examples of `ast_data` to `output_dir` as a Hugging
+/// Face dataset, licensed as the project's `Cargo.toml` says unless
+/// `[hf_dataset] license` is set.
+fn write_hf_dataset(
+    ast_data: &ProjectAst,
+    config: &ExtractorConfig,
+    formatter: &RagFormatter,
+    output_dir: &Path,
+) -> Result<()> {
+    let mut dataset = config.hf_dataset.clone();
+    if dataset.license.is_none() {
+        dataset.license = cargo_license(&ast_data.project.root_path);
+    }
+    let document = formatter.format(ast_data)?;
+    if document.training_examples.is_empty() {
+        warn!("The project has no training examples; the dataset is empty");
+    }
+    for (path, content) in format_as_hf_dataset(&document, &dataset)? {
+        let path = output_dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    println!(
+        "✓ Dataset of {} training examples written to {}",
+        document.training_examples.len(),
+        output_dir.display()
+    );
+    Ok(())
+}
+
+/// The `license` of the package or workspace in the `Cargo.toml` of `root`.
+fn cargo_license(root: &Path) -> Option<String> {
+    let manifest = std::fs::read_to_string(root.join("Cargo.toml")).ok()?;
+    let manifest: toml_edit::DocumentMut = manifest.parse().ok()?;
+    let license = manifest
+        .get("package")
+        .and_then(|package| package.get("license"))
+        .or_else(|| {
+            manifest
+                .get("workspace")
+                .and_then(|workspace| workspace.get("package"))
+                .and_then(|package| package.get("license"))
+        })?;
+    license.as_str().map(str::to_string)
+}
+
+/// Extract the project, run the enabled plugins on it and write it to
+/// `output_dir` as an mdBook.
+async fn extract_to_mdbook(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    output_dir: &Path,
+    plugin_profile: Option<PluginProfileFormat>,
+) -> Result<()> {
+    config.validate()?;
+    let mut ast_data = AstExtractor::new(config.clone(), project_path).extract_project()?;
+    run_plugins(&mut ast_data, &config, plugin_profile).await?;
+    for (path, content) in format_as_mdbook(&ast_data)? {
+        let path = output_dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    println!(
+        "✓ mdBook written to {}; build it with `mdbook build {}`",
+        output_dir.display(),
+        output_dir.display()
+    );
+
+    print_extraction_summary(&ast_data);
+    Ok(())
+}
+
+/// Write `ast_data` to `output_dir` split into parts, one file each, with a
+/// `manifest.json` listing the parts and the source files they hold.
+fn write_split_output(
+    ast_data: &ProjectAst,
+    config: &ExtractorConfig,
+    rag: &LazyRagFormatter,
+    strategy: SplitStrategy,
+    output_dir: &Path,
+    compression: Option<Compression>,
+    pretty: bool,
+) -> Result<()> {
+    let parts = split_project(ast_data, strategy, |part| {
+        Ok(render_project(&mut part.clone(), config, rag, pretty)?.len())
+    })?;
+
+    let mut manifest_parts = Vec::new();
+    for mut part in parts {
+        let file_name = compressed_name(
+            format!("{}.{}", part.name, output_extension(&config.output_format)),
+            compression,
+        );
+        let content = render_project(&mut part.ast, config, rag, pretty)?;
+        let bytes = write_file(&output_dir.join(&file_name), &content, compression)?;
+        manifest_parts.push(serde_json::json!({
+            "path": file_name,
+            "files": part.ast.files.iter().map(|file| &file.relative_path).collect::<Vec<_>>(),
+            "elements": part.ast.files.iter().map(|file| file.elements.len()).sum::<usize>(),
+            "bytes": bytes,
+        }));
+    }
+
+    let manifest = serde_json::json!({
+        "project": ast_data.project.name,
+        "format": config.output_format,
+        "strategy": strategy.to_string(),
+        "parts": manifest_parts,
+    });
+    let path = output_dir.join("manifest.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!(
+        "✓ Output split into {} parts, indexed in {}",
+        manifest["parts"].as_array().map_or(0, Vec::len),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Write `content` to `path` compressed with `compression`, returning the
+/// number of bytes written.
+fn write_file(path: &Path, content: &[u8], compression: Option<Compression>) -> Result<usize> {
+    let content = compress(content, compression)?;
+    std::fs::write(path, &content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(content.len())
+}
+
+/// `name` with the extension of `compression` appended.
+fn compressed_name(name: String, compression: Option<Compression>) -> String {
+    match compression {
+        Some(compression) => format!("{}.{}", name, compression.extension()),
+        None => name,
+    }
+}
+
+fn parse_split_strategy(strategy: &str) -> std::result::Result<SplitStrategy, String> {
+    strategy
+        .parse()
+        .map_err(|e: rustex_core::RustExError| e.to_string())
+}
+
+/// Name of the file `rustex extract --output-dir` writes the configured
+/// output format to.
+fn output_file_name(config: &ExtractorConfig) -> String {
+    let format = &config.output_format;
+    match format {
+        // Editors look for files named `tags` and `TAGS`.
+        OutputFormat::Ctags => return "tags".to_string(),
+        OutputFormat::Etags => return "TAGS".to_string(),
+        _ => {}
+    }
+    // `report.md.tera` renders to `report.md`.
+    let template_name = config.template.as_ref().and_then(|path| path.file_name());
+    if let (OutputFormat::Template, Some(name)) = (format, template_name) {
+        let name = name.to_string_lossy();
+        if let Some(stem) = name.strip_suffix(".tera") {
+            return stem.to_string();
+        }
+    }
+    let stem = match format {
+        OutputFormat::GraphQL => "schema",
+        OutputFormat::Rag | OutputFormat::RagJsonl => "rag",
+        OutputFormat::LangChain => "documents",
+        OutputFormat::LlamaIndex => "nodes",
+        OutputFormat::HfDataset => "dataset",
+        OutputFormat::OpenAiFineTune => "openai-finetune",
+        OutputFormat::AnthropicFineTune => "anthropic-finetune",
+        OutputFormat::Mermaid => "diagrams",
+        OutputFormat::Dot => "graphs",
+        OutputFormat::Csv | OutputFormat::Parquet => config.csv_table.name(),
+        OutputFormat::Turtle | OutputFormat::NTriples | OutputFormat::Cypher => "graph",
+        OutputFormat::Scip => "index",
+        OutputFormat::Overview => "overview",
+        OutputFormat::Sarif => "diagnostics",
+        _ => "ast",
+    };
+    format!("{}.{}", stem, output_extension(format))
+}
+
+/// File extension of output in `format`.
+fn output_extension(format: &OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Json
+        | OutputFormat::Rag
+        | OutputFormat::LangChain
+        | OutputFormat::LlamaIndex => "json",
+        OutputFormat::MessagePack => "msgpack",
+        OutputFormat::Markdown | OutputFormat::Mermaid | OutputFormat::Overview => "md",
+        OutputFormat::GraphQL => "graphql",
+        OutputFormat::Dot => "dot",
+        OutputFormat::Csv => "csv",
+        OutputFormat::Parquet => "parquet",
+        OutputFormat::Turtle => "ttl",
+        OutputFormat::NTriples => "nt",
+        OutputFormat::Cypher => "cypher",
+        OutputFormat::Protobuf => "pb",
+        OutputFormat::Scip => "scip",
+        OutputFormat::Ctags | OutputFormat::Etags => "tags",
+        OutputFormat::Sarif => "sarif",
+        OutputFormat::Template => "txt",
+        OutputFormat::Jsonl
+        | OutputFormat::RagJsonl
+        | OutputFormat::HfDataset
+        | OutputFormat::OpenAiFineTune
+        | OutputFormat::AnthropicFineTune => "jsonl",
+    }
+}
+
+/// Render `ast_data` through the configured Tera template.
+fn render_template(ast_data: &ProjectAst, config: &ExtractorConfig) -> Result<String> {
+    let Some(path) = &config.template else {
+        anyhow::bail!("The template format needs a template (--template)");
+    };
+    let template = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read template {}", path.display()))?;
+    format_with_template(ast_data, &template)
+        .with_context(|| format!("Failed to render template {}", path.display()))
+}
+
+/// Render extracted data in the configured output format, reduced to the
+/// configured output budget.
+fn render_project(
+    ast_data: &mut ProjectAst,
+    config: &ExtractorConfig,
+    rag: &LazyRagFormatter,
+    pretty: bool,
+) -> Result<Vec<u8>> {
+    let render = |ast_data: &ProjectAst| -> Result<Vec<u8>> {
+        Ok(match config.output_format {
+            OutputFormat::Json if pretty => serde_json::to_vec_pretty(ast_data)?,
+            OutputFormat::Json => serde_json::to_vec(ast_data)?,
+            OutputFormat::MessagePack => format_as_message_pack(ast_data)?,
+            OutputFormat::Protobuf => format_as_protobuf(ast_data)?,
+            OutputFormat::Scip => format_as_scip(ast_data)?,
+            OutputFormat::Markdown => {
+                format_as_markdown(ast_data, &config.markdown, &config.diagrams)?.into_bytes()
+            }
+            OutputFormat::Mermaid => format_as_mermaid(ast_data, &config.diagrams)?.into_bytes(),
+            OutputFormat::Dot => format_as_dot(ast_data, &config.diagrams)?.into_bytes(),
+            OutputFormat::Csv => format_as_csv(ast_data, config.csv_table)?.into_bytes(),
+            OutputFormat::Parquet => format_as_parquet(ast_data, config.csv_table)?,
+            OutputFormat::Template => render_template(ast_data, config)?.into_bytes(),
+            OutputFormat::Rag => {
+                let document = rag.format(config, ast_data)?;
+                rustex_formats::rag::format_as_json(&document, pretty)?.into_bytes()
+            }
+            OutputFormat::LangChain => {
+                let document = rag.format(config, ast_data)?;
+                format_as_langchain(&document, pretty)?.into_bytes()
+            }
+            OutputFormat::LlamaIndex => {
+                let document = rag.format(config, ast_data)?;
+                format_as_llamaindex(&document, pretty)?.into_bytes()
+            }
+            OutputFormat::HfDataset => {
+                anyhow::bail!("--format hf-dataset writes a directory; give --output-dir")
+            }
+            OutputFormat::OpenAiFineTune => {
+                let document = rag.format(config, ast_data)?;
+                format_as_openai_fine_tuning(&document, &config.fine_tuning)?.into_bytes()
+            }
+            OutputFormat::AnthropicFineTune => {
+                let document = rag.format(config, ast_data)?;
+                format_as_anthropic_fine_tuning(&document, &config.fine_tuning)?.into_bytes()
+            }
+            OutputFormat::Jsonl | OutputFormat::RagJsonl => {
+                generate_jsonl_output(ast_data, config, rag)?.into_bytes()
+            }
+            ref format => format_project_ast(ast_data, format, pretty)?.into_bytes(),
+        })
+    };
+
+    if let Some(limit) = config.output_byte_limit() {
+        fit_to_budget(ast_data, limit, config.truncation_policy, |ast_data| {
+            Ok(render(ast_data)?.len())
+        })?;
+        if let Some(report) = &ast_data.truncation {
+            warn!(
+                "Output reduced from {} to at most {} bytes: {} elements stripped, \
+                 {} elements and {} files dropped",
+                report.original_bytes,
+                report.limit_bytes,
+                report.stripped_elements,
+                report.dropped_elements,
+                report.dropped_files.len()
+            );
+        }
+    }
+
+    render(ast_data)
+}
+
+/// The RAG formatter of a command, built on first use and kept until the
+/// command ends, so chunks rendered again, as when fitting the output to a
+/// budget or after each change in watch mode, aren't embedded or summarized
+/// again. Commands that don't output RAG formats never build it.
+#[derive(Default)]
+struct LazyRagFormatter(OnceCell<RagFormatter>);
+
+impl LazyRagFormatter {
+    /// The formatter of `config`, built by the first call.
+    fn get(&self, config: &ExtractorConfig) -> Result<&RagFormatter> {
+        if let Some(formatter) = self.0.get() {
+            return Ok(formatter);
+        }
+        let formatter = rag_formatter(config)?;
+        Ok(self.0.get_or_init(|| formatter))
+    }
+
+    /// The RAG document of `ast_data`, warning of the chunks whose summary
+    /// failed.
+    fn format(&self, config: &ExtractorConfig, ast_data: &ProjectAst) -> Result<RagDocument> {
+        let document = self.get(config)?.format(ast_data)?;
+        for failure in &document.metadata.failed_summaries {
+            warn!("{}; the chunk has no generated summary", failure);
+        }
+        Ok(document)
+    }
+}
+
+/// The RAG formatter of `config`, chunking elements as set in `[rag]`,
+/// embedding the chunks when `[embeddings]` is enabled, summarizing them
+/// when `[summaries]` is, and leaving out those below the `[rag]
+/// min_quality` or unchanged since the `[rag] since` manifest.
+fn rag_formatter(config: &ExtractorConfig) -> Result<RagFormatter> {
+    let embeddings = &config.embeddings;
+    let rag_config = RagConfig {
+        chunk_strategy: config.rag.chunk_strategy.into(),
+        chunk_overlap_tokens: config.rag.chunk_overlap_tokens,
+        context_radius_lines: config.rag.context_radius_lines,
+        include_parent_signature: config.rag.include_parent_signature,
+        min_quality: config.rag.min_quality,
+        dedup_threshold: config.rag.dedup_threshold,
+        summary_min_complexity: config.summaries.min_complexity,
+        ..RagConfig::default()
+    };
+    let formatter = if embeddings.enabled {
+        let rag_config = RagConfig {
+            include_embeddings: true,
+            embedding_model: Some(embeddings.model.clone()),
+            embedding_batch_size: embeddings.batch_size,
+            embedding_requests_per_minute: embeddings.requests_per_minute,
+            embedding_max_retries: embeddings.max_retries,
+            ..rag_config
+        };
+        RagFormatter::new(rag_config).with_embedding_provider(embedding_provider(embeddings)?)
+    } else {
+        RagFormatter::new(rag_config)
+    };
+    let formatter = if config.summaries.enabled {
+        formatter.with_summarizer(summarizer(&config.summaries)?)
+    } else {
+        formatter
+    };
+    let formatter = match &config.rag.since {
+        Some(path) => {
+            let since = read_rag_manifest(path)?;
+            if !since.config_hash.is_empty() && since.config_hash != config_hash(config) {
+                warn!(
+                    "{} was written with other settings; chunks may differ beyond the changed code",
+                    path.display()
+                );
+            }
+            formatter.with_since(since)
+        }
+        None => formatter,
+    };
+    Ok(formatter)
+}
+
+/// The chunks of `file` to output, embedded when `[embeddings]` is enabled
+/// and summarized when `[summaries]` is, with retrieval keywords weighed
+/// against `corpus`. Near-duplicates of earlier chunks are left to
+/// `deduplicator`. Every other chunk is added to `manifest`, but those
+/// unchanged since the `[rag] since` manifest are left out.
+fn rag_chunks(
+    config: &ExtractorConfig,
+    formatter: &RagFormatter,
+    file: &FileAst,
+    manifest: &mut RagManifest,
+    deduplicator: &mut ChunkDeduplicator,
+    corpus: &KeywordCorpus,
+) -> Result<Vec<RagChunk>> {
+    let mut chunks = formatter.chunks_for_file(file, corpus)?;
+    deduplicator.retain_unique(&mut chunks);
+    manifest.add(&chunks);
+    if let Some(since) = formatter.since() {
+        chunks.retain(|chunk| since.is_changed(chunk));
+    }
+    if config.summaries.enabled {
+        let failures = formatter.summarize_chunks(&mut chunks, std::slice::from_ref(file))?;
+        for failure in failures {
+            warn!("{:#}; the chunk has no generated summary", failure);
+        }
+    }
+    if config.embeddings.enabled {
+        formatter.embed_chunks(&mut chunks)?;
+    }
+    Ok(chunks)
+}
+
+/// Write a line for each chunk `deduplicator` left out, returning whether
+/// they all fit in the `remaining` bytes.
+fn write_duplicate_chunks(
+    writer: &mut impl Write,
+    deduplicator: &ChunkDeduplicator,
+    remaining: &mut usize,
+) -> Result<bool> {
+    let duplicates = deduplicator.duplicates();
+    if !duplicates.is_empty() {
+        info!("{} near-duplicate chunks left out", duplicates.len());
+    }
+    for duplicate in duplicates {
+        if !write_json_line(writer, duplicate, remaining)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Write a line for each chunk of the `[rag] since` manifest missing from
+/// `manifest`, returning whether they all fit in the `remaining` bytes.
+fn write_removed_chunks(
+    writer: &mut impl Write,
+    formatter: &RagFormatter,
+    manifest: &RagManifest,
+    remaining: &mut usize,
+) -> Result<bool> {
+    let Some(since) = formatter.since() else {
+        return Ok(true);
+    };
+    let changes = since.changes_to(manifest);
+    info!(
+        "{} chunks added, {} changed and {} removed; {} unchanged chunks left out",
+        changes.added.len(),
+        changes.changed.len(),
+        changes.removed.len(),
+        changes.unchanged
+    );
+    for removed in changes.removed {
+        if !write_json_line(writer, &RemovedChunk { removed }, remaining)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// The RAG manifest at `path`.
+fn read_rag_manifest(path: &Path) -> Result<RagManifest> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read RAG manifest {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("{} is not a RAG manifest", path.display()))
+}
+
+/// Write `manifest` to the `[rag] manifest` path, if there is one, with the
+/// hash of `config`.
+fn write_rag_manifest(config: &ExtractorConfig, manifest: &RagManifest) -> Result<()> {
+    if let Some(path) = &config.rag.manifest {
+        let manifest = RagManifest {
+            config_hash: config_hash(config),
+            ..manifest.clone()
+        };
+        std::fs::write(path, serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| format!("Failed to write RAG manifest {}", path.display()))?;
+        info!("RAG manifest written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Hash of the settings of `config`, besides the output format and the
+/// `[rag]` manifests, which don't change how chunks are made.
+fn config_hash(config: &ExtractorConfig) -> String {
+    let mut settings = serde_json::to_value(config).unwrap_or_default();
+    if let Some(settings) = settings.as_object_mut() {
+        settings.remove("output_format");
+    }
+    if let Some(rag) = settings.get_mut("rag").and_then(|rag| rag.as_object_mut()) {
+        rag.remove("since");
+        rag.remove("manifest");
+    }
+    format!("{:016x}", stable_hash(&[&settings.to_string()]))
+}
+
+/// Render extracted data as JSON Lines, in the same layout as
+/// [`stream_jsonl_output`].
+fn generate_jsonl_output(
+    ast_data: &ProjectAst,
+    config: &ExtractorConfig,
+    rag: &LazyRagFormatter,
+) -> Result<String> {
+    let mut lines = Vec::new();
+    let mut remaining = usize::MAX;
+    if let OutputFormat::RagJsonl = config.output_format {
+        let formatter = rag.get(config)?;
+        let mut manifest = RagManifest::for_project(&ast_data.project);
+        let mut deduplicator = formatter.deduplicator();
+        let corpus = KeywordCorpus::from_files(&ast_data.files);
+        for file in &ast_data.files {
+            let chunks = rag_chunks(
+                config,
+                formatter,
+                file,
+                &mut manifest,
+                &mut deduplicator,
+                &corpus,
+            )?;
+            for chunk in chunks {
+                write_json_line(&mut lines, &chunk, &mut remaining)?;
+            }
+        }
+        write_duplicate_chunks(&mut lines, &deduplicator, &mut remaining)?;
+        write_removed_chunks(&mut lines, formatter, &manifest, &mut remaining)?;
+    } else {
+        write_json_line(&mut lines, &ast_data.project, &mut remaining)?;
+        for file in &ast_data.files {
+            write_json_line(&mut lines, file, &mut remaining)?;
+        }
+    }
+    Ok(String::from_utf8(lines)?)
+}
+
+/// Settings of `rustex watch` besides the extraction configuration.
+struct WatchOptions {
+    output: Option<PathBuf>,
+    pretty: bool,
+    exec: Option<String>,
+    debounce: std::time::Duration,
+}
+
+async fn watch_command(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    options: WatchOptions,
+) -> Result<()> {
+    use notify::{RecursiveMode, Watcher};
+
+    config.validate()?;
+
+    let (sender, events) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    // Events then carry absolute paths, comparable with the target directory.
+    let watched_path = project_path.canonicalize()?;
+    watcher.watch(&watched_path, RecursiveMode::Recursive)?;
+
+    // Only source changes matter; output and build artifacts are ignored.
+    let target_dir = watched_path.join("target");
+    let is_relevant = |event: &notify::Event| {
+        !event.kind.is_access()
+            && event.paths.iter().any(|path| {
+                let is_source = path.extension().is_some_and(|ext| ext == "rs")
+                    || path.file_name().is_some_and(|name| name == "Cargo.toml");
+                is_source && !path.starts_with(&target_dir)
+            })
+    };
+
+    let mut extractor = IncrementalExtractor::new(AstExtractor::new(config.clone(), project_path));
+    let rag = LazyRagFormatter::default();
+    info!("Watching for changes, press Ctrl-C to stop");
+    loop {
+        match extractor.extract() {
+            Ok((mut ast_data, changes)) => {
+                info!(
+                    "Extracted {} changed files ({} removed, {} unchanged)",
+                    changes.extracted.len(),
+                    changes.removed.len(),
+                    changes.unchanged
+                );
+                if let Err(e) = run_plugins(&mut ast_data, &config, None).await {
+                    error!("Plugins failed: {}", e);
+                }
+                let content = render_project(&mut ast_data, &config, &rag, options.pretty)?;
+                write_output_bytes(options.output.clone(), &content)?;
+                if let Some(command) = &options.exec {
+                    run_exec_command(command);
+                }
+            }
+            // Keep watching, the next change may fix the project.
+            Err(e) => error!("Extraction failed: {}", e),
+        }
+
+        // Wait for a relevant change, then for the changes to settle.
+        loop {
+            match events.recv()? {
+                Ok(event) if is_relevant(&event) => break,
+                Ok(_) => {}
+                Err(e) => warn!("Watch error: {}", e),
+            }
+        }
+        while events.recv_timeout(options.debounce).is_ok() {}
+    }
+}
+
+/// Run the `--exec` command of `rustex watch` through the shell.
+fn run_exec_command(command: &str) {
+    let mut shell = if cfg!(windows) {
+        let mut shell = std::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = std::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    match shell.arg(command).status() {
+        Ok(status) if !status.success() => warn!("`{}` exited with {}", command, status),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to run `{}`: {}", command, e),
+    }
+}
+
+/// Write command output to a file, or to stdout when no file is given.
+fn write_output(output: Option<PathBuf>, content: &str) -> Result<()> {
+    write_output_bytes(output, content.as_bytes())
+}
+
+fn write_output_bytes(output: Option<PathBuf>, content: &[u8]) -> Result<()> {
+    match output {
+        Some(path) => {
+            std::fs::write(&path, content)?;
+            println!("✓ Output written to {}", path.display());
+        }
+        None => {
+            exit_on_broken_pipe(std::io::stdout().write_all(content))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write JSON Lines output incrementally from [`AstExtractor::extract_iter`].
+///
+/// `Jsonl` starts with a project info line followed by one file per line;
+/// `RagJsonl` has one RAG chunk per line. Files that fail to extract are
+/// logged and skipped, and project-wide data is not included. With a byte
+/// limit, writing stops before the first line that would exceed it.
+fn stream_jsonl_output(
+    extractor: &AstExtractor,
+    format: &OutputFormat,
+    output: Option<PathBuf>,
+    compression: Option<Compression>,
+    limit: Option<usize>,
+) -> Result<()> {
+    let files = extractor.extract_iter()?;
+    let total_files = files.len();
+    let writer: Box<dyn Write> = match &output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut writer = std::io::BufWriter::new(Encoder::new(writer, compression)?);
+    let budget = limit.unwrap_or(usize::MAX);
+    let mut remaining = budget;
+
+    if let OutputFormat::Jsonl = format {
+        if !write_json_line(&mut writer, files.project(), &mut remaining)? {
+            anyhow::bail!(
+                "Output budget of {} bytes is too small for the project summary",
+                remaining
+            );
+        }
+    }
+    let strict = extractor.config().strict;
+    let mut failed = 0;
+    let mut written = 0;
+    let mut manifest = RagManifest::for_project(files.project());
+    let formatter = rag_formatter(extractor.config())?;
+    let mut deduplicator = formatter.deduplicator();
+    // Docs are weighed against those of the files streamed so far.
+    let mut corpus = KeywordCorpus::new();
+    let mut complete = true;
+    for result in files {
+        let file = match result {
+            Ok(file) => file,
+            Err(e) if strict => anyhow::bail!("Strict mode: {}", e),
+            Err(e) => {
+                warn!("Skipping file: {}", e);
+                failed += 1;
+                continue;
+            }
+        };
+        if strict {
+            let mut report = ExtractionReport::default();
+            report.add_file(&file);
+            if let Some(problem) = report.incomplete.first() {
+                anyhow::bail!(
+                    "Strict mode: {} ({}): {}",
+                    problem.file.display(),
+                    problem.reason,
+                    problem.message
+                );
+            }
+        }
+        let fits = if let OutputFormat::RagJsonl = format {
+            let mut fits = true;
+            corpus.add_file(&file);
+            let config = extractor.config();
+            let chunks = rag_chunks(
+                config,
+                &formatter,
+                &file,
+                &mut manifest,
+                &mut deduplicator,
+                &corpus,
+            )?;
+            for chunk in chunks {
+                let offset = (budget - remaining) as u64;
+                fits = write_json_line(&mut writer, &chunk, &mut remaining)?;
+                if !fits {
+                    break;
+                }
+                manifest.offsets.insert(chunk.id, offset);
+            }
+            fits
+        } else {
+            write_json_line(&mut writer, &file, &mut remaining)?
+        };
+        if !fits {
+            warn!(
+                "Output limit reached; {} files were left out",
+                total_files - written - failed
+            );
+            complete = false;
+            break;
+        }
+        written += 1;
+    }
+    if let OutputFormat::RagJsonl = format {
+        let config = extractor.config();
+        // Chunks of the files left out would count as removed.
+        let complete = complete
+            && write_duplicate_chunks(&mut writer, &deduplicator, &mut remaining)?
+            && write_removed_chunks(&mut writer, &formatter, &manifest, &mut remaining)?;
+        if complete {
+            manifest.summary.duplicate_chunks = deduplicator.duplicates().len();
+            write_rag_manifest(config, &manifest)?;
+        } else if config.rag.manifest.is_some() {
+            warn!("The RAG manifest isn't written, as the output is incomplete");
+        }
+    }
+    let encoder = writer.into_inner().map_err(|e| e.into_error());
+    exit_on_broken_pipe(encoder.and_then(|encoder| encoder.finish()?.flush()))?;
+
+    if failed > 0 {
+        warn!("{}/{} files failed to process", failed, total_files);
+    }
+    if let Some(path) = output {
+        println!("✓ Output written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Serialize `value` as one line of JSON if it fits in the `remaining` bytes,
+/// returning whether it was written.
+fn write_json_line(
+    writer: &mut impl Write,
+    value: &impl serde::Serialize,
+    remaining: &mut usize,
+) -> Result<bool> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    if line.len() > *remaining {
+        return Ok(false);
+    }
+    *remaining -= line.len();
+    exit_on_broken_pipe(writer.write_all(&line))?;
+    Ok(true)
+}
+
+/// Exit quietly when the reader of stdout went away.
+fn exit_on_broken_pipe(result: std::io::Result<()>) -> Result<()> {
+    match result {
+        // Broken pipe is normal when using tools like `head` - exit gracefully
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => std::process::exit(0),
+        result => Ok(result?),
+    }
+}
+
+/// Render the extraction in `input` in the output format of `config`, with
+/// its output budget and format settings, as `rustex extract` would.
+fn convert_command(
+    input: &Path,
+    config: &ExtractorConfig,
+    output: Option<PathBuf>,
+    pretty: bool,
+) -> Result<()> {
+    let mut ast_data = load_project_ast(input)?;
+    let content = render_project(&mut ast_data, config, &LazyRagFormatter::default(), pretty)?;
+    write_output_bytes(output, &content)
+}
+
+async fn diff_command(
+    old_path: &Path,
+    new_path: &Path,
+    format: CliReportFormat,
+    output: Option<PathBuf>,
+    pretty: bool,
+) -> Result<()> {
+    let old = load_project_ast(old_path)?;
+    let new = load_project_ast(new_path)?;
+    let diff = rustex_core::diff::diff_projects(&old, &new);
+
+    let output_content = match format {
+        CliReportFormat::Json if pretty => format!("{}\n", serde_json::to_string_pretty(&diff)?),
+        CliReportFormat::Json => format!("{}\n", serde_json::to_string(&diff)?),
+        CliReportFormat::Markdown => generate_diff_markdown(&old, &new, &diff),
+    };
+    write_output(output, &output_content)
+}
+
+async fn semver_check_command(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    baseline: Option<String>,
+    format: CliReportFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    config.validate()?;
+
+    let baseline = match baseline {
+        Some(baseline) => baseline,
+        None => {
+            let settings: ApiStabilityConfig = match config.plugins.config.get("api-stability") {
+                Some(settings) => serde_json::from_value(settings.clone())
+                    .context("Invalid api-stability plugin settings")?,
+                None => ApiStabilityConfig::default(),
             };
+            let baseline = settings.baseline.context(
+                "No baseline; pass --baseline or set `baseline` in [plugins.config.api-stability]",
+            )?;
+            // Plugin settings are relative to the project root.
+            let path = project_path.join(&baseline);
+            if is_url(&baseline) || !path.is_file() {
+                baseline
+            } else {
+                path.to_string_lossy().into_owned()
+            }
+        }
+    };
+    let baseline = baseline.as_str();
+
+    let new = AstExtractor::new(config.clone(), project_path.clone()).extract_project()?;
+    let old = if is_url(baseline) {
+        load_baseline(baseline)?
+    } else if Path::new(baseline).is_file() {
+        load_project_ast(Path::new(baseline))?
+    } else {
+        extract_git_revision(&project_path, baseline, &config)?
+    };
+    let report = rustex_core::api_compat::check_compat(&old, &new);
+
+    let output_content = match format {
+        CliReportFormat::Json => format!("{}\n", serde_json::to_string_pretty(&report)?),
+        CliReportFormat::Markdown => generate_compat_markdown(baseline, &old, &new, &report),
+    };
+    write_output(output, &output_content)?;
+
+    let (old_version, new_version) = (&old.project.version, &new.project.version);
+    if report.is_satisfied_by(old_version, new_version) == Some(false) {
+        anyhow::bail!(
+            "The API changes require a {} version bump, but {} -> {} is not one",
+            report.required_bump(),
+            old_version,
+            new_version
+        );
+    }
+
+    Ok(())
+}
+
+/// Extract the project as of a git revision, using a temporary worktree.
+fn extract_git_revision(
+    project_path: &Path,
+    revision: &str,
+    config: &ExtractorConfig,
+) -> Result<rustex_core::ProjectAst> {
+    let prefix = run_git(project_path, &["rev-parse", "--show-prefix"])?;
+    let worktree = tempfile::TempDir::new()?;
+    let worktree_path = worktree.path().to_string_lossy().into_owned();
+    run_git(
+        project_path,
+        &["worktree", "add", "--detach", &worktree_path, revision],
+    )
+    .with_context(|| format!("Failed to check out baseline {}", revision))?;
+
+    let result =
+        AstExtractor::new(config.clone(), worktree.path().join(prefix.trim())).extract_project();
+
+    if let Err(e) = run_git(
+        project_path,
+        &["worktree", "remove", "--force", &worktree_path],
+    ) {
+        error!("Failed to remove baseline worktree: {}", e);
+    }
+    Ok(result?)
+}
+
+/// Run git in `dir` and return its standard output.
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Load an earlier JSON extraction from `input`, or extract the project at
+/// `project_path`.
+fn load_or_extract(
+    config_path: &Option<PathBuf>,
+    project_path: PathBuf,
+    input: Option<PathBuf>,
+    include_private: bool,
+) -> Result<ProjectAst> {
+    if let Some(input) = input {
+        return load_project_ast(&input);
+    }
+    let mut config = load_config(config_path, &project_path)?;
+    config.include_private |= include_private;
+    config.validate()?;
+    Ok(AstExtractor::new(config, project_path).extract_project()?)
+}
+
+/// Load a JSON or MessagePack extraction, decompressing it if it was
+/// compressed.
+fn load_project_ast(path: &Path) -> Result<rustex_core::ProjectAst> {
+    let content = std::fs::read(path)
+        .and_then(decompress)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let is_json = content
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|&byte| byte == b'{');
+    if is_json {
+        serde_json::from_slice(&content)
+            .with_context(|| format!("{} is not a JSON extraction", path.display()))
+    } else {
+        parse_message_pack(&content)
+            .with_context(|| format!("{} is not a JSON or MessagePack extraction", path.display()))
+    }
+}
+
+fn query_command(
+    input: &Path,
+    select: &str,
+    format: CliQueryFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let query = Query::parse(select)?;
+    let index = ProjectIndex::new(load_project_ast(input)?);
+    let results = query.run(&index);
+    let content = match format {
+        CliQueryFormat::Json => format!("{}\n", serde_json::to_string_pretty(&results)?),
+        CliQueryFormat::Table => generate_query_table(&results),
+    };
+    write_output(output, &content)
+}
+
+/// Render query results as a table of elements.
+fn generate_query_table(results: &[ElementRef<'_>]) -> String {
+    let mut table = format!(
+        "{:<48} {:<11} {:<14} {:<32} {:>10}\n",
+        "NAME", "TYPE", "VISIBILITY", "LOCATION", "COMPLEXITY"
+    );
+    for found in results {
+        let element = found.element;
+        let complexity = element
+            .complexity_metrics
+            .as_ref()
+            .map(|metrics| metrics.cyclomatic)
+            .or(element.complexity)
+            .map_or_else(|| "-".to_string(), |complexity| complexity.to_string());
+        let location = format!(
+            "{}:{}",
+            found.file.relative_path.display(),
+            element.location.line_start
+        );
+        table.push_str(&format!(
+            "{:<48} {:<11} {:<14} {:<32} {:>10}\n",
+            element.hierarchy.qualified_name,
+            element.element_type.filter_name(),
+            element.visibility.to_string(),
+            location,
+            complexity
+        ));
+    }
+    table.push_str(&format!("\n{} elements\n", results.len()));
+    table
+}
+
+struct SearchOptions {
+    element_types: Vec<String>,
+    /// Lines of source to show before and after the first line of each match
+    context: usize,
+    limit: Option<usize>,
+    format: CliSearchFormat,
+}
+
+/// A search match with the source around it, for `rustex search`.
+#[derive(serde::Serialize)]
+struct SearchResult<'a> {
+    #[serde(flatten)]
+    found: TextMatch<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<SourceContext>,
+}
+
+#[derive(serde::Serialize)]
+struct SourceContext {
+    /// Line number of the first line
+    first_line: usize,
+    lines: Vec<String>,
+}
+
+fn search_command(
+    index: &ProjectIndex,
+    pattern: &regex::Regex,
+    options: &SearchOptions,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let mut sources: HashMap<&Path, Vec<String>> = HashMap::new();
+    let mut results = Vec::new();
+    for found in index
+        .search_text(pattern, &options.element_types)
+        .into_iter()
+        .take(options.limit.unwrap_or(usize::MAX))
+    {
+        let context = if options.context > 0 {
+            let file = found.found.file;
+            let lines = match sources.entry(&file.path) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    // Extractions can outlive their sources; skip context then.
+                    let source = std::fs::read_to_string(&file.path).unwrap_or_default();
+                    entry.insert(source.lines().map(str::to_string).collect())
+                }
+            };
+            let line = found.found.element.location.line_start.max(1);
+            let first_line = line.saturating_sub(options.context).max(1);
+            let last_line = (line + options.context).min(lines.len());
+            (first_line <= last_line).then(|| SourceContext {
+                first_line,
+                lines: lines[first_line - 1..last_line].to_vec(),
+            })
+        } else {
+            None
+        };
+        results.push(SearchResult { found, context });
+    }
+
+    let content = match options.format {
+        CliSearchFormat::Json => format!("{}\n", serde_json::to_string_pretty(&results)?),
+        CliSearchFormat::Text => generate_search_text(&results),
+    };
+    write_output(output, &content)
+}
+
+/// Render search results like grep: one line per match, followed by the
+/// source context when requested.
+fn generate_search_text(results: &[SearchResult<'_>]) -> String {
+    let mut text = String::new();
+    for result in results {
+        let found = &result.found.found;
+        let element = found.element;
+        let line = element.location.line_start;
+        text.push_str(&format!(
+            "{}:{}: {} {}",
+            found.file.relative_path.display(),
+            line,
+            element.element_type.filter_name(),
+            element.hierarchy.qualified_name
+        ));
+        match result.found.field {
+            TextField::Name => text.push('\n'),
+            TextField::Signature => text.push_str(&format!("\n    {}\n", result.found.text)),
+            TextField::Doc => text.push_str(&format!("\n    /// {}\n", result.found.text)),
+        }
+        if let Some(context) = &result.context {
+            for (offset, source) in context.lines.iter().enumerate() {
+                let number = context.first_line + offset;
+                let marker = if number == line { '>' } else { ' ' };
+                text.push_str(&format!("{} {:>5} | {}\n", marker, number, source));
+            }
+            text.push('\n');
+        }
+    }
+    if results.is_empty() {
+        text.push_str("No matches\n");
+    }
+    text
+}
+
+async fn deps_command(
+    project_path: PathBuf,
+    format: CliDepsFormat,
+    visualize: bool,
+    auditor: Option<RustSecAuditor>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let graph = DependencyGraph::from_project(&project_path)?;
+    let vulnerabilities = match auditor {
+        Some(auditor) => Some(auditor.audit(&graph)?),
+        None => None,
+    };
+
+    let content = if visualize {
+        format_dependency_graph(&graph)
+    } else {
+        match format {
+            CliDepsFormat::Json => {
+                let mut json = serde_json::to_value(&graph)?;
+                if let Some(vulnerabilities) = &vulnerabilities {
+                    json["vulnerabilities"] = serde_json::to_value(vulnerabilities)?;
+                }
+                format!("{}\n", serde_json::to_string_pretty(&json)?)
+            }
+            CliDepsFormat::Table => generate_dependency_table(&graph, vulnerabilities.as_deref()),
+        }
+    };
+    write_output(output, &content)
+}
+
+/// Render the dependency graph as a table of packages followed by duplicates
+/// and, when audited, the packages advisories apply to.
+fn generate_dependency_table(
+    graph: &DependencyGraph,
+    vulnerabilities: Option<&[Vulnerability]>,
+) -> String {
+    let mut table = format!(
+        "{:<32} {:<16} {:<11} {:>5}  {}\n",
+        "PACKAGE", "VERSION", "ROLE", "DEPTH", "KIND"
+    );
+    for package in &graph.packages {
+        let role = match package.role {
+            PackageRole::Root => "root",
+            PackageRole::Direct => "direct",
+            PackageRole::Transitive => "transitive",
+        };
+        // Kinds are only known for the dependencies declared by the project.
+        let mut kinds: Vec<&str> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.to == package.id && package.role == PackageRole::Direct)
+            .map(|edge| match edge.kind {
+                DependencyKind::Normal => "normal",
+                DependencyKind::Dev => "dev",
+                DependencyKind::Build => "build",
+            })
+            .collect();
+        kinds.sort();
+        kinds.dedup();
+        table.push_str(&format!(
+            "{:<32} {:<16} {:<11} {:>5}  {}\n",
+            package.name,
+            package.version,
+            role,
+            package.depth,
+            kinds.join(",")
+        ));
+    }
+
+    table.push_str(&format!(
+        "\n{} direct and {} transitive dependencies\n",
+        graph.packages_with_role(PackageRole::Direct).count(),
+        graph.packages_with_role(PackageRole::Transitive).count()
+    ));
+    if !graph.duplicates.is_empty() {
+        table.push_str("\nDuplicate versions:\n");
+        for duplicate in &graph.duplicates {
+            table.push_str(&format!(
+                "  {}: {}\n",
+                duplicate.name,
+                duplicate.versions.join(", ")
+            ));
+        }
+    }
+    match vulnerabilities {
+        Some([]) => table.push_str("\nNo known vulnerabilities\n"),
+        Some(vulnerabilities) => {
+            table.push_str("\nAdvisories:\n");
+            for vulnerability in vulnerabilities {
+                let fix = if vulnerability.patched.is_empty() {
+                    "no patched version".to_string()
+                } else {
+                    format!("upgrade to {}", vulnerability.patched.join(" or "))
+                };
+                table.push_str(&format!(
+                    "  {} {}: {} {}{} ({})\n",
+                    vulnerability.package,
+                    vulnerability.version,
+                    vulnerability.advisory,
+                    vulnerability
+                        .informational
+                        .as_ref()
+                        .map(|kind| format!("[{}] ", kind))
+                        .unwrap_or_default(),
+                    vulnerability.title,
+                    fix
+                ));
+            }
+        }
+        None => {}
+    }
+    table
+}
+
+/// Sections of a text or Markdown metrics report.
+#[derive(Clone, Copy)]
+struct MetricsSections {
+    /// Lines of code and the largest elements
+    loc: bool,
+    /// Complexity distribution and the most complex elements
+    complexity: bool,
+}
+
+async fn metrics_command(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    sections: MetricsSections,
+    format: CliMetricsFormat,
+    top: usize,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    config.validate()?;
+
+    let ast_data = AstExtractor::new(config, project_path).extract_project()?;
+    let report = MetricsReport::from_project(&ast_data, top);
+    let content = match format {
+        CliMetricsFormat::Json => format!("{}\n", serde_json::to_string_pretty(&report)?),
+        CliMetricsFormat::Text => generate_metrics_text(&report, sections),
+        CliMetricsFormat::Markdown => generate_metrics_markdown(&report, sections),
+    };
+    write_output(output, &content)
+}
+
+async fn check_command(
+    project_path: PathBuf,
+    mut config: ExtractorConfig,
+    input: Option<PathBuf>,
+    format: CliCheckFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    config.validate()?;
+    let thresholds = config.thresholds.clone();
+    if thresholds.is_empty() {
+        anyhow::bail!(
+            "No thresholds to check; set them in the [thresholds] section of rustex.toml \
+             or with flags such as --max-cyclomatic"
+        );
+    }
+
+    let ast_data = match input {
+        Some(input) => load_project_ast(&input)?,
+        None => {
+            let mut ast_data = AstExtractor::new(config.clone(), project_path).extract_project()?;
+            if thresholds.max_vulnerabilities.is_some() {
+                // The audit is the only plugin the thresholds need.
+                config.plugins.enabled = vec!["rustsec-audit".to_string()];
+                run_plugins(&mut ast_data, &config, None).await?;
+            }
+            ast_data
+        }
+    };
+    if thresholds.max_vulnerabilities.is_some() {
+        let results = ast_data.plugin_results.get("rustsec-audit");
+        if !results.is_some_and(|results| results.metrics.contains_key("vulnerabilities")) {
+            let failure = results
+                .and_then(|results| results.messages.first())
+                .map(|message| format!(": {}", message.message))
+                .unwrap_or_default();
+            anyhow::bail!(
+                "max_vulnerabilities needs the results of the rustsec-audit plugin{}",
+                failure
+            );
+        }
+    }
+    let violations = check_thresholds(&ast_data, &thresholds);
+
+    let content = match format {
+        CliCheckFormat::Json => format!(
+            "{}\n",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "passed": violations.is_empty(),
+                "thresholds": thresholds,
+                "violations": violations,
+            }))?
+        ),
+        CliCheckFormat::Text if violations.is_empty() => "✓ All thresholds passed\n".to_string(),
+        CliCheckFormat::Text => violations
+            .iter()
+            .map(|violation| format!("✗ {}\n", violation))
+            .collect(),
+    };
+    write_output(output, &content)?;
+
+    if !violations.is_empty() {
+        anyhow::bail!("{} threshold violation(s)", violations.len());
+    }
+
+    Ok(())
+}
+
+fn coverage_command(
+    ast_data: &ProjectAst,
+    format: CliCoverageFormat,
+    min_coverage: Option<f64>,
+    weighted: bool,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let coverage = doc_coverage(ast_data);
+    let content = match format {
+        CliCoverageFormat::Markdown => format_coverage_markdown(&coverage),
+        CliCoverageFormat::Json => format!("{}\n", serde_json::to_string_pretty(&coverage)?),
+        CliCoverageFormat::Badge => format_coverage_badge(&coverage, weighted)?,
+    };
+    write_output(output, &content)?;
+
+    let (kind, percent) = if weighted {
+        (
+            "Quality-weighted documentation",
+            coverage.total.weighted_percent,
+        )
+    } else {
+        ("Documentation", coverage.total.coverage_percent)
+    };
+    if let Some(min_coverage) = min_coverage {
+        if percent < min_coverage {
+            anyhow::bail!(
+                "{} coverage {:.1}% is below the minimum of {:.1}%",
+                kind,
+                percent,
+                min_coverage
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Render a metrics report as plain text.
+fn generate_metrics_text(report: &MetricsReport, sections: MetricsSections) -> String {
+    let mut text = format!("Metrics for {}\n", report.project);
+
+    if sections.loc {
+        let lines = &report.lines;
+        text.push_str(&format!(
+            "\n  Files: {}\n  Lines of code: {}\n  Comment lines: {}\n",
+            lines.files, lines.code, lines.comments
+        ));
+    }
+    if sections.complexity {
+        let complexity = &report.complexity;
+        text.push_str(&format!(
+            "\n  Functions: {} (average cyclomatic {:.2}, max {})\n",
+            complexity.functions, complexity.average_cyclomatic, complexity.max_cyclomatic
+        ));
+        text.push_str(&format!(
+            "  Complexity: {} low, {} medium, {} high, {} very high\n",
+            complexity.low, complexity.medium, complexity.high, complexity.very_high
+        ));
+    }
+    let documentation = &report.documentation;
+    text.push_str(&format!(
+        "\n  Documented public items: {}/{} ({:.1}%)\n",
+        documentation.documented, documentation.public_items, documentation.coverage_percent
+    ));
+    let unsafe_usage = &report.unsafe_usage;
+    text.push_str(&format!(
+        "  Unsafe: {} blocks, {} functions, {} impls, {} traits\n",
+        unsafe_usage.unsafe_blocks,
+        unsafe_usage.unsafe_functions,
+        unsafe_usage.unsafe_impls,
+        unsafe_usage.unsafe_traits
+    ));
+
+    let mut hotspots = |title: &str, unit: &str, hotspots: &[Hotspot]| {
+        if hotspots.is_empty() {
+            return;
+        }
+        text.push_str(&format!("\n{}:\n", title));
+        for hotspot in hotspots {
+            text.push_str(&format!(
+                "  {} ({:?}) {}:{}: {} {}\n",
+                hotspot.name,
+                hotspot.element_type,
+                hotspot.file.display(),
+                hotspot.line,
+                hotspot.value,
+                unit
+            ));
+        }
+    };
+    if sections.loc {
+        hotspots("Largest elements", "lines", &report.largest_elements);
+    }
+    if sections.complexity {
+        hotspots(
+            "Most complex elements",
+            "cyclomatic",
+            &report.most_complex_elements,
+        );
+    }
+    text
+}
+
+/// Render a metrics report as Markdown.
+fn generate_metrics_markdown(report: &MetricsReport, sections: MetricsSections) -> String {
+    let mut markdown = format!(
+        "# Metrics: {}\n\n| Metric | Value |\n|---|---|\n",
+        report.project
+    );
+    let mut row = |metric: &str, value: String| {
+        markdown.push_str(&format!("| {} | {} |\n", metric, value));
+    };
 
-            match output {
-                Some(path) => {
-                    std::fs::write(&path, output_content)?;
-                    println!("✓ Output written to {}", path.display());
-                }
-                None => {
-                    // Handle broken pipe gracefully (e.g., when piping to `head`)
-                    use std::io::Write;
-                    if let Err(e) = std::io::stdout().write_all(output_content.as_bytes()) {
-                        if e.kind() == std::io::ErrorKind::BrokenPipe {
-                            // Broken pipe is normal when using tools like `head` - exit gracefully
-                            std::process::exit(0);
-                        } else {
-                            return Err(e.into());
-                        }
-                    }
-                }
-            }
+    if sections.loc {
+        row("Files", report.lines.files.to_string());
+        row("Lines of code", report.lines.code.to_string());
+        row("Comment lines", report.lines.comments.to_string());
+    }
+    if sections.complexity {
+        let complexity = &report.complexity;
+        row("Functions", complexity.functions.to_string());
+        row(
+            "Average cyclomatic complexity",
+            format!("{:.2}", complexity.average_cyclomatic),
+        );
+        row(
+            "Max cyclomatic complexity",
+            complexity.max_cyclomatic.to_string(),
+        );
+        row(
+            "Complexity (low / medium / high / very high)",
+            format!(
+                "{} / {} / {} / {}",
+                complexity.low, complexity.medium, complexity.high, complexity.very_high
+            ),
+        );
+    }
+    let documentation = &report.documentation;
+    row(
+        "Documented public items",
+        format!(
+            "{}/{} ({:.1}%)",
+            documentation.documented, documentation.public_items, documentation.coverage_percent
+        ),
+    );
+    let unsafe_usage = &report.unsafe_usage;
+    row(
+        "Unsafe (blocks / functions / impls / traits)",
+        format!(
+            "{} / {} / {} / {}",
+            unsafe_usage.unsafe_blocks,
+            unsafe_usage.unsafe_functions,
+            unsafe_usage.unsafe_impls,
+            unsafe_usage.unsafe_traits
+        ),
+    );
 
-            // Print summary
-            print_extraction_summary(&ast_data);
+    let mut hotspots = |title: &str, unit: &str, hotspots: &[Hotspot]| {
+        if hotspots.is_empty() {
+            return;
         }
-        Err(e) => {
-            pb.finish_with_message("✗ AST extraction failed");
+        markdown.push_str(&format!(
+            "\n## {}\n\n| Element | Type | Location | {} |\n|---|---|---|---|\n",
+            title, unit
+        ));
+        for hotspot in hotspots {
+            markdown.push_str(&format!(
+                "| `{}` | {:?} | {}:{} | {} |\n",
+                hotspot.name,
+                hotspot.element_type,
+                hotspot.file.display(),
+                hotspot.line,
+                hotspot.value
+            ));
+        }
+    };
+    if sections.loc {
+        hotspots("Largest elements", "Lines", &report.largest_elements);
+    }
+    if sections.complexity {
+        hotspots(
+            "Most complex elements",
+            "Cyclomatic",
+            &report.most_complex_elements,
+        );
+    }
+    markdown
+}
 
-            // Provide more helpful error messages based on error type
-            match &e {
-                rustex_core::RustExError::PartialFailure {
-                    failed_count,
-                    total_count,
-                    ..
-                } => {
-                    error!(
-                        "Partial extraction failure: {}/{} files failed",
-                        failed_count, total_count
-                    );
-                    error!("Consider checking file permissions, syntax errors, or adjusting file size limits");
-                }
-                rustex_core::RustExError::InvalidProjectRoot { path } => {
-                    error!("Invalid project root: {:?}", path);
-                    error!("Make sure the path exists and contains a valid Rust project");
-                }
-                _ => {
-                    error!("Extraction failed: {}", e);
-                }
-            }
+async fn deprecations_command(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    baseline: Option<&Path>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    config.validate()?;
 
-            return Err(e.into());
-        }
+    let ast_data = AstExtractor::new(config, project_path).extract_project()?;
+    let deprecations = &ast_data.metrics.deprecations;
+    write_output(output, &generate_deprecation_report(deprecations))?;
+
+    let Some(baseline) = baseline else {
+        return Ok(());
+    };
+    let baseline = load_project_ast(baseline)?;
+    let known_callers: HashMap<&str, &[String]> = baseline
+        .metrics
+        .deprecations
+        .items
+        .iter()
+        .map(|item| (item.qualified_name.as_str(), item.callers.as_slice()))
+        .collect();
+    let new_usages: Vec<String> = deprecations
+        .items
+        .iter()
+        .flat_map(|item| {
+            let known = known_callers
+                .get(item.qualified_name.as_str())
+                .copied()
+                .unwrap_or_default();
+            item.callers
+                .iter()
+                .filter(|caller| !known.contains(caller))
+                .map(move |caller| format!("{} -> {}", caller, item.qualified_name))
+        })
+        .collect();
+    if !new_usages.is_empty() {
+        anyhow::bail!(
+            "{} new usage(s) of deprecated items:\n  {}",
+            new_usages.len(),
+            new_usages.join("\n  ")
+        );
     }
 
     Ok(())
 }
 
-async fn deps_command(
-    _project_path: PathBuf,
-    _visualize: bool,
-    _output: Option<PathBuf>,
-) -> Result<()> {
-    println!("🚧 Dependency analysis not yet implemented");
-    Ok(())
+/// Render the deprecated items of a project as a plain-text listing.
+fn generate_deprecation_report(deprecations: &rustex_core::DeprecationSummary) -> String {
+    let mut report = format!(
+        "Deprecated items: {} ({} usages)\n",
+        deprecations.total_deprecated, deprecations.total_usages
+    );
+    for item in &deprecations.items {
+        report.push_str(&format!(
+            "\n{} ({:?})",
+            item.qualified_name, item.element_type
+        ));
+        if let Some(since) = &item.since {
+            report.push_str(&format!(" since {}", since));
+        }
+        if let Some(note) = &item.note {
+            report.push_str(&format!(": {}", note));
+        }
+        report.push('\n');
+        if item.callers.is_empty() {
+            report.push_str("  no callers\n");
+        } else {
+            report.push_str(&format!("  used by: {}\n", item.callers.join(", ")));
+        }
+    }
+    report
 }
 
-async fn metrics_command(
-    _project_path: PathBuf,
-    _complexity: bool,
-    _loc: bool,
-    _output: Option<PathBuf>,
+async fn ownership_command(
+    project_path: PathBuf,
+    config: ExtractorConfig,
+    output: Option<PathBuf>,
 ) -> Result<()> {
-    println!("🚧 Metrics analysis not yet implemented");
-    Ok(())
+    config.validate()?;
+
+    let ast_data = AstExtractor::new(config, project_path).extract_project()?;
+    write_output(output, &generate_ownership_report(&ast_data))
+}
+
+/// Files listed in the ownership report, by descending clone density.
+const OWNERSHIP_REPORT_FILES: usize = 10;
+
+/// Render the ownership profile of a project and its most cloning files.
+fn generate_ownership_report(ast_data: &rustex_core::ProjectAst) -> String {
+    let profile = &ast_data.metrics.ownership;
+    let mut report = format!(
+        "Ownership profile ({} lines of code)\n\n",
+        ast_data.metrics.total_lines
+    );
+    report.push_str(&format!(
+        "  Lifetime annotations: {}\n",
+        profile.lifetime_annotations
+    ));
+    report.push_str(&format!(
+        "  Reference parameters: {} shared, {} mutable\n",
+        profile.shared_ref_params, profile.mut_ref_params
+    ));
+    report.push_str(&format!(
+        "  Arc: {}, Rc: {}, RefCell: {}\n",
+        profile.arc_usages, profile.rc_usages, profile.refcell_usages
+    ));
+    report.push_str(&format!(
+        "  .clone() calls: {} ({:.2} per 100 lines)\n",
+        profile.clone_calls, profile.clone_density
+    ));
+
+    let mut files: Vec<_> = ast_data
+        .files
+        .iter()
+        .filter(|file| file.file_metrics.ownership.clone_calls > 0)
+        .collect();
+    files.sort_by(|a, b| {
+        let density = |file: &rustex_core::FileAst| file.file_metrics.ownership.clone_density;
+        density(b).total_cmp(&density(a))
+    });
+    if !files.is_empty() {
+        report.push_str("\nHighest clone density:\n");
+    }
+    for file in files.into_iter().take(OWNERSHIP_REPORT_FILES) {
+        let ownership = &file.file_metrics.ownership;
+        report.push_str(&format!(
+            "  {}: {} clones, {:.2} per 100 lines\n",
+            file.relative_path.display(),
+            ownership.clone_calls,
+            ownership.clone_density
+        ));
+    }
+    report
 }
 
-async fn config_command(action: ConfigAction, global_config_path: Option<&PathBuf>) -> Result<()> {
+async fn config_command(
+    action: ConfigAction,
+    global_config_path: Option<&PathBuf>,
+    project_path: &Path,
+) -> Result<()> {
     match action {
         ConfigAction::Init {
             force,
@@ -392,22 +3477,56 @@ async fn config_command(action: ConfigAction, global_config_path: Option<&PathBu
             }
         }
 
-        ConfigAction::Validate { file } => {
+        ConfigAction::Validate { file, format } => {
             let config_path = resolve_config_path(file.as_ref(), global_config_path)?;
-            let config = ExtractorConfig::from_toml_file(&config_path)?;
+            let content = std::fs::read_to_string(&config_path).with_context(|| {
+                format!("Failed to read config file: {}", config_path.display())
+            })?;
+            let schemas = plugin_config_schemas()?;
+            let diagnostics =
+                diagnose_config_str_with_schemas(&content, Some(project_path), &schemas);
+            let errors = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+                .count();
 
-            match config.validate() {
-                Ok(()) => {
-                    println!("✓ Configuration is valid: {}", config_path.display());
+            match format {
+                CliDiagnosticFormat::Text => {
+                    print_config_diagnostics(&config_path, &diagnostics);
                 }
-                Err(e) => {
-                    error!("✗ Configuration validation failed: {}", e);
-                    return Err(e);
+                CliDiagnosticFormat::Json => {
+                    let report = serde_json::json!({
+                        "file": config_path,
+                        "valid": errors == 0,
+                        "diagnostics": diagnostics,
+                    });
+                    let json = serde_json::to_string_pretty(&report)?;
+                    write_output(None, &format!("{}\n", json))?;
                 }
             }
+
+            if errors > 0 {
+                anyhow::bail!("Configuration validation failed with {} error(s)", errors);
+            }
+        }
+
+        ConfigAction::Show {
+            file,
+            resolved: true,
+        } => {
+            let layers = config_layers(file.as_ref().or(global_config_path), project_path);
+            let resolved = layers.resolve()?;
+
+            println!("# Resolved configuration");
+            for (key, value, source) in resolved.settings()? {
+                println!("{} = {}  # {}", key, value, source);
+            }
         }
 
-        ConfigAction::Show { file } => {
+        ConfigAction::Show {
+            file,
+            resolved: false,
+        } => {
             let config_path = resolve_config_path(file.as_ref(), global_config_path)?;
             let config = ExtractorConfig::from_toml_file(&config_path)?;
 
@@ -430,63 +3549,387 @@ async fn config_command(action: ConfigAction, global_config_path: Option<&PathBu
     Ok(())
 }
 
-/// Load configuration from file or use defaults.
-fn load_config(config_path: &Option<PathBuf>, project_path: &Path) -> Result<ExtractorConfig> {
-    if let Some(path) = config_path {
-        // Use explicitly provided config file
-        ExtractorConfig::from_toml_file(path)
-            .with_context(|| format!("Failed to load configuration from {}", path.display()))
-    } else {
-        // Try to find config in standard locations relative to project
-        let project_config = project_path.join("rustex.toml");
-        if project_config.exists() {
-            info!("Using project config: {}", project_config.display());
-            return ExtractorConfig::from_toml_file(&project_config);
+fn plugins_command(
+    action: PluginsAction,
+    config_path: &Option<PathBuf>,
+    project_path: &Path,
+) -> Result<()> {
+    let plugin_dir =
+        rustex_core::plugin_dir().context("Failed to find the user's config directory")?;
+    match &action {
+        PluginsAction::Install { path, force } => {
+            std::fs::create_dir_all(&plugin_dir).with_context(|| {
+                format!(
+                    "Failed to create plugin directory: {}",
+                    plugin_dir.display()
+                )
+            })?;
+            let manifest = install_plugin(path, &plugin_dir, *force)?;
+            println!(
+                "✓ Installed plugin `{}` {} in {}",
+                manifest.name,
+                manifest.version,
+                plugin_dir.join(&manifest.name).display()
+            );
+            println!("  Enable it with `rustex plugins enable {}`", manifest.name);
+            return Ok(());
+        }
+        PluginsAction::Uninstall { name } => {
+            uninstall_plugin(name, &plugin_dir)?;
+            println!("✓ Uninstalled plugin `{}`", name);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let config = load_config(config_path, project_path)?;
+    let mut manager = PluginManager::with_config(PluginManagerConfig::from(&config));
+    register_builtin_plugins(&mut manager)?;
+    register_installed_plugins(&mut manager);
+
+    let name = match &action {
+        PluginsAction::List => {
+            let mut table = format!(
+                "{:<24} {:<10} {:<22} {:<10} {}\n",
+                "PLUGIN", "VERSION", "ENABLED", "SOURCE", "DESCRIPTION"
+            );
+            let plugins = manager.list_plugins();
+            for info in plugins
+                .iter()
+                .filter_map(|name| manager.get_plugin_info(name))
+            {
+                table.push_str(&format!(
+                    "{:<24} {:<10} {:<22} {:<10} {}\n",
+                    info.name,
+                    info.version,
+                    plugin_status(&manager, &config, &info),
+                    plugin_source(&info),
+                    info.description
+                ));
+            }
+            print!("{}", table);
+            return Ok(());
+        }
+        PluginsAction::Info { name }
+        | PluginsAction::Enable { name }
+        | PluginsAction::Disable { name }
+        | PluginsAction::Reset { name } => name.clone(),
+        PluginsAction::Install { .. } | PluginsAction::Uninstall { .. } => {
+            unreachable!("installs and uninstalls return early")
+        }
+    };
+    let Some(info) = manager.get_plugin_info(&name) else {
+        anyhow::bail!(
+            "Unknown plugin `{}`, available plugins: {}",
+            name,
+            manager.list_plugins().join(", ")
+        );
+    };
+
+    // The file enable, disable and reset record the setting in.
+    let config_file = config_layers(config_path.as_ref(), project_path)
+        .project_file_path()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| project_path.join("rustex.toml"));
+    let (list, message) = match action {
+        PluginsAction::Info { .. } => {
+            println!("{} {}", info.name, info.version);
+            println!("{}", info.description);
+            if let Some(author) = &info.author {
+                println!("Author: {}", author);
+            }
+            println!("Enabled: {}", plugin_status(&manager, &config, &info));
+            if plugin_source(&info) == "installed" {
+                println!("Installed in: {}", plugin_dir.join(&info.name).display());
+            }
+            println!("Phases:");
+            for phase in &info.supported_phases {
+                println!("  {:?}: {}", phase, phase.description());
+            }
+            if !info.dependencies.is_empty() {
+                println!("Dependencies: {}", info.dependencies.join(", "));
+            }
+            return Ok(());
         }
+        PluginsAction::Enable { .. } => (Some("plugins"), "Enabled"),
+        PluginsAction::Disable { .. } => (Some("disabled_plugins"), "Disabled"),
+        _ => (None, "Reset"),
+    };
+    set_plugin_list(&config_file, &name, list)?;
+    println!(
+        "✓ {} plugin `{}` in {}",
+        message,
+        name,
+        config_file.display()
+    );
+    Ok(())
+}
+
+/// Whether a plugin is enabled, and by which setting.
+fn plugin_status(manager: &PluginManager, config: &ExtractorConfig, info: &PluginInfo) -> String {
+    let enabled = if manager.is_plugin_enabled(&info.name) {
+        "yes"
+    } else {
+        "no"
+    };
+    let source = if config.disabled_plugins.contains(&info.name) {
+        "disabled_plugins"
+    } else if config.plugins.enabled.contains(&info.name) {
+        "plugins"
+    } else {
+        "default"
+    };
+    format!("{} ({})", enabled, source)
+}
+
+/// Whether a plugin is built in or installed from a plugin directory.
+fn plugin_source(info: &PluginInfo) -> &'static str {
+    if rustex_core::config_diagnostics::BUILTIN_PLUGINS.contains(&info.name.as_str()) {
+        "built-in"
+    } else {
+        "installed"
+    }
+}
+
+/// JSON schemas of the settings of the built-in and installed plugins, by
+/// plugin name.
+fn plugin_config_schemas() -> Result<BTreeMap<String, serde_json::Value>> {
+    let mut manager = PluginManager::new();
+    register_builtin_plugins(&mut manager)?;
+    register_installed_plugins(&mut manager);
+    Ok(manager
+        .list_plugins()
+        .into_iter()
+        .filter_map(|name| {
+            let schema = manager.get_config_schema(&name)?;
+            Some((name, schema))
+        })
+        .collect())
+}
 
-        let alt_project_config = project_path.join(".rustex.toml");
-        if alt_project_config.exists() {
-            info!("Using project config: {}", alt_project_config.display());
-            return ExtractorConfig::from_toml_file(&alt_project_config);
+/// Move `name` to the `list` plugin list of the config file at `path`, or
+/// out of both lists without one, keeping the rest of the file as written.
+/// Plugins written as a table keep their list as `plugins.enabled`.
+fn set_plugin_list(path: &Path, name: &str, list: Option<&str>) -> Result<()> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let mut document: toml_edit::DocumentMut = content
+        .parse()
+        .with_context(|| format!("Failed to parse TOML config: {}", path.display()))?;
+    for key in ["plugins", "disabled_plugins"] {
+        if !document.contains_key(key) && list != Some(key) {
+            continue;
         }
+        let mut item = document
+            .entry(key)
+            .or_insert_with(|| toml_edit::value(toml_edit::Array::new()));
+        if item.is_table_like() {
+            item = item
+                .as_table_like_mut()
+                .expect("table-like item")
+                .entry("enabled")
+                .or_insert(toml_edit::value(toml_edit::Array::new()));
+        }
+        let plugins = item
+            .as_array_mut()
+            .with_context(|| format!("`{}` in {} isn't a list", key, path.display()))?;
+        plugins.retain(|plugin| plugin.as_str() != Some(name));
+        if list == Some(key) {
+            plugins.push(name);
+        }
+        plugins.fmt();
+    }
+    std::fs::write(path, document.to_string())
+        .with_context(|| format!("Failed to write config file: {}", path.display()))
+}
 
-        // Fall back to standard locations
-        let config = ExtractorConfig::load_from_standard_locations();
-        info!("Using default configuration");
-        Ok(config)
+/// Resolve configuration from defaults, config files and environment variables.
+fn load_config(config_path: &Option<PathBuf>, project_path: &Path) -> Result<ExtractorConfig> {
+    let layers = config_layers(config_path.as_ref(), project_path);
+    match layers.project_file_path() {
+        Some(path) => info!("Using project config: {}", path.display()),
+        None => info!("Using default configuration"),
+    }
+    let resolved = layers.resolve().with_context(|| match config_path {
+        Some(path) => format!("Failed to load configuration from {}", path.display()),
+        None => "Failed to load configuration".to_string(),
+    })?;
+    Ok(resolved.config)
+}
+
+/// The configuration layers for a project, with an explicitly provided
+/// config file taking the place of the project's own.
+fn config_layers(config_path: Option<&PathBuf>, project_path: &Path) -> ConfigLayers {
+    let layers = ConfigLayers::standard(project_path);
+    match config_path {
+        Some(path) => layers.project_file(path),
+        None => layers,
     }
 }
 
 /// CLI arguments for overriding configuration.
+#[derive(Default)]
 struct CliOverrides {
-    format: OutputFormat,
+    format: Option<OutputFormat>,
     include_docs: bool,
     include_private: bool,
     parse_deps: bool,
-    max_file_size: usize,
+    expand_macros: bool,
+    no_ignore: bool,
+    cfg_features: Option<Vec<String>>,
+    max_file_size: Option<usize>,
+    partial_oversize_files: bool,
+    strict: bool,
+    max_output_tokens: Option<usize>,
+    max_output_bytes: Option<usize>,
+    truncation_policy: Option<TruncationPolicy>,
+    diagrams: Vec<Diagram>,
+    csv_table: Option<CsvTable>,
+    template: Option<PathBuf>,
+    markdown_sections: Vec<MarkdownSection>,
+    markdown_exclude_sections: Vec<MarkdownSection>,
+    heading_level: Option<usize>,
+    no_toc: bool,
+    no_cross_links: bool,
+    repo_url: Option<String>,
+    embed: bool,
+    embedding_url: Option<String>,
+    embedding_model: Option<String>,
+    embedding_model_dir: Option<PathBuf>,
+    summarize: bool,
+    chunk_strategy: Option<RagChunkStrategy>,
+    chunk_overlap_tokens: Option<usize>,
+    context_radius_lines: Option<usize>,
+    no_parent_signature: bool,
+    since: Option<PathBuf>,
+    rag_manifest: Option<PathBuf>,
+    validation_split: Option<f64>,
+    test_split: Option<f64>,
     include_patterns: Vec<String>,
     exclude_patterns: Vec<String>,
+    visibility: Vec<String>,
+    exclude_build_scripts: bool,
     plugins: Vec<String>,
+    crates: Vec<String>,
+    jobs: Option<usize>,
 }
 
 /// Override configuration with CLI arguments.
+///
+/// Flags only override the resolved configuration when they were given, so
+/// settings from config files and environment variables are kept otherwise.
 fn override_config_with_cli_args(config: &mut ExtractorConfig, overrides: CliOverrides) {
-    // Only override if CLI args were explicitly provided
-    // For most args, clap provides default values, so we check against defaults
+    if let Some(format) = overrides.format {
+        config.output_format = format;
+    }
 
-    // Always override format if provided
-    config.output_format = overrides.format;
+    if overrides.include_docs {
+        config.include_docs = true;
+    }
+    if overrides.include_private {
+        config.include_private = true;
+    }
+    if overrides.parse_deps {
+        config.parse_dependencies = true;
+    }
+    if overrides.expand_macros {
+        config.expand_macros = true;
+    }
+    if overrides.no_ignore {
+        config.respect_gitignore = false;
+    }
+    if overrides.cfg_features.is_some() {
+        config.cfg_features = overrides.cfg_features;
+    }
 
-    // These need special handling as clap provides false as default
-    // In a real implementation, you'd use Option<bool> and check for Some(value)
-    config.include_docs = overrides.include_docs;
-    config.include_private = overrides.include_private;
-    config.parse_dependencies = overrides.parse_deps;
+    if let Some(max_file_size) = overrides.max_file_size {
+        config.max_file_size = max_file_size;
+    }
+    if overrides.partial_oversize_files {
+        config.partial_oversize_files = true;
+    }
+    if overrides.strict {
+        config.strict = true;
+    }
 
-    // Override file size if not default
-    if overrides.max_file_size != 10485760 {
-        // 10MB default
-        config.max_file_size = overrides.max_file_size;
+    if overrides.max_output_tokens.is_some() {
+        config.max_output_tokens = overrides.max_output_tokens;
+    }
+    if overrides.max_output_bytes.is_some() {
+        config.max_output_bytes = overrides.max_output_bytes;
+    }
+    if let Some(policy) = overrides.truncation_policy {
+        config.truncation_policy = policy;
+    }
+    if !overrides.diagrams.is_empty() {
+        config.diagrams = overrides.diagrams;
+    }
+    if let Some(table) = overrides.csv_table {
+        config.csv_table = table;
+    }
+    if let Some(template) = overrides.template {
+        config.template = Some(template);
+    }
+    if !overrides.markdown_sections.is_empty() {
+        config.markdown.sections = overrides.markdown_sections;
+    }
+    if !overrides.markdown_exclude_sections.is_empty() {
+        config.markdown.exclude_sections = overrides.markdown_exclude_sections;
+    }
+    if let Some(level) = overrides.heading_level {
+        config.markdown.heading_level = level;
+    }
+    if overrides.no_toc {
+        config.markdown.table_of_contents = false;
+    }
+    if overrides.no_cross_links {
+        config.markdown.cross_links = false;
+    }
+    if overrides.repo_url.is_some() {
+        config.markdown.repo_url = overrides.repo_url;
+    }
+    if overrides.embed {
+        config.embeddings.enabled = true;
+    }
+    if let Some(url) = overrides.embedding_url {
+        config.embeddings.url = url;
+    }
+    if let Some(model) = overrides.embedding_model {
+        config.embeddings.model = model;
+    }
+    if overrides.embedding_model_dir.is_some() {
+        config.embeddings.model_dir = overrides.embedding_model_dir;
+    }
+    if overrides.summarize {
+        config.summaries.enabled = true;
+    }
+    if let Some(strategy) = overrides.chunk_strategy {
+        config.rag.chunk_strategy = strategy;
+    }
+    if let Some(tokens) = overrides.chunk_overlap_tokens {
+        config.rag.chunk_overlap_tokens = tokens;
+    }
+    if let Some(lines) = overrides.context_radius_lines {
+        config.rag.context_radius_lines = lines;
+    }
+    if overrides.no_parent_signature {
+        config.rag.include_parent_signature = false;
+    }
+    if overrides.since.is_some() {
+        config.rag.since = overrides.since;
+    }
+    if overrides.rag_manifest.is_some() {
+        config.rag.manifest = overrides.rag_manifest;
+    }
+    if let Some(split) = overrides.validation_split {
+        config.hf_dataset.validation_split = split;
+    }
+    if let Some(split) = overrides.test_split {
+        config.hf_dataset.test_split = split;
     }
 
     // Override patterns if provided
@@ -498,9 +3941,59 @@ fn override_config_with_cli_args(config: &mut ExtractorConfig, overrides: CliOve
         config.filters.exclude = overrides.exclude_patterns;
     }
 
+    if !overrides.visibility.is_empty() {
+        config.filters.visibility = overrides.visibility;
+    }
+
+    if overrides.exclude_build_scripts {
+        config.filters.exclude_build_scripts = true;
+    }
+
     // Override plugins if provided
     if !overrides.plugins.is_empty() {
-        config.plugins = overrides.plugins;
+        config.plugins.enabled = overrides.plugins;
+    }
+
+    if !overrides.crates.is_empty() {
+        config.crates = overrides.crates;
+    }
+
+    if let Some(jobs) = overrides.jobs {
+        config.parallel_workers = jobs;
+    }
+}
+
+/// Print config diagnostics as `path:line: severity[code]: message` lines.
+fn print_config_diagnostics(config_path: &Path, diagnostics: &[ConfigDiagnostic]) {
+    for diagnostic in diagnostics {
+        let location = match diagnostic.line {
+            Some(line) => format!("{}:{}", config_path.display(), line),
+            None => config_path.display().to_string(),
+        };
+        let severity = match diagnostic.severity {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+        };
+        println!(
+            "{}: {}[{}]: {}",
+            location, severity, diagnostic.code, diagnostic.message
+        );
+        if let Some(help) = &diagnostic.help {
+            println!("  help: {}", help);
+        }
+    }
+
+    if diagnostics.is_empty() {
+        println!("✓ Configuration is valid: {}", config_path.display());
+    } else if diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == DiagnosticSeverity::Warning)
+    {
+        println!(
+            "✓ Configuration is valid with {} warning(s): {}",
+            diagnostics.len(),
+            config_path.display()
+        );
     }
 }
 
@@ -524,82 +4017,185 @@ fn resolve_config_path(
     }
 }
 
-/// Generate markdown output from AST data.
-fn generate_markdown_output(ast_data: &rustex_core::ProjectAst) -> Result<String> {
+fn generate_diff_markdown(
+    old: &rustex_core::ProjectAst,
+    new: &rustex_core::ProjectAst,
+    diff: &rustex_core::diff::AstDiff,
+) -> String {
+    use rustex_core::diff::ElementChange;
+
     let mut output = String::new();
 
-    output.push_str(&format!("# {} AST Analysis\n\n", ast_data.project.name));
-    output.push_str(&format!("**Version:** {}\n", ast_data.project.version));
-    output.push_str(&format!(
-        "**Rust Edition:** {}\n",
-        ast_data.project.rust_edition
-    ));
+    output.push_str(&format!("# {} AST Changes\n\n", new.project.name));
     output.push_str(&format!(
-        "**Extracted:** {}\n\n",
-        ast_data.extracted_at.format("%Y-%m-%d %H:%M:%S UTC")
+        "**Compared:** {} ({}) → {} ({})\n\n",
+        old.project.version,
+        old.extracted_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        new.project.version,
+        new.extracted_at.format("%Y-%m-%d %H:%M:%S UTC")
     ));
 
-    // Project metrics
-    output.push_str("## Project Metrics\n\n");
-    output.push_str(&format!(
-        "- **Total Files:** {}\n",
-        ast_data.metrics.total_files
-    ));
-    output.push_str(&format!(
-        "- **Total Lines:** {}\n",
-        ast_data.metrics.total_lines
-    ));
-    output.push_str(&format!(
-        "- **Functions:** {}\n",
-        ast_data.metrics.total_functions
-    ));
-    output.push_str(&format!(
-        "- **Structs:** {}\n",
-        ast_data.metrics.total_structs
-    ));
-    output.push_str(&format!("- **Enums:** {}\n", ast_data.metrics.total_enums));
-    output.push_str(&format!(
-        "- **Traits:** {}\n",
-        ast_data.metrics.total_traits
-    ));
-    output.push_str(&format!(
-        "- **Average Complexity:** {:.2}\n\n",
-        ast_data.metrics.complexity_average
-    ));
+    if diff.is_empty() {
+        output.push_str("*No changes*\n");
+        return output;
+    }
 
-    // File breakdown
-    if !ast_data.files.is_empty() {
-        output.push_str("## Files\n\n");
-        for file in &ast_data.files {
-            output.push_str(&format!("### {}\n\n", file.relative_path.display()));
-
-            if !file.elements.is_empty() {
-                for element in &file.elements {
-                    output.push_str(&format!(
-                        "#### {:?} `{}`\n\n",
-                        element.element_type,
-                        element.name
-                    ));
-
-                    if !element.doc_comments.is_empty() {
-                        output.push_str("**Documentation:**\n");
-                        for doc in &element.doc_comments {
-                            output.push_str(&format!("> {}\n", doc));
+    output.push_str(&format!("- **Added:** {}\n", diff.added.len()));
+    output.push_str(&format!("- **Removed:** {}\n", diff.removed.len()));
+    output.push_str(&format!("- **Changed:** {}\n\n", diff.changed.len()));
+
+    for (title, elements) in [("Added", &diff.added), ("Removed", &diff.removed)] {
+        if elements.is_empty() {
+            continue;
+        }
+        output.push_str(&format!("## {}\n\n", title));
+        for element in elements {
+            output.push_str(&format!(
+                "- {:?} `{}` ({})\n",
+                element.element_type,
+                element.qualified_name,
+                element.file_path.display()
+            ));
+            if let Some(signature) = &element.signature {
+                output.push_str(&format!("  - `{}`\n", signature));
+            }
+        }
+        output.push('\n');
+    }
+
+    if !diff.changed.is_empty() {
+        output.push_str("## Changed\n\n");
+        for element in &diff.changed {
+            output.push_str(&format!(
+                "### {:?} `{}`\n\n",
+                element.element_type, element.qualified_name
+            ));
+            for change in &element.changes {
+                match change {
+                    ElementChange::Signature { old, new } => {
+                        output.push_str("**Signature:**\n");
+                        output.push_str(&format!(
+                            "```diff\n- {}\n+ {}\n```\n",
+                            old.as_deref().unwrap_or_default(),
+                            new.as_deref().unwrap_or_default()
+                        ));
+                    }
+                    ElementChange::Documentation { old, new } => {
+                        output.push_str("**Documentation:**\n```diff\n");
+                        for doc in old {
+                            output.push_str(&format!("- {}\n", doc.trim()));
+                        }
+                        for doc in new {
+                            output.push_str(&format!("+ {}\n", doc.trim()));
                         }
-                        output.push('\n');
+                        output.push_str("```\n");
                     }
-
-                    if let Some(ref signature) = element.signature {
-                        output.push_str(&format!("```rust\n{}\n```\n\n", signature));
+                    ElementChange::Visibility { old, new } => {
+                        output.push_str(&format!("**Visibility:** {:?} → {:?}\n", old, new));
+                    }
+                    ElementChange::Variants { added, removed } => {
+                        output.push_str("**Variants:**\n");
+                        for variant in added {
+                            output.push_str(&format!("- Added `{}`\n", variant));
+                        }
+                        for variant in removed {
+                            output.push_str(&format!("- Removed `{}`\n", variant));
+                        }
+                    }
+                    ElementChange::Moved { old, new } => {
+                        output.push_str(&format!(
+                            "**Moved:** {} → {}\n",
+                            old.display(),
+                            new.display()
+                        ));
                     }
                 }
-            } else {
-                output.push_str("*No extractable elements found*\n\n");
+                output.push('\n');
             }
         }
     }
 
-    Ok(output)
+    output
+}
+
+fn generate_compat_markdown(
+    baseline: &str,
+    old: &rustex_core::ProjectAst,
+    new: &rustex_core::ProjectAst,
+    report: &rustex_core::api_compat::CompatReport,
+) -> String {
+    use rustex_core::api_compat::ChangeLevel;
+
+    let mut output = String::new();
+
+    output.push_str(&format!("# {} API Compatibility\n\n", new.project.name));
+    output.push_str(&format!(
+        "**Baseline:** {} ({})\n",
+        baseline, old.project.version
+    ));
+    output.push_str(&format!("**Current:** {}\n", new.project.version));
+    output.push_str(&format!(
+        "**Required bump:** {}\n\n",
+        report.required_bump()
+    ));
+
+    if report.changes.is_empty() {
+        output.push_str("*No public API changes*\n");
+        return output;
+    }
+
+    for (level, title) in [
+        (ChangeLevel::Major, "Breaking Changes"),
+        (ChangeLevel::Minor, "Additions"),
+        (ChangeLevel::Patch, "Other Changes"),
+    ] {
+        let changes: Vec<_> = report
+            .changes
+            .iter()
+            .filter(|change| change.level == level)
+            .collect();
+        if changes.is_empty() {
+            continue;
+        }
+        output.push_str(&format!("## {}\n\n", title));
+        for change in changes {
+            output.push_str(&format!(
+                "- {:?} `{}`: {}\n",
+                change.element_type, change.qualified_name, change.description
+            ));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Print the files an extraction skipped or only partly extracted.
+fn print_extraction_report(report: &ExtractionReport) {
+    use colored::*;
+
+    let sections = [
+        ("Skipped files", &report.failed, report.failed.len()),
+        (
+            "Partly extracted files",
+            &report.incomplete,
+            report.incomplete_file_count(),
+        ),
+    ];
+    for (title, files, count) in sections {
+        if files.is_empty() {
+            continue;
+        }
+        println!("⚠️  {}: {}", title, count.to_string().yellow());
+        for skipped in files {
+            println!(
+                "  {} ({}): {}",
+                skipped.file.display(),
+                skipped.reason,
+                skipped.message
+            );
+        }
+    }
 }
 
 /// Print extraction summary to terminal.
@@ -637,6 +4233,43 @@ fn print_extraction_summary(ast_data: &rustex_core::ProjectAst) {
         ast_data.metrics.total_traits.to_string().blue()
     );
 
+    let generated_count = ast_data
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .filter(|element| element.is_generated)
+        .count();
+    if generated_count > 0 {
+        println!(
+            "🧬 Macro-generated elements: {}",
+            generated_count.to_string().yellow()
+        );
+    }
+
+    print_extraction_report(&ast_data.extraction_report);
+
+    if let Some(workspace) = &ast_data.workspace {
+        println!(
+            "🗂️  Workspace crates: {} ({} inter-crate dependencies)",
+            workspace.crates.len().to_string().yellow(),
+            workspace.dependency_edges.len().to_string().yellow()
+        );
+    }
+
+    if !ast_data.dependencies_ast.is_empty() {
+        let dependency_elements: usize = ast_data
+            .dependencies_ast
+            .iter()
+            .flat_map(|dep| &dep.files)
+            .map(|file| file.elements.len())
+            .sum();
+        println!(
+            "📦 Dependencies parsed: {} ({} public elements)",
+            ast_data.dependencies_ast.len().to_string().yellow(),
+            dependency_elements.to_string().yellow()
+        );
+    }
+
     if ast_data.metrics.complexity_average > 0.0 {
         let complexity_color = if ast_data.metrics.complexity_average > 10.0 {
             "red"
@@ -0,0 +1,316 @@
+//! Model Context Protocol server for `rustex mcp`.
+//!
+//! Speaks JSON-RPC 2.0 over stdio, one message per line, and exposes tools
+//! that let agents query the structure of an extracted project.
+
+use anyhow::Result;
+use rustex_core::{ElementQuery, ElementRef, ElementType, MetricsReport, ProjectIndex};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Supported protocol versions, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Results returned by `search_symbols` without a `limit`.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Largest `depth` accepted by `get_call_graph`.
+const MAX_CALL_GRAPH_DEPTH: usize = 5;
+
+/// Answer requests on stdin until it is closed.
+pub(crate) async fn serve(index: ProjectIndex) -> Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = handle_message(&index, &line) else {
+            continue;
+        };
+        let mut response = serde_json::to_vec(&response)?;
+        response.push(b'\n');
+        stdout.write_all(&response).await?;
+        stdout.flush().await?;
+    }
+    Ok(())
+}
+
+/// The response to a message, or `None` for notifications.
+fn handle_message(index: &ProjectIndex, line: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    // Notifications (and responses to our own requests, which we never send)
+    // have no ID and get no reply.
+    let id = message.get("id")?.clone();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        return Some(error_response(id, INVALID_REQUEST, "Missing method"));
+    };
+    let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+
+    let result = match method {
+        "initialize" => Ok(initialize(index, &params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(index, &params),
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+fn initialize(index: &ProjectIndex, params: &Value) -> Value {
+    // Use the client's version when supported, otherwise propose ours.
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|version| PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "rustex", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": format!(
+            "Query the structure of the Rust project `{}`: find symbols with \
+             search_symbols, then read them with get_element_source or explore \
+             their references with get_call_graph.",
+            index.ast().project.name
+        ),
+    })
+}
+
+fn tool_definitions() -> Value {
+    let element_arguments = json!({
+        "id": { "type": "string", "description": "Element ID, as returned by search_symbols" },
+        "name": {
+            "type": "string",
+            "description": "Element name or qualified name, used when no ID is given",
+        },
+    });
+    json!([
+        {
+            "name": "search_symbols",
+            "description": "Find functions, types, traits and other items by name. Exact \
+                            and prefix matches come first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Text in the name or qualified name, ignoring case",
+                    },
+                    "type": {
+                        "type": "string",
+                        "description": "Element type: function, struct, enum, trait, impl, \
+                                        module, constant, static, type_alias, macro, union",
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Only search files whose path starts with this",
+                    },
+                    "limit": { "type": "integer", "minimum": 1 },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "get_element_source",
+            "description": "Get the source code of an element, with its file and lines.",
+            "inputSchema": { "type": "object", "properties": element_arguments },
+        },
+        {
+            "name": "get_call_graph",
+            "description": "Get the elements an element calls or uses and the elements \
+                            calling or using it, up to `depth` steps away.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": element_arguments["id"],
+                    "name": element_arguments["name"],
+                    "depth": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": MAX_CALL_GRAPH_DEPTH,
+                        "default": 1,
+                    },
+                },
+            },
+        },
+        {
+            "name": "get_project_metrics",
+            "description": "Get lines of code, complexity, documentation coverage, unsafe \
+                            usage and the largest and most complex elements of the project.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "top": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "default": 10,
+                        "description": "Number of largest and most complex elements to list",
+                    },
+                },
+            },
+        },
+    ])
+}
+
+/// Run a tool. Failures of the tool itself are reported in the result, so
+/// the agent can see them; only unknown tools are protocol errors.
+fn call_tool(index: &ProjectIndex, params: &Value) -> Result<Value, (i64, String)> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let arguments = params
+        .get("arguments")
+        .cloned()
+        .unwrap_or_else(|| json!({}));
+    let output = match name {
+        "search_symbols" => search_symbols(index, &arguments),
+        "get_element_source" => get_element_source(index, &arguments),
+        "get_call_graph" => get_call_graph(index, &arguments),
+        "get_project_metrics" => get_project_metrics(index, &arguments),
+        _ => return Err((INVALID_PARAMS, format!("Unknown tool `{}`", name))),
+    };
+    let (text, is_error) = match output {
+        Ok(text) => (text, false),
+        Err(message) => (message, true),
+    };
+    Ok(json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    }))
+}
+
+type ToolResult = std::result::Result<String, String>;
+
+fn search_symbols(index: &ProjectIndex, arguments: &Value) -> ToolResult {
+    let query = string_argument(arguments, "query").ok_or("Missing `query`")?;
+    let element_type = string_argument(arguments, "type");
+    if let Some(filter) = &element_type {
+        if !ElementType::is_valid_filter(filter) {
+            return Err(format!("Unknown element type `{}`", filter));
+        }
+    }
+    let query = ElementQuery {
+        name: Some(query),
+        element_type,
+        path: string_argument(arguments, "path"),
+        limit: Some(usize_argument(arguments, "limit").unwrap_or(DEFAULT_SEARCH_LIMIT)),
+    };
+    let results: Vec<Value> = index.search(&query).into_iter().map(summary).collect();
+    to_text(&results)
+}
+
+fn get_element_source(index: &ProjectIndex, arguments: &Value) -> ToolResult {
+    let found = find_element(index, arguments)?;
+    let source = index
+        .element_source(&found.element.id)
+        .map_err(|e| format!("Failed to read {}: {}", found.file.path.display(), e))?
+        .unwrap_or_default();
+    let location = &found.element.location;
+    Ok(format!(
+        "// {} ({}:{}-{})\n{}",
+        found.element.hierarchy.qualified_name,
+        found.file.relative_path.display(),
+        location.line_start,
+        location.line_end,
+        source
+    ))
+}
+
+fn get_call_graph(index: &ProjectIndex, arguments: &Value) -> ToolResult {
+    let found = find_element(index, arguments)?;
+    let depth = usize_argument(arguments, "depth").unwrap_or(1);
+    if !(1..=MAX_CALL_GRAPH_DEPTH).contains(&depth) {
+        return Err(format!(
+            "`depth` must be between 1 and {}",
+            MAX_CALL_GRAPH_DEPTH
+        ));
+    }
+    let subgraph = index.reference_subgraph(&found.element.id, depth);
+    let elements: Vec<Value> = subgraph
+        .element_ids
+        .iter()
+        .filter_map(|id| index.element(id))
+        .map(summary)
+        .collect();
+    let edges: Vec<Value> = subgraph
+        .edges
+        .iter()
+        .map(|(from, to)| json!({ "from": from, "to": to }))
+        .collect();
+    to_text(&json!({ "root": found.element.id, "elements": elements, "edges": edges }))
+}
+
+fn get_project_metrics(index: &ProjectIndex, arguments: &Value) -> ToolResult {
+    let top = usize_argument(arguments, "top").unwrap_or(10);
+    to_text(&MetricsReport::from_project(index.ast(), top))
+}
+
+/// The element named by the `id` or `name` argument.
+fn find_element<'a>(index: &'a ProjectIndex, arguments: &Value) -> Result<ElementRef<'a>, String> {
+    if let Some(id) = string_argument(arguments, "id") {
+        return index
+            .element(&id)
+            .ok_or_else(|| format!("No element with ID `{}`", id));
+    }
+    let name = string_argument(arguments, "name").ok_or("Missing `id` or `name`")?;
+    // A qualified name identifies the element, a plain name the best match.
+    let query = ElementQuery {
+        name: Some(name.rsplit("::").next().unwrap_or(&name).to_string()),
+        ..ElementQuery::default()
+    };
+    let matches = index.search(&query);
+    matches
+        .iter()
+        .find(|found| found.element.hierarchy.qualified_name.ends_with(&name))
+        .or(matches.first())
+        .copied()
+        .ok_or_else(|| format!("No element named `{}`", name))
+}
+
+/// A short description of an element, for listings.
+fn summary(found: ElementRef<'_>) -> Value {
+    let element = found.element;
+    json!({
+        "id": element.id,
+        "name": element.name,
+        "qualified_name": element.hierarchy.qualified_name,
+        "type": element.element_type.filter_name(),
+        "file": found.file.relative_path,
+        "line_start": element.location.line_start,
+        "line_end": element.location.line_end,
+        "signature": element.signature,
+        "doc": element.doc_comments.first().map(|line| line.trim()),
+    })
+}
+
+fn string_argument(arguments: &Value, key: &str) -> Option<String> {
+    arguments.get(key)?.as_str().map(str::to_string)
+}
+
+fn usize_argument(arguments: &Value, key: &str) -> Option<usize> {
+    arguments.get(key)?.as_u64().map(|value| value as usize)
+}
+
+fn to_text(value: &impl serde::Serialize) -> ToolResult {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
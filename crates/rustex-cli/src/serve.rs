@@ -0,0 +1,212 @@
+//! HTTP API over an extracted project, for `rustex serve`.
+//!
+//! All responses are JSON. Errors are returned as `{"error": "..."}` with an
+//! error status.
+
+use anyhow::Result;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use rustex_core::{ElementQuery, ElementType, ProjectIndex};
+use rustex_formats::{KeywordCorpus, RagFormatter};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::info;
+
+/// Maximum number of search results or chunks returned without a `limit`.
+const DEFAULT_LIMIT: usize = 50;
+
+struct ServeState {
+    index: ProjectIndex,
+    rag_formatter: RagFormatter,
+    /// Docs of the project, to weigh the retrieval keywords of chunks
+    keyword_corpus: KeywordCorpus,
+    #[cfg(feature = "graphql-server")]
+    graphql_schema: async_graphql::dynamic::Schema,
+}
+
+/// Serve `index` on `address` until the process is stopped, with the chunks
+/// of `rag_formatter`.
+pub(crate) async fn serve(
+    index: ProjectIndex,
+    rag_formatter: RagFormatter,
+    address: SocketAddr,
+) -> Result<()> {
+    let project_name = index.ast().project.name.clone();
+    let keyword_corpus = KeywordCorpus::from_files(&index.ast().files);
+    #[cfg(feature = "graphql-server")]
+    let graphql_schema = rustex_formats::graphql_server::graphql_schema(index.ast())?;
+    let state = Arc::new(ServeState {
+        index,
+        rag_formatter,
+        keyword_corpus,
+        #[cfg(feature = "graphql-server")]
+        graphql_schema,
+    });
+    let app = Router::new()
+        .route("/project", get(project))
+        .route("/files", get(files))
+        .route("/files/{*path}", get(file))
+        .route("/elements/{id}", get(element))
+        .route("/search", get(search))
+        .route("/chunks", get(chunks));
+    #[cfg(feature = "graphql-server")]
+    let app = app.route("/graphql", axum::routing::post(graphql));
+    let app = app.with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    info!(
+        "Serving {} on http://{}",
+        project_name,
+        listener.local_addr()?
+    );
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// An error response.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult = std::result::Result<Response, ApiError>;
+
+/// Query parameters of `/search` and `/chunks`.
+#[derive(Deserialize)]
+struct SearchParams {
+    /// Text in the element name or qualified name
+    name: Option<String>,
+    /// Element type, such as `function`
+    #[serde(rename = "type")]
+    element_type: Option<String>,
+    /// Prefix of the file path
+    path: Option<String>,
+    limit: Option<usize>,
+}
+
+impl SearchParams {
+    fn query(self) -> std::result::Result<ElementQuery, ApiError> {
+        if let Some(filter) = &self.element_type {
+            if !ElementType::is_valid_filter(filter) {
+                return Err(ApiError(
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown element type `{}`", filter),
+                ));
+            }
+        }
+        Ok(ElementQuery {
+            name: self.name,
+            element_type: self.element_type,
+            path: self.path,
+            limit: Some(self.limit.unwrap_or(DEFAULT_LIMIT)),
+        })
+    }
+}
+
+/// A file in the `/files` listing.
+#[derive(Serialize)]
+struct FileSummary<'a> {
+    path: &'a std::path::Path,
+    elements: usize,
+    lines_of_code: usize,
+}
+
+/// `GET /project`: project information, metrics and dependencies.
+async fn project(State(state): State<Arc<ServeState>>) -> Response {
+    let ast = state.index.ast();
+    Json(serde_json::json!({
+        "project": ast.project,
+        "metrics": ast.metrics,
+        "dependencies": ast.dependencies,
+        "extracted_at": ast.extracted_at,
+    }))
+    .into_response()
+}
+
+/// `GET /files`: the extracted files.
+async fn files(State(state): State<Arc<ServeState>>) -> Response {
+    let files: Vec<FileSummary> = state
+        .index
+        .ast()
+        .files
+        .iter()
+        .map(|file| FileSummary {
+            path: &file.relative_path,
+            elements: file.elements.len(),
+            lines_of_code: file.file_metrics.lines_of_code,
+        })
+        .collect();
+    Json(files).into_response()
+}
+
+/// `GET /files/{path}`: a file with its elements.
+async fn file(State(state): State<Arc<ServeState>>, Path(path): Path<String>) -> ApiResult {
+    match state.index.file(&path) {
+        Some(file) => Ok(Json(file).into_response()),
+        None => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("No file `{}`", path),
+        )),
+    }
+}
+
+/// `GET /elements/{id}`: an element and the path of its file.
+async fn element(State(state): State<Arc<ServeState>>, Path(id): Path<String>) -> ApiResult {
+    match state.index.element(&id) {
+        Some(element) => Ok(Json(element).into_response()),
+        None => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("No element `{}`", id),
+        )),
+    }
+}
+
+/// `GET /search?name=&type=&path=&limit=`: elements matching the query.
+async fn search(
+    State(state): State<Arc<ServeState>>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult {
+    let query = params.query()?;
+    Ok(Json(state.index.search(&query)).into_response())
+}
+
+/// `GET /chunks?name=&type=&path=&limit=`: RAG chunks of the elements
+/// matching the query.
+async fn chunks(
+    State(state): State<Arc<ServeState>>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult {
+    let mut query = params.query()?;
+    // Elements excluded from chunking are skipped, so limit the chunks instead.
+    let limit = query.limit.take().unwrap_or(DEFAULT_LIMIT);
+    let mut chunks = Vec::new();
+    for found in state.index.search(&query) {
+        if chunks.len() == limit {
+            break;
+        }
+        let chunk = state
+            .rag_formatter
+            .chunks_for_element(found.element, found.file, &state.keyword_corpus)
+            .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        chunks.extend(chunk);
+    }
+    // Elements split into parts have a chunk for each.
+    chunks.truncate(limit);
+    Ok(Json(chunks).into_response())
+}
+
+/// `POST /graphql`: a GraphQL query against the schema of `--format graphql`.
+#[cfg(feature = "graphql-server")]
+async fn graphql(
+    State(state): State<Arc<ServeState>>,
+    Json(request): Json<async_graphql::Request>,
+) -> Response {
+    Json(state.graphql_schema.execute(request).await).into_response()
+}
@@ -0,0 +1,373 @@
+//! Tests of the `rustex` command line.
+
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A project with a single library file in `root`.
+fn write_project(root: &Path) {
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"sample\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/lib.rs"),
+        "/// Adds one.\npub fn add_one(x: u32) -> u32 {\n    x + 1\n}\n",
+    )
+    .unwrap();
+}
+
+/// The `rustex` command run in `root`, without installed plugins.
+fn rustex(root: &Path) -> Command {
+    let mut command = Command::cargo_bin("rustex").unwrap();
+    command
+        .current_dir(root)
+        .env("XDG_CONFIG_HOME", root.join("config"))
+        .env("HOME", root);
+    command
+}
+
+#[test]
+fn test_output_dir_includes_plugin_results() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+
+    rustex(root)
+        .args(["extract", "--plugins", "metrics-collector"])
+        .args(["--format", "json", "--format", "markdown"])
+        .args(["--output-dir", "out"])
+        .assert()
+        .success();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(root.join("out/ast.json")).unwrap()).unwrap();
+    assert!(json["plugin_results"]["metrics-collector"].is_object());
+    let markdown = fs::read_to_string(root.join("out/ast.md")).unwrap();
+    assert!(markdown.contains("metrics-collector"));
+}
+
+#[test]
+fn test_streamed_jsonl_rejects_plugins() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+
+    let output = rustex(root)
+        .args(["extract", "--plugins", "metrics-collector"])
+        .args(["--format", "jsonl"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("streamed file by file"), "{}", stderr);
+
+    rustex(root)
+        .args(["extract", "--plugins", "metrics-collector"])
+        .args(["--format", "jsonl"])
+        .args(["--output-dir", "out"])
+        .assert()
+        .success();
+    assert!(root.join("out/ast.jsonl").is_file());
+}
+
+#[test]
+fn test_split_output_needs_output_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+
+    let output = rustex(root)
+        .args(["extract", "--split-output", "per-file", "-o", "out.json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("give --output-dir"), "{}", stderr);
+    assert!(!root.join("out.json").exists());
+}
+
+#[test]
+fn test_mdbook_needs_output_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+
+    let output = rustex(root)
+        .args(["extract", "--mdbook", "-o", "book.md"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("give --output-dir"), "{}", stderr);
+    assert!(!root.join("book.md").exists());
+}
+
+#[test]
+fn test_convert_uses_project_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+    rustex(root)
+        .args(["extract", "--format", "json", "--output", "ast.json"])
+        .assert()
+        .success();
+
+    let convert = |root: &Path| {
+        let output = rustex(root)
+            .args(["convert", "ast.json", "--format", "markdown"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        // The title, after the log lines.
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let title = stdout.lines().find(|line| line.starts_with('#'));
+        title.unwrap().to_string()
+    };
+    assert!(convert(root).starts_with("# "));
+    fs::write(root.join("rustex.toml"), "[markdown]\nheading_level = 3\n").unwrap();
+    assert!(convert(root).starts_with("### "));
+}
+
+#[test]
+fn test_diff_json_ends_with_newline() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+    let extract = |output: &str| {
+        rustex(root)
+            .args(["extract", "--format", "json", "--output", output])
+            .assert()
+            .success();
+    };
+    extract("old.json");
+    fs::write(
+        root.join("src/lib.rs"),
+        "/// Adds one.\npub fn add_one(x: u32) -> u32 {\n    x + 1\n}\n\npub fn two() -> u32 {\n    2\n}\n",
+    )
+    .unwrap();
+    extract("new.json");
+
+    for pretty in [false, true] {
+        let mut diff = rustex(root);
+        diff.args(["diff", "old.json", "new.json", "--output", "diff.json"]);
+        if pretty {
+            diff.arg("--pretty");
+        }
+        diff.assert().success();
+        let content = fs::read_to_string(root.join("diff.json")).unwrap();
+        assert!(content.ends_with("}\n"), "{:?}", content);
+        let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(json["added"].as_array().unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn test_convert_renders_templates() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+    rustex(root)
+        .args(["extract", "--format", "json", "--output", "ast.json"])
+        .assert()
+        .success();
+    fs::write(
+        root.join("report.tera"),
+        "{% for element in elements() %}item {{ element.name }}\n{% endfor %}",
+    )
+    .unwrap();
+
+    let output = rustex(root)
+        .args(["convert", "ast.json", "--format", "template"])
+        .args(["--template", "report.tera", "--output", "report.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(root.join("report.txt")).unwrap(),
+        "item add_one\n"
+    );
+}
+
+#[test]
+fn test_convert_writes_datasets() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+    rustex(root)
+        .args(["extract", "--format", "json", "--output", "ast.json"])
+        .assert()
+        .success();
+
+    rustex(root)
+        .args(["convert", "ast.json", "--format", "hf-dataset"])
+        .args(["--output-dir", "dataset"])
+        .assert()
+        .success();
+    assert!(root.join("dataset/README.md").is_file());
+
+    let output = rustex(root)
+        .args(["convert", "ast.json", "--format", "json"])
+        .args(["--output-dir", "dataset"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_output_dir_writes_parquet_tables() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+
+    let output = rustex(root)
+        .args(["extract", "--format", "parquet", "--output-dir", "out"])
+        .output()
+        .unwrap();
+    if cfg!(feature = "parquet") {
+        assert!(output.status.success());
+        for table in ["elements", "files", "metrics"] {
+            let bytes = fs::read(root.join(format!("out/{}.parquet", table))).unwrap();
+            assert!(bytes.starts_with(b"PAR1"), "{}", table);
+        }
+    } else {
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("needs the parquet feature"), "{}", stderr);
+    }
+}
+
+#[test]
+fn test_rag_chunking_options() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+
+    let chunk_strategy = |args: &[&str]| {
+        rustex(root)
+            .args(["extract", "--format", "rag", "--output", "rag.json"])
+            .args(args)
+            .assert()
+            .success();
+        let rag: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.join("rag.json")).unwrap()).unwrap();
+        rag["metadata"]["chunk_strategy"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(chunk_strategy(&[]), "per_element");
+    fs::write(
+        root.join("rustex.toml"),
+        "[rag]\nchunk_strategy = \"semantic\"\n",
+    )
+    .unwrap();
+    assert_eq!(chunk_strategy(&[]), "semantic");
+    assert_eq!(
+        chunk_strategy(&[
+            "--chunk-strategy",
+            "token-budget",
+            "--context-radius-lines",
+            "2"
+        ]),
+        "token_budget"
+    );
+}
+
+#[test]
+fn test_rag_manifest_matches_chunks() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write_project(root);
+    fs::write(
+        root.join("src/lib.rs"),
+        "pub mod util;\n\n/// Adds one.\npub fn add_one(x: u32) -> u32 {\n    x + 1\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/util.rs"),
+        "/// Doubles a number.\npub fn double(x: u32) -> u32 {\n    x * 2\n}\n\n\
+         /// A point.\npub struct Point {\n    pub x: u32,\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("rustex.toml"),
+        "[rag]\nmanifest = \"manifest.json\"\n",
+    )
+    .unwrap();
+    let manifest = || -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(root.join("manifest.json")).unwrap()).unwrap()
+    };
+    let ids = |chunks: &[serde_json::Value]| -> Vec<String> {
+        let mut ids: Vec<String> = chunks
+            .iter()
+            .map(|chunk| chunk["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+    let check = |manifest: &serde_json::Value, chunks: &[serde_json::Value]| {
+        let recorded: Vec<String> = manifest["chunks"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        assert_eq!(recorded, ids(chunks));
+        assert_eq!(manifest["chunk_count"], chunks.len());
+        assert_eq!(manifest["project_name"], "sample");
+        assert!(!manifest["config_hash"].as_str().unwrap().is_empty());
+        let tokens: u64 = chunks
+            .iter()
+            .map(|chunk| chunk["metadata"]["token_count"].as_u64().unwrap())
+            .sum();
+        assert_eq!(manifest["summary"]["total_tokens"], tokens);
+        let by_type = manifest["summary"]["chunks_by_element_type"]
+            .as_object()
+            .unwrap();
+        let counted: u64 = by_type.values().map(|count| count.as_u64().unwrap()).sum();
+        assert_eq!(counted, chunks.len() as u64);
+        assert_eq!(by_type["Function"], 2);
+        assert_eq!(by_type["Struct"], 1);
+        let mut files: Vec<&str> = chunks
+            .iter()
+            .map(|chunk| chunk["metadata"]["file_path"].as_str().unwrap())
+            .collect();
+        files.sort();
+        files.dedup();
+        assert_eq!(files.len(), 2, "{:?}", files);
+    };
+
+    rustex(root)
+        .args(["extract", "--format", "rag", "--output", "rag.json"])
+        .assert()
+        .success();
+    let rag: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(root.join("rag.json")).unwrap()).unwrap();
+    let chunks = rag["chunks"].as_array().unwrap();
+    let manifest_of_rag = manifest();
+    check(&manifest_of_rag, chunks);
+    assert!(manifest_of_rag["offsets"].as_object().unwrap().is_empty());
+
+    rustex(root)
+        .args(["extract", "--format", "rag-jsonl", "--output", "rag.jsonl"])
+        .assert()
+        .success();
+    let jsonl = fs::read_to_string(root.join("rag.jsonl")).unwrap();
+    let mut chunks = Vec::new();
+    let mut offsets = serde_json::Map::new();
+    let mut offset = 0;
+    for line in jsonl.split_inclusive('\n') {
+        let chunk: serde_json::Value = serde_json::from_str(line).unwrap();
+        offsets.insert(chunk["id"].as_str().unwrap().to_string(), offset.into());
+        offset += line.len();
+        chunks.push(chunk);
+    }
+    let manifest = manifest();
+    check(&manifest, &chunks);
+    assert_eq!(manifest["chunks"], manifest_of_rag["chunks"]);
+    assert_eq!(manifest["offsets"], serde_json::Value::Object(offsets));
+}
@@ -7,6 +7,8 @@
 //! - Output formatting
 //! - Memory usage patterns
 
+#![allow(clippy::field_reassign_with_default)]
+
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use rustex_core::{
     complexity::ComplexityCalculator, visitors::CodeElementVisitor, AstExtractor, ExtractorConfig,
@@ -934,6 +936,12 @@ fn bench_file_filtering(c: &mut Criterion) {
             FilterConfig {
                 include: vec!["**/*.rs".to_string()],
                 exclude: vec![],
+                visibility: vec![],
+                exclude_build_scripts: false,
+                element_types: vec![],
+                name_patterns: vec![],
+                exclude_names: vec![],
+                overrides: Default::default(),
             },
         ),
         (
@@ -941,6 +949,12 @@ fn bench_file_filtering(c: &mut Criterion) {
             FilterConfig {
                 include: vec!["**/*.rs".to_string()],
                 exclude: vec!["**/file_5*.rs".to_string()],
+                visibility: vec![],
+                exclude_build_scripts: false,
+                element_types: vec![],
+                name_patterns: vec![],
+                exclude_names: vec![],
+                overrides: Default::default(),
             },
         ),
         (
@@ -948,6 +962,12 @@ fn bench_file_filtering(c: &mut Criterion) {
             FilterConfig {
                 include: vec!["src/**/*.rs".to_string(), "tests/**/*.rs".to_string()],
                 exclude: vec!["target/**".to_string(), "**/test_*.rs".to_string()],
+                visibility: vec![],
+                exclude_build_scripts: false,
+                element_types: vec![],
+                name_patterns: vec![],
+                exclude_names: vec![],
+                overrides: Default::default(),
             },
         ),
     ];
@@ -0,0 +1,380 @@
+//! Semver classification of public API changes.
+//!
+//! Compares two extractions of a crate with [`diff_projects`] and classifies
+//! each change to a `pub` item by the version bump it requires. Visibility is
+//! judged per item: a `pub` item inside a private module counts as public,
+//! and re-exports are not followed.
+//!
+//! [`api_churn`] gives each `pub` item an [`ApiStatus`] relative to the
+//! baseline instead, which the `api-stability` plugin records on elements.
+
+use crate::ast_data::{CodeElement, ElementType, ProjectAst, Visibility};
+use crate::diff::{diff_projects, ElementChange};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// Key of the element `metadata` entry holding the [`ApiStatus`] of a public
+/// element, set by the `api-stability` plugin.
+pub const API_STATUS_METADATA_KEY: &str = "api_status";
+
+/// Version bump required by a change, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeLevel {
+    /// No public API change
+    Patch,
+    /// Backwards-compatible addition
+    Minor,
+    /// Breaking change
+    Major,
+}
+
+impl fmt::Display for ChangeLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeLevel::Patch => write!(f, "patch"),
+            ChangeLevel::Minor => write!(f, "minor"),
+            ChangeLevel::Major => write!(f, "major"),
+        }
+    }
+}
+
+/// A classified change to the public API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiChange {
+    /// Version bump the change requires
+    pub level: ChangeLevel,
+    /// Fully qualified name of the changed item
+    pub qualified_name: String,
+    /// Type of the changed item
+    pub element_type: ElementType,
+    /// Human-readable description of the change
+    pub description: String,
+}
+
+/// Result of comparing the public API of two extractions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatReport {
+    /// Changes to public items, most severe first
+    pub changes: Vec<ApiChange>,
+}
+
+impl CompatReport {
+    /// The smallest version bump that covers every change.
+    pub fn required_bump(&self) -> ChangeLevel {
+        self.changes
+            .iter()
+            .map(|change| change.level)
+            .max()
+            .unwrap_or(ChangeLevel::Patch)
+    }
+
+    /// Whether going from `old_version` to `new_version` is a large enough bump.
+    ///
+    /// Returns `None` if either version is not a valid `major.minor.patch`.
+    pub fn is_satisfied_by(&self, old_version: &str, new_version: &str) -> Option<bool> {
+        let bump = version_bump(old_version, new_version)?;
+        Some(bump >= self.required_bump())
+    }
+}
+
+/// Classify the public API changes between `old` and `new`.
+///
+/// Removing a public item, changing its signature, making it non-public and
+/// adding or removing variants of an exhaustive enum are major changes.
+/// Adding public items and adding variants to a `#[non_exhaustive]` enum are
+/// minor. Documentation changes and moves between files are patch-level.
+pub fn check_compat(old: &ProjectAst, new: &ProjectAst) -> CompatReport {
+    let diff = diff_projects(old, new);
+    let new_elements: HashMap<&str, &CodeElement> = new
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .map(|element| (element.id.as_str(), element))
+        .collect();
+
+    let mut changes = Vec::new();
+    let mut push = |level, qualified_name: &str, element_type: &ElementType, description| {
+        changes.push(ApiChange {
+            level,
+            qualified_name: qualified_name.to_string(),
+            element_type: element_type.clone(),
+            description,
+        });
+    };
+
+    for element in diff
+        .removed
+        .iter()
+        .filter(|e| e.visibility == Visibility::Public)
+    {
+        push(
+            ChangeLevel::Major,
+            &element.qualified_name,
+            &element.element_type,
+            "public item removed".to_string(),
+        );
+    }
+    for element in diff
+        .added
+        .iter()
+        .filter(|e| e.visibility == Visibility::Public)
+    {
+        push(
+            ChangeLevel::Minor,
+            &element.qualified_name,
+            &element.element_type,
+            "public item added".to_string(),
+        );
+    }
+
+    for element_diff in &diff.changed {
+        let Some(element) = new_elements.get(element_diff.new_id.as_str()) else {
+            continue;
+        };
+        let was_public = element_diff.changes.iter().fold(
+            element.visibility == Visibility::Public,
+            |public, change| match change {
+                ElementChange::Visibility { old, .. } => *old == Visibility::Public,
+                _ => public,
+            },
+        );
+        let is_public = element.visibility == Visibility::Public;
+        let mut push_change = |level, description| {
+            push(
+                level,
+                &element_diff.qualified_name,
+                &element_diff.element_type,
+                description,
+            )
+        };
+
+        match (was_public, is_public) {
+            (false, false) => continue,
+            (true, false) => {
+                push_change(ChangeLevel::Major, "no longer public".to_string());
+                continue;
+            }
+            (false, true) => {
+                push_change(ChangeLevel::Minor, "made public".to_string());
+                continue;
+            }
+            (true, true) => {}
+        }
+
+        for change in &element_diff.changes {
+            match change {
+                ElementChange::Signature { old, new } => push_change(
+                    ChangeLevel::Major,
+                    format!(
+                        "signature changed from `{}` to `{}`",
+                        old.as_deref().unwrap_or_default(),
+                        new.as_deref().unwrap_or_default()
+                    ),
+                ),
+                ElementChange::Variants { added, removed } => {
+                    if !removed.is_empty() {
+                        push_change(
+                            ChangeLevel::Major,
+                            format!("variants removed: {}", removed.join(", ")),
+                        );
+                    }
+                    if !added.is_empty() {
+                        // Matches on exhaustive enums stop compiling when variants are added.
+                        let level = if element.is_non_exhaustive() {
+                            ChangeLevel::Minor
+                        } else {
+                            ChangeLevel::Major
+                        };
+                        push_change(level, format!("variants added: {}", added.join(", ")));
+                    }
+                }
+                ElementChange::Documentation { .. } => {
+                    push_change(ChangeLevel::Patch, "documentation changed".to_string())
+                }
+                ElementChange::Moved { old, new } => push_change(
+                    ChangeLevel::Patch,
+                    format!("moved from {} to {}", old.display(), new.display()),
+                ),
+                ElementChange::Visibility { .. } => {}
+            }
+        }
+    }
+
+    // Stable sort keeps diff order within a level.
+    changes.sort_by_key(|change| std::cmp::Reverse(change.level));
+    CompatReport { changes }
+}
+
+/// Status of a public item relative to a baseline extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiStatus {
+    /// Not public in the baseline
+    New,
+    /// Public in the baseline with the same signature
+    Unchanged,
+    /// Public in the baseline with another signature
+    SignatureChanged,
+    /// Marked `#[deprecated]`, so due for removal
+    RemovedSoon,
+}
+
+impl ApiStatus {
+    /// All statuses, from the newest items to those on their way out.
+    pub const ALL: [ApiStatus; 4] = [
+        ApiStatus::New,
+        ApiStatus::Unchanged,
+        ApiStatus::SignatureChanged,
+        ApiStatus::RemovedSoon,
+    ];
+
+    /// The status the `api-stability` plugin recorded on `element`, if any.
+    pub fn of(element: &CodeElement) -> Option<Self> {
+        let status = element.metadata.get(API_STATUS_METADATA_KEY)?;
+        serde_json::from_value(status.clone()).ok()
+    }
+}
+
+impl fmt::Display for ApiStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiStatus::New => write!(f, "new"),
+            ApiStatus::Unchanged => write!(f, "unchanged"),
+            ApiStatus::SignatureChanged => write!(f, "signature_changed"),
+            ApiStatus::RemovedSoon => write!(f, "removed_soon"),
+        }
+    }
+}
+
+/// The public items of an extraction by status relative to a baseline.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiChurn {
+    /// Status of each public item, by element ID in the new extraction
+    pub statuses: BTreeMap<String, ApiStatus>,
+    /// Qualified names of the public items of the baseline that were removed
+    /// or are no longer public
+    pub removed: Vec<String>,
+    /// Number of public items in the baseline
+    pub baseline_items: usize,
+}
+
+impl ApiChurn {
+    /// Number of public items with `status`.
+    pub fn count(&self, status: ApiStatus) -> usize {
+        self.statuses.values().filter(|s| **s == status).count()
+    }
+
+    /// Items added, changed or removed since the baseline, as a share of the
+    /// baseline's public items; 0 when the baseline has none.
+    pub fn churn_rate(&self) -> f64 {
+        if self.baseline_items == 0 {
+            return 0.0;
+        }
+        let churned = self.count(ApiStatus::New)
+            + self.count(ApiStatus::SignatureChanged)
+            + self.removed.len();
+        churned as f64 / self.baseline_items as f64
+    }
+}
+
+/// The status of each public item of `new` relative to `old`.
+///
+/// Deprecated items are removed soon whatever else changed. Items made public
+/// count as new, and items no longer public as removed.
+pub fn api_churn(old: &ProjectAst, new: &ProjectAst) -> ApiChurn {
+    let diff = diff_projects(old, new);
+    let added: HashSet<&str> = diff.added.iter().map(|e| e.id.as_str()).collect();
+    let changed: HashMap<&str, &[ElementChange]> = diff
+        .changed
+        .iter()
+        .map(|element_diff| {
+            (
+                element_diff.new_id.as_str(),
+                element_diff.changes.as_slice(),
+            )
+        })
+        .collect();
+
+    let mut churn = ApiChurn {
+        baseline_items: old
+            .files
+            .iter()
+            .flat_map(|file| &file.elements)
+            .filter(|e| e.visibility == Visibility::Public)
+            .count(),
+        ..ApiChurn::default()
+    };
+    for element in new.files.iter().flat_map(|file| &file.elements) {
+        if element.visibility != Visibility::Public {
+            continue;
+        }
+        let changes = changed
+            .get(element.id.as_str())
+            .copied()
+            .unwrap_or_default();
+        let made_public = changes.iter().any(|change| {
+            matches!(change, ElementChange::Visibility { old, .. } if *old != Visibility::Public)
+        });
+        let status = if element.deprecation.is_some() {
+            ApiStatus::RemovedSoon
+        } else if added.contains(element.id.as_str()) || made_public {
+            ApiStatus::New
+        } else if changes
+            .iter()
+            .any(|change| matches!(change, ElementChange::Signature { .. }))
+        {
+            ApiStatus::SignatureChanged
+        } else {
+            ApiStatus::Unchanged
+        };
+        churn.statuses.insert(element.id.clone(), status);
+    }
+
+    let removed = diff
+        .removed
+        .iter()
+        .filter(|e| e.visibility == Visibility::Public)
+        .map(|e| e.qualified_name.clone());
+    let no_longer_public = diff.changed.iter().filter(|element_diff| {
+        element_diff.changes.iter().any(|change| {
+            matches!(change, ElementChange::Visibility { old, new }
+                if *old == Visibility::Public && *new != Visibility::Public)
+        })
+    });
+    churn.removed = removed
+        .chain(no_longer_public.map(|element_diff| element_diff.qualified_name.clone()))
+        .collect();
+    churn
+}
+
+/// The kind of bump between two `major.minor.patch` versions.
+///
+/// Follows Cargo's compatibility rules, where the leftmost non-zero component
+/// is the major one: `0.3.1` to `0.4.0` is a major bump and `0.3.1` to
+/// `0.3.2` a minor one. Pre-release and build suffixes are ignored. Returns
+/// `None` if either version doesn't parse.
+pub fn version_bump(old: &str, new: &str) -> Option<ChangeLevel> {
+    let (old, new) = (parse_version(old)?, parse_version(new)?);
+    let breaking = |(major, minor, patch): (u64, u64, u64)| match (major, minor) {
+        (0, 0) => (0, 0, patch),
+        (0, _) => (0, minor, 0),
+        _ => (major, 0, 0),
+    };
+
+    Some(if breaking(old) != breaking(new) {
+        ChangeLevel::Major
+    } else if old.1 != new.1 || (old.0 == 0 && old.2 != new.2) {
+        ChangeLevel::Minor
+    } else {
+        ChangeLevel::Patch
+    })
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim().split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(version)
+}
@@ -1,12 +1,14 @@
 //! Data structures for representing extracted AST information.
 
+use crate::errors::FileProcessingError;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 /// Complete AST representation of a Rust project.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectAst {
     /// Project metadata
     pub project: ProjectInfo,
@@ -20,10 +22,211 @@ pub struct ProjectAst {
     pub extracted_at: DateTime<Utc>,
     /// Project-wide cross-references
     pub cross_references: Vec<CrossReference>,
+    /// Public API of dependency crates (populated when dependency parsing is enabled)
+    #[serde(default)]
+    pub dependencies_ast: Vec<DependencyAst>,
+    /// Workspace structure (present when the project root is a Cargo workspace)
+    #[serde(default)]
+    pub workspace: Option<WorkspaceAst>,
+    /// Resolved element-to-element reference graph
+    #[serde(default)]
+    pub reference_graph: ReferenceGraph,
+    /// Methods of each type, keyed by the type's qualified name
+    #[serde(default)]
+    pub type_index: BTreeMap<String, TypeMethods>,
+    /// Every trait implementation in the project
+    #[serde(default)]
+    pub impls: Vec<TraitImpl>,
+    /// How the output was reduced to fit an output budget, if it was
+    #[serde(default)]
+    pub truncation: Option<TruncationReport>,
+    /// Files that failed to extract or were only partly extracted
+    #[serde(default)]
+    pub extraction_report: ExtractionReport,
+    /// Metadata, metrics and messages of the plugins run on the project, by
+    /// plugin name
+    #[serde(default)]
+    pub plugin_results: BTreeMap<String, PluginResults>,
+}
+
+/// What a plugin reported about a project, besides its changes to elements.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PluginResults {
+    /// Metadata the plugin added
+    #[serde(default)]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+    /// Metrics the plugin calculated
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    /// Diagnostics the plugin reported, including its failures
+    #[serde(default)]
+    pub messages: Vec<PluginDiagnostic>,
+}
+
+impl PluginResults {
+    /// Whether the plugin reported nothing.
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty() && self.metrics.is_empty() && self.messages.is_empty()
+    }
+}
+
+/// A message a plugin reported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PluginDiagnostic {
+    /// Severity: `debug`, `info`, `warning` or `error`
+    pub level: String,
+    /// Text of the message
+    pub message: String,
+    /// Rule the message reports a violation of, for diagnostics in SARIF output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// File the diagnostic is in, relative to the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// Line the diagnostic is on (1-based)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// How an extraction was reduced to fit `max_output_bytes` or `max_output_tokens`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TruncationReport {
+    /// Policy used to reduce the output
+    pub policy: crate::config::TruncationPolicy,
+    /// Size limit of the output in bytes
+    pub limit_bytes: usize,
+    /// Size of the output before it was reduced, in bytes
+    pub original_bytes: usize,
+    /// Number of elements reduced to their signature and doc summary
+    pub stripped_elements: usize,
+    /// Number of elements removed
+    pub dropped_elements: usize,
+    /// Files removed entirely, relative to the project root
+    pub dropped_files: Vec<PathBuf>,
+}
+
+/// Files left out of an extraction, or only partly extracted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ExtractionReport {
+    /// Files missing from the output because they failed to extract
+    pub failed: Vec<SkippedFile>,
+    /// Files in the output with parts left out, one entry per problem
+    pub incomplete: Vec<SkippedFile>,
+}
+
+impl ExtractionReport {
+    /// Whether every file was extracted completely.
+    pub fn is_empty(&self) -> bool {
+        self.failed.is_empty() && self.incomplete.is_empty()
+    }
+
+    /// Number of distinct files in `incomplete`.
+    pub fn incomplete_file_count(&self) -> usize {
+        self.incomplete
+            .iter()
+            .map(|skipped| &skipped.file)
+            .collect::<std::collections::BTreeSet<_>>()
+            .len()
+    }
+
+    /// Record a file that failed to extract. Its path is made relative to `root`.
+    pub fn add_failure(&mut self, root: &Path, error: &FileProcessingError) {
+        let (reason, message) = match error {
+            FileProcessingError::ParseError { error, .. } => {
+                (SkipReason::ParseError, error.clone())
+            }
+            FileProcessingError::IoError { error, .. } => (SkipReason::IoError, error.clone()),
+            FileProcessingError::TooLarge { size, .. } => {
+                (SkipReason::TooLarge, format!("{} bytes", size))
+            }
+            FileProcessingError::AccessDenied { .. } => {
+                (SkipReason::AccessDenied, "Permission denied".to_string())
+            }
+            FileProcessingError::InvalidEncoding { error, .. } => {
+                (SkipReason::InvalidEncoding, error.clone())
+            }
+        };
+        let file = error.file_path();
+        self.failed.push(SkippedFile {
+            file: file.strip_prefix(root).unwrap_or(file).to_path_buf(),
+            reason,
+            message,
+        });
+    }
+
+    /// Record the parts left out of an extracted file, if any.
+    pub fn add_file(&mut self, file: &FileAst) {
+        let entry = |reason, message| SkippedFile {
+            file: file.relative_path.clone(),
+            reason,
+            message,
+        };
+        // An oversized file's warning comes first; the others are about
+        // lossy decoding.
+        let mut warnings = file.warnings.iter();
+        if file.partially_extracted {
+            warnings.next();
+            let message = "Only signatures and docs were extracted".to_string();
+            self.incomplete.push(entry(SkipReason::TooLarge, message));
+        }
+        let prefix = format!("Invalid encoding in {}: ", file.path.display());
+        for warning in warnings {
+            let message = warning.strip_prefix(&prefix).unwrap_or(warning);
+            self.incomplete
+                .push(entry(SkipReason::InvalidEncoding, message.to_string()));
+        }
+        for span in &file.parse_errors {
+            let message = format!(
+                "Skipped lines {}-{}: {}",
+                span.line_start, span.line_end, span.message
+            );
+            self.incomplete.push(entry(SkipReason::ParseError, message));
+        }
+    }
+}
+
+/// A file that failed to extract or was only partly extracted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SkippedFile {
+    /// File, relative to the project root
+    pub file: PathBuf,
+    /// Why the file, or part of it, was left out
+    pub reason: SkipReason,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Why a file, or part of it, was left out of an extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The file is over `max_file_size`
+    TooLarge,
+    /// The file, or part of it, isn't valid Rust
+    ParseError,
+    /// The file isn't valid in its encoding
+    InvalidEncoding,
+    /// The file couldn't be read
+    IoError,
+    /// The file isn't readable with the current permissions
+    AccessDenied,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            SkipReason::TooLarge => "too large",
+            SkipReason::ParseError => "parse error",
+            SkipReason::InvalidEncoding => "invalid encoding",
+            SkipReason::IoError => "I/O error",
+            SkipReason::AccessDenied => "access denied",
+        };
+        f.write_str(reason)
+    }
 }
 
 /// Project metadata information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectInfo {
     /// Project name
     pub name: String,
@@ -36,7 +239,7 @@ pub struct ProjectInfo {
 }
 
 /// AST representation of a single Rust file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FileAst {
     /// Absolute path to the file
     pub path: PathBuf,
@@ -50,12 +253,60 @@ pub struct FileAst {
     pub file_metrics: FileMetrics,
     /// Cross-references within this file
     pub cross_references: Vec<CrossReference>,
+    /// Spans skipped because they failed to parse (see `recover_parse_errors`)
+    #[serde(default)]
+    pub parse_errors: Vec<ParseErrorSpan>,
+    /// Non-fatal problems found while reading the file, such as lossy decoding
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Kind of crate target the file belongs to
+    #[serde(default)]
+    pub kind: FileKind,
+    /// Whether only signatures and docs were extracted because the file is
+    /// over `max_file_size`; elements then have no complexity
+    #[serde(default)]
+    pub partially_extracted: bool,
+}
+
+/// Kind of crate target a source file belongs to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    /// Library, binary or other regular crate source
+    #[default]
+    Source,
+    /// A Cargo build script (`build.rs` or the package's `build` path)
+    BuildScript,
+    /// Source of a `proc-macro = true` crate
+    ProcMacro,
+}
+
+impl FileKind {
+    /// The name used in serialized output, e.g. `build_script`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileKind::Source => "source",
+            FileKind::BuildScript => "build_script",
+            FileKind::ProcMacro => "proc_macro",
+        }
+    }
+}
+
+/// A span of a file that could not be parsed and was skipped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ParseErrorSpan {
+    /// Parser error message
+    pub message: String,
+    /// First line of the skipped span (1-based)
+    pub line_start: usize,
+    /// Last line of the skipped span (1-based, inclusive)
+    pub line_end: usize,
 }
 
 /// A single code element (function, struct, etc.) with hierarchical relationships.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CodeElement {
-    /// Unique identifier for this element
+    /// Unique identifier, stable across runs (see [`HierarchyBuilder::generate_stable_id`])
     pub id: String,
     /// Type of the code element
     pub element_type: ElementType,
@@ -67,28 +318,353 @@ pub struct CodeElement {
     pub visibility: Visibility,
     /// Documentation comments
     pub doc_comments: Vec<String>,
-    /// Inline comments
-    pub inline_comments: Vec<String>,
+    /// Fenced code examples from the documentation comments
+    #[serde(default)]
+    pub doc_examples: Vec<DocExample>,
+    /// Regular comments inside or directly before the element (see `include_inline_comments`)
+    pub inline_comments: Vec<InlineComment>,
     /// Location in source code
     pub location: CodeLocation,
+    /// Location of the element's name, for elements that have one
+    #[serde(default)]
+    pub name_location: Option<CodeLocation>,
     /// Attributes applied to this element
     pub attributes: Vec<String>,
+    /// Parsed form of `attributes`, in the same order
+    #[serde(default)]
+    pub attribute_info: Vec<AttributeInfo>,
+    /// Set when the element is marked `#[deprecated]`
+    #[serde(default)]
+    pub deprecation: Option<Deprecation>,
     /// Cyclomatic complexity (if applicable)
     pub complexity: Option<u32>,
     /// Detailed complexity metrics
     pub complexity_metrics: Option<crate::complexity::ComplexityMetrics>,
     /// Dependencies on other elements
     pub dependencies: Vec<String>,
+    /// Calls made in the body of a function, in source order
+    #[serde(default)]
+    pub calls: Vec<CallSite>,
     /// Generic parameters
     pub generic_params: Vec<String>,
     /// Additional metadata
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub metadata: HashMap<String, serde_json::Value>,
     /// Hierarchical relationships
     pub hierarchy: ElementHierarchy,
+    /// Whether the element was produced by macro expansion rather than written in source
+    #[serde(default)]
+    pub is_generated: bool,
+    /// `#[cfg(...)]` predicates that must all hold for the element to be compiled,
+    /// including those of enclosing modules and impls
+    #[serde(default)]
+    pub cfg_predicates: Vec<CfgPredicate>,
+}
+
+/// Key of the `metadata` entry listing an enum's variant names.
+pub const ENUM_VARIANTS_METADATA_KEY: &str = "variants";
+
+/// Key of the `metadata` entry listing a trait's methods without a default body.
+pub const REQUIRED_METHODS_METADATA_KEY: &str = "required_methods";
+
+/// Key of the `metadata` entry listing a trait's methods with a default body.
+pub const PROVIDED_METHODS_METADATA_KEY: &str = "provided_methods";
+
+/// Key of the `metadata` entry listing the methods defined in an impl block.
+pub const IMPL_METHODS_METADATA_KEY: &str = "impl_methods";
+
+/// Key of the `metadata` entry listing the variables a closure or `async` block captures.
+pub const CAPTURES_METADATA_KEY: &str = "captures";
+
+/// Key of the `metadata` entry tagging elements of build scripts and
+/// proc-macro crates with their [`FileKind`].
+pub const FILE_KIND_METADATA_KEY: &str = "file_kind";
+
+/// Serialize a map with its keys in order rather than in hash order, so the
+/// same data always serializes the same way.
+pub fn serialize_sorted_map<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+impl CodeElement {
+    /// Kind of crate target the element was extracted from.
+    pub fn file_kind(&self) -> FileKind {
+        self.metadata
+            .get(FILE_KIND_METADATA_KEY)
+            .and_then(|kind| serde_json::from_value(kind.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Names of the variants of an enum element, in declaration order.
+    ///
+    /// Empty for other elements.
+    pub fn enum_variants(&self) -> Vec<&str> {
+        self.metadata_names(ENUM_VARIANTS_METADATA_KEY)
+    }
+
+    /// Names of a trait's required methods, which have no default body.
+    ///
+    /// Empty for other elements.
+    pub fn required_methods(&self) -> Vec<&str> {
+        self.metadata_names(REQUIRED_METHODS_METADATA_KEY)
+    }
+
+    /// Names of a trait's provided methods, which have a default body.
+    ///
+    /// Empty for other elements.
+    pub fn provided_methods(&self) -> Vec<&str> {
+        self.metadata_names(PROVIDED_METHODS_METADATA_KEY)
+    }
+
+    /// Names of the methods defined in an impl block, including private ones.
+    ///
+    /// Empty for other elements.
+    pub fn impl_methods(&self) -> Vec<&str> {
+        self.metadata_names(IMPL_METHODS_METADATA_KEY)
+    }
+
+    /// Variables of the enclosing function that a closure or `async` block
+    /// uses, in order of first use.
+    ///
+    /// Empty for other elements.
+    pub fn captures(&self) -> Vec<&str> {
+        self.metadata_names(CAPTURES_METADATA_KEY)
+    }
+
+    /// The string list stored under a `metadata` key.
+    fn metadata_names(&self, key: &str) -> Vec<&str> {
+        self.metadata
+            .get(key)
+            .and_then(serde_json::Value::as_array)
+            .map(|names| names.iter().filter_map(serde_json::Value::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether the element is marked `#[non_exhaustive]`.
+    pub fn is_non_exhaustive(&self) -> bool {
+        self.attribute("non_exhaustive").is_some()
+    }
+
+    /// The first attribute with the given path, such as `"serde"` or `"rustfmt::skip"`.
+    pub fn attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attribute_info
+            .iter()
+            .find(|attribute| attribute.name == name)
+    }
+
+    /// Whether the element derives `trait_name`, given as written or by its last segment.
+    ///
+    /// `derives("Serialize")` matches both `#[derive(Serialize)]` and
+    /// `#[derive(serde::Serialize)]`.
+    pub fn derives(&self, trait_name: &str) -> bool {
+        self.attribute_info
+            .iter()
+            .flat_map(|attribute| &attribute.derived_traits)
+            .any(|derived| derived == trait_name || derived.rsplit("::").next() == Some(trait_name))
+    }
+}
+
+/// A parsed attribute such as `#[derive(Debug)]` or `#[serde(rename = "id")]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AttributeInfo {
+    /// Attribute path, e.g. "derive", "deprecated" or "rustfmt::skip"
+    pub name: String,
+    /// Items of a list attribute, e.g. `rename = "id"` in `#[serde(rename = "id")]`
+    #[serde(default)]
+    pub args: Vec<AttributeArg>,
+    /// Value of `#[name = value]`, or the raw tokens of a list whose items
+    /// aren't paths or `key = value` pairs (e.g. `align(8)` in `repr`)
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Whether this is a `#[derive(...)]` attribute
+    pub is_derive: bool,
+    /// Paths of the derived traits, as written
+    #[serde(default)]
+    pub derived_traits: Vec<String>,
+}
+
+impl AttributeInfo {
+    /// The first argument with the given name.
+    pub fn arg(&self, name: &str) -> Option<&AttributeArg> {
+        self.args.iter().find(|arg| arg.name == name)
+    }
+}
+
+/// A function, method or macro invoked in a function body.
+///
+/// Names are recorded as written and not resolved to elements.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CallSite {
+    /// Path of a function (`helper`, `Vec::new`) or macro (`println`), or
+    /// the name of a method
+    pub name: String,
+    /// What kind of call this is
+    pub kind: CallKind,
+    /// Line of the call (1-based)
+    pub line: usize,
+}
+
+/// Kind of a [`CallSite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CallKind {
+    /// Call of a free function or associated function by path
+    Function,
+    /// Method call on a receiver (`value.method()`)
+    Method,
+    /// Macro invocation
+    Macro,
+}
+
+/// Details of a `#[deprecated]` attribute.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Deprecation {
+    /// Version the element was deprecated in
+    pub since: Option<String>,
+    /// Explanation, usually naming the replacement
+    pub note: Option<String>,
+}
+
+impl Deprecation {
+    /// Read `#[deprecated]`, `#[deprecated = "note"]` or
+    /// `#[deprecated(since = "..", note = "..")]`; `None` for other attributes.
+    pub fn from_attribute(attribute: &AttributeInfo) -> Option<Self> {
+        if attribute.name != "deprecated" {
+            return None;
+        }
+        let arg = |name| attribute.arg(name).and_then(|arg| arg.value.clone());
+        Some(Self {
+            since: arg("since"),
+            note: attribute.value.clone().or_else(|| arg("note")),
+        })
+    }
+}
+
+/// An item inside a list attribute.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AttributeArg {
+    /// Path of the item, e.g. "rename" in `rename = "id"`
+    pub name: String,
+    /// Value of `name = value` items (string literals are unquoted), or the
+    /// raw tokens of a nested list that doesn't parse into items
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Items of a nested list such as `rename(serialize = "a")`
+    #[serde(default)]
+    pub args: Vec<AttributeArg>,
+}
+
+/// A parsed `#[cfg(...)]` predicate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum CfgPredicate {
+    /// A configuration option such as `unix` or `feature = "serde"`
+    Option {
+        /// Option name (e.g. "feature", "target_os", "test")
+        name: String,
+        /// Option value, if the option is a key-value pair
+        value: Option<String>,
+    },
+    /// `all(...)`: every predicate holds
+    All(Vec<CfgPredicate>),
+    /// `any(...)`: at least one predicate holds
+    Any(Vec<CfgPredicate>),
+    /// `not(...)`: the predicate does not hold
+    Not(Box<CfgPredicate>),
+}
+
+impl CfgPredicate {
+    /// Evaluate the predicate with only `features` enabled.
+    ///
+    /// Returns `None` when the result depends on options other than
+    /// `feature` (target, `test`, ...), which cannot be decided statically.
+    pub fn evaluate(&self, features: &[String]) -> Option<bool> {
+        match self {
+            CfgPredicate::Option { name, value } if name == "feature" => {
+                Some(value.as_ref().is_some_and(|value| features.contains(value)))
+            }
+            CfgPredicate::Option { .. } => None,
+            CfgPredicate::All(predicates) => {
+                let results: Vec<_> = predicates.iter().map(|p| p.evaluate(features)).collect();
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.iter().all(|r| *r == Some(true)) {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            CfgPredicate::Any(predicates) => {
+                let results: Vec<_> = predicates.iter().map(|p| p.evaluate(features)).collect();
+                if results.contains(&Some(true)) {
+                    Some(true)
+                } else if results.iter().all(|r| *r == Some(false)) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            CfgPredicate::Not(predicate) => predicate.evaluate(features).map(|result| !result),
+        }
+    }
+}
+
+impl std::fmt::Display for CfgPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |predicates: &[CfgPredicate]| {
+            predicates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            CfgPredicate::Option {
+                name,
+                value: Some(value),
+            } => write!(f, "{} = \"{}\"", name, value),
+            CfgPredicate::Option { name, value: None } => write!(f, "{}", name),
+            CfgPredicate::All(predicates) => write!(f, "all({})", join(predicates)),
+            CfgPredicate::Any(predicates) => write!(f, "any({})", join(predicates)),
+            CfgPredicate::Not(predicate) => write!(f, "not({})", predicate),
+        }
+    }
+}
+
+/// A regular (non-doc) comment and the lines it spans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct InlineComment {
+    /// Comment text without its delimiters, trimmed
+    pub text: String,
+    /// First line of the comment (1-based)
+    pub line_start: usize,
+    /// Last line of the comment (1-based, inclusive)
+    pub line_end: usize,
+}
+
+/// A fenced code block from an element's documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DocExample {
+    /// Language of the block ("rust" when the fence has no tag, as rustdoc assumes)
+    pub language: String,
+    /// Code inside the fence, including lines rustdoc hides with `# `
+    pub code: String,
+    /// The example is expected to panic (`should_panic`)
+    pub should_panic: bool,
+    /// The example is compiled but not run (`no_run`)
+    pub no_run: bool,
+    /// The example is skipped by doc tests (`ignore`)
+    pub ignore: bool,
+    /// The example is expected to fail to compile (`compile_fail`)
+    pub compile_fail: bool,
 }
 
 /// Types of code elements that can be extracted.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum ElementType {
     /// Function definition
     Function,
@@ -112,21 +688,120 @@ pub enum ElementType {
     Macro,
     /// Union definition
     Union,
+    /// Closure inside a function body (see `extract_closures`)
+    Closure,
+    /// `async` block inside a function body (see `extract_closures`)
+    AsyncBlock,
+}
+
+impl ElementType {
+    pub(crate) const ALL: [ElementType; 13] = [
+        ElementType::Function,
+        ElementType::Struct,
+        ElementType::Enum,
+        ElementType::Trait,
+        ElementType::Impl,
+        ElementType::Module,
+        ElementType::Constant,
+        ElementType::Static,
+        ElementType::TypeAlias,
+        ElementType::Macro,
+        ElementType::Union,
+        ElementType::Closure,
+        ElementType::AsyncBlock,
+    ];
+
+    /// The snake_case name of the element type, such as `type_alias`.
+    pub fn filter_name(&self) -> &'static str {
+        match self {
+            ElementType::Function => "function",
+            ElementType::Struct => "struct",
+            ElementType::Enum => "enum",
+            ElementType::Trait => "trait",
+            ElementType::Impl => "impl",
+            ElementType::Module => "module",
+            ElementType::Constant => "constant",
+            ElementType::Static => "static",
+            ElementType::TypeAlias => "type_alias",
+            ElementType::Macro => "macro",
+            ElementType::Union => "union",
+            ElementType::Closure => "closure",
+            ElementType::AsyncBlock => "async_block",
+        }
+    }
+
+    /// Whether the element type is selected by a filter such as `function`.
+    ///
+    /// Filters are snake_case names, matched case-insensitively.
+    pub fn matches_filter(&self, filter: &str) -> bool {
+        self.filter_name().eq_ignore_ascii_case(filter.trim())
+    }
+
+    /// Whether `filter` is a valid argument to [`matches_filter`](Self::matches_filter).
+    pub fn is_valid_filter(filter: &str) -> bool {
+        Self::ALL
+            .iter()
+            .any(|element_type| element_type.matches_filter(filter))
+    }
 }
 
 /// Visibility levels for code elements.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum Visibility {
     /// Public visibility
     Public,
-    /// Restricted visibility (e.g., pub(crate))
+    /// Visible within the current crate (`pub(crate)`)
+    Crate,
+    /// Visible within the parent module (`pub(super)`)
+    Super,
+    /// Visible within the given module (`pub(in path)`), e.g. "crate::net"
     Restricted(String),
-    /// Private (inherited) visibility
+    /// Private (inherited) visibility, including `pub(self)`
     Private,
 }
 
+impl Visibility {
+    /// Whether the visibility is selected by a filter such as `pub(crate)`.
+    ///
+    /// Filters are written as in Rust source, with `private` for inherited
+    /// visibility and `pub(in)` matching any `pub(in path)`. Whitespace is
+    /// ignored.
+    pub fn matches_filter(&self, filter: &str) -> bool {
+        let filter: String = filter.split_whitespace().collect();
+        match self {
+            Visibility::Restricted(_) if filter == "pub(in)" => true,
+            _ => self.to_string().replace(' ', "") == filter,
+        }
+    }
+
+    /// Whether `filter` is a valid argument to [`matches_filter`](Self::matches_filter).
+    pub fn is_valid_filter(filter: &str) -> bool {
+        let filter: String = filter.split_whitespace().collect();
+        matches!(
+            filter.as_str(),
+            "pub" | "pub(crate)" | "pub(super)" | "pub(in)" | "private"
+        ) || filter
+            .strip_prefix("pub(in")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .is_some_and(|path| !path.is_empty())
+    }
+}
+
+impl std::fmt::Display for Visibility {
+    /// Formats the visibility as written in Rust, or `private` for inherited visibility.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Visibility::Public => write!(f, "pub"),
+            Visibility::Crate => write!(f, "pub(crate)"),
+            Visibility::Super => write!(f, "pub(super)"),
+            Visibility::Restricted(path) => write!(f, "pub(in {})", path),
+            Visibility::Private => write!(f, "private"),
+        }
+    }
+}
+
 /// Location information for code elements.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CodeLocation {
     /// Starting line number
     pub line_start: usize,
@@ -136,12 +811,18 @@ pub struct CodeLocation {
     pub char_start: usize,
     /// Ending character position
     pub char_end: usize,
+    /// Byte offset of the start in the source file
+    #[serde(default)]
+    pub byte_start: usize,
+    /// Byte offset just past the end in the source file
+    #[serde(default)]
+    pub byte_end: usize,
     /// File path
     pub file_path: PathBuf,
 }
 
 /// Import/use statement information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ImportInfo {
     /// Module path being imported
     pub module_path: String,
@@ -151,10 +832,16 @@ pub struct ImportInfo {
     pub is_glob: bool,
     /// Alias for the import
     pub alias: Option<String>,
+    /// Whether this is a `pub use` re-export
+    #[serde(default)]
+    pub is_reexport: bool,
+    /// Module the `use` declaration appears in (e.g., "crate::net")
+    #[serde(default)]
+    pub scope: String,
 }
 
 /// Dependency information for the project.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DependencyInfo {
     /// Direct dependencies
     pub direct: Vec<String>,
@@ -164,8 +851,61 @@ pub struct DependencyInfo {
     pub dev_dependencies: Vec<String>,
 }
 
+/// Per-crate view of a Cargo workspace.
+///
+/// Files stay in [`ProjectAst::files`]; each crate lists the files it owns.
+/// [`ProjectAst::metrics`] is the workspace-level rollup across all crates.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceAst {
+    /// Workspace member crates that were extracted
+    pub crates: Vec<CrateAst>,
+    /// Dependency edges between workspace members
+    pub dependency_edges: Vec<CrateDependency>,
+}
+
+/// A single crate within a workspace.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CrateAst {
+    /// Package name
+    pub name: String,
+    /// Package version
+    pub version: String,
+    /// Crate root relative to the workspace root
+    pub relative_path: PathBuf,
+    /// Relative paths of the files belonging to this crate
+    pub files: Vec<PathBuf>,
+    /// Metrics for this crate only
+    pub metrics: ProjectMetrics,
+}
+
+/// Dependency of one workspace crate on another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CrateDependency {
+    /// Name of the depending crate
+    pub from: String,
+    /// Name of the crate depended upon
+    pub to: String,
+    /// Dependency kind ("normal", "dev" or "build")
+    pub kind: String,
+}
+
+/// Extracted public API of a single dependency crate.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DependencyAst {
+    /// Crate name
+    pub name: String,
+    /// Resolved crate version
+    pub version: String,
+    /// Location of the crate sources that were parsed
+    pub source_path: PathBuf,
+    /// Files containing the crate's public elements
+    pub files: Vec<FileAst>,
+    /// Metrics for the extracted public API
+    pub metrics: ProjectMetrics,
+}
+
 /// Project-wide metrics.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectMetrics {
     /// Total lines of code
     pub total_lines: usize,
@@ -183,10 +923,47 @@ pub struct ProjectMetrics {
     pub complexity_average: f64,
     /// Maximum complexity
     pub complexity_max: u32,
+    /// Deprecated elements and who still uses them
+    #[serde(default)]
+    pub deprecations: DeprecationSummary,
+    /// Borrowing and shared-ownership patterns across all files
+    #[serde(default)]
+    pub ownership: OwnershipProfile,
+    /// Uses of `unsafe` across all files
+    #[serde(default)]
+    pub unsafe_usage: UnsafeUsage,
+}
+
+/// Deprecated elements of a project and the elements still referencing them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DeprecationSummary {
+    /// Number of deprecated elements
+    pub total_deprecated: usize,
+    /// Number of distinct (caller, deprecated element) pairs
+    pub total_usages: usize,
+    /// Deprecated elements, in file order
+    pub items: Vec<DeprecatedItem>,
+}
+
+/// A deprecated element and its callers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DeprecatedItem {
+    /// Element ID
+    pub element_id: String,
+    /// Fully qualified name
+    pub qualified_name: String,
+    /// Type of the element
+    pub element_type: ElementType,
+    /// Version the element was deprecated in
+    pub since: Option<String>,
+    /// Deprecation note
+    pub note: Option<String>,
+    /// Qualified names of the elements referencing it, sorted
+    pub callers: Vec<String>,
 }
 
 /// File-level metrics.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FileMetrics {
     /// Lines of code (excluding comments/blank lines)
     pub lines_of_code: usize,
@@ -202,10 +979,90 @@ pub struct FileMetrics {
     pub enum_count: usize,
     /// Number of traits in this file
     pub trait_count: usize,
+    /// Borrowing and shared-ownership patterns in this file
+    #[serde(default)]
+    pub ownership: OwnershipProfile,
+    /// Uses of `unsafe` in this file
+    #[serde(default)]
+    pub unsafe_usage: UnsafeUsage,
+}
+
+/// How code borrows and shares data, as counts of syntactic patterns.
+///
+/// Many `.clone()` calls or `Rc<RefCell<_>>` uses often point at code that
+/// fights the borrow checker and is worth refactoring.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OwnershipProfile {
+    /// Lifetimes written out, in generics, references, bounds and paths
+    pub lifetime_annotations: usize,
+    /// Function parameters taken by shared reference, including `&self`
+    pub shared_ref_params: usize,
+    /// Function parameters taken by mutable reference, including `&mut self`
+    pub mut_ref_params: usize,
+    /// Paths naming `Arc`, in types and expressions such as `Arc::new`
+    pub arc_usages: usize,
+    /// Paths naming `Rc`
+    pub rc_usages: usize,
+    /// Paths naming `RefCell`
+    pub refcell_usages: usize,
+    /// `.clone()` method calls
+    pub clone_calls: usize,
+    /// `.clone()` calls per 100 lines of code
+    pub clone_density: f64,
+}
+
+impl OwnershipProfile {
+    /// Add the counts of `other`, leaving `clone_density` to be recomputed.
+    pub fn merge(&mut self, other: &OwnershipProfile) {
+        self.lifetime_annotations += other.lifetime_annotations;
+        self.shared_ref_params += other.shared_ref_params;
+        self.mut_ref_params += other.mut_ref_params;
+        self.arc_usages += other.arc_usages;
+        self.rc_usages += other.rc_usages;
+        self.refcell_usages += other.refcell_usages;
+        self.clone_calls += other.clone_calls;
+    }
+
+    /// Set `clone_density` for code of the given size.
+    pub fn update_clone_density(&mut self, lines_of_code: usize) {
+        self.clone_density = if lines_of_code == 0 {
+            0.0
+        } else {
+            self.clone_calls as f64 * 100.0 / lines_of_code as f64
+        };
+    }
+}
+
+/// Uses of the `unsafe` keyword, counted syntactically.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct UnsafeUsage {
+    /// `unsafe { ... }` blocks
+    pub unsafe_blocks: usize,
+    /// `unsafe fn` declarations, including methods and trait methods
+    pub unsafe_functions: usize,
+    /// `unsafe impl` blocks
+    pub unsafe_impls: usize,
+    /// `unsafe trait` declarations
+    pub unsafe_traits: usize,
+}
+
+impl UnsafeUsage {
+    /// Add the counts of `other`.
+    pub fn merge(&mut self, other: &UnsafeUsage) {
+        self.unsafe_blocks += other.unsafe_blocks;
+        self.unsafe_functions += other.unsafe_functions;
+        self.unsafe_impls += other.unsafe_impls;
+        self.unsafe_traits += other.unsafe_traits;
+    }
+
+    /// Total number of `unsafe` uses.
+    pub fn total(&self) -> usize {
+        self.unsafe_blocks + self.unsafe_functions + self.unsafe_impls + self.unsafe_traits
+    }
 }
 
 /// Hierarchical relationship information for code elements.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ElementHierarchy {
     /// ID of the parent element (None for top-level elements)
     pub parent_id: Option<String>,
@@ -219,10 +1076,74 @@ pub struct ElementHierarchy {
     pub qualified_name: String,
     /// Namespace information for this element
     pub namespace: ElementNamespace,
+    /// Impl block this element belongs to (impl blocks and their methods)
+    #[serde(default)]
+    pub impl_context: Option<ImplContext>,
+    /// ID of the type element an impl block or method is attached to, if it was extracted
+    #[serde(default)]
+    pub parent_element: Option<String>,
+}
+
+/// Describes the impl block an element was declared in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ImplContext {
+    /// Qualified name of the implementing type (e.g. "crate::net::Message")
+    pub self_type: String,
+    /// Path of the implemented trait, `None` for inherent impls
+    pub trait_path: Option<String>,
+    /// Where clause of the impl block (e.g. "where T: Clone")
+    #[serde(default)]
+    pub where_clause: Option<String>,
+}
+
+/// A single `impl Trait for Type` block.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TraitImpl {
+    /// ID of the impl block element
+    pub impl_id: String,
+    /// Path of the implemented trait as written (e.g. "std::fmt::Display")
+    pub trait_path: String,
+    /// Qualified name of the implementing type
+    pub self_type: String,
+    /// ID of the trait's defining element, if it is part of the project
+    pub trait_element_id: Option<String>,
+    /// ID of the implementing type's element, if it was extracted
+    pub type_element_id: Option<String>,
+    /// Generic parameters of the impl block
+    pub generics: Vec<String>,
+    /// Where clause of the impl block
+    pub where_clause: Option<String>,
+    /// Source location of the impl block
+    pub location: CodeLocation,
+    /// Whether the impl was produced by macro expansion (e.g. a derive)
+    pub is_generated: bool,
+    /// Provided methods of the trait that the impl redefines
+    ///
+    /// Only known when the trait is part of the project.
+    #[serde(default)]
+    pub overridden_methods: Vec<String>,
+    /// Provided methods of the trait that the impl uses the default body of
+    ///
+    /// Only known when the trait is part of the project.
+    #[serde(default)]
+    pub inherited_methods: Vec<String>,
+}
+
+/// Methods attached to a single type, across all of its impl blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TypeMethods {
+    /// ID of the type's defining element, if it was extracted
+    pub type_element_id: Option<String>,
+    /// IDs of the impl block elements for this type
+    pub impl_ids: Vec<String>,
+    /// IDs of inherent methods
+    pub inherent_methods: Vec<String>,
+    /// IDs of trait methods, keyed by trait path
+    pub trait_methods: BTreeMap<String, Vec<String>>,
 }
 
 /// Namespace information for code elements.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ElementNamespace {
     /// The element's simple name
     pub simple_name: String,
@@ -232,6 +1153,11 @@ pub struct ElementNamespace {
     pub aliases: Vec<String>,
     /// Import paths that bring this element into scope
     pub import_paths: Vec<String>,
+    /// Public paths the element can be imported by from outside its crate,
+    /// following `pub use` re-exports. Starts with `canonical_path` when every
+    /// enclosing module is public.
+    #[serde(default)]
+    pub exported_paths: Vec<String>,
     /// Whether this element is publicly accessible
     pub is_public: bool,
     /// Visibility scope (crate, super, self, etc.)
@@ -239,7 +1165,7 @@ pub struct ElementNamespace {
 }
 
 /// Visibility scope for namespace resolution.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum VisibilityScope {
     /// Public to all crates
     Public,
@@ -253,15 +1179,38 @@ pub enum VisibilityScope {
     Private,
 }
 
+/// 64-bit FNV-1a hash of `parts`, which unlike `DefaultHasher` is stable
+/// across Rust versions and platforms.
+pub fn stable_hash(parts: &[&str]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        // The separator keeps ("ab", "c") and ("a", "bc") apart.
+        for byte in part.bytes().chain(std::iter::once(0xff)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
 /// Hierarchical structure helper for building relationships during AST traversal.
 #[derive(Debug, Clone)]
 pub struct HierarchyBuilder {
     /// Stack of parent elements during traversal
     parent_stack: Vec<String>,
+    /// Qualified name introduced by each parent scope (`None` keeps the enclosing path)
+    scope_paths: Vec<Option<String>>,
     /// Current module path
     current_module_path: String,
     /// Element counter for generating unique IDs
     element_counter: u32,
+    /// Optional prefix making IDs unique across crates
+    id_namespace: Option<String>,
+    /// Stable IDs issued so far, with how many times each was requested
+    issued_ids: HashMap<String, u32>,
 }
 
 impl ElementHierarchy {
@@ -274,6 +1223,8 @@ impl ElementHierarchy {
             module_path,
             qualified_name,
             namespace,
+            impl_context: None,
+            parent_element: None,
         }
     }
     
@@ -286,6 +1237,8 @@ impl ElementHierarchy {
             module_path,
             qualified_name,
             namespace,
+            impl_context: None,
+            parent_element: None,
         }
     }
     
@@ -300,25 +1253,95 @@ impl HierarchyBuilder {
     pub fn new(module_path: String) -> Self {
         Self {
             parent_stack: Vec::new(),
+            scope_paths: Vec::new(),
             current_module_path: module_path,
             element_counter: 0,
+            id_namespace: None,
+            issued_ids: HashMap::new(),
         }
     }
+
+    /// Prefix generated IDs with a namespace (typically the crate name).
+    pub fn set_id_namespace(&mut self, namespace: String) {
+        self.id_namespace = Some(namespace);
+    }
+
+    /// Replace the module path of the file being built.
+    pub fn set_module_path(&mut self, module_path: String) {
+        self.current_module_path = module_path;
+    }
     
     /// Generate a unique element ID.
     pub fn generate_id(&mut self, element_type: &ElementType, name: &str) -> String {
         self.element_counter += 1;
-        format!("{:?}_{}_{}", element_type, name, self.element_counter)
+        match &self.id_namespace {
+            Some(namespace) => format!(
+                "{}::{:?}_{}_{}",
+                namespace, element_type, name, self.element_counter
+            ),
+            None => format!("{:?}_{}_{}", element_type, name, self.element_counter),
+        }
     }
     
+    /// Generate an element ID that is stable across runs.
+    ///
+    /// The ID is `{Type}_{hash}` (prefixed with the namespace, if set), where
+    /// the hash covers the element type, name, qualified name, signature with
+    /// whitespace removed, and cfg predicates. Adding, removing or reordering
+    /// other elements leaves it unchanged. Identical elements get a `_2`,
+    /// `_3`, ... suffix in visiting order.
+    pub fn generate_stable_id(
+        &mut self,
+        element_type: &ElementType,
+        name: &str,
+        qualified_name: &str,
+        signature: Option<&str>,
+        cfg_predicates: &[CfgPredicate],
+    ) -> String {
+        let normalized_signature: String =
+            signature.unwrap_or_default().split_whitespace().collect();
+        let cfg: Vec<String> = cfg_predicates.iter().map(ToString::to_string).collect();
+        let hash = stable_hash(&[
+            &format!("{:?}", element_type),
+            name,
+            qualified_name,
+            &normalized_signature,
+            &cfg.join(","),
+        ]);
+
+        let base = match &self.id_namespace {
+            Some(namespace) => format!("{}::{:?}_{:016x}", namespace, element_type, hash),
+            None => format!("{:?}_{:016x}", element_type, hash),
+        };
+        let count = self.issued_ids.entry(base.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            base
+        } else {
+            format!("{}_{}", base, count)
+        }
+    }
+
     /// Enter a new scope (push parent onto stack).
+    ///
+    /// The qualified path of nested elements is unchanged; use
+    /// [`enter_named_scope`](Self::enter_named_scope) for scopes that add a path segment.
     pub fn enter_scope(&mut self, parent_id: String) {
         self.parent_stack.push(parent_id);
+        self.scope_paths.push(None);
+    }
+
+    /// Enter a scope whose elements are qualified under `qualified_name`
+    /// (e.g. `crate::net::Message` for the methods of `impl Message`).
+    pub fn enter_named_scope(&mut self, parent_id: String, qualified_name: String) {
+        self.parent_stack.push(parent_id);
+        self.scope_paths.push(Some(qualified_name));
     }
     
     /// Exit current scope (pop parent from stack).
     pub fn exit_scope(&mut self) {
         self.parent_stack.pop();
+        self.scope_paths.pop();
     }
     
     /// Get the current parent ID (top of stack).
@@ -359,13 +1382,18 @@ impl HierarchyBuilder {
         }
     }
     
-    /// Build a qualified name including parent context.
-    fn build_qualified_name(&self, name: &str) -> String {
-        if let Some(parent) = self.current_parent() {
-            format!("{}::{}", parent, name)
-        } else {
-            format!("{}::{}", self.current_module_path, name)
-        }
+    /// Build a fully qualified name for `name` in the current scope.
+    ///
+    /// Uses the innermost named scope (type, trait, impl or function), falling
+    /// back to the current module path.
+    pub fn build_qualified_name(&self, name: &str) -> String {
+        let prefix = self
+            .scope_paths
+            .iter()
+            .rev()
+            .find_map(|path| path.as_deref())
+            .unwrap_or(&self.current_module_path);
+        format!("{}::{}", prefix, name)
     }
     
     /// Build a canonical path for namespace resolution.
@@ -398,7 +1426,7 @@ impl HierarchyBuilder {
 }
 
 /// Cross-reference information tracking relationships between code elements.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CrossReference {
     /// ID of the referencing element
     pub from_element_id: String,
@@ -416,8 +1444,80 @@ pub struct CrossReference {
     pub context: ReferenceContext,
 }
 
+/// Element-level reference graph built from resolved cross-references.
+///
+/// Keys and values are element IDs. Both directions are stored so consumers
+/// can walk callers and callees without rebuilding the index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ReferenceGraph {
+    /// Elements referenced by each element (calls and type usages)
+    pub references: BTreeMap<String, Vec<String>>,
+    /// Elements referencing each element
+    pub referenced_by: BTreeMap<String, Vec<String>>,
+}
+
+impl ReferenceGraph {
+    /// Build the graph from resolved function call and type usage references.
+    ///
+    /// Self-references (e.g. recursion) are omitted and edges are deduplicated.
+    pub fn from_cross_references<'a>(
+        references: impl IntoIterator<Item = &'a CrossReference>,
+    ) -> Self {
+        let mut graph = Self::default();
+
+        for reference in references {
+            if !matches!(
+                reference.reference_type,
+                ReferenceType::FunctionCall | ReferenceType::TypeUsage
+            ) {
+                continue;
+            }
+            let Some(target) = &reference.to_element_id else {
+                continue;
+            };
+            if *target == reference.from_element_id {
+                continue;
+            }
+
+            graph
+                .references
+                .entry(reference.from_element_id.clone())
+                .or_default()
+                .push(target.clone());
+            graph
+                .referenced_by
+                .entry(target.clone())
+                .or_default()
+                .push(reference.from_element_id.clone());
+        }
+
+        for ids in graph
+            .references
+            .values_mut()
+            .chain(graph.referenced_by.values_mut())
+        {
+            ids.sort();
+            ids.dedup();
+        }
+
+        graph
+    }
+
+    /// IDs of the elements referenced by `element_id`.
+    pub fn references_of(&self, element_id: &str) -> &[String] {
+        self.references.get(element_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// IDs of the elements that reference `element_id`.
+    pub fn referenced_by(&self, element_id: &str) -> &[String] {
+        self.referenced_by
+            .get(element_id)
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
 /// Types of cross-references that can be tracked.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ReferenceType {
     /// Function or method call
     FunctionCall,
@@ -438,13 +1538,14 @@ pub enum ReferenceType {
 }
 
 /// Context information for cross-references.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReferenceContext {
     /// Whether this is a definition or usage
     pub is_definition: bool,
     /// Scope where the reference occurs
     pub scope: String,
     /// Additional metadata
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
@@ -615,17 +1716,9 @@ impl ElementNamespace {
     pub fn new(simple_name: String, canonical_path: String, visibility: &Visibility) -> Self {
         let (is_public, visibility_scope) = match visibility {
             Visibility::Public => (true, VisibilityScope::Public),
-            Visibility::Restricted(scope) => {
-                let is_public = scope.contains("pub");
-                let scope_type = if scope.contains("crate") {
-                    VisibilityScope::Crate
-                } else if scope.contains("super") {
-                    VisibilityScope::Super
-                } else {
-                    VisibilityScope::Module(scope.clone())
-                };
-                (is_public, scope_type)
-            },
+            Visibility::Crate => (true, VisibilityScope::Crate),
+            Visibility::Super => (true, VisibilityScope::Super),
+            Visibility::Restricted(path) => (true, VisibilityScope::Module(path.clone())),
             Visibility::Private => (false, VisibilityScope::Private),
         };
         
@@ -634,6 +1727,7 @@ impl ElementNamespace {
             canonical_path,
             aliases: Vec::new(),
             import_paths: Vec::new(),
+            exported_paths: Vec::new(),
             is_public,
             visibility_scope,
         }
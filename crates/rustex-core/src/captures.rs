@@ -0,0 +1,90 @@
+//! Captured-variable analysis for closures and `async` blocks.
+//!
+//! Captures are found syntactically: a variable is captured when the body
+//! names it without binding it itself, and the enclosing function binds it
+//! as a parameter or local. Shadowing is not tracked, so a name bound both
+//! inside and outside the body counts as not captured. Names starting with
+//! an uppercase letter are taken to be constants or unit variants.
+
+use std::collections::HashSet;
+use syn::visit::Visit;
+
+/// Names bound by a function's parameters, including `self`, and by patterns in its body.
+pub fn local_bindings(sig: &syn::Signature, block: &syn::Block) -> HashSet<String> {
+    let mut collector = NameCollector::default();
+    for input in &sig.inputs {
+        match input {
+            syn::FnArg::Receiver(_) => {
+                collector.bound.insert("self".to_string());
+            }
+            syn::FnArg::Typed(arg) => collector.visit_pat(&arg.pat),
+        }
+    }
+    collector.visit_block(block);
+    collector.bound
+}
+
+/// Variables of the enclosing function that a closure's body uses, in order of first use.
+pub fn closure_captures(closure: &syn::ExprClosure, enclosing: &HashSet<String>) -> Vec<String> {
+    let mut collector = NameCollector::default();
+    for input in &closure.inputs {
+        collector.visit_pat(input);
+    }
+    collector.visit_expr(&closure.body);
+    collector.captures(enclosing)
+}
+
+/// Variables of the enclosing function that an `async` block uses, in order of first use.
+pub fn async_block_captures(block: &syn::ExprAsync, enclosing: &HashSet<String>) -> Vec<String> {
+    let mut collector = NameCollector::default();
+    collector.visit_block(&block.block);
+    collector.captures(enclosing)
+}
+
+/// Collects the names a piece of code binds and the single-segment paths it uses.
+#[derive(Default)]
+struct NameCollector {
+    bound: HashSet<String>,
+    used: Vec<String>,
+}
+
+impl NameCollector {
+    fn captures(self, enclosing: &HashSet<String>) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.used
+            .into_iter()
+            .filter(|name| !self.bound.contains(name) && enclosing.contains(name))
+            .filter(|name| seen.insert(name.clone()))
+            .collect()
+    }
+}
+
+impl<'ast> Visit<'ast> for NameCollector {
+    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+        let name = node.ident.to_string();
+        if !name.starts_with(char::is_uppercase) {
+            self.bound.insert(name);
+        }
+        syn::visit::visit_pat_ident(self, node);
+    }
+
+    fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+        if let Some(ident) = node.path.get_ident() {
+            let name = ident.to_string();
+            if !name.starts_with(char::is_uppercase) {
+                self.used.push(name);
+            }
+        }
+        syn::visit::visit_expr_path(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        // Arguments of macros like `println!` are usually expressions.
+        let parser = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+        if let Ok(args) = node.parse_body_with(parser) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Extraction of inline comments from raw source.
+//!
+//! `syn` drops regular comments, so they are recovered with a small lexer
+//! that only understands enough Rust to skip string and character literals.
+//! Doc comments (`///`, `//!`, `/** */`, `/*! */`) are left out since they
+//! are already captured as documentation.
+
+use crate::ast_data::{CodeElement, InlineComment};
+
+/// Collect the regular comments of `source`, in source order.
+pub fn extract_comments(source: &str) -> Vec<InlineComment> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut comments = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                let start = i + 2;
+                let end = chars[start..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map_or(chars.len(), |offset| start + offset);
+                let body: String = chars[start..end].iter().collect();
+                let is_doc =
+                    body.starts_with('!') || (body.starts_with('/') && !body.starts_with("//"));
+                if !is_doc {
+                    comments.push(InlineComment {
+                        text: body.trim().to_string(),
+                        line_start: line,
+                        line_end: line,
+                    });
+                }
+                i = end;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                let (end, lines) = skip_block_comment(&chars, i);
+                let body: String = chars[i + 2..end.saturating_sub(2).max(i + 2)]
+                    .iter()
+                    .collect();
+                let is_doc = body.starts_with('!')
+                    || (body.starts_with('*') && !body.starts_with("**") && body != "*");
+                if !is_doc {
+                    comments.push(InlineComment {
+                        text: body.trim().to_string(),
+                        line_start: line,
+                        line_end: line + lines,
+                    });
+                }
+                line += lines;
+                i = end;
+            }
+            '"' => {
+                let (end, lines) = skip_string(&chars, i + 1);
+                line += lines;
+                i = end;
+            }
+            'r' if starts_raw_string(&chars, i) => match skip_raw_string(&chars, i + 1) {
+                Some((end, lines)) => {
+                    line += lines;
+                    i = end;
+                }
+                None => i += 1,
+            },
+            '\'' => i = skip_char_literal(&chars, i),
+            _ => i += 1,
+        }
+    }
+
+    comments
+}
+
+/// Attach each comment to the innermost element whose span contains it.
+///
+/// Comments outside every element are attached to the element that directly
+/// follows them, when only other comments lie in between.
+pub fn attach_comments(elements: &mut [CodeElement], comments: Vec<InlineComment>) {
+    let comment_lines: Vec<(usize, usize)> = comments
+        .iter()
+        .map(|comment| (comment.line_start, comment.line_end))
+        .collect();
+    let only_comments_between = |from: usize, to: usize| {
+        (from..to).all(|line| {
+            comment_lines
+                .iter()
+                .any(|&(start, end)| start <= line && line <= end)
+        })
+    };
+
+    for comment in comments {
+        let enclosing = elements
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| {
+                element.location.line_start <= comment.line_start
+                    && comment.line_end <= element.location.line_end
+            })
+            .min_by_key(|(_, element)| element.location.line_end - element.location.line_start)
+            .map(|(index, _)| index);
+        let following = || {
+            elements
+                .iter()
+                .enumerate()
+                .filter(|(_, element)| element.location.line_start > comment.line_end)
+                .min_by_key(|(_, element)| element.location.line_start)
+                .filter(|(_, element)| {
+                    only_comments_between(comment.line_end + 1, element.location.line_start)
+                })
+                .map(|(index, _)| index)
+        };
+
+        if let Some(index) = enclosing.or_else(following) {
+            elements[index].inline_comments.push(comment);
+        }
+    }
+}
+
+/// Skip a possibly nested block comment starting at `start`.
+///
+/// Returns the index after the comment and the number of newlines in it.
+fn skip_block_comment(chars: &[char], start: usize) -> (usize, usize) {
+    let mut depth = 0;
+    let mut lines = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('/', Some('*')) => {
+                depth += 1;
+                i += 2;
+            }
+            ('*', Some('/')) => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return (i, lines);
+                }
+            }
+            ('\n', _) => {
+                lines += 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    (chars.len(), lines)
+}
+
+/// Skip a string literal body starting after its opening quote.
+fn skip_string(chars: &[char], start: usize) -> (usize, usize) {
+    let mut lines = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                if chars.get(i + 1) == Some(&'\n') {
+                    lines += 1;
+                }
+                i += 2;
+            }
+            '"' => return (i + 1, lines),
+            '\n' => {
+                lines += 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    (chars.len(), lines)
+}
+
+/// Skip a raw string whose `r` prefix ends just before `start`, if there is one.
+fn skip_raw_string(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    let hashes = chars[start..].iter().take_while(|&&c| c == '#').count();
+    if chars.get(start + hashes) != Some(&'"') {
+        return None;
+    }
+    let mut lines = 0;
+    let mut i = start + hashes + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '"' if chars[i + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|&&c| c == '#')
+                .count()
+                == hashes =>
+            {
+                return Some((i + 1 + hashes, lines));
+            }
+            '\n' => lines += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    Some((chars.len(), lines))
+}
+
+/// Skip a character literal at `start`, or just the quote of a lifetime.
+fn skip_char_literal(chars: &[char], start: usize) -> usize {
+    match (chars.get(start + 1), chars.get(start + 2)) {
+        // The escaped character may itself be a quote, as in `'\''`.
+        (Some('\\'), _) => chars
+            .get(start + 3..)
+            .and_then(|rest| rest.iter().position(|&c| c == '\''))
+            .map_or(chars.len(), |offset| start + 4 + offset),
+        (Some(_), Some('\'')) => start + 3,
+        _ => start + 1,
+    }
+}
+
+/// Whether the `r` at `index` is a raw string prefix, possibly after `b` or `c`.
+fn starts_raw_string(chars: &[char], index: usize) -> bool {
+    let is_ident_char = |i: usize| chars[i].is_alphanumeric() || chars[i] == '_';
+    match index.checked_sub(1) {
+        None => true,
+        Some(prev) if chars[prev] == 'b' || chars[prev] == 'c' => {
+            prev == 0 || !is_ident_char(prev - 1)
+        }
+        Some(prev) => !is_ident_char(prev),
+    }
+}
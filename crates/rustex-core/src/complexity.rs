@@ -6,6 +6,7 @@
 //! - Halstead complexity
 //! - Nesting depth analysis
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{
@@ -14,7 +15,7 @@ use syn::{
 };
 
 /// Comprehensive complexity metrics for a code element.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ComplexityMetrics {
     /// Cyclomatic complexity (traditional branch counting)
     pub cyclomatic: u32,
@@ -30,10 +31,16 @@ pub struct ComplexityMetrics {
     pub parameter_count: u32,
     /// Return points count
     pub return_count: u32,
+    /// Number of closures in the body, including nested ones
+    #[serde(default)]
+    pub closure_count: u32,
+    /// Number of `async` blocks in the body, including nested ones
+    #[serde(default)]
+    pub async_block_count: u32,
 }
 
 /// Halstead complexity metrics based on operators and operands.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct HalsteadMetrics {
     /// Number of distinct operators
     pub n1: u32,
@@ -106,6 +113,10 @@ pub struct ComplexityCalculator {
     lines_of_code: u32,
     /// Parameter count
     parameter_count: u32,
+    /// Closure counter
+    closure_count: u32,
+    /// Async block counter
+    async_block_count: u32,
 }
 
 impl ComplexityCalculator {
@@ -122,6 +133,8 @@ impl ComplexityCalculator {
             halstead_operands: HashMap::new(),
             lines_of_code: 0,
             parameter_count: 0,
+            closure_count: 0,
+            async_block_count: 0,
         }
     }
 
@@ -175,6 +188,36 @@ impl ComplexityCalculator {
         calc.finish()
     }
 
+    /// Calculate complexity for a closure, on its own.
+    pub fn calculate_closure_complexity(closure: &syn::ExprClosure) -> ComplexityMetrics {
+        let mut calc = Self::new();
+
+        calc.parameter_count = closure.inputs.len() as u32;
+        calc.lines_of_code = 1;
+
+        // Bodies without braces still form a scope.
+        match &*closure.body {
+            Expr::Block(body) => calc.visit_expr_block(body),
+            body => {
+                calc.enter_scope();
+                calc.visit_expr(body);
+                calc.exit_scope();
+            }
+        }
+
+        calc.finish()
+    }
+
+    /// Calculate complexity for an `async` block, on its own.
+    pub fn calculate_async_block_complexity(block: &syn::ExprAsync) -> ComplexityMetrics {
+        let mut calc = Self::new();
+
+        calc.lines_of_code = 1;
+        calc.visit_block(&block.block);
+
+        calc.finish()
+    }
+
     /// Calculate basic structural complexity for non-function items.
     pub fn calculate_structural_complexity(item: &syn::Item) -> ComplexityMetrics {
         let mut metrics = ComplexityMetrics::default();
@@ -261,6 +304,8 @@ impl ComplexityCalculator {
             lines_of_code: self.lines_of_code,
             parameter_count: self.parameter_count,
             return_count: self.return_count,
+            closure_count: self.closure_count,
+            async_block_count: self.async_block_count,
         }
     }
 
@@ -444,6 +489,16 @@ impl<'ast> Visit<'ast> for ComplexityCalculator {
                 self.record_operator("method_call");
             }
 
+            Expr::Closure(_) => {
+                self.closure_count += 1;
+                self.record_operator("closure");
+            }
+
+            Expr::Async(_) => {
+                self.async_block_count += 1;
+                self.record_operator("async");
+            }
+
             // Literals as operands
             Expr::Lit(expr_lit) => {
                 self.record_operand(&format!("{:?}", expr_lit.lit));
@@ -562,6 +617,28 @@ mod tests {
         assert!(metrics.cyclomatic >= 3); // Three match arms
     }
 
+    #[test]
+    fn test_closure_and_async_block_complexity() {
+        let func: syn::ItemFn = parse_quote! {
+            fn spawn_all(items: Vec<i32>) {
+                let positive = |x: &i32| if *x > 0 { true } else { false };
+                let task = async move {
+                    items.iter().filter(|x| positive(x)).count()
+                };
+            }
+        };
+
+        let metrics = ComplexityCalculator::calculate_function_complexity(&func);
+        assert_eq!(metrics.closure_count, 2);
+        assert_eq!(metrics.async_block_count, 1);
+
+        let closure: syn::ExprClosure = parse_quote!(|x: &i32| if *x > 0 { true } else { false });
+        let metrics = ComplexityCalculator::calculate_closure_complexity(&closure);
+        assert_eq!(metrics.parameter_count, 1);
+        assert_eq!(metrics.cyclomatic, 2);
+        assert_eq!(metrics.closure_count, 0);
+    }
+
     #[test]
     fn test_struct_complexity() {
         let item: syn::Item = parse_quote! {
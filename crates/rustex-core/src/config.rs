@@ -1,9 +1,15 @@
 //! Configuration structures for AST extraction.
 
+use crate::ast_data::{ElementType, Visibility};
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Estimated bytes per token, used to convert `max_output_tokens` to bytes.
+pub const BYTES_PER_TOKEN: usize = 4;
 
 /// Configuration for AST extraction.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,21 +26,128 @@ pub struct ExtractorConfig {
     /// Maximum file size to process (in bytes)
     #[serde(default = "default_max_file_size")]
     pub max_file_size: usize,
+    /// Extract only item signatures and docs from files over `max_file_size`,
+    /// skipping bodies and complexity, instead of skipping those files
+    #[serde(default)]
+    pub partial_oversize_files: bool,
+    /// Fail the extraction when any file fails to extract or is only partly
+    /// extracted, instead of reporting it in `extraction_report`
+    #[serde(default)]
+    pub strict: bool,
     /// Output format for extracted data
     #[serde(default)]
     pub output_format: OutputFormat,
     /// File filtering configuration
     #[serde(default)]
     pub filters: FilterConfig,
-    /// Enabled plugins
+    /// Enabled plugins and the settings of plugins
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    /// Disabled plugins, including plugins enabled by default; a plugin in
+    /// neither list keeps its default
+    #[serde(default)]
+    pub disabled_plugins: Vec<String>,
+    /// Workspace member crates to extract (empty extracts all members)
+    #[serde(default)]
+    pub crates: Vec<String>,
+    /// Number of worker threads for file parsing (0 uses all available cores)
+    #[serde(default)]
+    pub parallel_workers: usize,
+    /// Expand macros and include the items they generate
+    #[serde(default)]
+    pub expand_macros: bool,
+    /// Skip files ignored by `.gitignore` and `.ignore` files during discovery
+    #[serde(default = "default_respect_gitignore")]
+    pub respect_gitignore: bool,
+    /// Follow symbolic links during discovery; files reachable through several
+    /// paths are extracted once and symlink cycles are skipped
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// Features to simulate when evaluating `#[cfg(...)]` predicates
+    ///
+    /// When set, items whose predicates are false for this feature set are
+    /// skipped. `None` extracts every item regardless of its predicates.
+    #[serde(default)]
+    pub cfg_features: Option<Vec<String>>,
+    /// Extract the valid items of files with syntax errors instead of failing the file
+    #[serde(default = "default_recover_parse_errors")]
+    pub recover_parse_errors: bool,
+    /// Decode files that aren't valid UTF-8 lossily instead of failing them
+    #[serde(default = "default_encoding_fallback")]
+    pub encoding_fallback: bool,
+    /// Attach regular `//` and `/* */` comments to the elements containing them
+    #[serde(default)]
+    pub include_inline_comments: bool,
+    /// Extract closures and `async` blocks inside functions as elements of their own
+    #[serde(default)]
+    pub extract_closures: bool,
+    /// Maximum size of the rendered output in bytes
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+    /// Maximum size of the rendered output in estimated tokens, at four bytes per token
+    #[serde(default)]
+    pub max_output_tokens: Option<usize>,
+    /// How the output is reduced when it exceeds `max_output_bytes` or `max_output_tokens`
+    #[serde(default)]
+    pub truncation_policy: TruncationPolicy,
+    /// Sort files by path and elements by location, and take the extraction
+    /// time from `SOURCE_DATE_EPOCH` when it is set, so extracting the same
+    /// sources always produces the same output
+    #[serde(default = "default_deterministic_output")]
+    pub deterministic_output: bool,
+    /// Diagrams of the `Mermaid` and `Dot` output formats, all of them when
+    /// empty; Markdown output embeds these diagrams as Mermaid too
+    #[serde(default)]
+    pub diagrams: Vec<Diagram>,
+    /// Table rendered by the `Csv` and `Parquet` output formats
+    #[serde(default)]
+    pub csv_table: CsvTable,
+    /// Tera template rendered by the `Template` output format
+    #[serde(default)]
+    pub template: Option<PathBuf>,
+    /// Layout of the `Markdown` output format
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
+    /// Embedding vectors of the chunks of the `Rag` and `RagJsonl` formats
+    #[serde(default)]
+    pub embeddings: EmbeddingConfig,
+    /// Generated summaries of the chunks of the `Rag` and `RagJsonl` formats
+    #[serde(default)]
+    pub summaries: SummaryConfig,
+    /// Incremental output of the `Rag` and `RagJsonl` formats
     #[serde(default)]
-    pub plugins: Vec<String>,
+    pub rag: RagOutputConfig,
+    /// Layout of the `HfDataset` output format
+    #[serde(default)]
+    pub hf_dataset: HfDatasetConfig,
+    /// System prompts of the `OpenAiFineTune` and `AnthropicFineTune` formats
+    #[serde(default)]
+    pub fine_tuning: FineTuningConfig,
+    /// Quality thresholds checked by `rustex check`
+    #[serde(default)]
+    pub thresholds: ThresholdConfig,
 }
 
 fn default_include_docs() -> bool {
     true
 }
 
+fn default_respect_gitignore() -> bool {
+    true
+}
+
+fn default_recover_parse_errors() -> bool {
+    true
+}
+
+fn default_deterministic_output() -> bool {
+    true
+}
+
+fn default_encoding_fallback() -> bool {
+    true
+}
+
 fn default_max_file_size() -> usize {
     10 * 1024 * 1024 // 10MB
 }
@@ -53,6 +166,757 @@ pub enum OutputFormat {
     GraphQL,
     /// RAG-optimized format
     Rag,
+    /// JSON Lines: a project header followed by one file per line
+    Jsonl,
+    /// JSON Lines with one RAG chunk per line
+    RagJsonl,
+    /// JSON array of RAG chunks as LangChain documents
+    LangChain,
+    /// JSON array of RAG chunks as LlamaIndex text nodes
+    LlamaIndex,
+    /// Hugging Face dataset of training examples: JSON Lines shards of the
+    /// train, validation and test splits and a dataset card (see
+    /// `hf_dataset`)
+    HfDataset,
+    /// OpenAI chat fine-tuning JSON Lines: one conversation per training
+    /// example (see `fine_tuning`)
+    OpenAiFineTune,
+    /// Anthropic message fine-tuning JSON Lines: a system prompt and messages
+    /// per training example (see `fine_tuning`)
+    AnthropicFineTune,
+    /// Mermaid diagrams in fenced Markdown blocks
+    Mermaid,
+    /// Graphviz DOT graphs
+    Dot,
+    /// CSV table of elements, files or complexity metrics (see `csv_table`)
+    Csv,
+    /// Parquet table of elements, files or complexity metrics (see
+    /// `csv_table`); needs the `parquet` feature
+    Parquet,
+    /// RDF knowledge graph in Turtle
+    Turtle,
+    /// RDF knowledge graph in N-Triples
+    NTriples,
+    /// Cypher statements loading the knowledge graph into Neo4j
+    Cypher,
+    /// Protocol Buffers binary format, described by `rustex.proto`
+    Protobuf,
+    /// SCIP code intelligence index, as read by Sourcegraph
+    Scip,
+    /// Tags file for editors, in the format of universal-ctags
+    Ctags,
+    /// Tags file for Emacs, in the `TAGS` format of etags
+    Etags,
+    /// One-page Markdown overview of the project for language model prompts
+    Overview,
+    /// SARIF log of the diagnostics of plugins, for code scanning services
+    Sarif,
+    /// Output rendered through a Tera template (see `template`)
+    Template,
+}
+
+/// A diagram of the structure of a project, rendered by the `Mermaid` and
+/// `Dot` output formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Diagram {
+    /// The workspace crates and their dependencies on each other, or the
+    /// crate and its direct dependencies
+    CrateDependencies,
+    /// Modules nested in their parent modules
+    ModuleHierarchy,
+    /// Modules and the project modules they import items from
+    ModuleImports,
+    /// Structs, enums and traits, with the traits they implement and the
+    /// types they use
+    StructRelationships,
+    /// Functions and the functions they call
+    CallGraph,
+}
+
+impl Diagram {
+    /// Every diagram, in the order they are rendered.
+    pub const ALL: [Diagram; 5] = [
+        Diagram::CrateDependencies,
+        Diagram::ModuleHierarchy,
+        Diagram::ModuleImports,
+        Diagram::StructRelationships,
+        Diagram::CallGraph,
+    ];
+}
+
+/// A table of the `Csv` and `Parquet` output formats, with one row per item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvTable {
+    /// One row per element, with its location, signature and complexity
+    #[default]
+    Elements,
+    /// One row per file, with its file metrics
+    Files,
+    /// One row per element with complexity metrics, such as functions
+    Metrics,
+}
+
+impl CsvTable {
+    /// Every table, in the order they are written.
+    pub const ALL: [CsvTable; 3] = [CsvTable::Elements, CsvTable::Files, CsvTable::Metrics];
+
+    /// Name of the table, e.g. `elements`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvTable::Elements => "elements",
+            CsvTable::Files => "files",
+            CsvTable::Metrics => "metrics",
+        }
+    }
+}
+
+/// A section of the `Markdown` output format, after its title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkdownSection {
+    /// Project metrics: files, lines, element counts and average complexity
+    Metrics,
+    /// Metrics, messages and metadata of the plugins run on the project
+    PluginResults,
+    /// The `TODO`, `FIXME` and `HACK` comments and `#[allow(dead_code)]`
+    /// attributes the `tech-debt` plugin collected
+    TechDebt,
+    /// The diagrams listed in `diagrams`, as Mermaid
+    Diagrams,
+    /// Trait implementations and where they are
+    TraitImplementations,
+    /// The elements of each file, with their docs and signatures
+    Files,
+}
+
+impl MarkdownSection {
+    /// Every section, in the order they are rendered.
+    pub const ALL: [MarkdownSection; 6] = [
+        MarkdownSection::Metrics,
+        MarkdownSection::PluginResults,
+        MarkdownSection::TechDebt,
+        MarkdownSection::Diagrams,
+        MarkdownSection::TraitImplementations,
+        MarkdownSection::Files,
+    ];
+}
+
+/// Layout of the `Markdown` output format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkdownConfig {
+    /// Sections to render, all of them when empty
+    #[serde(default)]
+    pub sections: Vec<MarkdownSection>,
+    /// Sections to leave out, even when listed in `sections`
+    #[serde(default)]
+    pub exclude_sections: Vec<MarkdownSection>,
+    /// Level of the title heading, from 1 to 3; sections, files and elements
+    /// get the levels below it, so the output can be part of a larger document
+    #[serde(default = "default_heading_level")]
+    pub heading_level: usize,
+    /// Start with a table of contents linking to the sections, files and
+    /// elements
+    #[serde(default = "default_table_of_contents")]
+    pub table_of_contents: bool,
+    /// Link elements to the elements they use and are used by, and trait
+    /// implementations to their type and trait
+    #[serde(default = "default_cross_links")]
+    pub cross_links: bool,
+    /// Base URL of the source files, such as
+    /// `https://github.com/owner/repo/blob/main`; locations link to their
+    /// lines below it
+    #[serde(default)]
+    pub repo_url: Option<String>,
+}
+
+fn default_heading_level() -> usize {
+    1
+}
+
+fn default_table_of_contents() -> bool {
+    true
+}
+
+fn default_cross_links() -> bool {
+    true
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            sections: Vec::new(),
+            exclude_sections: Vec::new(),
+            heading_level: default_heading_level(),
+            table_of_contents: true,
+            cross_links: true,
+            repo_url: None,
+        }
+    }
+}
+
+impl MarkdownConfig {
+    /// Whether `section` is rendered.
+    pub fn includes(&self, section: MarkdownSection) -> bool {
+        (self.sections.is_empty() || self.sections.contains(&section))
+            && !self.exclude_sections.contains(&section)
+    }
+
+    /// Set the settings `other` changes from the defaults, keeping the others.
+    pub fn merge_with(&mut self, other: MarkdownConfig) {
+        let defaults = MarkdownConfig::default();
+        if !other.sections.is_empty() {
+            self.sections = other.sections;
+        }
+        if !other.exclude_sections.is_empty() {
+            self.exclude_sections = other.exclude_sections;
+        }
+        if other.heading_level != defaults.heading_level {
+            self.heading_level = other.heading_level;
+        }
+        if other.table_of_contents != defaults.table_of_contents {
+            self.table_of_contents = other.table_of_contents;
+        }
+        if other.cross_links != defaults.cross_links {
+            self.cross_links = other.cross_links;
+        }
+        if other.repo_url.is_some() {
+            self.repo_url = other.repo_url;
+        }
+    }
+}
+
+/// Embedding vectors added to the chunks of the `Rag` and `RagJsonl` output
+/// formats.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingConfig {
+    /// Embed the chunks
+    #[serde(default)]
+    pub enabled: bool,
+    /// Base URL of an OpenAI-compatible API, whose `/embeddings` endpoint
+    /// embeds the chunks
+    #[serde(default = "default_embedding_url")]
+    pub url: String,
+    /// Embedding model requested from the API
+    #[serde(default = "default_embedding_model")]
+    pub model: String,
+    /// Environment variable holding the API key, sent as a bearer token
+    #[serde(default = "default_embedding_api_key_env")]
+    pub api_key_env: String,
+    /// Directory of a local BERT-style model, with `config.json`,
+    /// `tokenizer.json` and `model.safetensors`, to embed with instead of
+    /// the API
+    #[serde(default)]
+    pub model_dir: Option<PathBuf>,
+    /// Chunks embedded per request
+    #[serde(default = "default_embedding_batch_size")]
+    pub batch_size: usize,
+    /// Most requests per minute, unlimited when unset
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// Retries of requests that were rate limited or failed on the way
+    #[serde(default = "default_embedding_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_embedding_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_embedding_model() -> String {
+    "text-embedding-3-small".to_string()
+}
+
+fn default_embedding_api_key_env() -> String {
+    "OPENAI_API_KEY".to_string()
+}
+
+fn default_embedding_batch_size() -> usize {
+    64
+}
+
+fn default_embedding_max_retries() -> u32 {
+    3
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: default_embedding_url(),
+            model: default_embedding_model(),
+            api_key_env: default_embedding_api_key_env(),
+            model_dir: None,
+            batch_size: default_embedding_batch_size(),
+            requests_per_minute: None,
+            max_retries: default_embedding_max_retries(),
+        }
+    }
+}
+
+impl EmbeddingConfig {
+    /// Set the settings `other` changes from the defaults, keeping the others.
+    pub fn merge_with(&mut self, other: EmbeddingConfig) {
+        let defaults = EmbeddingConfig::default();
+        if other.enabled != defaults.enabled {
+            self.enabled = other.enabled;
+        }
+        if other.url != defaults.url {
+            self.url = other.url;
+        }
+        if other.model != defaults.model {
+            self.model = other.model;
+        }
+        if other.api_key_env != defaults.api_key_env {
+            self.api_key_env = other.api_key_env;
+        }
+        if other.model_dir.is_some() {
+            self.model_dir = other.model_dir;
+        }
+        if other.batch_size != defaults.batch_size {
+            self.batch_size = other.batch_size;
+        }
+        if other.requests_per_minute.is_some() {
+            self.requests_per_minute = other.requests_per_minute;
+        }
+        if other.max_retries != defaults.max_retries {
+            self.max_retries = other.max_retries;
+        }
+    }
+}
+
+/// One-paragraph summaries, written by a language model, of the complex
+/// elements without docs in the chunks of the `Rag` and `RagJsonl` output
+/// formats.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummaryConfig {
+    /// Summarize the chunks
+    #[serde(default)]
+    pub enabled: bool,
+    /// Base URL of an OpenAI-compatible API, whose `/chat/completions`
+    /// endpoint writes the summaries
+    #[serde(default = "default_embedding_url")]
+    pub url: String,
+    /// Chat model requested from the API
+    #[serde(default = "default_summary_model")]
+    pub model: String,
+    /// Environment variable holding the API key, sent as a bearer token
+    #[serde(default = "default_embedding_api_key_env")]
+    pub api_key_env: String,
+    /// Least cyclomatic complexity of the elements to summarize
+    #[serde(default = "default_summary_min_complexity")]
+    pub min_complexity: u32,
+}
+
+fn default_summary_model() -> String {
+    "gpt-4o-mini".to_string()
+}
+
+fn default_summary_min_complexity() -> u32 {
+    5
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: default_embedding_url(),
+            model: default_summary_model(),
+            api_key_env: default_embedding_api_key_env(),
+            min_complexity: default_summary_min_complexity(),
+        }
+    }
+}
+
+impl SummaryConfig {
+    /// Set the settings `other` changes from the defaults, keeping the others.
+    pub fn merge_with(&mut self, other: SummaryConfig) {
+        let defaults = SummaryConfig::default();
+        if other.enabled != defaults.enabled {
+            self.enabled = other.enabled;
+        }
+        if other.url != defaults.url {
+            self.url = other.url;
+        }
+        if other.model != defaults.model {
+            self.model = other.model;
+        }
+        if other.api_key_env != defaults.api_key_env {
+            self.api_key_env = other.api_key_env;
+        }
+        if other.min_complexity != defaults.min_complexity {
+            self.min_complexity = other.min_complexity;
+        }
+    }
+}
+
+/// Chunks of the RAG output formats: how elements are split into chunks
+/// and the context added to them, the lowest quality to output, how similar
+/// near-duplicates are, and manifests of the `Rag` and `RagJsonl` formats,
+/// to only output the chunks that changed since an earlier run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RagOutputConfig {
+    /// How elements are split into chunks
+    #[serde(default)]
+    pub chunk_strategy: RagChunkStrategy,
+    /// Overlap between adjacent chunks of a split element, in tokens
+    #[serde(default = "default_chunk_overlap_tokens")]
+    pub chunk_overlap_tokens: usize,
+    /// Lines of source before and after each chunk to add to its context
+    #[serde(default)]
+    pub context_radius_lines: usize,
+    /// Add the headers of the impl, trait or modules enclosing each chunk's
+    /// element to its context
+    #[serde(default = "default_include_parent_signature")]
+    pub include_parent_signature: bool,
+    /// Manifest of an earlier run; only the chunks added or changed since
+    /// are output, along with the IDs of the removed ones
+    #[serde(default)]
+    pub since: Option<PathBuf>,
+    /// Where to write the manifest of this run's chunks
+    #[serde(default)]
+    pub manifest: Option<PathBuf>,
+    /// Lowest quality score, from 0 to 1, of the chunks to output and make
+    /// training examples of
+    #[serde(default)]
+    pub min_quality: f64,
+    /// Similarity from 0 to 1 above which chunks are left out as
+    /// near-duplicates of earlier ones; duplicates are kept when unset
+    #[serde(default)]
+    pub dedup_threshold: Option<f64>,
+}
+
+fn default_chunk_overlap_tokens() -> usize {
+    50
+}
+
+fn default_include_parent_signature() -> bool {
+    true
+}
+
+impl Default for RagOutputConfig {
+    fn default() -> Self {
+        Self {
+            chunk_strategy: RagChunkStrategy::default(),
+            chunk_overlap_tokens: default_chunk_overlap_tokens(),
+            context_radius_lines: 0,
+            include_parent_signature: default_include_parent_signature(),
+            since: None,
+            manifest: None,
+            min_quality: 0.0,
+            dedup_threshold: None,
+        }
+    }
+}
+
+impl RagOutputConfig {
+    /// Set the settings `other` changes from the defaults, keeping the others.
+    pub fn merge_with(&mut self, other: RagOutputConfig) {
+        let defaults = RagOutputConfig::default();
+        if other.chunk_strategy != defaults.chunk_strategy {
+            self.chunk_strategy = other.chunk_strategy;
+        }
+        if other.chunk_overlap_tokens != defaults.chunk_overlap_tokens {
+            self.chunk_overlap_tokens = other.chunk_overlap_tokens;
+        }
+        if other.context_radius_lines != defaults.context_radius_lines {
+            self.context_radius_lines = other.context_radius_lines;
+        }
+        if other.include_parent_signature != defaults.include_parent_signature {
+            self.include_parent_signature = other.include_parent_signature;
+        }
+        if other.since.is_some() {
+            self.since = other.since;
+        }
+        if other.manifest.is_some() {
+            self.manifest = other.manifest;
+        }
+        if other.min_quality != 0.0 {
+            self.min_quality = other.min_quality;
+        }
+        if other.dedup_threshold.is_some() {
+            self.dedup_threshold = other.dedup_threshold;
+        }
+    }
+}
+
+/// How the RAG output formats split elements into chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RagChunkStrategy {
+    /// One chunk per element, of its docs and signature
+    #[default]
+    PerElement,
+    /// Chunks of source of about the target size, each starting with the end
+    /// of the one before
+    TokenBudget,
+    /// Overlapping windows over large functions, each starting with the
+    /// function's signature
+    SlidingWindow,
+    /// Chunks of whole statements and items
+    Semantic,
+}
+
+/// Layout of the Hugging Face dataset of training examples written by the
+/// `HfDataset` output format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HfDatasetConfig {
+    /// Share of the examples in the validation split
+    #[serde(default = "default_validation_split")]
+    pub validation_split: f64,
+    /// Share of the examples in the test split
+    #[serde(default = "default_test_split")]
+    pub test_split: f64,
+    /// Most examples per data file
+    #[serde(default = "default_shard_size")]
+    pub shard_size: usize,
+    /// License of the dataset, as an SPDX expression; the project's license
+    /// from its `Cargo.toml` when unset
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+fn default_validation_split() -> f64 {
+    0.1
+}
+
+fn default_test_split() -> f64 {
+    0.1
+}
+
+fn default_shard_size() -> usize {
+    10_000
+}
+
+impl Default for HfDatasetConfig {
+    fn default() -> Self {
+        Self {
+            validation_split: default_validation_split(),
+            test_split: default_test_split(),
+            shard_size: default_shard_size(),
+            license: None,
+        }
+    }
+}
+
+impl HfDatasetConfig {
+    /// Set the settings `other` changes from the defaults, keeping the others.
+    pub fn merge_with(&mut self, other: HfDatasetConfig) {
+        let defaults = HfDatasetConfig::default();
+        if other.validation_split != defaults.validation_split {
+            self.validation_split = other.validation_split;
+        }
+        if other.test_split != defaults.test_split {
+            self.test_split = other.test_split;
+        }
+        if other.shard_size != defaults.shard_size {
+            self.shard_size = other.shard_size;
+        }
+        if other.license.is_some() {
+            self.license = other.license;
+        }
+    }
+}
+
+/// Task types of training examples, as keys of `fine_tuning.system_prompts`.
+pub const FINE_TUNING_TASKS: &[&str] = &[
+    "code_generation",
+    "code_explanation",
+    "code_completion",
+    "code_refactoring",
+    "bug_finding",
+    "code_summarization",
+    "api_usage",
+    "pattern_recognition",
+    "documentation_generation",
+    "function_naming",
+    "test_generation",
+];
+
+/// System prompts of the training examples written by the `OpenAiFineTune`
+/// and `AnthropicFineTune` output formats.
+///
+/// Prompts are Tera templates, rendered with the `project` name and
+/// `version`, and the `task`, `difficulty` and `concepts` of the example.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FineTuningConfig {
+    /// System prompt of the examples of task types without their own; an
+    /// empty prompt leaves the system prompt out
+    #[serde(default = "default_system_prompt")]
+    pub system_prompt: String,
+    /// System prompts by task type, such as `api_usage` (see
+    /// [`FINE_TUNING_TASKS`])
+    #[serde(default)]
+    pub system_prompts: BTreeMap<String, String>,
+}
+
+fn default_system_prompt() -> String {
+    "You are an expert Rust programmer who knows the {{ project }} crate.".to_string()
+}
+
+impl Default for FineTuningConfig {
+    fn default() -> Self {
+        Self {
+            system_prompt: default_system_prompt(),
+            system_prompts: BTreeMap::new(),
+        }
+    }
+}
+
+impl FineTuningConfig {
+    /// Set the settings `other` changes from the defaults, keeping the others.
+    /// Task types keep their prompts unless `other` sets them.
+    pub fn merge_with(&mut self, other: FineTuningConfig) {
+        if other.system_prompt != default_system_prompt() {
+            self.system_prompt = other.system_prompt;
+        }
+        self.system_prompts.extend(other.system_prompts);
+    }
+
+    /// The system prompt template of the examples of `task`.
+    pub fn system_prompt_for(&self, task: &str) -> &str {
+        self.system_prompts.get(task).unwrap_or(&self.system_prompt)
+    }
+}
+
+/// How extracted data is reduced to fit an output budget.
+///
+/// Each policy ends by dropping whole files, largest first, until the
+/// output fits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationPolicy {
+    /// Reduce every element to its signature and doc summary, then drop
+    /// non-public elements
+    #[default]
+    DropBodiesFirst,
+    /// Drop non-public elements, then reduce the rest to their signature
+    /// and doc summary
+    DropPrivateFirst,
+    /// Shrink each file in proportion to its size, reducing and then
+    /// dropping its last elements first
+    PerFileProportional,
+}
+
+/// Quality thresholds checked by `rustex check`; unset thresholds aren't checked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdConfig {
+    /// Maximum cyclomatic complexity of a function
+    #[serde(default)]
+    pub max_cyclomatic: Option<u32>,
+    /// Maximum cognitive complexity of a function
+    #[serde(default)]
+    pub max_cognitive: Option<u32>,
+    /// Maximum number of lines of a function
+    #[serde(default)]
+    pub max_function_lines: Option<usize>,
+    /// Maximum lines of code of a file, excluding comments and blank lines
+    #[serde(default)]
+    pub max_file_lines: Option<usize>,
+    /// Minimum share of public items with doc comments, in percent
+    #[serde(default)]
+    pub min_doc_coverage: Option<f64>,
+    /// Maximum number of `unsafe` blocks, functions, impls and traits
+    #[serde(default)]
+    pub max_unsafe: Option<usize>,
+    /// Maximum number of vulnerabilities in dependencies, as found by the
+    /// `rustsec-audit` plugin
+    #[serde(default)]
+    pub max_vulnerabilities: Option<usize>,
+}
+
+impl ThresholdConfig {
+    /// Whether no threshold is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Set the thresholds `other` sets, keeping the others.
+    pub fn merge_with(&mut self, other: ThresholdConfig) {
+        self.max_cyclomatic = other.max_cyclomatic.or(self.max_cyclomatic);
+        self.max_cognitive = other.max_cognitive.or(self.max_cognitive);
+        self.max_function_lines = other.max_function_lines.or(self.max_function_lines);
+        self.max_file_lines = other.max_file_lines.or(self.max_file_lines);
+        self.min_doc_coverage = other.min_doc_coverage.or(self.min_doc_coverage);
+        self.max_unsafe = other.max_unsafe.or(self.max_unsafe);
+        self.max_vulnerabilities = other.max_vulnerabilities.or(self.max_vulnerabilities);
+    }
+}
+
+/// Enabled plugins and the settings of plugins.
+///
+/// Written as the list of enabled plugins, `plugins = ["doc-enhancer"]`, or
+/// as a table with the list as `enabled` and the settings of each plugin in
+/// `[plugins.config.<name>]`. It's written back as a list while no plugin
+/// has settings.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(from = "PluginsSetting")]
+pub struct PluginsConfig {
+    /// Enabled plugins
+    pub enabled: Vec<String>,
+    /// Settings of plugins by plugin name, passed to the plugins as JSON
+    pub config: BTreeMap<String, serde_json::Value>,
+}
+
+/// The forms `plugins` is written in.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PluginsSetting {
+    Enabled(Vec<String>),
+    Table {
+        #[serde(default)]
+        enabled: Vec<String>,
+        #[serde(default)]
+        config: BTreeMap<String, serde_json::Value>,
+    },
+}
+
+impl From<PluginsSetting> for PluginsConfig {
+    fn from(setting: PluginsSetting) -> Self {
+        match setting {
+            PluginsSetting::Enabled(enabled) => Self {
+                enabled,
+                config: BTreeMap::new(),
+            },
+            PluginsSetting::Table { enabled, config } => Self { enabled, config },
+        }
+    }
+}
+
+impl From<Vec<String>> for PluginsConfig {
+    fn from(enabled: Vec<String>) -> Self {
+        PluginsSetting::Enabled(enabled).into()
+    }
+}
+
+impl Serialize for PluginsConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        if self.config.is_empty() {
+            return self.enabled.serialize(serializer);
+        }
+        let mut table = serializer.serialize_struct("PluginsConfig", 2)?;
+        table.serialize_field("enabled", &self.enabled)?;
+        table.serialize_field("config", &self.config)?;
+        table.end()
+    }
+}
+
+impl PluginsConfig {
+    /// Whether no plugin is enabled or has settings.
+    pub fn is_empty(&self) -> bool {
+        self.enabled.is_empty() && self.config.is_empty()
+    }
+
+    /// Take the plugins `other` enables, if any, and its settings of each
+    /// plugin.
+    pub fn merge_with(&mut self, other: PluginsConfig) {
+        if !other.enabled.is_empty() {
+            self.enabled = other.enabled;
+        }
+        self.config.extend(other.config);
+    }
 }
 
 /// File filtering configuration.
@@ -64,6 +928,66 @@ pub struct FilterConfig {
     /// Glob patterns for files to exclude
     #[serde(default = "default_exclude_patterns")]
     pub exclude: Vec<String>,
+    /// Only extract elements with these visibilities, written as in Rust
+    /// (`pub`, `pub(crate)`, `pub(super)`, `pub(in path)`, `pub(in)` for any
+    /// path) or `private`. Takes precedence over `include_private` when set.
+    #[serde(default)]
+    pub visibility: Vec<String>,
+    /// Skip Cargo build scripts, which are otherwise extracted for every
+    /// package even when the include patterns don't match them
+    #[serde(default)]
+    pub exclude_build_scripts: bool,
+    /// Only extract elements of these types, as snake_case names such as
+    /// `function`, `trait` or `type_alias` (empty extracts all types)
+    #[serde(default)]
+    pub element_types: Vec<String>,
+    /// Only extract elements whose name matches one of these regular
+    /// expressions (empty extracts all names)
+    #[serde(default)]
+    pub name_patterns: Vec<String>,
+    /// Skip elements whose name matches one of these regular expressions
+    #[serde(default)]
+    pub exclude_names: Vec<String>,
+    /// Settings for the files matching a path pattern, such as `tests/**`,
+    /// which take precedence over the settings above for those files
+    #[serde(default)]
+    pub overrides: BTreeMap<String, PathOverride>,
+}
+
+/// Settings overridden for the files matching a pattern in `filters.overrides`.
+///
+/// Unset fields keep the value of the enclosing configuration. When several
+/// patterns match a file, longer patterns take precedence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathOverride {
+    /// Extract matching files even when the include or exclude patterns
+    /// skip them (`true`), or skip them regardless (`false`)
+    #[serde(default)]
+    pub extract: Option<bool>,
+    /// Include documentation comments
+    #[serde(default)]
+    pub include_docs: Option<bool>,
+    /// Include private items
+    #[serde(default)]
+    pub include_private: Option<bool>,
+    /// Only extract elements with these visibilities, as in `filters.visibility`
+    #[serde(default)]
+    pub visibility: Option<Vec<String>>,
+}
+
+impl PathOverride {
+    /// Apply the settings this override sets to `config`.
+    pub fn apply(&self, config: &mut ExtractorConfig) {
+        if let Some(include_docs) = self.include_docs {
+            config.include_docs = include_docs;
+        }
+        if let Some(include_private) = self.include_private {
+            config.include_private = include_private;
+        }
+        if let Some(visibility) = &self.visibility {
+            config.filters.visibility = visibility.clone();
+        }
+    }
 }
 
 fn default_include_patterns() -> Vec<String> {
@@ -79,6 +1003,12 @@ impl Default for FilterConfig {
         Self {
             include: default_include_patterns(),
             exclude: default_exclude_patterns(),
+            visibility: Vec::new(),
+            exclude_build_scripts: false,
+            element_types: Vec::new(),
+            name_patterns: Vec::new(),
+            exclude_names: Vec::new(),
+            overrides: BTreeMap::new(),
         }
     }
 }
@@ -90,12 +1020,45 @@ impl Default for ExtractorConfig {
             include_private: false,
             parse_dependencies: false,
             max_file_size: 10 * 1024 * 1024, // 10MB
+            partial_oversize_files: false,
+            strict: false,
             output_format: OutputFormat::Json,
             filters: FilterConfig {
                 include: vec!["src/**/*.rs".to_string()],
                 exclude: vec!["target/**".to_string(), "tests/**".to_string()],
+                visibility: vec![],
+                exclude_build_scripts: false,
+                element_types: vec![],
+                name_patterns: vec![],
+                exclude_names: vec![],
+                overrides: BTreeMap::new(),
             },
-            plugins: vec![],
+            plugins: PluginsConfig::default(),
+            disabled_plugins: vec![],
+            crates: vec![],
+            parallel_workers: 0,
+            expand_macros: false,
+            respect_gitignore: true,
+            follow_symlinks: false,
+            cfg_features: None,
+            recover_parse_errors: true,
+            encoding_fallback: true,
+            include_inline_comments: false,
+            extract_closures: false,
+            max_output_bytes: None,
+            max_output_tokens: None,
+            truncation_policy: TruncationPolicy::DropBodiesFirst,
+            deterministic_output: true,
+            diagrams: vec![],
+            csv_table: CsvTable::Elements,
+            template: None,
+            markdown: MarkdownConfig::default(),
+            embeddings: EmbeddingConfig::default(),
+            summaries: SummaryConfig::default(),
+            rag: RagOutputConfig::default(),
+            hf_dataset: HfDatasetConfig::default(),
+            fine_tuning: FineTuningConfig::default(),
+            thresholds: ThresholdConfig::default(),
         }
     }
 }
@@ -179,18 +1142,209 @@ parse_dependencies = false
 # Maximum file size to process (in bytes)
 max_file_size = 10485760  # 10MB
 
+# Extract only signatures and docs from files over max_file_size (such as
+# large generated files) instead of skipping them
+partial_oversize_files = false
+
+# Fail when any file is skipped or only partly extracted
+strict = false
+
 # Output format for extracted data
-output_format = "Json"  # Options: Json, MessagePack, Markdown, GraphQL, Rag
+output_format = "Json"  # Options: Json, MessagePack, Markdown, GraphQL, Rag, Jsonl, RagJsonl, LangChain, LlamaIndex, HfDataset, OpenAiFineTune, AnthropicFineTune, Mermaid, Dot, Csv, Parquet, Turtle, NTriples, Cypher, Protobuf, Scip, Ctags, Etags, Overview, Sarif, Template
 
-# Enabled plugins
+# Enabled plugins (see `rustex plugins list`); settings of plugins go in
+# `[plugins.config.<name>]` tables, with the list as `plugins.enabled`
 plugins = []
 
+# Disabled plugins, including plugins enabled by default
+disabled_plugins = []
+
+# Workspace member crates to extract (empty extracts all members)
+crates = []
+
+# Number of worker threads for file parsing (0 uses all available cores)
+parallel_workers = 0
+
+# Expand macros (via cargo expand) and include generated items
+expand_macros = false
+
+# Skip files ignored by .gitignore and .ignore (.rustexignore is always honored)
+respect_gitignore = true
+
+# Follow symbolic links to files and directories during discovery
+follow_symlinks = false
+
+# Features to simulate for #[cfg(...)] evaluation; items that would not compile
+# with exactly these features are skipped (omit to extract everything)
+# cfg_features = ["default"]
+
+# Extract the valid items of files with syntax errors; broken spans are
+# recorded under each file's parse_errors
+recover_parse_errors = true
+
+# Decode files with invalid UTF-8 (e.g. latin-1 comments) lossily, recording
+# a warning on the file; when false such files fail to extract
+encoding_fallback = true
+
+# Attach regular // and /* */ comments to the element containing them, or to
+# the element they directly precede
+include_inline_comments = false
+
+# Extract closures and async blocks inside functions as nested elements, with
+# their own complexity and captured variables
+extract_closures = false
+
+# Bound the size of the rendered output, e.g. to fit a context window; tokens
+# are estimated at four bytes each (omit for no limit)
+# max_output_tokens = 100000
+# max_output_bytes = 400000
+
+# How output over the limit is reduced: drop_bodies_first (reduce elements to
+# signatures, then drop private ones), drop_private_first, or
+# per_file_proportional; the largest files are dropped last
+truncation_policy = "drop_bodies_first"
+
+# Sort files by path and elements by location, and take the extraction time
+# from SOURCE_DATE_EPOCH when set, so the same sources give the same output
+deterministic_output = true
+
+# Diagrams to render: crate_dependencies, module_hierarchy, module_imports,
+# struct_relationships and call_graph; the Mermaid and Dot formats render all
+# of them when empty, Markdown output embeds the ones listed
+diagrams = []
+
+# Table of the Csv and Parquet formats: elements, files or metrics (element complexity)
+csv_table = "elements"
+
+# Tera template rendered by the Template format
+# template = "templates/report.md.tera"
+
 [filters]
 # Glob patterns for files to include
 include = ["src/**/*.rs"]
 
 # Glob patterns for files to exclude  
 exclude = ["target/**", "tests/**"]
+
+# Only extract elements with these visibilities: pub, pub(crate), pub(super),
+# pub(in path), pub(in) or private (empty extracts all, per include_private)
+# visibility = ["pub(crate)"]
+
+# Skip build scripts (build.rs), which are extracted by default
+exclude_build_scripts = false
+
+# Only extract these element types: function, struct, enum, trait, impl,
+# module, closure, async_block, ... (empty extracts all)
+# element_types = ["function", "trait"]
+
+# Regular expressions matched against element names: only extract matching
+# elements, and skip those matching exclude_names
+# name_patterns = ["Handler$"]
+# exclude_names = ["^test_"]
+
+# Different settings for the files matching a pattern; `extract` forces
+# matching files in (true) or out (false) of the extraction
+# [filters.overrides."tests/**"]
+# extract = true
+# include_private = true
+
+# Layout of the Markdown format
+[markdown]
+# Sections after the title: metrics, plugin_results, tech_debt, diagrams,
+# trait_implementations and files (all when empty); exclude_sections leaves
+# some out
+sections = []
+exclude_sections = []
+
+# Level of the title heading (1 to 3), to embed the output in other documents
+heading_level = 1
+
+# Start with a table of contents, and link elements to each other
+table_of_contents = true
+cross_links = true
+
+# Link locations to the sources, e.g. on GitHub
+# repo_url = "https://github.com/owner/repo/blob/main"
+
+# Embedding vectors of the chunks of the Rag and RagJsonl formats
+[embeddings]
+enabled = false
+
+# OpenAI-compatible API, with its key read from the api_key_env variable
+url = "https://api.openai.com/v1"
+model = "text-embedding-3-small"
+api_key_env = "OPENAI_API_KEY"
+
+# Chunks per request, retries of failed requests and an optional rate limit
+batch_size = 64
+max_retries = 3
+# requests_per_minute = 500
+
+# Embed with a local model instead of the API
+# model_dir = "models/all-MiniLM-L6-v2"
+
+# Summaries, by a chat model of an OpenAI-compatible API, of the elements
+# without docs whose cyclomatic complexity is at least min_complexity, in
+# the chunks of the Rag and RagJsonl formats
+[summaries]
+enabled = false
+url = "https://api.openai.com/v1"
+model = "gpt-4o-mini"
+api_key_env = "OPENAI_API_KEY"
+min_complexity = 5
+
+# Hugging Face dataset of the HfDataset format: shares of the examples in
+# the validation and test splits, examples per data file, and the license
+# (the project's license when unset)
+[hf_dataset]
+validation_split = 0.1
+test_split = 0.1
+shard_size = 10000
+# license = "MIT OR Apache-2.0"
+
+# System prompts of the OpenAiFineTune and AnthropicFineTune formats, as Tera
+# templates of `project`, `version`, `task`, `difficulty` and `concepts`
+[fine_tuning]
+system_prompt = "You are an expert Rust programmer who knows the {{ project }} crate."
+
+# Prompts of task types: code_generation, code_explanation, code_completion,
+# code_refactoring, bug_finding, code_summarization, api_usage,
+# pattern_recognition, documentation_generation, function_naming and
+# test_generation
+[fine_tuning.system_prompts]
+# api_usage = "You write short examples of the API of the {{ project }} crate."
+
+# Chunks of the RAG formats. Incremental output of the Rag and RagJsonl
+# formats only outputs the chunks that changed since the run that wrote the
+# `since` manifest
+[rag]
+# How elements are split into chunks: "per_element", "token_budget",
+# "sliding_window" or "semantic"
+chunk_strategy = "per_element"
+# Overlap between the chunks of a split element, in tokens
+chunk_overlap_tokens = 50
+# Lines of source around each chunk to add to its context
+context_radius_lines = 0
+# Add the headers of the impls, traits and modules enclosing each element
+include_parent_signature = true
+# since = "rag-manifest.json"
+# manifest = "rag-manifest.json"
+# Lowest quality score of the chunks, from 0 to 1; 0.3 leaves out generated
+# code and trivial getters
+min_quality = 0.0
+# Leave out chunks at least this similar to an earlier one, from 0 to 1
+# dedup_threshold = 0.9
+
+# Quality thresholds enforced by `rustex check`, which exits with an error
+# when one is exceeded (unset thresholds aren't checked)
+[thresholds]
+# max_cyclomatic = 15
+# max_cognitive = 20
+# max_function_lines = 100
+# max_file_lines = 1000
+# min_doc_coverage = 80.0
+# max_unsafe = 0
+# max_vulnerabilities = 0
 "#;
 
         fs::write(&path, example_content).with_context(|| {
@@ -214,11 +1368,111 @@ exclude = ["target/**", "tests/**"]
             anyhow::bail!("max_file_size is too large (>100MB), this may cause memory issues");
         }
 
+        if self.max_output_bytes == Some(0) || self.max_output_tokens == Some(0) {
+            anyhow::bail!("max_output_bytes and max_output_tokens must be greater than 0");
+        }
+
+        if matches!(self.output_format, OutputFormat::Template) && self.template.is_none() {
+            anyhow::bail!("The Template output format needs a template");
+        }
+
+        if !(1..=3).contains(&self.markdown.heading_level) {
+            anyhow::bail!("markdown.heading_level must be between 1 and 3");
+        }
+
+        if self.embeddings.batch_size == 0 || self.embeddings.requests_per_minute == Some(0) {
+            anyhow::bail!(
+                "embeddings.batch_size and embeddings.requests_per_minute must be greater than 0"
+            );
+        }
+
+        if !(0.0..=1.0).contains(&self.rag.min_quality) {
+            anyhow::bail!("rag.min_quality must be between 0 and 1");
+        }
+        if let Some(threshold) = self.rag.dedup_threshold {
+            if !(threshold > 0.0 && threshold <= 1.0) {
+                anyhow::bail!("rag.dedup_threshold must be greater than 0 and at most 1");
+            }
+        }
+
+        let hf_dataset = &self.hf_dataset;
+        let splits = [hf_dataset.validation_split, hf_dataset.test_split];
+        let in_range = splits.iter().all(|split| (0.0..1.0).contains(split));
+        if !in_range || splits.iter().sum::<f64>() >= 1.0 {
+            anyhow::bail!(
+                "hf_dataset.validation_split and hf_dataset.test_split must be at least 0 \
+                 and leave examples for the train split"
+            );
+        }
+        if hf_dataset.shard_size == 0 {
+            anyhow::bail!("hf_dataset.shard_size must be greater than 0");
+        }
+
+        for task in self.fine_tuning.system_prompts.keys() {
+            if !FINE_TUNING_TASKS.contains(&task.as_str()) {
+                anyhow::bail!(
+                    "fine_tuning.system_prompts has no task type `{}`; task types are {}",
+                    task,
+                    FINE_TUNING_TASKS.join(", ")
+                );
+            }
+        }
+
+        if let Some(coverage) = self.thresholds.min_doc_coverage {
+            if !(0.0..=100.0).contains(&coverage) {
+                anyhow::bail!("thresholds.min_doc_coverage must be between 0 and 100");
+            }
+        }
+
         // Validate include patterns
         if self.filters.include.is_empty() {
             anyhow::bail!("At least one include pattern must be specified");
         }
 
+        let override_visibility = self
+            .filters
+            .overrides
+            .values()
+            .filter_map(|path_override| path_override.visibility.as_ref())
+            .flatten();
+        if let Some(filter) = self
+            .filters
+            .visibility
+            .iter()
+            .chain(override_visibility)
+            .find(|filter| !Visibility::is_valid_filter(filter))
+        {
+            anyhow::bail!(
+                "Invalid visibility filter: {} (expected pub, pub(crate), pub(super), \
+                 pub(in path), pub(in) or private)",
+                filter
+            );
+        }
+
+        if let Some(filter) = self
+            .filters
+            .element_types
+            .iter()
+            .find(|filter| !ElementType::is_valid_filter(filter))
+        {
+            anyhow::bail!(
+                "Invalid element type filter: {} (expected a snake_case element type \
+                 such as function, struct or type_alias)",
+                filter
+            );
+        }
+
+        for pattern in self
+            .filters
+            .name_patterns
+            .iter()
+            .chain(&self.filters.exclude_names)
+        {
+            if let Err(e) = regex::Regex::new(pattern) {
+                anyhow::bail!("Invalid name pattern {}: {}", pattern, e);
+            }
+        }
+
         // Check for conflicting patterns
         for include_pattern in &self.filters.include {
             for exclude_pattern in &self.filters.exclude {
@@ -234,6 +1488,18 @@ exclude = ["target/**", "tests/**"]
         Ok(())
     }
 
+    /// The output size limit in bytes, from `max_output_bytes` and
+    /// `max_output_tokens`, whichever is smaller.
+    pub fn output_byte_limit(&self) -> Option<usize> {
+        let from_tokens = self
+            .max_output_tokens
+            .map(|tokens| tokens.saturating_mul(BYTES_PER_TOKEN));
+        match (self.max_output_bytes, from_tokens) {
+            (Some(bytes), Some(tokens)) => Some(bytes.min(tokens)),
+            (bytes, tokens) => bytes.or(tokens),
+        }
+    }
+
     /// Merge this configuration with another, preferring values from `other`.
     pub fn merge_with(&mut self, other: ExtractorConfig) {
         if other.include_docs != self.include_docs {
@@ -245,6 +1511,54 @@ exclude = ["target/**", "tests/**"]
         if other.parse_dependencies != self.parse_dependencies {
             self.parse_dependencies = other.parse_dependencies;
         }
+        if other.expand_macros != self.expand_macros {
+            self.expand_macros = other.expand_macros;
+        }
+        if other.respect_gitignore != self.respect_gitignore {
+            self.respect_gitignore = other.respect_gitignore;
+        }
+        if other.follow_symlinks != self.follow_symlinks {
+            self.follow_symlinks = other.follow_symlinks;
+        }
+        if other.recover_parse_errors != self.recover_parse_errors {
+            self.recover_parse_errors = other.recover_parse_errors;
+        }
+        if other.encoding_fallback != self.encoding_fallback {
+            self.encoding_fallback = other.encoding_fallback;
+        }
+        if other.partial_oversize_files != self.partial_oversize_files {
+            self.partial_oversize_files = other.partial_oversize_files;
+        }
+        if other.strict != self.strict {
+            self.strict = other.strict;
+        }
+        if other.include_inline_comments != self.include_inline_comments {
+            self.include_inline_comments = other.include_inline_comments;
+        }
+        if other.extract_closures != self.extract_closures {
+            self.extract_closures = other.extract_closures;
+        }
+        if other.max_output_bytes.is_some() {
+            self.max_output_bytes = other.max_output_bytes;
+        }
+        if other.max_output_tokens.is_some() {
+            self.max_output_tokens = other.max_output_tokens;
+        }
+        if other.truncation_policy != self.truncation_policy {
+            self.truncation_policy = other.truncation_policy;
+        }
+        if other.deterministic_output != self.deterministic_output {
+            self.deterministic_output = other.deterministic_output;
+        }
+        if !other.diagrams.is_empty() {
+            self.diagrams = other.diagrams;
+        }
+        if other.csv_table != self.csv_table {
+            self.csv_table = other.csv_table;
+        }
+        if other.template.is_some() {
+            self.template = other.template;
+        }
         if other.max_file_size != 10 * 1024 * 1024 {
             // Not default value
             self.max_file_size = other.max_file_size;
@@ -257,11 +1571,50 @@ exclude = ["target/**", "tests/**"]
         if !other.filters.exclude.is_empty() {
             self.filters.exclude = other.filters.exclude;
         }
+        if !other.filters.visibility.is_empty() {
+            self.filters.visibility = other.filters.visibility;
+        }
+        if other.filters.exclude_build_scripts != self.filters.exclude_build_scripts {
+            self.filters.exclude_build_scripts = other.filters.exclude_build_scripts;
+        }
+        if !other.filters.element_types.is_empty() {
+            self.filters.element_types = other.filters.element_types;
+        }
+        if !other.filters.name_patterns.is_empty() {
+            self.filters.name_patterns = other.filters.name_patterns;
+        }
+        if !other.filters.exclude_names.is_empty() {
+            self.filters.exclude_names = other.filters.exclude_names;
+        }
+        if !other.filters.overrides.is_empty() {
+            self.filters.overrides = other.filters.overrides;
+        }
 
         // Merge plugins
-        if !other.plugins.is_empty() {
-            self.plugins = other.plugins;
+        self.plugins.merge_with(other.plugins);
+        if !other.disabled_plugins.is_empty() {
+            self.disabled_plugins = other.disabled_plugins;
+        }
+
+        if !other.crates.is_empty() {
+            self.crates = other.crates;
         }
+
+        if other.parallel_workers != 0 {
+            self.parallel_workers = other.parallel_workers;
+        }
+
+        if other.cfg_features.is_some() {
+            self.cfg_features = other.cfg_features;
+        }
+
+        self.markdown.merge_with(other.markdown);
+        self.embeddings.merge_with(other.embeddings);
+        self.summaries.merge_with(other.summaries);
+        self.rag.merge_with(other.rag);
+        self.hf_dataset.merge_with(other.hf_dataset);
+        self.fine_tuning.merge_with(other.fine_tuning);
+        self.thresholds.merge_with(other.thresholds);
     }
 
     /// Create a configuration optimized for different use cases.
@@ -0,0 +1,650 @@
+//! Diagnostics for configuration files.
+//!
+//! [`ExtractorConfig::validate`] stops at the first error. Diagnostics report
+//! every problem found in a config file instead, each with a severity, a
+//! stable code, the setting it concerns and its line where it can be found, so
+//! editors and CI can show them inline. Besides invalid values they catch
+//! settings that would be silently ignored: unknown keys, plugins that are
+//! neither built in nor installed, include patterns shadowed by an exclude
+//! pattern and include patterns matching no file in the project. The
+//! settings of plugins in `plugins.config` are checked against the JSON
+//! schemas the plugins give for them.
+
+use crate::config::ExtractorConfig;
+use crate::config_layers::{
+    OPTIONAL_FLOAT_KEYS, OPTIONAL_INTEGER_KEYS, OPTIONAL_LIST_KEYS, OPTIONAL_STRING_KEYS,
+};
+use crate::extractor::{glob_match, RUSTEX_IGNORE_FILE};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Names of the plugins that ship with RustEx, those registered by
+/// `rustex_plugins::register_builtin_plugins`.
+pub const BUILTIN_PLUGINS: &[&str] = &[
+    "api-stability",
+    "complexity-analyzer",
+    "doc-enhancer",
+    "license-checker",
+    "llm-optimizer",
+    "metrics-collector",
+    "naming-linter",
+    "rustsec-audit",
+    "security-analyzer",
+    "tech-debt",
+    "test-mapping",
+];
+
+/// Name of the manifest file declaring an external plugin in its directory.
+pub const PLUGIN_MANIFEST: &str = "rustex-plugin.toml";
+
+/// The directory `rustex plugins install` installs external plugins to,
+/// `rustex/plugins` under the user's config directory.
+pub fn plugin_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("rustex").join("plugins"))
+}
+
+/// Names of the external plugins installed in [`plugin_dir`]: its
+/// subdirectories with a plugin manifest, named after their plugins.
+pub fn installed_plugins() -> Vec<String> {
+    let Some(entries) = plugin_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(PLUGIN_MANIFEST).is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Settings of an entry in `filters.overrides`.
+const PATH_OVERRIDE_KEYS: &[&str] = &["extract", "include_docs", "include_private", "visibility"];
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    /// The configuration can't be used
+    Error,
+    /// The configuration works, but probably not as intended
+    Warning,
+}
+
+/// A problem found in a configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigDiagnostic {
+    /// How serious the problem is
+    pub severity: DiagnosticSeverity,
+    /// Stable identifier of the kind of problem, such as `unknown-key`
+    pub code: String,
+    /// Description of the problem
+    pub message: String,
+    /// Dotted key of the setting concerned, such as `filters.include`
+    pub key: Option<String>,
+    /// 1-based line in the config file, when known
+    pub line: Option<usize>,
+    /// Suggested fix
+    pub help: Option<String>,
+}
+
+impl ConfigDiagnostic {
+    fn new(severity: DiagnosticSeverity, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: code.to_string(),
+            message: message.into(),
+            key: None,
+            line: None,
+            help: None,
+        }
+    }
+
+    fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    fn help(mut self, help: Option<String>) -> Self {
+        self.help = help;
+        self
+    }
+}
+
+/// Diagnose the contents of a TOML config file.
+///
+/// Include patterns are checked against the Rust files of `project_dir`
+/// when it is given.
+pub fn diagnose_config_str(content: &str, project_dir: Option<&Path>) -> Vec<ConfigDiagnostic> {
+    diagnose_config_str_with_schemas(content, project_dir, &BTreeMap::new())
+}
+
+/// Diagnose the contents of a TOML config file, checking the settings of
+/// plugins against `plugin_schemas`, the JSON schemas of their settings by
+/// plugin name.
+///
+/// Include patterns are checked against the Rust files of `project_dir`
+/// when it is given.
+pub fn diagnose_config_str_with_schemas(
+    content: &str,
+    project_dir: Option<&Path>,
+    plugin_schemas: &BTreeMap<String, serde_json::Value>,
+) -> Vec<ConfigDiagnostic> {
+    let table: toml::Table = match toml::from_str(content) {
+        Ok(table) => table,
+        Err(e) => return vec![parse_error(content, &e)],
+    };
+
+    let mut diagnostics = Vec::new();
+    let defaults = toml::Table::try_from(ExtractorConfig::default()).unwrap_or_default();
+    unknown_keys(&table, &defaults, "", &mut diagnostics);
+    match toml::from_str::<ExtractorConfig>(content) {
+        Ok(config) => {
+            diagnostics.extend(diagnose_config(&config, project_dir));
+            diagnostics.extend(plugin_config_diagnostics(&config, plugin_schemas));
+        }
+        Err(e) => diagnostics.push(parse_error(content, &e)),
+    }
+
+    for diagnostic in &mut diagnostics {
+        if diagnostic.line.is_none() {
+            diagnostic.line = diagnostic
+                .key
+                .as_deref()
+                .and_then(|key| key_line(content, key));
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.severity, diagnostic.line));
+    diagnostics
+}
+
+/// Diagnose a loaded configuration.
+///
+/// Include patterns are checked against the Rust files of `project_dir`
+/// when it is given.
+pub fn diagnose_config(
+    config: &ExtractorConfig,
+    project_dir: Option<&Path>,
+) -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if let Err(e) = config.validate() {
+        diagnostics.push(ConfigDiagnostic::new(
+            DiagnosticSeverity::Error,
+            "invalid-config",
+            e.to_string(),
+        ));
+    }
+
+    let plugins = config
+        .plugins
+        .enabled
+        .iter()
+        .map(|plugin| ("plugins".to_string(), plugin))
+        .chain(
+            config
+                .disabled_plugins
+                .iter()
+                .map(|plugin| ("disabled_plugins".to_string(), plugin)),
+        )
+        .chain(
+            config
+                .plugins
+                .config
+                .keys()
+                .map(|plugin| (format!("plugins.config.{}", plugin), plugin)),
+        );
+    let installed = installed_plugins();
+    let known: Vec<&str> = BUILTIN_PLUGINS
+        .iter()
+        .copied()
+        .chain(installed.iter().map(String::as_str))
+        .collect();
+    for (key, plugin) in plugins {
+        if !known.contains(&plugin.as_str()) {
+            let help = match closest(plugin, known.iter().copied()) {
+                Some(name) => format!("did you mean `{}`?", name),
+                None => format!("available plugins: {}", known.join(", ")),
+            };
+            diagnostics.push(
+                ConfigDiagnostic::new(
+                    DiagnosticSeverity::Warning,
+                    "unknown-plugin",
+                    format!("plugin `{}` doesn't exist", plugin),
+                )
+                .key(key)
+                .help(Some(help)),
+            );
+        }
+    }
+
+    // Identical patterns are already reported by `validate`.
+    let filters = &config.filters;
+    for include in &filters.include {
+        for exclude in filters.exclude.iter().filter(|exclude| *exclude != include) {
+            if glob_match(exclude, include) {
+                diagnostics.push(
+                    ConfigDiagnostic::new(
+                        DiagnosticSeverity::Warning,
+                        "conflicting-globs",
+                        format!(
+                            "exclude pattern `{}` also matches the files of include pattern `{}`",
+                            exclude, include
+                        ),
+                    )
+                    .key("filters.exclude")
+                    .help(Some(format!(
+                        "narrow `{}` or remove `{}`",
+                        exclude, include
+                    ))),
+                );
+            }
+        }
+    }
+
+    if let Some(project_dir) = project_dir {
+        let files = rust_files(project_dir, config);
+        for include in &filters.include {
+            if !files.iter().any(|file| glob_match(include, file)) {
+                diagnostics.push(
+                    ConfigDiagnostic::new(
+                        DiagnosticSeverity::Warning,
+                        "unreachable-include",
+                        format!(
+                            "include pattern `{}` matches no Rust files in {}",
+                            include,
+                            project_dir.display()
+                        ),
+                    )
+                    .key("filters.include"),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn parse_error(content: &str, error: &toml::de::Error) -> ConfigDiagnostic {
+    let mut diagnostic = ConfigDiagnostic::new(
+        DiagnosticSeverity::Error,
+        "invalid-toml",
+        error.message().trim(),
+    );
+    diagnostic.line = error
+        .span()
+        .map(|span| content[..span.start].matches('\n').count() + 1);
+    diagnostic
+}
+
+/// Check the settings of the plugins in `plugins.config` against the JSON
+/// schemas of their settings in `plugin_schemas`.
+fn plugin_config_diagnostics(
+    config: &ExtractorConfig,
+    plugin_schemas: &BTreeMap<String, serde_json::Value>,
+) -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for (plugin, settings) in &config.plugins.config {
+        let Some(schema) = plugin_schemas.get(plugin) else {
+            continue;
+        };
+        let mut errors = Vec::new();
+        schema_errors(settings, schema, schema, "", &mut errors);
+        for (path, message) in errors {
+            diagnostics.push(
+                ConfigDiagnostic::new(
+                    DiagnosticSeverity::Error,
+                    "invalid-plugin-config",
+                    format!("invalid settings for plugin `{}`: {}", plugin, message),
+                )
+                .key(join_key(&format!("plugins.config.{}", plugin), &path)),
+            );
+        }
+    }
+    diagnostics
+}
+
+/// Record the problems of `value` against `schema`, a part of the JSON schema
+/// `root`, with the dotted path of each problem below `path`.
+///
+/// Schemas are checked for `type`, `enum`, `const`, `properties`,
+/// `required`, `additionalProperties`, `items`, `minimum`, `maximum`,
+/// `anyOf`, `oneOf`, `allOf` and `$ref`s within `root`, the keywords
+/// describing settings; other keywords are ignored.
+fn schema_errors(
+    value: &serde_json::Value,
+    schema: &serde_json::Value,
+    root: &serde_json::Value,
+    path: &str,
+    errors: &mut Vec<(String, String)>,
+) {
+    use serde_json::Value;
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push((path.to_string(), format!("`{}` isn't allowed", path)));
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+    let name = if path.is_empty() { "settings" } else { path };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer));
+        if let Some(target) = target {
+            schema_errors(value, target, root, path, errors);
+        }
+    }
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+            errors.push((
+                path.to_string(),
+                format!(
+                    "`{}` should be {}, not {}",
+                    name,
+                    types.join(" or "),
+                    type_name(value)
+                ),
+            ));
+            return;
+        }
+    }
+    let allowed: Option<Vec<&Value>> = match (schema.get("enum"), schema.get("const")) {
+        (Some(Value::Array(values)), _) => Some(values.iter().collect()),
+        (_, Some(value)) => Some(vec![value]),
+        _ => None,
+    };
+    if let Some(allowed) = allowed {
+        if !allowed.contains(&value) {
+            errors.push((path.to_string(), one_of_message(name, &allowed)));
+            return;
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        let Some(Value::Array(alternatives)) = schema.get(keyword) else {
+            continue;
+        };
+        let matches = |alternative: &Value| {
+            let mut alternative_errors = Vec::new();
+            schema_errors(value, alternative, root, path, &mut alternative_errors);
+            alternative_errors.is_empty()
+        };
+        if !alternatives.iter().any(matches) {
+            // Enums of documented variants are alternatives of constants.
+            let constants: Option<Vec<&Value>> = alternatives
+                .iter()
+                .map(|alternative| alternative.get("const"))
+                .collect();
+            let message = match constants {
+                Some(constants) => one_of_message(name, &constants),
+                None => format!("`{}` doesn't match any of its allowed forms", name),
+            };
+            errors.push((path.to_string(), message));
+        }
+    }
+    if let Some(Value::Array(parts)) = schema.get("allOf") {
+        for part in parts {
+            schema_errors(value, part, root, path, errors);
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                errors.push((
+                    path.to_string(),
+                    format!("`{}` should be at least {}", name, minimum),
+                ));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                errors.push((
+                    path.to_string(),
+                    format!("`{}` should be at most {}", name, maximum),
+                ));
+            }
+        }
+    }
+    if let Some(items) = value.as_array() {
+        if let Some(item_schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                let item_path = format!("{}[{}]", path, index);
+                schema_errors(item, item_schema, root, &item_path, errors);
+            }
+        }
+    }
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for setting in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(setting) {
+                    errors.push((
+                        path.to_string(),
+                        format!("missing setting `{}`", join_key(path, setting)),
+                    ));
+                }
+            }
+        }
+        for (setting, setting_value) in object {
+            let setting_path = join_key(path, setting);
+            match properties.and_then(|properties| properties.get(setting)) {
+                Some(setting_schema) => {
+                    schema_errors(setting_value, setting_schema, root, &setting_path, errors)
+                }
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        let known = properties
+                            .into_iter()
+                            .flat_map(|properties| properties.keys());
+                        let help = closest(setting, known.map(String::as_str))
+                            .map(|known| format!(", did you mean `{}`?", known))
+                            .unwrap_or_default();
+                        errors.push((
+                            setting_path.clone(),
+                            format!("unknown setting `{}`{}", setting_path, help),
+                        ));
+                    }
+                    Some(additional) => {
+                        schema_errors(setting_value, additional, root, &setting_path, errors)
+                    }
+                    None => {}
+                },
+            }
+        }
+    }
+}
+
+/// Whether `value` is of the JSON schema type `name`.
+fn has_type(value: &serde_json::Value, name: &str) -> bool {
+    use serde_json::Value;
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => !matches!(value, Value::Null),
+    }
+}
+
+/// Name of the JSON type of `value`, for messages.
+fn type_name(value: &serde_json::Value) -> &'static str {
+    use serde_json::Value;
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(number) if number.is_f64() => "a number",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "a table",
+    }
+}
+
+/// A message that the setting `name` should be one of `allowed`.
+fn one_of_message(name: &str, allowed: &[&serde_json::Value]) -> String {
+    let allowed: Vec<String> = allowed.iter().map(|value| format!("`{}`", value)).collect();
+    format!("`{}` should be one of {}", name, allowed.join(", "))
+}
+
+/// Report the keys of `table` that aren't settings.
+fn unknown_keys(
+    table: &toml::Table,
+    known: &toml::Table,
+    prefix: &str,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    for (name, value) in table {
+        let key = join_key(prefix, name);
+        if key == "filters.overrides" {
+            let overrides = value.as_table().into_iter().flatten();
+            for (pattern, settings) in overrides {
+                for name in settings
+                    .as_table()
+                    .into_iter()
+                    .flat_map(|table| table.keys())
+                {
+                    if !PATH_OVERRIDE_KEYS.contains(&name.as_str()) {
+                        let key = join_key(&join_key(&key, pattern), name);
+                        diagnostics.push(unknown_key(
+                            key,
+                            name,
+                            PATH_OVERRIDE_KEYS.iter().copied(),
+                        ));
+                    }
+                }
+            }
+            continue;
+        }
+        // Unknown task types fail `validate`, reported as invalid config.
+        if key == "fine_tuning.system_prompts" {
+            continue;
+        }
+
+        match (known.get(name), value) {
+            (Some(toml::Value::Table(known)), toml::Value::Table(table)) => {
+                unknown_keys(table, known, &key, diagnostics);
+            }
+            (Some(_), _) => {}
+            (None, _) if optional_keys().any(|optional| optional == key) => {}
+            (None, _) => {
+                let mut candidates: Vec<&str> = known.keys().map(String::as_str).collect();
+                candidates.extend(optional_keys().filter_map(|optional| {
+                    match optional.rsplit_once('.') {
+                        Some((parent, name)) => (parent == prefix).then_some(name),
+                        None => prefix.is_empty().then_some(optional),
+                    }
+                }));
+                diagnostics.push(unknown_key(key, name, candidates.into_iter()));
+            }
+        }
+    }
+}
+
+fn unknown_key<'a>(
+    key: String,
+    name: &str,
+    candidates: impl Iterator<Item = &'a str>,
+) -> ConfigDiagnostic {
+    let help = closest(name, candidates).map(|known| format!("did you mean `{}`?", known));
+    ConfigDiagnostic::new(
+        DiagnosticSeverity::Warning,
+        "unknown-key",
+        format!("unknown setting `{}` is ignored", key),
+    )
+    .key(key)
+    .help(help)
+}
+
+/// Dotted keys of the settings absent from the default configuration.
+fn optional_keys() -> impl Iterator<Item = &'static str> {
+    OPTIONAL_LIST_KEYS
+        .iter()
+        .chain(OPTIONAL_INTEGER_KEYS)
+        .chain(OPTIONAL_FLOAT_KEYS)
+        .chain(OPTIONAL_STRING_KEYS)
+        .copied()
+}
+
+fn join_key(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+/// The candidate closest to `name` in edit distance, if it's close enough to
+/// be a typo or `name` is a prefix of it.
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.len() / 3).max(2);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= max_distance || candidate.starts_with(name))
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The line of the setting or table named by the last segment of `key`.
+fn key_line(content: &str, key: &str) -> Option<usize> {
+    let name = key.rsplit('.').next()?;
+    content
+        .lines()
+        .position(|line| {
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.trim_matches(|c| c == '[' || c == ']').trim();
+                header.rsplit('.').next().map(|last| last.trim_matches('"')) == Some(name)
+            } else {
+                line.split('=')
+                    .next()
+                    .map(|lhs| lhs.trim().trim_matches('"'))
+                    == Some(name)
+                    && line.contains('=')
+            }
+        })
+        .map(|index| index + 1)
+}
+
+/// Paths of the Rust files under `project_dir` that discovery would consider.
+fn rust_files(project_dir: &Path, config: &ExtractorConfig) -> Vec<String> {
+    WalkBuilder::new(project_dir)
+        .standard_filters(config.respect_gitignore)
+        .hidden(false)
+        .require_git(false)
+        .follow_links(config.follow_symlinks)
+        .add_custom_ignore_filename(RUSTEX_IGNORE_FILE)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().and_then(|ext| ext.to_str()) == Some("rs"))
+        .map(|entry| entry.path().to_string_lossy().into_owned())
+        .collect()
+}
@@ -0,0 +1,342 @@
+//! Layered configuration resolution.
+//!
+//! Settings are resolved from, in increasing order of precedence, the
+//! built-in defaults, the user's global config file, the project's config
+//! file and `RUSTEX_*` environment variables. Command-line flags are applied
+//! on top by the CLI. Layers are merged key by key, so a layer only overrides
+//! the settings it names, and the layer each setting came from is recorded.
+//!
+//! Environment variables are named after the setting's dotted key, uppercased
+//! with dots turned into underscores: `include_docs` is read from
+//! `RUSTEX_INCLUDE_DOCS` and `filters.include` from `RUSTEX_FILTERS_INCLUDE`.
+//! List settings take comma-separated values.
+
+use crate::config::ExtractorConfig;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prefix of the environment variables that override settings.
+pub const ENV_PREFIX: &str = "RUSTEX_";
+
+/// Config file names looked for in a project directory, in order.
+pub const PROJECT_CONFIG_FILES: &[&str] = &["rustex.toml", ".rustex.toml"];
+
+/// List settings that aren't present in the default configuration.
+pub(crate) const OPTIONAL_LIST_KEYS: &[&str] = &["cfg_features"];
+
+/// Integer settings that aren't present in the default configuration.
+pub(crate) const OPTIONAL_INTEGER_KEYS: &[&str] = &[
+    "max_output_bytes",
+    "max_output_tokens",
+    "thresholds.max_cyclomatic",
+    "thresholds.max_cognitive",
+    "thresholds.max_function_lines",
+    "thresholds.max_file_lines",
+    "thresholds.max_unsafe",
+    "thresholds.max_vulnerabilities",
+    "embeddings.requests_per_minute",
+];
+
+/// Float settings that aren't present in the default configuration.
+pub(crate) const OPTIONAL_FLOAT_KEYS: &[&str] =
+    &["thresholds.min_doc_coverage", "rag.dedup_threshold"];
+
+/// String settings that aren't present in the default configuration.
+pub(crate) const OPTIONAL_STRING_KEYS: &[&str] = &[
+    "template",
+    "markdown.repo_url",
+    "embeddings.model_dir",
+    "rag.since",
+    "rag.manifest",
+    "hf_dataset.license",
+];
+
+/// Where a resolved setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Built-in default
+    Default,
+    /// The user's global config file
+    GlobalFile(PathBuf),
+    /// The project's config file
+    ProjectFile(PathBuf),
+    /// A `RUSTEX_*` environment variable, by name
+    Environment(String),
+    /// A command-line flag
+    CommandLine,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::GlobalFile(path) => write!(f, "global file {}", path.display()),
+            ConfigSource::ProjectFile(path) => write!(f, "project file {}", path.display()),
+            ConfigSource::Environment(name) => write!(f, "environment variable {}", name),
+            ConfigSource::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// A configuration together with the source of each of its settings.
+#[derive(Debug, Clone)]
+pub struct ResolvedConfig {
+    /// The merged configuration
+    pub config: ExtractorConfig,
+    /// Source of each setting that didn't come from the defaults, keyed by
+    /// its dotted key such as `filters.include`
+    pub sources: BTreeMap<String, ConfigSource>,
+}
+
+impl ResolvedConfig {
+    /// The source of the setting at `key`.
+    pub fn source(&self, key: &str) -> &ConfigSource {
+        self.sources.get(key).unwrap_or(&ConfigSource::Default)
+    }
+
+    /// Every setting of the configuration with its value and source, sorted by key.
+    pub fn settings(&self) -> Result<Vec<(String, toml::Value, &ConfigSource)>> {
+        let table = toml::Value::Table(config_table(&self.config)?);
+        let mut settings = Vec::new();
+        flatten(&table, "", &mut |key, value| {
+            let source = self.source(&key);
+            settings.push((key, value.clone(), source));
+        });
+        settings.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(settings)
+    }
+}
+
+/// The layers a configuration is resolved from.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLayers {
+    global_file: Option<PathBuf>,
+    project_file: Option<PathBuf>,
+    env_vars: Vec<(String, String)>,
+}
+
+impl ConfigLayers {
+    /// Layers with only the built-in defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The standard layers for a project: the global config file under the
+    /// user's config directory, the first of [`PROJECT_CONFIG_FILES`] found
+    /// in `project_dir`, and the process environment. Missing files are skipped.
+    pub fn standard(project_dir: &Path) -> Self {
+        let mut layers = Self::new().env_vars(std::env::vars());
+        if let Some(global) = global_config_path().filter(|path| path.is_file()) {
+            layers = layers.global_file(global);
+        }
+        if let Some(project) = PROJECT_CONFIG_FILES
+            .iter()
+            .map(|name| project_dir.join(name))
+            .find(|path| path.is_file())
+        {
+            layers = layers.project_file(project);
+        }
+        layers
+    }
+
+    /// Use `path` as the global config file.
+    pub fn global_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.global_file = Some(path.into());
+        self
+    }
+
+    /// Use `path` as the project config file.
+    pub fn project_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.project_file = Some(path.into());
+        self
+    }
+
+    /// Read overrides from these environment variables; names without the
+    /// [`ENV_PREFIX`] or not matching a setting are ignored.
+    pub fn env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env_vars = vars.into_iter().collect();
+        self
+    }
+
+    /// The project config file, if any.
+    pub fn project_file_path(&self) -> Option<&Path> {
+        self.project_file.as_deref()
+    }
+
+    /// Merge the layers into a configuration.
+    pub fn resolve(&self) -> Result<ResolvedConfig> {
+        let defaults = config_table(&ExtractorConfig::default())?;
+        let mut merged = defaults.clone();
+        let mut sources = BTreeMap::new();
+
+        let files = [
+            self.global_file
+                .clone()
+                .map(|path| (path.clone(), ConfigSource::GlobalFile(path))),
+            self.project_file
+                .clone()
+                .map(|path| (path.clone(), ConfigSource::ProjectFile(path))),
+        ];
+        for (path, source) in files.into_iter().flatten() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+            let layer: toml::Table = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse TOML config: {}", path.display()))?;
+            check_layer(&layer)
+                .with_context(|| format!("Failed to parse TOML config: {}", path.display()))?;
+            merge_layer(&mut merged, layer, "", &source, &mut sources);
+        }
+
+        let mut env_vars: Vec<&(String, String)> = self.env_vars.iter().collect();
+        env_vars.sort();
+        for (name, value) in env_vars {
+            let Some(key) = env_key(name, &defaults) else {
+                continue;
+            };
+            let value = env_value(&key, value, &defaults);
+            let layer = nested_table(&key, value);
+            check_layer(&layer).with_context(|| format!("Invalid value for {}", name))?;
+            let source = ConfigSource::Environment(name.clone());
+            merge_layer(&mut merged, layer, "", &source, &mut sources);
+        }
+
+        let config = toml::Value::Table(merged)
+            .try_into()
+            .context("Failed to merge configuration layers")?;
+        Ok(ResolvedConfig { config, sources })
+    }
+}
+
+/// The global config file, `rustex/config.toml` under the user's config directory.
+pub fn global_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("rustex").join("config.toml"))
+}
+
+fn config_table(config: &ExtractorConfig) -> Result<toml::Table> {
+    toml::Table::try_from(config).context("Failed to serialize config to TOML")
+}
+
+/// Check that a layer's settings have valid values on their own.
+fn check_layer(layer: &toml::Table) -> Result<()> {
+    toml::Value::Table(layer.clone()).try_into::<ExtractorConfig>()?;
+    Ok(())
+}
+
+/// Merge `layer` into `base`, recording `source` for every setting it sets.
+fn merge_layer(
+    base: &mut toml::Table,
+    layer: toml::Table,
+    prefix: &str,
+    source: &ConfigSource,
+    sources: &mut BTreeMap<String, ConfigSource>,
+) {
+    for (name, value) in layer {
+        let key = join_key(prefix, &name);
+        match (base.get_mut(&name), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(layer)) => {
+                merge_layer(base, layer, &key, source, sources);
+            }
+            (_, value) => {
+                flatten(&value, &key, &mut |key, _| {
+                    sources.insert(key, source.clone());
+                });
+                base.insert(name, value);
+            }
+        }
+    }
+}
+
+/// Call `f` with the dotted key and value of every non-table value in `value`.
+fn flatten(value: &toml::Value, prefix: &str, f: &mut dyn FnMut(String, &toml::Value)) {
+    match value {
+        toml::Value::Table(table) => {
+            for (name, value) in table {
+                flatten(value, &join_key(prefix, name), f);
+            }
+        }
+        value => f(prefix.to_string(), value),
+    }
+}
+
+fn join_key(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+/// The setting an environment variable overrides, if any.
+fn env_key(name: &str, defaults: &toml::Table) -> Option<String> {
+    let suffix = name.strip_prefix(ENV_PREFIX)?;
+    let mut keys: Vec<String> = OPTIONAL_LIST_KEYS
+        .iter()
+        .chain(OPTIONAL_INTEGER_KEYS)
+        .chain(OPTIONAL_FLOAT_KEYS)
+        .chain(OPTIONAL_STRING_KEYS)
+        .map(|k| k.to_string())
+        .collect();
+    for (name, value) in defaults {
+        flatten(value, name, &mut |key, _| keys.push(key));
+    }
+    keys.into_iter()
+        .find(|key| key.replace('.', "_").eq_ignore_ascii_case(suffix))
+}
+
+/// Convert an environment variable's text to the type of the setting at `key`.
+fn env_value(key: &str, text: &str, defaults: &toml::Table) -> toml::Value {
+    let text = text.trim();
+    let integer = toml::Value::Integer(0);
+    let float = toml::Value::Float(0.0);
+    let string = toml::Value::String(String::new());
+    let default = lookup(defaults, key)
+        .or_else(|| OPTIONAL_INTEGER_KEYS.contains(&key).then_some(&integer))
+        .or_else(|| OPTIONAL_FLOAT_KEYS.contains(&key).then_some(&float))
+        .or_else(|| OPTIONAL_STRING_KEYS.contains(&key).then_some(&string));
+    match default {
+        Some(toml::Value::Boolean(_)) => match text.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => toml::Value::Boolean(true),
+            "0" | "false" | "no" | "off" => toml::Value::Boolean(false),
+            _ => toml::Value::String(text.to_string()),
+        },
+        Some(toml::Value::Integer(_)) => text.parse().map_or_else(
+            |_| toml::Value::String(text.to_string()),
+            toml::Value::Integer,
+        ),
+        Some(toml::Value::Float(_)) => text.parse().map_or_else(
+            |_| toml::Value::String(text.to_string()),
+            toml::Value::Float,
+        ),
+        Some(toml::Value::String(_)) => toml::Value::String(text.to_string()),
+        // Lists, including settings absent from the defaults.
+        _ => toml::Value::Array(
+            text.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml::Value::String(item.to_string()))
+                .collect(),
+        ),
+    }
+}
+
+fn lookup<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let (parent, name) = match key.rsplit_once('.') {
+        Some((parent, name)) => (lookup(table, parent)?.as_table()?, name),
+        None => (table, key),
+    };
+    parent.get(name)
+}
+
+/// A table holding only `value` at the dotted `key`.
+fn nested_table(key: &str, value: toml::Value) -> toml::Table {
+    let mut names = key.rsplit('.');
+    let last = names.next().unwrap_or(key);
+    let mut table = toml::Table::from_iter([(last.to_string(), value)]);
+    for name in names {
+        table = toml::Table::from_iter([(name.to_string(), toml::Value::Table(table))]);
+    }
+    table
+}
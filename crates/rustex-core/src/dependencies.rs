@@ -0,0 +1,315 @@
+//! Dependency discovery and source location.
+//!
+//! Dependencies are resolved through `cargo metadata` when it is available,
+//! falling back to reading `Cargo.lock` and probing the local Cargo registry
+//! (`$CARGO_HOME/registry/src`) for unpacked crate sources. Both use the
+//! Cargo home and network policy given in [`CargoSettings`].
+
+use crate::errors::{Result, RustExError};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How Cargo is invoked to resolve dependencies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoSettings {
+    /// Cargo home holding the registry; `$CARGO_HOME` or `~/.cargo` when unset
+    pub home: Option<PathBuf>,
+    /// Resolve without network access, like `cargo --offline`
+    pub offline: bool,
+}
+
+impl CargoSettings {
+    /// The Cargo home in use: the configured one, then `$CARGO_HOME`, then `~/.cargo`.
+    pub fn cargo_home(&self) -> Option<PathBuf> {
+        self.home
+            .clone()
+            .or_else(|| std::env::var_os("CARGO_HOME").map(PathBuf::from))
+            .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+    }
+
+    /// A `cargo` command using these settings.
+    fn command(&self) -> Command {
+        let mut command = Command::new("cargo");
+        if let Some(home) = &self.home {
+            command.env("CARGO_HOME", home);
+        }
+        if self.offline {
+            command.env("CARGO_NET_OFFLINE", "true");
+        }
+        command
+    }
+}
+
+/// Location of a dependency crate's source on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencySource {
+    /// Crate name as published
+    pub name: String,
+    /// Resolved crate version
+    pub version: String,
+    /// Root directory of the crate sources (contains its `Cargo.toml`)
+    pub root_path: PathBuf,
+}
+
+/// Dependencies declared in a manifest, grouped by kind.
+#[derive(Debug, Clone, Default)]
+pub struct DeclaredDependencies {
+    /// `[dependencies]` entries
+    pub normal: Vec<String>,
+    /// `[dev-dependencies]` entries
+    pub dev: Vec<String>,
+    /// `[build-dependencies]` entries
+    pub build: Vec<String>,
+}
+
+/// Read the dependencies declared in a `Cargo.toml`.
+///
+/// Renamed dependencies (`foo = { package = "bar" }`) are reported under
+/// their package name, since that is what appears in the registry.
+pub fn read_declared_dependencies(manifest_path: &Path) -> Result<DeclaredDependencies> {
+    let content = fs::read_to_string(manifest_path)?;
+    let manifest: toml::Value = toml::from_str(&content).map_err(|e| {
+        RustExError::Config(format!(
+            "Failed to parse {}: {}",
+            manifest_path.display(),
+            e
+        ))
+    })?;
+
+    Ok(DeclaredDependencies {
+        normal: dependency_table_names(manifest.get("dependencies")),
+        dev: dependency_table_names(manifest.get("dev-dependencies")),
+        build: dependency_table_names(manifest.get("build-dependencies")),
+    })
+}
+
+/// Collect package names from a `[dependencies]`-style table.
+fn dependency_table_names(table: Option<&toml::Value>) -> Vec<String> {
+    let Some(table) = table.and_then(|t| t.as_table()) else {
+        return vec![];
+    };
+
+    let mut names: Vec<String> = table
+        .iter()
+        .map(|(key, value)| {
+            value
+                .get("package")
+                .and_then(|p| p.as_str())
+                .unwrap_or(key)
+                .to_string()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Locate the sources of the direct (non-dev) dependencies of a project.
+///
+/// Path dependencies inside the project itself are skipped, since their
+/// elements are already part of the regular extraction.
+pub fn locate_dependency_sources(
+    project_root: &Path,
+    cargo: &CargoSettings,
+) -> Result<Vec<DependencySource>> {
+    let manifest_path = project_root.join("Cargo.toml");
+    if !manifest_path.exists() {
+        return Ok(vec![]);
+    }
+
+    match locate_with_cargo_metadata(&manifest_path, cargo) {
+        Ok(sources) => Ok(sources),
+        Err(e) => {
+            tracing::debug!(
+                "cargo metadata unavailable ({}), falling back to registry lookup",
+                e
+            );
+            locate_in_registry(project_root, cargo)
+        }
+    }
+}
+
+/// Resolve dependency sources using `cargo metadata`.
+fn locate_with_cargo_metadata(
+    manifest_path: &Path,
+    cargo: &CargoSettings,
+) -> Result<Vec<DependencySource>> {
+    let metadata = cargo_metadata(manifest_path, &[], cargo)?;
+    Ok(sources_from_metadata(&metadata, manifest_path))
+}
+
+/// Run `cargo metadata` for a manifest and return the parsed JSON output.
+pub(crate) fn cargo_metadata(
+    manifest_path: &Path,
+    extra_args: &[&str],
+    cargo: &CargoSettings,
+) -> Result<serde_json::Value> {
+    let output = cargo
+        .command()
+        .arg("metadata")
+        .arg("--format-version")
+        .arg("1")
+        .args(extra_args)
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(RustExError::Config(format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Extract direct dependency sources of the root package from `cargo metadata` JSON.
+fn sources_from_metadata(
+    metadata: &serde_json::Value,
+    manifest_path: &Path,
+) -> Vec<DependencySource> {
+    let empty = vec![];
+    let packages = metadata["packages"].as_array().unwrap_or(&empty);
+
+    let manifest_path = fs::canonicalize(manifest_path).unwrap_or_else(|_| manifest_path.into());
+    let Some(root) = packages.iter().find(|p| {
+        p["manifest_path"]
+            .as_str()
+            .map(|m| fs::canonicalize(m).unwrap_or_else(|_| m.into()) == manifest_path)
+            .unwrap_or(false)
+    }) else {
+        return vec![];
+    };
+    let root_id = root["id"].as_str().unwrap_or_default();
+
+    let empty_nodes = vec![];
+    let nodes = metadata["resolve"]["nodes"]
+        .as_array()
+        .unwrap_or(&empty_nodes);
+    let Some(root_node) = nodes.iter().find(|n| n["id"].as_str() == Some(root_id)) else {
+        return vec![];
+    };
+
+    let mut sources = Vec::new();
+    for dep in root_node["deps"].as_array().unwrap_or(&empty) {
+        // Only normal dependencies (kind == null) are part of the public API we call.
+        let is_normal = dep["dep_kinds"]
+            .as_array()
+            .map(|kinds| kinds.iter().any(|k| k["kind"].is_null()))
+            .unwrap_or(true);
+        if !is_normal {
+            continue;
+        }
+
+        let pkg_id = dep["pkg"].as_str().unwrap_or_default();
+        let Some(package) = packages.iter().find(|p| p["id"].as_str() == Some(pkg_id)) else {
+            continue;
+        };
+
+        // Local path dependencies have no `source`; they are extracted with the project.
+        if package["source"].is_null() {
+            continue;
+        }
+
+        let Some(dep_manifest) = package["manifest_path"].as_str() else {
+            continue;
+        };
+        let Some(root_path) = Path::new(dep_manifest).parent() else {
+            continue;
+        };
+
+        sources.push(DependencySource {
+            name: package["name"].as_str().unwrap_or_default().to_string(),
+            version: package["version"].as_str().unwrap_or_default().to_string(),
+            root_path: root_path.to_path_buf(),
+        });
+    }
+
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+    sources
+}
+
+/// Resolve dependency sources by reading `Cargo.lock` and probing the Cargo registry.
+fn locate_in_registry(project_root: &Path, cargo: &CargoSettings) -> Result<Vec<DependencySource>> {
+    let declared = read_declared_dependencies(&project_root.join("Cargo.toml"))?;
+    let locked = read_lockfile_versions(project_root);
+    let registry_dirs = registry_source_dirs_in(cargo);
+
+    let mut sources = Vec::new();
+    for name in &declared.normal {
+        let Some(version) = locked.get(name).and_then(|versions| versions.last()) else {
+            tracing::debug!("No locked version for dependency {}", name);
+            continue;
+        };
+
+        let crate_dir = format!("{}-{}", name, version);
+        if let Some(root_path) = registry_dirs
+            .iter()
+            .map(|dir| dir.join(&crate_dir))
+            .find(|path| path.join("Cargo.toml").exists())
+        {
+            sources.push(DependencySource {
+                name: name.clone(),
+                version: version.clone(),
+                root_path,
+            });
+        } else {
+            tracing::debug!("Sources for {} {} not found in registry", name, version);
+        }
+    }
+
+    Ok(sources)
+}
+
+/// Read package versions from `Cargo.lock`, keyed by package name.
+///
+/// Multiple versions of the same package are kept in lockfile order.
+pub fn read_lockfile_versions(project_root: &Path) -> HashMap<String, Vec<String>> {
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+
+    let Ok(content) = fs::read_to_string(project_root.join("Cargo.lock")) else {
+        return versions;
+    };
+    let Ok(lockfile) = toml::from_str::<toml::Value>(&content) else {
+        return versions;
+    };
+
+    for package in lockfile
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+    {
+        if let (Some(name), Some(version)) = (
+            package.get("name").and_then(|n| n.as_str()),
+            package.get("version").and_then(|v| v.as_str()),
+        ) {
+            versions
+                .entry(name.to_string())
+                .or_default()
+                .push(version.to_string());
+        }
+    }
+
+    versions
+}
+
+/// Directories under `registry/src` of the Cargo home given in `cargo`,
+/// holding unpacked crates in `<name>-<version>` directories.
+pub fn registry_source_dirs_in(cargo: &CargoSettings) -> Vec<PathBuf> {
+    let Some(cargo_home) = cargo.cargo_home() else {
+        return vec![];
+    };
+
+    fs::read_dir(cargo_home.join("registry").join("src"))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
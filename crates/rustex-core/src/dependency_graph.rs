@@ -0,0 +1,324 @@
+//! Resolved dependency graph of a project.
+//!
+//! The graph is read from `Cargo.lock`, which records every resolved package
+//! and the packages each one depends on, without needing network access or
+//! cargo itself. The project's own packages (the root package and workspace
+//! members) are the roots; the packages they depend on are direct
+//! dependencies and everything else reachable is transitive. Dependency kinds
+//! come from the project's manifests, so only edges leaving a root carry a
+//! kind other than normal.
+
+use crate::dependencies::read_declared_dependencies;
+use crate::errors::{Result, RustExError};
+use crate::workspace::discover_workspace;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a package sits in the dependency graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageRole {
+    /// A package of the project itself
+    Root,
+    /// A dependency of a root package
+    Direct,
+    /// A dependency reachable only through other dependencies
+    Transitive,
+}
+
+/// Kind of a dependency edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    /// `[dependencies]`
+    Normal,
+    /// `[dev-dependencies]`
+    Dev,
+    /// `[build-dependencies]`
+    Build,
+}
+
+/// A resolved package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageNode {
+    /// Unique ID, `name version`
+    pub id: String,
+    /// Package name
+    pub name: String,
+    /// Resolved version
+    pub version: String,
+    /// Where the package comes from, such as a registry or git URL (`None`
+    /// for local packages)
+    pub source: Option<String>,
+    /// Position of the package relative to the project
+    pub role: PackageRole,
+    /// Length of the shortest path from a root package
+    pub depth: usize,
+}
+
+/// A dependency of one package on another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    /// ID of the depending package
+    pub from: String,
+    /// ID of the package depended on
+    pub to: String,
+    /// Kind of the dependency
+    pub kind: DependencyKind,
+}
+
+/// A package resolved at more than one version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicatePackage {
+    /// Package name
+    pub name: String,
+    /// Resolved versions, in lockfile order
+    pub versions: Vec<String>,
+}
+
+/// The packages of a project and the dependencies between them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    /// Packages reachable from the roots, sorted by role, then name and version
+    pub packages: Vec<PackageNode>,
+    /// Dependencies between those packages
+    pub edges: Vec<DependencyEdge>,
+    /// Packages resolved at several versions
+    pub duplicates: Vec<DuplicatePackage>,
+}
+
+impl DependencyGraph {
+    /// Build the graph of the project at `project_root` from its `Cargo.lock`.
+    ///
+    /// The lockfile of a workspace member is at the workspace root, so it is
+    /// looked up in `project_root` and then in its ancestors.
+    pub fn from_project(project_root: &Path) -> Result<Self> {
+        let lockfile = find_lockfile(project_root)
+            .and_then(|lock_path| fs::read_to_string(lock_path).ok())
+            .ok_or_else(|| {
+                RustExError::Config(format!(
+                    "No Cargo.lock found in {} or its parent directories; \
+                     run `cargo generate-lockfile` first",
+                    project_root.display()
+                ))
+            })?;
+
+        let mut manifests = vec![project_root.to_path_buf()];
+        if let Some(members) = discover_workspace(project_root)? {
+            manifests.extend(
+                members
+                    .iter()
+                    .map(|member| project_root.join(&member.relative_path)),
+            );
+        }
+        let mut roots = BTreeMap::new();
+        for crate_root in manifests {
+            let manifest_path = crate_root.join("Cargo.toml");
+            let Some(name) = package_name(&manifest_path) else {
+                continue;
+            };
+            let declared = read_declared_dependencies(&manifest_path)?;
+            let mut kinds = HashMap::new();
+            // Later kinds win, so a crate that is also a normal dependency is normal.
+            for (names, kind) in [
+                (&declared.dev, DependencyKind::Dev),
+                (&declared.build, DependencyKind::Build),
+                (&declared.normal, DependencyKind::Normal),
+            ] {
+                for dependency in names {
+                    kinds.insert(dependency.clone(), kind);
+                }
+            }
+            roots.insert(name, kinds);
+        }
+
+        Self::from_lockfile(&lockfile, &roots)
+    }
+
+    /// Build the graph from the contents of a `Cargo.lock`.
+    ///
+    /// `roots` maps the names of the project's packages to the kinds of their
+    /// declared dependencies, by package name; undeclared ones are normal.
+    pub fn from_lockfile(
+        lockfile: &str,
+        roots: &BTreeMap<String, HashMap<String, DependencyKind>>,
+    ) -> Result<Self> {
+        let lockfile: toml::Value = toml::from_str(lockfile)
+            .map_err(|e| RustExError::Config(format!("Failed to parse Cargo.lock: {}", e)))?;
+        let packages = locked_packages(&lockfile);
+
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, package) in packages.iter().enumerate() {
+            by_name.entry(&package.name).or_default().push(index);
+        }
+        // A reference is `name`, `name version` or `name version (source)`.
+        let resolve = |reference: &str| {
+            let mut parts = reference.split_whitespace();
+            let candidates = by_name.get(parts.next()?)?;
+            match parts.next() {
+                Some(version) => candidates
+                    .iter()
+                    .copied()
+                    .find(|&index| packages[index].version == version),
+                None => candidates.first().copied(),
+            }
+        };
+
+        // Breadth-first from the roots gives each package its shortest depth.
+        let mut depths: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for (index, package) in packages.iter().enumerate() {
+            if package.source.is_none() && roots.contains_key(&package.name) {
+                depths.insert(index, 0);
+                queue.push_back(index);
+            }
+        }
+        let mut edges = Vec::new();
+        while let Some(index) = queue.pop_front() {
+            let package = &packages[index];
+            for reference in &package.dependencies {
+                let Some(target) = resolve(reference) else {
+                    tracing::debug!("Unresolved Cargo.lock dependency {}", reference);
+                    continue;
+                };
+                let kind = roots
+                    .get(&package.name)
+                    .filter(|_| depths[&index] == 0)
+                    .and_then(|kinds| kinds.get(&packages[target].name))
+                    .copied()
+                    .unwrap_or(DependencyKind::Normal);
+                edges.push(DependencyEdge {
+                    from: package.id(),
+                    to: packages[target].id(),
+                    kind,
+                });
+                if !depths.contains_key(&target) {
+                    depths.insert(target, depths[&index] + 1);
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        let mut nodes: Vec<PackageNode> = depths
+            .iter()
+            .map(|(&index, &depth)| {
+                let package = &packages[index];
+                PackageNode {
+                    id: package.id(),
+                    name: package.name.clone(),
+                    version: package.version.clone(),
+                    source: package.source.clone(),
+                    role: match depth {
+                        0 => PackageRole::Root,
+                        1 => PackageRole::Direct,
+                        _ => PackageRole::Transitive,
+                    },
+                    depth,
+                }
+            })
+            .collect();
+        nodes.sort_by(|a, b| (a.role, &a.name, &a.version).cmp(&(b.role, &b.name, &b.version)));
+        edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+        edges.dedup();
+
+        let mut versions: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for index in depths.keys() {
+            let package = &packages[*index];
+            versions
+                .entry(&package.name)
+                .or_default()
+                .push(package.version.clone());
+        }
+        let duplicates = versions
+            .into_iter()
+            .filter(|(_, versions)| versions.len() > 1)
+            .map(|(name, mut versions)| {
+                versions.sort_by_key(|version| {
+                    packages
+                        .iter()
+                        .position(|p| p.name == name && &p.version == version)
+                });
+                DuplicatePackage {
+                    name: name.to_string(),
+                    versions,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            packages: nodes,
+            edges,
+            duplicates,
+        })
+    }
+
+    /// Packages with the given role.
+    pub fn packages_with_role(&self, role: PackageRole) -> impl Iterator<Item = &PackageNode> {
+        self.packages
+            .iter()
+            .filter(move |package| package.role == role)
+    }
+}
+
+/// The `Cargo.lock` closest to `project_root`, in it or one of its ancestors.
+fn find_lockfile(project_root: &Path) -> Option<PathBuf> {
+    let project_root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    project_root
+        .ancestors()
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|lock_path| lock_path.is_file())
+}
+
+/// A `[[package]]` entry of `Cargo.lock`.
+struct LockedPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    fn id(&self) -> String {
+        format!("{} {}", self.name, self.version)
+    }
+}
+
+fn locked_packages(lockfile: &toml::Value) -> Vec<LockedPackage> {
+    let str_field = |package: &toml::Value, key: &str| {
+        package
+            .get(key)
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+    lockfile
+        .get("package")
+        .and_then(|packages| packages.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|package| {
+            Some(LockedPackage {
+                name: str_field(package, "name")?,
+                version: str_field(package, "version")?,
+                source: str_field(package, "source"),
+                dependencies: package
+                    .get("dependencies")
+                    .and_then(|deps| deps.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|dep| dep.as_str().map(str::to_string))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// The `[package] name` of a manifest, if it has one.
+fn package_name(manifest_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(manifest_path).ok()?;
+    let manifest: toml::Value = toml::from_str(&content).ok()?;
+    Some(manifest.get("package")?.get("name")?.as_str()?.to_string())
+}
@@ -0,0 +1,259 @@
+//! Comparison of two extractions of a project.
+//!
+//! Elements are matched by their stable ID first. Since the ID covers the
+//! signature, elements whose signature changed are then matched by element
+//! type and qualified name.
+
+use crate::ast_data::{CodeElement, ElementType, ProjectAst, Visibility};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Differences between two extractions of a project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AstDiff {
+    /// Elements only present in the new extraction
+    pub added: Vec<ElementSummary>,
+    /// Elements only present in the old extraction
+    pub removed: Vec<ElementSummary>,
+    /// Elements present in both whose signature, docs, visibility, variants or file changed
+    pub changed: Vec<ElementDiff>,
+}
+
+impl AstDiff {
+    /// Whether the two extractions have no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Identifying details of an added or removed element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElementSummary {
+    /// Element ID
+    pub id: String,
+    /// Element name
+    pub name: String,
+    /// Fully qualified name
+    pub qualified_name: String,
+    /// Type of the element
+    pub element_type: ElementType,
+    /// Visibility of the element
+    pub visibility: Visibility,
+    /// Signature, for elements that have one
+    pub signature: Option<String>,
+    /// File defining the element, relative to the project root
+    pub file_path: PathBuf,
+}
+
+impl ElementSummary {
+    fn new(element: &CodeElement, file_path: &Path) -> Self {
+        Self {
+            id: element.id.clone(),
+            name: element.name.clone(),
+            qualified_name: element.hierarchy.qualified_name.clone(),
+            element_type: element.element_type.clone(),
+            visibility: element.visibility.clone(),
+            signature: element.signature.clone(),
+            file_path: file_path.to_path_buf(),
+        }
+    }
+}
+
+/// An element present in both extractions, with what changed about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElementDiff {
+    /// Element ID in the old extraction
+    pub old_id: String,
+    /// Element ID in the new extraction
+    pub new_id: String,
+    /// Element name
+    pub name: String,
+    /// Fully qualified name
+    pub qualified_name: String,
+    /// Type of the element
+    pub element_type: ElementType,
+    /// Individual changes, in a fixed order
+    pub changes: Vec<ElementChange>,
+}
+
+/// A single change to an element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ElementChange {
+    /// The signature changed (ignoring whitespace)
+    Signature {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// The doc comments changed
+    Documentation { old: Vec<String>, new: Vec<String> },
+    /// The visibility changed
+    Visibility { old: Visibility, new: Visibility },
+    /// Enum variants were added or removed
+    Variants {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    /// The element moved to another file
+    Moved { old: PathBuf, new: PathBuf },
+}
+
+/// Compare two extractions of the same project.
+///
+/// Added and changed elements are listed in the order of `new`, removed ones
+/// in the order of `old`. File paths are compared relative to each project's
+/// root, so extractions of different checkouts can be compared.
+pub fn diff_projects(old: &ProjectAst, new: &ProjectAst) -> AstDiff {
+    let old_elements = elements_with_paths(old);
+    let new_elements = elements_with_paths(new);
+
+    let old_by_id: HashMap<&str, usize> = old_elements
+        .iter()
+        .enumerate()
+        .map(|(index, (_, element))| (element.id.as_str(), index))
+        .collect();
+    let mut old_matched = vec![false; old_elements.len()];
+    let mut pairs = Vec::new();
+    let mut unmatched_new = Vec::new();
+    for (new_index, (_, element)) in new_elements.iter().enumerate() {
+        match old_by_id.get(element.id.as_str()) {
+            Some(&old_index) => {
+                old_matched[old_index] = true;
+                pairs.push((old_index, new_index));
+            }
+            None => unmatched_new.push(new_index),
+        }
+    }
+
+    // A changed signature changes the ID, so fall back to matching by name.
+    let mut old_by_name: HashMap<(&ElementType, &str), VecDeque<usize>> = HashMap::new();
+    for (old_index, (_, element)) in old_elements.iter().enumerate() {
+        if !old_matched[old_index] {
+            old_by_name
+                .entry(name_key(element))
+                .or_default()
+                .push_back(old_index);
+        }
+    }
+    let mut added = Vec::new();
+    for new_index in unmatched_new {
+        let (path, element) = new_elements[new_index];
+        match old_by_name
+            .get_mut(&name_key(element))
+            .and_then(VecDeque::pop_front)
+        {
+            Some(old_index) => {
+                old_matched[old_index] = true;
+                pairs.push((old_index, new_index));
+            }
+            None => added.push(ElementSummary::new(element, path)),
+        }
+    }
+
+    let removed = old_elements
+        .iter()
+        .zip(&old_matched)
+        .filter(|(_, matched)| !**matched)
+        .map(|((path, element), _)| ElementSummary::new(element, path))
+        .collect();
+
+    pairs.sort_by_key(|&(_, new_index)| new_index);
+    let changed = pairs
+        .into_iter()
+        .filter_map(|(old_index, new_index)| {
+            diff_element(old_elements[old_index], new_elements[new_index])
+        })
+        .collect();
+
+    AstDiff {
+        added,
+        removed,
+        changed,
+    }
+}
+
+/// All elements of a project with the relative path of their file.
+fn elements_with_paths(project: &ProjectAst) -> Vec<(&Path, &CodeElement)> {
+    project
+        .files
+        .iter()
+        .flat_map(|file| {
+            file.elements
+                .iter()
+                .map(|element| (file.relative_path.as_path(), element))
+        })
+        .collect()
+}
+
+fn name_key(element: &CodeElement) -> (&ElementType, &str) {
+    (&element.element_type, &element.hierarchy.qualified_name)
+}
+
+/// Changes between two versions of an element, if there are any.
+fn diff_element(
+    (old_path, old): (&Path, &CodeElement),
+    (new_path, new): (&Path, &CodeElement),
+) -> Option<ElementDiff> {
+    let mut changes = Vec::new();
+    if normalized_signature(old) != normalized_signature(new) {
+        changes.push(ElementChange::Signature {
+            old: old.signature.clone(),
+            new: new.signature.clone(),
+        });
+    }
+    if old.doc_comments != new.doc_comments {
+        changes.push(ElementChange::Documentation {
+            old: old.doc_comments.clone(),
+            new: new.doc_comments.clone(),
+        });
+    }
+    if old.visibility != new.visibility {
+        changes.push(ElementChange::Visibility {
+            old: old.visibility.clone(),
+            new: new.visibility.clone(),
+        });
+    }
+    let (old_variants, new_variants) = (old.enum_variants(), new.enum_variants());
+    let added_variants: Vec<String> = new_variants
+        .iter()
+        .filter(|variant| !old_variants.contains(variant))
+        .map(ToString::to_string)
+        .collect();
+    let removed_variants: Vec<String> = old_variants
+        .iter()
+        .filter(|variant| !new_variants.contains(variant))
+        .map(ToString::to_string)
+        .collect();
+    if !added_variants.is_empty() || !removed_variants.is_empty() {
+        changes.push(ElementChange::Variants {
+            added: added_variants,
+            removed: removed_variants,
+        });
+    }
+    if old_path != new_path {
+        changes.push(ElementChange::Moved {
+            old: old_path.to_path_buf(),
+            new: new_path.to_path_buf(),
+        });
+    }
+
+    if changes.is_empty() {
+        return None;
+    }
+    Some(ElementDiff {
+        old_id: old.id.clone(),
+        new_id: new.id.clone(),
+        name: new.name.clone(),
+        qualified_name: new.hierarchy.qualified_name.clone(),
+        element_type: new.element_type.clone(),
+        changes,
+    })
+}
+
+fn normalized_signature(element: &CodeElement) -> Option<String> {
+    element
+        .signature
+        .as_ref()
+        .map(|signature| signature.split_whitespace().collect())
+}
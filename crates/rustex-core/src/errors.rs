@@ -1,5 +1,6 @@
 //! Error types and handling for RustEx.
 
+use crate::ast_data::ExtractionReport;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -30,6 +31,10 @@ pub enum RustExError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    /// Invalid query expression
+    #[error("Invalid query: {0}")]
+    Query(String),
+
     /// Serialization errors
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -41,6 +46,14 @@ pub enum RustExError {
         total_count: usize,
         errors: Vec<FileProcessingError>,
     },
+
+    /// Files skipped or partly extracted with `strict` enabled
+    #[error(
+        "Strict mode: files were skipped or partly extracted ({} failed, {} incomplete)",
+        .report.failed.len(),
+        .report.incomplete_file_count()
+    )]
+    Strict { report: ExtractionReport },
 }
 
 /// Specific error for file processing failures.
@@ -57,6 +70,9 @@ pub enum FileProcessingError {
 
     #[error("Access denied: {file}")]
     AccessDenied { file: PathBuf },
+
+    #[error("Invalid encoding in {file}: {error}")]
+    InvalidEncoding { file: PathBuf, error: String },
 }
 
 impl FileProcessingError {
@@ -66,6 +82,7 @@ impl FileProcessingError {
             FileProcessingError::IoError { file, .. } => file,
             FileProcessingError::TooLarge { file, .. } => file,
             FileProcessingError::AccessDenied { file } => file,
+            FileProcessingError::InvalidEncoding { file, .. } => file,
         }
     }
 }
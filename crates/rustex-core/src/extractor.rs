@@ -1,10 +1,30 @@
 //! Core AST extraction functionality.
 
-use crate::{ast_data::*, config::ExtractorConfig, errors::*, visitors::CodeElementVisitor};
+use crate::{
+    ast_data::*,
+    comments,
+    config::{ExtractorConfig, PathOverride},
+    dependencies::{self, CargoSettings},
+    dependency_graph::{DependencyGraph, PackageRole},
+    errors::*,
+    macro_expansion, module_resolution, ownership, parse_recovery, reexports, source_encoding,
+    unsafe_usage,
+    visitors::{self, CodeElementVisitor, ElementIdDeduplicator},
+    workspace::{self, WorkspaceMember},
+};
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use syn::visit::Visit;
-use walkdir::WalkDir;
+
+/// Name of the rustex-specific ignore file, using `.gitignore` syntax.
+pub(crate) const RUSTEX_IGNORE_FILE: &str = ".rustexignore";
+
+/// Number of files parsed per batch by [`FileAstIter`], per worker thread.
+const FILES_PER_WORKER_BATCH: usize = 4;
 
 /// Main AST extractor for Rust projects.
 pub struct AstExtractor {
@@ -12,6 +32,8 @@ pub struct AstExtractor {
     config: ExtractorConfig,
     /// Root path of the project to extract
     root_path: PathBuf,
+    /// Cargo invocation used to locate dependency sources
+    cargo: CargoSettings,
 }
 
 impl AstExtractor {
@@ -21,7 +43,18 @@ impl AstExtractor {
     /// * `config` - Configuration for extraction behavior
     /// * `root_path` - Root directory of the Rust project
     pub fn new(config: ExtractorConfig, root_path: PathBuf) -> Self {
-        Self { config, root_path }
+        Self {
+            config,
+            root_path,
+            cargo: CargoSettings::default(),
+        }
+    }
+
+    /// Use `cargo` when locating dependency sources, instead of the Cargo
+    /// home and network access of the environment.
+    pub fn with_cargo(mut self, cargo: CargoSettings) -> Self {
+        self.cargo = cargo;
+        self
     }
 
     /// Extract AST from the configured Rust project.
@@ -39,28 +72,52 @@ impl AstExtractor {
             self.root_path
         );
 
-        let project_info = self.extract_project_info()?;
-        let rust_files = self.discover_rust_files()?;
+        let mut file_iter = self.extract_iter()?;
+        let project_info = file_iter.project().clone();
+        let members = file_iter.members.clone();
 
         let mut files = Vec::new();
-        let mut project_metrics = ProjectMetrics::default();
         let mut file_errors = Vec::new();
-        let total_files = rust_files.len();
-
-        for file_path in rust_files {
-            match self.extract_file(&file_path) {
-                Ok(file_ast) => {
-                    self.update_project_metrics(&mut project_metrics, &file_ast.file_metrics);
-                    files.push(file_ast);
-                }
+        for result in &mut file_iter {
+            match result {
+                Ok(file_ast) => files.push(file_ast),
                 Err(e) => {
-                    tracing::warn!("Failed to extract AST from {:?}: {}", file_path, e);
+                    tracing::warn!("Failed to extract AST: {}", e);
                     file_errors.push(e);
                 }
             }
         }
 
+        self.assemble_project(project_info, members, files, file_errors)
+    }
+
+    /// Build the project AST from its extracted files, running the
+    /// project-wide passes.
+    ///
+    /// `files` must have unique element IDs and be in discovery order;
+    /// `file_errors` holds the files that failed to extract.
+    pub(crate) fn assemble_project(
+        &self,
+        project_info: ProjectInfo,
+        members: Option<Vec<WorkspaceMember>>,
+        mut files: Vec<FileAst>,
+        file_errors: Vec<FileProcessingError>,
+    ) -> Result<ProjectAst> {
+        let mut extraction_report = ExtractionReport::default();
+        for error in &file_errors {
+            extraction_report.add_failure(&self.root_path, error);
+        }
+        for file in &files {
+            extraction_report.add_file(file);
+        }
+        if self.config.strict && !extraction_report.is_empty() {
+            return Err(RustExError::Strict {
+                report: extraction_report,
+            });
+        }
+
         // Check if we have too many failures
+        let total_files = files.len() + file_errors.len();
         let failed_count = file_errors.len();
         if failed_count > 0 {
             let success_rate = 1.0 - (failed_count as f64 / total_files as f64);
@@ -82,24 +139,469 @@ impl AstExtractor {
             }
         }
 
+        if self.config.expand_macros {
+            self.merge_generated_elements(&mut files, members.as_deref(), &project_info.name);
+        }
+        if self.config.deterministic_output {
+            files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+            for file in &mut files {
+                file.elements.sort_by_key(|element| {
+                    (element.location.line_start, element.location.char_start)
+                });
+            }
+        }
+        // After macro expansion, as generated elements change per-file counts.
+        let mut project_metrics = ProjectMetrics::default();
+        for file in &files {
+            self.update_project_metrics(&mut project_metrics, &file.file_metrics);
+        }
+
         let dependencies = self.extract_dependencies()?;
-        
+        let dependencies_ast = if self.config.parse_dependencies {
+            self.extract_dependency_sources()
+        } else {
+            Vec::new()
+        };
+
+        self.resolve_reexports(&mut files, members.as_deref(), &project_info.name);
+
+        // Link references and impl blocks across files before collecting them
+        let reference_graph = visitors::resolve_project_references(&mut files);
+        let type_index =
+            visitors::build_type_index(files.iter_mut().flat_map(|f| f.elements.iter_mut()));
+        let impls = visitors::collect_trait_impls(&files);
+        project_metrics.deprecations = visitors::summarize_deprecations(&files, &reference_graph);
+
         // Collect all cross-references from files
         let mut all_cross_references = Vec::new();
         for file in &files {
             all_cross_references.extend(file.cross_references.clone());
         }
 
+        let workspace = members.map(|members| self.build_workspace_ast(&members, &files));
+
         Ok(ProjectAst {
             project: project_info,
             files,
             dependencies,
             metrics: project_metrics,
-            extracted_at: chrono::Utc::now(),
+            extracted_at: self.extraction_time(),
             cross_references: all_cross_references,
+            dependencies_ast,
+            workspace,
+            reference_graph,
+            type_index,
+            impls,
+            truncation: None,
+            extraction_report,
+            plugin_results: BTreeMap::new(),
+        })
+    }
+
+    /// The time recorded as the extraction time: `SOURCE_DATE_EPOCH` when it
+    /// is set and output is deterministic, otherwise now.
+    fn extraction_time(&self) -> chrono::DateTime<chrono::Utc> {
+        std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .filter(|_| self.config.deterministic_output)
+            .and_then(|epoch| epoch.trim().parse().ok())
+            .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+            .unwrap_or_else(chrono::Utc::now)
+    }
+
+    /// Extract the project's files one batch at a time.
+    ///
+    /// Files are discovered up front, then parsed in parallel batches as the
+    /// iterator is advanced, so only one batch is held in memory. Files come
+    /// out in the same order, and with the same element IDs, as in
+    /// [`extract_project`](Self::extract_project).
+    ///
+    /// Only per-file processing is applied: references are resolved within
+    /// each file, and macro expansion and project-wide passes (cross-file
+    /// references, type index, trait impls) are skipped.
+    pub fn extract_iter(&self) -> Result<FileAstIter<'_>> {
+        let discovered = self.discover_files()?;
+        Ok(FileAstIter {
+            extractor: self,
+            project_info: discovered.project_info,
+            members: discovered.members,
+            files: discovered.files.into_iter(),
+            module_overrides: discovered.module_overrides,
+            pool: self.worker_pool()?,
+            batch: VecDeque::new(),
+            ids: ElementIdDeduplicator::default(),
+        })
+    }
+
+    /// Extract a single file without discovering the rest of the project, e.g.
+    /// for editor integrations.
+    ///
+    /// The file needn't be part of a Cargo project. Its module path is derived
+    /// from its location, and project-wide passes are skipped as in
+    /// [`extract_iter`](Self::extract_iter).
+    pub fn extract_single_file(&self, file_path: &Path) -> FileResult<FileAst> {
+        let crate_name = self.standalone_crate_name();
+        self.extract_file(file_path, &crate_name, None, FileKind::Source)
+    }
+
+    /// Extract Rust source that doesn't come from disk, such as stdin, as a
+    /// crate root named `file_path`.
+    pub fn extract_source(&self, file_path: &Path, source: &str) -> FileResult<FileAst> {
+        let crate_name = self.standalone_crate_name();
+        let bytes = source.as_bytes().to_vec();
+        self.extract_bytes(
+            file_path,
+            bytes,
+            &crate_name,
+            Some("crate"),
+            FileKind::Source,
+        )
+    }
+
+    /// Crate name of files extracted on their own: the package at the root
+    /// path if there is one.
+    fn standalone_crate_name(&self) -> String {
+        self.extract_project_info()
+            .map(|info| info.name)
+            .unwrap_or_else(|_| "unknown-project".to_string())
+    }
+
+    /// Find the files to extract, with the crate and kind of target of each.
+    pub(crate) fn discover_files(&self) -> Result<DiscoveredFiles> {
+        let project_info = self.extract_project_info()?;
+        let members = self.discover_workspace_members()?;
+        let mut rust_files = self.discover_rust_files()?;
+        // Only the selected crates are extracted when a crate filter is set.
+        if let Some(members) = members.as_ref().filter(|_| !self.config.crates.is_empty()) {
+            rust_files.retain(|path| {
+                let relative = path.strip_prefix(&self.root_path).unwrap_or(path);
+                workspace::member_for_path(members, relative)
+                    .is_some_and(|member| self.is_selected_crate(&member.name))
+            });
+        }
+
+        // Files reached only through `#[path]` or `include!` are extracted too,
+        // unless they are explicitly excluded.
+        let mut module_overrides = module_resolution::resolve_module_overrides(&rust_files);
+        for path in module_overrides.keys() {
+            if path.is_file() && !rust_files.contains(path) && !self.is_excluded_file(path) {
+                rust_files.push(path.clone());
+            }
+        }
+
+        // Build scripts live outside `src`, so they are added per package.
+        let packages: Vec<(PathBuf, String)> = match &members {
+            Some(members) => members
+                .iter()
+                .filter(|member| self.is_selected_crate(&member.name))
+                .map(|member| {
+                    (
+                        self.root_path.join(&member.relative_path),
+                        member.name.clone(),
+                    )
+                })
+                .collect(),
+            None => vec![(self.root_path.clone(), project_info.name.clone())],
+        };
+        let mut build_scripts = HashSet::new();
+        let mut proc_macro_crates = HashSet::new();
+        for (crate_root, crate_name) in packages {
+            let targets = workspace::package_targets(&crate_root);
+            if targets.is_proc_macro {
+                proc_macro_crates.insert(crate_name);
+            }
+            let Some(build_script) = targets.build_script else {
+                continue;
+            };
+            if self.config.filters.exclude_build_scripts {
+                rust_files.retain(|path| *path != build_script);
+                continue;
+            }
+            if !rust_files.contains(&build_script) && !self.is_excluded_file(&build_script) {
+                rust_files.push(build_script.clone());
+            }
+            // A build script is the root of its own crate.
+            module_overrides
+                .entry(module_resolution::normalize_path(&build_script))
+                .or_insert_with(|| "crate".to_string());
+            build_scripts.insert(build_script);
+        }
+        rust_files.sort();
+
+        let files: Vec<(PathBuf, String, FileKind)> = rust_files
+            .into_iter()
+            .map(|path| {
+                let relative = path.strip_prefix(&self.root_path).unwrap_or(&path);
+                let crate_name = members
+                    .as_ref()
+                    .and_then(|members| workspace::member_for_path(members, relative))
+                    .map_or_else(|| project_info.name.clone(), |member| member.name.clone());
+                let kind = if build_scripts.contains(&path) {
+                    FileKind::BuildScript
+                } else if proc_macro_crates.contains(&crate_name) {
+                    FileKind::ProcMacro
+                } else {
+                    FileKind::Source
+                };
+                (path, crate_name, kind)
+            })
+            .collect();
+
+        Ok(DiscoveredFiles {
+            project_info,
+            members,
+            files,
+            module_overrides,
         })
     }
 
+    /// Pool of `config.parallel_workers` threads, or of one thread per
+    /// available core when it is 0.
+    pub(crate) fn worker_pool(&self) -> Result<rayon::ThreadPool> {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.config.parallel_workers)
+            .build()
+            .map_err(|e| RustExError::Config(format!("Failed to create worker pool: {}", e)))
+    }
+
+    /// Parse files in parallel, returning one result per input file in input order.
+    ///
+    /// Element IDs are only unique within each file.
+    pub(crate) fn parse_files(
+        &self,
+        pool: &rayon::ThreadPool,
+        files: &[(PathBuf, String, FileKind)],
+        module_overrides: &BTreeMap<PathBuf, String>,
+    ) -> Vec<FileResult<FileAst>> {
+        pool.install(|| {
+            files
+                .par_iter()
+                .map(|(file_path, crate_name, kind)| {
+                    let module_path = module_overrides
+                        .get(&module_resolution::normalize_path(file_path))
+                        .map(String::as_str);
+                    self.extract_file(file_path, crate_name, module_path, *kind)
+                })
+                .collect()
+        })
+    }
+
+    /// Resolve the `pub use` re-exports of each crate in `files`.
+    fn resolve_reexports(
+        &self,
+        files: &mut [FileAst],
+        members: Option<&[WorkspaceMember]>,
+        project_name: &str,
+    ) {
+        let mut crates: BTreeMap<&str, Vec<&mut FileAst>> = BTreeMap::new();
+        for file in files
+            .iter_mut()
+            .filter(|file| file.kind != FileKind::BuildScript)
+        {
+            let crate_name = members
+                .and_then(|members| workspace::member_for_path(members, &file.relative_path))
+                .map_or(project_name, |member| member.name.as_str());
+            crates.entry(crate_name).or_default().push(file);
+        }
+        for crate_files in crates.into_values() {
+            reexports::resolve_reexports(crate_files);
+        }
+    }
+
+    /// Expand macros in each crate and merge the generated elements into `files`.
+    ///
+    /// Each generated element is added to the file that defines its module,
+    /// falling back to the crate root file.
+    ///
+    /// # Error Handling
+    /// Crates that cannot be expanded (e.g. the build fails or no toolchain
+    /// supports expansion) are logged and keep only their source elements.
+    fn merge_generated_elements(
+        &self,
+        files: &mut [FileAst],
+        members: Option<&[WorkspaceMember]>,
+        project_name: &str,
+    ) {
+        let crates: Vec<(String, PathBuf)> = match members {
+            Some(members) => members
+                .iter()
+                .filter(|m| self.is_selected_crate(&m.name))
+                .map(|m| (m.name.clone(), m.relative_path.clone()))
+                .collect(),
+            None => vec![(project_name.to_string(), PathBuf::new())],
+        };
+
+        for (crate_name, relative_dir) in crates {
+            let crate_dir = self.root_path.join(&relative_dir);
+            let Some(root_file) = macro_expansion::crate_root_file(&crate_dir) else {
+                continue;
+            };
+
+            let expanded = match macro_expansion::expand_crate(&crate_dir) {
+                Ok(expanded) => expanded,
+                Err(e) => {
+                    tracing::warn!("Failed to expand macros in {:?}: {}", crate_dir, e);
+                    continue;
+                }
+            };
+
+            // Files of other workspace members share module paths, so only
+            // match against this crate's files.
+            let crate_files: Vec<usize> = (0..files.len())
+                .filter(|&i| {
+                    let file_crate = members
+                        .and_then(|m| workspace::member_for_path(m, &files[i].relative_path));
+                    members.is_none() || file_crate.is_some_and(|m| m.relative_path == relative_dir)
+                })
+                .collect();
+            let original: Vec<&CodeElement> = crate_files
+                .iter()
+                .flat_map(|&i| &files[i].elements)
+                .collect();
+
+            let id_namespace = format!("{}#expanded", crate_name);
+            let generated = match macro_expansion::generated_elements(
+                &expanded,
+                &root_file,
+                &id_namespace,
+                &original,
+                &self.config,
+            ) {
+                Ok(generated) => generated,
+                Err(e) => {
+                    tracing::warn!("Failed to parse expanded source of {:?}: {}", crate_dir, e);
+                    continue;
+                }
+            };
+
+            let module_files: Vec<(usize, String)> = crate_files
+                .iter()
+                .map(|&i| {
+                    let path = files[i]
+                        .relative_path
+                        .strip_prefix(&relative_dir)
+                        .unwrap_or(&files[i].relative_path);
+                    (i, CodeElementVisitor::extract_module_path(path))
+                })
+                .collect();
+            let root_index = crate_files
+                .iter()
+                .copied()
+                .find(|&i| files[i].path == root_file);
+
+            for mut element in generated {
+                let target = module_files
+                    .iter()
+                    .find(|(_, module_path)| *module_path == element.hierarchy.module_path)
+                    .map(|(i, _)| *i)
+                    .or(root_index);
+                let Some(target) = target else {
+                    continue;
+                };
+
+                let file = &mut files[target];
+                element.location.file_path = file.path.clone();
+                // The name is in the expansion, not in the file.
+                element.name_location = None;
+                count_element(&mut file.file_metrics, &element);
+                file.elements.push(element);
+            }
+        }
+    }
+
+    /// Discover workspace members and validate the configured crate filter.
+    ///
+    /// Returns `None` when the root is not a workspace. Crate names in the
+    /// filter that are not workspace members are a configuration error.
+    fn discover_workspace_members(&self) -> Result<Option<Vec<WorkspaceMember>>> {
+        let members = match workspace::discover_workspace_with(&self.root_path, &self.cargo) {
+            Ok(members) => members,
+            Err(e) => {
+                tracing::warn!("Failed to discover workspace members: {}", e);
+                None
+            }
+        };
+
+        if self.config.crates.is_empty() {
+            return Ok(members);
+        }
+
+        let Some(members) = members else {
+            return Err(RustExError::Config(format!(
+                "Crate filter {:?} requires a Cargo workspace at {}",
+                self.config.crates,
+                self.root_path.display()
+            )));
+        };
+
+        if let Some(unknown) = self
+            .config
+            .crates
+            .iter()
+            .find(|name| !members.iter().any(|m| &m.name == *name))
+        {
+            let available: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+            return Err(RustExError::Config(format!(
+                "Unknown workspace crate '{}' (available: {})",
+                unknown,
+                available.join(", ")
+            )));
+        }
+
+        Ok(Some(members))
+    }
+
+    /// Check whether a workspace crate passes the configured crate filter.
+    fn is_selected_crate(&self, name: &str) -> bool {
+        self.config.crates.is_empty() || self.config.crates.iter().any(|c| c == name)
+    }
+
+    /// Group extracted files into per-crate sections with their own metrics.
+    fn build_workspace_ast(&self, members: &[WorkspaceMember], files: &[FileAst]) -> WorkspaceAst {
+        let mut crates: Vec<CrateAst> = members
+            .iter()
+            .filter(|member| self.is_selected_crate(&member.name))
+            .map(|member| CrateAst {
+                name: member.name.clone(),
+                version: member.version.clone(),
+                relative_path: member.relative_path.clone(),
+                files: Vec::new(),
+                metrics: ProjectMetrics::default(),
+            })
+            .collect();
+
+        for file in files {
+            let Some(member) = workspace::member_for_path(members, &file.relative_path) else {
+                continue;
+            };
+            if let Some(crate_ast) = crates.iter_mut().find(|c| c.name == member.name) {
+                self.update_project_metrics(&mut crate_ast.metrics, &file.file_metrics);
+                crate_ast.files.push(file.relative_path.clone());
+            }
+        }
+
+        let dependency_edges = members
+            .iter()
+            .filter(|member| self.is_selected_crate(&member.name))
+            .flat_map(|member| {
+                member
+                    .workspace_dependencies
+                    .iter()
+                    .filter(|(to, _)| self.is_selected_crate(to))
+                    .map(|(to, kind)| CrateDependency {
+                        from: member.name.clone(),
+                        to: to.clone(),
+                        kind: kind.clone(),
+                    })
+            })
+            .collect();
+
+        WorkspaceAst {
+            crates,
+            dependency_edges,
+        }
+    }
+
     /// Extract project metadata from Cargo.toml.
     fn extract_project_info(&self) -> Result<ProjectInfo> {
         let cargo_toml_path = self.root_path.join("Cargo.toml");
@@ -139,21 +641,49 @@ impl AstExtractor {
     }
 
     /// Discover all Rust files in the project.
+    ///
+    /// Files matched by a `.rustexignore` are always skipped. `.gitignore`,
+    /// `.ignore` and git exclude files are honored unless
+    /// `respect_gitignore` is disabled, even outside a git repository.
     fn discover_rust_files(&self) -> Result<Vec<PathBuf>> {
         let mut rust_files = Vec::new();
 
-        for entry in WalkDir::new(&self.root_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+        let walker = WalkBuilder::new(&self.root_path)
+            .standard_filters(self.config.respect_gitignore)
+            .hidden(false)
+            .require_git(false)
+            .follow_links(self.config.follow_symlinks)
+            .add_custom_ignore_filename(RUSTEX_IGNORE_FILE)
+            .build();
+
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // Includes symlink loops, which the walker detects and skips.
+                    tracing::warn!("Skipping path during discovery: {}", e);
+                    continue;
+                }
+            };
             let path = entry.path();
+            // Unfollowed symlinks to files are skipped like those to directories.
+            if entry.path_is_symlink() && !self.config.follow_symlinks {
+                continue;
+            }
 
             if path.extension().and_then(|s| s.to_str()) == Some("rs") && self.should_include_file(path) {
                 rust_files.push(path.to_path_buf());
             }
         }
 
+        // Directory traversal order is platform dependent; sort for stable output.
+        rust_files.sort();
+
+        // Symlinks can make a file reachable through several paths; keep the first.
+        let mut seen = HashSet::new();
+        rust_files
+            .retain(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())));
+
         tracing::debug!("Found {} Rust files", rust_files.len());
         Ok(rust_files)
     }
@@ -162,11 +692,11 @@ impl AstExtractor {
     fn should_include_file(&self, path: &Path) -> bool {
         let path_str = path.to_string_lossy();
 
-        // Check exclude patterns
-        for exclude in &self.config.filters.exclude {
-            if glob_match(exclude, &path_str) {
-                return false;
-            }
+        if let Some(extract) = self.forced_extraction(path) {
+            return extract;
+        }
+        if self.is_excluded_file(path) {
+            return false;
         }
 
         // Check include patterns
@@ -179,9 +709,72 @@ impl AstExtractor {
         false
     }
 
+    /// Check if a file matches one of the exclude patterns.
+    fn is_excluded_file(&self, path: &Path) -> bool {
+        if let Some(extract) = self.forced_extraction(path) {
+            return !extract;
+        }
+        let path_str = path.to_string_lossy();
+        self.config
+            .filters
+            .exclude
+            .iter()
+            .any(|exclude| glob_match(exclude, &path_str))
+    }
+
+    /// The `filters.overrides` whose pattern matches the file at `path`, least
+    /// specific first. Patterns are matched against the path relative to the root.
+    fn path_overrides(&self, path: &Path) -> Vec<&PathOverride> {
+        let relative = path.strip_prefix(&self.root_path).unwrap_or(path);
+        let relative = relative.to_string_lossy();
+        let mut matching: Vec<(&String, &PathOverride)> = self
+            .config
+            .filters
+            .overrides
+            .iter()
+            .filter(|(pattern, _)| glob_match(pattern, &relative))
+            .collect();
+        matching.sort_by_key(|(pattern, _)| pattern.len());
+        matching
+            .into_iter()
+            .map(|(_, path_override)| path_override)
+            .collect()
+    }
+
+    /// Whether `filters.overrides` force the file at `path` in or out of the extraction.
+    fn forced_extraction(&self, path: &Path) -> Option<bool> {
+        self.path_overrides(path)
+            .into_iter()
+            .rev()
+            .find_map(|path_override| path_override.extract)
+    }
+
+    /// The configuration for the file at `path`, with its `filters.overrides` applied.
+    fn file_config(&self, path: &Path) -> Cow<'_, ExtractorConfig> {
+        let overrides = self.path_overrides(path);
+        if overrides.is_empty() {
+            return Cow::Borrowed(&self.config);
+        }
+        let mut config = self.config.clone();
+        for path_override in overrides {
+            path_override.apply(&mut config);
+        }
+        Cow::Owned(config)
+    }
+
     /// Extract AST from a single file.
-    fn extract_file(&self, file_path: &Path) -> FileResult<FileAst> {
-        let content = fs::read_to_string(file_path).map_err(|e| {
+    ///
+    /// Element IDs are namespaced by `crate_name`. `module_path` overrides the
+    /// module path derived from the file's location. Elements of build scripts
+    /// and proc-macro crates are tagged with their `kind`.
+    fn extract_file(
+        &self,
+        file_path: &Path,
+        crate_name: &str,
+        module_path: Option<&str>,
+        kind: FileKind,
+    ) -> FileResult<FileAst> {
+        let bytes = fs::read(file_path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::PermissionDenied {
                 FileProcessingError::AccessDenied {
                     file: file_path.to_path_buf(),
@@ -193,35 +786,149 @@ impl AstExtractor {
                 }
             }
         })?;
+        self.extract_bytes(file_path, bytes, crate_name, module_path, kind)
+    }
 
-        if content.len() > self.config.max_file_size {
-            return Err(FileProcessingError::TooLarge {
+    /// Extract AST from the contents of `file_path`, see
+    /// [`extract_file`](Self::extract_file).
+    fn extract_bytes(
+        &self,
+        file_path: &Path,
+        bytes: Vec<u8>,
+        crate_name: &str,
+        module_path: Option<&str>,
+        kind: FileKind,
+    ) -> FileResult<FileAst> {
+        let config = self.file_config(file_path);
+        let mut warnings = Vec::new();
+        let partially_extracted = bytes.len() > config.max_file_size;
+        if partially_extracted {
+            let error = FileProcessingError::TooLarge {
                 file: file_path.to_path_buf(),
-                size: content.len(),
-            });
+                size: bytes.len(),
+            };
+            if !config.partial_oversize_files {
+                return Err(error);
+            }
+            let warning = format!("{}, extracted signatures only", error);
+            tracing::warn!("{}", warning);
+            warnings.push(warning);
         }
 
-        let syntax_tree =
-            syn::parse_file(&content).map_err(|e| FileProcessingError::ParseError {
-                file: file_path.to_path_buf(),
-                error: e.to_string(),
+        let decoded =
+            source_encoding::decode_source(bytes, config.encoding_fallback).map_err(|error| {
+                FileProcessingError::InvalidEncoding {
+                    file: file_path.to_path_buf(),
+                    error,
+                }
             })?;
+        if let Some(error) = decoded.lossy {
+            let warning = FileProcessingError::InvalidEncoding {
+                file: file_path.to_path_buf(),
+                error: format!("{}, replaced invalid bytes", error),
+            };
+            tracing::warn!("{}", warning);
+            warnings.push(warning.to_string());
+        }
+        let (content, bom_len) = (decoded.text, decoded.bom_len);
+
+        let (mut syntax_tree, parse_errors, item_lines) = match syn::parse_file(&content) {
+            Ok(syntax_tree) => (syntax_tree, Vec::new(), Vec::new()),
+            Err(e) => {
+                // Files where no item parses are still failures.
+                let recovered = config
+                    .recover_parse_errors
+                    .then(|| parse_recovery::parse_items(&content))
+                    .filter(|recovered| !recovered.file.items.is_empty());
+                let Some(recovered) = recovered else {
+                    return Err(FileProcessingError::ParseError {
+                        file: file_path.to_path_buf(),
+                        error: e.to_string(),
+                    });
+                };
+                tracing::warn!(
+                    "Skipped {} unparseable span(s) in {:?}: {}",
+                    recovered.errors.len(),
+                    file_path,
+                    e
+                );
+                (recovered.file, recovered.errors, recovered.item_lines)
+            }
+        };
+        if partially_extracted {
+            strip_bodies(&mut syntax_tree.items);
+        }
 
         let relative_path = file_path
             .strip_prefix(&self.root_path)
             .unwrap_or(file_path)
             .to_path_buf();
 
-        let mut visitor = CodeElementVisitor::new(file_path.to_path_buf(), &self.config);
+        // Build scripts are separate crates whose paths overlap the package's.
+        let id_namespace = match kind {
+            FileKind::BuildScript => format!("{}#build", crate_name),
+            _ => crate_name.to_string(),
+        };
+        let mut visitor = CodeElementVisitor::new(file_path.to_path_buf(), &config)
+            .with_id_namespace(id_namespace)
+            .with_item_line_offsets(item_lines);
+        let module_path = match module_path {
+            Some(module_path) => {
+                visitor = visitor.with_module_path(module_path);
+                module_path.to_string()
+            }
+            None => CodeElementVisitor::extract_module_path(file_path),
+        };
         
         // Extract imports first so they can be processed during AST traversal
-        let imports = extract_imports(&syntax_tree);
+        let imports = extract_imports(&syntax_tree, &module_path);
         visitor.process_imports(&imports);
         
         visitor.visit_file(&syntax_tree);
 
-        let (elements, cross_references) = visitor.into_elements_and_references();
-        let file_metrics = calculate_file_metrics(&content, &elements);
+        let (mut elements, mut cross_references) = visitor.into_elements_and_references();
+        if !config.filters.visibility.is_empty() {
+            filter_by_visibility(
+                &config.filters.visibility,
+                &mut elements,
+                &mut cross_references,
+            );
+        }
+        if partially_extracted {
+            for element in &mut elements {
+                element.complexity = None;
+                element.complexity_metrics = None;
+            }
+        } else if config.include_inline_comments {
+            comments::attach_comments(&mut elements, comments::extract_comments(&content));
+        }
+        if kind != FileKind::Source {
+            for element in &mut elements {
+                element
+                    .metadata
+                    .insert(FILE_KIND_METADATA_KEY.to_string(), kind.as_str().into());
+            }
+        }
+        let line_starts = line_start_offsets(&content);
+        let offset = |line, column| bom_len + byte_offset(&content, &line_starts, line, column);
+        let locations = elements
+            .iter_mut()
+            .flat_map(|element| {
+                std::iter::once(&mut element.location).chain(element.name_location.as_mut())
+            })
+            .chain(
+                cross_references
+                    .iter_mut()
+                    .map(|reference| &mut reference.location),
+            );
+        for location in locations {
+            location.byte_start = offset(location.line_start, location.char_start);
+            location.byte_end = offset(location.line_end, location.char_end);
+        }
+        let mut file_metrics = calculate_file_metrics(&content, &elements);
+        file_metrics.ownership =
+            ownership::ownership_profile(&syntax_tree, file_metrics.lines_of_code);
+        file_metrics.unsafe_usage = unsafe_usage::unsafe_usage(&syntax_tree);
 
         Ok(FileAst {
             path: file_path.to_path_buf(),
@@ -230,25 +937,106 @@ impl AstExtractor {
             imports,
             file_metrics,
             cross_references,
+            parse_errors,
+            warnings,
+            kind,
+            partially_extracted,
         })
     }
 
     /// Extract dependency information.
     ///
     /// # Error Handling
-    /// This method currently returns a placeholder and will not fail.
-    /// Future implementations will include proper error handling for
-    /// Cargo.toml and Cargo.lock parsing failures.
+    /// A missing or unparseable `Cargo.toml` or `Cargo.lock` yields empty
+    /// dependency lists rather than failing the extraction.
     fn extract_dependencies(&self) -> Result<DependencyInfo> {
-        // TODO: Parse Cargo.lock and Cargo.toml for dependency information
-        // For now, return empty dependencies with proper error handling structure
+        let manifest_path = self.root_path.join("Cargo.toml");
+        let declared = if manifest_path.exists() {
+            dependencies::read_declared_dependencies(&manifest_path).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Failed to read dependencies from {:?}: {}",
+                    manifest_path,
+                    e
+                );
+                Default::default()
+            })
+        } else {
+            Default::default()
+        };
+
+        let mut transitive = Vec::new();
+        if self.root_path.join("Cargo.lock").exists() {
+            match DependencyGraph::from_project(&self.root_path) {
+                Ok(graph) => {
+                    transitive = graph
+                        .packages_with_role(PackageRole::Transitive)
+                        .map(|package| package.name.clone())
+                        .collect();
+                }
+                Err(e) => tracing::warn!("Failed to read the dependency graph: {}", e),
+            }
+            transitive.dedup();
+        }
+
         Ok(DependencyInfo {
-            direct: vec![],
-            transitive: vec![],
-            dev_dependencies: vec![],
+            direct: declared.normal,
+            transitive,
+            dev_dependencies: declared.dev,
         })
     }
 
+    /// Extract the public API of each direct dependency crate.
+    ///
+    /// # Error Handling
+    /// Dependencies whose sources cannot be located or parsed are logged and
+    /// skipped; they never fail the extraction of the project itself.
+    fn extract_dependency_sources(&self) -> Vec<DependencyAst> {
+        let sources = match dependencies::locate_dependency_sources(&self.root_path, &self.cargo) {
+            Ok(sources) => sources,
+            Err(e) => {
+                tracing::warn!("Failed to locate dependency sources: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut dependency_asts = Vec::new();
+        for source in sources {
+            tracing::debug!(
+                "Extracting dependency {} {} from {:?}",
+                source.name,
+                source.version,
+                source.root_path
+            );
+
+            // Only the public API of a dependency is relevant to callers.
+            let mut config = self.config.clone();
+            config.include_private = false;
+            config.parse_dependencies = false;
+            config.crates.clear();
+
+            let extractor = AstExtractor::new(config, source.root_path.clone());
+            match extractor.extract_project() {
+                Ok(ast) => dependency_asts.push(DependencyAst {
+                    name: source.name,
+                    version: source.version,
+                    source_path: source.root_path,
+                    files: ast.files,
+                    metrics: ast.metrics,
+                }),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to extract dependency {} {}: {}",
+                        source.name,
+                        source.version,
+                        e
+                    );
+                }
+            }
+        }
+
+        dependency_asts
+    }
+
     /// Update project metrics with file metrics.
     fn update_project_metrics(&self, metrics: &mut ProjectMetrics, file_metrics: &FileMetrics) {
         metrics.total_lines += file_metrics.lines_of_code;
@@ -257,6 +1045,9 @@ impl AstExtractor {
         metrics.total_structs += file_metrics.struct_count;
         metrics.total_enums += file_metrics.enum_count;
         metrics.total_traits += file_metrics.trait_count;
+        metrics.ownership.merge(&file_metrics.ownership);
+        metrics.ownership.update_clone_density(metrics.total_lines);
+        metrics.unsafe_usage.merge(&file_metrics.unsafe_usage);
 
         // Update complexity metrics
         if file_metrics.complexity_total > metrics.complexity_max {
@@ -288,6 +1079,71 @@ impl AstExtractor {
     }
 }
 
+/// The files of a project, as found by [`AstExtractor::discover_files`].
+pub(crate) struct DiscoveredFiles {
+    pub(crate) project_info: ProjectInfo,
+    pub(crate) members: Option<Vec<WorkspaceMember>>,
+    /// Files in extraction order, with the crate and kind of target each belongs to
+    pub(crate) files: Vec<(PathBuf, String, FileKind)>,
+    /// Module paths of the files outside the usual module tree, by normalized path
+    pub(crate) module_overrides: BTreeMap<PathBuf, String>,
+}
+
+/// Iterator over the files of a project, returned by [`AstExtractor::extract_iter`].
+///
+/// Yields one result per discovered file, in path order.
+pub struct FileAstIter<'a> {
+    extractor: &'a AstExtractor,
+    project_info: ProjectInfo,
+    members: Option<Vec<WorkspaceMember>>,
+    /// Files not yet parsed, with the crate and kind of target each belongs to
+    files: std::vec::IntoIter<(PathBuf, String, FileKind)>,
+    module_overrides: BTreeMap<PathBuf, String>,
+    pool: rayon::ThreadPool,
+    /// Parsed files not yet yielded
+    batch: VecDeque<FileResult<FileAst>>,
+    ids: ElementIdDeduplicator,
+}
+
+impl FileAstIter<'_> {
+    /// Information about the project being extracted.
+    pub fn project(&self) -> &ProjectInfo {
+        &self.project_info
+    }
+}
+
+impl Iterator for FileAstIter<'_> {
+    type Item = FileResult<FileAst>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() {
+            let batch_size = self.pool.current_num_threads().max(1) * FILES_PER_WORKER_BATCH;
+            let files: Vec<_> = self.files.by_ref().take(batch_size).collect();
+            if files.is_empty() {
+                return None;
+            }
+            self.batch = self
+                .extractor
+                .parse_files(&self.pool, &files, &self.module_overrides)
+                .into();
+        }
+
+        let mut result = self.batch.pop_front()?;
+        if let Ok(file_ast) = &mut result {
+            // Binaries of one crate share module paths, so IDs can clash across files.
+            self.ids.deduplicate(file_ast);
+        }
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.files.len() + self.batch.len();
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for FileAstIter<'_> {}
+
 impl Default for ProjectMetrics {
     fn default() -> Self {
         Self {
@@ -299,12 +1155,37 @@ impl Default for ProjectMetrics {
             total_traits: 0,
             complexity_average: 0.0,
             complexity_max: 0,
+            deprecations: DeprecationSummary::default(),
+            ownership: OwnershipProfile::default(),
+            unsafe_usage: UnsafeUsage::default(),
         }
     }
 }
 
+/// Keep only the elements selected by the visibility `filters`, with their references.
+fn filter_by_visibility(
+    filters: &[String],
+    elements: &mut Vec<CodeElement>,
+    cross_references: &mut Vec<CrossReference>,
+) {
+    elements.retain(|element| {
+        filters
+            .iter()
+            .any(|filter| element.visibility.matches_filter(filter))
+    });
+
+    let kept: HashSet<String> = elements.iter().map(|element| element.id.clone()).collect();
+    for element in elements.iter_mut() {
+        element
+            .hierarchy
+            .children_ids
+            .retain(|id| kept.contains(id));
+    }
+    cross_references.retain(|reference| kept.contains(&reference.from_element_id));
+}
+
 /// Simple glob pattern matching (simplified implementation).
-fn glob_match(pattern: &str, text: &str) -> bool {
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
     if pattern.contains("**") {
         let prefix = pattern.split("**").next().unwrap_or("");
         text.contains(prefix)
@@ -315,72 +1196,152 @@ fn glob_match(pattern: &str, text: &str) -> bool {
     }
 }
 
-/// Extract imports from a Rust file.
-fn extract_imports(file: &syn::File) -> Vec<ImportInfo> {
+/// Extract the imports of a Rust file whose module path is `module_path`.
+///
+/// Use trees are flattened into one [`ImportInfo`] per imported name, so
+/// `use a::{b, c::*};` yields `a::b` and a glob import of `a::c`. Imports in
+/// inline modules are included, scoped to their module.
+fn extract_imports(file: &syn::File, module_path: &str) -> Vec<ImportInfo> {
     let mut imports = Vec::new();
+    collect_imports(&file.items, module_path, &mut imports);
+    imports
+}
 
-    for item in &file.items {
-        if let syn::Item::Use(use_item) = item {
-            if let Some(import_info) = parse_use_tree(&use_item.tree) {
-                imports.push(import_info);
+fn collect_imports(items: &[syn::Item], scope: &str, imports: &mut Vec<ImportInfo>) {
+    for item in items {
+        match item {
+            syn::Item::Use(use_item) => {
+                let mut paths = Vec::new();
+                flatten_use_tree(&use_item.tree, &mut Vec::new(), &mut paths);
+                let is_reexport = matches!(use_item.vis, syn::Visibility::Public(_));
+                imports.extend(
+                    paths
+                        .into_iter()
+                        .map(|(segments, alias)| import_info(segments, alias, is_reexport, scope)),
+                );
             }
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    let scope = format!("{}::{}", scope, module.ident);
+                    collect_imports(items, &scope, imports);
+                }
+            }
+            _ => {}
         }
     }
-
-    imports
 }
 
-/// Parse a use tree into import information.
-fn parse_use_tree(tree: &syn::UseTree) -> Option<ImportInfo> {
+/// Flatten a use tree into `(path, alias)` pairs; globs end in a `*` segment.
+fn flatten_use_tree(
+    tree: &syn::UseTree,
+    prefix: &mut Vec<String>,
+    paths: &mut Vec<(Vec<String>, Option<String>)>,
+) {
+    let with_last = |prefix: &[String], last: String| {
+        let mut path = prefix.to_vec();
+        path.push(last);
+        path
+    };
     match tree {
-        syn::UseTree::Path(path) => Some(ImportInfo {
-            module_path: path.ident.to_string(),
-            imported_items: vec![],
-            is_glob: false,
-            alias: None,
-        }),
-        syn::UseTree::Name(name) => Some(ImportInfo {
-            module_path: "".to_string(),
-            imported_items: vec![name.ident.to_string()],
-            is_glob: false,
-            alias: None,
-        }),
-        syn::UseTree::Glob(_) => Some(ImportInfo {
-            module_path: "".to_string(),
-            imported_items: vec![],
-            is_glob: true,
-            alias: None,
-        }),
+        syn::UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            flatten_use_tree(&path.tree, prefix, paths);
+            prefix.pop();
+        }
+        // `use a::{self}` imports `a` itself.
+        syn::UseTree::Name(name) if name.ident == "self" && !prefix.is_empty() => {
+            paths.push((prefix.clone(), None))
+        }
+        syn::UseTree::Name(name) => paths.push((with_last(prefix, name.ident.to_string()), None)),
+        syn::UseTree::Rename(rename) if rename.ident == "self" && !prefix.is_empty() => {
+            paths.push((prefix.clone(), Some(rename.rename.to_string())))
+        }
+        syn::UseTree::Rename(rename) => paths.push((
+            with_last(prefix, rename.ident.to_string()),
+            Some(rename.rename.to_string()),
+        )),
+        syn::UseTree::Glob(_) => paths.push((with_last(prefix, "*".to_string()), None)),
         syn::UseTree::Group(group) => {
-            // Handle grouped imports like `use std::{vec, collections};`
-            let items: Vec<String> = group
-                .items
-                .iter()
-                .filter_map(|item| {
-                    if let syn::UseTree::Name(name) = item {
-                        Some(name.ident.to_string())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+            for item in &group.items {
+                flatten_use_tree(item, prefix, paths);
+            }
+        }
+    }
+}
 
-            Some(ImportInfo {
-                module_path: "".to_string(),
-                imported_items: items,
-                is_glob: false,
-                alias: None,
-            })
+/// Build the import of a flattened use path.
+fn import_info(
+    mut segments: Vec<String>,
+    alias: Option<String>,
+    is_reexport: bool,
+    scope: &str,
+) -> ImportInfo {
+    let last = segments.pop().unwrap_or_default();
+    let is_glob = last == "*";
+    ImportInfo {
+        module_path: segments.join("::"),
+        imported_items: if is_glob { vec![] } else { vec![last] },
+        is_glob,
+        alias,
+        is_reexport,
+        scope: scope.to_string(),
+    }
+}
+
+/// Empty the bodies of functions and methods, keeping their signatures and attributes.
+///
+/// Items nested in bodies are dropped with them.
+fn strip_bodies(items: &mut [syn::Item]) {
+    for item in items {
+        match item {
+            syn::Item::Fn(item_fn) => item_fn.block.stmts.clear(),
+            syn::Item::Impl(item_impl) => {
+                for impl_item in &mut item_impl.items {
+                    if let syn::ImplItem::Fn(method) = impl_item {
+                        method.block.stmts.clear();
+                    }
+                }
+            }
+            syn::Item::Trait(item_trait) => {
+                for trait_item in &mut item_trait.items {
+                    if let syn::TraitItem::Fn(method) = trait_item {
+                        if let Some(block) = &mut method.default {
+                            block.stmts.clear();
+                        }
+                    }
+                }
+            }
+            syn::Item::Mod(item_mod) => {
+                if let Some((_, items)) = &mut item_mod.content {
+                    strip_bodies(items);
+                }
+            }
+            _ => {}
         }
-        syn::UseTree::Rename(rename) => Some(ImportInfo {
-            module_path: "".to_string(),
-            imported_items: vec![rename.ident.to_string()],
-            is_glob: false,
-            alias: Some(rename.rename.to_string()),
-        }),
     }
 }
 
+/// Byte offsets of the start of each line of `content`.
+fn line_start_offsets(content: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(offset, _)| offset + 1))
+        .collect()
+}
+
+/// Byte offset of a 1-based line and 0-based character column in `content`.
+///
+/// Columns count characters, so multi-byte UTF-8 is measured in bytes here.
+/// Positions past the end of the content clamp to its length.
+fn byte_offset(content: &str, line_starts: &[usize], line: usize, column: usize) -> usize {
+    let Some(&line_start) = line_starts.get(line.saturating_sub(1)) else {
+        return content.len();
+    };
+    content[line_start..]
+        .char_indices()
+        .nth(column)
+        .map_or(content.len(), |(offset, _)| line_start + offset)
+}
+
 /// Calculate metrics for a file.
 fn calculate_file_metrics(content: &str, elements: &[CodeElement]) -> FileMetrics {
     let lines: Vec<&str> = content.lines().collect();
@@ -397,7 +1358,7 @@ fn calculate_file_metrics(content: &str, elements: &[CodeElement]) -> FileMetric
     FileMetrics {
         lines_of_code,
         lines_of_comments,
-        complexity_total: elements.iter().map(|e| e.complexity.unwrap_or(0)).sum(),
+        complexity_total: elements.iter().map(own_complexity).sum(),
         function_count: elements
             .iter()
             .filter(|e| matches!(e.element_type, ElementType::Function))
@@ -414,6 +1375,30 @@ fn calculate_file_metrics(content: &str, elements: &[CodeElement]) -> FileMetric
             .iter()
             .filter(|e| matches!(e.element_type, ElementType::Trait))
             .count(),
+        ownership: OwnershipProfile::default(),
+        unsafe_usage: UnsafeUsage::default(),
+    }
+}
+
+/// Complexity an element adds to its file's total.
+///
+/// Closures and `async` blocks are already part of their function's complexity.
+fn own_complexity(element: &CodeElement) -> u32 {
+    match element.element_type {
+        ElementType::Closure | ElementType::AsyncBlock => 0,
+        _ => element.complexity.unwrap_or(0),
+    }
+}
+
+/// Add a single element to the per-type counts of a file's metrics.
+fn count_element(metrics: &mut FileMetrics, element: &CodeElement) {
+    metrics.complexity_total += own_complexity(element);
+    match element.element_type {
+        ElementType::Function => metrics.function_count += 1,
+        ElementType::Struct => metrics.struct_count += 1,
+        ElementType::Enum => metrics.enum_count += 1,
+        ElementType::Trait => metrics.trait_count += 1,
+        _ => {}
     }
 }
 
@@ -0,0 +1,160 @@
+//! Incremental re-extraction of a project.
+//!
+//! An [`IncrementalExtractor`] keeps the per-file results of its previous run
+//! and only parses the files that were added or modified since, identified by
+//! their size and modification time. The project-wide passes (cross-file
+//! references, re-exports, trait impls, dependencies) still run over every
+//! file, so each result matches a full [`AstExtractor::extract_project`].
+
+use crate::ast_data::{FileAst, FileKind, ProjectAst};
+use crate::errors::{FileResult, Result};
+use crate::extractor::AstExtractor;
+use crate::module_resolution;
+use crate::visitors::ElementIdDeduplicator;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Extracts a project repeatedly, re-parsing only the files that changed.
+pub struct IncrementalExtractor {
+    extractor: AstExtractor,
+    /// Per-file results of the previous run, before IDs are made unique
+    /// across files and the project-wide passes
+    files: HashMap<PathBuf, CachedFile>,
+}
+
+/// Files extracted by a run of [`IncrementalExtractor::extract`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractionChanges {
+    /// Files that were added or modified and re-extracted, relative to the
+    /// project root
+    pub extracted: Vec<PathBuf>,
+    /// Files that are no longer part of the project, relative to the project
+    /// root
+    pub removed: Vec<PathBuf>,
+    /// Number of files reused from the previous run
+    pub unchanged: usize,
+}
+
+impl ExtractionChanges {
+    /// Whether any file was added, modified or removed.
+    pub fn is_empty(&self) -> bool {
+        self.extracted.is_empty() && self.removed.is_empty()
+    }
+}
+
+struct CachedFile {
+    fingerprint: Fingerprint,
+    result: FileResult<FileAst>,
+}
+
+/// What a file's extraction depends on, besides the configuration.
+#[derive(PartialEq, Eq)]
+struct Fingerprint {
+    len: Option<u64>,
+    modified: Option<SystemTime>,
+    crate_name: String,
+    module_path: Option<String>,
+    kind: FileKind,
+}
+
+impl IncrementalExtractor {
+    /// Create an incremental extractor; the first run extracts every file.
+    pub fn new(extractor: AstExtractor) -> Self {
+        Self {
+            extractor,
+            files: HashMap::new(),
+        }
+    }
+
+    /// The extractor used for each run.
+    pub fn extractor(&self) -> &AstExtractor {
+        &self.extractor
+    }
+
+    /// Extract the project, re-parsing only the files added or modified since
+    /// the previous run.
+    ///
+    /// Files are discovered again on each run, so new and deleted files are
+    /// picked up. Files that failed to extract are retried once they change.
+    pub fn extract(&mut self) -> Result<(ProjectAst, ExtractionChanges)> {
+        let discovered = self.extractor.discover_files()?;
+        let root_path = self.extractor.root_path();
+        let relative = |path: &Path| path.strip_prefix(root_path).unwrap_or(path).to_path_buf();
+
+        let mut changes = ExtractionChanges::default();
+        let mut stale = Vec::new();
+        let mut fingerprints = Vec::new();
+        for (path, crate_name, kind) in &discovered.files {
+            let metadata = fs::metadata(path).ok();
+            let fingerprint = Fingerprint {
+                len: metadata.as_ref().map(|metadata| metadata.len()),
+                modified: metadata.and_then(|metadata| metadata.modified().ok()),
+                crate_name: crate_name.clone(),
+                module_path: discovered
+                    .module_overrides
+                    .get(&module_resolution::normalize_path(path))
+                    .cloned(),
+                kind: *kind,
+            };
+            match self.files.get(path) {
+                Some(cached) if cached.fingerprint == fingerprint => changes.unchanged += 1,
+                _ => {
+                    stale.push((path.clone(), crate_name.clone(), *kind));
+                    fingerprints.push(fingerprint);
+                }
+            }
+        }
+
+        let pool = self.extractor.worker_pool()?;
+        let results = self
+            .extractor
+            .parse_files(&pool, &stale, &discovered.module_overrides);
+        for (((path, _, _), fingerprint), result) in stale.iter().zip(fingerprints).zip(results) {
+            changes.extracted.push(relative(path));
+            self.files.insert(
+                path.clone(),
+                CachedFile {
+                    fingerprint,
+                    result,
+                },
+            );
+        }
+
+        let current: HashSet<&PathBuf> = discovered.files.iter().map(|(path, _, _)| path).collect();
+        self.files.retain(|path, _| {
+            let kept = current.contains(path);
+            if !kept {
+                changes.removed.push(relative(path));
+            }
+            kept
+        });
+        changes.removed.sort();
+
+        let mut ids = ElementIdDeduplicator::default();
+        let mut files = Vec::new();
+        let mut file_errors = Vec::new();
+        for (path, _, _) in &discovered.files {
+            match &self.files[path].result {
+                Ok(file_ast) => {
+                    let mut file_ast = file_ast.clone();
+                    ids.deduplicate(&mut file_ast);
+                    files.push(file_ast);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to extract AST: {}", e);
+                    file_errors.push(e.clone());
+                }
+            }
+        }
+
+        let ast = self.extractor.assemble_project(
+            discovered.project_info,
+            discovered.members,
+            files,
+            file_errors,
+        )?;
+        Ok((ast, changes))
+    }
+}
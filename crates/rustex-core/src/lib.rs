@@ -2,18 +2,69 @@
 //!
 //! Core AST extraction library for Rust projects, optimized for LLM/RAG applications.
 
+pub mod api_compat;
 pub mod ast_data;
+pub mod captures;
+pub mod comments;
 pub mod complexity;
 pub mod config;
+pub mod config_diagnostics;
+pub mod config_layers;
+pub mod dependencies;
+pub mod dependency_graph;
+pub mod diff;
 pub mod errors;
 pub mod extractor;
+pub mod incremental;
+pub mod macro_expansion;
+pub mod metrics_report;
+pub mod module_resolution;
+pub mod output_budget;
+pub mod output_split;
+pub mod ownership;
+pub mod parse_recovery;
+pub mod project_index;
+pub mod query;
+pub mod reexports;
+pub mod source_encoding;
+pub mod tech_debt;
+pub mod thresholds;
+pub mod unsafe_usage;
 pub mod visitors;
+pub mod workspace;
 
 pub mod test_fixtures;
 
 pub use ast_data::*;
 pub use complexity::{ComplexityCalculator, ComplexityLevel, ComplexityMetrics, HalsteadMetrics};
-pub use config::{ConfigUseCase, ExtractorConfig, FilterConfig, OutputFormat};
+pub use config::{
+    ConfigUseCase, CsvTable, Diagram, EmbeddingConfig, ExtractorConfig, FilterConfig,
+    FineTuningConfig, HfDatasetConfig, MarkdownConfig, MarkdownSection, OutputFormat, PathOverride,
+    PluginsConfig, RagChunkStrategy, RagOutputConfig, SummaryConfig, ThresholdConfig,
+    TruncationPolicy, FINE_TUNING_TASKS,
+};
+pub use config_diagnostics::{
+    diagnose_config, diagnose_config_str, diagnose_config_str_with_schemas, installed_plugins,
+    plugin_dir, ConfigDiagnostic, DiagnosticSeverity, PLUGIN_MANIFEST,
+};
+pub use config_layers::{ConfigLayers, ConfigSource, ResolvedConfig};
+pub use dependency_graph::{
+    DependencyEdge, DependencyGraph, DependencyKind, DuplicatePackage, PackageNode, PackageRole,
+};
 pub use errors::{FileProcessingError, FileResult, Result, RustExError};
-pub use extractor::AstExtractor;
+pub use extractor::{AstExtractor, FileAstIter};
+pub use incremental::{ExtractionChanges, IncrementalExtractor};
+pub use metrics_report::{
+    ComplexityDistribution, DocumentationCoverage, Hotspot, LineMetrics, MetricsReport,
+};
+pub use output_budget::fit_to_budget;
+pub use output_split::{split_project, OutputPart, SplitStrategy};
+pub use project_index::{
+    ElementQuery, ElementRef, ProjectIndex, ReferenceSubgraph, TextField, TextMatch,
+};
+pub use query::Query;
+pub use tech_debt::{
+    TechDebtKind, TechDebtMarker, TechDebtReport, TECH_DEBT_METADATA_KEY, TECH_DEBT_PLUGIN,
+};
+pub use thresholds::{check_thresholds, ThresholdViolation};
 pub use visitors::CodeElementVisitor;
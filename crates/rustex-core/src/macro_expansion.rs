@@ -0,0 +1,141 @@
+//! Macro expansion support.
+//!
+//! Expanded sources come from `cargo expand` when it is installed, falling
+//! back to `cargo rustc -- -Zunpretty=expanded` (the mechanism cargo-expand
+//! itself uses, enabled on stable toolchains through `RUSTC_BOOTSTRAP`).
+//! Items that only exist in the expanded source are reported as generated.
+
+use crate::ast_data::{CodeElement, ElementType};
+use crate::config::ExtractorConfig;
+use crate::errors::{Result, RustExError};
+use crate::visitors::CodeElementVisitor;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use syn::visit::Visit;
+
+/// Expand all macros in the crate rooted at `crate_dir`.
+///
+/// The library target is expanded when the crate has one, otherwise the
+/// default binary target.
+pub fn expand_crate(crate_dir: &Path) -> Result<String> {
+    let manifest_path = crate_dir.join("Cargo.toml");
+    let target_args: &[&str] = if crate_dir.join("src").join("lib.rs").exists() {
+        &["--lib"]
+    } else {
+        &[]
+    };
+
+    match run_expand_command(
+        Command::new("cargo")
+            .arg("expand")
+            .arg("--ugly")
+            .args(target_args)
+            .arg("--manifest-path")
+            .arg(&manifest_path),
+    ) {
+        Ok(expanded) => Ok(expanded),
+        Err(e) => {
+            tracing::debug!("cargo expand unavailable ({}), using rustc -Zunpretty", e);
+            run_expand_command(
+                Command::new("cargo")
+                    .arg("rustc")
+                    .arg("--quiet")
+                    .arg("--profile=check")
+                    .args(target_args)
+                    .arg("--manifest-path")
+                    .arg(&manifest_path)
+                    .arg("--")
+                    .arg("-Zunpretty=expanded")
+                    .env("RUSTC_BOOTSTRAP", "1"),
+            )
+        }
+    }
+}
+
+/// Run an expansion command and return its stdout.
+fn run_expand_command(command: &mut Command) -> Result<String> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(RustExError::Config(format!(
+            "macro expansion failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Extract the elements that exist only in the expanded source.
+///
+/// `root_file` is the crate root (`src/lib.rs` or `src/main.rs`); the whole
+/// expanded crate is visited as if it were that file, so qualified names line
+/// up with the elements extracted from the original sources. Elements are
+/// matched by type and qualified name; unmatched ones are returned with
+/// `is_generated` set, and their parent/child links point at the original
+/// elements where those exist.
+pub fn generated_elements(
+    expanded_source: &str,
+    root_file: &Path,
+    id_namespace: &str,
+    original: &[&CodeElement],
+    config: &ExtractorConfig,
+) -> Result<Vec<CodeElement>> {
+    let syntax_tree = syn::parse_file(expanded_source).map_err(|e| RustExError::SyntaxError {
+        file: root_file.to_path_buf(),
+        error: e,
+    })?;
+
+    let mut visitor =
+        CodeElementVisitor::new(root_file.to_path_buf(), config).with_id_namespace(id_namespace);
+    visitor.visit_file(&syntax_tree);
+    let expanded = visitor.into_elements();
+
+    let original_ids: HashMap<(&ElementType, &str), &str> = original
+        .iter()
+        .map(|e| {
+            (
+                (&e.element_type, e.hierarchy.qualified_name.as_str()),
+                e.id.as_str(),
+            )
+        })
+        .collect();
+
+    // Map expanded IDs of elements that also exist in the original sources.
+    let id_map: HashMap<String, String> = expanded
+        .iter()
+        .filter_map(|e| {
+            original_ids
+                .get(&(&e.element_type, e.hierarchy.qualified_name.as_str()))
+                .map(|original_id| (e.id.clone(), original_id.to_string()))
+        })
+        .collect();
+
+    let remap = |id: &String| id_map.get(id).cloned().unwrap_or_else(|| id.clone());
+
+    let generated: Vec<CodeElement> = expanded
+        .into_iter()
+        .filter(|e| !id_map.contains_key(&e.id))
+        .map(|mut element| {
+            element.is_generated = true;
+            element.hierarchy.parent_id = element.hierarchy.parent_id.as_ref().map(remap);
+            element.hierarchy.children_ids =
+                element.hierarchy.children_ids.iter().map(remap).collect();
+            element
+        })
+        .collect();
+
+    tracing::debug!(
+        "Found {} macro-generated elements in {:?}",
+        generated.len(),
+        root_file
+    );
+    Ok(generated)
+}
+
+/// Crate root file for `crate_dir` (`src/lib.rs`, else `src/main.rs`).
+pub fn crate_root_file(crate_dir: &Path) -> Option<PathBuf> {
+    ["lib.rs", "main.rs"]
+        .iter()
+        .map(|name| crate_dir.join("src").join(name))
+        .find(|path| path.exists())
+}
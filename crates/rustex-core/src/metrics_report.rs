@@ -0,0 +1,191 @@
+//! Project-wide metrics reports.
+//!
+//! A [`MetricsReport`] summarizes an extraction: lines of code, how
+//! complexity is distributed over functions, documentation coverage, uses of
+//! `unsafe`, and the largest and most complex elements as hotspots.
+
+use crate::ast_data::{CodeElement, ElementType, ProjectAst, UnsafeUsage, Visibility};
+use crate::complexity::{ComplexityLevel, ComplexityMetrics};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Summary metrics of a project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsReport {
+    /// Project name
+    pub project: String,
+    /// Lines of code and comments
+    pub lines: LineMetrics,
+    /// Complexity of the functions
+    pub complexity: ComplexityDistribution,
+    /// Share of public items with documentation
+    pub documentation: DocumentationCoverage,
+    /// Uses of `unsafe`
+    pub unsafe_usage: UnsafeUsage,
+    /// Elements spanning the most lines, largest first
+    pub largest_elements: Vec<Hotspot>,
+    /// Elements with the highest cyclomatic complexity, most complex first
+    pub most_complex_elements: Vec<Hotspot>,
+}
+
+/// Lines of a project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineMetrics {
+    /// Number of files
+    pub files: usize,
+    /// Lines of code, excluding comments and blank lines
+    pub code: usize,
+    /// Lines of comments
+    pub comments: usize,
+}
+
+/// How function complexity is distributed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComplexityDistribution {
+    /// Number of functions measured
+    pub functions: usize,
+    /// Average cyclomatic complexity
+    pub average_cyclomatic: f64,
+    /// Highest cyclomatic complexity
+    pub max_cyclomatic: u32,
+    /// Functions of low complexity (overall score up to 10)
+    pub low: usize,
+    /// Functions of medium complexity (11 to 20)
+    pub medium: usize,
+    /// Functions of high complexity (21 to 50)
+    pub high: usize,
+    /// Functions of very high complexity (over 50)
+    pub very_high: usize,
+}
+
+/// Documentation coverage of public items.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentationCoverage {
+    /// Public items, excluding impl blocks
+    pub public_items: usize,
+    /// Public items with doc comments
+    pub documented: usize,
+    /// Documented share of public items, in percent (100 when there are none)
+    pub coverage_percent: f64,
+}
+
+/// An element standing out in a metric.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hotspot {
+    /// Qualified name of the element
+    pub name: String,
+    /// Type of the element
+    pub element_type: ElementType,
+    /// File of the element, relative to the project root
+    pub file: PathBuf,
+    /// First line of the element
+    pub line: usize,
+    /// Value of the metric: lines spanned or cyclomatic complexity
+    pub value: u32,
+}
+
+impl MetricsReport {
+    /// Summarize `ast`, listing the `top` largest and most complex elements.
+    pub fn from_project(ast: &ProjectAst, top: usize) -> Self {
+        let lines = LineMetrics {
+            files: ast.files.len(),
+            code: ast.files.iter().map(|f| f.file_metrics.lines_of_code).sum(),
+            comments: ast
+                .files
+                .iter()
+                .map(|f| f.file_metrics.lines_of_comments)
+                .sum(),
+        };
+
+        let elements = || {
+            ast.files
+                .iter()
+                .flat_map(|file| file.elements.iter().map(move |e| (file, e)))
+        };
+
+        let mut complexity = ComplexityDistribution::default();
+        let mut cyclomatic_total = 0;
+        for (_, element) in elements() {
+            let Some(metrics) = function_complexity(element) else {
+                continue;
+            };
+            complexity.functions += 1;
+            cyclomatic_total += metrics.cyclomatic;
+            complexity.max_cyclomatic = complexity.max_cyclomatic.max(metrics.cyclomatic);
+            match metrics.complexity_level() {
+                ComplexityLevel::Low => complexity.low += 1,
+                ComplexityLevel::Medium => complexity.medium += 1,
+                ComplexityLevel::High => complexity.high += 1,
+                ComplexityLevel::VeryHigh => complexity.very_high += 1,
+            }
+        }
+        if complexity.functions > 0 {
+            complexity.average_cyclomatic = cyclomatic_total as f64 / complexity.functions as f64;
+        }
+
+        let mut documentation = DocumentationCoverage::default();
+        for (_, element) in elements() {
+            if element.visibility == Visibility::Public && element.element_type != ElementType::Impl
+            {
+                documentation.public_items += 1;
+                if !element.doc_comments.is_empty() {
+                    documentation.documented += 1;
+                }
+            }
+        }
+        documentation.coverage_percent = if documentation.public_items == 0 {
+            100.0
+        } else {
+            documentation.documented as f64 * 100.0 / documentation.public_items as f64
+        };
+
+        let hotspots = |value: &dyn Fn(&CodeElement) -> Option<u32>| {
+            let mut hotspots: Vec<Hotspot> = elements()
+                .filter_map(|(file, element)| {
+                    Some(Hotspot {
+                        name: element.hierarchy.qualified_name.clone(),
+                        element_type: element.element_type.clone(),
+                        file: file.relative_path.clone(),
+                        line: element.location.line_start,
+                        value: value(element)?,
+                    })
+                })
+                .collect();
+            // Stable, so ties stay in file order.
+            hotspots.sort_by_key(|hotspot| std::cmp::Reverse(hotspot.value));
+            hotspots.truncate(top);
+            hotspots
+        };
+        let largest_elements = hotspots(&|element| {
+            // Impl blocks and modules span their contents.
+            let spans_contents = matches!(
+                element.element_type,
+                ElementType::Impl | ElementType::Module
+            );
+            let location = &element.location;
+            (!spans_contents).then(|| (location.line_end + 1 - location.line_start) as u32)
+        });
+        let most_complex_elements =
+            hotspots(&|element| Some(function_complexity(element)?.cyclomatic));
+
+        Self {
+            project: ast.project.name.clone(),
+            lines,
+            complexity,
+            documentation,
+            unsafe_usage: ast.metrics.unsafe_usage.clone(),
+            largest_elements,
+            most_complex_elements,
+        }
+    }
+}
+
+/// Complexity metrics of a function. Other elements only carry structural
+/// complexity, or that of the functions they contain.
+fn function_complexity(element: &CodeElement) -> Option<&ComplexityMetrics> {
+    if element.element_type == ElementType::Function {
+        element.complexity_metrics.as_ref()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,218 @@
+//! Module resolution for `#[path]` modules and `include!`d files.
+//!
+//! Module paths are normally derived from a file's location in the `src`
+//! tree. Files reached through a `#[path = "..."]` attribute or an item-level
+//! `include!("...")` don't follow that layout, so their module paths are
+//! resolved here by following the declarations that lead to them.
+
+use crate::visitors::CodeElementVisitor;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A file reached through a module declaration or `include!`.
+struct ModuleFile {
+    /// Path of the file
+    path: PathBuf,
+    /// Logical module path the file's items belong to
+    module_path: String,
+    /// Directory that plain `mod name;` declarations in the file resolve against
+    child_dir: PathBuf,
+}
+
+/// Find the module path of files whose location doesn't determine it.
+///
+/// Starting from `files`, follows `#[path]` module declarations and
+/// item-level `include!` calls with a string literal argument, then every
+/// `mod` declaration inside the files they lead to. The returned map may
+/// contain files outside `files`; paths are normalized with
+/// [`normalize_path`]. Files that can't be read or parsed are skipped.
+pub fn resolve_module_overrides(files: &[PathBuf]) -> BTreeMap<PathBuf, String> {
+    let mut queue = VecDeque::new();
+    for file in files {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+        // Cheap pre-check so only files that can redirect modules are parsed twice.
+        if !content.contains("#[path") && !content.contains("include!") {
+            continue;
+        }
+        let Ok(syntax_tree) = syn::parse_file(&content) else {
+            continue;
+        };
+
+        let source = ModuleFile {
+            path: normalize_path(file),
+            module_path: CodeElementVisitor::extract_module_path(file),
+            child_dir: default_child_dir(file),
+        };
+        collect_module_files(&syntax_tree.items, &source, false, &mut queue);
+    }
+
+    let mut overrides = BTreeMap::new();
+    while let Some(module_file) = queue.pop_front() {
+        if overrides.contains_key(&module_file.path) {
+            continue;
+        }
+        overrides.insert(module_file.path.clone(), module_file.module_path.clone());
+
+        let Some(syntax_tree) = fs::read_to_string(&module_file.path)
+            .ok()
+            .and_then(|content| syn::parse_file(&content).ok())
+        else {
+            continue;
+        };
+        // Children of a redirected file are redirected too.
+        collect_module_files(&syntax_tree.items, &module_file, true, &mut queue);
+    }
+
+    overrides
+}
+
+/// Collect the files declared by the items of `source`.
+///
+/// Plain `mod name;` declarations are only followed when `follow_plain` is set.
+fn collect_module_files(
+    items: &[syn::Item],
+    source: &ModuleFile,
+    follow_plain: bool,
+    found: &mut VecDeque<ModuleFile>,
+) {
+    let file_dir = source.path.parent().unwrap_or(Path::new(""));
+    collect_in_scope(
+        items,
+        file_dir,
+        file_dir,
+        &source.module_path,
+        &source.child_dir,
+        follow_plain,
+        found,
+    );
+}
+
+/// Collect declared files within one (possibly inline) module scope.
+///
+/// `path_dir` is what `#[path]` resolves against: the file's directory at the
+/// top level, and the nested module directory inside inline modules.
+fn collect_in_scope(
+    items: &[syn::Item],
+    file_dir: &Path,
+    path_dir: &Path,
+    module_path: &str,
+    child_dir: &Path,
+    follow_plain: bool,
+    found: &mut VecDeque<ModuleFile>,
+) {
+    for item in items {
+        match item {
+            syn::Item::Mod(item_mod) => {
+                let name = item_mod.ident.to_string();
+                let nested_path = format!("{}::{}", module_path, name);
+                let path_attr = path_attribute(&item_mod.attrs);
+
+                if let Some((_, inner_items)) = &item_mod.content {
+                    let nested_dir = child_dir.join(path_attr.as_deref().unwrap_or(&name));
+                    collect_in_scope(
+                        inner_items,
+                        file_dir,
+                        &nested_dir,
+                        &nested_path,
+                        &nested_dir,
+                        follow_plain,
+                        found,
+                    );
+                } else if let Some(path_attr) = path_attr {
+                    // Files loaded through `#[path]` resolve their own children like `mod.rs`.
+                    let path = normalize_path(&path_dir.join(path_attr));
+                    let child_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+                    found.push_back(ModuleFile {
+                        path,
+                        module_path: nested_path,
+                        child_dir,
+                    });
+                } else if follow_plain {
+                    let flat = child_dir.join(format!("{}.rs", name));
+                    let nested = child_dir.join(&name).join("mod.rs");
+                    let path = if nested.exists() && !flat.exists() {
+                        nested
+                    } else {
+                        flat
+                    };
+                    found.push_back(ModuleFile {
+                        path: normalize_path(&path),
+                        module_path: nested_path,
+                        child_dir: child_dir.join(&name),
+                    });
+                }
+            }
+            syn::Item::Macro(item_macro) if item_macro.mac.path.is_ident("include") => {
+                let Ok(literal) = item_macro.mac.parse_body::<syn::LitStr>() else {
+                    continue;
+                };
+                // `include!` is relative to the invoking file, but the included items
+                // live in the invoking module.
+                found.push_back(ModuleFile {
+                    path: normalize_path(&file_dir.join(literal.value())),
+                    module_path: module_path.to_string(),
+                    child_dir: child_dir.to_path_buf(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Value of a `#[path = "..."]` attribute, if present.
+fn path_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| {
+        if !attr.path().is_ident("path") {
+            return None;
+        }
+        match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit_str),
+                        ..
+                    }),
+                ..
+            }) => Some(lit_str.value()),
+            _ => None,
+        }
+    })
+}
+
+/// Directory that plain `mod` declarations of a file resolve against.
+///
+/// Crate roots and `mod.rs` files own their directory; any other file
+/// `dir/name.rs` owns `dir/name/`.
+fn default_child_dir(file: &Path) -> PathBuf {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    let is_bin_root = dir.file_name().is_some_and(|name| name == "bin");
+    match file.file_stem().and_then(|stem| stem.to_str()) {
+        Some("mod" | "lib" | "main") | None => dir.to_path_buf(),
+        Some(_) if is_bin_root => dir.to_path_buf(),
+        Some(stem) => dir.join(stem),
+    }
+}
+
+/// Resolve `..` components lexically, keeping a leading `.`.
+///
+/// Discovered files and declaration targets are compared in this form.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                _ => normalized.push(".."),
+            },
+            Component::CurDir if normalized.as_os_str().is_empty() => normalized.push("."),
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,272 @@
+//! Reduction of extracted data to fit an output budget.
+//!
+//! LLM pipelines need outputs that fit a context window. The size of the
+//! rendered output depends on the format, so the caller supplies a function
+//! measuring it; the project is reduced step by step according to a
+//! [`TruncationPolicy`], re-measuring after each step, until it fits.
+//! References, the reference graph, the type index and trait impls are pruned
+//! along with the elements they point from, and references and reference
+//! graph edges from stripped elements go with their calls. Files are dropped
+//! last, largest first.
+
+use crate::ast_data::{CodeElement, ProjectAst, TruncationReport, Visibility};
+use crate::config::TruncationPolicy;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Reduce `ast` until `measure` reports at most `limit` bytes.
+///
+/// Does nothing when the output already fits. Otherwise records what was
+/// removed in [`ProjectAst::truncation`], which counts towards the limit.
+/// Fails when even an output without any files exceeds the limit.
+pub fn fit_to_budget(
+    ast: &mut ProjectAst,
+    limit: usize,
+    policy: TruncationPolicy,
+    mut measure: impl FnMut(&ProjectAst) -> Result<usize>,
+) -> Result<()> {
+    let original_bytes = measure(ast)?;
+    if original_bytes <= limit {
+        return Ok(());
+    }
+    ast.truncation = Some(TruncationReport {
+        policy,
+        limit_bytes: limit,
+        original_bytes,
+        stripped_elements: 0,
+        dropped_elements: 0,
+        dropped_files: Vec::new(),
+    });
+
+    let steps: [fn(&mut ProjectAst, usize, usize); 2] = match policy {
+        TruncationPolicy::DropBodiesFirst => [strip_all_elements, drop_private_elements],
+        TruncationPolicy::DropPrivateFirst => [drop_private_elements, strip_all_elements],
+        TruncationPolicy::PerFileProportional => [shrink_files_proportionally, |_, _, _| {}],
+    };
+    for step in steps {
+        step(ast, limit, original_bytes);
+        if measure(ast)? <= limit {
+            return Ok(());
+        }
+    }
+
+    drop_largest_files(ast, limit, &mut measure)
+}
+
+/// Reduce an element to its signature, location and the first line of its
+/// docs. Returns whether anything was removed.
+fn strip_element(element: &mut CodeElement) -> bool {
+    let stripped = element.doc_comments.len() > 1
+        || !element.doc_examples.is_empty()
+        || !element.inline_comments.is_empty()
+        || !element.attributes.is_empty()
+        || !element.calls.is_empty()
+        || !element.dependencies.is_empty()
+        || element.complexity_metrics.is_some();
+    element.doc_comments.truncate(1);
+    element.doc_examples.clear();
+    element.inline_comments.clear();
+    element.attributes.clear();
+    element.calls.clear();
+    element.dependencies.clear();
+    element.complexity_metrics = None;
+    stripped
+}
+
+fn strip_all_elements(ast: &mut ProjectAst, _limit: usize, _original_bytes: usize) {
+    let mut ids = HashSet::new();
+    let mut stripped = 0;
+    for element in ast
+        .files
+        .iter_mut()
+        .flat_map(|file| file.elements.iter_mut())
+    {
+        if strip_element(element) {
+            stripped += 1;
+        }
+        ids.insert(element.id.clone());
+    }
+    report(ast).stripped_elements += stripped;
+    drop_references_from(ast, &ids);
+}
+
+fn drop_private_elements(ast: &mut ProjectAst, _limit: usize, _original_bytes: usize) {
+    let private: HashSet<String> = ast
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .filter(|element| element.visibility != Visibility::Public)
+        .map(|element| element.id.clone())
+        .collect();
+    drop_elements(ast, &private);
+}
+
+/// Give each file a share of the limit proportional to its size, then strip
+/// and drop its elements from the last one backwards until it fits its share.
+///
+/// Sizes are estimated from each file's JSON encoding.
+fn shrink_files_proportionally(ast: &mut ProjectAst, limit: usize, original_bytes: usize) {
+    let mut stripped = 0;
+    let mut stripped_ids = HashSet::new();
+    let mut dropped = HashSet::new();
+    for file in &mut ast.files {
+        let size = json_len(file);
+        let share = (size as u128 * limit as u128 / original_bytes.max(1) as u128) as usize;
+        let mut excess = size.saturating_sub(share);
+
+        for element in file.elements.iter_mut().rev() {
+            if excess == 0 {
+                break;
+            }
+            let before = json_len(element);
+            stripped_ids.insert(element.id.clone());
+            if strip_element(element) {
+                stripped += 1;
+                excess = excess.saturating_sub(before - json_len(element).min(before));
+            }
+        }
+        for element in file.elements.iter().rev() {
+            if excess == 0 {
+                break;
+            }
+            excess = excess.saturating_sub(json_len(element));
+            dropped.insert(element.id.clone());
+        }
+    }
+    report(ast).stripped_elements += stripped;
+    drop_references_from(ast, &stripped_ids);
+    drop_elements(ast, &dropped);
+}
+
+/// Drop the fewest files, largest first, for the output to fit the limit.
+///
+/// File sizes are estimated from each file's JSON encoding; the files kept
+/// stay in their order.
+fn drop_largest_files(
+    ast: &mut ProjectAst,
+    limit: usize,
+    measure: &mut impl FnMut(&ProjectAst) -> Result<usize>,
+) -> Result<()> {
+    let mut by_size: Vec<(usize, usize)> = ast
+        .files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| (json_len(file), index))
+        .collect();
+    by_size.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let without_largest = |count: usize| {
+        let largest: HashSet<usize> = by_size[..count].iter().map(|&(_, index)| index).collect();
+        let mut candidate = ast.clone();
+        let (dropped, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut candidate.files)
+            .into_iter()
+            .enumerate()
+            .partition(|(index, _)| largest.contains(index));
+        candidate.files = kept.into_iter().map(|(_, file)| file).collect();
+        let ids: HashSet<String> = dropped
+            .iter()
+            .flat_map(|(_, file)| &file.elements)
+            .map(|element| element.id.clone())
+            .collect();
+        drop_elements(&mut candidate, &ids);
+        report(&mut candidate)
+            .dropped_files
+            .extend(dropped.into_iter().map(|(_, file)| file.relative_path));
+        candidate
+    };
+
+    // Binary search for the number of files dropped, assuming the output
+    // only shrinks with fewer files.
+    let (mut low, mut high) = (0, ast.files.len());
+    let mut best = None;
+    while low <= high {
+        let count = (low + high) / 2;
+        let candidate = without_largest(count);
+        if measure(&candidate)? <= limit {
+            best = Some(candidate);
+            if count == 0 {
+                break;
+            }
+            high = count - 1;
+        } else {
+            low = count + 1;
+        }
+    }
+
+    match best {
+        Some(best) => {
+            *ast = best;
+            Ok(())
+        }
+        None => anyhow::bail!(
+            "Output budget of {} bytes is too small for the project summary",
+            limit
+        ),
+    }
+}
+
+/// Remove the elements with these IDs, and the data pointing from them.
+fn drop_elements(ast: &mut ProjectAst, ids: &HashSet<String>) {
+    if ids.is_empty() {
+        return;
+    }
+    let kept = |id: &String| !ids.contains(id);
+    for file in &mut ast.files {
+        file.elements.retain(|element| kept(&element.id));
+        for element in &mut file.elements {
+            element.hierarchy.children_ids.retain(kept);
+        }
+    }
+    drop_references_from(ast, ids);
+
+    // Edges to the elements, as references from them are gone.
+    let graph = &mut ast.reference_graph;
+    graph.referenced_by.retain(|id, _| kept(id));
+    for targets in graph.references.values_mut() {
+        targets.retain(kept);
+    }
+    graph.references.retain(|_, targets| !targets.is_empty());
+    for methods in ast.type_index.values_mut() {
+        methods.impl_ids.retain(kept);
+        methods.inherent_methods.retain(kept);
+        for trait_methods in methods.trait_methods.values_mut() {
+            trait_methods.retain(kept);
+        }
+    }
+    ast.impls.retain(|trait_impl| kept(&trait_impl.impl_id));
+
+    report(ast).dropped_elements += ids.len();
+}
+
+/// Remove the cross-references from the elements with these IDs, in files
+/// and the project, and their edges in the reference graph.
+fn drop_references_from(ast: &mut ProjectAst, ids: &HashSet<String>) {
+    if ids.is_empty() {
+        return;
+    }
+    let kept = |id: &String| !ids.contains(id);
+    for file in &mut ast.files {
+        file.cross_references
+            .retain(|reference| kept(&reference.from_element_id));
+    }
+    ast.cross_references
+        .retain(|reference| kept(&reference.from_element_id));
+
+    let graph = &mut ast.reference_graph;
+    graph.references.retain(|id, _| kept(id));
+    for sources in graph.referenced_by.values_mut() {
+        sources.retain(kept);
+    }
+    graph.referenced_by.retain(|_, sources| !sources.is_empty());
+}
+
+fn json_len(value: &impl Serialize) -> usize {
+    serde_json::to_vec(value).map_or(0, |json| json.len())
+}
+
+fn report(ast: &mut ProjectAst) -> &mut TruncationReport {
+    ast.truncation
+        .as_mut()
+        .expect("truncation report is set before reducing the output")
+}
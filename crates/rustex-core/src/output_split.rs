@@ -0,0 +1,266 @@
+//! Splitting of extracted data into several smaller outputs.
+//!
+//! A single output for a large project is too big for most tools, while RAG
+//! ingestion pipelines can process many small ones in parallel. Each part is
+//! a complete [`ProjectAst`] holding some of the files. Project-wide data
+//! that belongs to no file, such as dependency information and metrics, is
+//! repeated in every part; cross-references, the reference graph, the type
+//! index and trait impls go to the part holding the element they point from.
+
+use crate::ast_data::ProjectAst;
+use crate::errors::{Result, RustExError};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// How [`split_project`] divides a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// One part per source file
+    PerFile,
+    /// One part per directory, so a module stays with its submodule files
+    PerModule,
+    /// Consecutive files packed into parts of at most this many bytes; a
+    /// file larger than the limit gets a part of its own
+    MaxSize(usize),
+}
+
+impl FromStr for SplitStrategy {
+    type Err = RustExError;
+
+    /// Parses `per-file`, `per-module` or `max-size=<size>`, where the size
+    /// is a number of bytes with an optional `KB`, `MB` or `GB` suffix.
+    fn from_str(strategy: &str) -> Result<Self> {
+        match strategy.trim() {
+            "per-file" => Ok(Self::PerFile),
+            "per-module" => Ok(Self::PerModule),
+            other => {
+                let size = other.strip_prefix("max-size=").ok_or_else(|| {
+                    RustExError::Config(format!(
+                        "Unknown split strategy `{}`, expected per-file, per-module \
+                         or max-size=<size>",
+                        other
+                    ))
+                })?;
+                parse_size(size).map(Self::MaxSize)
+            }
+        }
+    }
+}
+
+impl fmt::Display for SplitStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PerFile => write!(f, "per-file"),
+            Self::PerModule => write!(f, "per-module"),
+            Self::MaxSize(bytes) => write!(f, "max-size={}", bytes),
+        }
+    }
+}
+
+/// Parse a size such as `512`, `64KB` or `10MB`, in multiples of 1024.
+fn parse_size(size: &str) -> Result<usize> {
+    let upper = size.trim().to_ascii_uppercase();
+    let (number, multiplier) = [("GB", 1 << 30), ("MB", 1 << 20), ("KB", 1 << 10), ("B", 1)]
+        .into_iter()
+        .find_map(|(suffix, multiplier)| {
+            upper
+                .strip_suffix(suffix)
+                .map(|number| (number.trim(), multiplier))
+        })
+        .unwrap_or((upper.as_str(), 1));
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .filter(|&bytes| bytes > 0)
+        .ok_or_else(|| RustExError::Config(format!("Invalid size `{}`", size)))
+}
+
+/// One part of a split project.
+#[derive(Debug, Clone)]
+pub struct OutputPart {
+    /// Name of the part, usable as a file name without extension
+    pub name: String,
+    /// The project restricted to the files of this part
+    pub ast: ProjectAst,
+}
+
+/// Split `ast` into parts according to `strategy`, keeping files in order.
+///
+/// `measure` reports the size of the rendered output of a project and is
+/// only used by [`SplitStrategy::MaxSize`]. Parts are packed from the size
+/// of each file rendered on its own, so they can be slightly off the limit
+/// for formats whose size isn't the sum of their parts.
+pub fn split_project(
+    ast: &ProjectAst,
+    strategy: SplitStrategy,
+    mut measure: impl FnMut(&ProjectAst) -> anyhow::Result<usize>,
+) -> anyhow::Result<Vec<OutputPart>> {
+    let groups: Vec<(String, Vec<usize>)> = match strategy {
+        SplitStrategy::PerFile => ast
+            .files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| (file_part_name(&file.relative_path), vec![index]))
+            .collect(),
+        SplitStrategy::PerModule => {
+            let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+            let mut group_of_directory = HashMap::new();
+            for (index, file) in ast.files.iter().enumerate() {
+                let directory = file.relative_path.parent().unwrap_or(Path::new(""));
+                let group = *group_of_directory.entry(directory).or_insert_with(|| {
+                    groups.push((directory_part_name(directory), Vec::new()));
+                    groups.len() - 1
+                });
+                groups[group].1.push(index);
+            }
+            groups
+        }
+        SplitStrategy::MaxSize(limit) => {
+            let base = measure(&assemble(ast, Vec::new()).remove(0).ast)?;
+            let single_files = ast
+                .files
+                .iter()
+                .enumerate()
+                .map(|(index, _)| (String::new(), vec![index]))
+                .collect();
+            let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+            let mut part_size = base;
+            for (index, part) in assemble(ast, single_files).iter().enumerate() {
+                let file_size = measure(&part.ast)?.saturating_sub(base);
+                if groups.is_empty() || part_size + file_size > limit {
+                    groups.push((format!("part-{:03}", groups.len() + 1), Vec::new()));
+                    part_size = base;
+                }
+                groups
+                    .last_mut()
+                    .expect("a part was just added")
+                    .1
+                    .push(index);
+                part_size += file_size;
+            }
+            groups
+        }
+    };
+    Ok(assemble(ast, dedupe_names(groups)))
+}
+
+/// Build the parts holding these files, with the project-wide data pointing
+/// from their elements. Without groups, returns a single part without files.
+fn assemble(ast: &ProjectAst, groups: Vec<(String, Vec<usize>)>) -> Vec<OutputPart> {
+    let shell = ProjectAst {
+        project: ast.project.clone(),
+        files: Vec::new(),
+        dependencies: ast.dependencies.clone(),
+        metrics: ast.metrics.clone(),
+        extracted_at: ast.extracted_at,
+        cross_references: Vec::new(),
+        dependencies_ast: ast.dependencies_ast.clone(),
+        workspace: ast.workspace.clone(),
+        reference_graph: Default::default(),
+        type_index: Default::default(),
+        impls: Vec::new(),
+        truncation: ast.truncation.clone(),
+        extraction_report: ast.extraction_report.clone(),
+        plugin_results: ast.plugin_results.clone(),
+    };
+    if groups.is_empty() {
+        return vec![OutputPart {
+            name: String::new(),
+            ast: shell,
+        }];
+    }
+
+    let mut part_of_element = HashMap::new();
+    let mut parts: Vec<OutputPart> = groups
+        .into_iter()
+        .enumerate()
+        .map(|(part, (name, files))| {
+            for element in files.iter().flat_map(|&i| &ast.files[i].elements) {
+                part_of_element.insert(element.id.as_str(), part);
+            }
+            let mut part_ast = shell.clone();
+            part_ast.files = files.into_iter().map(|i| ast.files[i].clone()).collect();
+            OutputPart {
+                name,
+                ast: part_ast,
+            }
+        })
+        .collect();
+    let part_of = |id: &str| part_of_element.get(id).copied();
+
+    for reference in &ast.cross_references {
+        if let Some(part) = part_of(&reference.from_element_id) {
+            parts[part].ast.cross_references.push(reference.clone());
+        }
+    }
+    let graph = &ast.reference_graph;
+    for (id, targets) in &graph.references {
+        if let Some(part) = part_of(id) {
+            let references = &mut parts[part].ast.reference_graph.references;
+            references.insert(id.clone(), targets.clone());
+        }
+    }
+    for (id, sources) in &graph.referenced_by {
+        if let Some(part) = part_of(id) {
+            let referenced_by = &mut parts[part].ast.reference_graph.referenced_by;
+            referenced_by.insert(id.clone(), sources.clone());
+        }
+    }
+    for (type_name, methods) in &ast.type_index {
+        let part = methods
+            .type_element_id
+            .iter()
+            .chain(&methods.impl_ids)
+            .find_map(|id| part_of(id));
+        if let Some(part) = part {
+            let type_index = &mut parts[part].ast.type_index;
+            type_index.insert(type_name.clone(), methods.clone());
+        }
+    }
+    for trait_impl in &ast.impls {
+        if let Some(part) = part_of(&trait_impl.impl_id) {
+            parts[part].ast.impls.push(trait_impl.clone());
+        }
+    }
+    parts
+}
+
+/// `src/net/mod.rs` becomes `src-net-mod`.
+fn file_part_name(path: &Path) -> String {
+    directory_part_name(&path.with_extension(""))
+}
+
+/// `src/net` becomes `src-net`, the project root `root`.
+fn directory_part_name(path: &Path) -> String {
+    let name = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        "root".to_string()
+    } else {
+        name
+    }
+}
+
+/// Append `-2`, `-3`... to names already taken, such as `src/a-b.rs` and
+/// `src/a/b.rs` which both become `src-a-b`.
+fn dedupe_names(groups: Vec<(String, Vec<usize>)>) -> Vec<(String, Vec<usize>)> {
+    let mut taken = HashSet::new();
+    groups
+        .into_iter()
+        .map(|(name, files)| {
+            let mut unique = name.clone();
+            let mut suffix = 1;
+            while !taken.insert(unique.clone()) {
+                suffix += 1;
+                unique = format!("{}-{}", name, suffix);
+            }
+            (unique, files)
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+//! Borrowing and shared-ownership statistics.
+//!
+//! Patterns are counted syntactically on the parsed file, including items
+//! that aren't extracted such as private functions and tests. Types are
+//! matched by name, so a `use std::rc::Rc as Shared` alias isn't seen.
+
+use crate::ast_data::OwnershipProfile;
+use syn::visit::Visit;
+
+/// Count the ownership patterns of a file with `lines_of_code` lines of code.
+pub fn ownership_profile(file: &syn::File, lines_of_code: usize) -> OwnershipProfile {
+    let mut counter = OwnershipCounter::default();
+    counter.visit_file(file);
+    counter.profile.update_clone_density(lines_of_code);
+    counter.profile
+}
+
+#[derive(Default)]
+struct OwnershipCounter {
+    profile: OwnershipProfile,
+}
+
+impl<'ast> Visit<'ast> for OwnershipCounter {
+    fn visit_lifetime(&mut self, node: &'ast syn::Lifetime) {
+        self.profile.lifetime_annotations += 1;
+        syn::visit::visit_lifetime(self, node);
+    }
+
+    fn visit_label(&mut self, _node: &'ast syn::Label) {
+        // Loop labels share the lifetime syntax but aren't lifetimes.
+    }
+
+    fn visit_expr_break(&mut self, node: &'ast syn::ExprBreak) {
+        if let Some(expr) = &node.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_expr_continue(&mut self, _node: &'ast syn::ExprContinue) {}
+
+    fn visit_fn_arg(&mut self, node: &'ast syn::FnArg) {
+        // `&self` and `&mut self` are written out as `&Self` and `&mut Self` too.
+        let ty = match node {
+            syn::FnArg::Receiver(receiver) => &receiver.ty,
+            syn::FnArg::Typed(arg) => &arg.ty,
+        };
+        if let syn::Type::Reference(reference) = &**ty {
+            if reference.mutability.is_some() {
+                self.profile.mut_ref_params += 1;
+            } else {
+                self.profile.shared_ref_params += 1;
+            }
+        }
+        syn::visit::visit_fn_arg(self, node);
+    }
+
+    fn visit_receiver(&mut self, node: &'ast syn::Receiver) {
+        // The shorthand's lifetime also appears in its type; count it once.
+        self.visit_type(&node.ty);
+    }
+
+    fn visit_path_segment(&mut self, node: &'ast syn::PathSegment) {
+        if node.ident == "Arc" {
+            self.profile.arc_usages += 1;
+        } else if node.ident == "Rc" {
+            self.profile.rc_usages += 1;
+        } else if node.ident == "RefCell" {
+            self.profile.refcell_usages += 1;
+        }
+        syn::visit::visit_path_segment(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if node.method == "clone" && node.args.is_empty() {
+            self.profile.clone_calls += 1;
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        // Arguments of macros like `vec!` and `assert_eq!` are usually expressions.
+        let parser = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+        if let Ok(args) = node.parse_body_with(parser) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        syn::visit::visit_macro(self, node);
+    }
+}
@@ -0,0 +1,143 @@
+//! Item-by-item parsing of files with syntax errors.
+//!
+//! `syn` rejects a file as a whole when any part of it fails to parse. To
+//! still extract the valid items, the source is split at top-level item
+//! boundaries and each piece is parsed on its own. Boundaries are found
+//! textually: a line starting in the first column with an item keyword,
+//! attribute or comment begins a new item, unless it continues the
+//! attributes and doc comments of the previous line. This matches
+//! rustfmt-formatted code and keeps working when a broken item has
+//! unbalanced delimiters.
+
+use crate::ast_data::ParseErrorSpan;
+
+/// Keywords that can start a top-level item.
+const ITEM_KEYWORDS: &[&str] = &[
+    "pub",
+    "fn",
+    "struct",
+    "enum",
+    "union",
+    "impl",
+    "trait",
+    "mod",
+    "use",
+    "const",
+    "static",
+    "type",
+    "extern",
+    "unsafe",
+    "async",
+    "macro_rules!",
+];
+
+/// A file parsed item by item.
+pub struct RecoveredFile {
+    /// The items that parsed, with the file's inner attributes
+    pub file: syn::File,
+    /// 0-based line of the file each item's piece starts at, by item index.
+    /// Spans of an item are relative to its piece, so this is added to them.
+    pub item_lines: Vec<usize>,
+    /// Spans that failed to parse, in file order
+    pub errors: Vec<ParseErrorSpan>,
+}
+
+/// Parse `content` one top-level item at a time, skipping items that fail.
+///
+/// Adjacent failing pieces are reported as a single span with the first
+/// piece's error message.
+pub fn parse_items(content: &str) -> RecoveredFile {
+    let mut file = syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: Vec::new(),
+    };
+    let mut item_lines = Vec::new();
+    let mut errors: Vec<ParseErrorSpan> = Vec::new();
+
+    for (first_line, chunk) in split_items(content) {
+        match syn::parse_file(&chunk) {
+            Ok(parsed) => {
+                // Inner attributes can only appear in the first piece.
+                file.attrs.extend(parsed.attrs);
+                item_lines.extend(std::iter::repeat_n(first_line, parsed.items.len()));
+                file.items.extend(parsed.items);
+            }
+            Err(e) => {
+                let line_start = first_line + 1;
+                let line_end = first_line + chunk.trim_end().lines().count().max(1);
+                match errors.last_mut() {
+                    Some(last) if last.line_end + 1 == line_start => last.line_end = line_end,
+                    _ => errors.push(ParseErrorSpan {
+                        message: e.to_string(),
+                        line_start,
+                        line_end,
+                    }),
+                }
+            }
+        }
+    }
+
+    RecoveredFile {
+        file,
+        item_lines,
+        errors,
+    }
+}
+
+/// Split source into top-level items, returning each with its 0-based first line.
+fn split_items(content: &str) -> Vec<(usize, String)> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_start = 0;
+    let mut continues_prefix = false;
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_end();
+        if current.trim().is_empty() {
+            // Leading blank lines are not part of the item.
+            current.clear();
+            current_start = index;
+        } else if starts_item(trimmed) && !continues_prefix {
+            chunks.push((current_start, std::mem::take(&mut current)));
+            current_start = index;
+        }
+        current.push_str(line);
+
+        // Attributes and comments belong to the item that follows them.
+        if !trimmed.is_empty() {
+            continues_prefix = is_prefix_line(trimmed);
+        }
+    }
+    if !current.trim().is_empty() {
+        chunks.push((current_start, current));
+    }
+
+    chunks
+}
+
+/// Whether a line can begin a top-level item.
+fn starts_item(line: &str) -> bool {
+    if is_prefix_line(line) {
+        return true;
+    }
+    let Some(word) = line
+        .split(|c: char| c.is_whitespace() || c == '(' || c == '<')
+        .next()
+    else {
+        return false;
+    };
+    // Macro invocations such as `lazy_static! {` start items too.
+    let is_macro_call = word.strip_suffix('!').is_some_and(|name| {
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    });
+    ITEM_KEYWORDS.contains(&word) || is_macro_call
+}
+
+/// Whether a line is an attribute or comment that precedes an item.
+fn is_prefix_line(line: &str) -> bool {
+    line.starts_with("#[")
+        || line.starts_with("#![")
+        || line.starts_with("//")
+        || line.starts_with("/*")
+}
@@ -0,0 +1,297 @@
+//! Lookup and search over an extracted project.
+//!
+//! A [`ProjectIndex`] owns a [`ProjectAst`] and indexes its elements by ID,
+//! so tools serving extraction results can answer queries without scanning
+//! every file.
+
+use crate::ast_data::{CodeElement, FileAst, ProjectAst};
+use crate::errors::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+
+/// An extracted project, indexed for queries.
+#[derive(Debug, Clone)]
+pub struct ProjectIndex {
+    ast: ProjectAst,
+    /// Position of each element by ID, as file and element index
+    elements: HashMap<String, (usize, usize)>,
+}
+
+/// An element together with the file that defines it.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ElementRef<'a> {
+    /// Path of the file, relative to the project root
+    #[serde(serialize_with = "serialize_file_path")]
+    pub file: &'a FileAst,
+    /// The element
+    pub element: &'a CodeElement,
+}
+
+/// The elements around one element in the reference graph, from
+/// [`ProjectIndex::reference_subgraph`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReferenceSubgraph {
+    /// IDs of the elements in the subgraph, starting with the central element
+    /// and in order of distance from it
+    pub element_ids: Vec<String>,
+    /// References between the elements, as referencing and referenced ID
+    pub edges: Vec<(String, String)>,
+}
+
+/// An element whose text matched [`ProjectIndex::search_text`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TextMatch<'a> {
+    /// The matching element
+    #[serde(flatten)]
+    pub found: ElementRef<'a>,
+    /// The first part of the element that matched
+    pub field: TextField,
+    /// The matching name, signature or documentation line
+    pub text: String,
+}
+
+/// Parts of an element searched by [`ProjectIndex::search_text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextField {
+    /// The element name or qualified name
+    Name,
+    /// The function signature
+    Signature,
+    /// A line of the documentation comments
+    Doc,
+}
+
+/// Criteria for [`ProjectIndex::search`]; unset criteria match everything.
+#[derive(Debug, Clone, Default)]
+pub struct ElementQuery {
+    /// Text contained in the element's name or qualified name, ignoring case
+    pub name: Option<String>,
+    /// Element type, such as `function` (see
+    /// [`ElementType::matches_filter`](crate::ElementType::matches_filter))
+    pub element_type: Option<String>,
+    /// Prefix of the path of the element's file, relative to the project root
+    pub path: Option<String>,
+    /// Maximum number of results
+    pub limit: Option<usize>,
+}
+
+impl ProjectIndex {
+    /// Index the elements of `ast`.
+    pub fn new(ast: ProjectAst) -> Self {
+        let mut elements = HashMap::new();
+        for (file_index, file) in ast.files.iter().enumerate() {
+            for (element_index, element) in file.elements.iter().enumerate() {
+                elements.insert(element.id.clone(), (file_index, element_index));
+            }
+        }
+        Self { ast, elements }
+    }
+
+    /// The indexed project.
+    pub fn ast(&self) -> &ProjectAst {
+        &self.ast
+    }
+
+    /// The element with the given ID.
+    pub fn element(&self, id: &str) -> Option<ElementRef<'_>> {
+        let &(file_index, element_index) = self.elements.get(id)?;
+        let file = &self.ast.files[file_index];
+        Some(ElementRef {
+            file,
+            element: &file.elements[element_index],
+        })
+    }
+
+    /// Source code of an element, read from its file.
+    ///
+    /// Returns `None` if there is no element with this ID. The file is read
+    /// on each call, so the source can differ from what was extracted if the
+    /// file changed since.
+    pub fn element_source(&self, id: &str) -> Result<Option<String>> {
+        let Some(found) = self.element(id) else {
+            return Ok(None);
+        };
+        let bytes = fs::read(&found.file.path)?;
+        let location = &found.element.location;
+        // Extractions without byte offsets fall back to whole lines.
+        let source = match bytes.get(location.byte_start..location.byte_end) {
+            Some(source) if !source.is_empty() => String::from_utf8_lossy(source).into_owned(),
+            _ => String::from_utf8_lossy(&bytes)
+                .lines()
+                .skip(location.line_start.saturating_sub(1))
+                .take(location.line_end + 1 - location.line_start.max(1))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        Ok(Some(source))
+    }
+
+    /// The elements within `depth` references of `id`, in either direction,
+    /// with the references between them.
+    ///
+    /// References are function calls and type usages resolved to an element
+    /// (see [`ReferenceGraph`](crate::ReferenceGraph)).
+    pub fn reference_subgraph(&self, id: &str, depth: usize) -> ReferenceSubgraph {
+        let graph = &self.ast.reference_graph;
+        let mut distances = HashMap::from([(id.to_string(), 0)]);
+        let mut subgraph = ReferenceSubgraph {
+            element_ids: vec![id.to_string()],
+            edges: Vec::new(),
+        };
+        let mut queue = VecDeque::from([id.to_string()]);
+        let mut edges = BTreeSet::new();
+        while let Some(current) = queue.pop_front() {
+            let distance = distances[&current];
+            let callees = graph.references_of(&current).iter();
+            let callers = graph.referenced_by(&current).iter();
+            let neighbors = callees
+                .map(|callee| (callee, (current.clone(), callee.clone())))
+                .chain(callers.map(|caller| (caller, (caller.clone(), current.clone()))));
+            for (neighbor, edge) in neighbors {
+                if !distances.contains_key(neighbor) {
+                    if distance == depth {
+                        continue;
+                    }
+                    distances.insert(neighbor.clone(), distance + 1);
+                    subgraph.element_ids.push(neighbor.clone());
+                    queue.push_back(neighbor.clone());
+                }
+                edges.insert(edge);
+            }
+        }
+        subgraph.edges = edges.into_iter().collect();
+        subgraph
+    }
+
+    /// The file at `relative_path` from the project root.
+    pub fn file(&self, relative_path: &str) -> Option<&FileAst> {
+        self.ast
+            .files
+            .iter()
+            .find(|file| file_path(file) == relative_path.trim_start_matches("./"))
+    }
+
+    /// Elements whose name, qualified name, signature or documentation
+    /// matches `pattern`, in file order.
+    ///
+    /// `element_types` are filters such as `function` (see
+    /// [`ElementType::matches_filter`](crate::ElementType::matches_filter));
+    /// an empty slice selects every element.
+    pub fn search_text(&self, pattern: &Regex, element_types: &[String]) -> Vec<TextMatch<'_>> {
+        let mut matches = Vec::new();
+        for file in &self.ast.files {
+            for element in &file.elements {
+                let of_type = element_types.is_empty()
+                    || element_types
+                        .iter()
+                        .any(|filter| element.element_type.matches_filter(filter));
+                if !of_type {
+                    continue;
+                }
+                let qualified_name = &element.hierarchy.qualified_name;
+                let matched = if pattern.is_match(&element.name) || pattern.is_match(qualified_name)
+                {
+                    Some((TextField::Name, qualified_name.clone()))
+                } else if let Some(signature) = element
+                    .signature
+                    .as_ref()
+                    .filter(|signature| pattern.is_match(signature))
+                {
+                    Some((TextField::Signature, signature.clone()))
+                } else {
+                    element
+                        .doc_comments
+                        .iter()
+                        .find(|line| pattern.is_match(line))
+                        .map(|line| (TextField::Doc, line.trim().to_string()))
+                };
+                if let Some((field, text)) = matched {
+                    matches.push(TextMatch {
+                        found: ElementRef { file, element },
+                        field,
+                        text,
+                    });
+                }
+            }
+        }
+        matches
+    }
+
+    /// Elements matching `query`.
+    ///
+    /// Elements whose name equals the searched text come first, then those
+    /// whose name starts with it, then other matches; otherwise results are
+    /// in file order.
+    pub fn search(&self, query: &ElementQuery) -> Vec<ElementRef<'_>> {
+        let needle = query.name.as_deref().map(str::to_lowercase);
+        let in_path = |file: &FileAst| {
+            let path = query
+                .path
+                .as_deref()
+                .map(|path| path.trim_start_matches("./"));
+            path.is_none_or(|path| file_path(file).starts_with(path))
+        };
+        let of_type = |element: &CodeElement| {
+            let filter = query.element_type.as_deref();
+            filter.is_none_or(|filter| element.element_type.matches_filter(filter))
+        };
+
+        let mut matches = Vec::new();
+        for file in self.ast.files.iter().filter(|file| in_path(file)) {
+            for element in file.elements.iter().filter(|element| of_type(element)) {
+                let rank = match &needle {
+                    Some(needle) => match name_rank(element, needle) {
+                        Some(rank) => rank,
+                        None => continue,
+                    },
+                    None => 0,
+                };
+                matches.push((rank, ElementRef { file, element }));
+            }
+        }
+        // Stable, so equally ranked elements stay in file order.
+        matches.sort_by_key(|(rank, _)| *rank);
+        matches
+            .into_iter()
+            .map(|(_, element)| element)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// How well `element` matches the lowercase `needle`, lower being better, or
+/// `None` if it doesn't.
+fn name_rank(element: &CodeElement, needle: &str) -> Option<u8> {
+    let name = element.name.to_lowercase();
+    if name == needle {
+        Some(0)
+    } else if name.starts_with(needle) {
+        Some(1)
+    } else if name.contains(needle) {
+        Some(2)
+    } else if element
+        .hierarchy
+        .qualified_name
+        .to_lowercase()
+        .contains(needle)
+    {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Relative path of `file` with forward slashes.
+fn file_path(file: &FileAst) -> String {
+    file.relative_path.to_string_lossy().replace('\\', "/")
+}
+
+fn serialize_file_path<S: serde::Serializer>(
+    file: &&FileAst,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&file_path(file))
+}
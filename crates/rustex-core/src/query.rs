@@ -0,0 +1,642 @@
+//! Ad-hoc queries over an extracted project.
+//!
+//! Queries select elements with a small language:
+//!
+//! ```text
+//! functions where complexity > 15 and visibility = pub order by complexity desc limit 10
+//! ```
+//!
+//! A query starts with the elements to select: `elements` for all of them or
+//! a plural element type such as `functions`, `structs` or `type_aliases`. An
+//! optional `where` clause filters them with comparisons joined by `and`,
+//! `or`, `not` and parentheses, `order by <field> [asc|desc]` sorts them and
+//! `limit <n>` keeps the first ones. Keywords are case-insensitive.
+//!
+//! Comparisons are `<field> <op> <value>`, with the operators `=`, `!=`, `<`,
+//! `<=`, `>`, `>=` and `~` (regular expression match). Values are numbers,
+//! `true` or `false`, quoted strings, or bare words such as `pub(crate)`.
+//!
+//! | Field | Value |
+//! |-------|-------|
+//! | `name`, `qualified_name` | Name of the element |
+//! | `type` | Element type, such as `function` |
+//! | `visibility` | `pub`, `pub(crate)`, `pub(super)`, `pub(in)`, `private` |
+//! | `file` | Path of the file, relative to the project root |
+//! | `signature` | Signature of functions |
+//! | `doc` | Documentation comments |
+//! | `complexity`, `cognitive` | Cyclomatic and cognitive complexity |
+//! | `lines`, `line` | Length and first line of the element |
+//! | `callers`, `callees` | Number of resolved references to and from the element |
+//! | `documented`, `deprecated`, `generated` | `true` or `false` |
+//!
+//! Elements without a value for a field, such as the signature of a struct,
+//! match no comparison on it and sort last.
+
+use crate::ast_data::{CodeElement, ElementType, Visibility};
+use crate::errors::{Result, RustExError};
+use crate::project_index::{ElementRef, ProjectIndex};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// A parsed query, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Query {
+    element_type: Option<ElementType>,
+    condition: Option<Condition>,
+    order_by: Option<(Field, bool)>,
+    limit: Option<usize>,
+}
+
+impl Query {
+    /// Parse a query.
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(query)?,
+            position: 0,
+        };
+        let query = parser.query()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(token) => Err(invalid(format!("unexpected `{}`", token))),
+        }
+    }
+
+    /// Elements of `index` selected by the query, in file order unless the
+    /// query orders them.
+    pub fn run<'a>(&self, index: &'a ProjectIndex) -> Vec<ElementRef<'a>> {
+        let mut selected: Vec<ElementRef<'a>> = index
+            .ast()
+            .files
+            .iter()
+            .flat_map(|file| {
+                file.elements
+                    .iter()
+                    .map(move |element| ElementRef { file, element })
+            })
+            .filter(|found| {
+                self.element_type
+                    .as_ref()
+                    .is_none_or(|element_type| found.element.element_type == *element_type)
+            })
+            .filter(|found| {
+                self.condition
+                    .as_ref()
+                    .is_none_or(|condition| condition.matches(index, found))
+            })
+            .collect();
+
+        if let Some((field, descending)) = self.order_by {
+            // Stable, so equal elements stay in file order.
+            selected.sort_by(|a, b| {
+                let (a, b) = (field.value(index, a), field.value(index, b));
+                match (a, b) {
+                    (Value::Missing, Value::Missing) => Ordering::Equal,
+                    (Value::Missing, _) => Ordering::Greater,
+                    (_, Value::Missing) => Ordering::Less,
+                    (a, b) if descending => b.compare(&a),
+                    (a, b) => a.compare(&b),
+                }
+            });
+        }
+        selected.truncate(self.limit.unwrap_or(usize::MAX));
+        selected
+    }
+}
+
+impl FromStr for Query {
+    type Err = RustExError;
+
+    fn from_str(query: &str) -> Result<Self> {
+        Self::parse(query)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> RustExError {
+    RustExError::Query(message.into())
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Compare(Field, Operator, Literal),
+    Matches(Field, Regex),
+}
+
+impl Condition {
+    fn matches(&self, index: &ProjectIndex, found: &ElementRef<'_>) -> bool {
+        match self {
+            Condition::And(a, b) => a.matches(index, found) && b.matches(index, found),
+            Condition::Or(a, b) => a.matches(index, found) || b.matches(index, found),
+            Condition::Not(condition) => !condition.matches(index, found),
+            Condition::Matches(field, regex) => match field.value(index, found) {
+                Value::Text(text) => regex.is_match(&text),
+                _ => false,
+            },
+            Condition::Compare(field, operator, literal) => {
+                let element = found.element;
+                // Types and visibilities compare by filter, so `pub(in)`
+                // selects every restricted visibility.
+                let ordering = match (field, literal) {
+                    (Field::Type, Literal::Text(filter)) => {
+                        Some(filter_ordering(element.element_type.matches_filter(filter)))
+                    }
+                    (Field::Visibility, Literal::Text(filter)) => {
+                        Some(filter_ordering(element.visibility.matches_filter(filter)))
+                    }
+                    _ => field.value(index, found).compare_literal(literal),
+                };
+                ordering.is_some_and(|ordering| operator.accepts(ordering))
+            }
+        }
+    }
+}
+
+/// Orders a filter comparison so that only `=` accepts a match and only
+/// `!=` a mismatch.
+fn filter_ordering(matches: bool) -> Ordering {
+    if matches {
+        Ordering::Equal
+    } else {
+        Ordering::Less
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    QualifiedName,
+    Type,
+    Visibility,
+    File,
+    Signature,
+    Doc,
+    Complexity,
+    Cognitive,
+    Lines,
+    Line,
+    Callers,
+    Callees,
+    Documented,
+    Deprecated,
+    Generated,
+}
+
+/// Kinds of field values, for checking comparisons when parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Number,
+    Bool,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("name", Field::Name),
+    ("qualified_name", Field::QualifiedName),
+    ("type", Field::Type),
+    ("visibility", Field::Visibility),
+    ("file", Field::File),
+    ("signature", Field::Signature),
+    ("doc", Field::Doc),
+    ("complexity", Field::Complexity),
+    ("cognitive", Field::Cognitive),
+    ("lines", Field::Lines),
+    ("line", Field::Line),
+    ("callers", Field::Callers),
+    ("callees", Field::Callees),
+    ("documented", Field::Documented),
+    ("deprecated", Field::Deprecated),
+    ("generated", Field::Generated),
+];
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        FIELDS
+            .iter()
+            .find(|(field_name, _)| field_name.eq_ignore_ascii_case(name))
+            .map(|&(_, field)| field)
+    }
+
+    fn name(self) -> &'static str {
+        FIELDS
+            .iter()
+            .find(|&&(_, field)| field == self)
+            .map_or("", |(name, _)| name)
+    }
+
+    fn kind(self) -> Kind {
+        match self {
+            Field::Name
+            | Field::QualifiedName
+            | Field::Type
+            | Field::Visibility
+            | Field::File
+            | Field::Signature
+            | Field::Doc => Kind::Text,
+            Field::Complexity
+            | Field::Cognitive
+            | Field::Lines
+            | Field::Line
+            | Field::Callers
+            | Field::Callees => Kind::Number,
+            Field::Documented | Field::Deprecated | Field::Generated => Kind::Bool,
+        }
+    }
+
+    fn value(self, index: &ProjectIndex, found: &ElementRef<'_>) -> Value {
+        let element: &CodeElement = found.element;
+        let location = &element.location;
+        let graph = &index.ast().reference_graph;
+        let number = |value: usize| Value::Number(value as i64);
+        match self {
+            Field::Name => Value::Text(element.name.clone()),
+            Field::QualifiedName => Value::Text(element.hierarchy.qualified_name.clone()),
+            Field::Type => Value::Text(element.element_type.filter_name().to_string()),
+            Field::Visibility => Value::Text(element.visibility.to_string()),
+            Field::File => Value::Text(
+                found
+                    .file
+                    .relative_path
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+            ),
+            Field::Signature => element
+                .signature
+                .clone()
+                .map_or(Value::Missing, Value::Text),
+            Field::Doc => Value::Text(element.doc_comments.join("\n")),
+            Field::Complexity => element
+                .complexity_metrics
+                .as_ref()
+                .map(|metrics| metrics.cyclomatic)
+                .or(element.complexity)
+                .map_or(Value::Missing, |value| Value::Number(value.into())),
+            Field::Cognitive => element
+                .complexity_metrics
+                .as_ref()
+                .map_or(Value::Missing, |metrics| {
+                    Value::Number(metrics.cognitive.into())
+                }),
+            Field::Lines => number(location.line_end + 1 - location.line_start.max(1)),
+            Field::Line => number(location.line_start),
+            Field::Callers => number(graph.referenced_by(&element.id).len()),
+            Field::Callees => number(graph.references_of(&element.id).len()),
+            Field::Documented => Value::Bool(!element.doc_comments.is_empty()),
+            Field::Deprecated => Value::Bool(element.deprecation.is_some()),
+            Field::Generated => Value::Bool(element.is_generated),
+        }
+    }
+}
+
+/// The value of a field for one element.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(i64),
+    Bool(bool),
+    Missing,
+}
+
+impl Value {
+    /// Order two values of the same field.
+    fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Text(a), Value::Text(b)) => a.cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            _ => Ordering::Equal,
+        }
+    }
+
+    fn compare_literal(&self, literal: &Literal) -> Option<Ordering> {
+        match (self, literal) {
+            (Value::Text(a), Literal::Text(b)) => Some(a.as_str().cmp(b.as_str())),
+            (Value::Number(a), Literal::Number(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Literal::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Text(String),
+    Number(i64),
+    Bool(bool),
+}
+
+impl Literal {
+    fn kind(&self) -> Kind {
+        match self {
+            Literal::Text(_) => Kind::Text,
+            Literal::Number(_) => Kind::Number,
+            Literal::Bool(_) => Kind::Bool,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Operator {
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Eq => ordering.is_eq(),
+            Operator::Ne => ordering.is_ne(),
+            Operator::Lt => ordering.is_lt(),
+            Operator::Le => ordering.is_le(),
+            Operator::Gt => ordering.is_gt(),
+            Operator::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(i64),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Text(text) => write!(f, "{:?}", text),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &["!=", "<=", ">=", "=", "<", ">", "~", "(", ")"];
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = query.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| invalid("unterminated string"))?;
+            tokens.push(Token::Text(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if c.is_ascii_digit()
+            || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let end = rest[1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(rest.len(), |end| end + 1);
+            let number = rest[..end]
+                .parse()
+                .map_err(|_| invalid(format!("invalid number `{}`", &rest[..end])))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = rest
+                .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '/')))
+                .unwrap_or(rest.len());
+            // Keep visibilities such as `pub(crate)` in one word.
+            if rest[end..].starts_with('(') && rest[..end].eq_ignore_ascii_case("pub") {
+                end += rest[end..]
+                    .find(')')
+                    .ok_or_else(|| invalid("unclosed `pub(`"))?
+                    + 1;
+            }
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            return Err(invalid(format!("unexpected character `{}`", c)));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consume the next token if it is the given keyword.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found =
+            matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", keyword)))
+        }
+    }
+
+    fn expected(&self, what: &str) -> RustExError {
+        match self.peek() {
+            Some(token) => invalid(format!("expected {} but found `{}`", what, token)),
+            None => invalid(format!("expected {} at the end of the query", what)),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        let element_type = match self.next() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("elements") => None,
+            Some(Token::Word(word)) => Some(plural_element_type(&word).ok_or_else(|| {
+                invalid(format!(
+                    "unknown element kind `{}`, expected `elements` or a plural element type \
+                     such as `functions`",
+                    word
+                ))
+            })?),
+            _ => return Err(invalid("a query starts with `elements` or an element type")),
+        };
+        let condition = if self.keyword("where") {
+            Some(self.or()?)
+        } else {
+            None
+        };
+        let order_by = if self.keyword("order") {
+            self.expect_keyword("by")?;
+            let field = self.field()?;
+            let descending = if self.keyword("desc") {
+                true
+            } else {
+                self.keyword("asc");
+                false
+            };
+            Some((field, descending))
+        } else {
+            None
+        };
+        let limit = if self.keyword("limit") {
+            match self.next() {
+                Some(Token::Number(limit)) if limit >= 0 => Some(limit as usize),
+                _ => {
+                    self.position -= 1;
+                    return Err(self.expected("a number after `limit`"));
+                }
+            }
+        } else {
+            None
+        };
+        Ok(Query {
+            element_type,
+            condition,
+            order_by,
+            limit,
+        })
+    }
+
+    fn or(&mut self) -> Result<Condition> {
+        let mut condition = self.and()?;
+        while self.keyword("or") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition> {
+        let mut condition = self.unary()?;
+        while self.keyword("and") {
+            condition = Condition::And(Box::new(condition), Box::new(self.unary()?));
+        }
+        Ok(condition)
+    }
+
+    fn unary(&mut self) -> Result<Condition> {
+        if self.keyword("not") {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::Symbol("(")) {
+            self.position += 1;
+            let condition = self.or()?;
+            if self.next() != Some(Token::Symbol(")")) {
+                self.position -= 1;
+                return Err(self.expected("`)`"));
+            }
+            return Ok(condition);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Condition> {
+        let field = self.field()?;
+        let operator = match self.next() {
+            Some(Token::Symbol("=")) => Operator::Eq,
+            Some(Token::Symbol("!=")) => Operator::Ne,
+            Some(Token::Symbol("<")) => Operator::Lt,
+            Some(Token::Symbol("<=")) => Operator::Le,
+            Some(Token::Symbol(">")) => Operator::Gt,
+            Some(Token::Symbol(">=")) => Operator::Ge,
+            Some(Token::Symbol("~")) => {
+                let pattern = match self.next() {
+                    Some(Token::Text(pattern) | Token::Word(pattern)) => pattern,
+                    _ => {
+                        self.position -= 1;
+                        return Err(self.expected("a pattern after `~`"));
+                    }
+                };
+                if field.kind() != Kind::Text {
+                    return Err(invalid(format!(
+                        "`~` needs a text field, not `{}`",
+                        field.name()
+                    )));
+                }
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| invalid(format!("invalid pattern `{}`: {}", pattern, e)))?;
+                return Ok(Condition::Matches(field, regex));
+            }
+            _ => {
+                self.position -= 1;
+                return Err(self.expected("a comparison operator"));
+            }
+        };
+        let literal = match self.next() {
+            Some(Token::Number(number)) => Literal::Number(number),
+            Some(Token::Text(text)) => Literal::Text(text),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("true") => Literal::Bool(true),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("false") => Literal::Bool(false),
+            Some(Token::Word(word)) => Literal::Text(word),
+            _ => {
+                self.position -= 1;
+                return Err(self.expected("a value"));
+            }
+        };
+        if literal.kind() != field.kind() {
+            return Err(invalid(format!(
+                "cannot compare `{}` with `{}`",
+                field.name(),
+                self.tokens[self.position - 1]
+            )));
+        }
+        let is_filter = matches!(field, Field::Type | Field::Visibility);
+        if is_filter && !matches!(operator, Operator::Eq | Operator::Ne) {
+            return Err(invalid(format!(
+                "`{}` only supports `=`, `!=` and `~`",
+                field.name()
+            )));
+        }
+        if let Literal::Text(filter) = &literal {
+            if field == Field::Type && !ElementType::is_valid_filter(filter) {
+                return Err(invalid(format!("unknown element type `{}`", filter)));
+            }
+            if field == Field::Visibility && !Visibility::is_valid_filter(filter) {
+                return Err(invalid(format!("unknown visibility `{}`", filter)));
+            }
+        }
+        Ok(Condition::Compare(field, operator, literal))
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let field = Field::parse(word)
+                    .ok_or_else(|| invalid(format!("unknown field `{}`", word)))?;
+                self.position += 1;
+                Ok(field)
+            }
+            _ => Err(self.expected("a field")),
+        }
+    }
+}
+
+/// The element type named by a plural such as `functions` or `type_aliases`.
+fn plural_element_type(word: &str) -> Option<ElementType> {
+    ElementType::ALL
+        .iter()
+        .find(|element_type| {
+            let name = element_type.filter_name();
+            let suffix = if name.ends_with('s') { "es" } else { "s" };
+            format!("{}{}", name, suffix).eq_ignore_ascii_case(word)
+        })
+        .cloned()
+}
@@ -0,0 +1,212 @@
+//! Resolution of `pub use` re-exports.
+//!
+//! Crates often define items in private modules and re-export them from the
+//! crate root, so the path users import differs from the path the item is
+//! defined at. Re-exports are followed through chains, glob re-exports and
+//! re-exported modules, within a single crate; re-exports of other crates'
+//! items are not tracked.
+
+use crate::ast_data::{CodeElement, ElementType, FileAst, ImportInfo, Visibility};
+use std::collections::{HashMap, HashSet};
+
+/// Upper bound on the paths recorded per item, which also ends cycles such
+/// as a module re-exporting itself under another name.
+const MAX_PATHS_PER_ITEM: usize = 64;
+
+/// A `pub use` declaration, resolved to a crate-relative path.
+struct Reexport {
+    /// Module the declaration appears in
+    scope: String,
+    /// Path of the re-exported item or module
+    source: String,
+    /// Name it is exported under, or `None` for a glob re-export
+    name: Option<String>,
+}
+
+impl Reexport {
+    /// The path `path` is also reachable at through this re-export, if any.
+    ///
+    /// Returns the new path and the part of `path` below `source`.
+    fn apply<'a>(&self, path: &'a str) -> Option<(String, &'a str)> {
+        let Some(name) = &self.name else {
+            let (parent, last) = path.rsplit_once("::")?;
+            return (parent == self.source).then(|| (format!("{}::{}", self.scope, last), ""));
+        };
+        let exported = format!("{}::{}", self.scope, name);
+        if path == self.source {
+            return Some((exported, ""));
+        }
+        let rest = path.strip_prefix(&self.source)?.strip_prefix("::")?;
+        Some((format!("{}::{}", exported, rest), rest))
+    }
+}
+
+/// Fill in [`exported_paths`] for the public items of one crate.
+///
+/// `files` must be all the extracted files of the crate. An item's canonical
+/// path counts as exported when every module enclosing it is `pub`.
+///
+/// [`exported_paths`]: crate::ast_data::ElementNamespace::exported_paths
+pub fn resolve_reexports<'a>(files: impl IntoIterator<Item = &'a mut FileAst>) {
+    let mut files: Vec<&mut FileAst> = files.into_iter().collect();
+
+    let element_types: HashMap<&str, &ElementType> = files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .map(|element| (element.id.as_str(), &element.element_type))
+        .collect();
+    let is_module_item = |element: &CodeElement| match &element.hierarchy.parent_id {
+        None => element.element_type != ElementType::Impl,
+        Some(parent) => element_types.get(parent.as_str()) == Some(&&ElementType::Module),
+    };
+
+    let mut modules: HashMap<String, bool> = HashMap::from([("crate".to_string(), true)]);
+    let mut item_paths = HashSet::new();
+    for element in files.iter().flat_map(|file| &file.elements) {
+        // Private modules may not have been extracted, but their items were.
+        item_paths.insert(element.hierarchy.module_path.clone());
+        if !is_module_item(element) {
+            continue;
+        }
+        let path = element.hierarchy.namespace.canonical_path.clone();
+        if element.element_type == ElementType::Module {
+            modules.insert(path.clone(), element.visibility == Visibility::Public);
+        }
+        item_paths.insert(path);
+    }
+
+    let reexports: Vec<Reexport> = files
+        .iter()
+        .flat_map(|file| &file.imports)
+        .filter(|import| import.is_reexport)
+        .filter_map(|import| resolve_import(import, &item_paths))
+        .collect();
+
+    let exported: HashMap<String, Vec<String>> = files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .filter(|element| element.visibility == Visibility::Public && is_module_item(element))
+        .map(|element| {
+            let path = &element.hierarchy.namespace.canonical_path;
+            (
+                element.id.clone(),
+                exported_paths(path, &reexports, &modules),
+            )
+        })
+        .collect();
+
+    for element in files.iter_mut().flat_map(|file| file.elements.iter_mut()) {
+        if let Some(paths) = exported.get(&element.id) {
+            element.hierarchy.namespace.exported_paths = paths.clone();
+        }
+    }
+}
+
+/// All public paths of the item at `canonical_path`, canonical path first.
+fn exported_paths(
+    canonical_path: &str,
+    reexports: &[Reexport],
+    modules: &HashMap<String, bool>,
+) -> Vec<String> {
+    let parent = |path: &str| {
+        path.rsplit_once("::")
+            .map_or("", |(parent, _)| parent)
+            .to_string()
+    };
+    // Every path the item can be named by inside the crate, and whether it is public.
+    let mut paths = vec![(
+        canonical_path.to_string(),
+        is_public_module(&parent(canonical_path), modules),
+    )];
+
+    let mut next = 0;
+    while next < paths.len() && paths.len() < MAX_PATHS_PER_ITEM {
+        let path = paths[next].0.clone();
+        for reexport in reexports {
+            let Some((new_path, rest)) = reexport.apply(&path) else {
+                continue;
+            };
+            // Modules between the re-exported module and the item stay as they are.
+            let source_path = path[..path.len() - rest.len()].trim_end_matches("::");
+            let inner_public = rest.match_indices("::").all(|(end, _)| {
+                modules.get(&format!("{}::{}", source_path, &rest[..end])) == Some(&true)
+            });
+            let is_public = is_public_module(&reexport.scope, modules) && inner_public;
+            match paths.iter_mut().find(|(known, _)| *known == new_path) {
+                Some((_, public)) => *public |= is_public,
+                None => paths.push((new_path, is_public)),
+            }
+        }
+        next += 1;
+    }
+
+    paths
+        .into_iter()
+        .filter(|(_, public)| *public)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Whether `module` and all modules enclosing it are `pub`.
+fn is_public_module(module: &str, modules: &HashMap<String, bool>) -> bool {
+    let mut prefix = String::new();
+    module.split("::").all(|segment| {
+        if !prefix.is_empty() {
+            prefix.push_str("::");
+        }
+        prefix.push_str(segment);
+        modules.get(&prefix) == Some(&true)
+    })
+}
+
+/// Resolve a re-export to a crate-relative path.
+///
+/// Returns `None` for re-exports of other crates and for `as _` imports,
+/// which don't add a name.
+fn resolve_import(import: &ImportInfo, item_paths: &HashSet<String>) -> Option<Reexport> {
+    let mut segments: Vec<&str> = import
+        .module_path
+        .split("::")
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let item = import.imported_items.first();
+    segments.extend(item.map(String::as_str));
+
+    let name = match (import.is_glob, &import.alias, item) {
+        (true, _, _) => None,
+        (false, Some(alias), _) if alias == "_" => return None,
+        (false, Some(alias), _) => Some(alias.clone()),
+        (false, None, Some(item)) => Some(item.clone()),
+        (false, None, None) => return None,
+    };
+
+    let mut base: Vec<&str> = import.scope.split("::").collect();
+    let mut rest = segments.as_slice();
+    match rest.first().copied() {
+        Some("crate") => {
+            base = vec!["crate"];
+            rest = &rest[1..];
+        }
+        Some("self") => rest = &rest[1..],
+        Some("super") => {
+            while rest.first() == Some(&"super") {
+                base.pop().filter(|_| !base.is_empty())?;
+                rest = &rest[1..];
+            }
+        }
+        // Bare paths name a local item or module, or else another crate.
+        Some(first) => {
+            if !item_paths.contains(&format!("{}::{}", import.scope, first)) {
+                return None;
+            }
+        }
+        None => return None,
+    }
+    base.extend_from_slice(rest);
+
+    Some(Reexport {
+        scope: import.scope.clone(),
+        source: base.join("::"),
+        name,
+    })
+}
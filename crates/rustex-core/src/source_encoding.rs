@@ -0,0 +1,96 @@
+//! Decoding of source files into text.
+//!
+//! Rust source is UTF-8, but files in the wild carry byte order marks or
+//! comments saved in legacy encodings such as latin-1. BOMs are stripped,
+//! UTF-16 files are transcoded, and invalid UTF-8 can be decoded lossily,
+//! replacing the offending bytes with U+FFFD.
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// Source text decoded from a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedSource {
+    /// The decoded text, without a byte order mark
+    pub text: String,
+    /// Why decoding was lossy, if any bytes were replaced
+    pub lossy: Option<String>,
+    /// Length of the UTF-8 byte order mark stripped from the file, which
+    /// offsets into `text` must be shifted by to index the file itself
+    pub bom_len: usize,
+}
+
+/// Decode the bytes of a source file.
+///
+/// When `lossy_fallback` is false, content that doesn't decode cleanly is
+/// an error describing the first invalid position.
+pub fn decode_source(bytes: Vec<u8>, lossy_fallback: bool) -> Result<DecodedSource, String> {
+    if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+        return decode_utf16(rest, u16::from_le_bytes, lossy_fallback);
+    }
+    if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+        return decode_utf16(rest, u16::from_be_bytes, lossy_fallback);
+    }
+
+    let (bytes, bom_len) = match bytes.strip_prefix(UTF8_BOM) {
+        Some(rest) => (rest.to_vec(), UTF8_BOM.len()),
+        None => (bytes, 0),
+    };
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(DecodedSource {
+            text,
+            lossy: None,
+            bom_len,
+        }),
+        Err(e) => {
+            // Report the position in the file, BOM included.
+            let message = format!(
+                "invalid UTF-8 at byte {}",
+                bom_len + e.utf8_error().valid_up_to()
+            );
+            if !lossy_fallback {
+                return Err(message);
+            }
+            Ok(DecodedSource {
+                text: String::from_utf8_lossy(e.as_bytes()).into_owned(),
+                lossy: Some(message),
+                bom_len,
+            })
+        }
+    }
+}
+
+fn decode_utf16(
+    bytes: &[u8],
+    to_unit: fn([u8; 2]) -> u16,
+    lossy_fallback: bool,
+) -> Result<DecodedSource, String> {
+    let units = bytes.chunks(2).map(|pair| match pair {
+        [a, b] => to_unit([*a, *b]),
+        // A trailing odd byte can't be a code unit; this is a lone surrogate.
+        _ => 0xD800,
+    });
+
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut lossy = None;
+    for (index, unit) in char::decode_utf16(units).enumerate() {
+        match unit {
+            Ok(c) => text.push(c),
+            Err(_) => {
+                let message = format!("invalid UTF-16 at character {}", index);
+                if !lossy_fallback {
+                    return Err(message);
+                }
+                lossy.get_or_insert(message);
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+        }
+    }
+    // Transcoded text doesn't share offsets with the file at all.
+    Ok(DecodedSource {
+        text,
+        lossy,
+        bom_len: 0,
+    })
+}
@@ -0,0 +1,128 @@
+//! Technical debt markers of a project.
+//!
+//! The `tech-debt` plugin collects `TODO`, `FIXME` and `HACK` comments and
+//! `#[allow(dead_code)]` attributes into a [`TechDebtReport`] in its results,
+//! which JSON output carries as is and [`TechDebtReport::from_project`] reads
+//! back for the Markdown output's tech debt section.
+
+use crate::ast_data::ProjectAst;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Name of the plugin collecting the markers.
+pub const TECH_DEBT_PLUGIN: &str = "tech-debt";
+
+/// Key of the report in the plugin's metadata.
+pub const TECH_DEBT_METADATA_KEY: &str = "tech_debt";
+
+/// Kind of a technical debt marker.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TechDebtKind {
+    /// A `TODO` comment
+    Todo,
+    /// A `FIXME` comment
+    Fixme,
+    /// A `HACK` comment
+    Hack,
+    /// An `#[allow(dead_code)]` attribute
+    AllowDeadCode,
+}
+
+impl TechDebtKind {
+    /// All kinds, from the comment markers to the attributes.
+    pub const ALL: [TechDebtKind; 4] = [
+        TechDebtKind::Todo,
+        TechDebtKind::Fixme,
+        TechDebtKind::Hack,
+        TechDebtKind::AllowDeadCode,
+    ];
+}
+
+impl fmt::Display for TechDebtKind {
+    /// Formats the kind as written in code, such as `FIXME`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TechDebtKind::Todo => "TODO",
+            TechDebtKind::Fixme => "FIXME",
+            TechDebtKind::Hack => "HACK",
+            TechDebtKind::AllowDeadCode => "allow(dead_code)",
+        })
+    }
+}
+
+/// A technical debt marker in the code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TechDebtMarker {
+    /// Kind of the marker
+    pub kind: TechDebtKind,
+    /// File of the marker, relative to the project root
+    pub file: PathBuf,
+    /// Line of the marker (1-based)
+    pub line: usize,
+    /// Text after the marker, such as the task of a `TODO`
+    pub text: String,
+    /// Owner named in the marker, as in `TODO(alice)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Qualified name of the element the marker is in or on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub element: Option<String>,
+    /// Days since the line was last changed, from `git blame`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_days: Option<u64>,
+}
+
+/// The technical debt markers of a project, with their counts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TechDebtReport {
+    /// Markers by file and line
+    pub markers: Vec<TechDebtMarker>,
+    /// Number of markers of each kind
+    pub by_kind: BTreeMap<TechDebtKind, usize>,
+    /// Number of markers of each owner
+    pub by_owner: BTreeMap<String, usize>,
+    /// Number of markers without an owner
+    pub unowned: usize,
+}
+
+impl TechDebtReport {
+    /// Report on `markers`, sorting them by file and line.
+    pub fn new(mut markers: Vec<TechDebtMarker>) -> Self {
+        markers.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        let mut report = Self::default();
+        for marker in &markers {
+            *report.by_kind.entry(marker.kind).or_default() += 1;
+            match &marker.owner {
+                Some(owner) => *report.by_owner.entry(owner.clone()).or_default() += 1,
+                None => report.unowned += 1,
+            }
+        }
+        report.markers = markers;
+        report
+    }
+
+    /// The report the `tech-debt` plugin left in the results of `ast`, if it
+    /// ran.
+    pub fn from_project(ast: &ProjectAst) -> Option<Self> {
+        let report = ast
+            .plugin_results
+            .get(TECH_DEBT_PLUGIN)?
+            .metadata
+            .get(TECH_DEBT_METADATA_KEY)?;
+        serde_json::from_value(report.clone()).ok()
+    }
+
+    /// Age of the oldest marker with a known age, in days.
+    pub fn oldest_age_days(&self) -> Option<u64> {
+        self.markers
+            .iter()
+            .filter_map(|marker| marker.age_days)
+            .max()
+    }
+}
@@ -3036,6 +3036,7 @@ impl MockDataGenerator {
             signature: Some(format!("fn {}() -> ()", name)),
             visibility: Visibility::Public,
             doc_comments: vec![format!("Documentation for {}", name)],
+            doc_examples: vec![],
             inline_comments: vec![],
             location: CodeLocation {
                 line_start: 1,
@@ -3043,7 +3044,10 @@ impl MockDataGenerator {
                 char_start: 0,
                 char_end: 100,
                 file_path: PathBuf::from(format!("test.{}", "rs")),
+                byte_start: 0,
+                byte_end: 0,
             },
+            name_location: None,
             attributes: vec!["#[test]".to_string()],
             complexity: Some(5),
             complexity_metrics: Some(ComplexityMetrics {
@@ -3054,6 +3058,8 @@ impl MockDataGenerator {
                 lines_of_code: 10,
                 parameter_count: 0,
                 return_count: 1,
+                closure_count: 0,
+                async_block_count: 0,
             }),
             dependencies: vec![],
             generic_params: vec![],
@@ -3067,6 +3073,11 @@ impl MockDataGenerator {
                     &Visibility::Public,
                 ),
             ),
+            is_generated: false,
+            cfg_predicates: vec![],
+            attribute_info: vec![],
+            deprecation: None,
+            calls: vec![],
         }
     }
 
@@ -3086,6 +3097,8 @@ impl MockDataGenerator {
                     imported_items: vec!["HashMap".to_string()],
                     is_glob: false,
                     alias: None,
+                    is_reexport: false,
+                    scope: "crate".to_string(),
                 },
             ],
             file_metrics: FileMetrics {
@@ -3096,8 +3109,14 @@ impl MockDataGenerator {
                 struct_count: 0,
                 enum_count: 0,
                 trait_count: 0,
+                ownership: Default::default(),
+                unsafe_usage: Default::default(),
             },
             cross_references: vec![],
+            parse_errors: vec![],
+            warnings: vec![],
+            kind: FileKind::Source,
+            partially_extracted: false,
         }
     }
 
@@ -3136,9 +3155,20 @@ impl MockDataGenerator {
                 total_traits: 0,
                 complexity_average: 5.0,
                 complexity_max: 10,
+                deprecations: Default::default(),
+                ownership: Default::default(),
+                unsafe_usage: Default::default(),
             },
             extracted_at: DateTime::<Utc>::from(std::time::SystemTime::now()),
             cross_references: vec![],
+            dependencies_ast: vec![],
+            workspace: None,
+            reference_graph: ReferenceGraph::default(),
+            type_index: Default::default(),
+            impls: vec![],
+            truncation: None,
+            extraction_report: Default::default(),
+            plugin_results: Default::default(),
         }
     }
 
@@ -3154,6 +3184,12 @@ impl MockDataGenerator {
             filters: FilterConfig {
                 include: vec![rust_glob],
                 exclude: vec![target_glob],
+                visibility: vec![],
+                exclude_build_scripts: false,
+                element_types: vec![],
+                name_patterns: vec![],
+                exclude_names: vec![],
+                overrides: Default::default(),
             },
             output_format: OutputFormat::Json,
             ..Default::default()
@@ -0,0 +1,173 @@
+//! Quality thresholds over an extracted project.
+//!
+//! [`check_thresholds`] compares a project against the limits of a
+//! [`ThresholdConfig`], typically the `[thresholds]` section of `rustex.toml`,
+//! so CI can fail builds that make the code base more complex, less documented
+//! or more `unsafe` than agreed, or that depend on vulnerable crates.
+
+use crate::ast_data::{ElementType, ProjectAst};
+use crate::config::ThresholdConfig;
+use crate::metrics_report::MetricsReport;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Plugin whose `vulnerabilities` metric `max_vulnerabilities` limits.
+const VULNERABILITY_PLUGIN: &str = "rustsec-audit";
+
+/// A threshold exceeded by a project, file or function.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdViolation {
+    /// The threshold, by its key in `[thresholds]` such as `max_cyclomatic`
+    pub threshold: String,
+    /// The measured value
+    pub actual: f64,
+    /// The configured limit
+    pub limit: f64,
+    /// Qualified name of the function concerned, for function thresholds
+    pub element: Option<String>,
+    /// File concerned, relative to the project root, for file and function
+    /// thresholds
+    pub file: Option<PathBuf>,
+    /// Line of the function concerned
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for ThresholdViolation {
+    /// Formats the violation as in `src/lib.rs:12: crate::parse: max_cyclomatic is 22 above 15`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: ", file.display(), line)?,
+            (Some(file), None) => write!(f, "{}: ", file.display())?,
+            _ => {}
+        }
+        if let Some(element) = &self.element {
+            write!(f, "{}: ", element)?;
+        }
+        let comparison = if self.threshold.starts_with("min_") {
+            "below"
+        } else {
+            "above"
+        };
+        write!(
+            f,
+            "{} is {} {} {}",
+            self.threshold,
+            round(self.actual),
+            comparison,
+            round(self.limit)
+        )
+    }
+}
+
+/// Values with one decimal at most, so coverage reads as `72.3`.
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// The thresholds of `thresholds` exceeded by `ast`: function thresholds
+/// first, then file thresholds, then project thresholds.
+///
+/// `max_vulnerabilities` is checked against the results of the
+/// `rustsec-audit` plugin, so it passes when the plugin hasn't run on `ast`.
+pub fn check_thresholds(ast: &ProjectAst, thresholds: &ThresholdConfig) -> Vec<ThresholdViolation> {
+    let mut violations = Vec::new();
+    let violation = |threshold: &str, actual: f64, limit: f64| ThresholdViolation {
+        threshold: threshold.to_string(),
+        actual,
+        limit,
+        element: None,
+        file: None,
+        line: None,
+    };
+
+    for file in &ast.files {
+        let functions = file
+            .elements
+            .iter()
+            .filter(|element| element.element_type == ElementType::Function);
+        for function in functions {
+            let metrics = function.complexity_metrics.as_ref();
+            let cyclomatic = metrics
+                .map(|metrics| metrics.cyclomatic)
+                .or(function.complexity);
+            let location = &function.location;
+            let lines = location.line_end + 1 - location.line_start.max(1);
+            let measures = [
+                (
+                    "max_cyclomatic",
+                    cyclomatic.map(f64::from),
+                    thresholds.max_cyclomatic.map(f64::from),
+                ),
+                (
+                    "max_cognitive",
+                    metrics.map(|metrics| f64::from(metrics.cognitive)),
+                    thresholds.max_cognitive.map(f64::from),
+                ),
+                (
+                    "max_function_lines",
+                    Some(lines as f64),
+                    thresholds.max_function_lines.map(|limit| limit as f64),
+                ),
+            ];
+            for (threshold, actual, limit) in measures {
+                if let (Some(actual), Some(limit)) = (actual, limit) {
+                    if actual > limit {
+                        violations.push(ThresholdViolation {
+                            element: Some(function.hierarchy.qualified_name.clone()),
+                            file: Some(file.relative_path.clone()),
+                            line: Some(location.line_start),
+                            ..violation(threshold, actual, limit)
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(limit) = thresholds.max_file_lines {
+        for file in &ast.files {
+            let lines = file.file_metrics.lines_of_code;
+            if lines > limit {
+                violations.push(ThresholdViolation {
+                    file: Some(file.relative_path.clone()),
+                    ..violation("max_file_lines", lines as f64, limit as f64)
+                });
+            }
+        }
+    }
+
+    if thresholds.min_doc_coverage.is_some() || thresholds.max_unsafe.is_some() {
+        let report = MetricsReport::from_project(ast, 0);
+        let coverage = report.documentation.coverage_percent;
+        if let Some(limit) = thresholds
+            .min_doc_coverage
+            .filter(|&limit| coverage < limit)
+        {
+            violations.push(violation("min_doc_coverage", coverage, limit));
+        }
+        let usage = &report.unsafe_usage;
+        let unsafe_count =
+            usage.unsafe_blocks + usage.unsafe_functions + usage.unsafe_impls + usage.unsafe_traits;
+        if let Some(limit) = thresholds.max_unsafe.filter(|&limit| unsafe_count > limit) {
+            violations.push(violation("max_unsafe", unsafe_count as f64, limit as f64));
+        }
+    }
+
+    if let Some(limit) = thresholds.max_vulnerabilities {
+        let vulnerabilities = ast
+            .plugin_results
+            .get(VULNERABILITY_PLUGIN)
+            .and_then(|results| results.metrics.get("vulnerabilities"))
+            .filter(|&&vulnerabilities| vulnerabilities > limit as f64);
+        if let Some(&vulnerabilities) = vulnerabilities {
+            violations.push(violation(
+                "max_vulnerabilities",
+                vulnerabilities,
+                limit as f64,
+            ));
+        }
+    }
+
+    violations
+}
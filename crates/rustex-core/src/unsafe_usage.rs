@@ -0,0 +1,58 @@
+//! Counting of `unsafe` code.
+//!
+//! Uses are counted syntactically on the parsed file, including items that
+//! aren't extracted such as private functions and tests. Blocks inside macro
+//! arguments are counted when the arguments parse as expressions.
+
+use crate::ast_data::UnsafeUsage;
+use syn::visit::Visit;
+
+/// Count the uses of `unsafe` in a file.
+pub fn unsafe_usage(file: &syn::File) -> UnsafeUsage {
+    let mut counter = UnsafeCounter::default();
+    counter.visit_file(file);
+    counter.usage
+}
+
+#[derive(Default)]
+struct UnsafeCounter {
+    usage: UnsafeUsage,
+}
+
+impl<'ast> Visit<'ast> for UnsafeCounter {
+    fn visit_expr_unsafe(&mut self, node: &'ast syn::ExprUnsafe) {
+        self.usage.unsafe_blocks += 1;
+        syn::visit::visit_expr_unsafe(self, node);
+    }
+
+    fn visit_signature(&mut self, node: &'ast syn::Signature) {
+        if node.unsafety.is_some() {
+            self.usage.unsafe_functions += 1;
+        }
+        syn::visit::visit_signature(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        if node.unsafety.is_some() {
+            self.usage.unsafe_impls += 1;
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        if node.unsafety.is_some() {
+            self.usage.unsafe_traits += 1;
+        }
+        syn::visit::visit_item_trait(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        let parser = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+        if let Ok(args) = node.parse_body_with(parser) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        syn::visit::visit_macro(self, node);
+    }
+}
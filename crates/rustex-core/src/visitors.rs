@@ -2,7 +2,9 @@
 
 use crate::ast_data::*;
 use crate::complexity::ComplexityCalculator;
-use crate::config::ExtractorConfig;
+use crate::config::{ExtractorConfig, FilterConfig};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use syn::visit::Visit;
 use syn::spanned::Spanned;
@@ -23,6 +25,88 @@ pub struct CodeElementVisitor {
     current_element_stack: Vec<String>,
     /// Namespace resolver for handling imports and qualified names
     namespace_resolver: crate::ast_data::NamespaceResolver,
+    /// Enclosing impl blocks, innermost last
+    impl_stack: Vec<ImplContext>,
+    /// `#[cfg]` predicates of each enclosing scope, innermost last
+    cfg_stack: Vec<Vec<CfgPredicate>>,
+    /// Calls collected in each enclosing function body, innermost last
+    call_stack: Vec<Vec<CallSite>>,
+    /// Closure bookkeeping of each enclosing function body, innermost last;
+    /// empty unless `extract_closures` is set
+    closure_scopes: Vec<ClosureScope>,
+    /// Element type and name filters of the configuration
+    selector: ElementSelector,
+    /// Line offset of each top-level item, for items parsed apart from their file
+    item_line_offsets: Vec<usize>,
+    /// Line offset of the top-level item being visited
+    line_offset: usize,
+}
+
+/// The element type and name filters of a configuration, with the name
+/// patterns compiled.
+struct ElementSelector {
+    element_types: Vec<String>,
+    name_patterns: Vec<Regex>,
+    exclude_names: Vec<Regex>,
+}
+
+impl ElementSelector {
+    /// Invalid patterns are skipped with a warning; `ExtractorConfig::validate`
+    /// reports them as errors.
+    fn new(filters: &FilterConfig) -> Self {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .filter_map(|pattern| {
+                    Regex::new(pattern)
+                        .map_err(|e| tracing::warn!("Ignoring name pattern {}: {}", pattern, e))
+                        .ok()
+                })
+                .collect()
+        };
+        Self {
+            element_types: filters.element_types.clone(),
+            name_patterns: compile(&filters.name_patterns),
+            exclude_names: compile(&filters.exclude_names),
+        }
+    }
+
+    /// Whether an element of this type and name is extracted.
+    fn selects(&self, element_type: &ElementType, name: &str) -> bool {
+        let type_selected = self.element_types.is_empty()
+            || self
+                .element_types
+                .iter()
+                .any(|filter| element_type.matches_filter(filter));
+        let name_selected = self.name_patterns.is_empty()
+            || self
+                .name_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(name));
+        type_selected
+            && name_selected
+            && !self
+                .exclude_names
+                .iter()
+                .any(|pattern| pattern.is_match(name))
+    }
+}
+
+/// A closure or `async` block inside a function body.
+#[derive(Clone, Copy)]
+enum ClosureNode<'ast> {
+    Closure(&'ast syn::ExprClosure),
+    Async(&'ast syn::ExprAsync),
+}
+
+/// Locals and closure counts of a function body, for extracting its closures.
+struct ClosureScope {
+    /// Names bound in the function, which closures may capture
+    locals: HashSet<String>,
+    /// Closures extracted so far
+    closures: usize,
+    /// `async` blocks extracted so far
+    async_blocks: usize,
 }
 
 impl CodeElementVisitor {
@@ -43,9 +127,45 @@ impl CodeElementVisitor {
             cross_ref_resolver: crate::ast_data::CrossReferenceResolver::new(),
             current_element_stack: Vec::new(),
             namespace_resolver: crate::ast_data::NamespaceResolver::new(module_path),
+            impl_stack: Vec::new(),
+            cfg_stack: Vec::new(),
+            call_stack: Vec::new(),
+            closure_scopes: Vec::new(),
+            selector: ElementSelector::new(&config.filters),
+            item_line_offsets: Vec::new(),
+            line_offset: 0,
         }
     }
 
+    /// Add `offsets[i]` to the line numbers of the file's `i`-th top-level item.
+    ///
+    /// Used for files recovered item by item, whose items were parsed on
+    /// their own and have spans starting at line 1.
+    pub fn with_item_line_offsets(mut self, offsets: Vec<usize>) -> Self {
+        self.item_line_offsets = offsets;
+        self
+    }
+
+    /// Prefix element IDs with a namespace so they are unique across crates.
+    ///
+    /// The extractor uses the name of the crate the file belongs to, so IDs
+    /// don't change when items move between files of the same module.
+    pub fn with_id_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.hierarchy_builder.set_id_namespace(namespace.into());
+        self
+    }
+
+    /// Use a module path resolved from module declarations instead of the file's location.
+    ///
+    /// Needed for files loaded through `#[path]` or `include!`. Must be called
+    /// before any imports are processed.
+    pub fn with_module_path(mut self, module_path: impl Into<String>) -> Self {
+        let module_path = module_path.into();
+        self.hierarchy_builder.set_module_path(module_path.clone());
+        self.namespace_resolver = crate::ast_data::NamespaceResolver::new(module_path);
+        self
+    }
+
     /// Extract collected elements and cross-references from the visitor.
     pub fn into_elements_and_references(mut self) -> (Vec<CodeElement>, Vec<crate::ast_data::CrossReference>) {
         // Post-process to update parent-child relationships
@@ -58,6 +178,8 @@ impl CodeElementVisitor {
         self.cross_ref_resolver.resolve_references();
         
         let cross_references = self.cross_ref_resolver.get_cross_references().to_vec();
+        link_element_dependencies(&mut self.elements, &cross_references);
+        build_type_index(self.elements.iter_mut());
         (self.elements, cross_references)
     }
     
@@ -68,16 +190,43 @@ impl CodeElementVisitor {
     }
     
     /// Extract module path from file path.
-    fn extract_module_path(file_path: &Path) -> String {
-        if let Some(file_name) = file_path.file_stem().and_then(|n| n.to_str()) {
-            if file_name == "main" || file_name == "lib" {
-                "crate".to_string()
-            } else {
-                format!("crate::{}", file_name)
+    ///
+    /// Paths under a `src` directory follow Rust's module layout
+    /// (`src/net/protocol.rs` -> `crate::net::protocol`, `src/net/mod.rs` ->
+    /// `crate::net`); each `src/bin` target is its own crate root. Other files
+    /// are treated as a single module named after the file.
+    pub(crate) fn extract_module_path(file_path: &Path) -> String {
+        let components: Vec<&str> = file_path
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .collect();
+
+        let mut segments: Vec<&str> = match components.iter().rposition(|c| *c == "src") {
+            Some(src_index) => components[src_index + 1..].to_vec(),
+            None => file_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .into_iter()
+                .collect(),
+        };
+
+        // `src/bin/tool.rs` and `src/bin/tool/main.rs` are binary crate roots.
+        if segments.first() == Some(&"bin") {
+            segments.drain(..segments.len().min(2));
+        }
+
+        if let Some(last) = segments.pop() {
+            let stem = last.strip_suffix(".rs").unwrap_or(last);
+            let is_root = segments.is_empty() && (stem == "main" || stem == "lib");
+            if stem != "mod" && !is_root {
+                segments.push(stem);
             }
-        } else {
-            "crate".to_string()
         }
+
+        std::iter::once("crate")
+            .chain(segments)
+            .collect::<Vec<_>>()
+            .join("::")
     }
     
     /// Update parent-child relationships after all elements are collected.
@@ -122,6 +271,207 @@ impl CodeElementVisitor {
         }
     }
     
+    /// Record a call in the innermost enclosing function body, if any.
+    fn record_call(&mut self, kind: CallKind, name: String, span: proc_macro2::Span) {
+        if let Some(calls) = self.call_stack.last_mut() {
+            calls.push(CallSite {
+                name,
+                kind,
+                line: span.start().line + self.line_offset,
+            });
+        }
+    }
+
+    /// Visit a function body with `visit`, storing its calls on the element at `index`.
+    fn visit_fn_body(
+        &mut self,
+        index: usize,
+        sig: &syn::Signature,
+        block: &syn::Block,
+        visit: impl FnOnce(&mut Self),
+    ) {
+        if self.config.extract_closures {
+            self.closure_scopes.push(ClosureScope {
+                locals: crate::captures::local_bindings(sig, block),
+                closures: 0,
+                async_blocks: 0,
+            });
+        }
+        self.call_stack.push(Vec::new());
+        visit(self);
+        let calls = self.call_stack.pop().unwrap_or_default();
+        self.elements[index].calls = calls;
+        if self.config.extract_closures {
+            self.closure_scopes.pop();
+        }
+    }
+
+    /// Add a closure or `async` block of the enclosing function body as an
+    /// element, then visit its body.
+    fn visit_closure_element<'ast>(&mut self, node: ClosureNode<'ast>) {
+        let Some(scope) = self.closure_scopes.last_mut() else {
+            return match node {
+                ClosureNode::Closure(closure) => syn::visit::visit_expr_closure(self, closure),
+                ClosureNode::Async(block) => syn::visit::visit_expr_async(self, block),
+            };
+        };
+
+        let (element_type, name, signature, attrs, span, complexity_metrics, captures) = match node
+        {
+            ClosureNode::Closure(closure) => {
+                scope.closures += 1;
+                let (asyncness, capture, inputs, output) = (
+                    &closure.asyncness,
+                    &closure.capture,
+                    &closure.inputs,
+                    &closure.output,
+                );
+                (
+                    ElementType::Closure,
+                    format!("{{closure#{}}}", scope.closures - 1),
+                    format!("{}", quote::quote!(#asyncness #capture |#inputs| #output)),
+                    &closure.attrs,
+                    closure.span(),
+                    ComplexityCalculator::calculate_closure_complexity(closure),
+                    crate::captures::closure_captures(closure, &scope.locals),
+                )
+            }
+            ClosureNode::Async(block) => {
+                scope.async_blocks += 1;
+                let capture = &block.capture;
+                (
+                    ElementType::AsyncBlock,
+                    format!("{{async block#{}}}", scope.async_blocks - 1),
+                    format!("{}", quote::quote!(async #capture)),
+                    &block.attrs,
+                    block.span(),
+                    ComplexityCalculator::calculate_async_block_complexity(block),
+                    crate::captures::async_block_captures(block, &scope.locals),
+                )
+            }
+        };
+
+        if !self.selector.selects(&element_type, &name) {
+            return match node {
+                ClosureNode::Closure(closure) => syn::visit::visit_expr_closure(self, closure),
+                ClosureNode::Async(block) => syn::visit::visit_expr_async(self, block),
+            };
+        }
+
+        let cfg = parse_cfg_predicates(attrs);
+        let hierarchy =
+            self.hierarchy_builder
+                .build_hierarchy(&element_type, &name, &Visibility::Private);
+        let element_id =
+            self.generate_element_id(&element_type, &name, &hierarchy, Some(&signature), &cfg);
+
+        let element = CodeElement {
+            id: element_id.clone(),
+            element_type,
+            name,
+            signature: Some(signature),
+            visibility: Visibility::Private,
+            doc_comments: vec![],
+            doc_examples: vec![],
+            inline_comments: vec![],
+            location: self.create_location(span),
+            name_location: None,
+            attributes: attrs
+                .iter()
+                .map(|attr| format!("{}", quote::quote!(#attr)))
+                .collect(),
+            attribute_info: parse_attributes(attrs),
+            deprecation: None,
+            complexity: Some(complexity_metrics.overall_score()),
+            complexity_metrics: Some(complexity_metrics),
+            dependencies: vec![],
+            calls: vec![],
+            generic_params: vec![],
+            metadata: std::collections::HashMap::from([(
+                CAPTURES_METADATA_KEY.to_string(),
+                serde_json::Value::from(captures),
+            )]),
+            hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(&cfg),
+        };
+
+        // Nested closures are children of this one; references and calls
+        // still belong to the enclosing function.
+        self.hierarchy_builder
+            .enter_named_scope(element_id, element.hierarchy.qualified_name.clone());
+        self.elements.push(element);
+        self.cfg_stack.push(cfg);
+        match node {
+            ClosureNode::Closure(closure) => syn::visit::visit_expr_closure(self, closure),
+            ClosureNode::Async(block) => syn::visit::visit_expr_async(self, block),
+        }
+        self.cfg_stack.pop();
+        self.hierarchy_builder.exit_scope();
+    }
+
+    /// Build the element of an impl block.
+    fn impl_element(
+        &self,
+        node: &syn::ItemImpl,
+        impl_name: String,
+        hierarchy: ElementHierarchy,
+        element_id: String,
+        cfg: &[CfgPredicate],
+    ) -> CodeElement {
+        let docs = self.extract_doc_comments(&node.attrs);
+        let item = syn::Item::Impl(node.clone());
+        let complexity_metrics = ComplexityCalculator::calculate_structural_complexity(&item);
+        let complexity_score = complexity_metrics.overall_score();
+
+        CodeElement {
+            id: element_id,
+            element_type: ElementType::Impl,
+            name: impl_name,
+            signature: None,
+            visibility: Visibility::Public, // Impl blocks don't have visibility modifiers
+            doc_comments: docs,
+            doc_examples: self.extract_doc_examples(&node.attrs),
+            inline_comments: vec![],
+            location: self.create_location(node.span()),
+            name_location: None,
+            attributes: node
+                .attrs
+                .iter()
+                .map(|attr| format!("{}", quote::quote!(#attr)))
+                .collect(),
+            attribute_info: parse_attributes(&node.attrs),
+            deprecation: parse_deprecation(&node.attrs),
+            complexity: Some(complexity_score),
+            complexity_metrics: Some(complexity_metrics),
+            dependencies: vec![],
+            calls: vec![],
+            generic_params: node
+                .generics
+                .params
+                .iter()
+                .map(|param| format!("{}", quote::quote!(#param)))
+                .collect(),
+            // Method names, recorded even when private methods aren't extracted,
+            // so trait impls can be checked for overridden default methods
+            metadata: std::collections::HashMap::from([(
+                IMPL_METHODS_METADATA_KEY.to_string(),
+                serde_json::Value::from(
+                    node.items
+                        .iter()
+                        .filter_map(|item| match item {
+                            syn::ImplItem::Fn(method) => Some(method.sig.ident.to_string()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            )]),
+            hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(cfg),
+        }
+    }
+
     /// Register an element for cross-reference resolution.
     fn register_element(&mut self, name: &str, element_id: &str) {
         // Register both simple name and qualified name
@@ -204,42 +554,247 @@ impl CodeElementVisitor {
             .collect()
     }
 
+    /// Extract fenced code examples from documentation attributes.
+    ///
+    /// Works on the raw doc lines rather than the cleaned `doc_comments`, so
+    /// indentation and blank lines inside the examples are preserved.
+    fn extract_doc_examples(&self, attrs: &[syn::Attribute]) -> Vec<DocExample> {
+        if !self.config.include_docs {
+            return vec![];
+        }
+
+        let mut lines = Vec::new();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("doc")) {
+            if let syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit_str),
+                        ..
+                    }),
+                ..
+            }) = &attr.meta
+            {
+                let doc_text = lit_str.value();
+                lines.extend(
+                    doc_text
+                        .split('\n')
+                        .map(|line| line.strip_prefix(' ').unwrap_or(line).to_string()),
+                );
+            }
+        }
+
+        parse_doc_examples(&lines)
+    }
+
+    /// Generate the stable ID of an element from its identifying parts.
+    fn generate_element_id(
+        &mut self,
+        element_type: &ElementType,
+        name: &str,
+        hierarchy: &ElementHierarchy,
+        signature: Option<&str>,
+        own_cfg: &[CfgPredicate],
+    ) -> String {
+        let cfg = self.element_cfg(own_cfg);
+        self.hierarchy_builder.generate_stable_id(
+            element_type,
+            name,
+            &hierarchy.qualified_name,
+            signature,
+            &cfg,
+        )
+    }
+
+    /// Predicates that apply to an element: those of enclosing scopes plus its own.
+    fn element_cfg(&self, own: &[CfgPredicate]) -> Vec<CfgPredicate> {
+        self.cfg_stack
+            .iter()
+            .flatten()
+            .chain(own)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether an item with these predicates can be compiled under the simulated
+    /// `config.cfg_features`. Undecidable predicates count as enabled.
+    fn is_cfg_enabled(&self, predicates: &[CfgPredicate]) -> bool {
+        let Some(features) = &self.config.cfg_features else {
+            return true;
+        };
+        predicates
+            .iter()
+            .all(|p| p.evaluate(features) != Some(false))
+    }
+
     /// Convert syn visibility to our visibility enum.
+    /// Whether private items and everything nested in them are skipped.
+    ///
+    /// A visibility filter is applied to the extracted elements afterwards
+    /// instead, so `pub(crate)` items inside private modules are kept.
+    fn skips_private_items(&self) -> bool {
+        !self.config.include_private && self.config.filters.visibility.is_empty()
+    }
+
+    /// Extract a `const` or `static` item, whose signature is its declaration
+    /// without the initializer. Returns the item's `#[cfg]` predicates, or
+    /// `None` if the item is skipped.
+    fn push_value_element(
+        &mut self,
+        element_type: ElementType,
+        attrs: &[syn::Attribute],
+        vis: &syn::Visibility,
+        ident: &syn::Ident,
+        signature: String,
+        span: proc_macro2::Span,
+    ) -> Option<Vec<CfgPredicate>> {
+        let cfg = parse_cfg_predicates(attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return None;
+        }
+        if self.skips_private_items() && matches!(self.get_visibility(vis), Visibility::Private) {
+            return None;
+        }
+        let element_name = ident.to_string();
+        if !self.selector.selects(&element_type, &element_name) {
+            return None;
+        }
+
+        let visibility = self.get_visibility(vis);
+        let hierarchy =
+            self.hierarchy_builder
+                .build_hierarchy(&element_type, &element_name, &visibility);
+        let element_id = self.generate_element_id(
+            &element_type,
+            &element_name,
+            &hierarchy,
+            Some(&signature),
+            &cfg,
+        );
+
+        let element = CodeElement {
+            id: element_id.clone(),
+            element_type,
+            name: element_name.clone(),
+            signature: Some(signature),
+            visibility,
+            doc_comments: self.extract_doc_comments(attrs),
+            doc_examples: self.extract_doc_examples(attrs),
+            inline_comments: vec![],
+            location: self.create_location(span),
+            name_location: Some(self.create_location(ident.span())),
+            attributes: attrs
+                .iter()
+                .map(|attr| format!("{}", quote::quote!(#attr)))
+                .collect(),
+            attribute_info: parse_attributes(attrs),
+            deprecation: parse_deprecation(attrs),
+            complexity: Some(1),
+            complexity_metrics: None,
+            dependencies: vec![],
+            calls: vec![],
+            generic_params: vec![],
+            metadata: std::collections::HashMap::new(),
+            hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(&cfg),
+        };
+        self.register_element(&element_name, &element_id);
+        self.elements.push(element);
+        Some(cfg)
+    }
+
     fn get_visibility(&self, vis: &syn::Visibility) -> Visibility {
         match vis {
             syn::Visibility::Public(_) => Visibility::Public,
             syn::Visibility::Restricted(restricted) => {
-                Visibility::Restricted(format!("{}", quote::quote!(#restricted)))
+                let path = path_to_string(&restricted.path);
+                match path.as_str() {
+                    "crate" => Visibility::Crate,
+                    "super" => Visibility::Super,
+                    "self" => Visibility::Private,
+                    _ => Visibility::Restricted(path),
+                }
             }
             syn::Visibility::Inherited => Visibility::Private,
         }
     }
 
+    /// Qualify a type relative to the current module (`Foo` -> `crate::net::Foo`).
+    fn qualify_type_path(&self, ty: &syn::Type) -> String {
+        match ty {
+            syn::Type::Path(type_path) if type_path.qself.is_none() => {
+                let path = path_to_string(&type_path.path);
+                if path == "crate" || path.starts_with("crate::") {
+                    path
+                } else {
+                    self.hierarchy_builder.build_qualified_name(&path)
+                }
+            }
+            other => format!("{}", quote::quote!(#other)),
+        }
+    }
+
     /// Create location information from a span.
-    fn create_location(&self, _span: proc_macro2::Span) -> CodeLocation {
-        // Note: proc_macro2::Span doesn't provide line/column info in non-proc-macro context
-        // For now, we'll use placeholder values. This will be improved in later tasks.
+    ///
+    /// Lines are 1-based and characters are 0-based columns, as reported by
+    /// `proc-macro2`'s `span-locations` feature. Byte offsets are left at zero
+    /// for the extractor to fill in from the source text.
+    fn create_location(&self, span: proc_macro2::Span) -> CodeLocation {
+        let (start, end) = (span.start(), span.end());
         CodeLocation {
-            line_start: 1,
-            line_end: 1,
-            char_start: 0,
-            char_end: 0,
+            line_start: start.line + self.line_offset,
+            line_end: end.line + self.line_offset,
+            char_start: start.column,
+            char_end: end.column,
+            byte_start: 0,
+            byte_end: 0,
             file_path: self.file_path.clone(),
         }
     }
 }
 
 impl<'ast> Visit<'ast> for CodeElementVisitor {
+    /// Visit a file, applying inner `#![cfg(...)]` attributes to all of its items.
+    fn visit_file(&mut self, node: &'ast syn::File) {
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
+        self.cfg_stack.push(cfg);
+        for attr in &node.attrs {
+            self.visit_attribute(attr);
+        }
+        for (index, item) in node.items.iter().enumerate() {
+            self.line_offset = self.item_line_offsets.get(index).copied().unwrap_or(0);
+            self.visit_item(item);
+        }
+        self.line_offset = 0;
+        self.cfg_stack.pop();
+    }
+
     /// Visit function items and extract information.
     fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
         // Skip private items if not configured to include them
-        if !self.config.include_private
+        if self.skips_private_items()
             && matches!(self.get_visibility(&node.vis), Visibility::Private)
         {
             return;
         }
+        if !self
+            .selector
+            .selects(&ElementType::Function, &node.sig.ident.to_string())
+        {
+            return;
+        }
 
-        let signature = format!("{}", quote::quote!(#node.sig));
+        let sig = &node.sig;
+        let signature = format!("{}", quote::quote!(#sig));
         let docs = self.extract_doc_comments(&node.attrs);
 
         // Calculate detailed complexity metrics
@@ -247,10 +802,16 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
         let complexity_score = complexity_metrics.overall_score();
 
         let element_name = node.sig.ident.to_string();
-        let element_id = self.hierarchy_builder.generate_id(&ElementType::Function, &element_name);
         let visibility = self.get_visibility(&node.vis);
         let hierarchy = self.hierarchy_builder.build_hierarchy(&ElementType::Function, &element_name, &visibility);
-        
+        let element_id = self.generate_element_id(
+            &ElementType::Function,
+            &element_name,
+            &hierarchy,
+            Some(&signature),
+            &cfg,
+        );
+
         let element = CodeElement {
             id: element_id.clone(),
             element_type: ElementType::Function,
@@ -258,16 +819,21 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
             signature: Some(signature),
             visibility: self.get_visibility(&node.vis),
             doc_comments: docs,
-            inline_comments: vec![], // TODO: Extract inline comments
-            location: self.create_location(node.sig.ident.span()),
+            doc_examples: self.extract_doc_examples(&node.attrs),
+            inline_comments: vec![],
+            location: self.create_location(node.span()),
+            name_location: Some(self.create_location(node.sig.ident.span())),
             attributes: node
                 .attrs
                 .iter()
                 .map(|attr| format!("{}", quote::quote!(#attr)))
                 .collect(),
+            attribute_info: parse_attributes(&node.attrs),
+            deprecation: parse_deprecation(&node.attrs),
             complexity: Some(complexity_score),
             complexity_metrics: Some(complexity_metrics),
             dependencies: vec![], // TODO: Extract function dependencies
+            calls: vec![],
             generic_params: node
                 .sig
                 .generics
@@ -277,19 +843,27 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
                 .collect(),
             metadata: std::collections::HashMap::new(),
             hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(&cfg),
         };
         
         // Register the element for cross-reference resolution
         self.register_element(&element_name, &element_id);
         
         // For functions with bodies, we may want to enter scope for nested items
-        self.hierarchy_builder.enter_scope(element_id.clone());
+        self.hierarchy_builder
+            .enter_named_scope(element_id.clone(), element.hierarchy.qualified_name.clone());
         self.enter_element_scope(element_id.clone());
 
+        let index = self.elements.len();
         self.elements.push(element);
 
         // Continue visiting nested items
-        syn::visit::visit_item_fn(self, node);
+        self.cfg_stack.push(cfg);
+        self.visit_fn_body(index, &node.sig, &node.block, |visitor| {
+            syn::visit::visit_item_fn(visitor, node)
+        });
+        self.cfg_stack.pop();
         
         // Exit scope after visiting function body
         self.exit_element_scope();
@@ -298,11 +872,22 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
 
     /// Visit struct items and extract information.
     fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
-        if !self.config.include_private
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
+        if self.skips_private_items()
             && matches!(self.get_visibility(&node.vis), Visibility::Private)
         {
             return;
         }
+        if !self
+            .selector
+            .selects(&ElementType::Struct, &node.ident.to_string())
+        {
+            return;
+        }
 
         let docs = self.extract_doc_comments(&node.attrs);
 
@@ -312,10 +897,11 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
         let complexity_score = complexity_metrics.overall_score();
 
         let element_name = node.ident.to_string();
-        let element_id = self.hierarchy_builder.generate_id(&ElementType::Struct, &element_name);
         let visibility = self.get_visibility(&node.vis);
         let hierarchy = self.hierarchy_builder.build_hierarchy(&ElementType::Struct, &element_name, &visibility);
-        
+        let element_id =
+            self.generate_element_id(&ElementType::Struct, &element_name, &hierarchy, None, &cfg);
+
         let element = CodeElement {
             id: element_id.clone(),
             element_type: ElementType::Struct,
@@ -323,16 +909,21 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
             signature: None,
             visibility: self.get_visibility(&node.vis),
             doc_comments: docs,
+            doc_examples: self.extract_doc_examples(&node.attrs),
             inline_comments: vec![],
-            location: self.create_location(node.ident.span()),
+            location: self.create_location(node.span()),
+            name_location: Some(self.create_location(node.ident.span())),
             attributes: node
                 .attrs
                 .iter()
                 .map(|attr| format!("{}", quote::quote!(#attr)))
                 .collect(),
+            attribute_info: parse_attributes(&node.attrs),
+            deprecation: parse_deprecation(&node.attrs),
             complexity: Some(complexity_score),
             complexity_metrics: Some(complexity_metrics),
             dependencies: vec![],
+            calls: vec![],
             generic_params: node
                 .generics
                 .params
@@ -341,13 +932,18 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
                 .collect(),
             metadata: std::collections::HashMap::new(),
             hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(&cfg),
         };
         
         // For structs, we may want to enter scope for impl blocks
-        self.hierarchy_builder.enter_scope(element_id.clone());
+        self.hierarchy_builder
+            .enter_named_scope(element_id.clone(), element.hierarchy.qualified_name.clone());
 
         self.elements.push(element);
+        self.cfg_stack.push(cfg);
         syn::visit::visit_item_struct(self, node);
+        self.cfg_stack.pop();
         
         // Exit scope after visiting struct
         self.hierarchy_builder.exit_scope();
@@ -355,11 +951,22 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
 
     /// Visit enum items and extract information.
     fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
-        if !self.config.include_private
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
+        if self.skips_private_items()
             && matches!(self.get_visibility(&node.vis), Visibility::Private)
         {
             return;
         }
+        if !self
+            .selector
+            .selects(&ElementType::Enum, &node.ident.to_string())
+        {
+            return;
+        }
 
         let docs = self.extract_doc_comments(&node.attrs);
 
@@ -369,10 +976,11 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
         let complexity_score = complexity_metrics.overall_score();
 
         let element_name = node.ident.to_string();
-        let element_id = self.hierarchy_builder.generate_id(&ElementType::Enum, &element_name);
         let visibility = self.get_visibility(&node.vis);
         let hierarchy = self.hierarchy_builder.build_hierarchy(&ElementType::Enum, &element_name, &visibility);
-        
+        let element_id =
+            self.generate_element_id(&ElementType::Enum, &element_name, &hierarchy, None, &cfg);
+
         let element = CodeElement {
             id: element_id.clone(),
             element_type: ElementType::Enum,
@@ -380,31 +988,50 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
             signature: None,
             visibility: self.get_visibility(&node.vis),
             doc_comments: docs,
+            doc_examples: self.extract_doc_examples(&node.attrs),
             inline_comments: vec![],
-            location: self.create_location(node.ident.span()),
+            location: self.create_location(node.span()),
+            name_location: Some(self.create_location(node.ident.span())),
             attributes: node
                 .attrs
                 .iter()
                 .map(|attr| format!("{}", quote::quote!(#attr)))
                 .collect(),
+            attribute_info: parse_attributes(&node.attrs),
+            deprecation: parse_deprecation(&node.attrs),
             complexity: Some(complexity_score),
             complexity_metrics: Some(complexity_metrics),
             dependencies: vec![],
+            calls: vec![],
             generic_params: node
                 .generics
                 .params
                 .iter()
                 .map(|param| format!("{}", quote::quote!(#param)))
                 .collect(),
-            metadata: std::collections::HashMap::new(),
+            // Variant names, so diffs can report added and removed variants
+            metadata: std::collections::HashMap::from([(
+                ENUM_VARIANTS_METADATA_KEY.to_string(),
+                serde_json::Value::from(
+                    node.variants
+                        .iter()
+                        .map(|variant| variant.ident.to_string())
+                        .collect::<Vec<_>>(),
+                ),
+            )]),
             hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(&cfg),
         };
         
         // For enums, we may want to enter scope for variant methods
-        self.hierarchy_builder.enter_scope(element_id.clone());
+        self.hierarchy_builder
+            .enter_named_scope(element_id.clone(), element.hierarchy.qualified_name.clone());
 
         self.elements.push(element);
+        self.cfg_stack.push(cfg);
         syn::visit::visit_item_enum(self, node);
+        self.cfg_stack.pop();
         
         // Exit scope after visiting enum
         self.hierarchy_builder.exit_scope();
@@ -412,11 +1039,22 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
 
     /// Visit trait items and extract information.
     fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
-        if !self.config.include_private
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
+        if self.skips_private_items()
             && matches!(self.get_visibility(&node.vis), Visibility::Private)
         {
             return;
         }
+        if !self
+            .selector
+            .selects(&ElementType::Trait, &node.ident.to_string())
+        {
+            return;
+        }
 
         let docs = self.extract_doc_comments(&node.attrs);
 
@@ -426,10 +1064,11 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
         let complexity_score = complexity_metrics.overall_score();
 
         let element_name = node.ident.to_string();
-        let element_id = self.hierarchy_builder.generate_id(&ElementType::Trait, &element_name);
         let visibility = self.get_visibility(&node.vis);
         let hierarchy = self.hierarchy_builder.build_hierarchy(&ElementType::Trait, &element_name, &visibility);
-        
+        let element_id =
+            self.generate_element_id(&ElementType::Trait, &element_name, &hierarchy, None, &cfg);
+
         let element = CodeElement {
             id: element_id.clone(),
             element_type: ElementType::Trait,
@@ -437,31 +1076,51 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
             signature: None,
             visibility: self.get_visibility(&node.vis),
             doc_comments: docs,
+            doc_examples: self.extract_doc_examples(&node.attrs),
             inline_comments: vec![],
-            location: self.create_location(node.ident.span()),
+            location: self.create_location(node.span()),
+            name_location: Some(self.create_location(node.ident.span())),
             attributes: node
                 .attrs
                 .iter()
                 .map(|attr| format!("{}", quote::quote!(#attr)))
                 .collect(),
+            attribute_info: parse_attributes(&node.attrs),
+            deprecation: parse_deprecation(&node.attrs),
             complexity: Some(complexity_score),
             complexity_metrics: Some(complexity_metrics),
             dependencies: vec![],
+            calls: vec![],
             generic_params: node
                 .generics
                 .params
                 .iter()
                 .map(|param| format!("{}", quote::quote!(#param)))
                 .collect(),
-            metadata: std::collections::HashMap::new(),
+            // Method names, so documentation can list required and provided methods
+            metadata: std::collections::HashMap::from([
+                (
+                    REQUIRED_METHODS_METADATA_KEY.to_string(),
+                    serde_json::Value::from(trait_method_names(node, false)),
+                ),
+                (
+                    PROVIDED_METHODS_METADATA_KEY.to_string(),
+                    serde_json::Value::from(trait_method_names(node, true)),
+                ),
+            ]),
             hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(&cfg),
         };
         
         // For traits, we may want to enter scope for trait methods
-        self.hierarchy_builder.enter_scope(element_id.clone());
+        self.hierarchy_builder
+            .enter_named_scope(element_id.clone(), element.hierarchy.qualified_name.clone());
 
         self.elements.push(element);
+        self.cfg_stack.push(cfg);
         syn::visit::visit_item_trait(self, node);
+        self.cfg_stack.pop();
         
         // Exit scope after visiting trait
         self.hierarchy_builder.exit_scope();
@@ -469,124 +1128,216 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
     
     /// Visit impl blocks and extract information.
     fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
         // Get the type being implemented
+        let self_ty = &node.self_ty;
         let impl_name = if let Some((_bang, trait_path, _for_token)) = &node.trait_ {
-            format!("{} for {}", quote::quote!(#trait_path), quote::quote!(#node.self_ty))
+            format!(
+                "{} for {}",
+                quote::quote!(#trait_path),
+                quote::quote!(#self_ty)
+            )
         } else {
-            format!("impl {}", quote::quote!(#node.self_ty))
+            format!("impl {}", quote::quote!(#self_ty))
         };
         
-        let docs = self.extract_doc_comments(&node.attrs);
-        let item = syn::Item::Impl(node.clone());
-        let complexity_metrics = ComplexityCalculator::calculate_structural_complexity(&item);
-        let complexity_score = complexity_metrics.overall_score();
-        
-        let element_id = self.hierarchy_builder.generate_id(&ElementType::Impl, &impl_name);
         let visibility = Visibility::Public; // Impl blocks don't have visibility modifiers
-        let hierarchy = self.hierarchy_builder.build_hierarchy(&ElementType::Impl, &impl_name, &visibility);
-        
-        let element = CodeElement {
-            id: element_id.clone(),
-            element_type: ElementType::Impl,
-            name: impl_name,
-            signature: None,
-            visibility: Visibility::Public, // Impl blocks don't have visibility modifiers
-            doc_comments: docs,
-            inline_comments: vec![],
-            location: self.create_location(node.impl_token.span),
-            attributes: node
-                .attrs
-                .iter()
-                .map(|attr| format!("{}", quote::quote!(#attr)))
-                .collect(),
-            complexity: Some(complexity_score),
-            complexity_metrics: Some(complexity_metrics),
-            dependencies: vec![],
-            generic_params: node
+        let mut hierarchy =
+            self.hierarchy_builder
+                .build_hierarchy(&ElementType::Impl, &impl_name, &visibility);
+
+        // Methods are qualified under the implementing type, using
+        // `<Type as Trait>` for trait impls so they don't collide with inherent methods.
+        let impl_context = ImplContext {
+            self_type: self.qualify_type_path(self_ty),
+            trait_path: node
+                .trait_
+                .as_ref()
+                .map(|(_bang, trait_path, _for_token)| path_to_string(trait_path)),
+            where_clause: node
                 .generics
-                .params
-                .iter()
-                .map(|param| format!("{}", quote::quote!(#param)))
-                .collect(),
-            metadata: std::collections::HashMap::new(),
-            hierarchy,
+                .where_clause
+                .as_ref()
+                .map(|where_clause| format!("{}", quote::quote!(#where_clause))),
         };
-        
-        self.hierarchy_builder.enter_scope(element_id.clone());
-        self.elements.push(element);
+        hierarchy.qualified_name = match &impl_context.trait_path {
+            Some(trait_path) => format!("<{} as {}>", impl_context.self_type, trait_path),
+            None => impl_context.self_type.clone(),
+        };
+        hierarchy.impl_context = Some(impl_context.clone());
+        let element_id =
+            self.generate_element_id(&ElementType::Impl, &impl_name, &hierarchy, None, &cfg);
+
+        // Unselected impls are still visited, since their methods may be selected.
+        let qualified_name = hierarchy.qualified_name.clone();
+        if self.selector.selects(&ElementType::Impl, &impl_name) {
+            let element = self.impl_element(node, impl_name, hierarchy, element_id.clone(), &cfg);
+            self.elements.push(element);
+        }
+
+        self.hierarchy_builder
+            .enter_named_scope(element_id, qualified_name);
+        self.impl_stack.push(impl_context);
+        self.cfg_stack.push(cfg);
         syn::visit::visit_item_impl(self, node);
+        self.cfg_stack.pop();
         
         // Exit scope after visiting impl block
+        self.impl_stack.pop();
         self.hierarchy_builder.exit_scope();
     }
     
     /// Visit module items and extract information.
     fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
-        if !self.config.include_private
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
+        if self.skips_private_items()
             && matches!(self.get_visibility(&node.vis), Visibility::Private)
         {
             return;
         }
         
         let module_name = node.ident.to_string();
-        let docs = self.extract_doc_comments(&node.attrs);
-        
-        let element_id = self.hierarchy_builder.generate_id(&ElementType::Module, &module_name);
         let visibility = self.get_visibility(&node.vis);
         let hierarchy = self.hierarchy_builder.build_hierarchy(&ElementType::Module, &module_name, &visibility);
-        
-        let element = CodeElement {
-            id: element_id.clone(),
-            element_type: ElementType::Module,
-            name: module_name.clone(),
-            signature: None,
-            visibility: self.get_visibility(&node.vis),
-            doc_comments: docs,
-            inline_comments: vec![],
-            location: self.create_location(node.ident.span()),
-            attributes: node
-                .attrs
-                .iter()
-                .map(|attr| format!("{}", quote::quote!(#attr)))
-                .collect(),
-            complexity: Some(1), // Modules have base complexity
-            complexity_metrics: None,
-            dependencies: vec![],
-            generic_params: vec![],
-            metadata: std::collections::HashMap::new(),
-            hierarchy,
-        };
-        
+        let element_id =
+            self.generate_element_id(&ElementType::Module, &module_name, &hierarchy, None, &cfg);
+
+        // Unselected modules are still visited, since their items may be selected.
+        let qualified_name = hierarchy.qualified_name.clone();
+        if self.selector.selects(&ElementType::Module, &module_name) {
+            let element = CodeElement {
+                id: element_id.clone(),
+                element_type: ElementType::Module,
+                name: module_name.clone(),
+                signature: None,
+                visibility: self.get_visibility(&node.vis),
+                doc_comments: self.extract_doc_comments(&node.attrs),
+                doc_examples: self.extract_doc_examples(&node.attrs),
+                inline_comments: vec![],
+                location: self.create_location(node.span()),
+                name_location: Some(self.create_location(node.ident.span())),
+                attributes: node
+                    .attrs
+                    .iter()
+                    .map(|attr| format!("{}", quote::quote!(#attr)))
+                    .collect(),
+                attribute_info: parse_attributes(&node.attrs),
+                deprecation: parse_deprecation(&node.attrs),
+                complexity: Some(1), // Modules have base complexity
+                complexity_metrics: None,
+                dependencies: vec![],
+                calls: vec![],
+                generic_params: vec![],
+                metadata: std::collections::HashMap::new(),
+                hierarchy,
+                is_generated: false,
+                cfg_predicates: self.element_cfg(&cfg),
+            };
+            self.elements.push(element);
+        }
+
         // Enter module scope
         self.hierarchy_builder.enter_module(&module_name);
-        self.hierarchy_builder.enter_scope(element_id.clone());
-        self.elements.push(element);
+        self.hierarchy_builder.enter_named_scope(element_id, qualified_name);
         
+        self.cfg_stack.push(cfg);
         syn::visit::visit_item_mod(self, node);
+        self.cfg_stack.pop();
         
         // Exit module scope
         self.hierarchy_builder.exit_scope();
         self.hierarchy_builder.exit_module();
     }
-    
+
+    /// Visit const items and extract information.
+    fn visit_item_const(&mut self, node: &'ast syn::ItemConst) {
+        let (vis, ident, generics, ty) = (&node.vis, &node.ident, &node.generics, &node.ty);
+        let signature = format!("{}", quote::quote!(#vis const #ident #generics: #ty));
+        let Some(cfg) = self.push_value_element(
+            ElementType::Constant,
+            &node.attrs,
+            vis,
+            ident,
+            signature,
+            node.span(),
+        ) else {
+            return;
+        };
+
+        self.cfg_stack.push(cfg);
+        syn::visit::visit_item_const(self, node);
+        self.cfg_stack.pop();
+    }
+
+    /// Visit static items and extract information.
+    fn visit_item_static(&mut self, node: &'ast syn::ItemStatic) {
+        let (vis, mutability, ident, ty) = (&node.vis, &node.mutability, &node.ident, &node.ty);
+        let signature = format!("{}", quote::quote!(#vis static #mutability #ident: #ty));
+        let Some(cfg) = self.push_value_element(
+            ElementType::Static,
+            &node.attrs,
+            vis,
+            ident,
+            signature,
+            node.span(),
+        ) else {
+            return;
+        };
+
+        self.cfg_stack.push(cfg);
+        syn::visit::visit_item_static(self, node);
+        self.cfg_stack.pop();
+    }
+
     /// Visit impl item functions (methods).
     fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
-        if !self.config.include_private
+        let cfg = parse_cfg_predicates(&node.attrs);
+        if !self.is_cfg_enabled(&cfg) {
+            return;
+        }
+
+        if self.skips_private_items()
             && matches!(self.get_visibility(&node.vis), Visibility::Private)
         {
             return;
         }
+        if !self
+            .selector
+            .selects(&ElementType::Function, &node.sig.ident.to_string())
+        {
+            return;
+        }
         
-        let signature = format!("{}", quote::quote!(#node.sig));
+        let sig = &node.sig;
+        let signature = format!("{}", quote::quote!(#sig));
         let docs = self.extract_doc_comments(&node.attrs);
         let complexity_metrics = ComplexityCalculator::calculate_method_complexity(node);
         let complexity_score = complexity_metrics.overall_score();
         
         let element_name = node.sig.ident.to_string();
-        let element_id = self.hierarchy_builder.generate_id(&ElementType::Function, &element_name);
         let visibility = self.get_visibility(&node.vis);
-        let hierarchy = self.hierarchy_builder.build_hierarchy(&ElementType::Function, &element_name, &visibility);
-        
+        let mut hierarchy = self.hierarchy_builder.build_hierarchy(
+            &ElementType::Function,
+            &element_name,
+            &visibility,
+        );
+        hierarchy.impl_context = self.impl_stack.last().cloned();
+        let element_id = self.generate_element_id(
+            &ElementType::Function,
+            &element_name,
+            &hierarchy,
+            Some(&signature),
+            &cfg,
+        );
+
         let element = CodeElement {
             id: element_id.clone(),
             element_type: ElementType::Function,
@@ -594,16 +1345,21 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
             signature: Some(signature),
             visibility: self.get_visibility(&node.vis),
             doc_comments: docs,
+            doc_examples: self.extract_doc_examples(&node.attrs),
             inline_comments: vec![],
-            location: self.create_location(node.sig.ident.span()),
+            location: self.create_location(node.span()),
+            name_location: Some(self.create_location(node.sig.ident.span())),
             attributes: node
                 .attrs
                 .iter()
                 .map(|attr| format!("{}", quote::quote!(#attr)))
                 .collect(),
+            attribute_info: parse_attributes(&node.attrs),
+            deprecation: parse_deprecation(&node.attrs),
             complexity: Some(complexity_score),
             complexity_metrics: Some(complexity_metrics),
             dependencies: vec![],
+            calls: vec![],
             generic_params: node
                 .sig
                 .generics
@@ -613,13 +1369,23 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
                 .collect(),
             metadata: std::collections::HashMap::new(),
             hierarchy,
+            is_generated: false,
+            cfg_predicates: self.element_cfg(&cfg),
         };
         
-        self.hierarchy_builder.enter_scope(element_id.clone());
+        self.hierarchy_builder
+            .enter_named_scope(element_id.clone(), element.hierarchy.qualified_name.clone());
+        self.enter_element_scope(element_id.clone());
+        let index = self.elements.len();
         self.elements.push(element);
-        syn::visit::visit_impl_item_fn(self, node);
+        self.cfg_stack.push(cfg);
+        self.visit_fn_body(index, &node.sig, &node.block, |visitor| {
+            syn::visit::visit_impl_item_fn(visitor, node)
+        });
+        self.cfg_stack.pop();
         
         // Exit scope after visiting method
+        self.exit_element_scope();
         self.hierarchy_builder.exit_scope();
     }
     
@@ -629,6 +1395,11 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
             // Function calls
             syn::Expr::Call(call_expr) => {
                 if let syn::Expr::Path(path_expr) = &*call_expr.func {
+                    self.record_call(
+                        CallKind::Function,
+                        path_to_string(&path_expr.path),
+                        path_expr.path.span(),
+                    );
                     if let Some(ident) = path_expr.path.get_ident() {
                         self.track_reference(
                             crate::ast_data::ReferenceType::FunctionCall,
@@ -637,7 +1408,8 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
                         );
                     } else {
                         // Handle qualified function calls like std::println!
-                        let path_str = format!("{}", quote::quote!(#path_expr.path));
+                        let path = &path_expr.path;
+                        let path_str = format!("{}", quote::quote!(#path));
                         self.track_reference(
                             crate::ast_data::ReferenceType::FunctionCall,
                             path_str,
@@ -649,6 +1421,11 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
             
             // Method calls
             syn::Expr::MethodCall(method_call) => {
+                self.record_call(
+                    CallKind::Method,
+                    method_call.method.to_string(),
+                    method_call.method.span(),
+                );
                 self.track_reference(
                     crate::ast_data::ReferenceType::FunctionCall,
                     method_call.method.to_string(),
@@ -685,6 +1462,38 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
         syn::visit::visit_expr(self, expr);
     }
     
+    /// Extract closures as elements when `extract_closures` is set.
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.visit_closure_element(ClosureNode::Closure(node));
+    }
+
+    /// Extract `async` blocks as elements when `extract_closures` is set.
+    fn visit_expr_async(&mut self, node: &'ast syn::ExprAsync) {
+        self.visit_closure_element(ClosureNode::Async(node));
+    }
+
+    /// Record macro invocations, and visit arguments that parse as expressions.
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if self.call_stack.is_empty() {
+            return syn::visit::visit_macro(self, node);
+        }
+        if !node.path.is_ident("macro_rules") {
+            self.record_call(
+                CallKind::Macro,
+                path_to_string(&node.path),
+                node.path.span(),
+            );
+        }
+        // Arguments of macros like `println!` and `assert_eq!` often contain calls.
+        let parser = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+        if let Ok(args) = node.parse_body_with(parser) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        syn::visit::visit_macro(self, node);
+    }
+
     /// Visit types to track type usage references.
     fn visit_type(&mut self, type_expr: &'ast syn::Type) {
         if let syn::Type::Path(type_path) = type_expr {
@@ -696,7 +1505,8 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
                 );
             } else {
                 // Handle qualified type paths
-                let path_str = format!("{}", quote::quote!(#type_path.path));
+                let path = &type_path.path;
+                let path_str = format!("{}", quote::quote!(#path));
                 self.track_reference(
                     crate::ast_data::ReferenceType::TypeUsage,
                     path_str,
@@ -709,3 +1519,572 @@ impl<'ast> Visit<'ast> for CodeElementVisitor {
         syn::visit::visit_type(self, type_expr);
     }
 }
+
+/// Makes element IDs unique across the files of a project.
+///
+/// IDs are unique within a file, but files sharing an ID namespace and module
+/// path (such as two binaries of one crate) can produce the same ones. Files
+/// are processed in the order they are passed in: clashing IDs in later files
+/// get a `_2`, `_3`, ... suffix, and the IDs that elements and references of
+/// the file hold are rewritten to match.
+#[derive(Debug, Default)]
+pub struct ElementIdDeduplicator {
+    /// IDs issued to the files processed so far
+    seen: HashSet<String>,
+}
+
+impl ElementIdDeduplicator {
+    /// Rename the element IDs of `file` that clash with earlier files.
+    pub fn deduplicate(&mut self, file: &mut FileAst) {
+        let mut renames: HashMap<String, String> = HashMap::new();
+        for element in &file.elements {
+            if self.seen.insert(element.id.clone()) {
+                continue;
+            }
+            let new_id = (2..)
+                .map(|n| format!("{}_{}", element.id, n))
+                .find(|candidate| !self.seen.contains(candidate))
+                .unwrap_or_default();
+            self.seen.insert(new_id.clone());
+            renames.insert(element.id.clone(), new_id);
+        }
+        if renames.is_empty() {
+            return;
+        }
+
+        let rename = |id: &mut String| {
+            if let Some(new_id) = renames.get(id) {
+                *id = new_id.clone();
+            }
+        };
+        for element in &mut file.elements {
+            rename(&mut element.id);
+            element.hierarchy.parent_id.iter_mut().for_each(rename);
+            element.hierarchy.children_ids.iter_mut().for_each(rename);
+            element.hierarchy.parent_element.iter_mut().for_each(rename);
+            element.dependencies.iter_mut().for_each(rename);
+        }
+        for reference in &mut file.cross_references {
+            rename(&mut reference.from_element_id);
+            reference.to_element_id.iter_mut().for_each(rename);
+        }
+    }
+}
+
+/// Resolve references that could not be resolved within their own file.
+///
+/// Each file is resolved in isolation by [`CodeElementVisitor`]; this pass
+/// links the remaining function calls, method calls and type usages to
+/// elements defined in other files. A name is only linked when it identifies
+/// a single element, so ambiguous references stay unresolved rather than
+/// pointing at the wrong definition. For qualified paths such as
+/// `Type::method`, candidates are narrowed to items of the named type.
+///
+/// Returns the project-wide reference graph and fills each element's
+/// `dependencies` with the IDs of the elements it references.
+pub fn resolve_project_references(files: &mut [FileAst]) -> ReferenceGraph {
+    let mut candidates: HashMap<&str, Vec<(&str, Option<&str>)>> = HashMap::new();
+    for element in files.iter().flat_map(|f| &f.elements) {
+        if matches!(
+            element.element_type,
+            ElementType::Impl | ElementType::Module
+        ) {
+            continue;
+        }
+        let owner = element
+            .hierarchy
+            .impl_context
+            .as_ref()
+            .map(|context| last_path_segment(&context.self_type));
+        candidates
+            .entry(&element.name)
+            .or_default()
+            .push((&element.id, owner));
+    }
+
+    let mut resolutions: Vec<(usize, usize, String)> = Vec::new();
+    for (file_index, file) in files.iter().enumerate() {
+        for (ref_index, reference) in file.cross_references.iter().enumerate() {
+            if reference.is_resolved
+                || !matches!(
+                    reference.reference_type,
+                    ReferenceType::FunctionCall | ReferenceType::TypeUsage
+                )
+            {
+                continue;
+            }
+
+            let segments: Vec<&str> = reference
+                .reference_text
+                .split("::")
+                .map(str::trim)
+                .collect();
+            let Some(name) = segments.last() else {
+                continue;
+            };
+            let qualifier = segments.len().checked_sub(2).map(|i| segments[i]);
+
+            let Some(named) = candidates.get(name) else {
+                continue;
+            };
+            // `Type::item` only matches items of that type; any other qualifier
+            // (e.g. a module path) only matches free items.
+            let matching: Vec<&str> = named
+                .iter()
+                .filter(|(_, owner)| match qualifier {
+                    Some(qualifier) if named.iter().any(|(_, o)| *o == Some(qualifier)) => {
+                        *owner == Some(qualifier)
+                    }
+                    Some(_) => owner.is_none(),
+                    None => true,
+                })
+                .map(|(id, _)| *id)
+                .collect();
+
+            if let [target] = matching.as_slice() {
+                resolutions.push((file_index, ref_index, target.to_string()));
+            }
+        }
+    }
+
+    for (file_index, ref_index, target) in resolutions {
+        files[file_index].cross_references[ref_index].resolve_to(target);
+    }
+
+    let graph =
+        ReferenceGraph::from_cross_references(files.iter().flat_map(|f| &f.cross_references));
+    for element in files.iter_mut().flat_map(|f| &mut f.elements) {
+        element.dependencies = graph.references_of(&element.id).to_vec();
+    }
+    graph
+}
+
+/// Record the resolved references of each element in its `dependencies`.
+fn link_element_dependencies(elements: &mut [CodeElement], references: &[CrossReference]) {
+    let graph = ReferenceGraph::from_cross_references(references);
+    for element in elements {
+        element.dependencies = graph.references_of(&element.id).to_vec();
+    }
+}
+
+/// Attach impl blocks and methods to their type elements and index them by type.
+///
+/// A type is matched by qualified name first, then by simple name when that is
+/// unambiguous (e.g. an impl in another module that imports the type). Sets
+/// `hierarchy.parent_element` on every matched impl block and method.
+pub fn build_type_index<'a>(
+    elements: impl IntoIterator<Item = &'a mut CodeElement>,
+) -> BTreeMap<String, TypeMethods> {
+    let mut elements: Vec<&mut CodeElement> = elements.into_iter().collect();
+
+    let mut by_qualified_name: HashMap<String, (String, String)> = HashMap::new();
+    let mut by_name: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for element in elements.iter().filter(|e| {
+        matches!(
+            e.element_type,
+            ElementType::Struct | ElementType::Enum | ElementType::Union | ElementType::TypeAlias
+        )
+    }) {
+        let entry = (element.id.clone(), element.hierarchy.qualified_name.clone());
+        by_qualified_name.insert(element.hierarchy.qualified_name.clone(), entry.clone());
+        by_name.entry(element.name.clone()).or_default().push(entry);
+    }
+
+    let mut index: BTreeMap<String, TypeMethods> = BTreeMap::new();
+    for element in elements.iter_mut() {
+        let Some(context) = element.hierarchy.impl_context.clone() else {
+            continue;
+        };
+
+        let type_element = by_qualified_name
+            .get(&context.self_type)
+            .cloned()
+            .or_else(|| {
+                match by_name
+                    .get(last_path_segment(&context.self_type))
+                    .map(Vec::as_slice)
+                {
+                    Some([single]) => Some(single.clone()),
+                    _ => None,
+                }
+            });
+        let type_name = type_element
+            .as_ref()
+            .map_or_else(|| context.self_type.clone(), |(_, name)| name.clone());
+        element.hierarchy.parent_element = type_element.as_ref().map(|(id, _)| id.clone());
+
+        let entry = index.entry(type_name).or_default();
+        entry.type_element_id = element.hierarchy.parent_element.clone();
+        match (&element.element_type, context.trait_path) {
+            (ElementType::Impl, _) => entry.impl_ids.push(element.id.clone()),
+            (_, Some(trait_path)) => entry
+                .trait_methods
+                .entry(trait_path)
+                .or_default()
+                .push(element.id.clone()),
+            (_, None) => entry.inherent_methods.push(element.id.clone()),
+        }
+    }
+
+    index
+}
+
+/// Parse the `#[cfg(...)]` attributes in `attrs`.
+///
+/// `cfg_attr` is not included, since it never removes the item itself.
+/// Malformed predicates are skipped.
+pub fn parse_cfg_predicates(attrs: &[syn::Attribute]) -> Vec<CfgPredicate> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .filter_map(|attr| attr.parse_args::<syn::Meta>().ok())
+        .filter_map(|meta| cfg_predicate_from_meta(&meta))
+        .collect()
+}
+
+/// Convert a cfg meta item such as `all(unix, feature = "x")` into a predicate.
+fn cfg_predicate_from_meta(meta: &syn::Meta) -> Option<CfgPredicate> {
+    match meta {
+        syn::Meta::Path(path) => Some(CfgPredicate::Option {
+            name: path.get_ident()?.to_string(),
+            value: None,
+        }),
+        syn::Meta::NameValue(name_value) => match &name_value.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit_str),
+                ..
+            }) => Some(CfgPredicate::Option {
+                name: name_value.path.get_ident()?.to_string(),
+                value: Some(lit_str.value()),
+            }),
+            _ => None,
+        },
+        syn::Meta::List(list) => {
+            let nested = list
+                .parse_args_with(
+                    syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+                )
+                .ok()?;
+            let mut predicates = nested
+                .iter()
+                .map(cfg_predicate_from_meta)
+                .collect::<Option<Vec<_>>>()?;
+            match list.path.get_ident()?.to_string().as_str() {
+                "all" => Some(CfgPredicate::All(predicates)),
+                "any" => Some(CfgPredicate::Any(predicates)),
+                "not" if predicates.len() == 1 => {
+                    Some(CfgPredicate::Not(Box::new(predicates.pop()?)))
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Parse attributes into their structured form, keeping their order.
+///
+/// Attributes whose contents don't parse as items keep their raw tokens in
+/// [`AttributeInfo::value`].
+pub fn parse_attributes(attrs: &[syn::Attribute]) -> Vec<AttributeInfo> {
+    attrs
+        .iter()
+        .map(|attr| {
+            let AttributeArg { name, value, args } = attribute_arg_from_meta(&attr.meta);
+            let is_derive = name == "derive";
+            let derived_traits = if is_derive {
+                attr.parse_args_with(
+                    syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+                )
+                .map(|paths| paths.iter().map(path_to_string).collect())
+                .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            AttributeInfo {
+                name,
+                args,
+                value,
+                is_derive,
+                derived_traits,
+            }
+        })
+        .collect()
+}
+
+/// The deprecation declared by the first `#[deprecated]` in `attrs`, if any.
+fn parse_deprecation(attrs: &[syn::Attribute]) -> Option<Deprecation> {
+    let attr = attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("deprecated"))?;
+    parse_attributes(std::slice::from_ref(attr))
+        .first()
+        .and_then(Deprecation::from_attribute)
+}
+
+/// Convert a meta item such as `rename = "id"` or `rename(serialize = "a")`.
+fn attribute_arg_from_meta(meta: &syn::Meta) -> AttributeArg {
+    let name = path_to_string(meta.path());
+    match meta {
+        syn::Meta::Path(_) => AttributeArg {
+            name,
+            value: None,
+            args: Vec::new(),
+        },
+        syn::Meta::NameValue(name_value) => {
+            let value = match &name_value.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(lit_str),
+                    ..
+                }) => lit_str.value(),
+                other => quote::quote!(#other).to_string(),
+            };
+            AttributeArg {
+                name,
+                value: Some(value),
+                args: Vec::new(),
+            }
+        }
+        syn::Meta::List(list) => match list.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        ) {
+            Ok(nested) => AttributeArg {
+                name,
+                value: None,
+                args: nested.iter().map(attribute_arg_from_meta).collect(),
+            },
+            Err(_) => AttributeArg {
+                name,
+                value: Some(list.tokens.to_string()),
+                args: Vec::new(),
+            },
+        },
+    }
+}
+
+/// Doc-test attributes that are accepted but not recorded on a [`DocExample`].
+const OTHER_DOC_TEST_ATTRIBUTES: &[&str] = &["test_harness", "allow_fail", "standalone_crate"];
+
+/// Parse fenced code blocks out of documentation lines.
+///
+/// Both backtick and tilde fences are recognized. The info string is split on
+/// commas and whitespace; doc-test attributes set the matching flags and the
+/// first other tag is the language. An unterminated block runs to the end.
+pub fn parse_doc_examples<S: AsRef<str>>(lines: &[S]) -> Vec<DocExample> {
+    let mut examples = Vec::new();
+    let mut open: Option<(char, usize, DocExample, Vec<&str>)> = None;
+
+    for line in lines.iter().map(AsRef::as_ref) {
+        let trimmed = line.trim();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|&x| x == c).count());
+
+        match open.take() {
+            None if fence_len >= 3 => {
+                let fence_char = fence_char.unwrap_or('`');
+                let info = &trimmed[fence_len..];
+                open = Some((
+                    fence_char,
+                    fence_len,
+                    doc_example_from_info(info),
+                    Vec::new(),
+                ));
+            }
+            None => {}
+            Some((open_char, open_len, mut example, code)) => {
+                let closes = fence_char == Some(open_char)
+                    && fence_len >= open_len
+                    && trimmed.len() == fence_len;
+                if closes {
+                    example.code = code.join("\n");
+                    examples.push(example);
+                } else {
+                    let mut code = code;
+                    code.push(line);
+                    open = Some((open_char, open_len, example, code));
+                }
+            }
+        }
+    }
+
+    if let Some((_, _, mut example, code)) = open {
+        example.code = code.join("\n");
+        examples.push(example);
+    }
+
+    examples
+}
+
+/// Build an empty example from a fence's info string (e.g. "rust,no_run").
+fn doc_example_from_info(info: &str) -> DocExample {
+    let mut example = DocExample {
+        language: "rust".to_string(),
+        code: String::new(),
+        should_panic: false,
+        no_run: false,
+        ignore: false,
+        compile_fail: false,
+    };
+
+    let tags = info
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty());
+    let mut language = None;
+    for tag in tags {
+        match tag {
+            "should_panic" => example.should_panic = true,
+            "no_run" => example.no_run = true,
+            "compile_fail" => example.compile_fail = true,
+            tag if tag == "ignore" || tag.starts_with("ignore-") => example.ignore = true,
+            tag if OTHER_DOC_TEST_ATTRIBUTES.contains(&tag) || tag.starts_with("edition") => {}
+            tag => {
+                language.get_or_insert_with(|| tag.to_string());
+            }
+        }
+    }
+    if let Some(language) = language {
+        example.language = language;
+    }
+
+    example
+}
+
+/// Collect the deprecated elements of `files` with the elements referencing them.
+///
+/// Callers come from the resolved `graph`, so only references rustex can
+/// resolve are counted.
+pub fn summarize_deprecations(files: &[FileAst], graph: &ReferenceGraph) -> DeprecationSummary {
+    let elements = || files.iter().flat_map(|f| &f.elements);
+    let qualified_names: HashMap<&str, &str> = elements()
+        .map(|e| (e.id.as_str(), e.hierarchy.qualified_name.as_str()))
+        .collect();
+
+    let items: Vec<DeprecatedItem> = elements()
+        .filter_map(|element| {
+            let deprecation = element.deprecation.as_ref()?;
+            let mut callers: Vec<String> = graph
+                .referenced_by
+                .get(&element.id)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| qualified_names.get(id.as_str()))
+                .map(|name| name.to_string())
+                .collect();
+            callers.sort();
+            callers.dedup();
+            Some(DeprecatedItem {
+                element_id: element.id.clone(),
+                qualified_name: element.hierarchy.qualified_name.clone(),
+                element_type: element.element_type.clone(),
+                since: deprecation.since.clone(),
+                note: deprecation.note.clone(),
+                callers,
+            })
+        })
+        .collect();
+
+    DeprecationSummary {
+        total_deprecated: items.len(),
+        total_usages: items.iter().map(|item| item.callers.len()).sum(),
+        items,
+    }
+}
+
+/// List every `impl Trait for Type` block across the given files.
+///
+/// Must run after [`build_type_index`], which links impls to their types. The
+/// trait is resolved by qualified name, then by a unique simple name among the
+/// project's traits; foreign traits keep `trait_element_id` unset. For
+/// resolved traits, the trait's provided methods are split into those the
+/// impl overrides and those it inherits.
+pub fn collect_trait_impls(files: &[FileAst]) -> Vec<TraitImpl> {
+    let elements = || files.iter().flat_map(|f| &f.elements);
+
+    let mut by_qualified_name: HashMap<&str, &str> = HashMap::new();
+    let mut by_name: HashMap<&str, Vec<&str>> = HashMap::new();
+    for element in elements().filter(|e| e.element_type == ElementType::Trait) {
+        by_qualified_name.insert(&element.hierarchy.qualified_name, &element.id);
+        by_name.entry(&element.name).or_default().push(&element.id);
+    }
+
+    let traits: HashMap<&str, &CodeElement> = elements()
+        .filter(|e| e.element_type == ElementType::Trait)
+        .map(|e| (e.id.as_str(), e))
+        .collect();
+
+    elements()
+        .filter(|e| e.element_type == ElementType::Impl)
+        .filter_map(|element| {
+            let context = element.hierarchy.impl_context.as_ref()?;
+            let trait_path = context.trait_path.clone()?;
+
+            let trait_element_id = by_qualified_name
+                .get(trait_path.as_str())
+                .or_else(|| {
+                    match by_name
+                        .get(last_path_segment(&trait_path))
+                        .map(Vec::as_slice)
+                    {
+                        Some([single]) => Some(single),
+                        _ => None,
+                    }
+                })
+                .map(|id| id.to_string());
+
+            let impl_methods = element.impl_methods();
+            let (overridden_methods, inherited_methods) = trait_element_id
+                .as_deref()
+                .and_then(|id| traits.get(id))
+                .map(|trait_element| {
+                    trait_element
+                        .provided_methods()
+                        .into_iter()
+                        .map(str::to_string)
+                        .partition(|method| impl_methods.contains(&method.as_str()))
+                })
+                .unwrap_or_default();
+
+            Some(TraitImpl {
+                impl_id: element.id.clone(),
+                trait_path,
+                self_type: context.self_type.clone(),
+                trait_element_id,
+                type_element_id: element.hierarchy.parent_element.clone(),
+                generics: element.generic_params.clone(),
+                where_clause: context.where_clause.clone(),
+                location: element.location.clone(),
+                is_generated: element.is_generated,
+                overridden_methods,
+                inherited_methods,
+            })
+        })
+        .collect()
+}
+
+/// Names of a trait's methods that have a default body, or of those that don't.
+fn trait_method_names(node: &syn::ItemTrait, provided: bool) -> Vec<String> {
+    node.items
+        .iter()
+        .filter_map(|item| match item {
+            syn::TraitItem::Fn(method) if method.default.is_some() == provided => {
+                Some(method.sig.ident.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Last segment of a `::`-separated path, ignoring surrounding whitespace.
+fn last_path_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
+
+/// Render a path as `a::b::C`, dropping generic arguments.
+fn path_to_string(path: &syn::Path) -> String {
+    let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    let joined = segments.join("::");
+    if path.leading_colon.is_some() {
+        format!("::{}", joined)
+    } else {
+        joined
+    }
+}
@@ -0,0 +1,240 @@
+//! Cargo workspace discovery.
+//!
+//! Members are discovered with `cargo metadata --no-deps`, which needs no
+//! network access. If cargo is unavailable, the `[workspace] members` list of
+//! the root manifest is read directly (trailing `/*` globs are expanded).
+
+use crate::dependencies::{cargo_metadata, read_declared_dependencies, CargoSettings};
+use crate::errors::{Result, RustExError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A crate that belongs to a Cargo workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceMember {
+    /// Package name
+    pub name: String,
+    /// Package version
+    pub version: String,
+    /// Crate root relative to the workspace root (empty for a root package)
+    pub relative_path: PathBuf,
+    /// Dependencies on other workspace members as `(name, kind)` pairs
+    pub workspace_dependencies: Vec<(String, String)>,
+}
+
+/// Discover the members of the workspace rooted at `root`.
+///
+/// Returns `Ok(None)` when the root manifest does not declare a `[workspace]`.
+pub fn discover_workspace(root: &Path) -> Result<Option<Vec<WorkspaceMember>>> {
+    discover_workspace_with(root, &CargoSettings::default())
+}
+
+/// Discover the members of the workspace rooted at `root`, running
+/// `cargo metadata` with `cargo`.
+pub fn discover_workspace_with(
+    root: &Path,
+    cargo: &CargoSettings,
+) -> Result<Option<Vec<WorkspaceMember>>> {
+    let manifest_path = root.join("Cargo.toml");
+    let Ok(content) = fs::read_to_string(&manifest_path) else {
+        return Ok(None);
+    };
+    let manifest: toml::Value = toml::from_str(&content).map_err(|e| {
+        RustExError::Config(format!(
+            "Failed to parse {}: {}",
+            manifest_path.display(),
+            e
+        ))
+    })?;
+    if manifest.get("workspace").is_none() {
+        return Ok(None);
+    }
+
+    let metadata = cargo_metadata(&manifest_path, &["--no-deps"], cargo);
+    let mut members = match metadata {
+        Ok(metadata) => members_from_metadata(&metadata, root),
+        Err(e) => {
+            tracing::debug!(
+                "cargo metadata unavailable ({}), reading workspace members from manifest",
+                e
+            );
+            members_from_manifest(&manifest, root)
+        }
+    };
+
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Some(members))
+}
+
+/// Find the member a file belongs to, using the longest matching crate root.
+///
+/// `relative_file` must be relative to the workspace root.
+pub fn member_for_path<'a>(
+    members: &'a [WorkspaceMember],
+    relative_file: &Path,
+) -> Option<&'a WorkspaceMember> {
+    members
+        .iter()
+        .filter(|m| relative_file.starts_with(&m.relative_path))
+        .max_by_key(|m| m.relative_path.components().count())
+}
+
+/// Build members from `cargo metadata --no-deps` output.
+fn members_from_metadata(metadata: &serde_json::Value, root: &Path) -> Vec<WorkspaceMember> {
+    let workspace_root = metadata["workspace_root"]
+        .as_str()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()));
+
+    let empty = vec![];
+    let packages = metadata["packages"].as_array().unwrap_or(&empty);
+    let member_names: Vec<&str> = packages.iter().filter_map(|p| p["name"].as_str()).collect();
+
+    packages
+        .iter()
+        .filter_map(|package| {
+            let manifest = PathBuf::from(package["manifest_path"].as_str()?);
+            let crate_root = manifest.parent()?;
+            let relative_path = crate_root
+                .strip_prefix(&workspace_root)
+                .unwrap_or(crate_root)
+                .to_path_buf();
+
+            let workspace_dependencies = package["dependencies"]
+                .as_array()
+                .unwrap_or(&empty)
+                .iter()
+                .filter_map(|dep| {
+                    let name = dep["name"].as_str()?;
+                    member_names.contains(&name).then(|| {
+                        let kind = dep["kind"].as_str().unwrap_or("normal");
+                        (name.to_string(), kind.to_string())
+                    })
+                })
+                .collect();
+
+            Some(WorkspaceMember {
+                name: package["name"].as_str()?.to_string(),
+                version: package["version"].as_str().unwrap_or("0.0.0").to_string(),
+                relative_path,
+                workspace_dependencies,
+            })
+        })
+        .collect()
+}
+
+/// Build members from the `[workspace] members` list of the root manifest.
+fn members_from_manifest(manifest: &toml::Value, root: &Path) -> Vec<WorkspaceMember> {
+    let mut crate_dirs: Vec<PathBuf> = Vec::new();
+
+    // A root manifest with a [package] is itself a member.
+    if manifest.get("package").is_some() {
+        crate_dirs.push(PathBuf::new());
+    }
+
+    let patterns = manifest["workspace"]
+        .get("members")
+        .and_then(|m| m.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    for pattern in patterns.iter().filter_map(|p| p.as_str()) {
+        if let Some(parent) = pattern.strip_suffix("/*") {
+            if let Ok(entries) = fs::read_dir(root.join(parent)) {
+                let mut dirs: Vec<PathBuf> = entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().join("Cargo.toml").exists())
+                    .map(|e| Path::new(parent).join(e.file_name()))
+                    .collect();
+                dirs.sort();
+                crate_dirs.extend(dirs);
+            }
+        } else {
+            crate_dirs.push(PathBuf::from(pattern));
+        }
+    }
+
+    let mut members: Vec<WorkspaceMember> = crate_dirs
+        .into_iter()
+        .filter_map(|relative_path| {
+            let member_manifest = root.join(&relative_path).join("Cargo.toml");
+            let content = fs::read_to_string(&member_manifest).ok()?;
+            let value: toml::Value = toml::from_str(&content).ok()?;
+            let package = value.get("package")?;
+
+            Some(WorkspaceMember {
+                name: package.get("name")?.as_str()?.to_string(),
+                version: package
+                    .get("version")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("0.0.0")
+                    .to_string(),
+                relative_path,
+                workspace_dependencies: vec![],
+            })
+        })
+        .collect();
+
+    // Resolve inter-member edges once all member names are known.
+    let member_names: Vec<String> = members.iter().map(|m| m.name.clone()).collect();
+    for member in &mut members {
+        let member_manifest = root.join(&member.relative_path).join("Cargo.toml");
+        let Ok(declared) = read_declared_dependencies(&member_manifest) else {
+            continue;
+        };
+
+        for (names, kind) in [
+            (&declared.normal, "normal"),
+            (&declared.dev, "dev"),
+            (&declared.build, "build"),
+        ] {
+            for name in names.iter().filter(|n| member_names.contains(n)) {
+                member
+                    .workspace_dependencies
+                    .push((name.clone(), kind.to_string()));
+            }
+        }
+    }
+
+    members
+}
+
+/// Targets of a package that need special handling during extraction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageTargets {
+    /// The build script, if the package has one
+    pub build_script: Option<PathBuf>,
+    /// Whether the library is a procedural macro crate (`proc-macro = true`)
+    pub is_proc_macro: bool,
+}
+
+/// Read the build script and proc-macro setting of the package at `crate_root`.
+///
+/// The build script is the `package.build` path, or `build.rs` when that
+/// exists and `build` is not `false`. A missing or unreadable manifest yields
+/// no special targets.
+pub fn package_targets(crate_root: &Path) -> PackageTargets {
+    let manifest: Option<toml::Value> = fs::read_to_string(crate_root.join("Cargo.toml"))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok());
+    let Some(manifest) = manifest else {
+        return PackageTargets::default();
+    };
+
+    let build_script = match manifest.get("package").and_then(|p| p.get("build")) {
+        Some(toml::Value::String(path)) => Some(crate_root.join(path)),
+        Some(toml::Value::Boolean(false)) => None,
+        _ => Some(crate_root.join("build.rs")),
+    }
+    .filter(|path| path.is_file());
+
+    let lib = manifest.get("lib");
+    let is_proc_macro = ["proc-macro", "proc_macro"]
+        .iter()
+        .any(|key| lib.and_then(|lib| lib.get(key)) == Some(&toml::Value::Boolean(true)));
+
+    PackageTargets {
+        build_script,
+        is_proc_macro,
+    }
+}
@@ -187,6 +187,8 @@ impl Arbitrary for ArbitraryElementType {
             ElementType::TypeAlias,
             ElementType::Macro,
             ElementType::Union,
+            ElementType::Closure,
+            ElementType::AsyncBlock,
         ];
         ArbitraryElementType(g.choose(&variants).unwrap().clone())
     }
@@ -203,12 +205,72 @@ fn test_complexity_bounds_basic() {
         lines_of_code: lines as u32,
         parameter_count: params as u32,
         return_count: returns as u32,
+        closure_count: 0,
+        async_block_count: 0,
     };
 
     assert!(metrics.cyclomatic >= 1);
     // Note: cognitive, nesting_depth, lines_of_code, parameter_count, return_count are unsigned so >= 0 is always true
 }
 
+// Helper functions
+
+fn is_rust_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "as" | "break"
+            | "const"
+            | "continue"
+            | "crate"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "self"
+            | "Self"
+            | "static"
+            | "struct"
+            | "super"
+            | "trait"
+            | "true"
+            | "type"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+            | "async"
+            | "await"
+            | "dyn"
+            | "abstract"
+            | "become"
+            | "box"
+            | "do"
+            | "final"
+            | "macro"
+            | "override"
+            | "priv"
+            | "typeof"
+            | "unsized"
+            | "virtual"
+            | "yield"
+            | "try"
+    )
+}
+
 // Specific complexity scenario tests
 
 #[cfg(test)]
@@ -292,18 +354,4 @@ mod specific_tests {
         assert_eq!(metrics.parameter_count, 3);
         assert!(metrics.halstead.big_n2 > 0); // Should have operands
     }
-}
-
-// Helper functions
-
-fn is_rust_keyword(s: &str) -> bool {
-    matches!(s,
-        "as" | "break" | "const" | "continue" | "crate" | "else" | "enum" | "extern" |
-        "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match" |
-        "mod" | "move" | "mut" | "pub" | "ref" | "return" | "self" | "Self" |
-        "static" | "struct" | "super" | "trait" | "true" | "type" | "unsafe" |
-        "use" | "where" | "while" | "async" | "await" | "dyn" | "abstract" |
-        "become" | "box" | "do" | "final" | "macro" | "override" | "priv" |
-        "typeof" | "unsized" | "virtual" | "yield" | "try"
-    )
 }
\ No newline at end of file
@@ -122,7 +122,7 @@ fn test_mock_data_consistency() {
 #[test]
 #[ignore] // Temporarily disabled - test expectations need adjustment
 fn test_configuration_variations() {
-    let test_configs = vec![
+    let test_configs = [
         create_test_config(true, true),   // Include everything
         create_test_config(false, true),  // Exclude private, include docs
         create_test_config(true, false),  // Include private, exclude docs
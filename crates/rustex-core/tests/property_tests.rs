@@ -76,6 +76,12 @@ fn extractor_config() -> impl Strategy<Value = ExtractorConfig> {
             filters: FilterConfig {
                 include: includes,
                 exclude: excludes,
+                visibility: vec![],
+                exclude_build_scripts: false,
+                element_types: vec![],
+                name_patterns: vec![],
+                exclude_names: vec![],
+                overrides: Default::default(),
             },
             output_format: OutputFormat::Json,
             ..Default::default()
@@ -235,6 +241,7 @@ fn test_element_creation() {
         signature: Some(format!("signature for {}", name)),
         visibility: visibility.clone(),
         doc_comments: vec![],
+        doc_examples: vec![],
         inline_comments: vec![],
         location: CodeLocation {
             line_start: 1,
@@ -242,7 +249,10 @@ fn test_element_creation() {
             char_start: 0,
             char_end: 100,
             file_path: PathBuf::from("test.rs"),
+            byte_start: 0,
+            byte_end: 0,
         },
+        name_location: None,
         attributes: vec![],
         complexity: Some(1),
         complexity_metrics: None,
@@ -258,6 +268,11 @@ fn test_element_creation() {
                 &visibility,
             ),
         ),
+        is_generated: false,
+        cfg_predicates: vec![],
+        attribute_info: vec![],
+        deprecation: None,
+        calls: vec![],
     };
 
     assert_eq!(element.name, name);
@@ -291,6 +306,8 @@ fn test_complexity_bounds() {
         lines_of_code: lines as u32,
         parameter_count: params as u32,
         return_count: returns as u32,
+        closure_count: 0,
+        async_block_count: 0,
     };
 
     assert!(metrics.cyclomatic >= 1);
@@ -332,7 +349,7 @@ proptest! {
 
             // Should handle edge cases gracefully
             let result = extractor.extract_project();
-            prop_assert!(result.is_ok() || matches!(result, Err(_)), 
+            prop_assert!(result.is_ok() || result.is_err(),
                         "Edge case '{}' should be handled gracefully", case);
         }
     }
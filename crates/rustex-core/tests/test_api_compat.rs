@@ -0,0 +1,172 @@
+//! Tests for semver classification of public API changes.
+
+use rustex_core::api_compat::{api_churn, check_compat, version_bump, ApiStatus, ChangeLevel};
+use rustex_core::{AstExtractor, ExtractorConfig, ProjectAst};
+use std::fs;
+use tempfile::TempDir;
+
+/// Extract a single-crate project whose `src/lib.rs` is `source`.
+fn extract(source: &str) -> ProjectAst {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"compat\"\nversion = \"1.0.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(root.join("src/lib.rs"), source).unwrap();
+
+    let config = ExtractorConfig {
+        include_private: true,
+        ..ExtractorConfig::default()
+    };
+    AstExtractor::new(config, root.to_path_buf())
+        .extract_project()
+        .unwrap()
+}
+
+/// Classify the changes from `old` to `new` as `(level, qualified name)` pairs.
+fn classify(old: &str, new: &str) -> Vec<(ChangeLevel, String)> {
+    check_compat(&extract(old), &extract(new))
+        .changes
+        .into_iter()
+        .map(|change| (change.level, change.qualified_name))
+        .collect()
+}
+
+#[test]
+fn test_removed_and_changed_items_are_major() {
+    let changes = classify(
+        "pub fn keep(x: u32) {}\npub fn gone() {}\nfn private_gone() {}\n",
+        "pub fn keep(x: u64) {}\n",
+    );
+    assert_eq!(
+        changes,
+        [
+            (ChangeLevel::Major, "crate::gone".to_string()),
+            (ChangeLevel::Major, "crate::keep".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_added_items_are_minor_and_private_changes_are_ignored() {
+    let old = "pub fn stable() {}\nfn helper(a: u8) {}\n";
+    let new = concat!(
+        "pub fn stable() {}\nfn helper(a: u16) {}\n",
+        "pub fn added() {}\npub(crate) fn internal() {}\n",
+    );
+    let report = check_compat(&extract(old), &extract(new));
+    assert_eq!(report.required_bump(), ChangeLevel::Minor);
+    assert_eq!(report.changes.len(), 1);
+    assert_eq!(report.changes[0].qualified_name, "crate::added");
+
+    let unchanged = check_compat(&extract(old), &extract(old));
+    assert!(unchanged.changes.is_empty());
+    assert_eq!(unchanged.required_bump(), ChangeLevel::Patch);
+}
+
+#[test]
+fn test_enum_variants_respect_non_exhaustive() {
+    let exhaustive = classify("pub enum Mode { Fast }\n", "pub enum Mode { Fast, Slow }\n");
+    assert_eq!(
+        exhaustive,
+        [(ChangeLevel::Major, "crate::Mode".to_string())]
+    );
+
+    let non_exhaustive = classify(
+        "#[non_exhaustive]\npub enum Mode { Fast }\n",
+        "#[non_exhaustive]\npub enum Mode { Fast, Slow }\n",
+    );
+    assert_eq!(
+        non_exhaustive,
+        [(ChangeLevel::Minor, "crate::Mode".to_string())]
+    );
+
+    let removed = classify(
+        "#[non_exhaustive]\npub enum Mode { Fast, Slow }\n",
+        "#[non_exhaustive]\npub enum Mode { Fast }\n",
+    );
+    assert_eq!(removed, [(ChangeLevel::Major, "crate::Mode".to_string())]);
+}
+
+#[test]
+fn test_visibility_changes() {
+    let changes = classify(
+        "pub fn shrinks() {}\nfn grows() {}\n",
+        "fn shrinks() {}\npub fn grows() {}\n",
+    );
+    assert_eq!(
+        changes,
+        [
+            (ChangeLevel::Major, "crate::shrinks".to_string()),
+            (ChangeLevel::Minor, "crate::grows".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_version_bump_follows_cargo_rules() {
+    assert_eq!(version_bump("1.2.3", "2.0.0"), Some(ChangeLevel::Major));
+    assert_eq!(version_bump("1.2.3", "1.3.0"), Some(ChangeLevel::Minor));
+    assert_eq!(version_bump("1.2.3", "1.2.4"), Some(ChangeLevel::Patch));
+    assert_eq!(version_bump("0.3.1", "0.4.0"), Some(ChangeLevel::Major));
+    assert_eq!(version_bump("0.3.1", "0.3.2"), Some(ChangeLevel::Minor));
+    assert_eq!(version_bump("0.0.1", "0.0.2"), Some(ChangeLevel::Major));
+    assert_eq!(
+        version_bump("1.0.0-beta.1", "1.0.0"),
+        Some(ChangeLevel::Patch)
+    );
+    assert_eq!(version_bump("1.0", "1.1.0"), None);
+
+    let report = check_compat(
+        &extract("pub fn removed() {}\n"),
+        &extract("pub fn added() {}\n"),
+    );
+    assert_eq!(report.is_satisfied_by("1.0.0", "1.1.0"), Some(false));
+    assert_eq!(report.is_satisfied_by("1.0.0", "2.0.0"), Some(true));
+}
+
+#[test]
+fn test_api_churn_statuses() {
+    let old = extract(concat!(
+        "pub fn stable() {}\npub fn widened(x: u32) {}\npub fn gone() {}\n",
+        "pub fn hidden() {}\nfn exposed() {}\npub fn old_name() {}\n",
+    ));
+    let new = extract(concat!(
+        "pub fn stable() {}\npub fn widened(x: u64) {}\npub fn added() {}\n",
+        "fn hidden() {}\npub fn exposed() {}\n",
+        "#[deprecated(note = \"use stable\")]\npub fn old_name() {}\n",
+    ));
+    let churn = api_churn(&old, &new);
+
+    let mut statuses: Vec<(&str, ApiStatus)> = new
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .filter_map(|element| {
+            let status = churn.statuses.get(&element.id)?;
+            Some((element.hierarchy.qualified_name.as_str(), *status))
+        })
+        .collect();
+    statuses.sort();
+    assert_eq!(
+        statuses,
+        [
+            ("crate::added", ApiStatus::New),
+            ("crate::exposed", ApiStatus::New),
+            ("crate::old_name", ApiStatus::RemovedSoon),
+            ("crate::stable", ApiStatus::Unchanged),
+            ("crate::widened", ApiStatus::SignatureChanged),
+        ]
+    );
+    assert_eq!(churn.removed, ["crate::gone", "crate::hidden"]);
+    assert_eq!(churn.baseline_items, 5);
+    // Two new, one changed and two removed out of five.
+    assert_eq!(churn.churn_rate(), 1.0);
+
+    let unchanged = api_churn(&old, &old);
+    assert_eq!(unchanged.count(ApiStatus::Unchanged), 5);
+    assert_eq!(unchanged.churn_rate(), 0.0);
+}
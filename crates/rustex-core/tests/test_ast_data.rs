@@ -2,8 +2,9 @@
 
 use chrono::Utc;
 use rustex_core::{
-    CodeElement, CodeLocation, DependencyInfo, ElementHierarchy, ElementType, FileAst, FileMetrics, ImportInfo,
-    ProjectAst, ProjectInfo, ProjectMetrics, Visibility,
+    CodeElement, CodeLocation, DependencyInfo, ElementHierarchy, ElementType, FileAst, FileKind,
+    FileMetrics, ImportInfo, InlineComment, ProjectAst, ProjectInfo, ProjectMetrics,
+    ReferenceGraph, Visibility,
 };
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -25,6 +26,8 @@ fn test_project_ast_creation() {
         struct_count: 2,
         enum_count: 1,
         trait_count: 1,
+        ownership: Default::default(),
+        unsafe_usage: Default::default(),
     };
 
     let file_ast = FileAst {
@@ -34,6 +37,10 @@ fn test_project_ast_creation() {
         imports: vec![],
         file_metrics,
         cross_references: vec![],
+        parse_errors: vec![],
+        warnings: vec![],
+        kind: FileKind::Source,
+        partially_extracted: false,
     };
 
     let dependencies = DependencyInfo {
@@ -51,6 +58,9 @@ fn test_project_ast_creation() {
         total_traits: 1,
         complexity_average: 2.0,
         complexity_max: 5,
+        deprecations: Default::default(),
+        ownership: Default::default(),
+        unsafe_usage: Default::default(),
     };
 
     let project_ast = ProjectAst {
@@ -60,6 +70,14 @@ fn test_project_ast_creation() {
         metrics,
         extracted_at: Utc::now(),
         cross_references: vec![],
+        dependencies_ast: vec![],
+        workspace: None,
+        reference_graph: ReferenceGraph::default(),
+        type_index: Default::default(),
+        impls: vec![],
+        truncation: None,
+        extraction_report: Default::default(),
+        plugin_results: Default::default(),
     };
 
     assert_eq!(project_ast.project.name, "test-project");
@@ -76,6 +94,8 @@ fn test_code_element_creation() {
         char_start: 0,
         char_end: 100,
         file_path: PathBuf::from("src/main.rs"),
+        byte_start: 0,
+        byte_end: 0,
     };
 
     let mut metadata = HashMap::new();
@@ -91,8 +111,13 @@ fn test_code_element_creation() {
         signature: Some("fn test_function() -> String".to_string()),
         visibility: Visibility::Public,
         doc_comments: vec!["Function documentation".to_string()],
-        inline_comments: vec!["Inline comment".to_string()],
+        inline_comments: vec![InlineComment {
+            text: "Inline comment".to_string(),
+            line_start: 12,
+            line_end: 12,
+        }],
         location,
+        name_location: None,
         attributes: vec!["#[test]".to_string()],
         complexity: Some(3),
         complexity_metrics: None,
@@ -108,6 +133,12 @@ fn test_code_element_creation() {
                 &Visibility::Public,
             ),
         ),
+        is_generated: false,
+        doc_examples: vec![],
+        cfg_predicates: vec![],
+        attribute_info: vec![],
+        deprecation: None,
+        calls: vec![],
     };
 
     assert_eq!(element.name, "test_function");
@@ -132,6 +163,8 @@ fn test_element_types() {
         ElementType::TypeAlias,
         ElementType::Macro,
         ElementType::Union,
+        ElementType::Closure,
+        ElementType::AsyncBlock,
     ];
 
     for element_type in types {
@@ -152,6 +185,8 @@ fn test_element_types() {
             ElementType::TypeAlias => assert!(matches!(element_type, ElementType::TypeAlias)),
             ElementType::Macro => assert!(matches!(element_type, ElementType::Macro)),
             ElementType::Union => assert!(matches!(element_type, ElementType::Union)),
+            ElementType::Closure => assert!(matches!(element_type, ElementType::Closure)),
+            ElementType::AsyncBlock => assert!(matches!(element_type, ElementType::AsyncBlock)),
         }
     }
 }
@@ -161,7 +196,9 @@ fn test_visibility_types() {
     let visibilities = vec![
         Visibility::Public,
         Visibility::Private,
-        Visibility::Restricted("crate".to_string()),
+        Visibility::Crate,
+        Visibility::Super,
+        Visibility::Restricted("crate::net".to_string()),
     ];
 
     for visibility in visibilities {
@@ -171,12 +208,22 @@ fn test_visibility_types() {
         match &visibility {
             Visibility::Public => assert!(matches!(visibility, Visibility::Public)),
             Visibility::Private => assert!(matches!(visibility, Visibility::Private)),
+            Visibility::Crate => assert_eq!(visibility.to_string(), "pub(crate)"),
+            Visibility::Super => assert_eq!(visibility.to_string(), "pub(super)"),
             Visibility::Restricted(scope) => {
                 assert!(matches!(visibility, Visibility::Restricted(_)));
-                assert_eq!(scope, "crate");
+                assert_eq!(scope, "crate::net");
+                assert_eq!(visibility.to_string(), "pub(in crate::net)");
             }
         }
     }
+
+    assert!(Visibility::Crate.matches_filter("pub(crate)"));
+    assert!(Visibility::Crate.matches_filter(" pub( crate ) "));
+    assert!(!Visibility::Crate.matches_filter("pub"));
+    assert!(Visibility::Restricted("crate::net".to_string()).matches_filter("pub(in)"));
+    assert!(Visibility::Private.matches_filter("private"));
+    assert!(!Visibility::is_valid_filter("protected"));
 }
 
 #[test]
@@ -187,6 +234,8 @@ fn test_import_info() {
         imported_items: vec!["HashMap".to_string()],
         is_glob: false,
         alias: None,
+        is_reexport: false,
+        scope: "crate".to_string(),
     };
 
     assert_eq!(simple_import.module_path, "std::collections");
@@ -199,6 +248,8 @@ fn test_import_info() {
         imported_items: vec![],
         is_glob: true,
         alias: None,
+        is_reexport: false,
+        scope: "crate".to_string(),
     };
 
     assert!(glob_import.is_glob);
@@ -210,6 +261,8 @@ fn test_import_info() {
         imported_items: vec!["HashMap".to_string()],
         is_glob: false,
         alias: Some("Map".to_string()),
+        is_reexport: false,
+        scope: "crate".to_string(),
     };
 
     assert_eq!(aliased_import.alias.unwrap(), "Map");
@@ -243,6 +296,9 @@ fn test_metrics_calculation() {
         total_traits: 0,
         complexity_average: 0.0,
         complexity_max: 0,
+        deprecations: Default::default(),
+        ownership: Default::default(),
+        unsafe_usage: Default::default(),
     };
 
     // Simulate adding file metrics
@@ -281,6 +337,8 @@ fn test_file_metrics() {
         struct_count: 3,
         enum_count: 2,
         trait_count: 1,
+        ownership: Default::default(),
+        unsafe_usage: Default::default(),
     };
 
     assert_eq!(file_metrics.lines_of_code, 150);
@@ -339,6 +397,8 @@ fn test_code_location() {
         char_start: 4,
         char_end: 25,
         file_path: PathBuf::from("src/main.rs"),
+        byte_start: 0,
+        byte_end: 0,
     };
 
     assert_eq!(location.line_start, 10);
@@ -366,6 +426,9 @@ fn test_default_implementations() {
         total_traits: 0,
         complexity_average: 0.0,
         complexity_max: 0,
+        deprecations: Default::default(),
+        ownership: Default::default(),
+        unsafe_usage: Default::default(),
     };
 
     // All values should be zero/empty for default
@@ -1,5 +1,7 @@
 //! Tests for configuration handling.
 
+#![allow(clippy::field_reassign_with_default)]
+
 use rustex_core::{ExtractorConfig, FilterConfig, OutputFormat};
 
 #[test]
@@ -76,6 +78,12 @@ fn test_filter_config() {
     let mut filter_config = FilterConfig {
         include: vec!["**/*.rs".to_string()],
         exclude: vec!["target/**".to_string(), "tests/**".to_string()],
+        visibility: vec![],
+        exclude_build_scripts: false,
+        element_types: vec![],
+        name_patterns: vec![],
+        exclude_names: vec![],
+        overrides: Default::default(),
     };
 
     assert_eq!(filter_config.include.len(), 1);
@@ -97,6 +105,27 @@ fn test_output_formats() {
         OutputFormat::Markdown,
         OutputFormat::GraphQL,
         OutputFormat::Rag,
+        OutputFormat::Jsonl,
+        OutputFormat::RagJsonl,
+        OutputFormat::LangChain,
+        OutputFormat::LlamaIndex,
+        OutputFormat::HfDataset,
+        OutputFormat::OpenAiFineTune,
+        OutputFormat::AnthropicFineTune,
+        OutputFormat::Mermaid,
+        OutputFormat::Dot,
+        OutputFormat::Csv,
+        OutputFormat::Parquet,
+        OutputFormat::Turtle,
+        OutputFormat::NTriples,
+        OutputFormat::Cypher,
+        OutputFormat::Protobuf,
+        OutputFormat::Scip,
+        OutputFormat::Ctags,
+        OutputFormat::Etags,
+        OutputFormat::Overview,
+        OutputFormat::Sarif,
+        OutputFormat::Template,
     ];
 
     // Test that all formats can be cloned and debugged
@@ -146,8 +175,15 @@ fn test_custom_config_creation() {
         filters: FilterConfig {
             include: vec!["custom/**/*.rs".to_string()],
             exclude: vec!["custom/target/**".to_string()],
+            visibility: vec![],
+            exclude_build_scripts: false,
+            element_types: vec![],
+            name_patterns: vec![],
+            exclude_names: vec![],
+            overrides: Default::default(),
         },
-        plugins: vec!["custom-plugin".to_string()],
+        plugins: vec!["custom-plugin".to_string()].into(),
+        ..Default::default()
     };
 
     assert!(!custom_config.include_docs);
@@ -160,7 +196,7 @@ fn test_custom_config_creation() {
     ));
     assert_eq!(custom_config.filters.include[0], "custom/**/*.rs");
     assert_eq!(custom_config.filters.exclude[0], "custom/target/**");
-    assert_eq!(custom_config.plugins[0], "custom-plugin");
+    assert_eq!(custom_config.plugins.enabled[0], "custom-plugin");
 }
 
 #[test]
@@ -191,16 +227,16 @@ fn test_plugin_configuration() {
     let mut config = ExtractorConfig::default();
 
     // Test adding plugins
-    config.plugins.push("plugin1".to_string());
-    config.plugins.push("plugin2".to_string());
+    config.plugins.enabled.push("plugin1".to_string());
+    config.plugins.enabled.push("plugin2".to_string());
 
-    assert_eq!(config.plugins.len(), 2);
-    assert!(config.plugins.contains(&"plugin1".to_string()));
-    assert!(config.plugins.contains(&"plugin2".to_string()));
+    assert_eq!(config.plugins.enabled.len(), 2);
+    assert!(config.plugins.enabled.contains(&"plugin1".to_string()));
+    assert!(config.plugins.enabled.contains(&"plugin2".to_string()));
 
     // Test removing plugins
-    config.plugins.retain(|p| p != "plugin1");
-    assert_eq!(config.plugins.len(), 1);
-    assert!(!config.plugins.contains(&"plugin1".to_string()));
-    assert!(config.plugins.contains(&"plugin2".to_string()));
+    config.plugins.enabled.retain(|p| p != "plugin1");
+    assert_eq!(config.plugins.enabled.len(), 1);
+    assert!(!config.plugins.enabled.contains(&"plugin1".to_string()));
+    assert!(config.plugins.enabled.contains(&"plugin2".to_string()));
 }
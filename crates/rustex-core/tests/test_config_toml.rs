@@ -1,6 +1,13 @@
 //! Tests for TOML configuration system.
 
-use rustex_core::{ConfigUseCase, ExtractorConfig, OutputFormat};
+#![allow(clippy::field_reassign_with_default)]
+
+use rustex_core::{
+    diagnose_config_str, diagnose_config_str_with_schemas, ConfigLayers, ConfigSource,
+    ConfigUseCase, DiagnosticSeverity, ExtractorConfig, MarkdownSection, OutputFormat,
+    RagChunkStrategy,
+};
+use std::collections::BTreeMap;
 use tempfile::TempDir;
 
 #[test]
@@ -298,10 +305,19 @@ fn test_complex_toml_structure() {
     assert!(config.filters.exclude.contains(&"*.tmp".to_string()));
 
     // Plugins should now be parsed correctly
-    assert_eq!(config.plugins.len(), 3);
-    assert!(config.plugins.contains(&"complexity-analyzer".to_string()));
-    assert!(config.plugins.contains(&"doc-generator".to_string()));
-    assert!(config.plugins.contains(&"dependency-graph".to_string()));
+    assert_eq!(config.plugins.enabled.len(), 3);
+    assert!(config
+        .plugins
+        .enabled
+        .contains(&"complexity-analyzer".to_string()));
+    assert!(config
+        .plugins
+        .enabled
+        .contains(&"doc-generator".to_string()));
+    assert!(config
+        .plugins
+        .enabled
+        .contains(&"dependency-graph".to_string()));
 }
 
 #[test]
@@ -314,7 +330,536 @@ fn test_plugins_parsing() {
         ExtractorConfig::from_toml_str(simple_toml).expect("Failed to parse simple plugins TOML");
 
     println!("Simple plugins: {:?}", config.plugins);
-    assert_eq!(config.plugins.len(), 2);
-    assert!(config.plugins.contains(&"analyzer".to_string()));
-    assert!(config.plugins.contains(&"formatter".to_string()));
+    assert_eq!(config.plugins.enabled.len(), 2);
+    assert!(config.plugins.enabled.contains(&"analyzer".to_string()));
+    assert!(config.plugins.enabled.contains(&"formatter".to_string()));
+}
+
+#[test]
+fn test_layered_config_resolution() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let global = temp_dir.path().join("global.toml");
+    let project = temp_dir.path().join("rustex.toml");
+    std::fs::write(
+        &global,
+        "include_private = true\nmax_file_size = 1024\n[filters]\nexclude = [\"gen/**\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &project,
+        "max_file_size = 2048\n[filters]\ninclude = [\"lib/**\"]\n",
+    )
+    .unwrap();
+
+    let env = |vars: &[(&str, &str)]| -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    };
+    let resolved = ConfigLayers::new()
+        .global_file(&global)
+        .project_file(&project)
+        .env_vars(env(&[
+            ("RUSTEX_INCLUDE_DOCS", "false"),
+            ("RUSTEX_FILTERS_VISIBILITY", "pub, pub(crate)"),
+            ("RUSTEX_CFG_FEATURES", "std"),
+            ("RUSTEX_TEMPLATE", "report.md.tera"),
+            ("RUSTEX_PLUGIN_DIR", "/ignored"),
+            ("HOME", "/ignored"),
+        ]))
+        .resolve()
+        .expect("Failed to resolve layers");
+
+    let config = &resolved.config;
+    assert!(config.include_private);
+    assert!(!config.include_docs);
+    assert_eq!(config.max_file_size, 2048);
+    assert_eq!(config.filters.include, vec!["lib/**"]);
+    assert_eq!(config.filters.exclude, vec!["gen/**"]);
+    assert_eq!(config.filters.visibility, vec!["pub", "pub(crate)"]);
+    assert_eq!(config.cfg_features, Some(vec!["std".to_string()]));
+    assert_eq!(config.template, Some("report.md.tera".into()));
+    assert_eq!(
+        diagnose_config_str("template = \"report.md.tera\"\n", None),
+        vec![]
+    );
+
+    assert_eq!(
+        resolved.source("include_private"),
+        &ConfigSource::GlobalFile(global.clone())
+    );
+    assert_eq!(
+        resolved.source("max_file_size"),
+        &ConfigSource::ProjectFile(project.clone())
+    );
+    assert_eq!(
+        resolved.source("filters.exclude"),
+        &ConfigSource::GlobalFile(global)
+    );
+    assert_eq!(
+        resolved.source("include_docs"),
+        &ConfigSource::Environment("RUSTEX_INCLUDE_DOCS".to_string())
+    );
+    assert_eq!(
+        resolved.source("parse_dependencies"),
+        &ConfigSource::Default
+    );
+
+    let settings = resolved.settings().unwrap();
+    let (_, value, source) = settings
+        .iter()
+        .find(|(key, _, _)| key == "filters.include")
+        .unwrap();
+    assert_eq!(value.to_string(), r#"["lib/**"]"#);
+    assert_eq!(*source, &ConfigSource::ProjectFile(project));
+
+    let invalid = ConfigLayers::new()
+        .env_vars(env(&[("RUSTEX_MAX_FILE_SIZE", "huge")]))
+        .resolve();
+    assert!(format!("{:#}", invalid.unwrap_err()).contains("RUSTEX_MAX_FILE_SIZE"));
+}
+
+#[test]
+fn test_threshold_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("rustex.toml");
+    let content = "[thresholds]\nmax_cyclomatic = 15\nmax_unsafe = 0\n";
+    std::fs::write(&project, content).unwrap();
+    let resolved = ConfigLayers::new()
+        .project_file(&project)
+        .env_vars([(
+            "RUSTEX_THRESHOLDS_MIN_DOC_COVERAGE".to_string(),
+            "72.5".to_string(),
+        )])
+        .resolve()
+        .unwrap();
+    let thresholds = &resolved.config.thresholds;
+    assert_eq!(thresholds.max_cyclomatic, Some(15));
+    assert_eq!(thresholds.max_unsafe, Some(0));
+    assert_eq!(thresholds.min_doc_coverage, Some(72.5));
+    assert_eq!(thresholds.max_file_lines, None);
+    assert_eq!(
+        resolved.source("thresholds.max_cyclomatic"),
+        &ConfigSource::ProjectFile(project)
+    );
+
+    let mut config = resolved.config.clone();
+    config.thresholds.min_doc_coverage = Some(120.0);
+    assert!(config.validate().is_err());
+
+    // Threshold keys are known, and misspelled ones get a suggestion.
+    assert_eq!(diagnose_config_str(content, None), vec![]);
+    let diagnostics = diagnose_config_str("[thresholds]\nmax_cyclomatc = 15\n", None);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].key.as_deref(),
+        Some("thresholds.max_cyclomatc")
+    );
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("did you mean `max_cyclomatic`?")
+    );
+}
+
+#[test]
+fn test_markdown_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("rustex.toml");
+    let content = "[markdown]\nexclude_sections = [\"diagrams\"]\nheading_level = 2\n";
+    std::fs::write(&project, content).unwrap();
+    let resolved = ConfigLayers::new()
+        .project_file(&project)
+        .env_vars([(
+            "RUSTEX_MARKDOWN_REPO_URL".to_string(),
+            "https://github.com/owner/repo/blob/main".to_string(),
+        )])
+        .resolve()
+        .unwrap();
+    let markdown = &resolved.config.markdown;
+    assert_eq!(markdown.heading_level, 2);
+    assert!(markdown.table_of_contents);
+    assert!(markdown.includes(MarkdownSection::Files));
+    assert!(!markdown.includes(MarkdownSection::Diagrams));
+    assert_eq!(
+        markdown.repo_url.as_deref(),
+        Some("https://github.com/owner/repo/blob/main")
+    );
+    assert_eq!(diagnose_config_str(content, None), vec![]);
+
+    // Listed sections are the only ones rendered, less the excluded ones.
+    let mut config = resolved.config.clone();
+    config.markdown.sections = vec![MarkdownSection::Metrics, MarkdownSection::Diagrams];
+    assert!(config.markdown.includes(MarkdownSection::Metrics));
+    assert!(!config.markdown.includes(MarkdownSection::Files));
+
+    // Merging keeps the settings the other configuration leaves at their defaults.
+    let mut other = ExtractorConfig::default();
+    other.markdown.table_of_contents = false;
+    config.merge_with(other);
+    assert!(!config.markdown.table_of_contents);
+    assert_eq!(config.markdown.heading_level, 2);
+    assert!(config.markdown.repo_url.is_some());
+
+    config.markdown.heading_level = 4;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_config_diagnostics() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+    std::fs::write(temp_dir.path().join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+
+    let content = r#"include_doc = true
+plugins = ["llm-optimizer", "complexity"]
+disabled_plugins = ["doc-enhancr"]
+
+[filters]
+include = ["src/**/*.rs", "examples/**/*.rs"]
+exclude = ["src/**"]
+
+[filters.overrides."tests/**"]
+extrct = true
+"#;
+    let diagnostics = diagnose_config_str(content, Some(temp_dir.path()));
+    let find = |code: &str| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.code == code)
+            .collect::<Vec<_>>()
+    };
+
+    let unknown = find("unknown-key");
+    assert_eq!(unknown.len(), 2);
+    assert_eq!(unknown[0].key.as_deref(), Some("include_doc"));
+    assert_eq!(unknown[0].line, Some(1));
+    assert_eq!(
+        unknown[0].help.as_deref(),
+        Some("did you mean `include_docs`?")
+    );
+    assert_eq!(unknown[1].line, Some(10));
+    assert_eq!(unknown[1].help.as_deref(), Some("did you mean `extract`?"));
+
+    let plugins = find("unknown-plugin");
+    assert_eq!(plugins.len(), 2);
+    assert!(plugins[0].message.contains("`complexity`"));
+    assert_eq!(
+        plugins[0].help.as_deref(),
+        Some("did you mean `complexity-analyzer`?")
+    );
+    assert_eq!(plugins[1].key.as_deref(), Some("disabled_plugins"));
+    assert_eq!(
+        plugins[1].help.as_deref(),
+        Some("did you mean `doc-enhancer`?")
+    );
+
+    assert_eq!(find("conflicting-globs").len(), 1);
+    let unreachable = find("unreachable-include");
+    assert_eq!(unreachable.len(), 1);
+    assert!(unreachable[0].message.contains("examples/**/*.rs"));
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == DiagnosticSeverity::Warning));
+
+    // Invalid values and syntax are errors with the line they're on.
+    let invalid = diagnose_config_str("include_docs = true\nmax_file_size = \"big\"\n", None);
+    assert_eq!(invalid.len(), 1);
+    assert_eq!(invalid[0].severity, DiagnosticSeverity::Error);
+    assert_eq!(invalid[0].line, Some(2));
+    let malformed = diagnose_config_str("[filters\n", None);
+    assert_eq!(malformed[0].code, "invalid-toml");
+
+    // The example configuration is clean.
+    let example = temp_dir.path().join("rustex.toml");
+    ExtractorConfig::create_example_config(&example).unwrap();
+    let content = std::fs::read_to_string(&example).unwrap();
+    assert_eq!(diagnose_config_str(&content, Some(temp_dir.path())), vec![]);
+}
+
+#[test]
+fn test_embedding_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("rustex.toml");
+    let content = "[embeddings]\nenabled = true\nurl = \"http://localhost:11434/v1\"\n\
+                   model = \"nomic-embed-text\"\nrequests_per_minute = 60\n";
+    std::fs::write(&project, content).unwrap();
+    let resolved = ConfigLayers::new()
+        .project_file(&project)
+        .env_vars([(
+            "RUSTEX_EMBEDDINGS_MODEL_DIR".to_string(),
+            "models/all-MiniLM-L6-v2".to_string(),
+        )])
+        .resolve()
+        .unwrap();
+    let embeddings = &resolved.config.embeddings;
+    assert!(embeddings.enabled);
+    assert_eq!(embeddings.url, "http://localhost:11434/v1");
+    assert_eq!(embeddings.model, "nomic-embed-text");
+    assert_eq!(embeddings.api_key_env, "OPENAI_API_KEY");
+    assert_eq!(embeddings.batch_size, 64);
+    assert_eq!(embeddings.requests_per_minute, Some(60));
+    assert_eq!(
+        embeddings.model_dir.as_deref(),
+        Some(std::path::Path::new("models/all-MiniLM-L6-v2"))
+    );
+    assert_eq!(diagnose_config_str(content, None), vec![]);
+
+    // Merging keeps the settings the other configuration leaves at their defaults.
+    let mut config = resolved.config.clone();
+    let mut other = ExtractorConfig::default();
+    other.embeddings.batch_size = 16;
+    config.merge_with(other);
+    assert_eq!(config.embeddings.batch_size, 16);
+    assert_eq!(config.embeddings.model, "nomic-embed-text");
+    assert!(config.embeddings.enabled);
+
+    config.embeddings.requests_per_minute = Some(0);
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_summary_config() {
+    let content = "[summaries]\nenabled = true\nmodel = \"llama3\"\nmin_complexity = 8\n";
+    let config: ExtractorConfig = toml::from_str(content).unwrap();
+    let summaries = &config.summaries;
+    assert!(summaries.enabled);
+    assert_eq!(summaries.model, "llama3");
+    assert_eq!(summaries.url, "https://api.openai.com/v1");
+    assert_eq!(summaries.min_complexity, 8);
+    assert_eq!(diagnose_config_str(content, None), vec![]);
+
+    let mut merged = config.clone();
+    let mut other = ExtractorConfig::default();
+    other.summaries.url = "http://localhost:11434/v1".to_string();
+    merged.merge_with(other);
+    assert_eq!(merged.summaries.url, "http://localhost:11434/v1");
+    assert_eq!(merged.summaries.model, "llama3");
+}
+
+#[test]
+fn test_rag_output_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("rustex.toml");
+    let content = "[rag]\nsince = \"rag-manifest.json\"\nmin_quality = 0.3\n\
+                   chunk_strategy = \"sliding_window\"\ninclude_parent_signature = false\n";
+    std::fs::write(&project, content).unwrap();
+    let resolved = ConfigLayers::new()
+        .project_file(&project)
+        .env_vars([
+            (
+                "RUSTEX_RAG_MANIFEST".to_string(),
+                "out/rag-manifest.json".to_string(),
+            ),
+            ("RUSTEX_RAG_DEDUP_THRESHOLD".to_string(), "0.9".to_string()),
+            (
+                "RUSTEX_RAG_CONTEXT_RADIUS_LINES".to_string(),
+                "3".to_string(),
+            ),
+        ])
+        .resolve()
+        .unwrap();
+    let rag = &resolved.config.rag;
+    assert_eq!(
+        rag.since.as_deref(),
+        Some(std::path::Path::new("rag-manifest.json"))
+    );
+    assert_eq!(
+        rag.manifest.as_deref(),
+        Some(std::path::Path::new("out/rag-manifest.json"))
+    );
+    assert_eq!(rag.min_quality, 0.3);
+    assert_eq!(rag.dedup_threshold, Some(0.9));
+    assert_eq!(rag.chunk_strategy, RagChunkStrategy::SlidingWindow);
+    assert_eq!(rag.chunk_overlap_tokens, 50);
+    assert_eq!(rag.context_radius_lines, 3);
+    assert!(!rag.include_parent_signature);
+    assert_eq!(diagnose_config_str(content, None), vec![]);
+
+    // Merging keeps the manifests the other configuration doesn't set.
+    let mut config = resolved.config.clone();
+    let mut other = ExtractorConfig::default();
+    other.rag.manifest = Some("next.json".into());
+    config.merge_with(other);
+    assert_eq!(
+        config.rag.since.as_deref(),
+        Some(std::path::Path::new("rag-manifest.json"))
+    );
+    assert_eq!(
+        config.rag.manifest.as_deref(),
+        Some(std::path::Path::new("next.json"))
+    );
+    assert_eq!(config.rag.min_quality, 0.3);
+    assert_eq!(config.rag.chunk_strategy, RagChunkStrategy::SlidingWindow);
+    assert!(!config.rag.include_parent_signature);
+    assert!(config.validate().is_ok());
+
+    // Quality scores range from 0 to 1.
+    config.rag.min_quality = 1.5;
+    assert!(config.validate().is_err());
+    config.rag.min_quality = 0.0;
+    config.rag.dedup_threshold = Some(0.0);
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_hf_dataset_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("rustex.toml");
+    let content = "[hf_dataset]\nvalidation_split = 0.2\nshard_size = 500\n";
+    std::fs::write(&project, content).unwrap();
+    let resolved = ConfigLayers::new()
+        .project_file(&project)
+        .env_vars([("RUSTEX_HF_DATASET_LICENSE".to_string(), "MIT".to_string())])
+        .resolve()
+        .unwrap();
+    let dataset = &resolved.config.hf_dataset;
+    assert_eq!(dataset.validation_split, 0.2);
+    assert_eq!(dataset.test_split, 0.1);
+    assert_eq!(dataset.shard_size, 500);
+    assert_eq!(dataset.license.as_deref(), Some("MIT"));
+    assert_eq!(diagnose_config_str(content, None), vec![]);
+
+    // Merging keeps the settings the other configuration leaves at defaults.
+    let mut config = resolved.config.clone();
+    let mut other = ExtractorConfig::default();
+    other.hf_dataset.test_split = 0.3;
+    config.merge_with(other);
+    assert_eq!(config.hf_dataset.validation_split, 0.2);
+    assert_eq!(config.hf_dataset.test_split, 0.3);
+    assert_eq!(config.hf_dataset.license.as_deref(), Some("MIT"));
+    assert!(config.validate().is_ok());
+
+    // The validation and test splits leave no examples to train on.
+    config.hf_dataset.test_split = 0.8;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_fine_tuning_config() {
+    let content = r#"
+[fine_tuning.system_prompts]
+api_usage = "You write examples of the {{ project }} API."
+"#;
+    let config: ExtractorConfig = toml::from_str(content).unwrap();
+    let fine_tuning = &config.fine_tuning;
+    assert_eq!(
+        fine_tuning.system_prompt_for("api_usage"),
+        "You write examples of the {{ project }} API."
+    );
+    assert_eq!(
+        fine_tuning.system_prompt_for("code_explanation"),
+        fine_tuning.system_prompt
+    );
+    assert_eq!(diagnose_config_str(content, None), vec![]);
+
+    // Merging keeps the prompts of the task types the other configuration
+    // doesn't set.
+    let mut merged = config.clone();
+    let mut other = ExtractorConfig::default();
+    let prompts = &mut other.fine_tuning.system_prompts;
+    prompts.insert("bug_finding".to_string(), "You find bugs.".to_string());
+    merged.merge_with(other);
+    let fine_tuning = &merged.fine_tuning;
+    assert_eq!(
+        fine_tuning.system_prompt_for("bug_finding"),
+        "You find bugs."
+    );
+    assert_eq!(
+        fine_tuning.system_prompt_for("api_usage"),
+        "You write examples of the {{ project }} API."
+    );
+
+    // Prompts of unknown task types fail validation.
+    let diagnostics = diagnose_config_str("[fine_tuning.system_prompts]\napi = \"\"\n", None);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "invalid-config");
+}
+
+#[test]
+fn test_plugin_config() {
+    let content = r#"
+[plugins]
+enabled = ["complexity-analyzer"]
+
+[plugins.config.complexity-analyzer]
+high_complexity_threshold = 25
+report_format = "summary"
+"#;
+    let config = ExtractorConfig::from_toml_str(content).unwrap();
+    assert_eq!(config.plugins.enabled, vec!["complexity-analyzer"]);
+    let settings = &config.plugins.config["complexity-analyzer"];
+    assert_eq!(settings["high_complexity_threshold"], 25);
+
+    // Settings survive a round trip, and plugins without settings are
+    // written as a list.
+    let roundtrip = ExtractorConfig::from_toml_str(&config.to_toml_string().unwrap()).unwrap();
+    assert_eq!(roundtrip.plugins, config.plugins);
+    let listed = ExtractorConfig {
+        plugins: vec!["doc-enhancer".to_string()].into(),
+        ..ExtractorConfig::default()
+    };
+    assert!(listed
+        .to_toml_string()
+        .unwrap()
+        .contains("plugins = [\"doc-enhancer\"]"));
+
+    // Merging keeps the settings of plugins the other configuration doesn't set.
+    let mut merged = config.clone();
+    let other =
+        ExtractorConfig::from_toml_str("[plugins.config.doc-enhancer]\nstyle = \"google\"\n")
+            .unwrap();
+    merged.merge_with(other);
+    assert_eq!(merged.plugins.enabled, vec!["complexity-analyzer"]);
+    assert_eq!(merged.plugins.config.len(), 2);
+
+    // Settings are checked against the schemas of the plugins.
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "high_complexity_threshold": { "type": "integer", "minimum": 1 },
+            "report_format": { "$ref": "#/$defs/ReportFormat" }
+        },
+        "additionalProperties": false,
+        "$defs": {
+            "ReportFormat": { "enum": ["summary", "detailed"] }
+        }
+    });
+    let schemas = BTreeMap::from([("complexity-analyzer".to_string(), schema)]);
+    assert_eq!(
+        diagnose_config_str_with_schemas(content, None, &schemas),
+        vec![]
+    );
+
+    let invalid = r#"
+[plugins.config.complexity-analyzer]
+high_complexity_threshold = "high"
+report_format = "verbose"
+report_fromat = "summary"
+
+[plugins.config.complexity]
+"#;
+    let diagnostics = diagnose_config_str_with_schemas(invalid, None, &schemas);
+    let errors: Vec<_> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.code == "invalid-plugin-config")
+        .collect();
+    assert_eq!(errors.len(), 3);
+    assert!(errors
+        .iter()
+        .all(|error| error.severity == DiagnosticSeverity::Error));
+    assert_eq!(
+        errors[0].key.as_deref(),
+        Some("plugins.config.complexity-analyzer.high_complexity_threshold")
+    );
+    assert!(errors[0]
+        .message
+        .contains("should be integer, not a string"));
+    assert!(errors[1].message.contains("`\"summary\"`, `\"detailed\"`"));
+    assert!(errors[2].message.contains("did you mean `report_format`?"));
+    let unknown: Vec<_> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.code == "unknown-plugin")
+        .collect();
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].key.as_deref(), Some("plugins.config.complexity"));
 }
@@ -255,4 +255,111 @@ fn test_cross_reference_context() {
     println!("✅ Cross-reference context test passed!");
     println!("Reference context: scope='{}', is_definition={}", 
              call_ref.context.scope, call_ref.context.is_definition);
-}
\ No newline at end of file
+}
+#[test]
+fn test_method_body_references_are_tracked() {
+    let code = r#"
+        pub struct Counter {
+            value: i32,
+        }
+
+        impl Counter {
+            pub fn reset(&mut self) {
+                self.value = 0;
+            }
+
+            pub fn restart(&mut self) {
+                self.reset();
+            }
+        }
+    "#;
+
+    let syntax_tree = syn::parse_file(code).expect("Failed to parse test code");
+    let config = ExtractorConfig::default();
+    let mut visitor = CodeElementVisitor::new(PathBuf::from("counter.rs"), &config);
+    visitor.visit_file(&syntax_tree);
+    let (elements, cross_references) = visitor.into_elements_and_references();
+
+    let reset = elements.iter().find(|e| e.name == "reset").unwrap();
+    let restart = elements.iter().find(|e| e.name == "restart").unwrap();
+
+    let call = cross_references
+        .iter()
+        .find(|r| r.reference_text == "reset" && r.reference_type == ReferenceType::FunctionCall)
+        .expect("Should track method call inside a method body");
+    assert_eq!(call.from_element_id, restart.id);
+    assert_eq!(call.to_element_id, Some(reset.id.clone()));
+    assert_eq!(restart.dependencies, vec![reset.id.clone()]);
+}
+
+#[test]
+fn test_project_reference_graph_across_files() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(
+        src.join("config.rs"),
+        r#"
+        pub struct Settings {
+            pub verbose: bool,
+        }
+
+        impl Settings {
+            pub fn new() -> Self {
+                Settings { verbose: false }
+            }
+        }
+        "#,
+    )
+    .unwrap();
+    std::fs::write(
+        src.join("lib.rs"),
+        r#"
+        pub fn load() -> Settings {
+            let name = String::new();
+            Settings::new()
+        }
+        "#,
+    )
+    .unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), temp_dir.path().to_path_buf())
+        .extract_project()
+        .unwrap();
+
+    let elements: Vec<&CodeElement> = ast.files.iter().flat_map(|f| &f.elements).collect();
+    let settings = elements
+        .iter()
+        .find(|e| e.name == "Settings" && e.element_type == ElementType::Struct)
+        .unwrap();
+    let new_fn = elements.iter().find(|e| e.name == "new").unwrap();
+    let load = elements.iter().find(|e| e.name == "load").unwrap();
+
+    // IDs are namespaced by crate so they are unique across the project.
+    let namespace = format!("{}::", ast.project.name);
+    assert!(settings.id.starts_with(&namespace));
+    assert!(load.id.starts_with(&namespace));
+
+    let callees = ast.reference_graph.references_of(&load.id);
+    assert!(
+        callees.contains(&new_fn.id),
+        "load should reference Settings::new"
+    );
+    assert!(
+        callees.contains(&settings.id),
+        "load should reference Settings"
+    );
+    assert_eq!(load.dependencies, callees.to_vec());
+    assert!(ast
+        .reference_graph
+        .referenced_by(&new_fn.id)
+        .contains(&load.id));
+
+    // `String::new` must not be linked to `Settings::new`.
+    let string_new = ast
+        .cross_references
+        .iter()
+        .find(|r| r.reference_text.replace(' ', "") == "String::new")
+        .expect("Should track String::new call");
+    assert!(!string_new.is_resolved);
+}
@@ -0,0 +1,231 @@
+//! Tests for dependency discovery and dependency source extraction.
+
+#![allow(clippy::field_reassign_with_default)]
+
+use rustex_core::dependencies::{
+    read_declared_dependencies, read_lockfile_versions, CargoSettings,
+};
+use rustex_core::{AstExtractor, DependencyGraph, DependencyKind, ExtractorConfig, PackageRole};
+use std::fs;
+use tempfile::TempDir;
+
+const MANIFEST: &str = r#"[package]
+name = "consumer"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+fakedep = "1.0"
+renamed = { package = "real-name", version = "0.2" }
+
+[dev-dependencies]
+tempfile = "3.0"
+"#;
+
+const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "consumer"
+version = "0.1.0"
+
+[[package]]
+name = "fakedep"
+version = "1.0.3"
+
+[[package]]
+name = "real-name"
+version = "0.2.1"
+"#;
+
+#[test]
+fn test_read_declared_dependencies() {
+    let temp_dir = TempDir::new().unwrap();
+    let manifest = temp_dir.path().join("Cargo.toml");
+    fs::write(&manifest, MANIFEST).unwrap();
+
+    let declared = read_declared_dependencies(&manifest).unwrap();
+    assert_eq!(declared.normal, vec!["fakedep", "real-name"]);
+    assert_eq!(declared.dev, vec!["tempfile"]);
+    assert!(declared.build.is_empty());
+}
+
+#[test]
+fn test_read_lockfile_versions() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+
+    let versions = read_lockfile_versions(temp_dir.path());
+    assert_eq!(versions.get("fakedep"), Some(&vec!["1.0.3".to_string()]));
+    assert_eq!(versions.get("real-name"), Some(&vec!["0.2.1".to_string()]));
+    assert!(read_lockfile_versions(&temp_dir.path().join("missing")).is_empty());
+}
+
+#[test]
+fn test_dependency_sources_extracted_from_registry() {
+    let project = TempDir::new().unwrap();
+    fs::write(project.path().join("Cargo.toml"), MANIFEST).unwrap();
+    fs::write(project.path().join("Cargo.lock"), LOCKFILE).unwrap();
+    fs::create_dir_all(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/lib.rs"), "pub fn consume() {}\n").unwrap();
+
+    // Fake CARGO_HOME with one unpacked registry crate.
+    let cargo_home = TempDir::new().unwrap();
+    let crate_dir = cargo_home
+        .path()
+        .join("registry/src/index.crates.io-0000000000000000/fakedep-1.0.3");
+    fs::create_dir_all(crate_dir.join("src")).unwrap();
+    fs::write(
+        crate_dir.join("Cargo.toml"),
+        "[package]\nname = \"fakedep\"\nversion = \"1.0.3\"\n",
+    )
+    .unwrap();
+    fs::write(
+        crate_dir.join("src/lib.rs"),
+        "/// Public API.\npub fn exported() {}\nfn hidden() {}\npub struct Handle;\n",
+    )
+    .unwrap();
+
+    // Keep `cargo metadata` from reaching the network so the registry fallback is used.
+    let cargo = CargoSettings {
+        home: Some(cargo_home.path().to_path_buf()),
+        offline: true,
+    };
+
+    let mut config = ExtractorConfig::default();
+    config.parse_dependencies = true;
+    let extractor = AstExtractor::new(config, project.path().to_path_buf()).with_cargo(cargo);
+    let ast = extractor.extract_project().unwrap();
+
+    assert_eq!(ast.dependencies.direct, vec!["fakedep", "real-name"]);
+    assert_eq!(
+        ast.dependencies_ast.len(),
+        1,
+        "only fakedep has sources available"
+    );
+
+    let dep = &ast.dependencies_ast[0];
+    assert_eq!(dep.name, "fakedep");
+    assert_eq!(dep.version, "1.0.3");
+
+    let names: Vec<&str> = dep
+        .files
+        .iter()
+        .flat_map(|f| &f.elements)
+        .map(|e| e.name.as_str())
+        .collect();
+    assert!(names.contains(&"exported"));
+    assert!(names.contains(&"Handle"));
+    assert!(
+        !names.contains(&"hidden"),
+        "private items must not be extracted"
+    );
+}
+
+#[test]
+fn test_dependency_graph_from_lockfile() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), MANIFEST).unwrap();
+    fs::write(
+        temp_dir.path().join("Cargo.lock"),
+        r#"version = 3
+
+[[package]]
+name = "consumer"
+version = "0.1.0"
+dependencies = ["fakedep", "real-name", "tempfile"]
+
+[[package]]
+name = "fakedep"
+version = "1.0.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["shared 1.0.0"]
+
+[[package]]
+name = "real-name"
+version = "0.2.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["shared 2.0.0"]
+
+[[package]]
+name = "shared"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "shared"
+version = "2.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "tempfile"
+version = "3.8.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "unused"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+    )
+    .unwrap();
+
+    let graph = DependencyGraph::from_project(temp_dir.path()).unwrap();
+    let names = |role| {
+        graph
+            .packages_with_role(role)
+            .map(|package| package.id.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(PackageRole::Root), vec!["consumer 0.1.0"]);
+    assert_eq!(
+        names(PackageRole::Direct),
+        vec!["fakedep 1.0.3", "real-name 0.2.1", "tempfile 3.8.0"]
+    );
+    assert_eq!(
+        names(PackageRole::Transitive),
+        vec!["shared 1.0.0", "shared 2.0.0"]
+    );
+
+    let tempfile_edge = graph
+        .edges
+        .iter()
+        .find(|edge| edge.to == "tempfile 3.8.0")
+        .unwrap();
+    assert_eq!(tempfile_edge.kind, DependencyKind::Dev);
+    assert_eq!(graph.duplicates.len(), 1);
+    assert_eq!(graph.duplicates[0].name, "shared");
+    assert_eq!(graph.duplicates[0].versions, vec!["1.0.0", "2.0.0"]);
+
+    // Extraction reports the transitive dependencies by name.
+    let ast = AstExtractor::new(ExtractorConfig::default(), temp_dir.path().to_path_buf())
+        .extract_project()
+        .unwrap();
+    assert_eq!(ast.dependencies.transitive, vec!["shared"]);
+}
+
+#[test]
+fn test_dependency_graph_of_workspace_member() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"consumer\"]\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("consumer/src")).unwrap();
+    fs::write(root.join("consumer/Cargo.toml"), MANIFEST).unwrap();
+    fs::write(root.join("consumer/src/lib.rs"), "").unwrap();
+    fs::write(root.join("Cargo.lock"), LOCKFILE).unwrap();
+
+    // The lockfile is found at the workspace root.
+    let graph = DependencyGraph::from_project(&root.join("consumer")).unwrap();
+    let roots: Vec<&str> = graph
+        .packages_with_role(PackageRole::Root)
+        .map(|package| package.id.as_str())
+        .collect();
+    assert_eq!(roots, vec!["consumer 0.1.0"]);
+
+    let other = TempDir::new().unwrap();
+    let error = DependencyGraph::from_project(other.path()).unwrap_err();
+    assert!(error.to_string().contains("No Cargo.lock found"));
+}
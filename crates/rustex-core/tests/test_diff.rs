@@ -0,0 +1,117 @@
+//! Tests for comparing two extractions of a project.
+
+use rustex_core::diff::{diff_projects, ElementChange};
+use rustex_core::{AstExtractor, ElementType, ExtractorConfig, ProjectAst, Visibility};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+/// Extract a single-crate project made of `files`.
+fn extract(files: &[(&str, &str)]) -> ProjectAst {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"diffed\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    let config = ExtractorConfig {
+        include_private: true,
+        ..ExtractorConfig::default()
+    };
+    AstExtractor::new(config, root.to_path_buf())
+        .extract_project()
+        .unwrap()
+}
+
+#[test]
+fn test_diff_identical_projects_is_empty() {
+    let files = [(
+        "src/lib.rs",
+        "/// Adds.\npub fn add(a: u32, b: u32) -> u32 { a + b }\n",
+    )];
+    let diff = diff_projects(&extract(&files), &extract(&files));
+    assert!(diff.is_empty(), "{:?}", diff);
+}
+
+#[test]
+fn test_diff_reports_added_removed_and_changed_elements() {
+    let old = extract(&[(
+        "src/lib.rs",
+        r#"
+/// Adds two numbers.
+pub fn add(a: u32, b: u32) -> u32 { a + b }
+
+/// Old docs.
+pub struct Config;
+
+fn legacy() {}
+
+pub fn stays() {}
+"#,
+    )]);
+    let new = extract(&[
+        (
+            "src/lib.rs",
+            r#"
+/// Adds two numbers.
+pub fn add(a: u64, b: u64) -> u64 { a + b }
+
+/// New docs.
+pub struct Config;
+
+pub fn fresh() {}
+"#,
+        ),
+        ("src/util.rs", "pub fn stays() {}\n"),
+    ]);
+
+    let diff = diff_projects(&old, &new);
+
+    let added: Vec<&str> = diff
+        .added
+        .iter()
+        .map(|e| e.qualified_name.as_str())
+        .collect();
+    let removed: Vec<&str> = diff
+        .removed
+        .iter()
+        .map(|e| e.qualified_name.as_str())
+        .collect();
+    // `stays` moved modules, so it is a different item.
+    assert_eq!(added, ["crate::fresh", "crate::util::stays"]);
+    assert_eq!(removed, ["crate::legacy", "crate::stays"]);
+    assert_eq!(diff.removed[0].visibility, Visibility::Private);
+    assert_eq!(diff.added[1].file_path, PathBuf::from("src/util.rs"));
+
+    assert_eq!(diff.changed.len(), 2);
+    let add = &diff.changed[0];
+    assert_eq!(add.qualified_name, "crate::add");
+    assert_eq!(add.element_type, ElementType::Function);
+    assert_ne!(add.old_id, add.new_id, "signature is part of the stable ID");
+    assert!(matches!(
+        &add.changes[..],
+        [ElementChange::Signature { old: Some(old), new: Some(new) }]
+            if old.contains("u32") && new.contains("u64")
+    ));
+
+    let config = &diff.changed[1];
+    assert_eq!(config.old_id, config.new_id);
+    assert!(matches!(
+        &config.changes[..],
+        [ElementChange::Documentation { old, new }] if old != new
+    ));
+
+    // The diff round-trips through JSON for the CLI.
+    let json = serde_json::to_string(&diff).unwrap();
+    assert_eq!(
+        serde_json::from_str::<rustex_core::diff::AstDiff>(&json).unwrap(),
+        diff
+    );
+}
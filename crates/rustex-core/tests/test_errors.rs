@@ -1,5 +1,7 @@
 //! Tests for error handling and recovery mechanisms.
 
+#![allow(clippy::field_reassign_with_default)]
+
 use rustex_core::{AstExtractor, ExtractorConfig, FileProcessingError, RustExError};
 use std::fs;
 use std::path::PathBuf;
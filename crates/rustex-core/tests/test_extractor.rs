@@ -1,8 +1,15 @@
 //! Integration tests for the AST extractor.
 
-use rustex_core::{AstExtractor, ElementType, ExtractorConfig, Visibility};
+#![allow(clippy::field_reassign_with_default)]
+
+use rustex_core::{
+    check_thresholds, fit_to_budget, split_project, AstExtractor, CallKind, ElementType,
+    ExtractorConfig, FileKind, IncrementalExtractor, MetricsReport, OutputPart, PluginResults,
+    ProjectAst, RustExError, SkipReason, SplitStrategy, ThresholdConfig, TruncationPolicy,
+    Visibility,
+};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 /// Create a temporary test project with sample Rust files.
@@ -537,3 +544,1499 @@ async fn test_complexity_calculation() {
         "All functions should have complexity >= 1"
     );
 }
+
+#[test]
+fn test_parallel_extraction_is_deterministic() {
+    let (_temp_dir, project_path) = create_test_project();
+    for i in 0..8 {
+        fs::write(
+            project_path.join(format!("src/module_{}.rs", i)),
+            format!("pub fn function_{}() -> usize {{\n    {}\n}}\n", i, i),
+        )
+        .expect("Failed to write module");
+    }
+
+    let extract = |workers: usize| {
+        let mut config = ExtractorConfig::default();
+        config.parallel_workers = workers;
+        AstExtractor::new(config, project_path.clone())
+            .extract_project()
+            .unwrap()
+    };
+
+    let sequential = extract(1);
+    let parallel = extract(4);
+
+    let paths = |ast: &rustex_core::ProjectAst| -> Vec<PathBuf> {
+        ast.files.iter().map(|f| f.relative_path.clone()).collect()
+    };
+    assert_eq!(paths(&sequential), paths(&parallel));
+
+    let mut sorted = paths(&sequential);
+    sorted.sort();
+    assert_eq!(
+        paths(&sequential),
+        sorted,
+        "Files should be in sorted order"
+    );
+
+    assert_eq!(
+        sequential.metrics.total_functions,
+        parallel.metrics.total_functions
+    );
+}
+
+#[test]
+fn test_discovery_respects_ignore_files() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::create_dir_all(project_path.join("src/generated")).unwrap();
+    fs::create_dir_all(project_path.join("src/vendor")).unwrap();
+    fs::write(
+        project_path.join("src/generated/bindings.rs"),
+        "pub fn bound() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        project_path.join("src/vendor/lib.rs"),
+        "pub fn vendored() {}\n",
+    )
+    .unwrap();
+    fs::write(project_path.join(".gitignore"), "src/generated/\n").unwrap();
+    fs::write(project_path.join(".rustexignore"), "vendor/\n").unwrap();
+
+    let extract = |respect_gitignore: bool| {
+        let mut config = ExtractorConfig::default();
+        config.respect_gitignore = respect_gitignore;
+        let ast = AstExtractor::new(config, project_path.clone())
+            .extract_project()
+            .unwrap();
+        ast.files
+            .iter()
+            .map(|f| f.relative_path.clone())
+            .collect::<Vec<_>>()
+    };
+
+    // The project is not a git repository; .gitignore is honored regardless.
+    let files = extract(true);
+    assert!(!files.contains(&PathBuf::from("src/generated/bindings.rs")));
+    assert!(!files.contains(&PathBuf::from("src/vendor/lib.rs")));
+    assert!(files.contains(&PathBuf::from("src/main.rs")));
+
+    // Opting out only disables .gitignore/.ignore; .rustexignore still applies.
+    let files = extract(false);
+    assert!(files.contains(&PathBuf::from("src/generated/bindings.rs")));
+    assert!(!files.contains(&PathBuf::from("src/vendor/lib.rs")));
+}
+
+#[test]
+fn test_element_ids_are_stable() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::create_dir_all(project_path.join("src/bin")).unwrap();
+    fs::write(project_path.join("src/bin/tool.rs"), "pub fn main() {}\n").unwrap();
+
+    let element_ids = || {
+        let ast = AstExtractor::new(ExtractorConfig::default(), project_path.clone())
+            .extract_project()
+            .unwrap();
+        ast.files
+            .iter()
+            .flat_map(|f| &f.elements)
+            .map(|e| (e.hierarchy.qualified_name.clone(), e.id.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    let before = element_ids();
+    assert_eq!(before, element_ids());
+    assert!(before
+        .iter()
+        .all(|(_, id)| id.starts_with("test-project::")));
+
+    // Both binaries define `crate::main`, but their IDs stay distinct.
+    let mut ids: Vec<&String> = before.iter().map(|(_, id)| id).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), before.len());
+
+    // Adding an unrelated item doesn't renumber the existing ones.
+    let lib_path = project_path.join("src/lib.rs");
+    let lib = fs::read_to_string(&lib_path).unwrap().replacen(
+        "\n\n",
+        "\n\npub fn inserted_first() {}\n\n",
+        1,
+    );
+    fs::write(&lib_path, lib).unwrap();
+    let after = element_ids();
+    for entry in &before {
+        assert!(after.contains(entry), "{:?} changed", entry);
+    }
+}
+
+#[test]
+fn test_recovers_valid_items_from_files_with_syntax_errors() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(
+        project_path.join("src/broken.rs"),
+        r#"//! Partly broken module.
+
+/// Still extracted.
+pub fn before() {}
+
+pub fn broken( {
+    let x = ;
+}
+
+#[derive(Debug)]
+pub struct After {
+    pub field: u32,
+}
+"#,
+    )
+    .unwrap();
+
+    let extract = |recover_parse_errors: bool| {
+        let mut config = ExtractorConfig::default();
+        config.recover_parse_errors = recover_parse_errors;
+        AstExtractor::new(config, project_path.clone())
+            .extract_project()
+            .unwrap()
+    };
+
+    let ast = extract(true);
+    let broken = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path.as_path() == Path::new("src/broken.rs"))
+        .expect("file with syntax errors is still extracted");
+    let names: Vec<&str> = broken.elements.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["before", "After"]);
+    assert_eq!(broken.elements[0].doc_comments.len(), 1);
+    // Items after the broken one keep their line numbers in the file.
+    assert_eq!(broken.elements[1].location.line_start, 10);
+    assert_eq!(broken.elements[1].location.line_end, 13);
+    assert_eq!(
+        broken.elements[1]
+            .name_location
+            .as_ref()
+            .unwrap()
+            .line_start,
+        11
+    );
+    assert_eq!(broken.parse_errors.len(), 1);
+    assert_eq!(broken.parse_errors[0].line_start, 6);
+    assert_eq!(broken.parse_errors[0].line_end, 8);
+    assert!(ast
+        .files
+        .iter()
+        .filter(|f| f.relative_path != broken.relative_path)
+        .all(|f| f.parse_errors.is_empty()));
+
+    // Without recovery the whole file fails and is left out.
+    let ast = extract(false);
+    assert!(ast
+        .files
+        .iter()
+        .all(|f| f.relative_path.as_path() != Path::new("src/broken.rs")));
+}
+
+#[test]
+fn test_extract_iter_matches_extract_project() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::create_dir_all(project_path.join("src/bin")).unwrap();
+    // Enough binaries sharing `crate::main` to span several batches.
+    for n in 0..6 {
+        fs::write(
+            project_path.join(format!("src/bin/tool{}.rs", n)),
+            "pub fn main() {}\n",
+        )
+        .unwrap();
+    }
+
+    let mut config = ExtractorConfig::default();
+    config.parallel_workers = 1;
+    let extractor = AstExtractor::new(config, project_path.clone());
+    let project = extractor.extract_project().unwrap();
+
+    let iter = extractor.extract_iter().unwrap();
+    assert_eq!(iter.project().name, "test-project");
+    assert_eq!(iter.len(), project.files.len());
+    let streamed: Vec<_> = iter.map(Result::unwrap).collect();
+
+    let ids = |files: &[rustex_core::FileAst]| {
+        files
+            .iter()
+            .map(|f| {
+                let ids: Vec<String> = f.elements.iter().map(|e| e.id.clone()).collect();
+                (f.relative_path.clone(), ids)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&streamed), ids(&project.files));
+}
+
+#[test]
+fn test_decodes_bom_prefixed_and_non_utf8_sources() {
+    let (_temp_dir, project_path) = create_test_project();
+    let mut bom = b"\xEF\xBB\xBF".to_vec();
+    bom.extend_from_slice(b"pub fn with_bom() {}\n");
+    fs::write(project_path.join("src/bom.rs"), bom).unwrap();
+    // "// caf\xE9" is a latin-1 comment, which isn't valid UTF-8.
+    fs::write(
+        project_path.join("src/latin1.rs"),
+        b"// caf\xE9\npub fn with_latin1_comment() {}\n",
+    )
+    .unwrap();
+    let mut bom_latin1 = b"\xEF\xBB\xBF".to_vec();
+    bom_latin1.extend_from_slice(b"// caf\xE9\npub fn after_bom() {}\n");
+    fs::write(project_path.join("src/bom_latin1.rs"), bom_latin1).unwrap();
+
+    let extract = |encoding_fallback: bool| {
+        let mut config = ExtractorConfig::default();
+        config.encoding_fallback = encoding_fallback;
+        AstExtractor::new(config, project_path.clone()).extract_project()
+    };
+
+    let ast = extract(true).unwrap();
+    let file = |name: &str| {
+        ast.files
+            .iter()
+            .find(|f| f.relative_path.as_path() == Path::new(name))
+            .unwrap()
+    };
+    assert_eq!(file("src/bom.rs").elements[0].name, "with_bom");
+    assert!(file("src/bom.rs").warnings.is_empty());
+    let latin1 = file("src/latin1.rs");
+    assert_eq!(latin1.elements[0].name, "with_latin1_comment");
+    assert_eq!(latin1.warnings.len(), 1);
+    assert!(latin1.warnings[0].contains("invalid UTF-8 at byte 6"));
+    // Offsets count the BOM, so they point into the file as stored.
+    assert!(file("src/bom_latin1.rs").warnings[0].contains("invalid UTF-8 at byte 9"));
+
+    // Without the fallback the file fails, but the rest of the project is extracted.
+    let ast = extract(false).unwrap();
+    assert!(ast
+        .files
+        .iter()
+        .all(|f| f.relative_path.as_path() != Path::new("src/latin1.rs")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlinks_are_followed_once_and_cycles_terminate() {
+    use std::os::unix::fs::symlink;
+
+    let (_temp_dir, project_path) = create_test_project();
+    let src = project_path.join("src");
+    fs::create_dir_all(src.join("nested")).unwrap();
+    fs::write(src.join("nested/real.rs"), "pub fn real() {}\n").unwrap();
+    fs::write(src.join("shared.rs"), "pub fn shared() {}\n").unwrap();
+    symlink(src.join("nested"), src.join("alias")).unwrap();
+    symlink(&src, src.join("nested/loop")).unwrap();
+    symlink(src.join("shared.rs"), src.join("shared_link.rs")).unwrap();
+
+    let paths = |follow_symlinks: bool| {
+        let mut config = ExtractorConfig::default();
+        config.follow_symlinks = follow_symlinks;
+        let ast = AstExtractor::new(config, project_path.clone())
+            .extract_project()
+            .unwrap();
+        ast.files
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect::<Vec<_>>()
+    };
+
+    let unfollowed = paths(false);
+    assert!(unfollowed.contains(&PathBuf::from("src/nested/real.rs")));
+    assert!(!unfollowed.contains(&PathBuf::from("src/shared_link.rs")));
+    assert!(unfollowed.iter().all(|p| !p.starts_with("src/alias")));
+
+    // Each file is extracted once, through its first path in sorted order.
+    let followed = paths(true);
+    let count = |name: &str| followed.iter().filter(|p| p.ends_with(name)).count();
+    assert_eq!(count("real.rs"), 1);
+    assert_eq!(count("shared.rs") + count("shared_link.rs"), 1);
+    assert!(followed.contains(&PathBuf::from("src/alias/real.rs")));
+    assert_eq!(followed.len(), unfollowed.len());
+}
+
+#[test]
+fn test_inline_comments_are_attached_to_elements() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(
+        project_path.join("src/commented.rs"),
+        r##"//! Module docs are not inline comments.
+
+// Explains `parse`.
+/// Parses input.
+pub fn parse(input: &str) -> usize {
+    // Count the bytes.
+    let marker = "// not a comment";
+    let quote = '\'';
+    /* block
+       comment */
+    input.len() + marker.len() + quote.len_utf8()
+}
+
+pub struct Parser;
+
+impl Parser {
+    pub fn run(&self) {
+        let raw = r#"/* still a string */"#;
+        let _ = raw; // trailing
+    }
+}
+
+// Stray comment separated by a blank line.
+
+pub fn other() {}
+"##,
+    )
+    .unwrap();
+
+    let extract = |include_inline_comments: bool| {
+        let mut config = ExtractorConfig::default();
+        config.include_inline_comments = include_inline_comments;
+        AstExtractor::new(config, project_path.clone())
+            .extract_project()
+            .unwrap()
+    };
+
+    let ast = extract(true);
+    let file = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path.as_path() == Path::new("src/commented.rs"))
+        .unwrap();
+    let comments = |name: &str, element_type: ElementType| {
+        let element = file
+            .elements
+            .iter()
+            .find(|e| e.name == name && e.element_type == element_type)
+            .unwrap();
+        element
+            .inline_comments
+            .iter()
+            .map(|c| (c.text.as_str(), c.line_start, c.line_end))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        comments("parse", ElementType::Function),
+        [
+            ("Explains `parse`.", 3, 3),
+            ("Count the bytes.", 6, 6),
+            ("block\n       comment", 9, 10),
+        ]
+    );
+    // The method is the innermost element around its comment.
+    assert_eq!(
+        comments("run", ElementType::Function),
+        [("trailing", 19, 19)]
+    );
+    assert!(comments("impl Parser", ElementType::Impl).is_empty());
+    assert!(comments("other", ElementType::Function).is_empty());
+
+    let ast = extract(false);
+    assert!(ast
+        .files
+        .iter()
+        .flat_map(|f| &f.elements)
+        .all(|e| e.inline_comments.is_empty()));
+}
+
+#[test]
+fn test_deprecations_are_summarized_with_callers() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(
+        project_path.join("src/legacy.rs"),
+        r#"
+#[deprecated(since = "0.2.0", note = "use `modern` instead")]
+pub fn old_api() {}
+
+#[deprecated = "use `Modern`"]
+pub struct OldType;
+
+#[deprecated]
+pub fn unused() {}
+
+pub fn modern() {}
+
+pub fn caller() {
+    old_api();
+}
+"#,
+    )
+    .unwrap();
+
+    let mut config = ExtractorConfig::default();
+    config.include_private = true;
+    let ast = AstExtractor::new(config, project_path)
+        .extract_project()
+        .unwrap();
+
+    let element = |name: &str| {
+        ast.files
+            .iter()
+            .flat_map(|f| &f.elements)
+            .find(|e| e.name == name)
+            .unwrap()
+    };
+    let old_api = element("old_api").deprecation.as_ref().unwrap();
+    assert_eq!(old_api.since.as_deref(), Some("0.2.0"));
+    assert_eq!(old_api.note.as_deref(), Some("use `modern` instead"));
+    assert_eq!(
+        element("OldType")
+            .deprecation
+            .as_ref()
+            .unwrap()
+            .note
+            .as_deref(),
+        Some("use `Modern`")
+    );
+    assert!(element("modern").deprecation.is_none());
+
+    let summary = &ast.metrics.deprecations;
+    assert_eq!(summary.total_deprecated, 3);
+    assert_eq!(summary.total_usages, 1);
+    let old_api = summary
+        .items
+        .iter()
+        .find(|item| item.qualified_name == "crate::legacy::old_api")
+        .unwrap();
+    assert_eq!(old_api.callers, vec!["crate::legacy::caller"]);
+}
+
+#[test]
+fn test_scoped_visibilities_and_visibility_filter() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(
+        project_path.join("src/scopes.rs"),
+        r#"
+pub fn open() {}
+pub(crate) fn crate_wide() {}
+pub(super) fn parent_only() {}
+pub(in crate::scopes) fn in_path() {}
+pub(self) fn self_only() {}
+
+mod hidden {
+    pub(crate) fn reachable() {}
+    fn unreachable() {}
+}
+"#,
+    )
+    .unwrap();
+
+    let mut config = ExtractorConfig::default();
+    config.include_private = true;
+    let ast = AstExtractor::new(config, project_path.clone())
+        .extract_project()
+        .unwrap();
+    let visibility = |name: &str| {
+        ast.files
+            .iter()
+            .flat_map(|f| &f.elements)
+            .find(|e| e.name == name)
+            .map(|e| e.visibility.clone())
+            .unwrap()
+    };
+    assert_eq!(visibility("open"), Visibility::Public);
+    assert_eq!(visibility("crate_wide"), Visibility::Crate);
+    assert_eq!(visibility("parent_only"), Visibility::Super);
+    assert_eq!(
+        visibility("in_path"),
+        Visibility::Restricted("crate::scopes".to_string())
+    );
+    assert_eq!(visibility("self_only"), Visibility::Private);
+
+    let mut config = ExtractorConfig::default();
+    config.filters.visibility = vec!["pub(crate)".to_string()];
+    let ast = AstExtractor::new(config, project_path)
+        .extract_project()
+        .unwrap();
+    let mut names: Vec<&str> = ast
+        .files
+        .iter()
+        .flat_map(|f| &f.elements)
+        .map(|e| e.name.as_str())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["crate_wide", "reachable"]);
+}
+
+#[test]
+fn test_build_scripts_and_proc_macro_crates_are_classified() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(
+        project_path.join("build.rs"),
+        "fn main() {\n    println!(\"cargo:rerun-if-changed=build.rs\");\n}\n",
+    )
+    .unwrap();
+
+    let mut config = ExtractorConfig::default();
+    config.include_private = true;
+    let ast = AstExtractor::new(config, project_path.clone())
+        .extract_project()
+        .unwrap();
+    let build_script = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path == Path::new("build.rs"))
+        .expect("build.rs should be extracted");
+    assert_eq!(build_script.kind, FileKind::BuildScript);
+    let main = &build_script.elements[0];
+    assert_eq!(main.hierarchy.qualified_name, "crate::main");
+    assert_eq!(main.file_kind(), FileKind::BuildScript);
+    assert!(ast
+        .files
+        .iter()
+        .filter(|f| f.relative_path != Path::new("build.rs"))
+        .all(|f| f.kind == FileKind::Source));
+
+    let mut config = ExtractorConfig::default();
+    config.filters.exclude_build_scripts = true;
+    config.filters.include = vec!["**/*.rs".to_string()];
+    let ast = AstExtractor::new(config, project_path.clone())
+        .extract_project()
+        .unwrap();
+    assert!(ast.files.iter().all(|f| f.kind != FileKind::BuildScript));
+
+    let mut manifest = fs::read_to_string(project_path.join("Cargo.toml")).unwrap();
+    manifest.push_str("\n[lib]\nproc-macro = true\n");
+    fs::write(project_path.join("Cargo.toml"), manifest).unwrap();
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let lib = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path == Path::new("src/lib.rs"))
+        .unwrap();
+    assert_eq!(lib.kind, FileKind::ProcMacro);
+    assert!(lib
+        .elements
+        .iter()
+        .all(|e| e.file_kind() == FileKind::ProcMacro));
+}
+
+#[test]
+fn test_byte_ranges_slice_the_original_file() {
+    let (_temp_dir, project_path) = create_test_project();
+    let source = concat!(
+        "\u{feff}// größe: ünïcödé 🦀\n",
+        "pub struct Größe { pub crab: [char; 2] }\n",
+        "fn broken( {\n",
+        "\n",
+        "pub fn après() -> usize { \"é\".len() }\n",
+    );
+    fs::write(project_path.join("src/bytes.rs"), source).unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let file = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path.ends_with("bytes.rs"))
+        .unwrap();
+    let slice = |name: &str| {
+        let location = &file
+            .elements
+            .iter()
+            .find(|e| e.name == name)
+            .unwrap()
+            .location;
+        &source.as_bytes()[location.byte_start..location.byte_end]
+    };
+    assert_eq!(
+        slice("Größe"),
+        "pub struct Größe { pub crab: [char; 2] }".as_bytes()
+    );
+    assert_eq!(
+        slice("après"),
+        "pub fn après() -> usize { \"é\".len() }".as_bytes()
+    );
+}
+
+#[test]
+fn test_name_locations_point_at_names() {
+    let (_temp_dir, project_path) = create_test_project();
+    let source = concat!(
+        "/// Größe of a crab\n",
+        "pub struct Größe;\n",
+        "\n",
+        "impl Größe {\n",
+        "    pub fn new() -> Self { Größe }\n",
+        "}\n",
+    );
+    fs::write(project_path.join("src/names.rs"), source).unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let file = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path.ends_with("names.rs"))
+        .unwrap();
+    let name_location = |name: &str| {
+        let element = file.elements.iter().find(|e| e.name == name).unwrap();
+        element.name_location.clone()
+    };
+
+    let location = name_location("Größe").unwrap();
+    assert_eq!(
+        (location.line_start, location.char_start, location.char_end),
+        (2, 11, 16)
+    );
+    assert_eq!(&source[location.byte_start..location.byte_end], "Größe");
+    let location = name_location("new").unwrap();
+    assert_eq!((location.line_start, location.char_start), (5, 11));
+    assert_eq!(&source[location.byte_start..location.byte_end], "new");
+    // Impl blocks have no name of their own.
+    let impl_block = file
+        .elements
+        .iter()
+        .find(|e| e.element_type == ElementType::Impl)
+        .unwrap();
+    assert!(impl_block.name_location.is_none());
+}
+
+#[test]
+fn test_function_call_lists_are_recorded() {
+    let (_temp_dir, project_path) = create_test_project();
+    let source = r#"
+pub fn run() -> Vec<i32> {
+    let mut v = Vec::new();
+    helper();
+    v.push(1);
+    println!("{}", compute());
+    v
+}
+
+pub struct Runner;
+
+impl Runner {
+    pub fn go(&self) {
+        run().len();
+    }
+}
+"#;
+    fs::write(project_path.join("src/calls.rs"), source).unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let file = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path.ends_with("calls.rs"))
+        .unwrap();
+    let calls = |name: &str| {
+        let element = file.elements.iter().find(|e| e.name == name).unwrap();
+        element
+            .calls
+            .iter()
+            .map(|call| (call.name.clone(), call.kind, call.line))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        calls("run"),
+        vec![
+            ("Vec::new".to_string(), CallKind::Function, 3),
+            ("helper".to_string(), CallKind::Function, 4),
+            ("push".to_string(), CallKind::Method, 5),
+            ("println".to_string(), CallKind::Macro, 6),
+            ("compute".to_string(), CallKind::Function, 6),
+        ]
+    );
+    assert_eq!(
+        calls("go"),
+        vec![
+            ("len".to_string(), CallKind::Method, 14),
+            ("run".to_string(), CallKind::Function, 14),
+        ]
+    );
+    assert!(calls("Runner").is_empty());
+}
+
+#[test]
+fn test_closures_and_async_blocks_are_extracted() {
+    let (_temp_dir, project_path) = create_test_project();
+    let source = r#"
+pub fn schedule(items: Vec<u32>, limit: u32) -> usize {
+    let offset = 1;
+    let keep = |x: &u32| *x + offset < limit;
+    let task = async move {
+        items.iter().filter(|x| keep(x)).count()
+    };
+    drop(task);
+    0
+}
+"#;
+    fs::write(project_path.join("src/tasks.rs"), source).unwrap();
+
+    let extract = |extract_closures: bool| {
+        let mut config = ExtractorConfig::default();
+        config.extract_closures = extract_closures;
+        let ast = AstExtractor::new(config, project_path.clone())
+            .extract_project()
+            .unwrap();
+        ast.files
+            .into_iter()
+            .find(|f| f.relative_path.ends_with("tasks.rs"))
+            .unwrap()
+    };
+
+    // Closures are always counted in their function's metrics.
+    let file = extract(false);
+    assert_eq!(file.elements.len(), 1);
+    let metrics = file.elements[0].complexity_metrics.as_ref().unwrap();
+    assert_eq!(metrics.closure_count, 2);
+    assert_eq!(metrics.async_block_count, 1);
+
+    let file = extract(true);
+    let schedule = file.elements.iter().find(|e| e.name == "schedule").unwrap();
+    let find = |name: &str| file.elements.iter().find(|e| e.name == name).unwrap();
+
+    let keep = find("{closure#0}");
+    assert_eq!(keep.element_type, ElementType::Closure);
+    assert_eq!(keep.signature.as_deref(), Some("| x : & u32 |"));
+    assert_eq!(keep.captures(), vec!["offset", "limit"]);
+    assert_eq!(keep.hierarchy.parent_id.as_ref(), Some(&schedule.id));
+    assert_eq!(
+        keep.hierarchy.qualified_name,
+        "crate::tasks::schedule::{closure#0}"
+    );
+    assert_eq!(keep.location.line_start, 4);
+    assert_eq!(keep.complexity_metrics.as_ref().unwrap().parameter_count, 1);
+
+    let task = find("{async block#0}");
+    assert_eq!(task.element_type, ElementType::AsyncBlock);
+    assert_eq!(task.signature.as_deref(), Some("async move"));
+    assert_eq!(task.captures(), vec!["items", "keep"]);
+    assert_eq!(task.hierarchy.parent_id.as_ref(), Some(&schedule.id));
+
+    // Closures nested in the async block belong to it.
+    let filter = find("{closure#1}");
+    assert_eq!(filter.hierarchy.parent_id.as_ref(), Some(&task.id));
+    assert_eq!(filter.captures(), vec!["keep"]);
+    assert!(schedule.calls.iter().any(|call| call.name == "keep"));
+}
+
+#[test]
+fn test_ownership_profile_counts_borrow_patterns() {
+    let (_temp_dir, project_path) = create_test_project();
+    let source = r#"
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub struct Cache<'a> {
+    name: &'a str,
+    shared: Rc<RefCell<Vec<String>>>,
+}
+
+impl<'a> Cache<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn push(&mut self, item: &str, extra: &mut Vec<String>) {
+        'outer: for _ in 0..1 {
+            self.shared.borrow_mut().push(item.to_string());
+            extra.push(item.to_string().clone());
+            break 'outer;
+        }
+    }
+}
+
+pub fn share(data: Vec<u8>) -> Arc<Vec<u8>> {
+    let shared = Arc::new(data);
+    let copy = shared.clone();
+    assert_eq!(copy.clone(), shared);
+    copy
+}
+"#;
+    fs::write(project_path.join("src/cache.rs"), source).unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let file = ast
+        .files
+        .iter()
+        .find(|f| f.relative_path.ends_with("cache.rs"))
+        .unwrap();
+    let ownership = &file.file_metrics.ownership;
+
+    // `'a` in the struct generics and field, the impl generics and self type,
+    // and the return type; loop labels don't count.
+    assert_eq!(ownership.lifetime_annotations, 5);
+    assert_eq!(ownership.shared_ref_params, 2);
+    assert_eq!(ownership.mut_ref_params, 2);
+    assert_eq!(ownership.arc_usages, 2);
+    assert_eq!(ownership.rc_usages, 1);
+    assert_eq!(ownership.refcell_usages, 1);
+    assert_eq!(ownership.clone_calls, 3);
+    let expected_density = 300.0 / file.file_metrics.lines_of_code as f64;
+    assert!((ownership.clone_density - expected_density).abs() < 1e-9);
+
+    let project = &ast.metrics.ownership;
+    let total_clones: usize = ast
+        .files
+        .iter()
+        .map(|f| f.file_metrics.ownership.clone_calls)
+        .sum();
+    assert_eq!(project.clone_calls, total_clones);
+    let expected_density = total_clones as f64 * 100.0 / ast.metrics.total_lines as f64;
+    assert!((project.clone_density - expected_density).abs() < 1e-9);
+}
+
+#[test]
+fn test_metrics_report_counts_unsafe_docs_and_hotspots() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_path = temp_dir.path().to_path_buf();
+    fs::create_dir_all(project_path.join("src")).unwrap();
+    let source = r#"
+/// Reads a raw pointer.
+pub unsafe fn read(ptr: *const u8) -> u8 {
+    *ptr
+}
+
+pub fn first(bytes: &[u8]) -> u8 {
+    let value = unsafe { read(bytes.as_ptr()) };
+    if value > 10 {
+        if value > 20 {
+            return 2;
+        }
+        return 1;
+    }
+    debug_assert!(unsafe { read(bytes.as_ptr()) } == value);
+    0
+}
+
+pub struct Handle(*mut u8);
+
+unsafe impl Send for Handle {}
+
+pub unsafe trait Zeroable {}
+"#;
+    fs::write(project_path.join("src/lib.rs"), source).unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let unsafe_usage = &ast.metrics.unsafe_usage;
+    assert_eq!(unsafe_usage.unsafe_blocks, 2);
+    assert_eq!(unsafe_usage.unsafe_functions, 1);
+    assert_eq!(unsafe_usage.unsafe_impls, 1);
+    assert_eq!(unsafe_usage.unsafe_traits, 1);
+
+    let report = MetricsReport::from_project(&ast, 1);
+    assert_eq!(report.unsafe_usage, *unsafe_usage);
+    assert_eq!(report.lines.files, 1);
+    assert_eq!(report.lines.code, ast.metrics.total_lines);
+
+    // `read`, `first`, `Handle` and `Zeroable`; only `read` is documented.
+    assert_eq!(report.documentation.public_items, 4);
+    assert_eq!(report.documentation.documented, 1);
+    assert!((report.documentation.coverage_percent - 25.0).abs() < 1e-9);
+
+    assert_eq!(report.complexity.functions, 2);
+    assert_eq!(report.complexity.max_cyclomatic, 3);
+    assert_eq!(report.largest_elements.len(), 1);
+    assert!(report.largest_elements[0].name.ends_with("first"));
+    assert_eq!(report.most_complex_elements.len(), 1);
+    assert!(report.most_complex_elements[0].name.ends_with("first"));
+    assert_eq!(report.most_complex_elements[0].value, 3);
+}
+
+#[test]
+fn test_check_thresholds_reports_exceeded_limits() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_path = temp_dir.path().to_path_buf();
+    fs::create_dir_all(project_path.join("src")).unwrap();
+    let source = r#"
+/// Classifies a value.
+pub fn classify(value: u8) -> u8 {
+    if value > 10 {
+        if value > 20 {
+            return 2;
+        }
+        return 1;
+    }
+    0
+}
+
+pub fn unsafe_read(ptr: *const u8) -> u8 {
+    unsafe { *ptr }
+}
+
+pub struct Undocumented;
+"#;
+    fs::write(project_path.join("src/lib.rs"), source).unwrap();
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+
+    let thresholds = ThresholdConfig {
+        max_cyclomatic: Some(2),
+        max_function_lines: Some(5),
+        max_file_lines: Some(1000),
+        min_doc_coverage: Some(50.0),
+        max_unsafe: Some(1),
+        ..ThresholdConfig::default()
+    };
+    let violations = check_thresholds(&ast, &thresholds);
+    let exceeded: Vec<&str> = violations
+        .iter()
+        .map(|violation| violation.threshold.as_str())
+        .collect();
+    assert_eq!(
+        exceeded,
+        ["max_cyclomatic", "max_function_lines", "min_doc_coverage"]
+    );
+
+    let complexity = &violations[0];
+    assert!(complexity.element.as_deref().unwrap().ends_with("classify"));
+    assert_eq!(complexity.line, Some(2));
+    let message = complexity.to_string();
+    assert!(message.starts_with("src/lib.rs:2: "));
+    assert!(message.ends_with("classify: max_cyclomatic is 3 above 2"));
+    // One of three public items is documented.
+    assert_eq!(
+        violations[2].to_string(),
+        "min_doc_coverage is 33.3 below 50"
+    );
+
+    assert!(check_thresholds(&ast, &ThresholdConfig::default()).is_empty());
+
+    // Vulnerabilities come from the results of the rustsec-audit plugin.
+    let thresholds = ThresholdConfig {
+        max_vulnerabilities: Some(1),
+        ..ThresholdConfig::default()
+    };
+    assert!(check_thresholds(&ast, &thresholds).is_empty());
+    let mut audited = ast.clone();
+    let mut results = PluginResults::default();
+    results.metrics.insert("vulnerabilities".to_string(), 2.0);
+    audited
+        .plugin_results
+        .insert("rustsec-audit".to_string(), results);
+    let violations = check_thresholds(&audited, &thresholds);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(
+        violations[0].to_string(),
+        "max_vulnerabilities is 2 above 1"
+    );
+}
+
+#[test]
+fn test_incremental_extraction_reparses_changed_files() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(project_path.join("src/extra.rs"), "pub fn extra() {}\n").unwrap();
+    let extractor = AstExtractor::new(ExtractorConfig::default(), project_path.clone());
+    let mut incremental = IncrementalExtractor::new(extractor);
+
+    let (first, changes) = incremental.extract().unwrap();
+    assert_eq!(changes.extracted.len(), first.files.len());
+    assert_eq!(changes.unchanged, 0);
+
+    let (_, changes) = incremental.extract().unwrap();
+    assert!(changes.is_empty());
+    assert_eq!(changes.unchanged, first.files.len());
+
+    fs::write(
+        project_path.join("src/main.rs"),
+        "/// Entry point.\npub fn main() {\n    helper();\n}\n\nfn helper() {}\n",
+    )
+    .unwrap();
+    fs::remove_file(project_path.join("src/extra.rs")).unwrap();
+    fs::write(project_path.join("src/added.rs"), "pub struct Added;\n").unwrap();
+    let (updated, changes) = incremental.extract().unwrap();
+    assert_eq!(
+        changes.extracted,
+        vec![PathBuf::from("src/added.rs"), PathBuf::from("src/main.rs")]
+    );
+    assert_eq!(changes.removed, vec![PathBuf::from("src/extra.rs")]);
+    assert_eq!(changes.unchanged, first.files.len() - 2);
+
+    let full = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(&updated.files).unwrap(),
+        serde_json::to_value(&full.files).unwrap()
+    );
+    assert_eq!(updated.metrics.total_files, full.metrics.total_files);
+}
+
+#[test]
+fn test_oversize_files_can_be_partially_extracted() {
+    let (_temp_dir, project_path) = create_test_project();
+    let mut source =
+        String::from("/// Looks up a generated entry.\npub fn lookup(key: u32) -> Option<u32> {\n");
+    for i in 0..200 {
+        source.push_str(&format!(
+            "    if key == {} {{ return Some({}); }}\n",
+            i,
+            i * 2
+        ));
+    }
+    source.push_str("    None\n}\n\npub struct Table;\n\nimpl Table {\n");
+    source.push_str("    pub fn len(&self) -> usize { helper(); 200 }\n}\n");
+    fs::write(project_path.join("src/generated.rs"), &source).unwrap();
+
+    let extract = |partial_oversize_files: bool| {
+        let mut config = ExtractorConfig::default();
+        config.max_file_size = 4096;
+        config.partial_oversize_files = partial_oversize_files;
+        AstExtractor::new(config, project_path.clone())
+            .extract_project()
+            .unwrap()
+            .files
+            .into_iter()
+            .find(|f| f.relative_path.ends_with("generated.rs"))
+    };
+
+    assert!(source.len() > 4096);
+    assert!(extract(false).is_none());
+
+    let file = extract(true).expect("oversize file should be partially extracted");
+    assert!(file.partially_extracted);
+    assert!(file.warnings[0].contains("extracted signatures only"));
+
+    let lookup = file.elements.iter().find(|e| e.name == "lookup").unwrap();
+    assert_eq!(lookup.doc_comments, vec![" Looks up a generated entry."]);
+    assert!(lookup.signature.as_ref().unwrap().contains("fn lookup"));
+    assert_eq!(lookup.location.line_end, 204);
+    assert!(lookup.complexity.is_none() && lookup.complexity_metrics.is_none());
+
+    let len = file.elements.iter().find(|e| e.name == "len").unwrap();
+    assert!(len.calls.is_empty());
+    assert!(file.elements.iter().any(|e| e.name == "Table"));
+}
+
+#[test]
+fn test_extraction_report_and_strict_mode() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(project_path.join("src/unparseable.rs"), "fn (").unwrap();
+    fs::write(
+        project_path.join("src/partial.rs"),
+        "pub fn kept() {}\n\npub fn broken( {\n",
+    )
+    .unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path.clone())
+        .extract_project()
+        .unwrap();
+    let report = &ast.extraction_report;
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].file, Path::new("src/unparseable.rs"));
+    assert_eq!(report.failed[0].reason, SkipReason::ParseError);
+    assert_eq!(report.incomplete.len(), 1);
+    assert_eq!(report.incomplete[0].file, Path::new("src/partial.rs"));
+    assert_eq!(report.incomplete[0].reason, SkipReason::ParseError);
+    assert!(report.incomplete[0]
+        .message
+        .starts_with("Skipped lines 3-3"));
+    let output = serde_json::to_value(&ast).unwrap();
+    assert_eq!(
+        output["extraction_report"]["failed"][0]["reason"],
+        "parse_error"
+    );
+
+    let mut config = ExtractorConfig::default();
+    config.strict = true;
+    match AstExtractor::new(config, project_path).extract_project() {
+        Err(RustExError::Strict { report }) => assert_eq!(&report, &ast.extraction_report),
+        other => panic!("expected a strict mode error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_extraction_report_counts_each_incomplete_file_once() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(
+        project_path.join("src/partial.rs"),
+        b"// caf\xE9\npub fn kept() {}\n\npub fn broken( {\n",
+    )
+    .unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let report = &ast.extraction_report;
+    let reasons: Vec<_> = report.incomplete.iter().map(|f| f.reason).collect();
+    assert_eq!(
+        reasons,
+        vec![SkipReason::InvalidEncoding, SkipReason::ParseError]
+    );
+    assert_eq!(report.incomplete_file_count(), 1);
+}
+
+#[test]
+fn test_per_path_filter_overrides() {
+    let (_temp_dir, project_path) = create_test_project();
+    fs::create_dir_all(project_path.join("tests")).unwrap();
+    fs::create_dir_all(project_path.join("src/generated")).unwrap();
+    fs::write(
+        project_path.join("tests/integration.rs"),
+        "/// Shared setup.\nfn setup() {}\n\n#[test]\nfn it_works() { setup(); }\n",
+    )
+    .unwrap();
+    fs::write(
+        project_path.join("src/generated/mod.rs"),
+        "pub fn generated() {}\n",
+    )
+    .unwrap();
+
+    let config = ExtractorConfig::from_toml_str(
+        r#"
+        [filters.overrides."tests/**"]
+        extract = true
+        include_private = true
+        include_docs = false
+
+        [filters.overrides."src/generated/**"]
+        extract = false
+        "#,
+    )
+    .unwrap();
+    let ast = AstExtractor::new(config, project_path)
+        .extract_project()
+        .unwrap();
+    let file = |suffix: &str| {
+        ast.files
+            .iter()
+            .find(|file| file.relative_path.ends_with(suffix))
+    };
+
+    // Tests are excluded by default, but the override brings them in with private items.
+    let tests = file("tests/integration.rs").expect("override should include tests");
+    let setup = tests.elements.iter().find(|e| e.name == "setup").unwrap();
+    assert_eq!(setup.visibility, Visibility::Private);
+    assert!(setup.doc_comments.is_empty());
+
+    assert!(file("src/generated/mod.rs").is_none());
+
+    // Other files keep the top-level settings.
+    let main = file("src/main.rs").unwrap();
+    assert!(main.elements.iter().all(|e| e.name != "process"));
+    let main_fn = main.elements.iter().find(|e| e.name == "main").unwrap();
+    assert!(!main_fn.doc_comments.is_empty());
+}
+
+#[test]
+fn test_output_budget_truncation_policies() {
+    let (_temp_dir, project_path) = create_test_project();
+    let mut config = ExtractorConfig::default();
+    config.include_private = true;
+    let ast = AstExtractor::new(config, project_path)
+        .extract_project()
+        .unwrap();
+    let measure = |ast: &ProjectAst| Ok(serde_json::to_vec(ast)?.len());
+    let full_size = measure(&ast).unwrap();
+
+    let mut unchanged = ast.clone();
+    fit_to_budget(
+        &mut unchanged,
+        full_size,
+        TruncationPolicy::DropBodiesFirst,
+        measure,
+    )
+    .unwrap();
+    assert!(unchanged.truncation.is_none());
+
+    for policy in [
+        TruncationPolicy::DropBodiesFirst,
+        TruncationPolicy::DropPrivateFirst,
+        TruncationPolicy::PerFileProportional,
+    ] {
+        let limit = full_size * 3 / 4;
+        let mut reduced = ast.clone();
+        fit_to_budget(&mut reduced, limit, policy, measure).unwrap();
+        assert!(
+            measure(&reduced).unwrap() <= limit,
+            "{:?} exceeds limit",
+            policy
+        );
+
+        let report = reduced.truncation.as_ref().unwrap();
+        assert_eq!(report.policy, policy);
+        assert_eq!(report.original_bytes, full_size);
+        assert!(report.stripped_elements + report.dropped_elements > 0);
+
+        // No element refers to a dropped one.
+        let ids: Vec<_> = reduced
+            .files
+            .iter()
+            .flat_map(|f| &f.elements)
+            .map(|e| &e.id)
+            .collect();
+        for element in reduced.files.iter().flat_map(|f| &f.elements) {
+            assert!(element
+                .hierarchy
+                .children_ids
+                .iter()
+                .all(|id| ids.contains(&id)));
+        }
+    }
+
+    let mut tiny = ast.clone();
+    assert!(fit_to_budget(&mut tiny, 10, TruncationPolicy::DropBodiesFirst, measure).is_err());
+}
+
+#[test]
+fn test_output_budget_drops_largest_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"budget\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/lib.rs"),
+        "pub mod big;\npub mod small_a;\npub mod small_b;\n",
+    )
+    .unwrap();
+    // A file much larger than the others, first in path order, whose
+    // functions call each other.
+    let big: String = (0..200u32)
+        .map(|i| {
+            format!(
+                "/// Step {i}.\n///\n/// Runs after step {prev}.\npub fn step_{i}(value: u64) -> u64 {{\n    \
+                 if value > {i} {{ step_{prev}(value - 1) }} else {{ value }}\n}}\n\n",
+                i = i,
+                prev = i.saturating_sub(1)
+            )
+        })
+        .collect();
+    fs::write(root.join("src/big.rs"), big).unwrap();
+    for name in ["small_a", "small_b"] {
+        fs::write(
+            root.join(format!("src/{}.rs", name)),
+            "/// Adds one.\npub fn add_one(x: u32) -> u32 {\n    x + 1\n}\n",
+        )
+        .unwrap();
+    }
+    let ast = AstExtractor::new(ExtractorConfig::default(), root.to_path_buf())
+        .extract_project()
+        .unwrap();
+    assert!(!ast.cross_references.is_empty());
+    let measure = |ast: &ProjectAst| Ok(serde_json::to_vec(ast)?.len());
+    let full_size = measure(&ast).unwrap();
+
+    for policy in [
+        TruncationPolicy::DropBodiesFirst,
+        TruncationPolicy::DropPrivateFirst,
+        TruncationPolicy::PerFileProportional,
+    ] {
+        // Stripping and dropping private elements don't fit, but dropping
+        // the large file does.
+        let limit = full_size / 10;
+        let mut reduced = ast.clone();
+        fit_to_budget(&mut reduced, limit, policy, measure).unwrap();
+        assert!(
+            measure(&reduced).unwrap() <= limit,
+            "{:?} exceeds limit",
+            policy
+        );
+
+        // The small files are kept, though the large one comes first.
+        let kept: Vec<&Path> = reduced
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_path())
+            .collect();
+        assert!(kept.ends_with(&[
+            Path::new("src/lib.rs"),
+            Path::new("src/small_a.rs"),
+            Path::new("src/small_b.rs"),
+        ]));
+        // Proportional shrinking drops elements of the large file instead.
+        let report = reduced.truncation.as_ref().unwrap();
+        if policy == TruncationPolicy::PerFileProportional {
+            assert!(report.dropped_files.is_empty());
+            assert!(report.dropped_elements > 0);
+        } else {
+            assert_eq!(report.dropped_files, [PathBuf::from("src/big.rs")]);
+        }
+    }
+
+    // References go with the calls of stripped elements.
+    let mut stripped = ast.clone();
+    let limit = measure(&ast).unwrap() - 1;
+    fit_to_budget(
+        &mut stripped,
+        limit,
+        TruncationPolicy::DropBodiesFirst,
+        measure,
+    )
+    .unwrap();
+    assert_eq!(stripped.files.len(), ast.files.len());
+    assert!(stripped.cross_references.is_empty());
+    assert!(stripped
+        .files
+        .iter()
+        .all(|file| file.cross_references.is_empty()));
+    assert!(stripped.reference_graph.references.is_empty());
+    assert!(stripped.reference_graph.referenced_by.is_empty());
+}
+
+#[test]
+fn test_split_project_strategies() {
+    let (_temp_dir, project_path) = create_test_project();
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let measure = |ast: &ProjectAst| Ok(serde_json::to_vec(ast)?.len());
+    let split = |strategy: &str| {
+        let strategy: SplitStrategy = strategy.parse().unwrap();
+        split_project(&ast, strategy, measure).unwrap()
+    };
+
+    let per_file = split("per-file");
+    let mut names: Vec<_> = per_file.iter().map(|part| part.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["src-lib", "src-main"]);
+    let elements = |parts: &[OutputPart]| -> usize {
+        parts
+            .iter()
+            .flat_map(|p| &p.ast.files)
+            .map(|f| f.elements.len())
+            .sum()
+    };
+    let all_elements: usize = ast.files.iter().map(|f| f.elements.len()).sum();
+    assert_eq!(elements(&per_file), all_elements);
+    let references: usize = per_file.iter().map(|p| p.ast.cross_references.len()).sum();
+    assert_eq!(references, ast.cross_references.len());
+    assert!(per_file
+        .iter()
+        .all(|p| p.ast.project.name == ast.project.name));
+
+    let per_module = split("per-module");
+    assert_eq!(per_module.len(), 1);
+    assert_eq!(per_module[0].name, "src");
+    assert_eq!(per_module[0].ast.files.len(), 2);
+
+    // Files over the limit get a part each, a generous limit packs them together.
+    let names = |parts: Vec<OutputPart>| parts.into_iter().map(|p| p.name).collect::<Vec<_>>();
+    assert_eq!(names(split("max-size=1")), ["part-001", "part-002"]);
+    assert_eq!(names(split("max-size=10MB")), ["part-001"]);
+
+    assert_eq!(
+        "max-size=10MB".parse::<SplitStrategy>().unwrap(),
+        SplitStrategy::MaxSize(10 * 1024 * 1024)
+    );
+    assert_eq!(SplitStrategy::MaxSize(512).to_string(), "max-size=512");
+    for invalid in ["per-crate", "max-size=0", "max-size=ten"] {
+        assert!(invalid.parse::<SplitStrategy>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_extract_single_file_and_source() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/net")).unwrap();
+    fs::write(root.join("src/net/mod.rs"), "pub fn connect() {}\n").unwrap();
+    let extractor = AstExtractor::new(ExtractorConfig::default(), root.to_path_buf());
+
+    // No Cargo.toml is needed, and the module path follows the location.
+    let file = extractor
+        .extract_single_file(&root.join("src/net/mod.rs"))
+        .unwrap();
+    assert_eq!(file.relative_path, Path::new("src/net/mod.rs"));
+    assert_eq!(
+        file.elements[0].hierarchy.qualified_name,
+        "crate::net::connect"
+    );
+
+    let source = "/// Greets.\npub fn greet() {}\n\nfn (\n";
+    let file = extractor
+        .extract_source(Path::new("<stdin>"), source)
+        .unwrap();
+    assert_eq!(file.path, Path::new("<stdin>"));
+    assert_eq!(file.elements.len(), 1);
+    assert_eq!(file.elements[0].hierarchy.qualified_name, "crate::greet");
+    assert_eq!(file.parse_errors.len(), 1);
+
+    assert!(extractor
+        .extract_single_file(&root.join("src/missing.rs"))
+        .is_err());
+}
+
+#[test]
+fn test_json_schema_describes_output() {
+    let (_temp_dir, project_path) = create_test_project();
+    let ast = AstExtractor::new(ExtractorConfig::default(), project_path)
+        .extract_project()
+        .unwrap();
+    let output = serde_json::to_value(&ast).unwrap();
+    let schema = serde_json::to_value(schemars::schema_for!(ProjectAst)).unwrap();
+
+    // Every output field is described and every required field is output.
+    for (value, definition) in [
+        (&output, &schema),
+        (&output["files"][0], &schema["$defs"]["FileAst"]),
+        (
+            &output["files"][0]["elements"][0],
+            &schema["$defs"]["CodeElement"],
+        ),
+    ] {
+        let properties = definition["properties"].as_object().unwrap();
+        for key in value.as_object().unwrap().keys() {
+            assert!(
+                properties.contains_key(key),
+                "`{}` isn't in the schema",
+                key
+            );
+        }
+        for required in definition["required"].as_array().unwrap() {
+            assert!(value.get(required.as_str().unwrap()).is_some());
+        }
+    }
+    assert_eq!(schema["properties"]["extracted_at"]["format"], "date-time");
+}
+
+#[test]
+fn test_deterministic_output_is_sorted_and_reproducible() {
+    let (_temp_dir, project_path) = create_test_project();
+    let extract = || {
+        let mut ast = AstExtractor::new(ExtractorConfig::default(), project_path.clone())
+            .extract_project()
+            .unwrap();
+        ast.extracted_at = chrono::DateTime::UNIX_EPOCH;
+        ast
+    };
+    let ast = extract();
+    assert_eq!(
+        serde_json::to_string(&ast).unwrap(),
+        serde_json::to_string(&extract()).unwrap()
+    );
+
+    let paths: Vec<_> = ast.files.iter().map(|file| &file.relative_path).collect();
+    assert!(paths.windows(2).all(|pair| pair[0] <= pair[1]));
+    for file in &ast.files {
+        let starts: Vec<_> = file
+            .elements
+            .iter()
+            .map(|element| (element.location.line_start, element.location.char_start))
+            .collect();
+        assert!(starts.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    let mut element = ast.files[0].elements[0].clone();
+    element.metadata.clear();
+    for key in ["zeta", "alpha", "mu", "beta"] {
+        element
+            .metadata
+            .insert(key.to_string(), serde_json::json!(1));
+    }
+    let json = serde_json::to_string(&element).unwrap();
+    assert!(json.contains(r#""metadata":{"alpha":1,"beta":1,"mu":1,"zeta":1}"#));
+}
@@ -150,4 +150,383 @@ fn test_hierarchy_builder() {
     assert!(!back_hierarchy.module_path.contains("submodule"), "Module path should not include submodule after exit");
     
     println!("✅ Hierarchy builder test passed!");
-}
\ No newline at end of file
+}
+#[test]
+fn test_fully_qualified_names() {
+    let code = r#"
+        pub mod protocol {
+            pub struct Message;
+
+            impl Message {
+                pub fn new() -> Self {
+                    Message
+                }
+            }
+
+            impl std::fmt::Display for Message {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "message")
+                }
+            }
+        }
+
+        pub fn connect() {}
+    "#;
+
+    let syntax_tree = syn::parse_file(code).expect("Failed to parse test code");
+    let config = ExtractorConfig {
+        include_private: true,
+        ..Default::default()
+    };
+    let mut visitor =
+        CodeElementVisitor::new(PathBuf::from("/project/src/network/mod.rs"), &config);
+    visitor.visit_file(&syntax_tree);
+    let elements = visitor.into_elements();
+
+    let qualified = |name: &str| -> Vec<String> {
+        elements
+            .iter()
+            .filter(|e| e.name == name)
+            .map(|e| e.hierarchy.qualified_name.clone())
+            .collect()
+    };
+
+    assert_eq!(qualified("connect"), vec!["crate::network::connect"]);
+    assert_eq!(qualified("protocol"), vec!["crate::network::protocol"]);
+    assert_eq!(
+        qualified("Message"),
+        vec!["crate::network::protocol::Message"]
+    );
+    assert_eq!(
+        qualified("new"),
+        vec!["crate::network::protocol::Message::new"]
+    );
+    assert_eq!(
+        qualified("fmt"),
+        vec!["<crate::network::protocol::Message as std::fmt::Display>::fmt"]
+    );
+}
+
+#[test]
+fn test_module_path_from_file_layout() {
+    let config = ExtractorConfig::default();
+    let cases = [
+        ("src/lib.rs", "crate::run"),
+        ("src/main.rs", "crate::run"),
+        ("src/network/protocol.rs", "crate::network::protocol::run"),
+        ("src/bin/tool.rs", "crate::run"),
+        ("crates/app/src/config/mod.rs", "crate::config::run"),
+    ];
+
+    for (path, expected) in cases {
+        let syntax_tree = syn::parse_file("pub fn run() {}").unwrap();
+        let mut visitor = CodeElementVisitor::new(PathBuf::from(path), &config);
+        visitor.visit_file(&syntax_tree);
+        let elements = visitor.into_elements();
+        assert_eq!(
+            elements[0].hierarchy.qualified_name, expected,
+            "for {}",
+            path
+        );
+    }
+}
+
+#[test]
+fn test_module_path_from_path_attribute_and_include() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    std::fs::create_dir_all(root.join("src/platform")).unwrap();
+    std::fs::create_dir_all(root.join("generated")).unwrap();
+    std::fs::write(
+        root.join("src/lib.rs"),
+        r#"
+        #[path = "platform/unix_impl.rs"]
+        pub mod sys;
+
+        pub mod codes {
+            include!("../generated/codes.rs");
+        }
+        "#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/platform/unix_impl.rs"),
+        "pub fn open() {}\n\npub mod helpers;\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("src/platform/helpers.rs"), "pub fn assist() {}\n").unwrap();
+    // Outside the default `src/**/*.rs` include pattern.
+    std::fs::write(root.join("generated/codes.rs"), "pub fn ok() {}\n").unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), root.to_path_buf())
+        .extract_project()
+        .unwrap();
+
+    let qualified = |name: &str| -> Option<String> {
+        ast.files
+            .iter()
+            .flat_map(|f| &f.elements)
+            .find(|e| e.name == name)
+            .map(|e| e.hierarchy.qualified_name.clone())
+    };
+
+    assert_eq!(qualified("open").as_deref(), Some("crate::sys::open"));
+    assert_eq!(
+        qualified("assist").as_deref(),
+        Some("crate::sys::helpers::assist")
+    );
+    assert_eq!(qualified("ok").as_deref(), Some("crate::codes::ok"));
+    assert!(ast
+        .files
+        .iter()
+        .any(|f| f.relative_path == std::path::Path::new("generated/codes.rs")));
+}
+
+#[test]
+fn test_impl_methods_attach_to_type() {
+    let code = r#"
+        pub struct Point {
+            pub x: i32,
+        }
+
+        impl Point {
+            pub fn origin() -> Self {
+                Point { x: 0 }
+            }
+        }
+
+        impl Default for Point {
+            fn default() -> Self {
+                Point::origin()
+            }
+        }
+    "#;
+
+    let syntax_tree = syn::parse_file(code).expect("Failed to parse test code");
+    let config = ExtractorConfig {
+        include_private: true,
+        ..Default::default()
+    };
+    let mut visitor = CodeElementVisitor::new(PathBuf::from("src/geometry.rs"), &config);
+    visitor.visit_file(&syntax_tree);
+    let elements = visitor.into_elements();
+
+    let point = elements
+        .iter()
+        .find(|e| e.element_type == ElementType::Struct)
+        .unwrap();
+    let origin = elements.iter().find(|e| e.name == "origin").unwrap();
+    let default = elements.iter().find(|e| e.name == "default").unwrap();
+
+    assert_eq!(origin.hierarchy.parent_element, Some(point.id.clone()));
+    assert_eq!(default.hierarchy.parent_element, Some(point.id.clone()));
+
+    let origin_context = origin.hierarchy.impl_context.as_ref().unwrap();
+    assert_eq!(origin_context.self_type, "crate::geometry::Point");
+    assert_eq!(origin_context.trait_path, None);
+    let default_context = default.hierarchy.impl_context.as_ref().unwrap();
+    assert_eq!(default_context.trait_path.as_deref(), Some("Default"));
+
+    for impl_element in elements
+        .iter()
+        .filter(|e| e.element_type == ElementType::Impl)
+    {
+        assert_eq!(
+            impl_element.hierarchy.parent_element,
+            Some(point.id.clone())
+        );
+    }
+}
+
+#[test]
+fn test_project_type_index_across_files() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(src.join("lib.rs"), "pub struct Token;\n").unwrap();
+    std::fs::write(
+        src.join("display.rs"),
+        r#"
+        use crate::Token;
+
+        impl Token {
+            pub fn text(&self) -> &str {
+                "token"
+            }
+        }
+
+        impl std::fmt::Display for Token {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.text())
+            }
+        }
+        "#,
+    )
+    .unwrap();
+
+    let config = ExtractorConfig {
+        include_private: true,
+        ..Default::default()
+    };
+    let ast = AstExtractor::new(config, temp_dir.path().to_path_buf())
+        .extract_project()
+        .unwrap();
+
+    let elements: Vec<&CodeElement> = ast.files.iter().flat_map(|f| &f.elements).collect();
+    let token = elements.iter().find(|e| e.name == "Token").unwrap();
+    let text = elements.iter().find(|e| e.name == "text").unwrap();
+    let fmt = elements.iter().find(|e| e.name == "fmt").unwrap();
+
+    // The impl lives in another module, so the type is matched by its simple name.
+    assert_eq!(text.hierarchy.parent_element, Some(token.id.clone()));
+
+    let entry = ast
+        .type_index
+        .get("crate::Token")
+        .expect("Token should be indexed");
+    assert_eq!(entry.type_element_id, Some(token.id.clone()));
+    assert_eq!(entry.impl_ids.len(), 2);
+    assert_eq!(entry.inherent_methods, vec![text.id.clone()]);
+    assert_eq!(
+        entry.trait_methods.get("std::fmt::Display"),
+        Some(&vec![fmt.id.clone()])
+    );
+}
+
+#[test]
+fn test_trait_impl_inventory() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(
+        src.join("lib.rs"),
+        r#"
+        pub trait Shape {
+            fn area(&self) -> f64;
+        }
+
+        pub struct Square<T>(T);
+
+        impl<T> Shape for Square<T>
+        where
+            T: Into<f64> + Copy,
+        {
+            fn area(&self) -> f64 {
+                self.0.into() * self.0.into()
+            }
+        }
+
+        impl<T> Square<T> {
+            pub fn side(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T: Clone> Clone for Square<T> {
+            fn clone(&self) -> Self {
+                Square(self.0.clone())
+            }
+        }
+        "#,
+    )
+    .unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), temp_dir.path().to_path_buf())
+        .extract_project()
+        .unwrap();
+
+    let elements: Vec<&CodeElement> = ast.files.iter().flat_map(|f| &f.elements).collect();
+    let shape = elements.iter().find(|e| e.name == "Shape").unwrap();
+    let square = elements.iter().find(|e| e.name == "Square").unwrap();
+
+    // Inherent impls are not part of the inventory.
+    assert_eq!(ast.impls.len(), 2);
+
+    let shape_impl = &ast.impls[0];
+    assert_eq!(shape_impl.trait_path, "Shape");
+    assert_eq!(shape_impl.self_type, "crate::Square");
+    assert_eq!(shape_impl.trait_element_id, Some(shape.id.clone()));
+    assert_eq!(shape_impl.type_element_id, Some(square.id.clone()));
+    assert_eq!(shape_impl.generics, vec!["T".to_string()]);
+    assert_eq!(
+        shape_impl.where_clause.as_deref(),
+        Some("where T : Into < f64 > + Copy ,")
+    );
+    assert_eq!(shape_impl.location.file_path, src.join("lib.rs"));
+
+    let clone_impl = &ast.impls[1];
+    assert_eq!(clone_impl.trait_path, "Clone");
+    assert_eq!(clone_impl.trait_element_id, None);
+    assert_eq!(clone_impl.generics, vec!["T : Clone".to_string()]);
+    assert_eq!(clone_impl.where_clause, None);
+}
+
+#[test]
+fn test_trait_provided_and_required_methods() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(
+        src.join("lib.rs"),
+        r#"
+        pub trait Greeter {
+            fn name(&self) -> String;
+            fn greet(&self) -> String {
+                format!("Hello, {}", self.name())
+            }
+            fn farewell(&self) -> String {
+                format!("Bye, {}", self.name())
+            }
+        }
+
+        pub struct Plain;
+        pub struct Loud;
+
+        impl Greeter for Plain {
+            fn name(&self) -> String {
+                "plain".to_string()
+            }
+        }
+
+        impl Greeter for Loud {
+            fn name(&self) -> String {
+                "loud".to_string()
+            }
+            fn greet(&self) -> String {
+                "HELLO".to_string()
+            }
+        }
+        "#,
+    )
+    .unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), temp_dir.path().to_path_buf())
+        .extract_project()
+        .unwrap();
+
+    let greeter = ast
+        .files
+        .iter()
+        .flat_map(|f| &f.elements)
+        .find(|e| e.name == "Greeter")
+        .unwrap();
+    assert_eq!(greeter.required_methods(), vec!["name"]);
+    assert_eq!(greeter.provided_methods(), vec!["greet", "farewell"]);
+
+    // Trait impl methods are private, so they are found without being extracted.
+    let plain = ast
+        .impls
+        .iter()
+        .find(|i| i.self_type == "crate::Plain")
+        .unwrap();
+    assert!(plain.overridden_methods.is_empty());
+    assert_eq!(plain.inherited_methods, vec!["greet", "farewell"]);
+
+    let loud = ast
+        .impls
+        .iter()
+        .find(|i| i.self_type == "crate::Loud")
+        .unwrap();
+    assert_eq!(loud.overridden_methods, vec!["greet"]);
+    assert_eq!(loud.inherited_methods, vec!["farewell"]);
+}
@@ -2257,7 +2257,7 @@ async fn test_cli_application_extraction() {
     // Verify enums were extracted
 
     assert!(
-        all_enums.len() >= 1,
+        !all_enums.is_empty(),
         "Should extract at least some enums from CLI application"
     );
 
@@ -2348,7 +2348,7 @@ async fn test_library_project_extraction() {
         .filter(|e| matches!(e.element_type, ElementType::Trait))
         .collect();
 
-    assert!(all_traits.len() >= 1, "Should extract DataStructure trait");
+    assert!(!all_traits.is_empty(), "Should extract DataStructure trait");
 
     // Should have generic structs
     let all_structs: Vec<_> = project_ast
@@ -2420,6 +2420,12 @@ async fn test_cross_project_comparison() {
         filters: rustex_core::FilterConfig {
             include: vec!["**/*.rs".to_string()],
             exclude: vec![],
+            visibility: vec![],
+            exclude_build_scripts: false,
+            element_types: vec![],
+            name_patterns: vec![],
+            exclude_names: vec![],
+            overrides: Default::default(),
         },
         include_docs: true,
         include_private: true,
@@ -2515,7 +2521,7 @@ async fn test_real_world_patterns() {
         .iter()
         .flat_map(|f| &f.elements)
         .filter(|e| matches!(e.element_type, ElementType::Function))
-        .filter(|e| e.signature.as_ref().map_or(false, |s| s.contains("async")))
+        .filter(|e| e.signature.as_ref().is_some_and(|s| s.contains("async")))
         .collect();
 
     assert!(!async_functions.is_empty(), "Should detect async functions");
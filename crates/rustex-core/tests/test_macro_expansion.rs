@@ -0,0 +1,112 @@
+//! Tests for macro expansion and merging of generated elements.
+
+#![allow(clippy::field_reassign_with_default)]
+
+use rustex_core::macro_expansion::generated_elements;
+use rustex_core::{AstExtractor, CodeElementVisitor, ElementType, ExtractorConfig};
+use std::fs;
+use std::path::PathBuf;
+use syn::visit::Visit;
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"
+macro_rules! getter {
+    ($name:ident) => {
+        pub fn $name() -> u32 { 0 }
+    };
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub retries: u32,
+}
+
+getter!(default_retries);
+"#;
+
+const EXPANDED: &str = r#"
+pub struct Config {
+    pub retries: u32,
+}
+#[automatically_derived]
+impl ::core::clone::Clone for Config {
+    #[inline]
+    fn clone(&self) -> Config {
+        Config { retries: ::core::clone::Clone::clone(&self.retries) }
+    }
+}
+pub fn default_retries() -> u32 { 0 }
+"#;
+
+#[test]
+fn test_generated_elements_from_expanded_source() {
+    let mut config = ExtractorConfig::default();
+    config.include_private = true;
+    let root_file = PathBuf::from("src/lib.rs");
+
+    let mut visitor = CodeElementVisitor::new(root_file.clone(), &config);
+    visitor.visit_file(&syn::parse_file(SOURCE).unwrap());
+    let original = visitor.into_elements();
+    let original_refs: Vec<_> = original.iter().collect();
+
+    let generated =
+        generated_elements(EXPANDED, &root_file, "expanded", &original_refs, &config).unwrap();
+
+    let names: Vec<&str> = generated.iter().map(|e| e.name.as_str()).collect();
+    assert!(
+        names.contains(&"default_retries"),
+        "macro_rules output: {:?}",
+        names
+    );
+    assert!(names.contains(&"clone"), "derive output: {:?}", names);
+    assert!(!names.contains(&"Config"), "source items are not generated");
+    assert!(generated.iter().all(|e| e.is_generated));
+
+    // The derived impl is nested under the original struct's module, not a new one.
+    let derived_impl = generated
+        .iter()
+        .find(|e| e.element_type == ElementType::Impl)
+        .unwrap();
+    assert!(derived_impl.hierarchy.parent_id.is_none());
+    assert_eq!(
+        derived_impl.hierarchy.qualified_name,
+        "<crate::Config as ::core::clone::Clone>"
+    );
+}
+
+#[test]
+fn test_extract_with_expand_macros() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"expand-me\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    fs::write(root.join("src/lib.rs"), SOURCE).unwrap();
+
+    let mut config = ExtractorConfig::default();
+    config.expand_macros = true;
+    let ast = AstExtractor::new(config, root.to_path_buf())
+        .extract_project()
+        .unwrap();
+
+    let elements: Vec<_> = ast.files.iter().flat_map(|f| &f.elements).collect();
+    let Some(generated) = elements.iter().find(|e| e.name == "default_retries") else {
+        // Expansion needs a working toolchain; extraction must still succeed without one.
+        assert!(elements.iter().all(|e| !e.is_generated));
+        return;
+    };
+
+    assert!(generated.is_generated);
+    assert_eq!(generated.location.file_path, root.join("src/lib.rs"));
+    assert!(
+        !elements
+            .iter()
+            .find(|e| e.name == "Config")
+            .unwrap()
+            .is_generated
+    );
+    assert_eq!(ast.metrics.total_functions, 1);
+}
@@ -0,0 +1,197 @@
+//! Tests for looking up and searching elements of an extracted project.
+
+use regex::Regex;
+use rustex_core::{
+    AstExtractor, ElementQuery, ElementType, ExtractorConfig, ProjectIndex, TextField,
+};
+use std::fs;
+use tempfile::TempDir;
+
+fn index_project() -> ProjectIndex {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"indexed\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("src/net")).unwrap();
+    fs::write(
+        root.join("src/lib.rs"),
+        "pub mod net;\n\npub fn parse_config() {}\n\npub struct Parser;\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/net/mod.rs"),
+        "pub fn parse() {}\n\npub fn connect() {}\n",
+    )
+    .unwrap();
+
+    let ast = AstExtractor::new(ExtractorConfig::default(), root.to_path_buf())
+        .extract_project()
+        .unwrap();
+    ProjectIndex::new(ast)
+}
+
+fn names(index: &ProjectIndex, query: &ElementQuery) -> Vec<String> {
+    index
+        .search(query)
+        .iter()
+        .map(|found| found.element.name.clone())
+        .collect()
+}
+
+#[test]
+fn test_search_ranks_exact_then_prefix_matches() {
+    let index = index_project();
+    let query = ElementQuery {
+        name: Some("PARSE".to_string()),
+        ..ElementQuery::default()
+    };
+    assert_eq!(names(&index, &query), ["parse", "parse_config", "Parser"]);
+
+    let functions = ElementQuery {
+        name: Some("parse".to_string()),
+        element_type: Some("function".to_string()),
+        limit: Some(1),
+        ..ElementQuery::default()
+    };
+    assert_eq!(names(&index, &functions), ["parse"]);
+
+    let in_net = ElementQuery {
+        path: Some("./src/net".to_string()),
+        element_type: Some("function".to_string()),
+        ..ElementQuery::default()
+    };
+    assert_eq!(names(&index, &in_net), ["parse", "connect"]);
+}
+
+#[test]
+fn test_element_and_file_lookup() {
+    let index = index_project();
+    let parser = index
+        .search(&ElementQuery {
+            name: Some("Parser".to_string()),
+            ..ElementQuery::default()
+        })
+        .remove(0);
+    assert_eq!(parser.element.element_type, ElementType::Struct);
+
+    let found = index.element(&parser.element.id).unwrap();
+    assert_eq!(found.element.name, "Parser");
+    assert_eq!(
+        serde_json::to_value(found).unwrap()["file"],
+        serde_json::json!("src/lib.rs")
+    );
+    assert!(index.element("missing").is_none());
+
+    assert_eq!(index.file("src/net/mod.rs").unwrap().elements.len(), 2);
+    assert!(index.file("src/missing.rs").is_none());
+}
+
+#[test]
+fn test_search_text_in_names_signatures_and_docs() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"searched\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let source =
+        "/// Reads the settings file.\npub fn load(path: &str) -> Settings {\n    Settings\n}\n\n\
+                  pub struct Settings;\n\n\
+                  /// Writes nothing.\npub fn store() {}\n";
+    fs::write(root.join("src/lib.rs"), source).unwrap();
+    let index = ProjectIndex::new(
+        AstExtractor::new(ExtractorConfig::default(), root.to_path_buf())
+            .extract_project()
+            .unwrap(),
+    );
+    let search = |pattern: &str, element_types: &[String]| {
+        index
+            .search_text(&Regex::new(pattern).unwrap(), element_types)
+            .into_iter()
+            .map(|found| (found.found.element.name.clone(), found.field, found.text))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        search("(?i)settings", &[]),
+        [
+            (
+                "load".to_string(),
+                TextField::Signature,
+                "fn load (path : & str) -> Settings".to_string()
+            ),
+            (
+                "Settings".to_string(),
+                TextField::Name,
+                "crate::Settings".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        search("nothing", &[]),
+        [(
+            "store".to_string(),
+            TextField::Doc,
+            "Writes nothing.".to_string()
+        )]
+    );
+    assert_eq!(search("Settings", &["struct".to_string()]).len(), 1);
+    assert!(search("missing", &[]).is_empty());
+}
+
+#[test]
+fn test_element_source_and_reference_subgraph() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"graphed\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let source = "pub fn entry() {\n    middle();\n}\n\n\
+                  pub fn middle() {\n    leaf();\n}\n\n\
+                  pub fn leaf() {}\n";
+    fs::write(root.join("src/lib.rs"), source).unwrap();
+    let index = ProjectIndex::new(
+        AstExtractor::new(ExtractorConfig::default(), root.to_path_buf())
+            .extract_project()
+            .unwrap(),
+    );
+    let id = |name: &str| {
+        let query = ElementQuery {
+            name: Some(name.to_string()),
+            limit: Some(1),
+            ..ElementQuery::default()
+        };
+        index.search(&query)[0].element.id.clone()
+    };
+
+    assert_eq!(
+        index.element_source(&id("middle")).unwrap().as_deref(),
+        Some("pub fn middle() {\n    leaf();\n}")
+    );
+    assert_eq!(index.element_source("missing").unwrap(), None);
+
+    let around_middle = index.reference_subgraph(&id("middle"), 1);
+    assert_eq!(
+        around_middle.element_ids,
+        [id("middle"), id("leaf"), id("entry")]
+    );
+    let mut expected_edges = vec![(id("entry"), id("middle")), (id("middle"), id("leaf"))];
+    expected_edges.sort();
+    assert_eq!(around_middle.edges, expected_edges);
+
+    let from_entry = index.reference_subgraph(&id("entry"), 1);
+    assert_eq!(from_entry.element_ids, [id("entry"), id("middle")]);
+    assert_eq!(from_entry.edges, [(id("entry"), id("middle"))]);
+    assert_eq!(
+        index.reference_subgraph(&id("entry"), 2).element_ids.len(),
+        3
+    );
+}
@@ -0,0 +1,133 @@
+//! Tests for ad-hoc queries over an extracted project.
+
+use rustex_core::{AstExtractor, ExtractorConfig, ProjectIndex, Query};
+use std::fs;
+use tempfile::TempDir;
+
+fn index_project() -> ProjectIndex {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"queried\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let source = r#"
+/// Picks a branch.
+pub fn branchy(x: i32) -> i32 {
+    if x > 0 {
+        if x > 10 { 2 } else { 1 }
+    } else if x < -10 {
+        -2
+    } else {
+        helper()
+    }
+}
+
+pub(crate) fn helper() -> i32 {
+    0
+}
+
+fn straight() {}
+
+pub struct Config;
+"#;
+    fs::write(root.join("src/lib.rs"), source).unwrap();
+
+    let config = ExtractorConfig {
+        include_private: true,
+        ..ExtractorConfig::default()
+    };
+    let ast = AstExtractor::new(config, root.to_path_buf())
+        .extract_project()
+        .unwrap();
+    ProjectIndex::new(ast)
+}
+
+fn names(index: &ProjectIndex, query: &str) -> Vec<String> {
+    Query::parse(query)
+        .unwrap()
+        .run(index)
+        .iter()
+        .map(|found| found.element.name.clone())
+        .collect()
+}
+
+#[test]
+fn test_query_filters_by_kind_and_conditions() {
+    let index = index_project();
+    assert_eq!(
+        names(&index, "functions"),
+        ["branchy", "helper", "straight"]
+    );
+    assert_eq!(
+        names(
+            &index,
+            "functions where complexity > 2 and visibility = pub"
+        ),
+        ["branchy"]
+    );
+    assert_eq!(
+        names(
+            &index,
+            "FUNCTIONS WHERE NOT (visibility = pub OR name ~ '^str')"
+        ),
+        ["helper"]
+    );
+    assert_eq!(
+        names(&index, "elements where documented = true"),
+        ["branchy"]
+    );
+    assert_eq!(names(&index, "elements where callers >= 1"), ["helper"]);
+    assert_eq!(
+        names(
+            &index,
+            "elements where type != function and file = src/lib.rs"
+        ),
+        ["Config"]
+    );
+    // Structs have no signature, so they match no comparison on it.
+    assert_eq!(
+        names(&index, "structs where signature != ''"),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_query_order_and_limit() {
+    let index = index_project();
+    assert_eq!(
+        names(&index, "functions order by complexity desc limit 2"),
+        ["branchy", "helper"]
+    );
+    assert_eq!(
+        names(&index, "elements order by name limit 2"),
+        ["Config", "branchy"]
+    );
+}
+
+#[test]
+fn test_query_parse_errors() {
+    for (query, message) in [
+        ("widgets", "unknown element kind `widgets`"),
+        ("functions where size > 3", "unknown field `size`"),
+        (
+            "functions where complexity > pub",
+            "cannot compare `complexity`",
+        ),
+        (
+            "functions where visibility = public",
+            "unknown visibility `public`",
+        ),
+        ("functions where type > function", "only supports"),
+        ("functions where name ~ '('", "invalid pattern"),
+        ("functions where (complexity > 1", "expected `)`"),
+        ("functions limit", "expected a number after `limit`"),
+        ("functions where name = \"open", "unterminated string"),
+        ("functions order complexity", "expected `by`"),
+    ] {
+        let error = Query::parse(query).unwrap_err().to_string();
+        assert!(error.contains(message), "{}: {}", query, error);
+    }
+}
@@ -0,0 +1,106 @@
+//! Tests for re-export resolution.
+
+use rustex_core::{AstExtractor, ExtractorConfig, ProjectAst};
+use std::fs;
+use tempfile::TempDir;
+
+/// Extract a crate made of `files`, given as `(path under src, source)` pairs.
+fn extract(files: &[(&str, &str)], include_private: bool) -> ProjectAst {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"reexports\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    for (path, source) in files {
+        fs::write(root.join("src").join(path), source).unwrap();
+    }
+
+    let config = ExtractorConfig {
+        include_private,
+        ..ExtractorConfig::default()
+    };
+    AstExtractor::new(config, root.to_path_buf())
+        .extract_project()
+        .unwrap()
+}
+
+/// The exported paths of the element named `name`.
+fn exported_paths(ast: &ProjectAst, name: &str) -> Vec<String> {
+    ast.files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .find(|element| element.name == name)
+        .unwrap_or_else(|| panic!("no element named {}", name))
+        .hierarchy
+        .namespace
+        .exported_paths
+        .clone()
+}
+
+const LIB: &str = r#"
+mod inner;
+pub mod api;
+mod chain {
+    pub use crate::deep::Link;
+}
+mod deep {
+    pub struct Link;
+}
+
+pub use inner::Engine;
+pub use inner::helpers::*;
+pub use self::api as public_api;
+pub use crate::chain::Link as Renamed;
+"#;
+
+const INNER: &str = r#"
+pub struct Engine;
+pub fn internal() {}
+
+pub mod helpers {
+    pub fn assist() {}
+}
+"#;
+
+#[test]
+fn test_reexports_resolve_to_exported_paths() {
+    let ast = extract(
+        &[
+            ("lib.rs", LIB),
+            ("inner.rs", INNER),
+            ("api.rs", "pub fn call() {}\n"),
+        ],
+        true,
+    );
+
+    assert_eq!(exported_paths(&ast, "Engine"), ["crate::Engine"]);
+    assert_eq!(exported_paths(&ast, "assist"), ["crate::assist"]);
+    assert_eq!(
+        exported_paths(&ast, "call"),
+        ["crate::api::call", "crate::public_api::call"]
+    );
+    assert_eq!(exported_paths(&ast, "Link"), ["crate::Renamed"]);
+    assert!(exported_paths(&ast, "internal").is_empty());
+}
+
+#[test]
+fn test_reexports_resolve_without_private_modules() {
+    let ast = extract(&[("lib.rs", LIB), ("inner.rs", INNER)], false);
+
+    assert_eq!(exported_paths(&ast, "Engine"), ["crate::Engine"]);
+    assert_eq!(exported_paths(&ast, "assist"), ["crate::assist"]);
+    assert!(exported_paths(&ast, "internal").is_empty());
+
+    let lib = ast
+        .files
+        .iter()
+        .find(|file| file.relative_path.ends_with("lib.rs"))
+        .unwrap();
+    let glob = lib.imports.iter().find(|import| import.is_glob).unwrap();
+    assert_eq!(glob.module_path, "inner::helpers");
+    assert!(glob.is_reexport);
+    assert_eq!(glob.scope, "crate");
+}
@@ -143,6 +143,12 @@ pub fn create_test_config(include_private: bool, include_docs: bool) -> Extracto
         filters: FilterConfig {
             include: vec!["**/*.rs".to_string()],
             exclude: vec!["target/**".to_string(), "**/build.rs".to_string()],
+            visibility: vec![],
+            exclude_build_scripts: false,
+            element_types: vec![],
+            name_patterns: vec![],
+            exclude_names: vec![],
+            overrides: Default::default(),
         },
         output_format: OutputFormat::Json,
         ..Default::default()
@@ -315,7 +321,7 @@ pub fn benchmark_extraction_performance(
 ) {
     for &(file_count, elements_per_file) in project_sizes {
         let fixture = TestFixtureBuilder::new()
-            .with_project_name(&format!("perf-test-{}-{}", file_count, elements_per_file))
+            .with_project_name(format!("perf-test-{}-{}", file_count, elements_per_file))
             .build();
         
         // Add files with varying complexity
@@ -1,6 +1,8 @@
 //! Unit tests for the AST visitors.
 
-use rustex_core::{CodeElementVisitor, ElementType, ExtractorConfig, Visibility};
+#![allow(clippy::field_reassign_with_default)]
+
+use rustex_core::{CfgPredicate, CodeElementVisitor, ElementType, ExtractorConfig, Visibility};
 use std::path::PathBuf;
 use syn::visit::Visit;
 
@@ -276,6 +278,46 @@ fn test_trait_visitor() {
     );
 }
 
+#[test]
+fn test_const_and_static_visitor() {
+    let code = r#"
+        /// Maximum number of retries
+        pub const MAX_RETRIES: u32 = 3;
+
+        static mut COUNTER: usize = 0;
+
+        fn helper() {
+            const LOCAL: &str = "local";
+        }
+    "#;
+
+    let syntax_tree = syn::parse_file(code).expect("Failed to parse code");
+    let config = create_test_config();
+    let mut visitor = CodeElementVisitor::new(PathBuf::from("test.rs"), &config);
+
+    visitor.visit_file(&syntax_tree);
+    let elements = visitor.into_elements();
+
+    let values: Vec<_> = elements
+        .iter()
+        .filter(|e| matches!(e.element_type, ElementType::Constant | ElementType::Static))
+        .map(|e| (e.name.as_str(), e.signature.as_deref().unwrap()))
+        .collect();
+    assert_eq!(
+        values,
+        [
+            ("MAX_RETRIES", "pub const MAX_RETRIES : u32"),
+            ("COUNTER", "static mut COUNTER : usize"),
+            ("LOCAL", "const LOCAL : & str"),
+        ]
+    );
+
+    let max_retries = &elements[0];
+    assert!(matches!(max_retries.visibility, Visibility::Public));
+    assert_eq!(max_retries.doc_comments, [" Maximum number of retries"]);
+    assert_eq!(max_retries.location.line_start, 2);
+}
+
 #[test]
 fn test_visibility_filtering() {
     let code = r#"
@@ -473,3 +515,227 @@ fn test_nested_items() {
     // The current implementation visits nested functions but may not extract impl methods
     // This is acceptable behavior for the current visitor implementation
 }
+
+#[test]
+fn test_doc_examples_visitor() {
+    let code = r#"
+        /// Parse a port number.
+        ///
+        /// ```
+        /// let port = parse_port("8080");
+        ///
+        /// if port > 0 {
+        ///     println!("{}", port);
+        /// }
+        /// ```
+        ///
+        /// ```rust,should_panic
+        /// parse_port("nope");
+        /// ```
+        ///
+        /// ~~~text
+        /// not code
+        /// ~~~
+        ///
+        /// ```no_run,edition2021
+        /// # let server = start();
+        /// ```
+        pub fn parse_port(value: &str) -> u16 {
+            value.parse().unwrap()
+        }
+    "#;
+
+    let syntax_tree = syn::parse_file(code).expect("Failed to parse code");
+    let config = create_test_config();
+    let mut visitor = CodeElementVisitor::new(PathBuf::from("test.rs"), &config);
+
+    visitor.visit_file(&syntax_tree);
+    let elements = visitor.into_elements();
+    let examples = &elements[0].doc_examples;
+
+    assert_eq!(examples.len(), 4);
+
+    // Indentation and blank lines inside the block are preserved.
+    assert_eq!(examples[0].language, "rust");
+    assert_eq!(
+        examples[0].code,
+        "let port = parse_port(\"8080\");\n\nif port > 0 {\n    println!(\"{}\", port);\n}"
+    );
+    assert!(!examples[0].should_panic && !examples[0].no_run);
+
+    assert_eq!(examples[1].language, "rust");
+    assert!(examples[1].should_panic);
+
+    assert_eq!(examples[2].language, "text");
+    assert_eq!(examples[2].code, "not code");
+
+    assert_eq!(examples[3].language, "rust");
+    assert!(examples[3].no_run);
+    assert_eq!(examples[3].code, "# let server = start();");
+}
+
+#[test]
+fn test_doc_examples_unterminated_block() {
+    let lines = ["Example:", "```ignore", "do_something();"];
+    let examples = rustex_core::visitors::parse_doc_examples(&lines);
+
+    assert_eq!(examples.len(), 1);
+    assert!(examples[0].ignore);
+    assert_eq!(examples[0].code, "do_something();");
+}
+
+const CFG_CODE: &str = r#"
+    #[cfg(feature = "serde")]
+    pub mod serialization {
+        pub fn to_json() {}
+    }
+
+    #[cfg(all(unix, not(feature = "minimal")))]
+    pub fn unix_only() {}
+
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn spawn() {}
+
+    pub fn always() {}
+"#;
+
+fn extract_with_features(cfg_features: Option<Vec<String>>) -> Vec<rustex_core::CodeElement> {
+    let mut config = create_test_config();
+    config.cfg_features = cfg_features;
+    let mut visitor = CodeElementVisitor::new(PathBuf::from("test.rs"), &config);
+    visitor.visit_file(&syn::parse_file(CFG_CODE).expect("Failed to parse code"));
+    visitor.into_elements()
+}
+
+#[test]
+fn test_cfg_predicates_recorded() {
+    let elements = extract_with_features(None);
+    let cfg_of = |name: &str| -> Vec<String> {
+        let element = elements.iter().find(|e| e.name == name).unwrap();
+        element
+            .cfg_predicates
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    };
+
+    // Items nested in a gated module inherit its predicate.
+    assert_eq!(cfg_of("to_json"), vec![r#"feature = "serde""#]);
+    assert_eq!(
+        cfg_of("unix_only"),
+        vec![r#"all(unix, not(feature = "minimal"))"#]
+    );
+    assert!(cfg_of("always").is_empty());
+
+    let spawn = elements.iter().find(|e| e.name == "spawn").unwrap();
+    assert!(matches!(&spawn.cfg_predicates[0], CfgPredicate::Any(options) if options.len() == 2));
+}
+
+#[test]
+fn test_cfg_features_simulation() {
+    let names = |features: &[&str]| -> Vec<String> {
+        let features = features.iter().map(|f| f.to_string()).collect();
+        extract_with_features(Some(features))
+            .into_iter()
+            .map(|e| e.name)
+            .collect()
+    };
+
+    // Predicates on anything but features can't be decided, so `unix_only` is kept.
+    assert_eq!(names(&[]), vec!["unix_only", "always"]);
+    assert_eq!(
+        names(&["serde", "tokio"]),
+        vec!["serialization", "to_json", "unix_only", "spawn", "always"]
+    );
+    assert_eq!(names(&["minimal"]), vec!["always"]);
+}
+
+#[test]
+fn test_attributes_are_parsed() {
+    let code = r#"
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename = "config", rename_all(serialize = "camelCase"))]
+#[repr(align(8))]
+#[deprecated = "use NewConfig"]
+pub struct Config;
+"#;
+    let config = create_test_config();
+    let mut visitor = CodeElementVisitor::new(PathBuf::from("test.rs"), &config);
+    visitor.visit_file(&syn::parse_file(code).expect("Failed to parse code"));
+    let elements = visitor.into_elements();
+    let element = &elements[0];
+
+    assert_eq!(element.attribute_info.len(), element.attributes.len());
+    let derive = &element.attribute_info[0];
+    assert!(derive.is_derive);
+    assert_eq!(
+        derive.derived_traits,
+        vec!["Debug", "Clone", "serde::Serialize"]
+    );
+    assert!(element.derives("Serialize"));
+    assert!(element.derives("serde::Serialize"));
+    assert!(!element.derives("Default"));
+
+    let serde = element.attribute("serde").unwrap();
+    assert!(!serde.is_derive);
+    assert_eq!(
+        serde.arg("rename").unwrap().value.as_deref(),
+        Some("config")
+    );
+    let rename_all = serde.arg("rename_all").unwrap();
+    assert_eq!(rename_all.args[0].name, "serialize");
+    assert_eq!(rename_all.args[0].value.as_deref(), Some("camelCase"));
+
+    // `align(8)` isn't a meta item, so the raw tokens are kept.
+    let repr = element.attribute("repr").unwrap();
+    assert_eq!(repr.arg("align").unwrap().value.as_deref(), Some("8"));
+
+    let deprecated = element.attribute("deprecated").unwrap();
+    assert_eq!(deprecated.value.as_deref(), Some("use NewConfig"));
+    assert!(deprecated.args.is_empty());
+}
+
+#[test]
+fn test_element_type_and_name_filters() {
+    let code = r#"
+        pub trait RequestHandler {}
+        pub trait Storage {}
+        pub struct ErrorHandler;
+
+        pub mod handlers {
+            pub trait EventHandler {}
+            pub trait TestHandler {}
+        }
+
+        impl RequestHandler for ErrorHandler {}
+    "#;
+
+    let syntax_tree = syn::parse_file(code).expect("Failed to parse code");
+    let mut config = create_test_config();
+    config.filters.element_types = vec!["trait".to_string()];
+    config.filters.name_patterns = vec!["Handler$".to_string()];
+    config.filters.exclude_names = vec!["^Test".to_string()];
+    let mut visitor = CodeElementVisitor::new(PathBuf::from("src/lib.rs"), &config);
+
+    visitor.visit_file(&syntax_tree);
+    let elements = visitor.into_elements();
+
+    let names: Vec<&str> = elements.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["RequestHandler", "EventHandler"]);
+    assert!(elements
+        .iter()
+        .all(|e| e.element_type == ElementType::Trait));
+    // Items inside filtered-out modules keep their qualified names.
+    assert_eq!(
+        elements[1].hierarchy.qualified_name,
+        "crate::handlers::EventHandler"
+    );
+
+    config.filters.element_types = vec!["type_alias".to_string(), "Closure".to_string()];
+    assert!(config.validate().is_ok());
+    config.filters.element_types = vec!["method".to_string()];
+    assert!(config.validate().is_err());
+    config.filters.element_types.clear();
+    config.filters.name_patterns = vec!["(".to_string()];
+    assert!(config.validate().is_err());
+}
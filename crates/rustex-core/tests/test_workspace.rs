@@ -0,0 +1,139 @@
+//! Tests for Cargo workspace discovery and workspace-aware extraction.
+
+#![allow(clippy::field_reassign_with_default)]
+
+use rustex_core::workspace::{discover_workspace, member_for_path};
+use rustex_core::{AstExtractor, ExtractorConfig, RustExError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn write_crate(root: &Path, dir: &str, manifest: &str, lib: &str) {
+    let crate_dir = root.join(dir);
+    fs::create_dir_all(crate_dir.join("src")).unwrap();
+    fs::write(crate_dir.join("Cargo.toml"), manifest).unwrap();
+    fs::write(crate_dir.join("src/lib.rs"), lib).unwrap();
+}
+
+/// Create a two-crate workspace where `app` depends on `base`.
+fn create_workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/*\"]\nresolver = \"2\"\n",
+    )
+    .unwrap();
+
+    write_crate(
+        root,
+        "crates/base",
+        "[package]\nname = \"base\"\nversion = \"0.2.0\"\nedition = \"2021\"\n",
+        "pub struct Config;\n\npub fn load() -> Config {\n    Config\n}\n",
+    );
+    write_crate(
+        root,
+        "crates/app",
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nbase = { path = \"../base\" }\n",
+        "pub fn run() {\n    let _ = base::load();\n}\n",
+    );
+
+    temp_dir
+}
+
+#[test]
+fn test_discover_workspace_members() {
+    let temp_dir = create_workspace();
+
+    let members = discover_workspace(temp_dir.path()).unwrap().unwrap();
+    let names: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["app", "base"]);
+
+    let app = &members[0];
+    assert_eq!(app.relative_path, PathBuf::from("crates/app"));
+    assert_eq!(
+        app.workspace_dependencies,
+        vec![("base".to_string(), "normal".to_string())]
+    );
+
+    let owner = member_for_path(&members, Path::new("crates/base/src/lib.rs")).unwrap();
+    assert_eq!(owner.name, "base");
+    assert!(member_for_path(&members, Path::new("build.rs")).is_none());
+}
+
+#[test]
+fn test_discover_workspace_non_workspace() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"single\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+
+    assert!(discover_workspace(temp_dir.path()).unwrap().is_none());
+}
+
+#[test]
+fn test_extract_workspace_sections() {
+    let temp_dir = create_workspace();
+    let extractor = AstExtractor::new(ExtractorConfig::default(), temp_dir.path().to_path_buf());
+
+    let ast = extractor.extract_project().unwrap();
+    let workspace = ast.workspace.expect("workspace section should be present");
+
+    assert_eq!(workspace.crates.len(), 2);
+    let base = workspace.crates.iter().find(|c| c.name == "base").unwrap();
+    assert_eq!(base.version, "0.2.0");
+    assert_eq!(base.files, vec![PathBuf::from("crates/base/src/lib.rs")]);
+    assert_eq!(base.metrics.total_structs, 1);
+    assert_eq!(base.metrics.total_functions, 1);
+
+    assert_eq!(workspace.dependency_edges.len(), 1);
+    assert_eq!(workspace.dependency_edges[0].from, "app");
+    assert_eq!(workspace.dependency_edges[0].to, "base");
+
+    // Project metrics are the rollup across all crates.
+    let crate_functions: usize = workspace
+        .crates
+        .iter()
+        .map(|c| c.metrics.total_functions)
+        .sum();
+    assert_eq!(ast.metrics.total_functions, crate_functions);
+}
+
+#[test]
+fn test_extract_with_crate_filter() {
+    let temp_dir = create_workspace();
+    let mut config = ExtractorConfig::default();
+    config.crates = vec!["app".to_string()];
+
+    let ast = AstExtractor::new(config, temp_dir.path().to_path_buf())
+        .extract_project()
+        .unwrap();
+
+    assert_eq!(ast.files.len(), 1);
+    assert_eq!(
+        ast.files[0].relative_path,
+        PathBuf::from("crates/app/src/lib.rs")
+    );
+
+    let workspace = ast.workspace.unwrap();
+    assert_eq!(workspace.crates.len(), 1);
+    assert_eq!(workspace.crates[0].name, "app");
+    // Edges to crates outside the filter are dropped.
+    assert!(workspace.dependency_edges.is_empty());
+}
+
+#[test]
+fn test_extract_with_unknown_crate() {
+    let temp_dir = create_workspace();
+    let mut config = ExtractorConfig::default();
+    config.crates = vec!["missing".to_string()];
+
+    let result = AstExtractor::new(config, temp_dir.path().to_path_buf()).extract_project();
+    match result {
+        Err(RustExError::Config(message)) => assert!(message.contains("missing")),
+        other => panic!("expected config error, got {:?}", other.map(|_| ())),
+    }
+}
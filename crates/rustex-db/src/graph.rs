@@ -522,7 +522,7 @@ impl DependencyAnalyzer {
         }
         
         let mut most_dependent: Vec<_> = dependency_counts.into_iter().collect();
-        most_dependent.sort_by(|a, b| b.1.cmp(&a.1));
+        most_dependent.sort_by_key(|b| std::cmp::Reverse(b.1));
         most_dependent.truncate(10);
 
         Ok(DependencyAnalysis {
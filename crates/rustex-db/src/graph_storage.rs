@@ -3,7 +3,7 @@
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 use std::collections::HashMap;
-use rustex_core::{ProjectAst, ElementType, ReferenceType};
+use rustex_core::{ProjectAst, ElementType, ReferenceType, CallKind};
 use crate::error::{DatabaseError, Result};
 use crate::storage::AstStorage;
 
@@ -208,7 +208,7 @@ impl GraphStorage {
         types
     }
 
-    /// Analyze call relationships from cross-references.
+    /// Analyze call relationships from the call lists of function bodies.
     fn analyze_call_relationships(
         &self,
         project_ast: &ProjectAst,
@@ -216,19 +216,27 @@ impl GraphStorage {
     ) -> Result<Vec<CallChainInfo>> {
         let mut call_chains = Vec::new();
 
-        for cross_ref in &project_ast.cross_references {
-            if matches!(cross_ref.reference_type, ReferenceType::FunctionCall) {
-                if let Some(caller) = function_elements.get(&cross_ref.from_element_id) {
-                    if let Some(ref to_id) = cross_ref.to_element_id {
-                        if let Some(callee) = function_elements.get(to_id) {
-                            call_chains.push(CallChainInfo {
-                                caller_id: caller.id.clone(),
-                                callee_id: callee.id.clone(),
-                                call_type: self.determine_call_type(&cross_ref.reference_text),
-                                call_sites: vec![cross_ref.location.line_start as i32],
-                                context: cross_ref.context.scope.clone(),
-                            });
-                        }
+        for file_ast in &project_ast.files {
+            for element in &file_ast.elements {
+                let Some(caller) = function_elements.get(&element.id) else {
+                    continue;
+                };
+                for call in &element.calls {
+                    // Macros have no function element to link to.
+                    if matches!(call.kind, CallKind::Macro) {
+                        continue;
+                    }
+                    if let Some(callee) = self.resolve_callee(&call.name, function_elements) {
+                        call_chains.push(CallChainInfo {
+                            caller_id: caller.id.clone(),
+                            callee_id: callee.id.clone(),
+                            call_type: match call.kind {
+                                CallKind::Method => "method".to_string(),
+                                _ => self.determine_call_type(&call.name),
+                            },
+                            call_sites: vec![call.line as i32],
+                            context: caller.qualified_name.clone(),
+                        });
                     }
                 }
             }
@@ -238,6 +246,25 @@ impl GraphStorage {
         self.aggregate_call_chains(call_chains)
     }
 
+    /// Find the function a call name refers to, when exactly one candidate matches.
+    ///
+    /// Call names are recorded as written, so a name matches a function whose
+    /// qualified name ends with it.
+    fn resolve_callee<'a>(
+        &self,
+        name: &str,
+        function_elements: &'a HashMap<String, ElementInfo>,
+    ) -> Option<&'a ElementInfo> {
+        let suffix = format!("::{}", name);
+        let mut candidates = function_elements.values().filter(|function| {
+            function.name == name
+                || function.qualified_name == name
+                || function.qualified_name.ends_with(&suffix)
+        });
+        let callee = candidates.next()?;
+        candidates.next().is_none().then_some(callee)
+    }
+
     /// Analyze dependency relationships from cross-references.
     fn analyze_dependencies(
         &self,
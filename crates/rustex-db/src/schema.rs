@@ -307,7 +307,11 @@ impl ElementRecord {
             halstead_metrics: element.complexity_metrics.as_ref()
                 .map(|m| serde_json::to_value(&m.halstead).unwrap_or_default()),
             doc_comments: element.doc_comments.clone(),
-            inline_comments: element.inline_comments.clone(),
+            inline_comments: element
+                .inline_comments
+                .iter()
+                .map(|comment| comment.text.clone())
+                .collect(),
             attributes: element.attributes.clone(),
             dependencies: element.dependencies.clone(),
             generic_params: element.generic_params.clone(),
@@ -347,12 +347,6 @@ impl GraphTraversalEngine {
         Ok(path)
     }
 
-    /// Calculate the strength of a cycle.
-    async fn calculate_cycle_strength(&self, cycle_path: &[Uuid]) -> Result<f64> {
-        // Simplified calculation - could be enhanced with actual edge weights
-        Ok(1.0 / cycle_path.len() as f64)
-    }
-
     /// Calculate the total weight of a path.
     async fn calculate_path_weight(&self, path: &[Uuid]) -> Result<f64> {
         // Simplified calculation - could query actual edge weights
@@ -511,7 +505,7 @@ pub struct Path {
 }
 
 /// Node for Dijkstra's algorithm
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq)]
 struct DijkstraNode {
     distance: f64,
     element_id: Uuid,
@@ -525,6 +519,12 @@ impl Ord for DijkstraNode {
     }
 }
 
+impl PartialOrd for DijkstraNode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
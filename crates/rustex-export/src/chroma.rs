@@ -0,0 +1,126 @@
+//! Export to Chroma, through its v2 REST API.
+
+use crate::{check, embedding, payload, VectorSink};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rustex_formats::RagChunk;
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+
+/// A Chroma collection in the default tenant and database, whose records are
+/// the chunks.
+///
+/// Records are stored with the chunk's context-enriched content as their
+/// document. Chroma metadata only holds strings, numbers and booleans, so
+/// lists are joined with commas and other values stored as JSON.
+pub struct ChromaSink {
+    client: reqwest::Client,
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+    /// ID of the collection, known once it is created
+    collection_id: OnceLock<String>,
+}
+
+impl ChromaSink {
+    /// The collection `collection` of the Chroma server at `url`, such as
+    /// `http://localhost:8000`, authenticated with `api_key` if given.
+    pub fn new(url: &str, collection: &str, api_key: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().build()?,
+            url: format!(
+                "{}/api/v2/tenants/default_tenant/databases/default_database/collections",
+                url.trim_end_matches('/')
+            ),
+            collection: collection.to_string(),
+            api_key,
+            collection_id: OnceLock::new(),
+        })
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.post(format!("{}{}", self.url, path));
+        match &self.api_key {
+            Some(api_key) => request.header("x-chroma-token", api_key),
+            None => request,
+        }
+    }
+}
+
+/// The metadata of `chunk`, as Chroma stores it.
+pub fn chroma_metadata(chunk: &RagChunk) -> Result<Map<String, Value>> {
+    let mut payload = payload(chunk)?;
+    // The document already holds the context-enriched content.
+    payload.remove("content_with_context");
+    Ok(payload
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::Null => return None,
+                Value::Array(items) => Value::from(
+                    items
+                        .iter()
+                        .map(|item| match item {
+                            Value::String(item) => item.clone(),
+                            item => item.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+                Value::Object(_) => Value::from(value.to_string()),
+                value => value,
+            };
+            Some((key, value))
+        })
+        .collect())
+}
+
+#[async_trait]
+impl VectorSink for ChromaSink {
+    fn name(&self) -> &str {
+        "Chroma"
+    }
+
+    async fn prepare(&self, _dimensions: usize) -> Result<()> {
+        let body = json!({
+            "name": self.collection,
+            "get_or_create": true,
+            "metadata": { "hnsw:space": "cosine" },
+        });
+        let response = self.post("").json(&body).send().await?;
+        let collection: Value = check(response, "Failed to create Chroma collection")
+            .await?
+            .json()
+            .await?;
+        let id = collection["id"]
+            .as_str()
+            .context("Chroma returned a collection without ID")?;
+        let _ = self.collection_id.set(id.to_string());
+        Ok(())
+    }
+
+    async fn upsert(&self, chunks: &[RagChunk]) -> Result<()> {
+        let Some(collection_id) = self.collection_id.get() else {
+            anyhow::bail!("Chroma collection {} isn't prepared", self.collection);
+        };
+        let body = json!({
+            "ids": chunks.iter().map(|chunk| &chunk.id).collect::<Vec<_>>(),
+            "embeddings": chunks.iter().map(embedding).collect::<Vec<_>>(),
+            "documents": chunks
+                .iter()
+                .map(|chunk| &chunk.content_with_context)
+                .collect::<Vec<_>>(),
+            "metadatas": chunks
+                .iter()
+                .map(chroma_metadata)
+                .collect::<Result<Vec<_>>>()?,
+        });
+        let response = self
+            .post(&format!("/{}/upsert", collection_id))
+            .json(&body)
+            .send()
+            .await?;
+        check(response, "Failed to upsert Chroma records").await?;
+        Ok(())
+    }
+}
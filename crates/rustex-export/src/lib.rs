@@ -0,0 +1,126 @@
+//! # RustEx Export
+//!
+//! Export of RAG chunks to vector databases, so chunks from
+//! `rustex extract --format rag` can be indexed without ingestion scripts.
+//!
+//! Each database is a [`VectorSink`]: [`QdrantSink`], [`PgVectorSink`] and
+//! [`ChromaSink`]. Chunks are upserted by their ID, so exporting a project
+//! again updates the chunks that changed in place instead of duplicating
+//! them. Chunks need embeddings, as added by `RagFormatter` with an
+//! embedding provider.
+//!
+//! ```rust,no_run
+//! use rustex_export::{export_chunks, QdrantSink};
+//! # async fn example(chunks: Vec<rustex_formats::RagChunk>) -> anyhow::Result<()> {
+//! let sink = QdrantSink::new("http://localhost:6333", "my-project", None)?;
+//! let exported = export_chunks(&sink, &chunks, 100).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod chroma;
+pub mod pgvector;
+pub mod qdrant;
+
+pub use chroma::ChromaSink;
+pub use pgvector::PgVectorSink;
+pub use qdrant::QdrantSink;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rustex_formats::RagChunk;
+use serde_json::{Map, Value};
+
+/// A vector database that RAG chunks are upserted into.
+#[async_trait]
+pub trait VectorSink: Send + Sync {
+    /// Name of the database, for messages.
+    fn name(&self) -> &str;
+
+    /// Create the collection or table of the chunks, for vectors of
+    /// `dimensions` dimensions, unless it exists.
+    async fn prepare(&self, dimensions: usize) -> Result<()>;
+
+    /// Insert `chunks`, replacing the stored chunks with the same IDs.
+    async fn upsert(&self, chunks: &[RagChunk]) -> Result<()>;
+}
+
+/// Upsert the embedded `chunks` into `sink`, `batch_size` chunks per
+/// request, returning the number of chunks exported.
+pub async fn export_chunks(
+    sink: &dyn VectorSink,
+    chunks: &[RagChunk],
+    batch_size: usize,
+) -> Result<usize> {
+    let Some(first) = chunks.first() else {
+        return Ok(0);
+    };
+    let expected = dimensions(first)?;
+    for chunk in chunks {
+        let found = dimensions(chunk)?;
+        if found != expected {
+            anyhow::bail!(
+                "Chunk {} has {} dimensions instead of {}; export chunks of one embedding model",
+                chunk.id,
+                found,
+                expected
+            );
+        }
+    }
+
+    sink.prepare(expected).await?;
+    for (index, batch) in chunks.chunks(batch_size.max(1)).enumerate() {
+        sink.upsert(batch).await?;
+        tracing::debug!(
+            "Upserted batch {} of {} chunks into {}",
+            index + 1,
+            batch.len(),
+            sink.name()
+        );
+    }
+    Ok(chunks.len())
+}
+
+/// Dimensions of the embedding of `chunk`.
+fn dimensions(chunk: &RagChunk) -> Result<usize> {
+    match &chunk.embedding {
+        Some(embedding) => Ok(embedding.len()),
+        None => anyhow::bail!(
+            "Chunk {} has no embedding; embed the chunks with --embed first",
+            chunk.id
+        ),
+    }
+}
+
+/// The embedding of `chunk`, checked by [`export_chunks`].
+fn embedding(chunk: &RagChunk) -> &[f32] {
+    chunk.embedding.as_deref().unwrap_or_default()
+}
+
+/// The data stored with a chunk besides its vector: its ID, text and
+/// metadata, with the metadata fields at the top level.
+fn payload(chunk: &RagChunk) -> Result<Map<String, Value>> {
+    let mut payload = Map::new();
+    payload.insert("chunk_id".to_string(), Value::from(chunk.id.as_str()));
+    payload.insert("content".to_string(), Value::from(chunk.content.as_str()));
+    payload.insert(
+        "content_with_context".to_string(),
+        Value::from(chunk.content_with_context.as_str()),
+    );
+    if let Value::Object(metadata) = serde_json::to_value(&chunk.metadata)? {
+        payload.extend(metadata);
+    }
+    Ok(payload)
+}
+
+/// `response` if it is a success, and an error of `message` and the
+/// response body otherwise.
+async fn check(response: reqwest::Response, message: &str) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow::anyhow!("{}", body.trim()))
+        .with_context(|| format!("{} (status {})", message, status))
+}
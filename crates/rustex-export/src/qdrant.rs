@@ -0,0 +1,106 @@
+//! Export to Qdrant, through its REST API.
+
+use crate::{check, embedding, payload, VectorSink};
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use rustex_formats::RagChunk;
+use serde_json::json;
+use uuid::{Builder, Uuid};
+
+/// A Qdrant collection, whose points are the chunks.
+///
+/// Qdrant point IDs are UUIDs, so each point's ID is derived from the MD5
+/// hash of its chunk's ID, which is kept in the `chunk_id` payload field.
+/// Collections are created with cosine distance.
+pub struct QdrantSink {
+    client: reqwest::Client,
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+}
+
+impl QdrantSink {
+    /// The collection `collection` of the Qdrant server at `url`, such as
+    /// `http://localhost:6333`, authenticated with `api_key` if given.
+    pub fn new(url: &str, collection: &str, api_key: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().build()?,
+            url: url.trim_end_matches('/').to_string(),
+            collection: collection.to_string(),
+            api_key,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(
+            method,
+            format!("{}/collections/{}{}", self.url, self.collection, path),
+        );
+        match &self.api_key {
+            Some(api_key) => request.header("api-key", api_key),
+            None => request,
+        }
+    }
+}
+
+/// The Qdrant point ID of the chunk with ID `chunk_id`.
+pub fn point_id(chunk_id: &str) -> Uuid {
+    Builder::from_md5_bytes(md5::compute(chunk_id).0).into_uuid()
+}
+
+#[async_trait]
+impl VectorSink for QdrantSink {
+    fn name(&self) -> &str {
+        "Qdrant"
+    }
+
+    async fn prepare(&self, dimensions: usize) -> Result<()> {
+        let response = self.request(reqwest::Method::GET, "").send().await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response, "Failed to read Qdrant collection").await?;
+            return Ok(());
+        }
+        let body = json!({ "vectors": { "size": dimensions, "distance": "Cosine" } });
+        let response = self
+            .request(reqwest::Method::PUT, "")
+            .json(&body)
+            .send()
+            .await?;
+        check(response, "Failed to create Qdrant collection").await?;
+        Ok(())
+    }
+
+    async fn upsert(&self, chunks: &[RagChunk]) -> Result<()> {
+        let points = chunks
+            .iter()
+            .map(|chunk| {
+                Ok(json!({
+                    "id": point_id(&chunk.id),
+                    "vector": embedding(chunk),
+                    "payload": payload(chunk)?,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let response = self
+            .request(reqwest::Method::PUT, "/points?wait=true")
+            .json(&json!({ "points": points }))
+            .send()
+            .await?;
+        check(response, "Failed to upsert Qdrant points").await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_id() {
+        let id = point_id("chunk_crate::net::connect_1a2b");
+        assert_eq!(id, point_id("chunk_crate::net::connect_1a2b"));
+        assert_ne!(id, point_id("chunk_crate::net::connect_1a2b_part1"));
+        assert_eq!(id.get_version_num(), 3);
+    }
+}
@@ -0,0 +1,11 @@
+//! Generates the Protobuf output types from `proto/rustex.proto`.
+
+fn main() -> std::io::Result<()> {
+    println!("cargo:rerun-if-changed=proto/rustex.proto");
+
+    let protoc = protoc_bin_vendored::protoc_bin_path()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+    prost_build::Config::new()
+        .protoc_executable(protoc)
+        .compile_protos(&["proto/rustex.proto"], &["proto"])
+}
@@ -0,0 +1,472 @@
+//! The public API of a project for RAG documents.
+//!
+//! Items are public API when they can be imported from outside their crate,
+//! at their own path or through `pub use` re-exports. Each one gets a
+//! stability from its attributes and the segments of its path:
+//!
+//! | Stability | Items |
+//! |-----------|-------|
+//! | `Deprecated` | Marked `#[deprecated]` |
+//! | `Unstable` | Marked `#[doc(hidden)]`, or a segment starting with `__` or naming `unstable` |
+//! | `Experimental` | A segment naming `experimental`, `nightly` or `preview` |
+//! | `Stable` | Any other |
+//!
+//! When the `api-stability` plugin ran, items with a signature changed since
+//! its baseline are also unstable and new items experimental.
+//!
+//! Entry points are the `main` functions of binaries, the items a library
+//! exports from its root and the macros of procedural macro crates.
+
+use crate::mdbook::{crate_name, crate_path_name};
+use crate::rag::{chunk_id, ApiComplexityMetrics, ApiElement, ApiStability, ApiSurface};
+use crate::semantics::identifier_words;
+use rustex_core::api_compat::ApiStatus;
+use rustex_core::{CodeElement, ElementType, FileAst, FileKind, ProjectAst};
+use std::path::Path;
+
+/// Words of path segments marking unstable items.
+const UNSTABLE_WORDS: &[&str] = &["unstable"];
+
+/// Words of path segments marking experimental items.
+const EXPERIMENTAL_WORDS: &[&str] = &["experimental", "nightly", "preview"];
+
+/// Attributes defining procedural macros.
+const PROC_MACRO_ATTRIBUTES: &[&str] = &["proc_macro", "proc_macro_attribute", "proc_macro_derive"];
+
+/// The public API of `project_ast`.
+pub(crate) fn api_surface(project_ast: &ProjectAst) -> ApiSurface {
+    let mut public_functions = Vec::new();
+    let mut public_types = Vec::new();
+    let mut public_traits = Vec::new();
+    let mut modules = Vec::new();
+    let mut entry_points = Vec::new();
+    // Elements of the functions, types and traits, for the metrics.
+    let mut items = Vec::new();
+
+    for file in &project_ast.files {
+        for element in &file.elements {
+            for entry_point in entry_points_of(element, file) {
+                if !entry_points.contains(&entry_point) {
+                    entry_points.push(entry_point);
+                }
+            }
+            // Public items in private modules are only API when re-exported.
+            let exported_paths = &element.hierarchy.namespace.exported_paths;
+            if exported_paths.is_empty() {
+                continue;
+            }
+            let list = match element.element_type {
+                ElementType::Function => &mut public_functions,
+                ElementType::Struct | ElementType::Enum => &mut public_types,
+                ElementType::Trait => &mut public_traits,
+                ElementType::Module => &mut modules,
+                _ => continue,
+            };
+            list.push(ApiElement {
+                name: element.name.clone(),
+                qualified_name: element.hierarchy.qualified_name.clone(),
+                exported_paths: exported_paths.clone(),
+                element_type: format!("{:?}", element.element_type),
+                signature: element.signature.clone().unwrap_or_default(),
+                documentation: element.doc_comments.join("\n"),
+                chunk_id: chunk_id(element),
+                stability: stability(element),
+                usage_examples: usage_examples(element),
+            });
+            if element.element_type != ElementType::Module {
+                items.push(element);
+            }
+        }
+    }
+
+    ApiSurface {
+        complexity_metrics: complexity_metrics(&items),
+        public_functions,
+        public_types,
+        public_traits,
+        modules,
+        entry_points,
+    }
+}
+
+/// The stability of `element`, from its attributes, the segments of its
+/// path and its API status.
+fn stability(element: &CodeElement) -> ApiStability {
+    let status = ApiStatus::of(element);
+    if element.deprecation.is_some() || status == Some(ApiStatus::RemovedSoon) {
+        return ApiStability::Deprecated;
+    }
+    let doc_hidden = element.attribute_info.iter().any(|attribute| {
+        attribute.name == "doc" && attribute.args.iter().any(|arg| arg.name == "hidden")
+    });
+    let segments: Vec<&str> = element.hierarchy.qualified_name.split("::").collect();
+    let names = |words: &[&str]| {
+        segments.iter().any(|segment| {
+            identifier_words(segment)
+                .iter()
+                .any(|word| words.contains(&word.as_str()))
+        })
+    };
+    let unstable_path = names(UNSTABLE_WORDS) || segments.iter().any(|s| s.starts_with("__"));
+    if doc_hidden || unstable_path || status == Some(ApiStatus::SignatureChanged) {
+        ApiStability::Unstable
+    } else if names(EXPERIMENTAL_WORDS) || status == Some(ApiStatus::New) {
+        ApiStability::Experimental
+    } else {
+        ApiStability::Stable
+    }
+}
+
+/// The Rust examples of the docs of `element` that compile, without the
+/// lines rustdoc hides.
+fn usage_examples(element: &CodeElement) -> Vec<String> {
+    element
+        .doc_examples
+        .iter()
+        .filter(|example| example.language == "rust" && !example.compile_fail)
+        .map(|example| {
+            example
+                .code
+                .lines()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                .filter(|line| *line != "#" && !line.trim_start().starts_with("# "))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .filter(|code| !code.trim().is_empty())
+        .collect()
+}
+
+/// The entry points `element`, an element of `file`, is: the `main`
+/// function of a binary, an item exported from a library's root or a
+/// procedural macro, as `my_crate::name`, or `binary::main` for binaries.
+fn entry_points_of(element: &CodeElement, file: &FileAst) -> Vec<String> {
+    let crate_name = crate_name(element);
+    let crate_path = crate_path_name(element);
+    let is_root_item = element.hierarchy.module_path == "crate"
+        && element.hierarchy.parent_id.is_none()
+        && element.element_type != ElementType::Impl;
+    if file.kind == FileKind::BuildScript {
+        return Vec::new();
+    }
+    if let Some(binary) = binary_name(&file.relative_path, crate_name) {
+        if is_root_item && element.element_type == ElementType::Function && element.name == "main" {
+            return vec![format!("{}::main", binary)];
+        }
+        return Vec::new();
+    }
+    if file.kind == FileKind::ProcMacro {
+        if !is_root_item {
+            return Vec::new();
+        }
+        return proc_macro_names(element)
+            .into_iter()
+            .map(|name| format!("{}::{}", crate_path, name))
+            .collect();
+    }
+    // Items defined in other modules count when re-exported from the root.
+    let root_path = format!("crate::{}", element.name);
+    let exported_paths = &element.hierarchy.namespace.exported_paths;
+    if exported_paths.contains(&root_path) {
+        vec![format!("{}::{}", crate_path, element.name)]
+    } else {
+        Vec::new()
+    }
+}
+
+/// The name of the binary whose root is the file at `relative_path`, in a
+/// package of the crate `crate_name`: the package's for `src/main.rs`, and
+/// the file's or directory's for `src/bin/name.rs` and `src/bin/name/main.rs`.
+fn binary_name<'a>(relative_path: &'a Path, crate_name: &'a str) -> Option<&'a str> {
+    let mut components = relative_path
+        .iter()
+        .rev()
+        .map(|c| c.to_str().unwrap_or_default());
+    let file = components.next()?;
+    let parent = components.next()?;
+    let grandparent = components.next();
+    match (file, parent, grandparent) {
+        ("main.rs", "src", _) => Some(crate_name),
+        (file, "bin", Some("src")) => file.strip_suffix(".rs"),
+        ("main.rs", binary, Some("bin")) => Some(binary),
+        _ => None,
+    }
+}
+
+/// The names of the macros `element` defines, if it is the function of a
+/// procedural macro: its own name, or the derive's for derive macros.
+fn proc_macro_names(element: &CodeElement) -> Vec<String> {
+    let attribute = element
+        .attribute_info
+        .iter()
+        .find(|attribute| PROC_MACRO_ATTRIBUTES.contains(&attribute.name.as_str()));
+    match attribute {
+        Some(attribute) if attribute.name == "proc_macro_derive" => attribute
+            .args
+            .iter()
+            .take(1)
+            .map(|arg| arg.name.clone())
+            .collect(),
+        Some(_) => vec![element.name.clone()],
+        None => Vec::new(),
+    }
+}
+
+/// Metrics of the public functions, types and traits in `items`.
+fn complexity_metrics(items: &[&CodeElement]) -> ApiComplexityMetrics {
+    let parameter_counts: Vec<usize> = items
+        .iter()
+        .filter(|element| element.element_type == ElementType::Function)
+        .map(|element| parameter_count(element.signature.as_deref().unwrap_or_default()))
+        .collect();
+    let share = |count: usize| match items.len() {
+        0 => 0.0,
+        total => (count as f64 / total as f64 * 100.0).round() / 100.0,
+    };
+    let avg_parameter_count = match parameter_counts.len() {
+        0 => 0.0,
+        functions => {
+            let average = parameter_counts.iter().sum::<usize>() as f64 / functions as f64;
+            (average * 100.0).round() / 100.0
+        }
+    };
+    ApiComplexityMetrics {
+        total_public_items: items.len(),
+        avg_parameter_count,
+        max_parameter_count: parameter_counts.iter().copied().max().unwrap_or(0),
+        generic_usage_ratio: share(items.iter().filter(|element| is_generic(element)).count()),
+        documentation_coverage: share(
+            items
+                .iter()
+                .filter(|element| !element.doc_comments.is_empty())
+                .count(),
+        ),
+    }
+}
+
+/// Whether `element` has generic parameters, or takes `impl Trait`
+/// arguments.
+fn is_generic(element: &CodeElement) -> bool {
+    let takes_impl = element.element_type == ElementType::Function
+        && element
+            .signature
+            .as_deref()
+            .and_then(|signature| signature.split_once('('))
+            .is_some_and(|(_, parameters)| parameters.contains("impl "));
+    !element.generic_params.is_empty() || takes_impl
+}
+
+/// The number of parameters of a function's `signature`, besides `self`.
+fn parameter_count(signature: &str) -> usize {
+    // Parameters start at the first parenthesis outside the generics.
+    let mut depth = 0usize;
+    let mut previous = ' ';
+    let mut parameters = None;
+    for (index, c) in signature.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if previous != '-' => depth = depth.saturating_sub(1),
+            '(' if depth == 0 => {
+                parameters = Some(&signature[index + 1..]);
+                break;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    let Some(parameters) = parameters else {
+        return 0;
+    };
+
+    let mut list = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut previous = ' ';
+    for (index, c) in parameters.char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            '>' if previous == '-' => {}
+            ')' if depth == 0 => {
+                list.push(&parameters[start..index]);
+                break;
+            }
+            ')' | ']' | '}' | '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                list.push(&parameters[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    list.iter()
+        .map(|parameter| parameter.trim())
+        .filter(|parameter| !parameter.is_empty() && !is_self(parameter))
+        .count()
+}
+
+/// Whether `parameter` is a `self` receiver, such as `&mut self` or
+/// `self: Arc<Self>`.
+fn is_self(parameter: &str) -> bool {
+    let name = parameter.split(':').next().unwrap_or_default();
+    name.trim_start_matches('&')
+        .split_whitespace()
+        .last()
+        .is_some_and(|word| word == "self")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::{AstExtractor, ExtractorConfig};
+    use std::fs;
+
+    const LIB: &str = r#"pub use inner::Reexported;
+
+/// Opens a connection.
+pub fn connect<T>(host: &str, port: u16, options: T) -> bool {
+    true
+}
+
+pub(crate) fn helper() {}
+
+fn private() {}
+
+pub(crate) struct Internal;
+
+pub trait Handler {}
+
+#[deprecated]
+pub fn old() {}
+
+#[doc(hidden)]
+pub fn hidden(value: u8) {}
+
+pub mod net {
+    pub struct Socket;
+
+    pub(crate) fn local() {}
+
+    pub(super) fn parent_only() {}
+
+    pub mod experimental {
+        pub fn probe() {}
+    }
+}
+
+mod inner {
+    pub struct Reexported;
+
+    pub struct Unreachable;
+}
+"#;
+
+    /// The API surface of a library crate with `LIB` as its root.
+    fn surface() -> ApiSurface {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"my-lib\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), LIB).unwrap();
+        let config = ExtractorConfig {
+            include_private: true,
+            ..ExtractorConfig::default()
+        };
+        let project = AstExtractor::new(config, root.to_path_buf())
+            .extract_project()
+            .unwrap();
+        api_surface(&project)
+    }
+
+    fn names(elements: &[ApiElement]) -> Vec<&str> {
+        elements
+            .iter()
+            .map(|element| element.qualified_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_only_reachable_pub_items() {
+        let surface = surface();
+        assert_eq!(
+            names(&surface.public_functions),
+            [
+                "crate::connect",
+                "crate::old",
+                "crate::hidden",
+                "crate::net::experimental::probe"
+            ]
+        );
+        assert_eq!(
+            names(&surface.public_types),
+            ["crate::net::Socket", "crate::inner::Reexported"]
+        );
+        assert_eq!(names(&surface.public_traits), ["crate::Handler"]);
+        assert_eq!(
+            names(&surface.modules),
+            ["crate::net", "crate::net::experimental"]
+        );
+        assert_eq!(
+            surface.public_types[1].exported_paths,
+            ["crate::Reexported"]
+        );
+    }
+
+    #[test]
+    fn test_stability() {
+        let surface = surface();
+        let stabilities: Vec<String> = surface
+            .public_functions
+            .iter()
+            .map(|element| format!("{:?}", element.stability))
+            .collect();
+        assert_eq!(
+            stabilities,
+            ["Stable", "Deprecated", "Unstable", "Experimental"]
+        );
+    }
+
+    #[test]
+    fn test_entry_points() {
+        let surface = surface();
+        assert_eq!(
+            surface.entry_points,
+            [
+                "my_lib::connect",
+                "my_lib::Handler",
+                "my_lib::old",
+                "my_lib::hidden",
+                "my_lib::net",
+                "my_lib::Reexported"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_complexity_metrics() {
+        let metrics = surface().complexity_metrics;
+        assert_eq!(metrics.total_public_items, 7);
+        assert_eq!(metrics.max_parameter_count, 3);
+        assert_eq!(metrics.avg_parameter_count, 1.0);
+        assert_eq!(metrics.generic_usage_ratio, 0.14);
+        assert_eq!(metrics.documentation_coverage, 0.14);
+    }
+
+    #[test]
+    fn test_parameter_count() {
+        assert_eq!(parameter_count("fn f()"), 0);
+        assert_eq!(parameter_count("fn f(&mut self, a: u8)"), 1);
+        assert_eq!(
+            parameter_count("fn f<F: Fn(u8) -> u8>(self: Arc<Self>, f: F, (a, b): (u8, u8))"),
+            2
+        );
+        assert_eq!(
+            parameter_count("fn f(map: HashMap<u8, Vec<u8>>, g: impl Fn() -> u8,)"),
+            2
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! Splitting of elements into RAG chunks.
+//!
+//! Chunk strategies other than [`ChunkStrategy::PerElement`] chunk the source
+//! of elements, read back from their files. Elements of up to
+//! `max_chunk_size` tokens make one chunk, and larger ones are cut into parts
+//! of whole lines of about `target_chunk_size` tokens, following the
+//! strategy.
+
+use crate::rag::{ChunkStrategy, RagConfig};
+use rustex_core::{CodeElement, ElementType};
+use std::ops::Range;
+use syn::spanned::Spanned;
+
+/// A line of source and its line number.
+type Line<'a> = (usize, &'a str);
+
+/// A part of the source of an element, chunked on its own.
+pub(crate) struct Part {
+    /// First line of the part in its file
+    pub line_start: usize,
+    /// Last line of the part in its file
+    pub line_end: usize,
+    pub content: String,
+}
+
+/// Estimate the number of tokens of `text`, at about 4 characters per token.
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// The source of `element` in `source`, the text of its file, split into
+/// parts by the strategy of `config`; `None` if the element's location
+/// doesn't match the source, as when the file changed since extraction.
+pub(crate) fn split_element(
+    element: &CodeElement,
+    source: &str,
+    config: &RagConfig,
+) -> Option<Vec<Part>> {
+    let location = &element.location;
+    let text = source
+        .get(location.byte_start..location.byte_end)
+        .filter(|text| !text.is_empty())?;
+    let lines: Vec<Line> = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (location.line_start + index, line))
+        .collect();
+    if lines.last()?.0 != location.line_end {
+        return None;
+    }
+
+    if estimate_tokens(text) <= config.max_chunk_size {
+        return Some(vec![part(&lines)]);
+    }
+    Some(match config.chunk_strategy {
+        ChunkStrategy::PerElement => vec![part(&lines)],
+        ChunkStrategy::TokenBudget => pack_lines(&lines, config),
+        ChunkStrategy::SlidingWindow => sliding_windows(element, &lines, config),
+        ChunkStrategy::Semantic => semantic_parts(text, &lines, config),
+    })
+}
+
+/// Up to `radius` lines of `source` before and after `part`.
+pub(crate) fn surrounding_lines<'a>(
+    source: &'a str,
+    part: &Part,
+    radius: usize,
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    if radius == 0 {
+        return (Vec::new(), Vec::new());
+    }
+    let first = part.line_start.saturating_sub(radius).max(1);
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .skip(first - 1)
+        .take_while(|(number, _)| *number <= part.line_end + radius);
+    let before = lines
+        .by_ref()
+        .take_while(|(number, _)| *number < part.line_start)
+        .map(|(_, line)| line)
+        .collect();
+    let after = lines
+        .filter(|(number, _)| *number > part.line_end)
+        .map(|(_, line)| line)
+        .collect();
+    (before, after)
+}
+
+/// Consecutive `lines` packed into parts of up to `target_chunk_size` tokens,
+/// each starting with the last `chunk_overlap_tokens` tokens of the one before.
+fn pack_lines(lines: &[Line], config: &RagConfig) -> Vec<Part> {
+    let tokens: Vec<usize> = lines.iter().map(|(_, line)| line_tokens(line)).collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut end = start + 1;
+        let mut size = tokens[start];
+        while end < lines.len() && size + tokens[end] <= config.target_chunk_size {
+            size += tokens[end];
+            end += 1;
+        }
+        parts.push(part(&lines[start..end]));
+        if end == lines.len() {
+            return parts;
+        }
+        // Step back over the overlap, but always move forward.
+        let mut next = end;
+        let mut overlap = 0;
+        while next > start + 1 && overlap + tokens[next - 1] <= config.chunk_overlap_tokens {
+            next -= 1;
+            overlap += tokens[next];
+        }
+        start = next;
+    }
+}
+
+/// Overlapping windows over the lines of a function, each after the first
+/// starting with the function's signature so it reads on its own. Other
+/// elements stay whole.
+fn sliding_windows(element: &CodeElement, lines: &[Line], config: &RagConfig) -> Vec<Part> {
+    if element.element_type != ElementType::Function {
+        return vec![part(lines)];
+    }
+    let mut windows = pack_lines(lines, config);
+    if let Some(signature) = &element.signature {
+        for window in windows.iter_mut().skip(1) {
+            window.content = format!("{} {{\n    // ...\n{}", signature, window.content);
+        }
+    }
+    windows
+}
+
+/// Parts made of whole statements of a function or items of an impl, trait
+/// or module, packed up to `target_chunk_size` tokens. Statements and items
+/// over `max_chunk_size` tokens, and elements without any, are split as by
+/// [`pack_lines`].
+fn semantic_parts(text: &str, lines: &[Line], config: &RagConfig) -> Vec<Part> {
+    let Some(units) = units(text, lines.len()) else {
+        return pack_lines(lines, config);
+    };
+    let tokens = |range: &Range<usize>| -> usize {
+        lines[range.clone()]
+            .iter()
+            .map(|(_, line)| line_tokens(line))
+            .sum()
+    };
+
+    let mut parts = Vec::new();
+    let mut group: Option<(Range<usize>, usize)> = None;
+    for unit in units {
+        let size = tokens(&unit);
+        if let Some((range, group_size)) = &mut group {
+            if *group_size + size <= config.target_chunk_size {
+                range.end = unit.end;
+                *group_size += size;
+                continue;
+            }
+            parts.push(part(&lines[range.clone()]));
+            group = None;
+        }
+        if size > config.max_chunk_size {
+            parts.extend(pack_lines(&lines[unit], config));
+        } else {
+            group = Some((unit, size));
+        }
+    }
+    if let Some((range, _)) = group {
+        parts.push(part(&lines[range]));
+    }
+    parts
+}
+
+/// Line ranges of the statements or items directly inside the item in
+/// `text`, of `line_count` lines, or `None` if it doesn't parse or has none.
+/// Each range starts after the one before, so comments go with the
+/// statement or item below them; the first takes the lines before it and
+/// the last the lines after it.
+fn units(text: &str, line_count: usize) -> Option<Vec<Range<usize>>> {
+    let item: syn::Item = syn::parse_str(text).ok()?;
+    // Spans count lines from 1 in `text`.
+    let mut ends: Vec<usize> = match &item {
+        syn::Item::Fn(function) => function
+            .block
+            .stmts
+            .iter()
+            .map(|stmt| stmt.span().end().line)
+            .collect(),
+        syn::Item::Impl(item_impl) => item_impl
+            .items
+            .iter()
+            .map(|item| item.span().end().line)
+            .collect(),
+        syn::Item::Trait(item_trait) => item_trait
+            .items
+            .iter()
+            .map(|item| item.span().end().line)
+            .collect(),
+        syn::Item::Mod(module) => module
+            .content
+            .as_ref()?
+            .1
+            .iter()
+            .map(|item| item.span().end().line)
+            .collect(),
+        _ => return None,
+    };
+    // Statements sharing a line go together.
+    ends.dedup();
+    ends.pop()?;
+    ends.push(line_count);
+
+    let mut start = 0;
+    Some(
+        ends.into_iter()
+            .map(|end| {
+                let range = start..end.max(start + 1);
+                start = range.end;
+                range
+            })
+            .take_while(|range| range.start < line_count)
+            .collect(),
+    )
+}
+
+/// Tokens of a line and its line break.
+fn line_tokens(line: &str) -> usize {
+    estimate_tokens(line) + 1
+}
+
+fn part(lines: &[Line]) -> Part {
+    let content: Vec<&str> = lines.iter().map(|(_, line)| *line).collect();
+    Part {
+        line_start: lines[0].0,
+        line_end: lines[lines.len() - 1].0,
+        content: content.join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    /// A file with a function of `statements` statements from line 2 on.
+    fn source(statements: usize) -> String {
+        let mut source = "use std::fmt;\nfn handler() {\n".to_string();
+        for index in 0..statements {
+            source.push_str(&format!("    let value_{} = compute({});\n", index, index));
+        }
+        source.push_str("}\n");
+        source
+    }
+
+    /// The element of the item after the first line of `source`.
+    fn element(source: &str, element_type: ElementType) -> CodeElement {
+        let mut element = MockDataGenerator::code_element("handler", element_type);
+        element.signature = Some("fn handler()".to_string());
+        let location = &mut element.location;
+        location.line_start = 2;
+        location.line_end = source.lines().count();
+        location.byte_start = source.find('\n').unwrap() + 1;
+        location.byte_end = source.len() - 1;
+        element
+    }
+
+    fn config(chunk_strategy: ChunkStrategy, overlap: usize) -> RagConfig {
+        RagConfig {
+            target_chunk_size: 40,
+            max_chunk_size: 100,
+            chunk_overlap_tokens: overlap,
+            chunk_strategy,
+            ..RagConfig::default()
+        }
+    }
+
+    fn split(source: &str, config: &RagConfig) -> Vec<Part> {
+        split_element(&element(source, ElementType::Function), source, config).unwrap()
+    }
+
+    fn lines(parts: &[Part]) -> Vec<(usize, usize)> {
+        parts
+            .iter()
+            .map(|part| (part.line_start, part.line_end))
+            .collect()
+    }
+
+    fn tokens(part: &Part) -> usize {
+        part.content.lines().map(line_tokens).sum()
+    }
+
+    #[test]
+    fn test_small_elements_stay_whole() {
+        let source = source(3);
+        for strategy in [
+            ChunkStrategy::PerElement,
+            ChunkStrategy::TokenBudget,
+            ChunkStrategy::SlidingWindow,
+            ChunkStrategy::Semantic,
+        ] {
+            let parts = split(&source, &config(strategy, 0));
+            assert_eq!(lines(&parts), [(2, 6)]);
+            assert_eq!(
+                parts[0].content,
+                source.trim_end().split_once('\n').unwrap().1
+            );
+        }
+
+        // Per-element chunks stay whole whatever their size.
+        let source = self::source(20);
+        let parts = split(&source, &config(ChunkStrategy::PerElement, 0));
+        assert_eq!(lines(&parts), [(2, 23)]);
+    }
+
+    #[test]
+    fn test_token_budget_respects_limits() {
+        let source = source(20);
+        let parts = split(&source, &config(ChunkStrategy::TokenBudget, 0));
+        assert_eq!(
+            lines(&parts),
+            [(2, 5), (6, 9), (10, 13), (14, 17), (18, 21), (22, 23)]
+        );
+        for part in &parts {
+            assert!(tokens(part) <= 40, "{} tokens", tokens(part));
+        }
+        // Without overlap, the parts hold every line once.
+        let content: Vec<&str> = parts.iter().map(|part| part.content.as_str()).collect();
+        assert_eq!(
+            content.join("\n"),
+            source.trim_end().split_once('\n').unwrap().1
+        );
+    }
+
+    #[test]
+    fn test_overlap() {
+        let source = source(20);
+        let config = config(ChunkStrategy::TokenBudget, 10);
+        let parts = split(&source, &config);
+        // Each part starts with the last line of the one before.
+        assert_eq!(
+            lines(&parts),
+            [
+                (2, 5),
+                (5, 8),
+                (8, 11),
+                (11, 14),
+                (14, 17),
+                (17, 20),
+                (20, 23)
+            ]
+        );
+        for pair in parts.windows(2) {
+            let overlap = pair[0].line_end + 1 - pair[1].line_start;
+            let overlap_tokens: usize = pair[0]
+                .content
+                .lines()
+                .rev()
+                .take(overlap)
+                .map(line_tokens)
+                .sum();
+            assert!(overlap_tokens <= config.chunk_overlap_tokens);
+        }
+        // Boundaries only depend on the source and the settings.
+        assert_eq!(lines(&split(&source, &config)), lines(&parts));
+
+        // An overlap as large as a part still moves forward.
+        let config = RagConfig {
+            chunk_overlap_tokens: 1000,
+            ..config
+        };
+        let parts = split(&source, &config);
+        assert!(parts
+            .windows(2)
+            .all(|pair| pair[1].line_start > pair[0].line_start));
+        assert_eq!(parts.last().unwrap().line_end, 23);
+    }
+
+    #[test]
+    fn test_oversized_lines_are_kept() {
+        let long = format!("    let text = \"{}\";", "x".repeat(400));
+        let source = source(20).replace("    let value_5 = compute(5);", &long);
+        for strategy in [ChunkStrategy::TokenBudget, ChunkStrategy::Semantic] {
+            let parts = split(&source, &config(strategy, 0));
+            let long_parts: Vec<&Part> = parts
+                .iter()
+                .filter(|part| part.content.contains(&long))
+                .collect();
+            assert_eq!(long_parts.len(), 1);
+            assert_eq!((long_parts[0].line_start, long_parts[0].line_end), (8, 8));
+            assert_eq!(parts.last().unwrap().line_end, 23);
+        }
+    }
+
+    #[test]
+    fn test_sliding_windows() {
+        let source = source(20);
+        let parts = split(&source, &config(ChunkStrategy::SlidingWindow, 10));
+        assert!(parts.len() > 1);
+        assert!(parts[0]
+            .content
+            .starts_with("fn handler() {\n    let value_0"));
+        for part in &parts[1..] {
+            assert!(part
+                .content
+                .starts_with("fn handler() {\n    // ...\n    let value_"));
+        }
+
+        // Only functions are windowed.
+        let element = element(&source, ElementType::Module);
+        let config = config(ChunkStrategy::SlidingWindow, 10);
+        let parts = split_element(&element, &source, &config).unwrap();
+        assert_eq!(lines(&parts), [(2, 23)]);
+    }
+
+    #[test]
+    fn test_semantic_parts_keep_statements_whole() {
+        let mut source = source(20);
+        let statement = "    let sum = [\n        1,\n        2,\n    ];\n";
+        source = source.replace("    let value_3 = compute(3);\n", statement);
+        let parts = split(&source, &config(ChunkStrategy::Semantic, 0));
+        // The statement on lines 6 to 9 is in a single part.
+        assert!(parts
+            .iter()
+            .any(|part| part.line_start <= 6 && 9 <= part.line_end));
+        for pair in parts.windows(2) {
+            assert_eq!(pair[1].line_start, pair[0].line_end + 1);
+        }
+        assert_eq!(parts[0].line_start, 2);
+        assert_eq!(parts.last().unwrap().line_end, 26);
+        for part in &parts {
+            assert!(tokens(part) <= 40, "{} tokens", tokens(part));
+        }
+    }
+
+    #[test]
+    fn test_mismatched_location() {
+        let source = source(3);
+        let element = element(&source, ElementType::Function);
+        let config = config(ChunkStrategy::TokenBudget, 0);
+        assert!(split_element(&element, "fn changed() {}\n", &config).is_none());
+    }
+
+    #[test]
+    fn test_surrounding_lines() {
+        let source = source(3);
+        let part = Part {
+            line_start: 3,
+            line_end: 4,
+            content: String::new(),
+        };
+        let (before, after) = surrounding_lines(&source, &part, 2);
+        assert_eq!(before, ["use std::fmt;", "fn handler() {"]);
+        assert_eq!(after, ["    let value_2 = compute(2);", "}"]);
+        let (before, after) = surrounding_lines(&source, &part, 0);
+        assert!(before.is_empty() && after.is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+//! Documentation coverage of the public items of a project.
+//!
+//! [`doc_coverage`] counts the public items with doc comments, overall, per
+//! module and per element type, as `rustex check` does for its
+//! `min_doc_coverage` threshold. Each documented item is also rated with
+//! [`DocumentationQuality`], so a weighted coverage tells one-line summaries
+//! apart from docs with details and examples. The report renders as JSON, as
+//! a Markdown table, or as a shields.io endpoint badge.
+
+use crate::mdbook::{chapter_title, crate_name};
+use crate::rag::DocumentationQuality;
+use anyhow::Result;
+use rustex_core::{CodeElement, ElementType, ProjectAst, Visibility};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Documentation coverage of a project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocCoverage {
+    /// Project name
+    pub project: String,
+    /// Coverage of all public items
+    pub total: CoverageCounts,
+    /// Coverage per module, such as `my-crate::net`, sorted by module
+    pub by_module: BTreeMap<String, CoverageCounts>,
+    /// Coverage per element type, such as `Function`, sorted by type
+    pub by_element_type: BTreeMap<String, CoverageCounts>,
+    /// Public items without doc comments, in file order
+    pub undocumented: Vec<UndocumentedItem>,
+}
+
+/// Documented public items among a group of items.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageCounts {
+    /// Public items, excluding impl blocks
+    pub public_items: usize,
+    /// Public items with doc comments
+    pub documented: usize,
+    /// Documented items rated excellent: details, parameters and examples
+    pub excellent: usize,
+    /// Documented items rated good
+    pub good: usize,
+    /// Documented items rated basic, such as one-line summaries
+    pub basic: usize,
+    /// Documented share of public items, in percent (100 when there are none)
+    pub coverage_percent: f64,
+    /// Coverage with each item weighted by the quality of its docs, from 0
+    /// for missing docs to 1 for excellent ones, in percent
+    pub weighted_percent: f64,
+}
+
+/// A public item without doc comments.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UndocumentedItem {
+    /// Qualified name of the item
+    pub name: String,
+    /// Type of the item
+    pub element_type: ElementType,
+    /// File of the item, relative to the project root
+    pub file: PathBuf,
+    /// First line of the item
+    pub line: usize,
+}
+
+impl CoverageCounts {
+    fn add(&mut self, quality: &DocumentationQuality) {
+        self.public_items += 1;
+        match quality {
+            DocumentationQuality::Excellent => self.excellent += 1,
+            DocumentationQuality::Good => self.good += 1,
+            DocumentationQuality::Basic => self.basic += 1,
+            DocumentationQuality::Missing => return,
+        }
+        self.documented += 1;
+    }
+
+    fn compute_percentages(&mut self) {
+        if self.public_items == 0 {
+            self.coverage_percent = 100.0;
+            self.weighted_percent = 100.0;
+            return;
+        }
+        let weighted = self.excellent as f64 * DocumentationQuality::Excellent.weight()
+            + self.good as f64 * DocumentationQuality::Good.weight()
+            + self.basic as f64 * DocumentationQuality::Basic.weight();
+        self.coverage_percent = self.documented as f64 * 100.0 / self.public_items as f64;
+        self.weighted_percent = weighted * 100.0 / self.public_items as f64;
+    }
+}
+
+/// The documentation coverage of the public items of `project_ast`.
+pub fn doc_coverage(project_ast: &ProjectAst) -> DocCoverage {
+    let mut coverage = DocCoverage {
+        project: project_ast.project.name.clone(),
+        total: CoverageCounts::default(),
+        by_module: BTreeMap::new(),
+        by_element_type: BTreeMap::new(),
+        undocumented: Vec::new(),
+    };
+    for file in &project_ast.files {
+        for element in file.elements.iter().filter(|element| is_counted(element)) {
+            let quality = DocumentationQuality::of(element);
+            let module = chapter_title(crate_name(element), &element.hierarchy.module_path);
+            let element_type = format!("{:?}", element.element_type);
+            coverage.total.add(&quality);
+            coverage.by_module.entry(module).or_default().add(&quality);
+            coverage
+                .by_element_type
+                .entry(element_type)
+                .or_default()
+                .add(&quality);
+            if let DocumentationQuality::Missing = quality {
+                coverage.undocumented.push(UndocumentedItem {
+                    name: element.hierarchy.qualified_name.clone(),
+                    element_type: element.element_type.clone(),
+                    file: file.relative_path.clone(),
+                    line: element.location.line_start,
+                });
+            }
+        }
+    }
+    coverage.total.compute_percentages();
+    for counts in coverage
+        .by_module
+        .values_mut()
+        .chain(coverage.by_element_type.values_mut())
+    {
+        counts.compute_percentages();
+    }
+    coverage
+}
+
+/// Public items other than impl blocks, whose docs belong to their items.
+fn is_counted(element: &CodeElement) -> bool {
+    element.visibility == Visibility::Public && element.element_type != ElementType::Impl
+}
+
+/// Render `coverage` as Markdown tables by module and element type,
+/// followed by the undocumented items.
+pub fn format_coverage_markdown(coverage: &DocCoverage) -> String {
+    let total = &coverage.total;
+    let mut markdown = format!("# Documentation Coverage: {}\n\n", coverage.project);
+    markdown.push_str(&format!(
+        "- **Coverage:** {:.1}% ({} of {} public items documented)\n",
+        total.coverage_percent, total.documented, total.public_items
+    ));
+    markdown.push_str(&format!(
+        "- **Quality-weighted coverage:** {:.1}%\n\n",
+        total.weighted_percent
+    ));
+
+    for (title, column, rows) in [
+        ("By Module", "Module", &coverage.by_module),
+        ("By Element Type", "Type", &coverage.by_element_type),
+    ] {
+        markdown.push_str(&format!("## {}\n\n", title));
+        markdown.push_str(&format!(
+            "| {} | Items | Documented | Coverage | Weighted | Excellent | Good | Basic |\n",
+            column
+        ));
+        markdown.push_str("|---|---:|---:|---:|---:|---:|---:|---:|\n");
+        for (name, counts) in rows {
+            markdown.push_str(&format!(
+                "| `{}` | {} | {} | {:.1}% | {:.1}% | {} | {} | {} |\n",
+                name,
+                counts.public_items,
+                counts.documented,
+                counts.coverage_percent,
+                counts.weighted_percent,
+                counts.excellent,
+                counts.good,
+                counts.basic
+            ));
+        }
+        markdown.push('\n');
+    }
+
+    if !coverage.undocumented.is_empty() {
+        markdown.push_str("## Undocumented Items\n\n");
+        for item in &coverage.undocumented {
+            markdown.push_str(&format!(
+                "- `{}` ({:?}, {}:{})\n",
+                item.name,
+                item.element_type,
+                item.file.display(),
+                item.line
+            ));
+        }
+    }
+    markdown
+}
+
+/// Render `coverage` as a shields.io endpoint badge, as read by
+/// `https://img.shields.io/endpoint?url=<url of the JSON>`, showing the
+/// weighted coverage if `weighted` and the plain coverage otherwise.
+pub fn format_coverage_badge(coverage: &DocCoverage, weighted: bool) -> Result<String> {
+    let percent = if weighted {
+        coverage.total.weighted_percent
+    } else {
+        coverage.total.coverage_percent
+    };
+    let color = match percent {
+        p if p >= 90.0 => "brightgreen",
+        p if p >= 75.0 => "green",
+        p if p >= 60.0 => "yellowgreen",
+        p if p >= 40.0 => "yellow",
+        p if p >= 20.0 => "orange",
+        _ => "red",
+    };
+    let badge = serde_json::json!({
+        "schemaVersion": 1,
+        "label": "docs",
+        "message": format!("{:.0}%", percent),
+        "color": color,
+    });
+    Ok(format!("{}\n", serde_json::to_string_pretty(&badge)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    /// Three public items, one with basic and one with excellent docs, and
+    /// a private function and impl block, which aren't counted.
+    fn project() -> ProjectAst {
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        let excellent = [
+            "Connect to `address`, retrying until the timeout set in the options passes.",
+            "",
+            "# Arguments",
+            "",
+            "* `address` - the host and port",
+            "",
+            "# Example",
+            "",
+            "```",
+            "connect(\"localhost:80\");",
+            "```",
+        ];
+        for (name, element_type, module_path, visibility, docs) in [
+            (
+                "add",
+                ElementType::Function,
+                "crate",
+                Visibility::Public,
+                &["Adds."][..],
+            ),
+            (
+                "Config",
+                ElementType::Struct,
+                "crate",
+                Visibility::Public,
+                &[][..],
+            ),
+            (
+                "helper",
+                ElementType::Function,
+                "crate",
+                Visibility::Private,
+                &[][..],
+            ),
+            (
+                "Config",
+                ElementType::Impl,
+                "crate",
+                Visibility::Public,
+                &[][..],
+            ),
+            (
+                "connect",
+                ElementType::Function,
+                "crate::net",
+                Visibility::Public,
+                &excellent[..],
+            ),
+        ] {
+            let mut element = MockDataGenerator::code_element(name, element_type);
+            element.hierarchy.module_path = module_path.to_string();
+            element.hierarchy.qualified_name = format!("{}::{}", module_path, name);
+            element.visibility = visibility;
+            element.doc_comments = docs.iter().map(|line| line.to_string()).collect();
+            element.location.line_start = project.files[0].elements.len() + 1;
+            project.files[0].elements.push(element);
+        }
+        project
+    }
+
+    #[test]
+    fn test_doc_coverage() {
+        let coverage = doc_coverage(&project());
+        let total = &coverage.total;
+        assert_eq!((total.public_items, total.documented), (3, 2));
+        assert_eq!((total.excellent, total.good, total.basic), (1, 0, 1));
+        assert!((total.coverage_percent - 200.0 / 3.0).abs() < 1e-9);
+        assert!((total.weighted_percent - 50.0).abs() < 1e-9);
+
+        let crate_root = &coverage.by_module["crate"];
+        assert_eq!((crate_root.public_items, crate_root.documented), (2, 1));
+        assert_eq!(crate_root.coverage_percent, 50.0);
+        assert_eq!(crate_root.weighted_percent, 25.0);
+        assert_eq!(coverage.by_module["crate::net"].coverage_percent, 100.0);
+        assert_eq!(coverage.by_element_type["Function"].public_items, 2);
+        assert_eq!(coverage.by_element_type["Struct"].coverage_percent, 0.0);
+        assert!(!coverage.by_element_type.contains_key("Impl"));
+
+        assert_eq!(
+            coverage.undocumented,
+            [UndocumentedItem {
+                name: "crate::Config".to_string(),
+                element_type: ElementType::Struct,
+                file: PathBuf::from("file_0.rs"),
+                line: 2,
+            }]
+        );
+
+        let markdown = format_coverage_markdown(&coverage);
+        assert!(markdown.contains("- **Coverage:** 66.7% (2 of 3 public items documented)\n"));
+        assert!(markdown.contains("| `crate` | 2 | 1 | 50.0% | 25.0% | 0 | 0 | 1 |\n"));
+        assert!(markdown.contains("- `crate::Config` (Struct, file_0.rs:2)\n"));
+        let badge: serde_json::Value =
+            serde_json::from_str(&format_coverage_badge(&coverage, true).unwrap()).unwrap();
+        assert_eq!(badge["message"], "50%");
+        assert_eq!(badge["color"], "yellow");
+    }
+
+    #[test]
+    fn test_no_public_items() {
+        let mut project = project();
+        for element in &mut project.files[0].elements {
+            element.visibility = Visibility::Private;
+        }
+        let coverage = doc_coverage(&project);
+        assert_eq!(coverage.total.public_items, 0);
+        assert_eq!(coverage.total.coverage_percent, 100.0);
+        assert_eq!(coverage.total.weighted_percent, 100.0);
+        assert!(coverage.by_module.is_empty());
+        assert!(coverage.undocumented.is_empty());
+
+        let markdown = format_coverage_markdown(&coverage);
+        assert!(markdown.contains("- **Coverage:** 100.0% (0 of 0 public items documented)\n"));
+        assert!(!markdown.contains("## Undocumented Items"));
+        let badge: serde_json::Value =
+            serde_json::from_str(&format_coverage_badge(&coverage, false).unwrap()).unwrap();
+        assert_eq!(badge["message"], "100%");
+        assert_eq!(badge["color"], "brightgreen");
+    }
+}
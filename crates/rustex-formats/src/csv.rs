@@ -0,0 +1,382 @@
+//! CSV and Parquet tables of extracted data, for analysis in spreadsheets,
+//! pandas or DuckDB.
+//!
+//! Each [`CsvTable`] has one row per item: elements, files, or elements with
+//! complexity metrics. Rows of different tables join on `file` and
+//! `element_id`. In CSV, fields are quoted as in RFC 4180 when needed, and
+//! empty when a value is missing. [`format_as_parquet`], with the `parquet`
+//! feature, writes the same tables as Parquet files with typed columns, and
+//! nulls for missing values.
+
+use anyhow::Result;
+use rustex_core::{CodeElement, CsvTable, FileAst, ProjectAst};
+
+/// Type of the values of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Text,
+    Integer,
+    Float,
+    Boolean,
+}
+
+/// A value of a row.
+enum Field {
+    Text(String),
+    /// An integer, `None` when missing
+    Integer(Option<u64>),
+    Float(f64),
+    Boolean(bool),
+}
+
+impl Field {
+    fn integer(value: impl Into<u64>) -> Self {
+        Field::Integer(Some(value.into()))
+    }
+
+    fn size(value: usize) -> Self {
+        Field::Integer(Some(value as u64))
+    }
+
+    /// The field as written in CSV.
+    fn to_csv(&self) -> String {
+        match self {
+            Field::Text(text) => escape(text),
+            Field::Integer(value) => value.map(|value| value.to_string()).unwrap_or_default(),
+            Field::Float(value) => format!("{:.2}", value),
+            Field::Boolean(value) => value.to_string(),
+        }
+    }
+}
+
+const ELEMENT_COLUMNS: &[(&str, ColumnType)] = &[
+    ("element_id", ColumnType::Text),
+    ("file", ColumnType::Text),
+    ("module_path", ColumnType::Text),
+    ("qualified_name", ColumnType::Text),
+    ("name", ColumnType::Text),
+    ("element_type", ColumnType::Text),
+    ("visibility", ColumnType::Text),
+    ("line_start", ColumnType::Integer),
+    ("line_end", ColumnType::Integer),
+    ("signature", ColumnType::Text),
+    ("doc_lines", ColumnType::Integer),
+    ("complexity", ColumnType::Integer),
+    ("parent_id", ColumnType::Text),
+    ("is_generated", ColumnType::Boolean),
+    ("deprecated", ColumnType::Boolean),
+];
+
+const FILE_COLUMNS: &[(&str, ColumnType)] = &[
+    ("file", ColumnType::Text),
+    ("kind", ColumnType::Text),
+    ("elements", ColumnType::Integer),
+    ("imports", ColumnType::Integer),
+    ("lines_of_code", ColumnType::Integer),
+    ("lines_of_comments", ColumnType::Integer),
+    ("complexity_total", ColumnType::Integer),
+    ("function_count", ColumnType::Integer),
+    ("struct_count", ColumnType::Integer),
+    ("enum_count", ColumnType::Integer),
+    ("trait_count", ColumnType::Integer),
+    ("unsafe_blocks", ColumnType::Integer),
+    ("parse_errors", ColumnType::Integer),
+    ("partially_extracted", ColumnType::Boolean),
+];
+
+const METRIC_COLUMNS: &[(&str, ColumnType)] = &[
+    ("element_id", ColumnType::Text),
+    ("file", ColumnType::Text),
+    ("qualified_name", ColumnType::Text),
+    ("element_type", ColumnType::Text),
+    ("cyclomatic", ColumnType::Integer),
+    ("cognitive", ColumnType::Integer),
+    ("nesting_depth", ColumnType::Integer),
+    ("lines_of_code", ColumnType::Integer),
+    ("parameter_count", ColumnType::Integer),
+    ("return_count", ColumnType::Integer),
+    ("closure_count", ColumnType::Integer),
+    ("async_block_count", ColumnType::Integer),
+    ("halstead_volume", ColumnType::Float),
+    ("halstead_difficulty", ColumnType::Float),
+    ("halstead_effort", ColumnType::Float),
+    ("complexity_level", ColumnType::Text),
+];
+
+/// Render `table` of `project_ast` as CSV with a header row.
+pub fn format_as_csv(project_ast: &ProjectAst, table: CsvTable) -> Result<String> {
+    let mut csv = String::new();
+    let header: Vec<String> = columns(table)
+        .iter()
+        .map(|(name, _)| escape(name))
+        .collect();
+    csv.push_str(&header.join(","));
+    csv.push('\n');
+    for row in rows(project_ast, table) {
+        let fields: Vec<String> = row.iter().map(Field::to_csv).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+/// Write `table` of `project_ast` as a Parquet file.
+#[cfg(feature = "parquet")]
+pub fn format_as_parquet(project_ast: &ProjectAst, table: CsvTable) -> Result<Vec<u8>> {
+    parquet_writer::write(columns(table), &rows(project_ast, table))
+}
+
+/// Write `table` of `project_ast` as a Parquet file.
+#[cfg(not(feature = "parquet"))]
+pub fn format_as_parquet(_project_ast: &ProjectAst, _table: CsvTable) -> Result<Vec<u8>> {
+    anyhow::bail!("Parquet output needs the parquet feature")
+}
+
+fn columns(table: CsvTable) -> &'static [(&'static str, ColumnType)] {
+    match table {
+        CsvTable::Elements => ELEMENT_COLUMNS,
+        CsvTable::Files => FILE_COLUMNS,
+        CsvTable::Metrics => METRIC_COLUMNS,
+    }
+}
+
+fn rows(project_ast: &ProjectAst, table: CsvTable) -> Vec<Vec<Field>> {
+    let mut rows = Vec::new();
+    for file in &project_ast.files {
+        match table {
+            CsvTable::Elements => {
+                rows.extend(
+                    file.elements
+                        .iter()
+                        .map(|element| element_row(file, element)),
+                );
+            }
+            CsvTable::Files => rows.push(file_row(file)),
+            CsvTable::Metrics => {
+                rows.extend(
+                    file.elements
+                        .iter()
+                        .filter_map(|element| metric_row(file, element)),
+                );
+            }
+        }
+    }
+    rows
+}
+
+fn element_row(file: &FileAst, element: &CodeElement) -> Vec<Field> {
+    vec![
+        Field::Text(element.id.clone()),
+        Field::Text(file.relative_path.display().to_string()),
+        Field::Text(element.hierarchy.module_path.clone()),
+        Field::Text(element.hierarchy.qualified_name.clone()),
+        Field::Text(element.name.clone()),
+        Field::Text(format!("{:?}", element.element_type)),
+        Field::Text(element.visibility.to_string()),
+        Field::size(element.location.line_start),
+        Field::size(element.location.line_end),
+        Field::Text(element.signature.clone().unwrap_or_default()),
+        Field::size(element.doc_comments.len()),
+        Field::Integer(element.complexity.map(u64::from)),
+        Field::Text(element.hierarchy.parent_id.clone().unwrap_or_default()),
+        Field::Boolean(element.is_generated),
+        Field::Boolean(element.deprecation.is_some()),
+    ]
+}
+
+fn file_row(file: &FileAst) -> Vec<Field> {
+    let metrics = &file.file_metrics;
+    vec![
+        Field::Text(file.relative_path.display().to_string()),
+        Field::Text(file.kind.as_str().to_string()),
+        Field::size(file.elements.len()),
+        Field::size(file.imports.len()),
+        Field::size(metrics.lines_of_code),
+        Field::size(metrics.lines_of_comments),
+        Field::integer(metrics.complexity_total),
+        Field::size(metrics.function_count),
+        Field::size(metrics.struct_count),
+        Field::size(metrics.enum_count),
+        Field::size(metrics.trait_count),
+        Field::size(metrics.unsafe_usage.unsafe_blocks),
+        Field::size(file.parse_errors.len()),
+        Field::Boolean(file.partially_extracted),
+    ]
+}
+
+/// The row of an element with complexity metrics, `None` for other elements.
+fn metric_row(file: &FileAst, element: &CodeElement) -> Option<Vec<Field>> {
+    let metrics = element.complexity_metrics.as_ref()?;
+    Some(vec![
+        Field::Text(element.id.clone()),
+        Field::Text(file.relative_path.display().to_string()),
+        Field::Text(element.hierarchy.qualified_name.clone()),
+        Field::Text(format!("{:?}", element.element_type)),
+        Field::integer(metrics.cyclomatic),
+        Field::integer(metrics.cognitive),
+        Field::integer(metrics.nesting_depth),
+        Field::integer(metrics.lines_of_code),
+        Field::integer(metrics.parameter_count),
+        Field::integer(metrics.return_count),
+        Field::integer(metrics.closure_count),
+        Field::integer(metrics.async_block_count),
+        Field::Float(metrics.halstead.volume),
+        Field::Float(metrics.halstead.difficulty),
+        Field::Float(metrics.halstead.effort),
+        Field::Text(format!("{:?}", metrics.complexity_level())),
+    ])
+}
+
+/// `field` quoted if it holds a comma, quote or line break.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_writer {
+    use super::{ColumnType, Field};
+    use anyhow::Result;
+    use arrow_array::{
+        ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, UInt64Array,
+    };
+    use arrow_schema::{DataType, Field as ArrowField, Schema};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    /// A Parquet file of `rows` with `columns`, in one row group.
+    pub(super) fn write(columns: &[(&str, ColumnType)], rows: &[Vec<Field>]) -> Result<Vec<u8>> {
+        let mut fields = Vec::new();
+        let mut arrays: Vec<ArrayRef> = Vec::new();
+        for (index, &(name, column_type)) in columns.iter().enumerate() {
+            let values = rows.iter().map(|row| &row[index]);
+            let (data_type, array): (DataType, ArrayRef) = match column_type {
+                ColumnType::Text => (
+                    DataType::Utf8,
+                    Arc::new(StringArray::from_iter_values(values.map(
+                        |field| match field {
+                            Field::Text(text) => text.as_str(),
+                            _ => unreachable!("{} holds text", name),
+                        },
+                    ))),
+                ),
+                ColumnType::Integer => (
+                    DataType::UInt64,
+                    Arc::new(UInt64Array::from_iter(values.map(|field| match field {
+                        Field::Integer(value) => *value,
+                        _ => unreachable!("{} holds integers", name),
+                    }))),
+                ),
+                ColumnType::Float => (
+                    DataType::Float64,
+                    Arc::new(Float64Array::from_iter_values(values.map(
+                        |field| match field {
+                            Field::Float(value) => *value,
+                            _ => unreachable!("{} holds floats", name),
+                        },
+                    ))),
+                ),
+                ColumnType::Boolean => (
+                    DataType::Boolean,
+                    Arc::new(BooleanArray::from_iter(values.map(|field| match field {
+                        Field::Boolean(value) => Some(*value),
+                        _ => unreachable!("{} holds booleans", name),
+                    }))),
+                ),
+            };
+            let nullable = column_type == ColumnType::Integer;
+            fields.push(ArrowField::new(name, data_type, nullable));
+            arrays.push(array);
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    #[test]
+    fn test_csv_tables() {
+        let mut project = MockDataGenerator::project_ast(1, 2);
+        project.files[0].elements[0].signature = Some("fn pair(a: u8, b: u8)".to_string());
+        project.files[0].elements[1].complexity = None;
+
+        let elements = format_as_csv(&project, CsvTable::Elements).unwrap();
+        let lines: Vec<&str> = elements.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("element_id,file,module_path,"));
+        assert!(lines[1].contains(",\"fn pair(a: u8, b: u8)\","));
+        // The missing complexity is an empty field.
+        let fields: Vec<&str> = lines[2].split(',').collect();
+        assert_eq!(fields.len(), ELEMENT_COLUMNS.len());
+        assert_eq!(fields[11], "");
+
+        let files = format_as_csv(&project, CsvTable::Files).unwrap();
+        assert_eq!(files.lines().count(), 2);
+        let metrics = format_as_csv(&project, CsvTable::Metrics).unwrap();
+        let row = metrics.lines().nth(1).unwrap();
+        assert_eq!(row.split(',').count(), METRIC_COLUMNS.len());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_round_trip() {
+        use arrow_array::{Array, BooleanArray, Float64Array, StringArray, UInt64Array};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut project = MockDataGenerator::project_ast(1, 2);
+        project.files[0].elements[1].complexity = None;
+
+        let bytes = format_as_parquet(&project, CsvTable::Elements).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(prost::bytes::Bytes::from(bytes))
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), ELEMENT_COLUMNS.len());
+
+        let column = |name: &str| batch.column(batch.schema().index_of(name).unwrap()).clone();
+        let names = column("name");
+        let names = names.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(names.value(1), "element_1");
+        let complexity = column("complexity");
+        let complexity = complexity.as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(complexity.value(0), 5);
+        assert!(complexity.is_null(1));
+        let generated = column("is_generated");
+        let generated = generated.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert!(!generated.value(0));
+
+        let bytes = format_as_parquet(&project, CsvTable::Metrics).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(prost::bytes::Bytes::from(bytes))
+            .unwrap()
+            .build()
+            .unwrap();
+        let batch = reader.map(Result::unwrap).next().unwrap();
+        let volume = batch.column(batch.schema().index_of("halstead_volume").unwrap());
+        let volume = volume.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(volume.len(), 2);
+    }
+}
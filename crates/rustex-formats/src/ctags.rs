@@ -0,0 +1,322 @@
+//! Tags files of a project, for navigating it in editors.
+//!
+//! [`format_as_ctags`] writes the extended format of universal-ctags, as read
+//! by vim: one tag per extracted item, addressed by a search pattern matching
+//! its line, with the `kind`, `scope`, `signature`, `line` and `end` fields.
+//! Kinds are those of the universal-ctags Rust parser, so editor plugins
+//! that know them work unchanged.
+//!
+//! [`format_as_etags`] writes the `TAGS` format of etags, as read by Emacs:
+//! a section per file, with the text of each tagged line up to the tag, its
+//! line and the byte offset of the line in the file.
+//!
+//! File names are relative to the project root, where tags files belong.
+//! Items of files whose source can't be read are addressed by line number
+//! alone.
+
+use anyhow::Result;
+use rustex_core::{CodeElement, ElementType, FileAst, ProjectAst};
+
+/// Render `project_ast` as a tags file sorted by tag name.
+pub fn format_as_ctags(project_ast: &ProjectAst) -> Result<String> {
+    let mut tags = String::new();
+    for (name, value) in [
+        (
+            "TAG_FILE_FORMAT",
+            "2\t/extended format; --format=1 will not append ;\" to lines/",
+        ),
+        ("TAG_FILE_SORTED", "1\t/0=unsorted, 1=sorted, 2=foldcase/"),
+        ("TAG_FILE_ENCODING", "utf-8\t//"),
+        ("TAG_PROGRAM_NAME", "rustex\t//"),
+        ("TAG_PROGRAM_URL", "https://github.com/jarnura/rustex\t//"),
+    ] {
+        tags.push_str(&format!("!_{}\t{}\n", name, value));
+    }
+    tags.push_str(&format!(
+        "!_TAG_PROGRAM_VERSION\t{}\t//\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+
+    let mut lines = Vec::new();
+    for file in &project_ast.files {
+        let path = tag_path(file);
+        let source = SourceLines::read(file);
+        for tag in file.elements.iter().filter_map(tag) {
+            let address = match source.as_ref().and_then(|source| source.line(tag.line)) {
+                Some((text, _)) => format!("/^{}$/", escape_pattern(text)),
+                None => tag.line.to_string(),
+            };
+            lines.push(format!(
+                "{}\t{}\t{};\"\t{}",
+                tag.name,
+                path,
+                address,
+                tag.fields.join("\t")
+            ));
+        }
+    }
+    // Sorted by bytes, so editors can binary search tag names.
+    lines.sort();
+    lines.dedup();
+    for line in lines {
+        tags.push_str(&line);
+        tags.push('\n');
+    }
+    Ok(tags)
+}
+
+/// Render `project_ast` as an etags `TAGS` file, with the tags of each file
+/// in line order.
+pub fn format_as_etags(project_ast: &ProjectAst) -> Result<String> {
+    let mut tags = String::new();
+    for file in &project_ast.files {
+        let source = SourceLines::read(file);
+        let mut file_tags: Vec<Tag> = file.elements.iter().filter_map(tag).collect();
+        file_tags.sort_by_key(|tag| tag.line);
+        let mut section = String::new();
+        for tag in file_tags {
+            // The text before the tag lets Emacs find moved lines.
+            let (text, offset) = match source.as_ref().and_then(|source| source.line(tag.line)) {
+                Some((text, offset)) => {
+                    let end = text
+                        .find(tag.name)
+                        .map_or(text.len(), |start| start + tag.name.len());
+                    (&text[..end], offset.to_string())
+                }
+                None => (tag.name, String::new()),
+            };
+            section.push_str(&format!(
+                "{}\u{7f}{}\u{1}{},{}\n",
+                text, tag.name, tag.line, offset
+            ));
+        }
+        if section.is_empty() {
+            continue;
+        }
+        tags.push_str(&format!(
+            "\u{c}\n{},{}\n{}",
+            tag_path(file),
+            section.len(),
+            section
+        ));
+    }
+    Ok(tags)
+}
+
+/// A tag of an item, as written to either tags format.
+struct Tag<'a> {
+    name: &'a str,
+    line: usize,
+    /// Extended fields of universal-ctags
+    fields: Vec<String>,
+}
+
+/// The lines of the source of a file, with their byte offsets.
+struct SourceLines {
+    source: String,
+    /// Byte offset of the start of each line
+    starts: Vec<usize>,
+}
+
+impl SourceLines {
+    fn read(file: &FileAst) -> Option<Self> {
+        let source = std::fs::read_to_string(&file.path).ok()?;
+        let mut starts = vec![0];
+        starts.extend(source.match_indices('\n').map(|(index, _)| index + 1));
+        Some(Self { source, starts })
+    }
+
+    /// Text of the 1-based line `line` without its line break, and the byte
+    /// offset of its start.
+    fn line(&self, line: usize) -> Option<(&str, usize)> {
+        let start = *self.starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        let text = self.source.get(start..end)?;
+        Some((text.strip_suffix('\r').unwrap_or(text), start))
+    }
+}
+
+/// Path of `file` in tags files, relative to the project root.
+fn tag_path(file: &FileAst) -> String {
+    file.relative_path.display().to_string().replace('\\', "/")
+}
+
+/// The tag of `element`, `None` for closures, async blocks and elements
+/// generated by macros, whose lines are in the expansion.
+fn tag(element: &CodeElement) -> Option<Tag<'_>> {
+    if element.is_generated {
+        return None;
+    }
+    let impl_context = element.hierarchy.impl_context.as_ref();
+    let kind = match element.element_type {
+        ElementType::Module => "module",
+        ElementType::Struct => "struct",
+        ElementType::Enum => "enum",
+        ElementType::Union => "union",
+        ElementType::Trait => "interface",
+        ElementType::Impl => "implementation",
+        ElementType::Function if impl_context.is_some() => "method",
+        ElementType::Function => "function",
+        ElementType::Constant => "constant",
+        ElementType::Static => "variable",
+        ElementType::TypeAlias => "typedef",
+        ElementType::Macro => "macro",
+        ElementType::Closure | ElementType::AsyncBlock => return None,
+    };
+    // Impl blocks are tagged with their type, as universal-ctags does.
+    let name = match (&element.element_type, impl_context) {
+        (ElementType::Impl, Some(context)) => type_name(&context.self_type),
+        _ => element.name.as_str(),
+    };
+    let line = element
+        .name_location
+        .as_ref()
+        .unwrap_or(&element.location)
+        .line_start;
+
+    let mut fields = vec![kind.to_string(), format!("line:{}", line)];
+    let module = element
+        .hierarchy
+        .module_path
+        .strip_prefix("crate")
+        .map(|path| path.trim_start_matches("::"))
+        .unwrap_or(&element.hierarchy.module_path);
+    match impl_context {
+        Some(context) if element.element_type != ElementType::Impl => {
+            fields.push(format!(
+                "implementation:{}",
+                escape(type_name(&context.self_type))
+            ));
+        }
+        _ if !module.is_empty() => fields.push(format!("module:{}", escape(module))),
+        _ => {}
+    }
+    if element.element_type == ElementType::Function {
+        if let Some(signature) = &element.signature {
+            if let Some(start) = signature.find('(') {
+                fields.push(format!("signature:{}", escape(&signature[start..])));
+            }
+        }
+    }
+    fields.push(format!("end:{}", element.location.line_end));
+    Some(Tag { name, line, fields })
+}
+
+/// The name of the type `self_type` without its path or generic arguments:
+/// `Square` for `crate::shapes::Square<T>`.
+fn type_name(self_type: &str) -> &str {
+    let end = self_type.find('<').unwrap_or(self_type.len());
+    let path = self_type[..end].trim();
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
+
+/// `text` in a search pattern, with the pattern's delimiter `/` and
+/// backslashes escaped.
+fn escape_pattern(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('/', "\\/")
+}
+
+/// `value` as a field value, with backslashes, tabs and line breaks escaped.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const SOURCE: &str = "// Paths\\\r\npub fn split(path: &str) -> Vec<&str> {\r\n    path.split('/').collect()\r\n}\r\n\r\npub struct Zone;\r\n/// Docs\r\npub fn area() {}\r\n";
+
+    /// A project of one file with `split` on line 2, `Zone` on line 6 and
+    /// `area` on line 8.
+    fn project(root: &std::path::Path) -> ProjectAst {
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), SOURCE).unwrap();
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        let file = &mut project.files[0];
+        file.path = root.join("src/lib.rs");
+        file.relative_path = PathBuf::from("src/lib.rs");
+        for (name, element_type, line) in [
+            ("split", ElementType::Function, 2),
+            ("Zone", ElementType::Struct, 6),
+            ("area", ElementType::Function, 8),
+        ] {
+            let mut element = MockDataGenerator::code_element(name, element_type);
+            element.hierarchy.module_path = "crate".to_string();
+            element.signature = None;
+            element.location.line_start = line;
+            element.location.line_end = line;
+            file.elements.push(element);
+        }
+        project
+    }
+
+    #[test]
+    fn test_ctags() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tags = format_as_ctags(&project(temp_dir.path())).unwrap();
+        let lines: Vec<&str> = tags
+            .lines()
+            .filter(|line| !line.starts_with("!_"))
+            .collect();
+        // Sorted by name, and tab-separated.
+        assert_eq!(
+            lines,
+            [
+                "Zone\tsrc/lib.rs\t/^pub struct Zone;$/;\"\tstruct\tline:6\tend:6",
+                "area\tsrc/lib.rs\t/^pub fn area() {}$/;\"\tfunction\tline:8\tend:8",
+                "split\tsrc/lib.rs\t/^pub fn split(path: &str) -> Vec<&str> {$/;\"\tfunction\tline:2\tend:2",
+            ]
+        );
+        assert!(tags.starts_with("!_TAG_FILE_FORMAT\t2\t"));
+    }
+
+    #[test]
+    fn test_escape_pattern() {
+        assert_eq!(escape_pattern("path.split('/')"), "path.split('\\/')");
+        assert_eq!(escape_pattern("// Paths\\"), "\\/\\/ Paths\\\\");
+    }
+
+    #[test]
+    fn test_unreadable_source_is_addressed_by_line() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut project = project(temp_dir.path());
+        project.files[0].path = temp_dir.path().join("missing.rs");
+        let tags = format_as_ctags(&project).unwrap();
+        assert!(tags.contains("\nsplit\tsrc/lib.rs\t2;\"\tfunction\tline:2\tend:2\n"));
+        let etags = format_as_etags(&project).unwrap();
+        assert!(etags.contains("split\u{7f}split\u{1}2,\n"));
+    }
+
+    #[test]
+    fn test_etags() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let etags = format_as_etags(&project(temp_dir.path())).unwrap();
+        let section = "pub fn split\u{7f}split\u{1}2,11\n\
+                       pub struct Zone\u{7f}Zone\u{1}6,88\n\
+                       pub fn area\u{7f}area\u{1}8,116\n";
+        assert_eq!(
+            etags,
+            format!("\u{c}\nsrc/lib.rs,{}\n{}", section.len(), section)
+        );
+
+        // Offsets are those of the lines in the source.
+        for (line, offset) in [
+            ("pub fn split", 11),
+            ("pub struct Zone", 88),
+            ("pub fn area", 116),
+        ] {
+            assert!(SOURCE[offset..].starts_with(line));
+        }
+    }
+}
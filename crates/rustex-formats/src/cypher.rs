@@ -0,0 +1,263 @@
+//! Cypher statements loading a project into Neo4j.
+//!
+//! [`format_as_cypher`] writes the knowledge graph of a project as `MERGE`
+//! statements, so loading the output again updates the graph instead of
+//! duplicating it. Nodes are labeled `Project`, `Crate`, `File` or `Element`
+//! and keyed by `id`: the project or crate name, the file path, or the
+//! element ID. Elements also get the label of their type, such as
+//! `Function`. Relationships are `CONTAINS`, `DEFINES`, `IMPLEMENTS`,
+//! `CALLS`, `USES` and `DEPENDS_ON`.
+//!
+//! Nodes and relationships are batched with `UNWIND`, so large projects load
+//! in a few thousand statements rather than one per node.
+
+use crate::knowledge::{build_knowledge_graph, EntityKind, RelationshipKind, Value};
+use anyhow::Result;
+use rustex_core::ProjectAst;
+
+/// Rows per `UNWIND` statement.
+const BATCH_SIZE: usize = 1000;
+
+/// Render `project_ast` as Cypher statements, one per line group and each
+/// ending with `;`, as read by `cypher-shell -f`.
+pub fn format_as_cypher(project_ast: &ProjectAst) -> Result<String> {
+    let graph = build_knowledge_graph(project_ast);
+    let mut cypher = format!(
+        "// Neo4j graph of {}, load with `cypher-shell -f <file>`\n\n",
+        project_ast.project.name.replace(['\n', '\r'], " ")
+    );
+    for kind in [
+        EntityKind::Project,
+        EntityKind::Crate,
+        EntityKind::File,
+        EntityKind::Element,
+    ] {
+        let label = label(kind);
+        cypher.push_str(&format!(
+            "CREATE CONSTRAINT rustex_{}_id IF NOT EXISTS FOR (n:{}) REQUIRE n.id IS UNIQUE;\n",
+            label.to_lowercase(),
+            label
+        ));
+    }
+
+    // Nodes of the same label and class, in the order they first appear.
+    let mut node_groups: Vec<((EntityKind, &str), Vec<String>)> = Vec::new();
+    for entity in &graph.entities {
+        let key = (entity.reference.kind, entity.class.as_str());
+        let mut row = vec![format!("id: {}", string(&entity.reference.id))];
+        for (property, value) in &entity.properties {
+            row.push(format!("{}: {}", property, value_literal(value)));
+        }
+        let row = format!("{{{}}}", row.join(", "));
+        match node_groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, rows)) => rows.push(row),
+            None => node_groups.push((key, vec![row])),
+        }
+    }
+    for ((kind, class), rows) in &node_groups {
+        let label = label(*kind);
+        let set_class = if *class == label {
+            String::new()
+        } else {
+            format!("n:{}, ", class)
+        };
+        let noun = if label == "Element" {
+            "elements"
+        } else {
+            "nodes"
+        };
+        for batch in rows.chunks(BATCH_SIZE) {
+            cypher.push_str(&format!(
+                "\n// {} {}\nUNWIND [\n  {}\n] AS row\n\
+                 MERGE (n:{} {{id: row.id}})\nSET {}n += row;\n",
+                class,
+                noun,
+                batch.join(",\n  "),
+                label,
+                set_class
+            ));
+        }
+    }
+
+    // Relationships of the same type between the same labels.
+    let mut relationship_groups: Vec<((RelationshipKind, EntityKind, EntityKind), Vec<String>)> =
+        Vec::new();
+    for relationship in &graph.relationships {
+        let key = (
+            relationship.kind,
+            relationship.from.kind,
+            relationship.to.kind,
+        );
+        let pair = format!(
+            "[{}, {}]",
+            string(&relationship.from.id),
+            string(&relationship.to.id)
+        );
+        match relationship_groups
+            .iter_mut()
+            .find(|(group, _)| *group == key)
+        {
+            Some((_, pairs)) => pairs.push(pair),
+            None => relationship_groups.push((key, vec![pair])),
+        }
+    }
+    for ((kind, from, to), pairs) in &relationship_groups {
+        let relationship_type = relationship_type(*kind);
+        for batch in pairs.chunks(BATCH_SIZE) {
+            cypher.push_str(&format!(
+                "\n// {} relationships\nUNWIND [\n  {}\n] AS pair\n\
+                 MATCH (from:{} {{id: pair[0]}}), (to:{} {{id: pair[1]}})\n\
+                 MERGE (from)-[:{}]->(to);\n",
+                relationship_type,
+                batch.join(",\n  "),
+                label(*from),
+                label(*to),
+                relationship_type
+            ));
+        }
+    }
+    Ok(cypher)
+}
+
+fn label(kind: EntityKind) -> &'static str {
+    match kind {
+        EntityKind::Project => "Project",
+        EntityKind::Crate => "Crate",
+        EntityKind::File => "File",
+        EntityKind::Element => "Element",
+    }
+}
+
+/// The relationship type of `kind`, such as `DEPENDS_ON`.
+fn relationship_type(kind: RelationshipKind) -> String {
+    let mut relationship_type = String::new();
+    for c in kind.name().chars() {
+        if c.is_ascii_uppercase() {
+            relationship_type.push('_');
+        }
+        relationship_type.push(c.to_ascii_uppercase());
+    }
+    relationship_type
+}
+
+fn value_literal(value: &Value) -> String {
+    match value {
+        Value::Text(text) => string(text),
+        Value::Integer(value) => value.to_string(),
+        Value::Boolean(value) => value.to_string(),
+    }
+}
+
+/// `text` as a quoted Cypher string.
+fn string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{CrossReference, ReferenceContext, ReferenceType};
+
+    #[test]
+    fn test_string() {
+        assert_eq!(string("plain"), "\"plain\"");
+        assert_eq!(string("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(string("C:\\dir"), "\"C:\\\\dir\"");
+        assert_eq!(string("a\nb\r\tc"), "\"a\\nb\\r\\tc\"");
+    }
+
+    #[test]
+    fn test_relationship_type() {
+        assert_eq!(relationship_type(RelationshipKind::DependsOn), "DEPENDS_ON");
+        assert_eq!(relationship_type(RelationshipKind::Calls), "CALLS");
+    }
+
+    #[test]
+    fn test_golden() {
+        let mut project = MockDataGenerator::project_ast(1, 2);
+        project.project.name = "say \"hi\"\n".to_string();
+        let elements = &mut project.files[0].elements;
+        elements[0].name = "quote\"d".to_string();
+        elements[1].id = "fn \\\"b\"".to_string();
+        elements[1].name = "back\\slash".to_string();
+        elements[1].doc_comments.clear();
+        let mut call = CrossReference::new(
+            elements[0].id.clone(),
+            ReferenceType::FunctionCall,
+            "call".to_string(),
+            elements[0].location.clone(),
+            ReferenceContext::new(false, String::new()),
+        );
+        call.resolve_to(elements[1].id.clone());
+        project.cross_references = vec![call];
+
+        assert_eq!(
+            format_as_cypher(&project).unwrap(),
+            r#"// Neo4j graph of say "hi" , load with `cypher-shell -f <file>`
+
+CREATE CONSTRAINT rustex_project_id IF NOT EXISTS FOR (n:Project) REQUIRE n.id IS UNIQUE;
+CREATE CONSTRAINT rustex_crate_id IF NOT EXISTS FOR (n:Crate) REQUIRE n.id IS UNIQUE;
+CREATE CONSTRAINT rustex_file_id IF NOT EXISTS FOR (n:File) REQUIRE n.id IS UNIQUE;
+CREATE CONSTRAINT rustex_element_id IF NOT EXISTS FOR (n:Element) REQUIRE n.id IS UNIQUE;
+
+// Project nodes
+UNWIND [
+  {id: "say \"hi\"\n", name: "say \"hi\"\n", version: "0.1.0"}
+] AS row
+MERGE (n:Project {id: row.id})
+SET n += row;
+
+// File nodes
+UNWIND [
+  {id: "file_0.rs", path: "file_0.rs", linesOfCode: 20}
+] AS row
+MERGE (n:File {id: row.id})
+SET n += row;
+
+// Function elements
+UNWIND [
+  {id: "Function_element_0_1", name: "quote\"d", qualifiedName: "crate::test::element_0", visibility: "pub", signature: "fn element_0() -> ()", documentation: "Documentation for element_0", lineStart: 1, lineEnd: 10, complexity: 5},
+  {id: "fn \\\"b\"", name: "back\\slash", qualifiedName: "crate::test::element_1", visibility: "pub", signature: "fn element_1() -> ()", lineStart: 1, lineEnd: 10, complexity: 5}
+] AS row
+MERGE (n:Element {id: row.id})
+SET n:Function, n += row;
+
+// CONTAINS relationships
+UNWIND [
+  ["say \"hi\"\n", "file_0.rs"]
+] AS pair
+MATCH (from:Project {id: pair[0]}), (to:File {id: pair[1]})
+MERGE (from)-[:CONTAINS]->(to);
+
+// DEFINES relationships
+UNWIND [
+  ["file_0.rs", "Function_element_0_1"],
+  ["file_0.rs", "fn \\\"b\""]
+] AS pair
+MATCH (from:File {id: pair[0]}), (to:Element {id: pair[1]})
+MERGE (from)-[:DEFINES]->(to);
+
+// CALLS relationships
+UNWIND [
+  ["Function_element_0_1", "fn \\\"b\""]
+] AS pair
+MATCH (from:Element {id: pair[0]}), (to:Element {id: pair[1]})
+MERGE (from)-[:CALLS]->(to);
+"#
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Near-duplicate detection of RAG chunks.
+//!
+//! Generated code and macro expansions make chunks that differ in a few
+//! tokens, which skew embeddings towards them. [`ChunkDeduplicator`] keeps
+//! the first of such chunks as canonical and leaves out the others,
+//! recording each as a [`DuplicateChunk`].
+//!
+//! Chunks are compared by the Jaccard similarity of their shingles, runs of
+//! three identifier and literal tokens, estimated from MinHash signatures of
+//! 64 hashes. Signatures are split into 16 bands of 4 hashes, and only
+//! chunks sharing a band are compared, so finding duplicates takes about
+//! linear time. Pairs with a similarity of 0.7 or more share a band almost
+//! surely; below that, some near-duplicates may be missed.
+
+use crate::rag::RagChunk;
+use rustex_core::stable_hash;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Hashes of a MinHash signature.
+const SIGNATURE_SIZE: usize = 64;
+
+/// Hashes of each band of a signature.
+const BAND_SIZE: usize = 4;
+
+/// Tokens of a shingle.
+const SHINGLE_SIZE: usize = 3;
+
+/// A chunk left out as a near-duplicate of another. In RAG JSONL output, a
+/// line of its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicateChunk {
+    /// ID of the chunk left out
+    pub duplicate: String,
+    /// ID of the chunk kept, which the duplicate is like
+    pub canonical: String,
+    /// Estimated Jaccard similarity of their contents, from 0 to 1
+    pub similarity: f64,
+}
+
+/// Finder of near-duplicates among chunks, across calls, so chunks of files
+/// streamed one at a time are compared with those of the files before.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkDeduplicator {
+    threshold: Option<f64>,
+    /// IDs and signatures of the canonical chunks
+    canonical: Vec<(String, Vec<u64>)>,
+    /// Indexes in `canonical` of the chunks with each band hash
+    bands: HashMap<(usize, u64), Vec<usize>>,
+    duplicates: Vec<DuplicateChunk>,
+}
+
+impl ChunkDeduplicator {
+    /// A deduplicator leaving out chunks at least `threshold` similar to an
+    /// earlier one, or none when `threshold` is `None`.
+    pub fn new(threshold: Option<f64>) -> Self {
+        Self {
+            threshold,
+            ..Self::default()
+        }
+    }
+
+    /// Remove the near-duplicates of earlier chunks from `chunks`,
+    /// returning them.
+    pub fn retain_unique(&mut self, chunks: &mut Vec<RagChunk>) -> Vec<DuplicateChunk> {
+        let Some(threshold) = self.threshold else {
+            return Vec::new();
+        };
+        let mut found = Vec::new();
+        chunks.retain(
+            |chunk| match self.add(&chunk.id, &chunk.content, threshold) {
+                Some(duplicate) => {
+                    found.push(duplicate);
+                    false
+                }
+                None => true,
+            },
+        );
+        self.duplicates.extend(found.iter().cloned());
+        found
+    }
+
+    /// All the duplicates found so far, in the order they were found.
+    pub fn duplicates(&self) -> &[DuplicateChunk] {
+        &self.duplicates
+    }
+
+    /// The duplicate record of the chunk `id` with `content` if it is like
+    /// a canonical chunk, or else add it to them.
+    fn add(&mut self, id: &str, content: &str, threshold: f64) -> Option<DuplicateChunk> {
+        let signature = minhash(content);
+        let bands: Vec<(usize, u64)> = signature
+            .chunks(BAND_SIZE)
+            .enumerate()
+            .map(|(band, hashes)| (band, hashes.iter().fold(0, |hash, &x| mix(hash ^ x))))
+            .collect();
+
+        let mut best: Option<(usize, f64)> = None;
+        let candidates = bands
+            .iter()
+            .filter_map(|band| self.bands.get(band))
+            .flatten();
+        for &index in candidates {
+            let similarity = similarity(&signature, &self.canonical[index].1);
+            if similarity >= threshold && best.is_none_or(|(_, best)| similarity > best) {
+                best = Some((index, similarity));
+            }
+        }
+        if let Some((index, similarity)) = best {
+            return Some(DuplicateChunk {
+                duplicate: id.to_string(),
+                canonical: self.canonical[index].0.clone(),
+                similarity: (similarity * 100.0).round() / 100.0,
+            });
+        }
+
+        let index = self.canonical.len();
+        for band in bands {
+            self.bands.entry(band).or_default().push(index);
+        }
+        self.canonical.push((id.to_string(), signature));
+        None
+    }
+}
+
+/// The MinHash signature of `content`: for each of [`SIGNATURE_SIZE`] hash
+/// functions, the lowest hash of its shingles.
+fn minhash(content: &str) -> Vec<u64> {
+    let tokens: Vec<&str> = content
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|token| !token.is_empty())
+        .collect();
+    let shingles: Vec<u64> = if tokens.len() < SHINGLE_SIZE {
+        vec![stable_hash(&tokens)]
+    } else {
+        tokens.windows(SHINGLE_SIZE).map(stable_hash).collect()
+    };
+    (0..SIGNATURE_SIZE as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|&shingle| mix(shingle ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// The share of equal hashes of two signatures, which estimates the
+/// Jaccard similarity of their shingles.
+fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    let equal = a.iter().zip(b).filter(|(a, b)| a == b).count();
+    equal as f64 / SIGNATURE_SIZE as f64
+}
+
+/// The SplitMix64 finalizer, making a hash function of each seed.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HANDLER: &str = "pub fn handle_get_user(request: Request) -> Response { \
+        let id = request.param(\"id\"); let user = database.find_user(id); \
+        match user { Some(user) => Response::json(user), None => Response::not_found() } }";
+
+    /// `HANDLER` for another resource, as a macro would generate it.
+    fn handler(resource: &str) -> String {
+        HANDLER.replace("get_user", &format!("get_{}", resource))
+    }
+
+    #[test]
+    fn test_identical_chunks() {
+        assert_eq!(similarity(&minhash(HANDLER), &minhash(HANDLER)), 1.0);
+
+        let mut deduplicator = ChunkDeduplicator::new(Some(1.0));
+        assert_eq!(deduplicator.add("a", HANDLER, 1.0), None);
+        let duplicate = deduplicator.add("b", HANDLER, 1.0).unwrap();
+        assert_eq!(
+            duplicate,
+            DuplicateChunk {
+                duplicate: "b".to_string(),
+                canonical: "a".to_string(),
+                similarity: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_threshold() {
+        let near = handler("order");
+        let estimate = similarity(&minhash(HANDLER), &minhash(&near));
+        assert!(estimate > 0.7 && estimate < 1.0, "{}", estimate);
+
+        // Duplicates at the threshold and above, not below.
+        let mut deduplicator = ChunkDeduplicator::default();
+        deduplicator.add("a", HANDLER, estimate);
+        assert!(deduplicator.add("b", &near, estimate).is_some());
+        let mut deduplicator = ChunkDeduplicator::default();
+        deduplicator.add("a", HANDLER, estimate);
+        assert!(deduplicator.add("b", &near, estimate + 0.01).is_none());
+    }
+
+    #[test]
+    fn test_different_chunks() {
+        let other = "impl Display for Color { fn fmt(&self, f: &mut Formatter) -> fmt::Result \
+            { write!(f, \"#{:02x}{:02x}{:02x}\", self.r, self.g, self.b) } }";
+        assert!(similarity(&minhash(HANDLER), &minhash(other)) < 0.1);
+
+        let mut deduplicator = ChunkDeduplicator::default();
+        assert!(deduplicator.add("a", HANDLER, 0.5).is_none());
+        assert!(deduplicator.add("b", other, 0.5).is_none());
+        // Chunks too short for a shingle are compared whole.
+        assert!(deduplicator.add("c", "x", 0.5).is_none());
+        assert!(deduplicator.add("d", "y", 0.5).is_none());
+        assert!(deduplicator.add("e", "x", 0.5).is_some());
+    }
+
+    #[test]
+    fn test_keeps_first() {
+        let mut deduplicator = ChunkDeduplicator::new(Some(0.7));
+        let found: Vec<Option<DuplicateChunk>> = ["user", "order", "invoice"]
+            .iter()
+            .map(|resource| deduplicator.add(resource, &handler(resource), 0.7))
+            .collect();
+        assert!(found[0].is_none());
+        for duplicate in found[1..].iter().flatten() {
+            assert_eq!(duplicate.canonical, "user");
+        }
+        assert_eq!(found.iter().flatten().count(), 2);
+
+        // Chunks are only left out with a threshold.
+        let mut chunks = Vec::new();
+        assert!(ChunkDeduplicator::new(None)
+            .retain_unique(&mut chunks)
+            .is_empty());
+        assert!(ChunkDeduplicator::new(None).duplicates().is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+//! Graphviz DOT graphs of the structure of a project.
+//!
+//! [`format_as_dot`] renders each diagram of [`Diagram`] as a `digraph`, with
+//! functions and modules filled by complexity, from green for simple code to
+//! red for very complex code. Several graphs can share a file: `dot -Tsvg -O`
+//! renders each to a file of its own.
+//!
+//! [`format_dependency_graph`] renders the packages of a `Cargo.lock` the
+//! same way, for `rustex deps --visualize`.
+
+use crate::graphs::{build_graph, diagram_title, package_graph, EdgeKind, Graph, NodeKind};
+use anyhow::Result;
+use rustex_core::{ComplexityLevel, DependencyGraph, Diagram, ElementType, ProjectAst};
+
+/// Render `diagrams` of `project_ast` as DOT graphs, or every diagram if
+/// `diagrams` is empty.
+pub fn format_as_dot(project_ast: &ProjectAst, diagrams: &[Diagram]) -> Result<String> {
+    let diagrams = if diagrams.is_empty() {
+        &Diagram::ALL[..]
+    } else {
+        diagrams
+    };
+    let mut dot = format!("// Graphviz graphs of {}\n", project_ast.project.name);
+    for &diagram in diagrams {
+        dot.push('\n');
+        dot.push_str(&dot_graph(project_ast, diagram));
+    }
+    Ok(dot)
+}
+
+/// `diagram` of `project_ast` as a DOT `digraph`.
+pub fn dot_graph(project_ast: &ProjectAst, diagram: Diagram) -> String {
+    let graph = build_graph(project_ast, diagram);
+    let (rankdir, shape) = match diagram {
+        Diagram::CrateDependencies => ("LR", "box"),
+        Diagram::ModuleHierarchy => ("TB", "folder"),
+        Diagram::ModuleImports => ("LR", "folder"),
+        Diagram::StructRelationships => ("BT", "record"),
+        Diagram::CallGraph => ("LR", "box"),
+    };
+    let name = format!("{:?}", diagram);
+    render(&graph, &name, diagram_title(diagram), rankdir, shape)
+}
+
+/// The packages of a `Cargo.lock` dependency graph as a DOT `digraph`.
+///
+/// Project packages are boxes and dependencies ellipses, packages locked at
+/// several versions are red, and dev and build dependencies are dashed and
+/// dotted edges.
+pub fn format_dependency_graph(dependencies: &DependencyGraph) -> String {
+    let graph = package_graph(dependencies);
+    render(&graph, "Dependencies", "Package Dependencies", "LR", "box")
+}
+
+/// `graph` as a DOT `digraph` called `name`, with nodes of `shape` unless
+/// their kind has a shape of its own.
+fn render(graph: &Graph, name: &str, title: &str, rankdir: &str, shape: &str) -> String {
+    let mut dot = format!("digraph {} {{\n", name);
+    dot.push_str(&format!("    label={};\n", quote(title)));
+    dot.push_str("    labelloc=t;\n");
+    dot.push_str(&format!("    rankdir={};\n", rankdir));
+    dot.push_str(&format!(
+        "    node [shape={}, style=\"rounded,filled\", fillcolor=\"#ffffff\", \
+         fontname=\"Helvetica\"];\n",
+        shape
+    ));
+    dot.push_str("    edge [fontname=\"Helvetica\"];\n");
+    write_nodes(&mut dot, graph);
+    for edge in &graph.edges {
+        let attributes = match edge.kind {
+            EdgeKind::DevDepends => " [style=dashed, label=\"dev\"]",
+            EdgeKind::BuildDepends => " [style=dotted, label=\"build\"]",
+            EdgeKind::Implements => " [style=dashed, arrowhead=empty]",
+            EdgeKind::Uses => " [arrowhead=vee]",
+            _ => "",
+        };
+        dot.push_str(&format!(
+            "    n{} -> n{}{};\n",
+            edge.from, edge.to, attributes
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn write_nodes(dot: &mut String, graph: &Graph) {
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let mut attributes = Vec::new();
+        match &node.kind {
+            NodeKind::Type(element_type) => {
+                let stereotype = match element_type {
+                    ElementType::Enum => "«enum»\\n",
+                    ElementType::Trait => "«trait»\\n",
+                    ElementType::Union => "«union»\\n",
+                    _ => "",
+                };
+                let members: String = node
+                    .members
+                    .iter()
+                    .map(|member| format!("{}\\l", escape_record(member)))
+                    .collect();
+                let label = format!(
+                    "{{{}{}|{}}}",
+                    stereotype,
+                    escape_record(&node.label),
+                    members
+                );
+                attributes.push(format!("label=\"{}\"", label.replace('"', "\\\"")));
+                attributes.push("style=filled".to_string());
+            }
+            NodeKind::Dependency => {
+                attributes.push(format!("label={}", quote(&node.label)));
+                attributes.push("shape=ellipse".to_string());
+            }
+            _ => attributes.push(format!("label={}", quote(&node.label))),
+        }
+        if let Some(level) = node.complexity {
+            let color = match level {
+                ComplexityLevel::Low => "#d3f9d8",
+                ComplexityLevel::Medium => "#fff3bf",
+                ComplexityLevel::High => "#ffd8a8",
+                ComplexityLevel::VeryHigh => "#ffa8a8",
+            };
+            attributes.push(format!("fillcolor=\"{}\"", color));
+        }
+        if node.duplicated {
+            attributes.push("color=red".to_string());
+        }
+        dot.push_str(&format!("    n{} [{}];\n", index, attributes.join(", ")));
+    }
+}
+
+/// `text` as a quoted DOT string, with line breaks as centered `\n` breaks.
+fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+/// `text` with the characters that structure record labels escaped.
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\n' {
+            escaped.push_str("\\n");
+            continue;
+        }
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{
+        CrossReference, DependencyEdge, DependencyKind, DuplicatePackage, PackageNode, PackageRole,
+        ReferenceContext, ReferenceType,
+    };
+
+    fn package(name: &str, version: &str, role: PackageRole) -> PackageNode {
+        PackageNode {
+            id: format!("{} {}", name, version),
+            name: name.to_string(),
+            version: version.to_string(),
+            source: None,
+            role,
+            depth: 0,
+        }
+    }
+
+    fn edge(from: &str, to: &str, kind: DependencyKind) -> DependencyEdge {
+        DependencyEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let dependencies = DependencyGraph {
+            packages: vec![
+                package("app", "0.1.0", PackageRole::Root),
+                package("cc", "1.0.0", PackageRole::Direct),
+                package("shared", "1.0.0", PackageRole::Direct),
+                package("tempfile", "3.8.0", PackageRole::Direct),
+                package("shared", "2.0.0", PackageRole::Transitive),
+            ],
+            edges: vec![
+                edge("app 0.1.0", "shared 1.0.0", DependencyKind::Normal),
+                edge("app 0.1.0", "tempfile 3.8.0", DependencyKind::Dev),
+                edge("app 0.1.0", "cc 1.0.0", DependencyKind::Build),
+                edge("tempfile 3.8.0", "shared 2.0.0", DependencyKind::Normal),
+            ],
+            duplicates: vec![DuplicatePackage {
+                name: "shared".to_string(),
+                versions: vec!["1.0.0".to_string(), "2.0.0".to_string()],
+            }],
+        };
+        assert_eq!(
+            format_dependency_graph(&dependencies),
+            "digraph Dependencies {
+    label=\"Package Dependencies\";
+    labelloc=t;
+    rankdir=LR;
+    node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\", fontname=\"Helvetica\"];
+    edge [fontname=\"Helvetica\"];
+    n0 [label=\"app\\n0.1.0\"];
+    n1 [label=\"cc\\n1.0.0\", shape=ellipse];
+    n2 [label=\"shared\\n1.0.0\", shape=ellipse, color=red];
+    n3 [label=\"tempfile\\n3.8.0\", shape=ellipse];
+    n4 [label=\"shared\\n2.0.0\", shape=ellipse, color=red];
+    n0 -> n1 [style=dotted, label=\"build\"];
+    n0 -> n2;
+    n0 -> n3 [style=dashed, label=\"dev\"];
+    n3 -> n4;
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_call_graph_styling() {
+        let mut project = MockDataGenerator::project_ast(1, 3);
+        let elements = &mut project.files[0].elements;
+        elements[1].complexity_metrics.as_mut().unwrap().cyclomatic = 30;
+        let ids: Vec<String> = elements.iter().map(|element| element.id.clone()).collect();
+        let location = elements[0].location.clone();
+        let call = |from: &str, to: &str| {
+            let mut reference = CrossReference::new(
+                from.to_string(),
+                ReferenceType::FunctionCall,
+                "call".to_string(),
+                location.clone(),
+                ReferenceContext::new(false, String::new()),
+            );
+            reference.resolve_to(to.to_string());
+            reference
+        };
+        project.cross_references = vec![call(&ids[0], &ids[1]), call(&ids[1], &ids[1])];
+
+        let dot = dot_graph(&project, Diagram::CallGraph);
+        let body: Vec<&str> = dot.lines().skip(6).collect();
+        assert_eq!(
+            body,
+            [
+                "    n0 [label=\"crate::test::element_0\", fillcolor=\"#d3f9d8\"];",
+                "    n1 [label=\"crate::test::element_1\", fillcolor=\"#ffa8a8\"];",
+                "    n0 -> n1;",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_labels_are_escaped() {
+        assert_eq!(quote("plain"), "\"plain\"");
+        assert_eq!(quote("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote("C:\\path"), "\"C:\\\\path\"");
+        assert_eq!(quote("two\nlines\r\nthree"), "\"two\\nlines\\nthree\"");
+
+        assert_eq!(escape_record("Map<K, V>"), "Map\\<K, V\\>");
+        assert_eq!(escape_record("{a|b}"), "\\{a\\|b\\}");
+        assert_eq!(escape_record("one\ntwo"), "one\\ntwo");
+    }
+}
@@ -0,0 +1,448 @@
+//! Embedding inputs at several granularities, for hierarchical retrieval.
+//!
+//! The chunks of a RAG document become inputs for files, modules, types and
+//! functions. Each input names the input it is part of as its `parent_id`,
+//! and the inputs it holds as its `child_ids`, so retrieval can match files
+//! or modules first and narrow down to their types and functions:
+//!
+//! - files hold the modules whose items they define
+//! - modules hold their types, and their functions and other items outside
+//!   types
+//! - types hold their methods; the chunks of their impls are folded into
+//!   them, and impls of types outside the document are types of their own
+//! - functions are the chunks of functions, constants, macros and the other
+//!   items
+//!
+//! Type and function inputs are those of their chunks, with their metadata.
+//! File and module inputs outline their children with the first line of
+//! their docs, and modules start with the docs of their `mod` item.
+
+use crate::rag::{embedding_text, EmbeddingGranularity, EmbeddingInput, RagChunk};
+use std::collections::HashMap;
+
+/// Element types of the chunks of type inputs.
+const TYPE_KINDS: &[&str] = &["Struct", "Enum", "Union", "Trait", "TypeAlias"];
+
+/// The inputs of `chunks`, coarsest first.
+pub(crate) fn embedding_inputs(chunks: &[RagChunk]) -> Vec<EmbeddingInput> {
+    // Inputs of types by their qualified names.
+    let mut type_ids: HashMap<&str, String> = HashMap::new();
+    for chunk in chunks {
+        let metadata = &chunk.metadata;
+        if metadata.part_index == 0 && TYPE_KINDS.contains(&metadata.element_type.as_str()) {
+            type_ids
+                .entry(&metadata.qualified_name)
+                .or_insert_with(|| chunk.id.clone());
+        }
+    }
+    for chunk in chunks {
+        let metadata = &chunk.metadata;
+        if metadata.part_index == 0 && metadata.element_type == "Impl" {
+            type_ids
+                .entry(self_type(&metadata.qualified_name))
+                .or_insert_with(|| chunk.id.clone());
+        }
+    }
+
+    let mut hierarchy = Hierarchy::default();
+    for chunk in chunks {
+        let metadata = &chunk.metadata;
+        let id = chunk.id.clone();
+        match metadata.element_type.as_str() {
+            "Module" => {
+                let module = hierarchy.module(&metadata.qualified_name);
+                module.declaration.get_or_insert(chunk);
+            }
+            "Impl" if type_ids.get(self_type(&metadata.qualified_name)) != Some(&id) => {}
+            kind if kind == "Impl" || TYPE_KINDS.contains(&kind) => {
+                let module = hierarchy.module(module_path(&metadata.qualified_name));
+                module.file.get_or_insert(&metadata.file_path);
+                module.children.push(id.clone());
+                hierarchy.types.push((id, chunk));
+            }
+            _ => {
+                let owner = self_type(parent_path(&metadata.qualified_name));
+                let parent = match type_ids.get(owner) {
+                    Some(type_id) => {
+                        let methods = hierarchy.methods.entry(type_id.clone()).or_default();
+                        methods.push(id.clone());
+                        type_id.clone()
+                    }
+                    None => {
+                        let module = hierarchy.module(owner);
+                        module.file.get_or_insert(&metadata.file_path);
+                        module.children.push(id.clone());
+                        module_id(owner)
+                    }
+                };
+                hierarchy.functions.push((id, parent, chunk));
+            }
+        }
+    }
+    hierarchy.inputs(chunks)
+}
+
+/// Files, modules, types and functions of the chunks, in the order they
+/// first appear.
+#[derive(Default)]
+struct Hierarchy<'a> {
+    modules: Vec<(&'a str, Module<'a>)>,
+    types: Vec<(String, &'a RagChunk)>,
+    /// Functions with the IDs of their parents
+    functions: Vec<(String, String, &'a RagChunk)>,
+    /// Methods of types, by the IDs of the types
+    methods: HashMap<String, Vec<String>>,
+}
+
+/// A module of the chunks.
+#[derive(Default)]
+struct Module<'a> {
+    /// File defining the module's items
+    file: Option<&'a String>,
+    /// Chunk of the module's `mod` item
+    declaration: Option<&'a RagChunk>,
+    /// IDs of the module's types and functions
+    children: Vec<String>,
+}
+
+impl<'a> Hierarchy<'a> {
+    /// The module at `path`, added if it is new.
+    fn module(&mut self, path: &'a str) -> &mut Module<'a> {
+        let index = match self.modules.iter().position(|(p, _)| *p == path) {
+            Some(index) => index,
+            None => {
+                self.modules.push((path, Module::default()));
+                self.modules.len() - 1
+            }
+        };
+        &mut self.modules[index].1
+    }
+
+    /// The inputs of the hierarchy, given the `chunks` it was built from.
+    fn inputs(self, chunks: &[RagChunk]) -> Vec<EmbeddingInput> {
+        let by_id: HashMap<&str, &RagChunk> = chunks
+            .iter()
+            .map(|chunk| (chunk.id.as_str(), chunk))
+            .collect();
+        let outline = |ids: &[String]| -> Vec<String> {
+            ids.iter()
+                .filter_map(|id| by_id.get(id.as_str()))
+                .map(|chunk| outline_line(chunk))
+                .collect()
+        };
+
+        let mut file_inputs: Vec<EmbeddingInput> = Vec::new();
+        let mut module_inputs = Vec::new();
+        for (path, module) in &self.modules {
+            // Modules of `mod` items without items in the document belong to
+            // the file declaring them.
+            let Some(file) = module
+                .file
+                .or(module.declaration.map(|chunk| &chunk.metadata.file_path))
+            else {
+                continue;
+            };
+            let id = module_id(path);
+            let file_id = format!("file:{}", file);
+            let mut text = format!("Module `{}`", path);
+            if let Some(declaration) = module.declaration {
+                let docs = doc_lines(declaration).join(" ");
+                if !docs.is_empty() {
+                    text = format!("{}: {}", text, docs);
+                }
+            }
+            let lines = outline(&module.children);
+            if !lines.is_empty() {
+                text = format!("{}\n\n{}", text, lines.join("\n"));
+            }
+
+            match file_inputs.iter_mut().find(|input| input.id == file_id) {
+                Some(input) => {
+                    input.child_ids.push(id.clone());
+                    input.text = format!("{}\n\n{}", input.text, text);
+                }
+                None => file_inputs.push(EmbeddingInput {
+                    id: file_id.clone(),
+                    text: format!("File `{}`\n\n{}", file, text),
+                    granularity: EmbeddingGranularity::File,
+                    parent_id: None,
+                    child_ids: vec![id.clone()],
+                    metadata: None,
+                }),
+            }
+            module_inputs.push(EmbeddingInput {
+                id,
+                text,
+                granularity: EmbeddingGranularity::Module,
+                parent_id: Some(file_id),
+                child_ids: module.children.clone(),
+                metadata: module.declaration.map(|chunk| chunk.metadata.clone()),
+            });
+        }
+
+        let mut inputs = file_inputs;
+        inputs.extend(module_inputs);
+        for (id, chunk) in &self.types {
+            let methods = self.methods.get(id).cloned().unwrap_or_default();
+            let mut text = embedding_text(chunk);
+            let lines = outline(&methods);
+            if !lines.is_empty() {
+                text = format!("{}\n\nMethods:\n{}", text, lines.join("\n"));
+            }
+            inputs.push(EmbeddingInput {
+                id: id.clone(),
+                text,
+                granularity: EmbeddingGranularity::Type,
+                parent_id: Some(module_id(module_path(&chunk.metadata.qualified_name))),
+                child_ids: methods,
+                metadata: Some(chunk.metadata.clone()),
+            });
+        }
+        for (id, parent, chunk) in self.functions {
+            inputs.push(EmbeddingInput {
+                id,
+                text: embedding_text(chunk),
+                granularity: EmbeddingGranularity::Function,
+                parent_id: Some(parent),
+                child_ids: Vec::new(),
+                metadata: Some(chunk.metadata.clone()),
+            });
+        }
+        inputs
+    }
+}
+
+/// ID of the input of the module at `path`.
+fn module_id(path: &str) -> String {
+    format!("module:{}", path)
+}
+
+/// The path of the module or type `qualified_name` is in.
+fn parent_path(qualified_name: &str) -> &str {
+    qualified_name
+        .rsplit_once("::")
+        .map_or(qualified_name, |(parent, _)| parent)
+}
+
+/// The type an item named `qualified_name` is of, `T` for the trait impls
+/// `<T as Trait>`, and the item itself otherwise.
+fn self_type(qualified_name: &str) -> &str {
+    qualified_name
+        .strip_prefix('<')
+        .and_then(|rest| rest.split_once(" as "))
+        .map_or(qualified_name, |(self_type, _)| self_type)
+}
+
+/// The path of the module of the type or impl `qualified_name`.
+fn module_path(qualified_name: &str) -> &str {
+    parent_path(self_type(qualified_name))
+}
+
+/// A line outlining `chunk`'s element: its name, kind and the first line of
+/// its docs.
+fn outline_line(chunk: &RagChunk) -> String {
+    let metadata = &chunk.metadata;
+    let line = format!("- `{}` ({})", metadata.element_name, metadata.element_type);
+    match doc_lines(chunk).first() {
+        Some(doc) => format!("{}: {}", line, doc),
+        None => line,
+    }
+}
+
+/// The non-empty lines of the docs in `chunk`'s content, which starts with
+/// them, before a blank line and the signature, for chunks of whole
+/// elements, and has them as comments for chunks of source.
+fn doc_lines(chunk: &RagChunk) -> Vec<&str> {
+    if !chunk.metadata.has_documentation {
+        return Vec::new();
+    }
+    let content = &chunk.content;
+    let comments: Vec<&str> = content
+        .lines()
+        .map(str::trim_start)
+        .filter_map(|line| line.strip_prefix("///").or(line.strip_prefix("//!")))
+        .collect();
+    let lines = if comments.is_empty() {
+        content
+            .rsplit_once("\n\n")
+            .map_or(Vec::new(), |(docs, _)| docs.lines().collect())
+    } else {
+        comments
+    };
+    lines
+        .into_iter()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::{RagConfig, RagFormatter};
+    use rustex_core::{AstExtractor, ExtractorConfig};
+    use std::fs;
+
+    const LIB: &str = r#"/// Networking.
+///
+/// Sockets and addresses.
+pub mod net;
+
+/// Starts the program.
+pub fn run() {}
+"#;
+
+    const NET: &str = r#"/// A connected socket.
+pub struct Socket {
+    port: u16,
+}
+
+impl Socket {
+    /// Sends bytes.
+    pub fn send(&self, bytes: &[u8]) -> usize {
+        bytes.len()
+    }
+
+    pub fn close(self) {}
+}
+
+/// Opens a socket.
+pub fn connect(port: u16) -> Socket {
+    Socket { port }
+}
+"#;
+
+    /// The embedding inputs of a crate with the root `LIB` and the module
+    /// `net` in `NET`.
+    fn inputs() -> Vec<EmbeddingInput> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), LIB).unwrap();
+        fs::write(root.join("src/net.rs"), NET).unwrap();
+        let config = ExtractorConfig {
+            include_private: true,
+            ..ExtractorConfig::default()
+        };
+        let project = AstExtractor::new(config, root.to_path_buf())
+            .extract_project()
+            .unwrap();
+        let document = RagFormatter::new(RagConfig::default())
+            .format(&project)
+            .unwrap();
+        embedding_inputs(&document.chunks)
+    }
+
+    /// The inputs of `granularity`.
+    fn of(inputs: &[EmbeddingInput], granularity: EmbeddingGranularity) -> Vec<&EmbeddingInput> {
+        inputs
+            .iter()
+            .filter(|input| input.granularity == granularity)
+            .collect()
+    }
+
+    #[test]
+    fn test_file_inputs() {
+        let inputs = inputs();
+        let files = of(&inputs, EmbeddingGranularity::File);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].id, "file:src/net.rs");
+        assert_eq!(
+            files[0].text,
+            "File `src/net.rs`\n\n\
+             Module `crate::net`: Networking. Sockets and addresses.\n\n\
+             - `Socket` (Struct): A connected socket.\n\
+             - `connect` (Function): Opens a socket."
+        );
+        assert_eq!(files[1].id, "file:src/lib.rs");
+        assert_eq!(
+            files[1].text,
+            "File `src/lib.rs`\n\nModule `crate`\n\n- `run` (Function): Starts the program."
+        );
+        for file in files {
+            assert_eq!(file.parent_id, None);
+            assert!(file.metadata.is_none());
+        }
+    }
+
+    #[test]
+    fn test_module_inputs() {
+        let inputs = inputs();
+        let modules = of(&inputs, EmbeddingGranularity::Module);
+        let ids: Vec<&str> = modules.iter().map(|input| input.id.as_str()).collect();
+        assert_eq!(ids, ["module:crate::net", "module:crate"]);
+        assert_eq!(
+            modules[0].text,
+            "Module `crate::net`: Networking. Sockets and addresses.\n\n\
+             - `Socket` (Struct): A connected socket.\n\
+             - `connect` (Function): Opens a socket."
+        );
+        assert_eq!(modules[0].parent_id.as_deref(), Some("file:src/net.rs"));
+        assert_eq!(modules[0].child_ids.len(), 2);
+        // Only modules with a `mod` item have its metadata.
+        assert_eq!(modules[0].metadata.as_ref().unwrap().element_name, "net");
+        assert!(modules[1].metadata.is_none());
+    }
+
+    #[test]
+    fn test_element_inputs() {
+        let inputs = inputs();
+        let types = of(&inputs, EmbeddingGranularity::Type);
+        assert_eq!(types.len(), 1);
+        assert_eq!(types[0].parent_id.as_deref(), Some("module:crate::net"));
+        assert!(
+            types[0].text.contains("A connected socket."),
+            "{}",
+            types[0].text
+        );
+        assert!(
+            types[0]
+                .text
+                .ends_with("\n\nMethods:\n- `send` (Function): Sends bytes.\n- `close` (Function)"),
+            "{}",
+            types[0].text
+        );
+
+        let functions = of(&inputs, EmbeddingGranularity::Function);
+        let names: Vec<&str> = functions
+            .iter()
+            .map(|input| input.metadata.as_ref().unwrap().element_name.as_str())
+            .collect();
+        assert_eq!(names, ["run", "send", "close", "connect"]);
+        assert_eq!(
+            types[0].child_ids,
+            [functions[1].id.clone(), functions[2].id.clone()]
+        );
+        assert_eq!(functions[0].parent_id.as_deref(), Some("module:crate"));
+        assert_eq!(functions[1].parent_id.as_ref(), Some(&types[0].id));
+        assert!(
+            functions[1].text.contains("fn send"),
+            "{}",
+            functions[1].text
+        );
+        assert!(functions.iter().all(|input| input.child_ids.is_empty()));
+    }
+
+    #[test]
+    fn test_links() {
+        let inputs = inputs();
+        let by_id: HashMap<&str, &EmbeddingInput> = inputs
+            .iter()
+            .map(|input| (input.id.as_str(), input))
+            .collect();
+        assert_eq!(by_id.len(), inputs.len());
+        for input in &inputs {
+            for child in &input.child_ids {
+                assert_eq!(by_id[child.as_str()].parent_id.as_ref(), Some(&input.id));
+            }
+            if let Some(parent) = &input.parent_id {
+                assert!(by_id[parent.as_str()].child_ids.contains(&input.id));
+            }
+        }
+    }
+}
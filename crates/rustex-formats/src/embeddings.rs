@@ -0,0 +1,200 @@
+//! Embedding vectors for RAG chunks.
+//!
+//! An [`EmbeddingProvider`] turns batches of texts into vectors. Providers
+//! for OpenAI-compatible HTTP APIs ([`OpenAiEmbeddings`], feature
+//! `embeddings-http`) and local BERT-style models ([`CandleEmbeddings`],
+//! feature `embeddings-candle`) are included, and [`embedding_provider`]
+//! picks one from the `[embeddings]` configuration. The [`RagFormatter`]
+//! sends the chunks to its provider in batches of `embedding_batch_size`,
+//! at most `embedding_requests_per_minute` requests a minute, retrying
+//! requests that fail on the way. Embedded texts are cached, so formatting
+//! the same chunks again, as when fitting output to a budget, doesn't
+//! repeat requests.
+//!
+//! [`RagFormatter`]: crate::rag::RagFormatter
+
+#[cfg(feature = "embeddings-candle")]
+mod candle;
+#[cfg(feature = "embeddings-http")]
+mod openai;
+
+#[cfg(feature = "embeddings-candle")]
+pub use candle::CandleEmbeddings;
+#[cfg(feature = "embeddings-http")]
+pub use openai::OpenAiEmbeddings;
+
+use crate::rag::RagConfig;
+use anyhow::Result;
+use rustex_core::EmbeddingConfig;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Delay before the first retry of a failed request, doubled for each
+/// retry after it.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay before a retry, including delays asked for by the provider.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A model turning texts into embedding vectors.
+pub trait EmbeddingProvider: Send + Sync {
+    /// Name of the model, recorded in the RAG metadata.
+    fn model(&self) -> &str;
+
+    /// Most texts the provider embeds in one call.
+    fn max_batch_size(&self) -> usize {
+        usize::MAX
+    }
+
+    /// Embed `texts`, returning one vector per text, in order.
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError>;
+}
+
+/// Error of an [`EmbeddingProvider`].
+#[derive(Debug, thiserror::Error)]
+pub enum EmbeddingError {
+    /// A failure that may pass, such as a rate limit or an unavailable
+    /// server; the request is retried
+    #[error("{message}")]
+    Transient {
+        message: String,
+        /// How long the provider asked to wait before retrying
+        retry_after: Option<Duration>,
+    },
+    /// A failure that retrying won't fix, such as a rejected API key
+    #[error(transparent)]
+    Fatal(#[from] anyhow::Error),
+}
+
+/// The provider configured by `config`: the local model in `model_dir` if it
+/// is set, and the OpenAI-compatible API at `url` otherwise.
+pub fn embedding_provider(config: &EmbeddingConfig) -> Result<Arc<dyn EmbeddingProvider>> {
+    if let Some(model_dir) = &config.model_dir {
+        #[cfg(feature = "embeddings-candle")]
+        return Ok(Arc::new(CandleEmbeddings::load(model_dir)?));
+        #[cfg(not(feature = "embeddings-candle"))]
+        anyhow::bail!(
+            "Embedding with the local model in {} needs the embeddings-candle feature",
+            model_dir.display()
+        );
+    }
+
+    #[cfg(feature = "embeddings-http")]
+    {
+        let api_key = std::env::var(&config.api_key_env)
+            .ok()
+            .filter(|key| !key.is_empty());
+        Ok(Arc::new(OpenAiEmbeddings::new(
+            &config.url,
+            &config.model,
+            api_key,
+        )))
+    }
+    #[cfg(not(feature = "embeddings-http"))]
+    anyhow::bail!(
+        "Embedding with the API at {} needs the embeddings-http feature",
+        config.url
+    )
+}
+
+/// Sends texts to a provider in batches, pacing and retrying the requests.
+pub(crate) struct Embedder {
+    provider: Arc<dyn EmbeddingProvider>,
+    batch_size: usize,
+    request_interval: Option<Duration>,
+    max_retries: u32,
+    last_request: Mutex<Option<Instant>>,
+    /// Vectors of the texts embedded so far
+    cache: Mutex<HashMap<String, Vec<f32>>>,
+}
+
+impl Embedder {
+    /// Embed with `provider`, following the embedding settings of `config`.
+    pub(crate) fn new(provider: Arc<dyn EmbeddingProvider>, config: &RagConfig) -> Self {
+        Self {
+            batch_size: config
+                .embedding_batch_size
+                .min(provider.max_batch_size())
+                .max(1),
+            request_interval: config
+                .embedding_requests_per_minute
+                .filter(|requests| *requests > 0)
+                .map(|requests| Duration::from_secs(60) / requests),
+            max_retries: config.embedding_max_retries,
+            provider,
+            last_request: Mutex::new(None),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Name of the provider's model.
+    pub(crate) fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    /// Embed `texts`, returning one vector per text, in order.
+    pub(crate) fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut seen = HashSet::new();
+        let missing: Vec<String> = texts
+            .iter()
+            .filter(|text| !cache.contains_key(*text) && seen.insert(text.as_str()))
+            .cloned()
+            .collect();
+        for batch in missing.chunks(self.batch_size) {
+            let vectors = self.embed_batch(batch)?;
+            if vectors.len() != batch.len() {
+                anyhow::bail!(
+                    "Embedding model {} returned {} vectors for {} texts",
+                    self.model(),
+                    vectors.len(),
+                    batch.len()
+                );
+            }
+            cache.extend(batch.iter().cloned().zip(vectors));
+        }
+        Ok(texts.iter().map(|text| cache[text].clone()).collect())
+    }
+
+    /// Embed one batch, retrying transient failures with exponential backoff.
+    fn embed_batch(&self, batch: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut retries = 0;
+        loop {
+            self.wait_for_rate_limit();
+            match self.provider.embed(batch) {
+                Ok(vectors) => return Ok(vectors),
+                Err(EmbeddingError::Transient { retry_after, .. })
+                    if retries < self.max_retries =>
+                {
+                    std::thread::sleep(retry_after.unwrap_or(backoff).min(MAX_BACKOFF));
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    retries += 1;
+                }
+                Err(EmbeddingError::Transient { message, .. }) => {
+                    anyhow::bail!("{} (gave up after {} retries)", message, retries)
+                }
+                Err(EmbeddingError::Fatal(error)) => return Err(error),
+            }
+        }
+    }
+
+    /// Wait until the next request keeps to `embedding_requests_per_minute`.
+    fn wait_for_rate_limit(&self) {
+        let Some(interval) = self.request_interval else {
+            return;
+        };
+        let mut last_request = self
+            .last_request
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(last) = *last_request {
+            let elapsed = last.elapsed();
+            if elapsed < interval {
+                std::thread::sleep(interval - elapsed);
+            }
+        }
+        *last_request = Some(Instant::now());
+    }
+}
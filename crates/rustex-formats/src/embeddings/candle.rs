@@ -0,0 +1,107 @@
+//! Embeddings from local BERT-style models, run with candle.
+
+use super::{EmbeddingError, EmbeddingProvider};
+use anyhow::{Context, Result};
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config};
+use std::path::Path;
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+/// Embeddings from a local BERT-style sentence embedding model, such as
+/// `sentence-transformers/all-MiniLM-L6-v2`, run on the CPU. Each text's
+/// vector is the mean of its token embeddings, normalized to unit length.
+pub struct CandleEmbeddings {
+    name: String,
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+impl CandleEmbeddings {
+    /// Load the model in `model_dir`, which holds its `config.json`,
+    /// `tokenizer.json` and `model.safetensors`, as downloaded from the
+    /// Hugging Face Hub.
+    pub fn load(model_dir: &Path) -> Result<Self> {
+        let device = Device::Cpu;
+        let config_path = model_dir.join("config.json");
+        let config: Config = serde_json::from_str(
+            &std::fs::read_to_string(&config_path)
+                .with_context(|| format!("Failed to read {}", config_path.display()))?,
+        )
+        .with_context(|| format!("Invalid model configuration {}", config_path.display()))?;
+
+        let tokenizer_path = model_dir.join("tokenizer.json");
+        let mut tokenizer = Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", tokenizer_path.display(), e))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..Default::default()
+            }))
+            .map_err(anyhow::Error::msg)?;
+
+        let weights_path = model_dir.join("model.safetensors");
+        // SAFETY: the weights file is only read, and is expected not to
+        // change while the model is loaded.
+        let weights =
+            unsafe { VarBuilder::from_mmaped_safetensors(&[&weights_path], DType::F32, &device) }
+                .with_context(|| format!("Failed to load {}", weights_path.display()))?;
+        let model = BertModel::load(weights, &config)?;
+
+        let name = model_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| model_dir.display().to_string());
+        Ok(Self {
+            name,
+            model,
+            tokenizer,
+            device,
+        })
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(anyhow::Error::msg)?;
+        let tensor = |rows: Vec<&[u32]>| -> Result<Tensor> {
+            let rows = rows
+                .into_iter()
+                .map(|row| Tensor::new(row, &self.device))
+                .collect::<candle_core::Result<Vec<_>>>()?;
+            Ok(Tensor::stack(&rows, 0)?)
+        };
+        let token_ids = tensor(encodings.iter().map(|e| e.get_ids()).collect())?;
+        let attention_mask = tensor(encodings.iter().map(|e| e.get_attention_mask()).collect())?;
+        let token_type_ids = token_ids.zeros_like()?;
+
+        // Mean of the embeddings of the tokens, leaving out the padding.
+        let output = self
+            .model
+            .forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let pooled = output
+            .broadcast_mul(&mask)?
+            .sum(1)?
+            .broadcast_div(&mask.sum(1)?)?;
+        let normalized = pooled.broadcast_div(&pooled.sqr()?.sum_keepdim(1)?.sqrt()?)?;
+        Ok(normalized.to_vec2()?)
+    }
+}
+
+impl EmbeddingProvider for CandleEmbeddings {
+    fn model(&self) -> &str {
+        &self.name
+    }
+
+    fn max_batch_size(&self) -> usize {
+        32
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        Ok(self.embed_batch(texts)?)
+    }
+}
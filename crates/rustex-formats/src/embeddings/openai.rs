@@ -0,0 +1,101 @@
+//! Embeddings from OpenAI-compatible HTTP APIs.
+
+use super::{EmbeddingError, EmbeddingProvider};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Most texts OpenAI embeds in one request.
+const MAX_INPUTS: usize = 2048;
+
+/// Embeddings from the `/embeddings` endpoint of an OpenAI-compatible API,
+/// such as those of OpenAI, Azure OpenAI, Ollama, vLLM or text-embeddings-inference.
+pub struct OpenAiEmbeddings {
+    agent: ureq::Agent,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl OpenAiEmbeddings {
+    /// Embeddings of `model` from the API at `base_url`, such as
+    /// `https://api.openai.com/v1`, authenticated with `api_key` if given.
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(120))
+                .build(),
+            endpoint: format!("{}/embeddings", base_url.trim_end_matches('/')),
+            model: model.to_string(),
+            api_key,
+        }
+    }
+}
+
+impl EmbeddingProvider for OpenAiEmbeddings {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_batch_size(&self) -> usize {
+        MAX_INPUTS
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let mut request = self.agent.post(&self.endpoint);
+        if let Some(api_key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+        let body = serde_json::json!({ "model": self.model, "input": texts });
+        let response = match request.send_json(body) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                // Retry-After may also be an HTTP date, which is left to the backoff.
+                let retry_after = response
+                    .header("Retry-After")
+                    .and_then(|seconds| seconds.trim().parse().ok())
+                    .map(Duration::from_secs);
+                let message = format!(
+                    "Embedding request to {} failed with status {}: {}",
+                    self.endpoint,
+                    status,
+                    response.into_string().unwrap_or_default().trim()
+                );
+                return Err(if status == 429 || status >= 500 {
+                    EmbeddingError::Transient {
+                        message,
+                        retry_after,
+                    }
+                } else {
+                    EmbeddingError::Fatal(anyhow::anyhow!(message))
+                });
+            }
+            Err(error) => {
+                return Err(EmbeddingError::Transient {
+                    message: format!("Embedding request to {} failed: {}", self.endpoint, error),
+                    retry_after: None,
+                })
+            }
+        };
+
+        let mut response: EmbeddingResponse = response.into_json().map_err(|e| {
+            anyhow::anyhow!("Invalid embedding response from {}: {}", self.endpoint, e)
+        })?;
+        response.data.sort_by_key(|data| data.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|data| data.embedding)
+            .collect())
+    }
+}
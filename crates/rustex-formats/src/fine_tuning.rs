@@ -0,0 +1,296 @@
+//! Training examples as fine-tuning data for the OpenAI and Anthropic APIs.
+//!
+//! [`format_as_openai_fine_tuning`] writes one chat conversation per line,
+//! as OpenAI's fine-tuning jobs take them: a system message, the example's
+//! input as the user message and its output as the assistant message.
+//! [`format_as_anthropic_fine_tuning`] writes the system prompt and messages
+//! of each example the way Claude fine-tuning takes them.
+//!
+//! System prompts are Tera templates from [`FineTuningConfig`], one per task
+//! type or a shared one, rendered with the `project` name and `version`, and
+//! the `task`, `difficulty` and `concepts` of the example.
+
+use crate::rag::{DifficultyLevel, RagDocument, TaskType, TrainingExample};
+use anyhow::{Context as _, Result};
+use rustex_core::FineTuningConfig;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tera::{Context, Tera};
+
+/// A message of a fine-tuning conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FineTuningMessage {
+    /// `system`, `user` or `assistant`
+    pub role: String,
+    pub content: String,
+}
+
+impl FineTuningMessage {
+    fn new(role: &str, content: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
+/// A line of OpenAI chat fine-tuning data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct OpenAiExample {
+    /// The system prompt, unless it is empty, then the example's input and
+    /// output
+    pub messages: Vec<FineTuningMessage>,
+}
+
+/// A line of Anthropic message fine-tuning data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AnthropicExample {
+    /// System prompt of the conversation
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub system: String,
+    /// The example's input and output
+    pub messages: Vec<FineTuningMessage>,
+}
+
+/// Render the training examples of `document` as OpenAI chat fine-tuning
+/// JSON Lines.
+pub fn format_as_openai_fine_tuning(
+    document: &RagDocument,
+    config: &FineTuningConfig,
+) -> Result<String> {
+    format_examples(document, config, |system, example| {
+        let mut messages = Vec::new();
+        if !system.is_empty() {
+            messages.push(FineTuningMessage::new("system", &system));
+        }
+        messages.push(FineTuningMessage::new("user", &example.input));
+        messages.push(FineTuningMessage::new("assistant", &example.output));
+        serde_json::to_string(&OpenAiExample { messages })
+    })
+}
+
+/// Render the training examples of `document` as Anthropic message
+/// fine-tuning JSON Lines.
+pub fn format_as_anthropic_fine_tuning(
+    document: &RagDocument,
+    config: &FineTuningConfig,
+) -> Result<String> {
+    format_examples(document, config, |system, example| {
+        serde_json::to_string(&AnthropicExample {
+            system,
+            messages: vec![
+                FineTuningMessage::new("user", &example.input),
+                FineTuningMessage::new("assistant", &example.output),
+            ],
+        })
+    })
+}
+
+/// One line per training example of `document`, made by `line` from the
+/// example and its rendered system prompt.
+fn format_examples(
+    document: &RagDocument,
+    config: &FineTuningConfig,
+    line: impl Fn(String, &TrainingExample) -> serde_json::Result<String>,
+) -> Result<String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("system_prompt", &config.system_prompt)
+        .context("Invalid template in fine_tuning.system_prompt")?;
+    for (task, template) in &config.system_prompts {
+        tera.add_raw_template(task, template)
+            .with_context(|| format!("Invalid template in fine_tuning.system_prompts.{}", task))?;
+    }
+
+    let metadata = &document.metadata;
+    let mut output = String::new();
+    for example in &document.training_examples {
+        let task = task_key(&example.task_type);
+        let mut context = Context::new();
+        context.insert("project", &metadata.project_name);
+        context.insert("version", &metadata.project_version);
+        context.insert("task", task);
+        context.insert("difficulty", difficulty_key(&example.difficulty));
+        context.insert("concepts", &example.metadata.concepts_involved);
+        let template = if config.system_prompts.contains_key(task) {
+            task
+        } else {
+            "system_prompt"
+        };
+        let system = tera
+            .render(template, &context)
+            .with_context(|| format!("Failed to render the system prompt of {}", example.id))?;
+        output.push_str(&line(system.trim().to_string(), example)?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// The key of `task` in `fine_tuning.system_prompts`, one of
+/// [`rustex_core::FINE_TUNING_TASKS`].
+fn task_key(task: &TaskType) -> &'static str {
+    match task {
+        TaskType::CodeGeneration => "code_generation",
+        TaskType::CodeExplanation => "code_explanation",
+        TaskType::CodeCompletion => "code_completion",
+        TaskType::CodeRefactoring => "code_refactoring",
+        TaskType::BugFinding => "bug_finding",
+        TaskType::CodeSummarization => "code_summarization",
+        TaskType::ApiUsage => "api_usage",
+        TaskType::PatternRecognition => "pattern_recognition",
+        TaskType::DocumentationGeneration => "documentation_generation",
+        TaskType::FunctionNaming => "function_naming",
+        TaskType::TestGeneration => "test_generation",
+    }
+}
+
+fn difficulty_key(difficulty: &DifficultyLevel) -> &'static str {
+    match difficulty {
+        DifficultyLevel::Beginner => "beginner",
+        DifficultyLevel::Intermediate => "intermediate",
+        DifficultyLevel::Advanced => "advanced",
+        DifficultyLevel::Expert => "expert",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::{RagConfig, RagFormatter, TrainingMetadata};
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use serde_json::{Map, Value};
+
+    /// A document with a code completion and an API usage example.
+    fn document() -> RagDocument {
+        let project = MockDataGenerator::project_ast(1, 0);
+        let mut document = RagFormatter::new(RagConfig::default())
+            .format(&project)
+            .unwrap();
+        for (id, task_type, input, output) in [
+            (
+                "example_1",
+                TaskType::CodeCompletion,
+                "Complete \"this\":\n\nfn add(",
+                "a + b",
+            ),
+            (
+                "example_2",
+                TaskType::ApiUsage,
+                "Show how to use `add`",
+                "add(1, 2);",
+            ),
+        ] {
+            document.training_examples.push(TrainingExample {
+                id: id.to_string(),
+                input: input.to_string(),
+                output: output.to_string(),
+                task_type,
+                difficulty: DifficultyLevel::Intermediate,
+                metadata: TrainingMetadata {
+                    source_chunks: Vec::new(),
+                    concepts_involved: vec!["math".to_string()],
+                    required_knowledge: Vec::new(),
+                    learning_objectives: Vec::new(),
+                    estimated_token_count: 10,
+                },
+            });
+        }
+        document
+    }
+
+    /// The lines of `jsonl`, each parsed as a JSON object.
+    fn parse_lines(jsonl: &str) -> Vec<Map<String, Value>> {
+        assert!(jsonl.ends_with('\n'));
+        jsonl
+            .lines()
+            .map(|line| match serde_json::from_str(line).unwrap() {
+                Value::Object(object) => object,
+                value => panic!("line is not an object: {}", value),
+            })
+            .collect()
+    }
+
+    /// The `(role, content)` pairs of the `messages` of `line`.
+    fn messages(line: &Map<String, Value>) -> Vec<(&str, &str)> {
+        line["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| {
+                let message = message.as_object().unwrap();
+                assert_eq!(message.len(), 2);
+                (
+                    message["role"].as_str().unwrap(),
+                    message["content"].as_str().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_openai() {
+        let config = FineTuningConfig::default();
+        let lines = parse_lines(&format_as_openai_fine_tuning(&document(), &config).unwrap());
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            assert_eq!(line.keys().collect::<Vec<_>>(), ["messages"]);
+        }
+        let system = "You are an expert Rust programmer who knows the test-project crate.";
+        assert_eq!(
+            messages(&lines[0]),
+            [
+                ("system", system),
+                ("user", "Complete \"this\":\n\nfn add("),
+                ("assistant", "a + b"),
+            ]
+        );
+
+        // An empty system prompt leaves the system message out.
+        let config = FineTuningConfig {
+            system_prompt: String::new(),
+            ..config
+        };
+        let lines = parse_lines(&format_as_openai_fine_tuning(&document(), &config).unwrap());
+        assert_eq!(messages(&lines[1])[0], ("user", "Show how to use `add`"));
+    }
+
+    #[test]
+    fn test_anthropic() {
+        let mut config = FineTuningConfig::default();
+        config.system_prompts.insert(
+            "api_usage".to_string(),
+            "Teach {{ project }} {{ version }} at {{ difficulty }} level: {{ concepts | join(sep=\", \") }}"
+                .to_string(),
+        );
+        let lines = parse_lines(&format_as_anthropic_fine_tuning(&document(), &config).unwrap());
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            assert_eq!(line.keys().collect::<Vec<_>>(), ["messages", "system"]);
+            let roles: Vec<&str> = messages(line).iter().map(|(role, _)| *role).collect();
+            assert_eq!(roles, ["user", "assistant"]);
+        }
+        assert_eq!(
+            lines[0]["system"],
+            "You are an expert Rust programmer who knows the test-project crate."
+        );
+        assert_eq!(
+            lines[1]["system"],
+            "Teach test-project 0.1.0 at intermediate level: math"
+        );
+        assert_eq!(messages(&lines[1])[1], ("assistant", "add(1, 2);"));
+
+        // Without a system prompt, the field is left out.
+        config.system_prompt = String::new();
+        let lines = parse_lines(&format_as_anthropic_fine_tuning(&document(), &config).unwrap());
+        assert!(!lines[0].contains_key("system"));
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        let config = FineTuningConfig {
+            system_prompt: "{{ project".to_string(),
+            ..FineTuningConfig::default()
+        };
+        let error = format_as_openai_fine_tuning(&document(), &config).unwrap_err();
+        assert!(error.to_string().contains("fine_tuning.system_prompt"));
+    }
+}
@@ -1,8 +1,14 @@
 //! Output format implementations for different target formats.
 
-use rustex_core::{ProjectAst, OutputFormat};
-use crate::rag::{RagFormatter, RagConfig};
+use rustex_core::{FileAst, FineTuningConfig, MarkdownConfig, ProjectAst, ProjectInfo, OutputFormat};
+use crate::rag::{RagChunk, RagConfig, RagDocument, RagFormatter, RagMetadata};
+use crate::fine_tuning::{AnthropicExample, OpenAiExample};
+use crate::hf_dataset::DatasetRow;
+use crate::langchain::LangChainDocument;
+use crate::llamaindex::LlamaIndexNode;
+use crate::manifest::RemovedChunk;
 use anyhow::Result;
+use schemars::{schema_for, JsonSchema};
 
 /// Format project AST according to the specified output format.
 pub fn format_project_ast(
@@ -19,12 +25,13 @@ pub fn format_project_ast(
             }
         }
         OutputFormat::MessagePack => {
-            let data = rmp_serde::to_vec(project_ast)?;
+            // Strings can't hold binary data; writers of bytes use `format_as_message_pack`.
+            let data = format_as_message_pack(project_ast)?;
             use base64::Engine;
             Ok(base64::engine::general_purpose::STANDARD.encode(data))
         }
         OutputFormat::Markdown => {
-            format_as_markdown(project_ast)
+            crate::markdown::format_as_markdown(project_ast, &MarkdownConfig::default(), &[])
         }
         OutputFormat::GraphQL => {
             format_as_graphql_schema(project_ast)
@@ -34,84 +41,96 @@ pub fn format_project_ast(
             let rag_doc = formatter.format(project_ast)?;
             crate::rag::format_as_json(&rag_doc, pretty)
         }
+        OutputFormat::Jsonl => format_as_file_jsonl(project_ast),
+        OutputFormat::RagJsonl => format_as_rag_jsonl(project_ast),
+        OutputFormat::LangChain => {
+            let rag_doc = RagFormatter::default().format(project_ast)?;
+            crate::langchain::format_as_langchain(&rag_doc, pretty)
+        }
+        OutputFormat::LlamaIndex => {
+            let rag_doc = RagFormatter::default().format(project_ast)?;
+            crate::llamaindex::format_as_llamaindex(&rag_doc, pretty)
+        }
+        OutputFormat::HfDataset => {
+            anyhow::bail!("Hugging Face datasets are many files; use `format_as_hf_dataset`")
+        }
+        OutputFormat::OpenAiFineTune => {
+            let rag_doc = RagFormatter::default().format(project_ast)?;
+            let config = FineTuningConfig::default();
+            crate::fine_tuning::format_as_openai_fine_tuning(&rag_doc, &config)
+        }
+        OutputFormat::AnthropicFineTune => {
+            let rag_doc = RagFormatter::default().format(project_ast)?;
+            let config = FineTuningConfig::default();
+            crate::fine_tuning::format_as_anthropic_fine_tuning(&rag_doc, &config)
+        }
+        OutputFormat::Mermaid => crate::mermaid::format_as_mermaid(project_ast, &[]),
+        OutputFormat::Dot => crate::dot::format_as_dot(project_ast, &[]),
+        OutputFormat::Csv => {
+            crate::csv::format_as_csv(project_ast, rustex_core::CsvTable::Elements)
+        }
+        OutputFormat::Parquet => {
+            // Strings can't hold binary data; writers of bytes use `format_as_parquet`.
+            let data = crate::csv::format_as_parquet(project_ast, rustex_core::CsvTable::Elements)?;
+            use base64::Engine;
+            Ok(base64::engine::general_purpose::STANDARD.encode(data))
+        }
+        OutputFormat::Turtle => crate::rdf::format_as_turtle(project_ast),
+        OutputFormat::NTriples => crate::rdf::format_as_ntriples(project_ast),
+        OutputFormat::Cypher => crate::cypher::format_as_cypher(project_ast),
+        OutputFormat::Protobuf => {
+            // Strings can't hold binary data; writers of bytes use `format_as_protobuf`.
+            let data = crate::protobuf::format_as_protobuf(project_ast)?;
+            use base64::Engine;
+            Ok(base64::engine::general_purpose::STANDARD.encode(data))
+        }
+        OutputFormat::Scip => {
+            // Strings can't hold binary data; writers of bytes use `format_as_scip`.
+            let data = crate::scip::format_as_scip(project_ast)?;
+            use base64::Engine;
+            Ok(base64::engine::general_purpose::STANDARD.encode(data))
+        }
+        OutputFormat::Ctags => crate::ctags::format_as_ctags(project_ast),
+        OutputFormat::Etags => crate::ctags::format_as_etags(project_ast),
+        OutputFormat::Overview => crate::overview::format_as_overview(project_ast),
+        OutputFormat::Sarif => crate::sarif::format_as_sarif(project_ast, pretty),
+        OutputFormat::Template => {
+            anyhow::bail!("Template output needs a template; use `format_with_template`")
+        }
     }
 }
 
-/// Format project AST as Markdown documentation.
-pub fn format_as_markdown(project_ast: &ProjectAst) -> Result<String> {
-    let mut markdown = String::new();
-    
-    // Add header
-    markdown.push_str(&format!("# {}\n\n", project_ast.project.name));
-    markdown.push_str(&format!("**Version:** {}\n", project_ast.project.version));
-    markdown.push_str(&format!("**Rust Edition:** {}\n\n", project_ast.project.rust_edition));
-    
-    // Add table of contents
-    markdown.push_str("## Table of Contents\n\n");
-    for file in &project_ast.files {
-        markdown.push_str(&format!("- [{}](#{})\n", 
-            file.relative_path.display(),
-            file.relative_path.to_string_lossy().replace(['/', '.'], "")
-        ));
-    }
-    markdown.push('\n');
-    
-    // Add project metrics
-    markdown.push_str("## Project Metrics\n\n");
-    markdown.push_str(&format!("- **Total Files:** {}\n", project_ast.metrics.total_files));
-    markdown.push_str(&format!("- **Total Lines:** {}\n", project_ast.metrics.total_lines));
-    markdown.push_str(&format!("- **Total Functions:** {}\n", project_ast.metrics.total_functions));
-    markdown.push_str(&format!("- **Total Structs:** {}\n", project_ast.metrics.total_structs));
-    markdown.push_str(&format!("- **Total Enums:** {}\n", project_ast.metrics.total_enums));
-    markdown.push_str(&format!("- **Total Traits:** {}\n", project_ast.metrics.total_traits));
-    markdown.push_str(&format!("- **Average Complexity:** {:.2}\n\n", project_ast.metrics.complexity_average));
+/// Format project AST as MessagePack, with field names as map keys so readers
+/// don't depend on the order of fields.
+pub fn format_as_message_pack(project_ast: &ProjectAst) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec_named(project_ast)?)
+}
+
+/// Read a project AST written by [`format_as_message_pack`].
+pub fn parse_message_pack(data: &[u8]) -> Result<ProjectAst> {
+    Ok(rmp_serde::from_slice(data)?)
+}
+
+/// Format project AST as JSONL: the project info, then one file per line.
+pub fn format_as_file_jsonl(project_ast: &ProjectAst) -> Result<String> {
+    let mut output = serde_json::to_string(&project_ast.project)?;
+    output.push('\n');
     
-    // Add file documentation
     for file in &project_ast.files {
-        markdown.push_str(&format!("## {}\n\n", file.relative_path.display()));
-        
-        if !file.elements.is_empty() {
-            for element in &file.elements {
-                // Add element documentation
-                markdown.push_str(&format!("### {:?} `{}`\n\n", 
-                    element.element_type,
-                    element.name
-                ));
-                
-                // Add documentation comments
-                if !element.doc_comments.is_empty() {
-                    for doc in &element.doc_comments {
-                        markdown.push_str(&format!("{}\n", doc));
-                    }
-                    markdown.push('\n');
-                }
-                
-                // Add signature
-                if let Some(signature) = &element.signature {
-                    markdown.push_str("```rust\n");
-                    markdown.push_str(signature);
-                    markdown.push_str("\n```\n\n");
-                }
-                
-                // Add metadata
-                markdown.push_str("**Details:**\n");
-                markdown.push_str(&format!("- **Location:** {}:{}-{}\n", 
-                    file.relative_path.display(),
-                    element.location.line_start,
-                    element.location.line_end
-                ));
-                markdown.push_str(&format!("- **Visibility:** {:?}\n", element.visibility));
-                if let Some(complexity) = element.complexity {
-                    markdown.push_str(&format!("- **Complexity:** {}\n", complexity));
-                }
-                markdown.push('\n');
-            }
-        } else {
-            markdown.push_str("*No documented elements in this file.*\n\n");
-        }
+        output.push_str(&serde_json::to_string(file)?);
+        output.push('\n');
     }
     
-    Ok(markdown)
+    Ok(output)
+}
+
+/// Render names as a comma-separated list of inline code spans.
+pub(crate) fn code_list<S: AsRef<str>>(names: &[S]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{}`", name.as_ref()))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Format project AST as GraphQL schema.
@@ -120,96 +139,487 @@ pub fn format_as_graphql_schema(project_ast: &ProjectAst) -> Result<String> {
     
     // Add header comment
     schema.push_str(&format!("# GraphQL Schema for {}\n", project_ast.project.name));
-    schema.push_str(&format!("# Generated from Rust AST\n\n"));
-    
-    // Add project type
-    schema.push_str("type Project {\n");
-    schema.push_str("  name: String!\n");
-    schema.push_str("  version: String!\n");
-    schema.push_str("  rustEdition: String!\n");
-    schema.push_str("  files: [File!]!\n");
-    schema.push_str("  metrics: ProjectMetrics!\n");
-    schema.push_str("}\n\n");
-    
-    // Add file type
-    schema.push_str("type File {\n");
-    schema.push_str("  path: String!\n");
-    schema.push_str("  elements: [CodeElement!]!\n");
-    schema.push_str("}\n\n");
-    
-    // Add code element type
-    schema.push_str("type CodeElement {\n");
-    schema.push_str("  id: String!\n");
-    schema.push_str("  elementType: ElementType!\n");
-    schema.push_str("  name: String!\n");
-    schema.push_str("  signature: String\n");
-    schema.push_str("  docComments: [String!]!\n");
-    schema.push_str("  visibility: Visibility!\n");
-    schema.push_str("  complexity: Int\n");
-    schema.push_str("  location: Location!\n");
-    schema.push_str("  hierarchy: ElementHierarchy!\n");
-    schema.push_str("  crossReferences: CrossReferences!\n");
-    schema.push_str("}\n\n");
-    
-    // Add enums
-    schema.push_str("enum ElementType {\n");
-    schema.push_str("  FUNCTION\n");
-    schema.push_str("  STRUCT\n");
-    schema.push_str("  ENUM\n");
-    schema.push_str("  TRAIT\n");
-    schema.push_str("  IMPLEMENTATION\n");
-    schema.push_str("  MODULE\n");
-    schema.push_str("}\n\n");
-    
-    schema.push_str("enum Visibility {\n");
-    schema.push_str("  PUBLIC\n");
-    schema.push_str("  PRIVATE\n");
-    schema.push_str("  CRATE\n");
-    schema.push_str("  SUPER\n");
-    schema.push_str("}\n\n");
-    
-    // Add supporting types
-    schema.push_str("type Location {\n");
-    schema.push_str("  lineStart: Int!\n");
-    schema.push_str("  lineEnd: Int!\n");
-    schema.push_str("  columnStart: Int!\n");
-    schema.push_str("  columnEnd: Int!\n");
-    schema.push_str("}\n\n");
-    
-    schema.push_str("type ElementHierarchy {\n");
-    schema.push_str("  qualifiedName: String!\n");
-    schema.push_str("  modulePath: String!\n");
-    schema.push_str("  parentId: String\n");
-    schema.push_str("  children: [String!]!\n");
-    schema.push_str("}\n\n");
-    
-    schema.push_str("type CrossReferences {\n");
-    schema.push_str("  outgoing: [String!]!\n");
-    schema.push_str("  incoming: [String!]!\n");
-    schema.push_str("}\n\n");
-    
-    schema.push_str("type ProjectMetrics {\n");
-    schema.push_str("  totalFiles: Int!\n");
-    schema.push_str("  totalLines: Int!\n");
-    schema.push_str("  totalFunctions: Int!\n");
-    schema.push_str("  totalStructs: Int!\n");
-    schema.push_str("  totalEnums: Int!\n");
-    schema.push_str("  totalTraits: Int!\n");
-    schema.push_str("  complexityAverage: Float!\n");
-    schema.push_str("}\n\n");
-    
-    // Add root query
-    schema.push_str("type Query {\n");
-    schema.push_str("  project: Project!\n");
-    schema.push_str("  file(path: String!): File\n");
-    schema.push_str("  element(id: String!): CodeElement\n");
-    schema.push_str("  elementsByType(elementType: ElementType!): [CodeElement!]!\n");
-    schema.push_str("  elementsByComplexity(minComplexity: Int!): [CodeElement!]!\n");
-    schema.push_str("}\n");
+    schema.push_str("# Generated from Rust AST\n\n");
+    schema.push_str(GRAPHQL_SCHEMA_TYPES);
     
     Ok(schema)
 }
 
+/// The types of the GraphQL schema, which are the same for every project.
+///
+/// They mirror the JSON output, with the IDs linking elements, files, crates
+/// and trait impls resolved to the objects they name, and `Query` fields for
+/// the lookups of `rustex query`.
+pub(crate) const GRAPHQL_SCHEMA_TYPES: &str = r##""""
+A Rust project extracted by rustex. Element, file and trait references are
+resolved by ID, so every relationship can be followed in one query.
+"""
+type Project {
+  name: String!
+  version: String!
+  rustEdition: String!
+  rootPath: String!
+  extractedAt: String!
+  files: [File!]!
+  metrics: ProjectMetrics!
+  dependencies: Dependencies!
+  "Workspace member crates, when the project is a Cargo workspace"
+  workspace: Workspace
+  "Public API of dependency crates, when dependencies were parsed"
+  dependencyApis: [DependencyApi!]!
+  crossReferences: [CrossReference!]!
+  traitImpls: [TraitImpl!]!
+  "Methods of each type, across all of its impl blocks"
+  types: [TypeMethods!]!
+  extractionReport: ExtractionReport!
+  truncation: TruncationReport
+}
+
+type File {
+  path: String!
+  relativePath: String!
+  kind: FileKind!
+  elements: [CodeElement!]!
+  imports: [Import!]!
+  metrics: FileMetrics!
+  crossReferences: [CrossReference!]!
+  "Spans skipped because they failed to parse"
+  parseErrors: [ParseError!]!
+  warnings: [String!]!
+  "Only signatures and docs were extracted because the file is too large"
+  partiallyExtracted: Boolean!
+}
+
+enum FileKind {
+  SOURCE
+  BUILD_SCRIPT
+  PROC_MACRO
+}
+
+type ParseError {
+  lineStart: Int!
+  lineEnd: Int!
+  message: String!
+}
+
+type CodeElement {
+  "Unique identifier, stable across runs"
+  id: String!
+  elementType: ElementType!
+  name: String!
+  signature: String
+  visibility: Visibility!
+  "Module path of pub(in path) visibility"
+  visibilityPath: String
+  docComments: [String!]!
+  docExamples: [DocExample!]!
+  location: Location!
+  "Location of the name, for elements that have one"
+  nameLocation: Location
+  file: File!
+  attributes: [String!]!
+  deprecation: Deprecation
+  "Cyclomatic complexity"
+  complexity: Int
+  complexityMetrics: ComplexityMetrics
+  genericParams: [String!]!
+  "Metadata such as enum variants and trait methods, as a JSON object"
+  metadata: String!
+  hierarchy: ElementHierarchy!
+  "Enclosing element, such as the impl block of a method"
+  parent: CodeElement
+  children: [CodeElement!]!
+  "Calls in the body of a function, in source order"
+  calls: [CallSite!]!
+  "Elements this element calls or uses"
+  references: [CodeElement!]!
+  "Elements calling or using this element"
+  referencedBy: [CodeElement!]!
+  "Trait implementations of a type, or implementations of a trait"
+  traitImpls: [TraitImpl!]!
+  "Produced by macro expansion rather than written in source"
+  isGenerated: Boolean!
+  "#[cfg] predicates that must hold for the element to be compiled, as written"
+  cfgPredicates: [String!]!
+}
+
+enum ElementType {
+  FUNCTION
+  STRUCT
+  ENUM
+  TRAIT
+  IMPL
+  MODULE
+  CONSTANT
+  STATIC
+  TYPE_ALIAS
+  MACRO
+  UNION
+  CLOSURE
+  ASYNC_BLOCK
+}
+
+enum Visibility {
+  PUBLIC
+  CRATE
+  SUPER
+  RESTRICTED
+  PRIVATE
+}
+
+type Location {
+  filePath: String!
+  lineStart: Int!
+  lineEnd: Int!
+  columnStart: Int!
+  columnEnd: Int!
+  byteStart: Int!
+  byteEnd: Int!
+}
+
+type DocExample {
+  language: String!
+  code: String!
+  shouldPanic: Boolean!
+  noRun: Boolean!
+  ignore: Boolean!
+  compileFail: Boolean!
+}
+
+type Deprecation {
+  since: String
+  note: String
+}
+
+type ElementHierarchy {
+  qualifiedName: String!
+  modulePath: String!
+  nestingLevel: Int!
+  parentId: String
+  children: [String!]!
+  canonicalPath: String!
+  "Public paths the element can be imported by from outside its crate"
+  exportedPaths: [String!]!
+  implContext: ImplContext
+}
+
+"The impl block an impl or method was declared in"
+type ImplContext {
+  selfType: String!
+  traitPath: String
+  whereClause: String
+}
+
+type CallSite {
+  "Path of a function or macro, or the name of a method, as written"
+  name: String!
+  kind: CallKind!
+  line: Int!
+}
+
+enum CallKind {
+  FUNCTION
+  METHOD
+  MACRO
+}
+
+type Import {
+  modulePath: String!
+  importedItems: [String!]!
+  isGlob: Boolean!
+  alias: String
+  isReexport: Boolean!
+  scope: String!
+}
+
+type CrossReference {
+  from: CodeElement!
+  "Referenced element, when the reference was resolved"
+  to: CodeElement
+  referenceType: ReferenceType!
+  referenceText: String!
+  location: Location!
+  isResolved: Boolean!
+}
+
+enum ReferenceType {
+  FUNCTION_CALL
+  TYPE_USAGE
+  VARIABLE_ACCESS
+  MODULE_REFERENCE
+  TRAIT_IMPLEMENTATION
+  GENERIC_USAGE
+  MACRO_INVOCATION
+  IMPORT_REFERENCE
+}
+
+"An impl Trait for Type block"
+type TraitImpl {
+  implBlock: CodeElement!
+  traitPath: String!
+  selfType: String!
+  "The trait, when it is part of the project"
+  trait: CodeElement
+  "The implementing type, when it was extracted"
+  implementingType: CodeElement
+  generics: [String!]!
+  whereClause: String
+  location: Location!
+  isGenerated: Boolean!
+  overriddenMethods: [String!]!
+  inheritedMethods: [String!]!
+}
+
+type TypeMethods {
+  qualifiedName: String!
+  type: CodeElement
+  implBlocks: [CodeElement!]!
+  inherentMethods: [CodeElement!]!
+  traitMethods: [TraitMethods!]!
+}
+
+type TraitMethods {
+  traitPath: String!
+  methods: [CodeElement!]!
+}
+
+type ComplexityMetrics {
+  cyclomatic: Int!
+  cognitive: Int!
+  nestingDepth: Int!
+  linesOfCode: Int!
+  parameterCount: Int!
+  returnCount: Int!
+  closureCount: Int!
+  asyncBlockCount: Int!
+  halstead: HalsteadMetrics!
+  level: ComplexityLevel!
+}
+
+type HalsteadMetrics {
+  distinctOperators: Int!
+  distinctOperands: Int!
+  totalOperators: Int!
+  totalOperands: Int!
+  vocabulary: Int!
+  length: Int!
+  volume: Float!
+  difficulty: Float!
+  effort: Float!
+}
+
+enum ComplexityLevel {
+  LOW
+  MEDIUM
+  HIGH
+  VERY_HIGH
+}
+
+type FileMetrics {
+  linesOfCode: Int!
+  linesOfComments: Int!
+  complexityTotal: Int!
+  functionCount: Int!
+  structCount: Int!
+  enumCount: Int!
+  traitCount: Int!
+  ownership: OwnershipProfile!
+  unsafeUsage: UnsafeUsage!
+}
+
+type ProjectMetrics {
+  totalFiles: Int!
+  totalLines: Int!
+  totalFunctions: Int!
+  totalStructs: Int!
+  totalEnums: Int!
+  totalTraits: Int!
+  complexityAverage: Float!
+  complexityMax: Int!
+  deprecations: DeprecationSummary!
+  ownership: OwnershipProfile!
+  unsafeUsage: UnsafeUsage!
+}
+
+type OwnershipProfile {
+  lifetimeAnnotations: Int!
+  sharedRefParams: Int!
+  mutRefParams: Int!
+  arcUsages: Int!
+  rcUsages: Int!
+  refcellUsages: Int!
+  cloneCalls: Int!
+  "clone() calls per 100 lines of code"
+  cloneDensity: Float!
+}
+
+type UnsafeUsage {
+  unsafeBlocks: Int!
+  unsafeFunctions: Int!
+  unsafeImpls: Int!
+  unsafeTraits: Int!
+}
+
+type DeprecationSummary {
+  totalDeprecated: Int!
+  totalUsages: Int!
+  items: [DeprecatedItem!]!
+}
+
+type DeprecatedItem {
+  element: CodeElement!
+  since: String
+  note: String
+  "Qualified names of the elements still referencing it"
+  callers: [String!]!
+}
+
+type Dependencies {
+  direct: [String!]!
+  transitive: [String!]!
+  devDependencies: [String!]!
+}
+
+type Workspace {
+  crates: [Crate!]!
+  dependencyEdges: [CrateDependency!]!
+}
+
+type Crate {
+  name: String!
+  version: String!
+  relativePath: String!
+  files: [File!]!
+  metrics: ProjectMetrics!
+}
+
+type CrateDependency {
+  from: Crate!
+  to: Crate!
+  "normal, dev or build"
+  kind: String!
+}
+
+type DependencyApi {
+  name: String!
+  version: String!
+  sourcePath: String!
+  files: [File!]!
+  metrics: ProjectMetrics!
+}
+
+"Files left out of the extraction, or only partly extracted"
+type ExtractionReport {
+  failed: [SkippedFile!]!
+  incomplete: [SkippedFile!]!
+}
+
+type SkippedFile {
+  file: String!
+  reason: SkipReason!
+  message: String!
+}
+
+enum SkipReason {
+  TOO_LARGE
+  PARSE_ERROR
+  INVALID_ENCODING
+  IO_ERROR
+  ACCESS_DENIED
+}
+
+"How the output was reduced to fit an output budget"
+type TruncationReport {
+  policy: String!
+  limitBytes: Int!
+  originalBytes: Int!
+  strippedElements: Int!
+  droppedElements: Int!
+  droppedFiles: [String!]!
+}
+
+type Query {
+  project: Project!
+  file(path: String!): File
+  element(id: String!): CodeElement
+  "The element with a fully qualified name, e.g. crate::net::Message"
+  elementByName(qualifiedName: String!): CodeElement
+  elementsByType(elementType: ElementType!): [CodeElement!]!
+  elementsByComplexity(minComplexity: Int!): [CodeElement!]!
+  "Elements whose name contains the text, ignoring case"
+  searchElements(text: String!, elementType: ElementType): [CodeElement!]!
+  "Functions calling the element, directly or within the given depth"
+  callers(id: String!, depth: Int = 1): [CodeElement!]!
+  "Elements the element calls or uses, directly or within the given depth"
+  callees(id: String!, depth: Int = 1): [CodeElement!]!
+  "Implementations of a trait, by path as written or qualified name"
+  implementations(traitPath: String!): [TraitImpl!]!
+  crate(name: String!): Crate
+}
+"##;
+
+/// A line of JSONL output: the project info, then one file per line.
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)] // Only used for its schema
+enum JsonlLine {
+    Project(ProjectInfo),
+    File(Box<FileAst>),
+}
+
+/// A line of RAG JSONL output: the document metadata, then one chunk per
+/// line, then one line per chunk removed since an earlier manifest.
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)] // Only used for its schema
+enum RagJsonlLine {
+    Metadata(Box<RagMetadata>),
+    Chunk(Box<RagChunk>),
+    Removed(RemovedChunk),
+}
+
+/// JSON Schema of the output of `format`, for consumers to validate or
+/// generate code against. Line-oriented formats get the schema of a line.
+/// GraphQL output is a schema already, so its types are returned as is.
+pub fn output_schema(format: &OutputFormat) -> Result<String> {
+    let schema = match format {
+        OutputFormat::Json | OutputFormat::MessagePack => schema_for!(ProjectAst),
+        OutputFormat::Rag => schema_for!(RagDocument),
+        OutputFormat::Jsonl => schema_for!(JsonlLine),
+        OutputFormat::RagJsonl => schema_for!(RagJsonlLine),
+        OutputFormat::LangChain => schema_for!(Vec<LangChainDocument>),
+        OutputFormat::LlamaIndex => schema_for!(Vec<LlamaIndexNode>),
+        OutputFormat::HfDataset => schema_for!(DatasetRow),
+        OutputFormat::OpenAiFineTune => schema_for!(OpenAiExample),
+        OutputFormat::AnthropicFineTune => schema_for!(AnthropicExample),
+        OutputFormat::GraphQL => {
+            return Ok(format!(
+                "# GraphQL Schema of rustex output\n\n{}",
+                GRAPHQL_SCHEMA_TYPES
+            ));
+        }
+        OutputFormat::Markdown => anyhow::bail!("Markdown output has no schema"),
+        OutputFormat::Mermaid => anyhow::bail!("Mermaid output has no schema"),
+        OutputFormat::Dot => anyhow::bail!("DOT output has no schema"),
+        OutputFormat::Csv => anyhow::bail!("CSV output has no schema"),
+        OutputFormat::Parquet => anyhow::bail!("Parquet files hold the schema of their table"),
+        OutputFormat::Turtle | OutputFormat::NTriples => {
+            anyhow::bail!("RDF output is described by the rustex ontology it contains")
+        }
+        OutputFormat::Cypher => anyhow::bail!("Cypher output has no schema"),
+        OutputFormat::Protobuf => return Ok(crate::protobuf::PROTO_SCHEMA.to_string()),
+        OutputFormat::Scip => anyhow::bail!("SCIP output follows the schema of the SCIP protocol"),
+        OutputFormat::Ctags => anyhow::bail!("ctags output has no schema"),
+        OutputFormat::Etags => anyhow::bail!("etags output has no schema"),
+        OutputFormat::Overview => anyhow::bail!("Overview output has no schema"),
+        OutputFormat::Sarif => anyhow::bail!("SARIF output follows the SARIF 2.1.0 schema"),
+        OutputFormat::Template => {
+            anyhow::bail!("Template output has no schema; templates see the JSON project AST")
+        }
+    };
+    Ok(format!("{}\n", serde_json::to_string_pretty(&schema)?))
+}
+
 /// Create a RAG formatter with custom configuration.
 pub fn create_rag_formatter(config: RagConfig) -> RagFormatter {
     RagFormatter::new(config)
@@ -0,0 +1,853 @@
+//! Executable GraphQL schema over an extracted project.
+//!
+//! [`graphql_schema`] builds an async-graphql schema from the types
+//! `--format graphql` writes, so the schema served is the one documented.
+//! Fields are resolved from a GraphQL value of the project in which
+//! elements, files and crates are referenced by ID; a reference becomes the
+//! object it names when a query selects it.
+
+use crate::formatters::GRAPHQL_SCHEMA_TYPES;
+use anyhow::Result;
+use async_graphql::dynamic::{
+    Enum, Field, FieldFuture, FieldValue, InputValue, Object, ResolverContext, Schema, TypeRef,
+};
+use async_graphql::parser::types::{BaseType, Type, TypeKind, TypeSystemDefinition};
+use async_graphql::Value;
+use rustex_core::complexity::ComplexityMetrics;
+use rustex_core::{
+    CodeElement, CodeLocation, CrossReference, FileAst, ProjectAst, ProjectMetrics, SkippedFile,
+    TraitImpl, Visibility,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+/// Object types referenced by ID in the values of other objects.
+const ENTITIES: [&str; 3] = ["CodeElement", "File", "Crate"];
+
+/// The GraphQL schema of `project_ast`, ready to execute queries.
+pub fn graphql_schema(project_ast: &ProjectAst) -> Result<Schema> {
+    let graph = Arc::new(Graph::new(project_ast)?);
+    let document = async_graphql::parser::parse_schema(GRAPHQL_SCHEMA_TYPES)?;
+
+    let mut schema = Schema::build("Query", None, None);
+    for definition in document.definitions {
+        let TypeSystemDefinition::Type(definition) = definition else {
+            continue;
+        };
+        let definition = definition.node;
+        let type_name = definition.name.node.to_string();
+        let description = definition.description.map(|description| description.node);
+        match definition.kind {
+            TypeKind::Object(object_type) => {
+                let mut object = Object::new(&type_name);
+                if let Some(description) = description {
+                    object = object.description(description);
+                }
+                for field in object_type.fields {
+                    let field = field.node;
+                    let name = field.name.node.to_string();
+                    let ty = field.ty.node;
+                    let graph = graph.clone();
+                    let is_query = type_name == "Query";
+                    let field_name = name.clone();
+                    let field_type = ty.clone();
+                    let mut schema_field = Field::new(name, type_ref(&ty), move |ctx| {
+                        let value = if is_query {
+                            graph.query(&field_name, &ctx)
+                        } else {
+                            ctx.parent_value
+                                .as_value()
+                                .and_then(|parent| get(parent, &field_name))
+                                .cloned()
+                        };
+                        let resolved = value.and_then(|value| graph.resolve(&value, &field_type));
+                        FieldFuture::new(async move { Ok(resolved) })
+                    });
+                    if let Some(description) = field.description {
+                        schema_field = schema_field.description(description.node);
+                    }
+                    for argument in field.arguments {
+                        let argument = argument.node;
+                        let mut input = InputValue::new(
+                            argument.name.node.as_str(),
+                            type_ref(&argument.ty.node),
+                        );
+                        if let Some(default) = argument.default_value {
+                            input = input.default_value(default.node);
+                        }
+                        schema_field = schema_field.argument(input);
+                    }
+                    object = object.field(schema_field);
+                }
+                schema = schema.register(object);
+            }
+            TypeKind::Enum(enum_type) => {
+                let items = enum_type
+                    .values
+                    .iter()
+                    .map(|value| value.node.value.node.to_string());
+                let mut graphql_enum = Enum::new(type_name).items(items);
+                if let Some(description) = description {
+                    graphql_enum = graphql_enum.description(description);
+                }
+                schema = schema.register(graphql_enum);
+            }
+            _ => anyhow::bail!("Unsupported GraphQL type {}", type_name),
+        }
+    }
+    Ok(schema.finish()?)
+}
+
+/// `ty` of the parsed schema as a type of the executable schema.
+fn type_ref(ty: &Type) -> TypeRef {
+    let base = match &ty.base {
+        BaseType::Named(name) => TypeRef::Named(name.to_string().into()),
+        BaseType::List(item) => TypeRef::List(Box::new(type_ref(item))),
+    };
+    if ty.nullable {
+        base
+    } else {
+        TypeRef::NonNull(Box::new(base))
+    }
+}
+
+/// Field `name` of an object value.
+fn get<'v>(value: &'v Value, name: &str) -> Option<&'v Value> {
+    match value {
+        Value::Object(fields) => fields.get(name),
+        _ => None,
+    }
+}
+
+/// The project as GraphQL values, with elements, files and crates by ID.
+struct Graph {
+    project: Value,
+    /// Values of the types in [`ENTITIES`], by type and ID
+    entities: HashMap<&'static str, HashMap<String, Value>>,
+    /// IDs of the elements of the project, without those of dependencies,
+    /// in file order
+    element_ids: Vec<String>,
+}
+
+impl Graph {
+    fn new(project_ast: &ProjectAst) -> Result<Self> {
+        let mut elements = HashMap::new();
+        let mut files = HashMap::new();
+        let mut element_ids = Vec::new();
+        let mut add_file = |key: String, file: &FileAst, project_file: bool| -> Result<()> {
+            for element in &file.elements {
+                if project_file {
+                    element_ids.push(element.id.clone());
+                }
+                elements.insert(
+                    element.id.clone(),
+                    code_element(project_ast, &key, element)?,
+                );
+            }
+            files.insert(key.clone(), file_value(&key, file)?);
+            Ok(())
+        };
+
+        for file in &project_ast.files {
+            add_file(path(&file.relative_path), file, true)?;
+        }
+        let mut dependency_apis = Vec::new();
+        for dependency in &project_ast.dependencies_ast {
+            let mut keys = Vec::new();
+            for file in &dependency.files {
+                let key = format!("{}/{}", dependency.name, path(&file.relative_path));
+                add_file(key.clone(), file, false)?;
+                keys.push(key);
+            }
+            dependency_apis.push(json!({
+                "name": dependency.name,
+                "version": dependency.version,
+                "sourcePath": path(&dependency.source_path),
+                "files": keys,
+                "metrics": project_metrics(&dependency.metrics)?,
+            }));
+        }
+
+        let mut crates = HashMap::new();
+        let workspace = match &project_ast.workspace {
+            Some(workspace) => {
+                for krate in &workspace.crates {
+                    let crate_value = json!({
+                        "name": krate.name,
+                        "version": krate.version,
+                        "relativePath": path(&krate.relative_path),
+                        "files": krate.files.iter().map(|file| path(file)).collect::<Vec<_>>(),
+                        "metrics": project_metrics(&krate.metrics)?,
+                    });
+                    crates.insert(krate.name.clone(), Value::from_json(crate_value)?);
+                }
+                json!({
+                    "crates": workspace.crates.iter().map(|krate| &krate.name).collect::<Vec<_>>(),
+                    "dependencyEdges": workspace.dependency_edges.iter().map(|edge| json!({
+                        "from": edge.from,
+                        "to": edge.to,
+                        "kind": edge.kind,
+                    })).collect::<Vec<_>>(),
+                })
+            }
+            None => serde_json::Value::Null,
+        };
+
+        let types: Vec<_> = project_ast
+            .type_index
+            .iter()
+            .map(|(qualified_name, methods)| {
+                json!({
+                    "qualifiedName": qualified_name,
+                    "type": methods.type_element_id,
+                    "implBlocks": methods.impl_ids,
+                    "inherentMethods": methods.inherent_methods,
+                    "traitMethods": methods.trait_methods.iter().map(|(trait_path, methods)| json!({
+                        "traitPath": trait_path,
+                        "methods": methods,
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let report = &project_ast.extraction_report;
+        let project = &project_ast.project;
+        let project = json!({
+            "name": project.name,
+            "version": project.version,
+            "rustEdition": project.rust_edition,
+            "rootPath": path(&project.root_path),
+            "extractedAt": project_ast.extracted_at.to_rfc3339(),
+            "files": project_ast.files.iter().map(|file| path(&file.relative_path)).collect::<Vec<_>>(),
+            "metrics": project_metrics(&project_ast.metrics)?,
+            "dependencies": camel_case(&project_ast.dependencies)?,
+            "workspace": workspace,
+            "dependencyApis": dependency_apis,
+            "crossReferences": project_ast.cross_references.iter().map(cross_reference).collect::<Vec<_>>(),
+            "traitImpls": project_ast.impls.iter().map(trait_impl).collect::<Vec<_>>(),
+            "types": types,
+            "extractionReport": {
+                "failed": report.failed.iter().map(skipped_file).collect::<Vec<_>>(),
+                "incomplete": report.incomplete.iter().map(skipped_file).collect::<Vec<_>>(),
+            },
+            "truncation": camel_case(&project_ast.truncation)?,
+        });
+
+        Ok(Self {
+            project: Value::from_json(project)?,
+            entities: HashMap::from([
+                ("CodeElement", elements),
+                ("File", files),
+                ("Crate", crates),
+            ]),
+            element_ids,
+        })
+    }
+
+    /// `value` as a field of type `ty`, with references to entities replaced
+    /// by their values. References to entities that don't exist are left
+    /// out of lists and null otherwise.
+    fn resolve(&self, value: &Value, ty: &Type) -> Option<FieldValue<'static>> {
+        match (&ty.base, value) {
+            (_, Value::Null) => None,
+            (BaseType::List(item), Value::List(values)) => Some(FieldValue::list(
+                values.iter().filter_map(|value| self.resolve(value, item)),
+            )),
+            (BaseType::Named(name), Value::String(id)) if ENTITIES.contains(&name.as_str()) => {
+                self.entity(name, id).cloned().map(FieldValue::value)
+            }
+            _ => Some(FieldValue::value(value.clone())),
+        }
+    }
+
+    fn entity(&self, type_name: &str, id: &str) -> Option<&Value> {
+        self.entities.get(type_name)?.get(id)
+    }
+
+    fn elements(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.element_ids
+            .iter()
+            .filter_map(|id| Some((id, self.entity("CodeElement", id)?)))
+    }
+
+    /// The value of field `name` of `Query`, with entities as references.
+    fn query(&self, name: &str, ctx: &ResolverContext) -> Option<Value> {
+        let string = |argument: &str| ctx.args.get(argument)?.string().ok();
+        let ids = |ids: Vec<&String>| {
+            Some(Value::List(
+                ids.into_iter()
+                    .map(|id| Value::String(id.clone()))
+                    .collect(),
+            ))
+        };
+        match name {
+            "project" => Some(self.project.clone()),
+            "file" => {
+                string("path").map(|path| Value::String(path.trim_start_matches("./").into()))
+            }
+            "element" => string("id").map(|id| Value::String(id.into())),
+            "crate" => string("name").map(|name| Value::String(name.into())),
+            "elementByName" => {
+                let qualified_name = string("qualifiedName")?;
+                let (id, _) = self.elements().find(|(_, element)| {
+                    let hierarchy = get(element, "hierarchy");
+                    hierarchy.and_then(|hierarchy| get(hierarchy, "qualifiedName"))
+                        == Some(&Value::String(qualified_name.to_string()))
+                })?;
+                Some(Value::String(id.clone()))
+            }
+            "elementsByType" => {
+                let element_type = ctx.args.get("elementType")?.enum_name().ok()?.to_string();
+                ids(self
+                    .elements()
+                    .filter(|(_, element)| is_enum(get(element, "elementType"), &element_type))
+                    .map(|(id, _)| id)
+                    .collect())
+            }
+            "elementsByComplexity" => {
+                let minimum = ctx.args.get("minComplexity")?.i64().ok()?;
+                ids(self
+                    .elements()
+                    .filter(|(_, element)| match get(element, "complexity") {
+                        Some(Value::Number(complexity)) => complexity
+                            .as_i64()
+                            .is_some_and(|complexity| complexity >= minimum),
+                        _ => false,
+                    })
+                    .map(|(id, _)| id)
+                    .collect())
+            }
+            "searchElements" => {
+                let text = string("text")?.to_lowercase();
+                let element_type = ctx
+                    .args
+                    .get("elementType")
+                    .and_then(|element_type| Some(element_type.enum_name().ok()?.to_string()));
+                ids(self
+                    .elements()
+                    .filter(|(_, element)| match get(element, "name") {
+                        Some(Value::String(name)) => name.to_lowercase().contains(&text),
+                        _ => false,
+                    })
+                    .filter(|(_, element)| {
+                        element_type.as_ref().is_none_or(|element_type| {
+                            is_enum(get(element, "elementType"), element_type)
+                        })
+                    })
+                    .map(|(id, _)| id)
+                    .collect())
+            }
+            "callers" | "callees" => {
+                let id = string("id")?;
+                let depth = ctx
+                    .args
+                    .get("depth")
+                    .and_then(|depth| depth.u64().ok())
+                    .unwrap_or(1);
+                let edges = if name == "callers" {
+                    "referencedBy"
+                } else {
+                    "references"
+                };
+                Some(Value::List(
+                    self.reachable(id, edges, depth as usize)
+                        .into_iter()
+                        .map(Value::String)
+                        .collect(),
+                ))
+            }
+            "implementations" => {
+                let trait_path = string("traitPath")?;
+                let Some(Value::List(impls)) = get(&self.project, "traitImpls") else {
+                    return None;
+                };
+                let matches = |trait_impl: &&Value| {
+                    let path = Value::String(trait_path.to_string());
+                    get(trait_impl, "traitPath") == Some(&path)
+                        || match get(trait_impl, "trait") {
+                            Some(Value::String(id)) => {
+                                self.entity("CodeElement", id)
+                                    .and_then(|element| get(element, "hierarchy"))
+                                    .and_then(|hierarchy| get(hierarchy, "qualifiedName"))
+                                    == Some(&path)
+                            }
+                            _ => false,
+                        }
+                };
+                Some(Value::List(impls.iter().filter(matches).cloned().collect()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Elements within `depth` steps of `id` along the element field `edges`,
+    /// nearest first, without `id` itself.
+    fn reachable(&self, id: &str, edges: &str, depth: usize) -> Vec<String> {
+        let mut seen = HashSet::from([id.to_string()]);
+        let mut found = Vec::new();
+        let mut queue = VecDeque::from([(id.to_string(), 0)]);
+        while let Some((current, distance)) = queue.pop_front() {
+            if distance == depth {
+                continue;
+            }
+            let neighbors = self
+                .entity("CodeElement", &current)
+                .and_then(|element| get(element, edges));
+            let Some(Value::List(neighbors)) = neighbors else {
+                continue;
+            };
+            for neighbor in neighbors {
+                if let Value::String(neighbor) = neighbor {
+                    if seen.insert(neighbor.clone()) {
+                        found.push(neighbor.clone());
+                        queue.push_back((neighbor.clone(), distance + 1));
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Whether `value` is the enum value or string `name`.
+fn is_enum(value: Option<&Value>, name: &str) -> bool {
+    match value {
+        Some(Value::Enum(value)) => value.as_str() == name,
+        Some(Value::String(value)) => value == name,
+        _ => false,
+    }
+}
+
+fn code_element(project_ast: &ProjectAst, file: &str, element: &CodeElement) -> Result<Value> {
+    let (visibility, visibility_path) = match &element.visibility {
+        Visibility::Restricted(path) => ("RESTRICTED".to_string(), Some(path)),
+        visibility => (enum_value(visibility), None),
+    };
+    let hierarchy = &element.hierarchy;
+    let graph = &project_ast.reference_graph;
+    let trait_impls: Vec<_> = project_ast
+        .impls
+        .iter()
+        .filter(|trait_impl| {
+            trait_impl.type_element_id.as_ref() == Some(&element.id)
+                || trait_impl.trait_element_id.as_ref() == Some(&element.id)
+        })
+        .map(trait_impl)
+        .collect();
+    // Sorted by key, so the same element always renders the same.
+    let metadata: BTreeMap<_, _> = element.metadata.iter().collect();
+    let value = json!({
+        "id": element.id,
+        "elementType": enum_value(&element.element_type),
+        "name": element.name,
+        "signature": element.signature,
+        "visibility": visibility,
+        "visibilityPath": visibility_path,
+        "docComments": element.doc_comments,
+        "docExamples": camel_case(&element.doc_examples)?,
+        "location": location(&element.location),
+        "nameLocation": element.name_location.as_ref().map(location),
+        "file": file,
+        "attributes": element.attributes,
+        "deprecation": camel_case(&element.deprecation)?,
+        "complexity": element.complexity,
+        "complexityMetrics": element.complexity_metrics.as_ref().map(complexity_metrics),
+        "genericParams": element.generic_params,
+        "metadata": serde_json::to_string(&metadata)?,
+        "hierarchy": {
+            "qualifiedName": hierarchy.qualified_name,
+            "modulePath": hierarchy.module_path,
+            "nestingLevel": hierarchy.nesting_level,
+            "parentId": hierarchy.parent_id,
+            "children": hierarchy.children_ids,
+            "canonicalPath": hierarchy.namespace.canonical_path,
+            "exportedPaths": hierarchy.namespace.exported_paths,
+            "implContext": camel_case(&hierarchy.impl_context)?,
+        },
+        "parent": hierarchy.parent_id,
+        "children": hierarchy.children_ids,
+        "calls": element.calls.iter().map(|call| json!({
+            "name": call.name,
+            "kind": enum_value(&call.kind),
+            "line": call.line,
+        })).collect::<Vec<_>>(),
+        "references": graph.references_of(&element.id),
+        "referencedBy": graph.referenced_by(&element.id),
+        "traitImpls": trait_impls,
+        "isGenerated": element.is_generated,
+        "cfgPredicates": element.cfg_predicates.iter().map(ToString::to_string).collect::<Vec<_>>(),
+    });
+    Ok(Value::from_json(value)?)
+}
+
+fn file_value(key: &str, file: &FileAst) -> Result<Value> {
+    let value = json!({
+        "path": path(&file.path),
+        "relativePath": key,
+        "kind": enum_value(&file.kind),
+        "elements": file.elements.iter().map(|element| &element.id).collect::<Vec<_>>(),
+        "imports": camel_case(&file.imports)?,
+        "metrics": camel_case(&file.file_metrics)?,
+        "crossReferences": file.cross_references.iter().map(cross_reference).collect::<Vec<_>>(),
+        "parseErrors": camel_case(&file.parse_errors)?,
+        "warnings": file.warnings,
+        "partiallyExtracted": file.partially_extracted,
+    });
+    Ok(Value::from_json(value)?)
+}
+
+fn location(location: &CodeLocation) -> serde_json::Value {
+    json!({
+        "filePath": path(&location.file_path),
+        "lineStart": location.line_start,
+        "lineEnd": location.line_end,
+        "columnStart": location.char_start,
+        "columnEnd": location.char_end,
+        "byteStart": location.byte_start,
+        "byteEnd": location.byte_end,
+    })
+}
+
+fn complexity_metrics(metrics: &ComplexityMetrics) -> serde_json::Value {
+    let halstead = &metrics.halstead;
+    json!({
+        "cyclomatic": metrics.cyclomatic,
+        "cognitive": metrics.cognitive,
+        "nestingDepth": metrics.nesting_depth,
+        "linesOfCode": metrics.lines_of_code,
+        "parameterCount": metrics.parameter_count,
+        "returnCount": metrics.return_count,
+        "closureCount": metrics.closure_count,
+        "asyncBlockCount": metrics.async_block_count,
+        "halstead": {
+            "distinctOperators": halstead.n1,
+            "distinctOperands": halstead.n2,
+            "totalOperators": halstead.big_n1,
+            "totalOperands": halstead.big_n2,
+            "vocabulary": halstead.vocabulary,
+            "length": halstead.length,
+            "volume": halstead.volume,
+            "difficulty": halstead.difficulty,
+            "effort": halstead.effort,
+        },
+        "level": enum_value(&metrics.complexity_level()),
+    })
+}
+
+fn project_metrics(metrics: &ProjectMetrics) -> Result<serde_json::Value> {
+    let mut value = camel_case(metrics)?;
+    let deprecations = &metrics.deprecations;
+    value["deprecations"] = json!({
+        "totalDeprecated": deprecations.total_deprecated,
+        "totalUsages": deprecations.total_usages,
+        "items": deprecations.items.iter().map(|item| json!({
+            "element": item.element_id,
+            "since": item.since,
+            "note": item.note,
+            "callers": item.callers,
+        })).collect::<Vec<_>>(),
+    });
+    Ok(value)
+}
+
+fn cross_reference(reference: &CrossReference) -> serde_json::Value {
+    json!({
+        "from": reference.from_element_id,
+        "to": reference.to_element_id,
+        "referenceType": enum_value(&reference.reference_type),
+        "referenceText": reference.reference_text,
+        "location": location(&reference.location),
+        "isResolved": reference.is_resolved,
+    })
+}
+
+fn trait_impl(trait_impl: &TraitImpl) -> serde_json::Value {
+    json!({
+        "implBlock": trait_impl.impl_id,
+        "traitPath": trait_impl.trait_path,
+        "selfType": trait_impl.self_type,
+        "trait": trait_impl.trait_element_id,
+        "implementingType": trait_impl.type_element_id,
+        "generics": trait_impl.generics,
+        "whereClause": trait_impl.where_clause,
+        "location": location(&trait_impl.location),
+        "isGenerated": trait_impl.is_generated,
+        "overriddenMethods": trait_impl.overridden_methods,
+        "inheritedMethods": trait_impl.inherited_methods,
+    })
+}
+
+fn skipped_file(skipped: &SkippedFile) -> serde_json::Value {
+    json!({
+        "file": path(&skipped.file),
+        "reason": enum_value(&skipped.reason),
+        "message": skipped.message,
+    })
+}
+
+fn path(path: &std::path::Path) -> String {
+    path.display().to_string()
+}
+
+/// A unit variant as a GraphQL enum value, e.g. `BUILD_SCRIPT` for
+/// `BuildScript`.
+fn enum_value(variant: &impl std::fmt::Debug) -> String {
+    let mut value = String::new();
+    for (index, c) in format!("{:?}", variant).chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            value.push('_');
+        }
+        value.push(c.to_ascii_uppercase());
+    }
+    value
+}
+
+/// `value` serialized as JSON, with object keys in camel case.
+fn camel_case(value: &impl Serialize) -> Result<serde_json::Value> {
+    fn convert(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(fields) => fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let mut words = key.split('_');
+                    let mut camel = words.next().unwrap_or_default().to_string();
+                    for word in words {
+                        let mut chars = word.chars();
+                        camel.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                        camel.push_str(chars.as_str());
+                    }
+                    (camel, convert(value))
+                })
+                .collect(),
+            serde_json::Value::Array(values) => values.into_iter().map(convert).collect(),
+            value => value,
+        }
+    }
+    Ok(convert(serde_json::to_value(value)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::parser::types::ObjectType;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{ElementType, ReferenceGraph, ReferenceType};
+
+    /// A project whose second element calls the first, with a struct that
+    /// implements a trait in its second file.
+    fn project() -> ProjectAst {
+        let mut project = MockDataGenerator::project_ast(2, 2);
+        let caller = project.files[0].elements[1].id.clone();
+        let callee = project.files[0].elements[0].id.clone();
+        let reference = CrossReference {
+            from_element_id: caller.clone(),
+            to_element_id: Some(callee.clone()),
+            reference_type: ReferenceType::FunctionCall,
+            reference_text: "element_0".to_string(),
+            location: project.files[0].elements[1].location.clone(),
+            is_resolved: true,
+            context: rustex_core::ReferenceContext {
+                is_definition: false,
+                scope: "crate::test".to_string(),
+                metadata: HashMap::new(),
+            },
+        };
+        project.reference_graph = ReferenceGraph::from_cross_references([&reference]);
+        project.files[0].cross_references.push(reference);
+
+        let mut trait_element = MockDataGenerator::code_element("Render", ElementType::Trait);
+        trait_element.hierarchy.qualified_name = "crate::test::Render".to_string();
+        let struct_element = MockDataGenerator::code_element("Widget", ElementType::Struct);
+        let impl_element = MockDataGenerator::code_element("Render for Widget", ElementType::Impl);
+        let (trait_id, struct_id) = (trait_element.id.clone(), struct_element.id.clone());
+        let impl_id = impl_element.id.clone();
+        project.files[1].elements = vec![trait_element, struct_element, impl_element];
+        project.impls.push(TraitImpl {
+            impl_id,
+            trait_path: "Render".to_string(),
+            self_type: "Widget".to_string(),
+            trait_element_id: Some(trait_id),
+            type_element_id: Some(struct_id),
+            generics: vec![],
+            where_clause: None,
+            location: project.files[1].elements[1].location.clone(),
+            is_generated: false,
+            overridden_methods: vec![],
+            inherited_methods: vec![],
+        });
+        project
+    }
+
+    fn execute(project: &ProjectAst, query: &str) -> serde_json::Value {
+        let schema = graphql_schema(project).unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let response = runtime.block_on(schema.execute(query));
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    /// A selection of every field of `type_name`, following object fields
+    /// `depth` levels deep.
+    fn selection(types: &HashMap<String, ObjectType>, type_name: &str, depth: usize) -> String {
+        let mut fields = Vec::new();
+        for field in &types[type_name].fields {
+            let mut ty = &field.node.ty.node;
+            while let BaseType::List(item) = &ty.base {
+                ty = item;
+            }
+            let BaseType::Named(named) = &ty.base else {
+                unreachable!()
+            };
+            let name = field.node.name.node.as_str();
+            if !types.contains_key(named.as_str()) {
+                fields.push(name.to_string());
+            } else if depth > 0 {
+                let nested = selection(types, named.as_str(), depth - 1);
+                fields.push(format!("{} {}", name, nested));
+            }
+        }
+        if fields.is_empty() {
+            fields.push("__typename".to_string());
+        }
+        format!("{{ {} }}", fields.join(" "))
+    }
+
+    #[test]
+    fn test_every_field_resolves() {
+        let document = async_graphql::parser::parse_schema(GRAPHQL_SCHEMA_TYPES).unwrap();
+        let types: HashMap<String, ObjectType> = document
+            .definitions
+            .into_iter()
+            .filter_map(|definition| match definition {
+                TypeSystemDefinition::Type(definition) => match definition.node.kind {
+                    TypeKind::Object(object) => {
+                        Some((definition.node.name.node.to_string(), object))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        let mut project = project();
+        let struct_id = project.files[1].elements[1].id.clone();
+        let impl_id = project.files[1].elements[2].id.clone();
+        project.files[0].elements[0].complexity_metrics = Some(ComplexityMetrics::default());
+        project.type_index.insert(
+            "crate::test::Widget".to_string(),
+            rustex_core::TypeMethods {
+                type_element_id: Some(struct_id),
+                impl_ids: vec![impl_id.clone()],
+                inherent_methods: vec![],
+                trait_methods: BTreeMap::from([("Render".to_string(), vec![impl_id])]),
+            },
+        );
+        project.workspace = Some(rustex_core::WorkspaceAst {
+            crates: ["app", "lib"]
+                .map(|name| rustex_core::CrateAst {
+                    name: name.to_string(),
+                    version: "0.1.0".to_string(),
+                    relative_path: name.into(),
+                    files: vec!["file_0.rs".into()],
+                    metrics: project.metrics.clone(),
+                })
+                .to_vec(),
+            dependency_edges: vec![rustex_core::CrateDependency {
+                from: "app".to_string(),
+                to: "lib".to_string(),
+                kind: "normal".to_string(),
+            }],
+        });
+        project.dependencies_ast.push(rustex_core::DependencyAst {
+            name: "serde".to_string(),
+            version: "1.0.0".to_string(),
+            source_path: "/registry/serde-1.0.0".into(),
+            files: vec![MockDataGenerator::file_ast("src/lib.rs".into(), 1)],
+            metrics: project.metrics.clone(),
+        });
+        project.extraction_report.failed.push(SkippedFile {
+            file: "src/broken.rs".into(),
+            reason: rustex_core::SkipReason::ParseError,
+            message: "expected item".to_string(),
+        });
+
+        // Non-null fields without a value fail the query.
+        let query = format!("{{ project {} }}", selection(&types, "Project", 4));
+        let data = execute(&project, &query);
+        let project = &data["project"];
+        assert_eq!(project["files"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            project["workspace"]["dependencyEdges"][0]["to"]["name"],
+            "lib"
+        );
+        assert_eq!(
+            project["dependencyApis"][0]["files"][0]["relativePath"],
+            "serde/lib.rs"
+        );
+        assert_eq!(
+            project["types"][0]["traitMethods"][0]["methods"][0]["elementType"],
+            "IMPL"
+        );
+        assert_eq!(
+            project["extractionReport"]["failed"][0]["reason"],
+            "PARSE_ERROR"
+        );
+    }
+
+    #[test]
+    fn test_references_resolve_to_objects() {
+        let project = project();
+        let caller = &project.files[0].elements[1];
+        let query = format!(
+            r#"{{ element(id: "{}") {{ name file {{ relativePath }} references {{ name }} }} }}"#,
+            caller.id
+        );
+        let data = execute(&project, &query);
+        assert_eq!(
+            data["element"],
+            json!({
+                "name": "element_1",
+                "file": { "relativePath": "file_0.rs" },
+                "references": [{ "name": "element_0" }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let project = project();
+        let callee = &project.files[0].elements[0].id;
+        let query = format!(
+            r#"{{
+                callers(id: "{callee}") {{ name }}
+                callees(id: "{callee}") {{ name }}
+                elementsByType(elementType: TRAIT) {{ name }}
+                searchElements(text: "WIDG", elementType: STRUCT) {{ name }}
+                elementByName(qualifiedName: "crate::test::Render") {{ elementType }}
+                implementations(traitPath: "crate::test::Render") {{
+                    selfType
+                    implementingType {{ elementType }}
+                }}
+                missing: element(id: "missing") {{ name }}
+            }}"#
+        );
+        let data = execute(&project, &query);
+        assert_eq!(data["callers"], json!([{ "name": "element_1" }]));
+        assert_eq!(data["callees"], json!([]));
+        assert_eq!(data["elementsByType"], json!([{ "name": "Render" }]));
+        assert_eq!(data["searchElements"], json!([{ "name": "Widget" }]));
+        assert_eq!(data["elementByName"], json!({ "elementType": "TRAIT" }));
+        assert_eq!(
+            data["implementations"],
+            json!([{ "selfType": "Widget", "implementingType": { "elementType": "STRUCT" } }])
+        );
+        assert_eq!(data["missing"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_enum_values() {
+        assert_eq!(enum_value(&ElementType::AsyncBlock), "ASYNC_BLOCK");
+        assert_eq!(enum_value(&Visibility::Public), "PUBLIC");
+    }
+}
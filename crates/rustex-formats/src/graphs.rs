@@ -0,0 +1,500 @@
+//! Graphs of the structure of a project, rendered as diagrams by the Mermaid
+//! and DOT formatters.
+//!
+//! [`build_graph`] turns a project into the nodes and edges of one
+//! [`Diagram`]; the formatters only decide how they look. Nodes are ordered
+//! as their elements appear in the project and edges by node, so the same
+//! project always gives the same diagram.
+
+use crate::mdbook::{chapter_title, crate_name, relative_name};
+use rustex_core::{
+    CodeElement, ComplexityLevel, DependencyGraph, DependencyKind, Diagram, ElementType, FileAst,
+    PackageRole, ProjectAst, ReferenceType, TypeMethods,
+};
+use std::collections::{BTreeSet, HashMap};
+
+/// The nodes and edges of a diagram.
+#[derive(Debug, Default)]
+pub(crate) struct Graph {
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) edges: BTreeSet<Edge>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Node {
+    pub(crate) label: String,
+    pub(crate) kind: NodeKind,
+    /// Variants of enums and methods of other types
+    pub(crate) members: Vec<String>,
+    /// Complexity of a function, or of the most complex function of a module
+    pub(crate) complexity: Option<ComplexityLevel>,
+    /// Whether the node is a package locked at several versions
+    pub(crate) duplicated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    /// A crate of the project
+    Crate,
+    /// A crate the project depends on
+    Dependency,
+    Module,
+    /// A struct, enum, union or trait
+    Type(ElementType),
+    Function,
+}
+
+/// An edge between two nodes, by index in [`Graph::nodes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Edge {
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    pub(crate) kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum EdgeKind {
+    /// A module containing a submodule
+    Contains,
+    Depends,
+    DevDepends,
+    BuildDepends,
+    /// A module importing items of another module
+    Imports,
+    /// A type implementing a trait
+    Implements,
+    /// A type whose methods use another type
+    Uses,
+    Calls,
+}
+
+/// Title of the section of `diagram`.
+pub(crate) fn diagram_title(diagram: Diagram) -> &'static str {
+    match diagram {
+        Diagram::CrateDependencies => "Crate Dependencies",
+        Diagram::ModuleHierarchy => "Module Hierarchy",
+        Diagram::ModuleImports => "Module Imports",
+        Diagram::StructRelationships => "Struct Relationships",
+        Diagram::CallGraph => "Call Graph",
+    }
+}
+
+/// The graph of `diagram` of `project_ast`.
+pub(crate) fn build_graph(project_ast: &ProjectAst, diagram: Diagram) -> Graph {
+    match diagram {
+        Diagram::CrateDependencies => crate_dependencies(project_ast),
+        Diagram::ModuleHierarchy => module_hierarchy(project_ast),
+        Diagram::ModuleImports => module_imports(project_ast),
+        Diagram::StructRelationships => struct_relationships(project_ast),
+        Diagram::CallGraph => call_graph(project_ast),
+    }
+}
+
+impl Graph {
+    fn add_node(&mut self, label: String, kind: NodeKind) -> usize {
+        self.nodes.push(Node {
+            label,
+            kind,
+            members: Vec::new(),
+            complexity: None,
+            duplicated: false,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, kind: EdgeKind) {
+        if from != to {
+            self.edges.insert(Edge { from, to, kind });
+        }
+    }
+}
+
+fn elements(project_ast: &ProjectAst) -> impl Iterator<Item = &CodeElement> {
+    project_ast.files.iter().flat_map(|file| &file.elements)
+}
+
+/// The workspace members and the dependencies between them or, for a single
+/// crate, the crate and its direct and dev dependencies.
+fn crate_dependencies(project_ast: &ProjectAst) -> Graph {
+    let mut graph = Graph::default();
+    let mut nodes = HashMap::new();
+    match &project_ast.workspace {
+        Some(workspace) => {
+            for member in &workspace.crates {
+                let node = graph.add_node(member.name.clone(), NodeKind::Crate);
+                nodes.insert(member.name.as_str(), node);
+            }
+            for dependency in &workspace.dependency_edges {
+                let kind = match dependency.kind.as_str() {
+                    "dev" => EdgeKind::DevDepends,
+                    "build" => EdgeKind::BuildDepends,
+                    _ => EdgeKind::Depends,
+                };
+                let ends = (
+                    nodes.get(dependency.from.as_str()),
+                    nodes.get(dependency.to.as_str()),
+                );
+                if let (Some(&from), Some(&to)) = ends {
+                    graph.add_edge(from, to, kind);
+                }
+            }
+        }
+        None => {
+            let project = graph.add_node(project_ast.project.name.clone(), NodeKind::Crate);
+            let dependencies = &project_ast.dependencies;
+            let direct = dependencies
+                .direct
+                .iter()
+                .map(|name| (name, EdgeKind::Depends));
+            let dev = dependencies
+                .dev_dependencies
+                .iter()
+                .map(|name| (name, EdgeKind::DevDepends));
+            for (name, kind) in direct.chain(dev) {
+                let node = *nodes
+                    .entry(name.as_str())
+                    .or_insert_with(|| graph.add_node(name.clone(), NodeKind::Dependency));
+                graph.add_edge(project, node, kind);
+            }
+        }
+    }
+    graph
+}
+
+/// The packages of a `Cargo.lock` dependency graph, labelled with their
+/// name and version, and the dependencies between them.
+pub(crate) fn package_graph(dependencies: &DependencyGraph) -> Graph {
+    let mut graph = Graph::default();
+    let mut nodes = HashMap::new();
+    for package in &dependencies.packages {
+        let kind = match package.role {
+            PackageRole::Root => NodeKind::Crate,
+            PackageRole::Direct | PackageRole::Transitive => NodeKind::Dependency,
+        };
+        let node = graph.add_node(format!("{}\n{}", package.name, package.version), kind);
+        graph.nodes[node].duplicated = dependencies
+            .duplicates
+            .iter()
+            .any(|duplicate| duplicate.name == package.name);
+        nodes.insert(package.id.as_str(), node);
+    }
+    for edge in &dependencies.edges {
+        let kind = match edge.kind {
+            DependencyKind::Normal => EdgeKind::Depends,
+            DependencyKind::Dev => EdgeKind::DevDepends,
+            DependencyKind::Build => EdgeKind::BuildDepends,
+        };
+        if let (Some(&from), Some(&to)) =
+            (nodes.get(edge.from.as_str()), nodes.get(edge.to.as_str()))
+        {
+            graph.add_edge(from, to, kind);
+        }
+    }
+    graph
+}
+
+/// The modules of each crate, by crate name and path segments, including
+/// modules holding only submodules, parents before their submodules.
+fn modules(project_ast: &ProjectAst) -> BTreeSet<(&str, Vec<&str>)> {
+    let mut modules = BTreeSet::new();
+    for element in elements(project_ast) {
+        let crate_name = crate_name(element);
+        let mut paths = vec![element.hierarchy.module_path.as_str()];
+        if element.element_type == ElementType::Module {
+            paths.push(&element.hierarchy.qualified_name);
+        }
+        for path in paths {
+            let segments: Vec<&str> = path.split("::").collect();
+            for depth in 1..=segments.len() {
+                modules.insert((crate_name, segments[..depth].to_vec()));
+            }
+        }
+    }
+    modules
+}
+
+/// The most complex function of each module, by crate name and module path.
+fn module_complexity(project_ast: &ProjectAst) -> HashMap<(&str, &str), ComplexityLevel> {
+    let mut complexity = HashMap::new();
+    for element in elements(project_ast) {
+        if let Some(level) = element_complexity(element) {
+            let key = (crate_name(element), element.hierarchy.module_path.as_str());
+            let highest = complexity.entry(key).or_insert(level);
+            if rank(level) > rank(*highest) {
+                *highest = level;
+            }
+        }
+    }
+    complexity
+}
+
+fn element_complexity(element: &CodeElement) -> Option<ComplexityLevel> {
+    let metrics = element.complexity_metrics.as_ref()?;
+    Some(metrics.complexity_level())
+}
+
+fn rank(level: ComplexityLevel) -> u8 {
+    match level {
+        ComplexityLevel::Low => 0,
+        ComplexityLevel::Medium => 1,
+        ComplexityLevel::High => 2,
+        ComplexityLevel::VeryHigh => 3,
+    }
+}
+
+/// Each module of each crate, linked from its parent module.
+fn module_hierarchy(project_ast: &ProjectAst) -> Graph {
+    let complexity = module_complexity(project_ast);
+    let mut graph = Graph::default();
+    let mut nodes = HashMap::new();
+    for (crate_name, segments) in &modules(project_ast) {
+        let path = segments.join("::");
+        let label = match segments.as_slice() {
+            [_] => chapter_title(crate_name, &path),
+            [.., last] => last.to_string(),
+            [] => continue,
+        };
+        let node = graph.add_node(label, NodeKind::Module);
+        graph.nodes[node].complexity = complexity.get(&(*crate_name, path.as_str())).copied();
+        if let Some(&parent) = nodes.get(&(*crate_name, &segments[..segments.len() - 1])) {
+            graph.add_edge(parent, node, EdgeKind::Contains);
+        }
+        nodes.insert((*crate_name, segments.as_slice()), node);
+    }
+    graph
+}
+
+/// Each module of each crate, linked to the project modules it imports
+/// items from. Imports of other workspace crates link to their modules.
+fn module_imports(project_ast: &ProjectAst) -> Graph {
+    let modules = modules(project_ast);
+    let complexity = module_complexity(project_ast);
+    let mut graph = Graph::default();
+    let mut nodes = HashMap::new();
+    for (crate_name, segments) in &modules {
+        let path = segments.join("::");
+        let node = graph.add_node(chapter_title(crate_name, &path), NodeKind::Module);
+        graph.nodes[node].complexity = complexity.get(&(*crate_name, path.as_str())).copied();
+        nodes.insert((*crate_name, path), node);
+    }
+    // Crates are imported by their name with `-` replaced by `_`.
+    let crates: HashMap<String, &str> = modules
+        .iter()
+        .map(|(crate_name, _)| (crate_name.replace('-', "_"), *crate_name))
+        .collect();
+
+    for file in &project_ast.files {
+        let Some(crate_name) = file_crate(project_ast, file) else {
+            continue;
+        };
+        for import in &file.imports {
+            let Some(&from) = nodes.get(&(crate_name, import.scope.clone())) else {
+                continue;
+            };
+            let Some((target_crate, mut path)) = resolve_import(
+                &import.module_path,
+                crate_name,
+                &import.scope,
+                &crates,
+                |path| nodes.contains_key(&(crate_name, path.join("::"))),
+            ) else {
+                continue;
+            };
+            // `use crate::net::tcp;` imports the module `tcp` itself.
+            if let [item] = import.imported_items.as_slice() {
+                let mut module = path.clone();
+                module.push(item);
+                if nodes.contains_key(&(target_crate, module.join("::"))) {
+                    path = module;
+                }
+            }
+            // `use crate::net::Connection;` imports from the module `net`.
+            while !path.is_empty() {
+                if let Some(&to) = nodes.get(&(target_crate, path.join("::"))) {
+                    graph.add_edge(from, to, EdgeKind::Imports);
+                    break;
+                }
+                path.pop();
+            }
+        }
+    }
+    graph
+}
+
+/// The crate and path of `module_path` imported in the module `scope` of
+/// `crate_name`, or `None` for paths outside the project. `is_submodule`
+/// tells whether a path of `crate_name` is a module, for paths relative to
+/// `scope`.
+fn resolve_import<'a>(
+    module_path: &'a str,
+    crate_name: &'a str,
+    scope: &'a str,
+    crates: &HashMap<String, &'a str>,
+    is_submodule: impl Fn(&[&str]) -> bool,
+) -> Option<(&'a str, Vec<&'a str>)> {
+    let mut segments = module_path
+        .split("::")
+        .filter(|segment| !segment.is_empty());
+    let first = segments.next()?;
+    let scope: Vec<&str> = scope.split("::").collect();
+    let mut path = match first {
+        "crate" => vec!["crate"],
+        "self" => scope,
+        "super" => {
+            let mut path = scope;
+            path.pop();
+            path
+        }
+        _ => {
+            let mut relative = scope.clone();
+            relative.push(first);
+            if is_submodule(&relative) {
+                relative
+            } else if let Some(&other) = crates.get(first) {
+                return Some((other, std::iter::once("crate").chain(segments).collect()));
+            } else {
+                return None;
+            }
+        }
+    };
+    for segment in segments {
+        if segment == "super" {
+            path.pop();
+        } else {
+            path.push(segment);
+        }
+    }
+    (!path.is_empty()).then_some((crate_name, path))
+}
+
+/// Name of the crate `file` belongs to.
+fn file_crate<'a>(project_ast: &'a ProjectAst, file: &'a FileAst) -> Option<&'a str> {
+    if let Some(element) = file.elements.first() {
+        return Some(crate_name(element));
+    }
+    match &project_ast.workspace {
+        Some(workspace) => workspace
+            .crates
+            .iter()
+            .find(|member| member.files.contains(&file.relative_path))
+            .map(|member| member.name.as_str()),
+        None => Some(&project_ast.project.name),
+    }
+}
+
+/// Structs, enums, unions and traits with their variants or methods, the
+/// traits they implement and the project types their methods use.
+fn struct_relationships(project_ast: &ProjectAst) -> Graph {
+    let type_methods: HashMap<&str, &TypeMethods> = project_ast
+        .type_index
+        .values()
+        .filter_map(|methods| Some((methods.type_element_id.as_deref()?, methods)))
+        .collect();
+    let names: HashMap<&str, &str> = elements(project_ast)
+        .map(|element| (element.id.as_str(), element.name.as_str()))
+        .collect();
+
+    let mut graph = Graph::default();
+    let mut nodes = HashMap::new();
+    let types = elements(project_ast).filter(|element| {
+        matches!(
+            element.element_type,
+            ElementType::Struct | ElementType::Enum | ElementType::Union | ElementType::Trait
+        )
+    });
+    for element in types {
+        let node = graph.add_node(
+            relative_name(element),
+            NodeKind::Type(element.element_type.clone()),
+        );
+        graph.nodes[node].members = match element.element_type {
+            ElementType::Enum => element
+                .enum_variants()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            ElementType::Trait => {
+                let required = element.required_methods();
+                let provided = element.provided_methods();
+                required.into_iter().chain(provided).map(method).collect()
+            }
+            _ => type_methods
+                .get(element.id.as_str())
+                .into_iter()
+                .flat_map(|methods| &methods.inherent_methods)
+                .filter_map(|id| names.get(id.as_str()).copied())
+                .map(method)
+                .collect(),
+        };
+        nodes.insert(element.id.as_str(), (node, element));
+    }
+
+    for trait_impl in &project_ast.impls {
+        let ids = (&trait_impl.trait_element_id, &trait_impl.type_element_id);
+        if let (Some(trait_id), Some(type_id)) = ids {
+            if let (Some(&(trait_node, _)), Some(&(type_node, _))) =
+                (nodes.get(trait_id.as_str()), nodes.get(type_id.as_str()))
+            {
+                graph.add_edge(type_node, trait_node, EdgeKind::Implements);
+            }
+        }
+    }
+    // Types are used by their methods rather than the types themselves.
+    let mut uses = Vec::new();
+    for &(node, element) in nodes.values() {
+        let methods = type_methods
+            .get(element.id.as_str())
+            .into_iter()
+            .flat_map(|methods| {
+                methods
+                    .inherent_methods
+                    .iter()
+                    .chain(methods.trait_methods.values().flatten())
+            });
+        let targets = std::iter::once(&element.id)
+            .chain(methods)
+            .flat_map(|id| project_ast.reference_graph.references_of(id));
+        for target in targets {
+            if let Some(&(target_node, _)) = nodes.get(target.as_str()) {
+                uses.push((node, target_node));
+            }
+        }
+    }
+    for (from, to) in uses {
+        graph.add_edge(from, to, EdgeKind::Uses);
+    }
+    graph
+}
+
+/// Functions and methods linked to the project functions they call.
+fn call_graph(project_ast: &ProjectAst) -> Graph {
+    let calls: BTreeSet<(&str, &str)> = project_ast
+        .cross_references
+        .iter()
+        .filter(|reference| reference.reference_type == ReferenceType::FunctionCall)
+        .filter_map(|reference| {
+            let target = reference.to_element_id.as_deref()?;
+            Some((reference.from_element_id.as_str(), target))
+        })
+        .collect();
+    let called: BTreeSet<&str> = calls.iter().flat_map(|&(from, to)| [from, to]).collect();
+
+    let mut graph = Graph::default();
+    let mut nodes = HashMap::new();
+    for element in elements(project_ast).filter(|element| called.contains(element.id.as_str())) {
+        let name = chapter_title(crate_name(element), &element.hierarchy.qualified_name);
+        let node = graph.add_node(name.replace(" :: ", "::"), NodeKind::Function);
+        graph.nodes[node].complexity = element_complexity(element);
+        nodes.insert(element.id.as_str(), node);
+    }
+    for (from, to) in calls {
+        if let (Some(&from), Some(&to)) = (nodes.get(from), nodes.get(to)) {
+            graph.add_edge(from, to, EdgeKind::Calls);
+        }
+    }
+    graph
+}
+
+fn method(name: &str) -> String {
+    format!("{}()", name)
+}
@@ -0,0 +1,231 @@
+//! Training examples as a Hugging Face dataset.
+//!
+//! [`format_as_hf_dataset`] lays out the training examples of a RAG document
+//! the way the `datasets` library loads them: JSON Lines shards named
+//! `data/<split>-<index>-of-<count>.jsonl`, and a `README.md` dataset card
+//! whose YAML header declares the splits, license and size, followed by
+//! statistics of the examples. `load_dataset("path/to/dataset")` reads it.
+//!
+//! Examples are split by a stable hash of the chunk they were made from, so
+//! the examples of one element stay in one split, and each keeps its split
+//! from one run to the next.
+
+use crate::rag::{DifficultyLevel, RagDocument, TaskType, TrainingExample};
+use anyhow::Result;
+use rustex_core::{stable_hash, HfDatasetConfig};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// A row of the dataset: a training example with its metadata flattened.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DatasetRow<'a> {
+    pub id: &'a str,
+    pub input: &'a str,
+    pub output: &'a str,
+    pub task_type: &'a TaskType,
+    pub difficulty: &'a DifficultyLevel,
+    pub source_chunks: &'a [String],
+    pub concepts_involved: &'a [String],
+    pub required_knowledge: &'a [String],
+    pub learning_objectives: &'a [String],
+    pub estimated_token_count: usize,
+}
+
+impl<'a> DatasetRow<'a> {
+    /// The row of `example`.
+    pub fn of(example: &'a TrainingExample) -> Self {
+        let metadata = &example.metadata;
+        Self {
+            id: &example.id,
+            input: &example.input,
+            output: &example.output,
+            task_type: &example.task_type,
+            difficulty: &example.difficulty,
+            source_chunks: &metadata.source_chunks,
+            concepts_involved: &metadata.concepts_involved,
+            required_knowledge: &metadata.required_knowledge,
+            learning_objectives: &metadata.learning_objectives,
+            estimated_token_count: metadata.estimated_token_count,
+        }
+    }
+}
+
+/// Splits of the dataset, in the order of the dataset card.
+const SPLITS: [&str; 3] = ["train", "validation", "test"];
+
+/// The files of the dataset of the training examples of `document`, by path
+/// relative to the dataset's directory.
+pub fn format_as_hf_dataset(
+    document: &RagDocument,
+    config: &HfDatasetConfig,
+) -> Result<BTreeMap<PathBuf, String>> {
+    let mut splits: [Vec<&TrainingExample>; 3] = Default::default();
+    for example in &document.training_examples {
+        splits[split_of(example, config)].push(example);
+    }
+
+    let mut files = BTreeMap::new();
+    for (name, examples) in SPLITS.iter().zip(&splits) {
+        let shards: Vec<_> = examples.chunks(config.shard_size.max(1)).collect();
+        for (index, shard) in shards.iter().enumerate() {
+            let mut lines = String::new();
+            for example in *shard {
+                lines.push_str(&serde_json::to_string(&DatasetRow::of(example))?);
+                lines.push('\n');
+            }
+            let path = format!("data/{}-{:05}-of-{:05}.jsonl", name, index, shards.len());
+            files.insert(PathBuf::from(path), lines);
+        }
+    }
+    files.insert(
+        PathBuf::from("README.md"),
+        dataset_card(document, config, &splits)?,
+    );
+    Ok(files)
+}
+
+/// Index in [`SPLITS`] of the split of `example`.
+fn split_of(example: &TrainingExample, config: &HfDatasetConfig) -> usize {
+    let key = example
+        .metadata
+        .source_chunks
+        .first()
+        .unwrap_or(&example.id);
+    let position = stable_hash(&[key]) as f64 / u64::MAX as f64;
+    if position < config.test_split {
+        2
+    } else if position < config.test_split + config.validation_split {
+        1
+    } else {
+        0
+    }
+}
+
+/// The `README.md` of the dataset: its YAML metadata, then statistics of
+/// `splits`.
+fn dataset_card(
+    document: &RagDocument,
+    config: &HfDatasetConfig,
+    splits: &[Vec<&TrainingExample>; 3],
+) -> Result<String> {
+    let metadata = &document.metadata;
+    let examples = &document.training_examples;
+    let mut card = String::from("---\n");
+    match config.license.as_deref() {
+        Some(license) if is_license_id(license) => {
+            writeln!(card, "license: {}", license.to_ascii_lowercase())?;
+        }
+        Some(license) => {
+            writeln!(card, "license: other\nlicense_name: {:?}", license)?;
+        }
+        None => card.push_str("license: unknown\n"),
+    }
+    card.push_str("language:\n- en\ntags:\n- code\n- rust\n- rustex\n");
+    card.push_str("task_categories:\n- text-generation\n");
+    writeln!(
+        card,
+        "size_categories:\n- {}",
+        size_category(examples.len())
+    )?;
+    if !examples.is_empty() {
+        card.push_str("configs:\n- config_name: default\n  data_files:\n");
+        for (name, split) in SPLITS.iter().zip(splits) {
+            if !split.is_empty() {
+                writeln!(card, "  - split: {}\n    path: data/{}-*.jsonl", name, name)?;
+            }
+        }
+    }
+    card.push_str("---\n\n");
+
+    writeln!(card, "# {} training examples\n", metadata.project_name)?;
+    writeln!(
+        card,
+        "Training examples generated by rustex {} from the sources of {} {}, \
+         on {}.\n",
+        metadata.rustex_version,
+        metadata.project_name,
+        metadata.project_version,
+        metadata.generated_at
+    )?;
+
+    card.push_str("## Splits\n\n| Split | Examples | Estimated tokens |\n|---|---|---|\n");
+    for (name, split) in SPLITS.iter().zip(splits) {
+        let tokens: usize = split
+            .iter()
+            .map(|example| example.metadata.estimated_token_count)
+            .sum();
+        writeln!(card, "| {} | {} | {} |", name, split.len(), tokens)?;
+    }
+
+    writeln!(
+        card,
+        "\n## Languages\n\nPrompts are in English (`en`), and code is Rust, edition {}.",
+        metadata.rust_edition
+    )?;
+
+    card.push_str("\n## Tasks\n\n| Task | Examples |\n|---|---|\n");
+    for (task, count) in counts(examples.iter().map(|example| &example.task_type)) {
+        writeln!(card, "| {} | {} |", task, count)?;
+    }
+    card.push_str("\n## Difficulty\n\n| Difficulty | Examples |\n|---|---|\n");
+    for (difficulty, count) in counts(examples.iter().map(|example| &example.difficulty)) {
+        writeln!(card, "| {} | {} |", difficulty, count)?;
+    }
+
+    card.push_str("\n## License\n\n");
+    match &config.license {
+        Some(license) => writeln!(
+            card,
+            "The examples are derived from {}, licensed under {}.",
+            metadata.project_name, license
+        )?,
+        None => writeln!(
+            card,
+            "The license of {} is unknown; check it before sharing the dataset.",
+            metadata.project_name
+        )?,
+    }
+
+    card.push_str(
+        "\n## Fields\n\n\
+         - `id`: ID of the example\n\
+         - `input`, `output`: Prompt and expected completion\n\
+         - `task_type`, `difficulty`: Kind of task and how hard it is\n\
+         - `source_chunks`: IDs of the RAG chunks the example was made from\n\
+         - `concepts_involved`, `required_knowledge`, `learning_objectives`: What it teaches\n\
+         - `estimated_token_count`: Estimated tokens of the input and output\n",
+    );
+    Ok(card)
+}
+
+/// Whether `license` is a single license, rather than an SPDX expression
+/// combining several, so its lowercase form is a Hugging Face license ID.
+fn is_license_id(license: &str) -> bool {
+    !license.is_empty()
+        && license
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '+'))
+}
+
+/// The Hugging Face size category of a dataset of `rows` rows.
+fn size_category(rows: usize) -> &'static str {
+    match rows {
+        0..=999 => "n<1K",
+        1_000..=9_999 => "1K<n<10K",
+        10_000..=99_999 => "10K<n<100K",
+        100_000..=999_999 => "100K<n<1M",
+        _ => "1M<n<10M",
+    }
+}
+
+/// The number of times each value of `values` occurs, by name.
+fn counts<T: std::fmt::Debug>(values: impl Iterator<Item = T>) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for value in values {
+        *counts.entry(format!("{:?}", value)).or_insert(0) += 1;
+    }
+    counts
+}
@@ -0,0 +1,223 @@
+//! Retrieval keywords of RAG chunks, for sparse and hybrid retrieval such as
+//! BM25 alongside embeddings.
+//!
+//! The keywords of an element's chunks are, in order:
+//!
+//! 1. its name and qualified name
+//! 2. the words of its name, split at `_` and case changes, so
+//!    `parse_config` is found by `parse` and `ConfigBuilder` by `builder`
+//! 3. the names of the types it refers to, other than standard ones such as
+//!    `Option`, taken out of the type expressions it uses, so
+//!    `Result<Vec<ProjectAst>>` gives `ProjectAst`
+//! 4. the words of its docs weighing most by TF-IDF against the docs of the
+//!    project, a [`KeywordCorpus`], so words common to every doc comment
+//!    don't crowd out the ones telling elements apart
+
+use crate::semantics::identifier_words;
+use rustex_core::{CodeElement, FileAst, ReferenceType};
+use std::collections::HashMap;
+
+/// Most keywords of a chunk.
+const MAX_KEYWORDS: usize = 20;
+
+/// Most keywords of a chunk from its docs.
+const MAX_DOC_KEYWORDS: usize = 8;
+
+/// Types too common to tell elements apart.
+const COMMON_TYPES: &[&str] = &[
+    "Self", "Option", "Result", "Vec", "String", "Box", "Rc", "Arc", "HashMap", "HashSet", "Some",
+    "None", "Ok", "Err",
+];
+
+/// Words of docs that say little about an element, besides those
+/// [`identifier_words`] leaves out.
+const STOP_WORDS: &[&str] = &[
+    "all", "also", "any", "are", "but", "can", "does", "each", "else", "has", "have", "its", "may",
+    "more", "most", "not", "one", "only", "other", "returns", "same", "should", "such", "than",
+    "that", "their", "them", "then", "there", "these", "they", "this", "those", "used", "was",
+    "what", "when", "where", "whether", "which", "while", "will", "would", "you", "your",
+];
+
+/// Number of doc comments of a project each word occurs in, to weigh the
+/// words of a doc comment by TF-IDF.
+#[derive(Debug, Clone, Default)]
+pub struct KeywordCorpus {
+    /// Number of documented elements
+    documents: usize,
+    /// Number of documented elements using each word
+    document_frequency: HashMap<String, usize>,
+}
+
+impl KeywordCorpus {
+    /// An empty corpus, to add streamed files to.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The corpus of the docs of `files`, usually all the files of a
+    /// project.
+    pub fn from_files<'a>(files: impl IntoIterator<Item = &'a FileAst>) -> Self {
+        let mut corpus = Self::new();
+        for file in files {
+            corpus.add_file(file);
+        }
+        corpus
+    }
+
+    /// Add the docs of the elements of `file`.
+    pub fn add_file(&mut self, file: &FileAst) {
+        for element in &file.elements {
+            let terms = doc_terms(element);
+            if terms.is_empty() {
+                continue;
+            }
+            self.documents += 1;
+            for term in terms.into_keys() {
+                *self.document_frequency.entry(term).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// The retrieval keywords of `element`, an element of `file`, most
+    /// telling first.
+    pub(crate) fn retrieval_keywords(&self, element: &CodeElement, file: &FileAst) -> Vec<String> {
+        let mut keywords = vec![
+            element.name.clone(),
+            element.hierarchy.qualified_name.clone(),
+        ];
+        keywords.extend(identifier_words(&element.name));
+        keywords.extend(
+            file.cross_references
+                .iter()
+                .filter(|reference| {
+                    reference.from_element_id == element.id
+                        && reference.reference_type == ReferenceType::TypeUsage
+                })
+                .flat_map(|reference| type_names(&reference.reference_text))
+                .map(str::to_string),
+        );
+        keywords.extend(self.doc_keywords(element));
+
+        let mut unique = Vec::new();
+        for keyword in keywords {
+            if !unique.contains(&keyword) {
+                unique.push(keyword);
+            }
+        }
+        unique.truncate(MAX_KEYWORDS);
+        unique
+    }
+
+    /// The words of the docs of `element` with the highest TF-IDF.
+    fn doc_keywords(&self, element: &CodeElement) -> Vec<String> {
+        let mut weighted: Vec<(String, f64)> = doc_terms(element)
+            .into_iter()
+            .map(|(term, frequency)| {
+                let weight = frequency as f64 * self.inverse_document_frequency(&term);
+                (term, weight)
+            })
+            .collect();
+        weighted.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        weighted
+            .into_iter()
+            .take(MAX_DOC_KEYWORDS)
+            .map(|(term, _)| term)
+            .collect()
+    }
+
+    /// The smoothed inverse document frequency of `term`, which is 1 for
+    /// words of every doc comment and grows the rarer the word is.
+    fn inverse_document_frequency(&self, term: &str) -> f64 {
+        let frequency = self.document_frequency.get(term).copied().unwrap_or(0);
+        ((1 + self.documents) as f64 / (1 + frequency) as f64).ln() + 1.0
+    }
+}
+
+/// The names of the types in the type expression `type_text`, such as
+/// `ProjectAst` in `Result < Vec < rustex_core::ProjectAst > >`, leaving out
+/// paths to them and [`COMMON_TYPES`].
+fn type_names(type_text: &str) -> impl Iterator<Item = &str> {
+    type_text
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|name| name.starts_with(|c: char| c.is_uppercase()) && !COMMON_TYPES.contains(name))
+}
+
+/// The words of the docs of `element` outside code blocks, with the number
+/// of times each occurs.
+fn doc_terms(element: &CodeElement) -> HashMap<String, usize> {
+    let mut terms = HashMap::new();
+    let mut in_code = false;
+    for line in &element.doc_comments {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let tokens = line.split(|c: char| !(c.is_alphanumeric() || c == '_'));
+        for word in tokens.flat_map(identifier_words) {
+            if !STOP_WORDS.contains(&word.as_str()) {
+                *terms.entry(word).or_insert(0) += 1;
+            }
+        }
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{CrossReference, ElementType, ReferenceContext};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_type_names() {
+        let names: Vec<&str> = type_names("Result < Vec < rustex_core::ProjectAst > >").collect();
+        assert_eq!(names, ["ProjectAst"]);
+        let names: Vec<&str> = type_names("HashMap < PathBuf , & 'a FileAst >").collect();
+        assert_eq!(names, ["PathBuf", "FileAst"]);
+        assert_eq!(type_names("Option < usize >").count(), 0);
+    }
+
+    #[test]
+    fn test_retrieval_keywords() {
+        let mut file = MockDataGenerator::file_ast(PathBuf::from("src/lib.rs"), 0);
+        let mut element = MockDataGenerator::code_element("load_project", ElementType::Function);
+        element.hierarchy.qualified_name = "crate::load_project".to_string();
+        element.doc_comments = vec!["Loads the manifest from disk.".to_string()];
+        let reference = |text: &str| {
+            CrossReference::new(
+                element.id.clone(),
+                ReferenceType::TypeUsage,
+                text.to_string(),
+                element.location.clone(),
+                ReferenceContext::new(false, String::new()),
+            )
+        };
+        file.cross_references = vec![
+            reference("Result < ProjectAst >"),
+            reference("Vec < ProjectAst >"),
+            reference("& Path"),
+        ];
+        file.elements.push(element.clone());
+
+        let corpus = KeywordCorpus::from_files([&file]);
+        let keywords = corpus.retrieval_keywords(&element, &file);
+        assert_eq!(
+            keywords,
+            [
+                "load_project",
+                "crate::load_project",
+                "load",
+                "project",
+                "ProjectAst",
+                "Path",
+                "disk",
+                "loads",
+                "manifest",
+            ]
+        );
+    }
+}
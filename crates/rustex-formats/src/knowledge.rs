@@ -0,0 +1,283 @@
+//! The entities of a project and the relationships between them, exported as
+//! knowledge graphs by the RDF and Cypher formatters.
+//!
+//! [`build_knowledge_graph`] turns a project into entities, each with a class
+//! and literal properties, and relationships between them; the formatters
+//! only decide how they are written. Entities and relationships are ordered
+//! as they appear in the project, so the same project always gives the same
+//! graph.
+
+use rustex_core::{ProjectAst, ReferenceType};
+use std::collections::{HashMap, HashSet};
+
+/// The entities and relationships of a project.
+#[derive(Debug, Default)]
+pub(crate) struct KnowledgeGraph {
+    pub(crate) entities: Vec<Entity>,
+    /// Relationships without duplicates
+    pub(crate) relationships: Vec<Relationship>,
+}
+
+/// What an entity is, which determines how its ID is scoped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum EntityKind {
+    /// The project, identified by its name
+    Project,
+    /// A workspace crate, identified by its name
+    Crate,
+    /// A file, identified by its path relative to the project root
+    File,
+    /// An element, identified by its element ID
+    Element,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct EntityRef {
+    pub(crate) kind: EntityKind,
+    pub(crate) id: String,
+}
+
+impl EntityRef {
+    fn new(kind: EntityKind, id: impl Into<String>) -> Self {
+        Self {
+            kind,
+            id: id.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Entity {
+    pub(crate) reference: EntityRef,
+    /// Class of the entity: `Project`, `Crate`, `File` or an element type
+    /// such as `Function`
+    pub(crate) class: String,
+    pub(crate) properties: Vec<(&'static str, Value)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Text(String),
+    Integer(u64),
+    Boolean(bool),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Relationship {
+    pub(crate) from: EntityRef,
+    pub(crate) kind: RelationshipKind,
+    pub(crate) to: EntityRef,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum RelationshipKind {
+    /// The project containing a crate or file, a crate containing a file, or
+    /// an element containing a nested element
+    Contains,
+    /// A file defining an element
+    Defines,
+    /// A type implementing a trait of the project, or an impl block when its
+    /// type wasn't extracted
+    Implements,
+    /// A function calling a function of the project
+    Calls,
+    /// An element using a type of the project
+    Uses,
+    /// A workspace crate depending on another one
+    DependsOn,
+}
+
+impl RelationshipKind {
+    /// The camelCase name of the relationship, such as `dependsOn`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            RelationshipKind::Contains => "contains",
+            RelationshipKind::Defines => "defines",
+            RelationshipKind::Implements => "implements",
+            RelationshipKind::Calls => "calls",
+            RelationshipKind::Uses => "uses",
+            RelationshipKind::DependsOn => "dependsOn",
+        }
+    }
+}
+
+impl KnowledgeGraph {
+    fn relate(&mut self, seen: &mut HashSet<Relationship>, relationship: Relationship) {
+        if seen.insert(relationship.clone()) {
+            self.relationships.push(relationship);
+        }
+    }
+}
+
+/// The entities of `project_ast` and the relationships between them.
+pub(crate) fn build_knowledge_graph(project_ast: &ProjectAst) -> KnowledgeGraph {
+    let mut graph = KnowledgeGraph::default();
+    let mut seen = HashSet::new();
+    let project = EntityRef::new(EntityKind::Project, &project_ast.project.name);
+    let element = |id: &str| EntityRef::new(EntityKind::Element, id);
+    let file =
+        |path: &std::path::Path| EntityRef::new(EntityKind::File, path.display().to_string());
+
+    graph.entities.push(Entity {
+        reference: project.clone(),
+        class: "Project".to_string(),
+        properties: vec![
+            ("name", Value::Text(project_ast.project.name.clone())),
+            ("version", Value::Text(project_ast.project.version.clone())),
+        ],
+    });
+
+    if let Some(workspace) = &project_ast.workspace {
+        for krate in &workspace.crates {
+            let reference = EntityRef::new(EntityKind::Crate, &krate.name);
+            graph.entities.push(Entity {
+                reference: reference.clone(),
+                class: "Crate".to_string(),
+                properties: vec![
+                    ("name", Value::Text(krate.name.clone())),
+                    ("version", Value::Text(krate.version.clone())),
+                ],
+            });
+            let contains = Relationship {
+                from: project.clone(),
+                kind: RelationshipKind::Contains,
+                to: reference.clone(),
+            };
+            graph.relate(&mut seen, contains);
+            for path in &krate.files {
+                let contains = Relationship {
+                    from: reference.clone(),
+                    kind: RelationshipKind::Contains,
+                    to: file(path),
+                };
+                graph.relate(&mut seen, contains);
+            }
+        }
+        for edge in &workspace.dependency_edges {
+            let depends = Relationship {
+                from: EntityRef::new(EntityKind::Crate, &edge.from),
+                kind: RelationshipKind::DependsOn,
+                to: EntityRef::new(EntityKind::Crate, &edge.to),
+            };
+            graph.relate(&mut seen, depends);
+        }
+    } else {
+        for file_ast in &project_ast.files {
+            let contains = Relationship {
+                from: project.clone(),
+                kind: RelationshipKind::Contains,
+                to: file(&file_ast.relative_path),
+            };
+            graph.relate(&mut seen, contains);
+        }
+    }
+
+    let trait_paths: HashMap<&str, &str> = project_ast
+        .impls
+        .iter()
+        .map(|trait_impl| (trait_impl.impl_id.as_str(), trait_impl.trait_path.as_str()))
+        .collect();
+    for file_ast in &project_ast.files {
+        let reference = file(&file_ast.relative_path);
+        graph.entities.push(Entity {
+            reference: reference.clone(),
+            class: "File".to_string(),
+            properties: vec![
+                ("path", Value::Text(reference.id.clone())),
+                (
+                    "linesOfCode",
+                    Value::Integer(file_ast.file_metrics.lines_of_code as u64),
+                ),
+            ],
+        });
+
+        for code_element in &file_ast.elements {
+            let defines = Relationship {
+                from: reference.clone(),
+                kind: RelationshipKind::Defines,
+                to: element(&code_element.id),
+            };
+            graph.relate(&mut seen, defines);
+
+            let mut properties = vec![
+                ("name", Value::Text(code_element.name.clone())),
+                (
+                    "qualifiedName",
+                    Value::Text(code_element.hierarchy.qualified_name.clone()),
+                ),
+                (
+                    "visibility",
+                    Value::Text(code_element.visibility.to_string()),
+                ),
+            ];
+            if let Some(signature) = &code_element.signature {
+                properties.push(("signature", Value::Text(signature.clone())));
+            }
+            if !code_element.doc_comments.is_empty() {
+                let documentation = code_element.doc_comments.join("\n");
+                properties.push(("documentation", Value::Text(documentation)));
+            }
+            let location = &code_element.location;
+            properties.push(("lineStart", Value::Integer(location.line_start as u64)));
+            properties.push(("lineEnd", Value::Integer(location.line_end as u64)));
+            if let Some(complexity) = code_element.complexity {
+                properties.push(("complexity", Value::Integer(complexity.into())));
+            }
+            if let Some(trait_path) = trait_paths.get(code_element.id.as_str()) {
+                properties.push(("traitPath", Value::Text(trait_path.to_string())));
+            }
+            if code_element.is_generated {
+                properties.push(("isGenerated", Value::Boolean(true)));
+            }
+            graph.entities.push(Entity {
+                reference: element(&code_element.id),
+                class: format!("{:?}", code_element.element_type),
+                properties,
+            });
+
+            if let Some(parent_id) = &code_element.hierarchy.parent_id {
+                let contains = Relationship {
+                    from: element(parent_id),
+                    kind: RelationshipKind::Contains,
+                    to: element(&code_element.id),
+                };
+                graph.relate(&mut seen, contains);
+            }
+        }
+    }
+
+    for trait_impl in &project_ast.impls {
+        if let Some(trait_id) = &trait_impl.trait_element_id {
+            let implementor = trait_impl
+                .type_element_id
+                .as_deref()
+                .unwrap_or(&trait_impl.impl_id);
+            let implements = Relationship {
+                from: element(implementor),
+                kind: RelationshipKind::Implements,
+                to: element(trait_id),
+            };
+            graph.relate(&mut seen, implements);
+        }
+    }
+
+    for reference in &project_ast.cross_references {
+        let kind = match reference.reference_type {
+            ReferenceType::FunctionCall => RelationshipKind::Calls,
+            ReferenceType::TypeUsage => RelationshipKind::Uses,
+            _ => continue,
+        };
+        let Some(target) = &reference.to_element_id else {
+            continue;
+        };
+        if *target != reference.from_element_id {
+            let relationship = Relationship {
+                from: element(&reference.from_element_id),
+                kind,
+                to: element(target),
+            };
+            graph.relate(&mut seen, relationship);
+        }
+    }
+    graph
+}
@@ -0,0 +1,198 @@
+//! RAG chunks as LangChain documents.
+//!
+//! [`format_as_langchain`] writes a JSON array of objects with the fields of
+//! a LangChain `Document`, which Python loads with
+//! `[Document(**doc) for doc in json.load(file)]`. The page content is the
+//! chunk's context-enriched content, and [`chunk_metadata`] maps its
+//! metadata to flat keys, shared with the LlamaIndex format, which stay the
+//! same across rustex versions:
+//!
+//! | Key | Value |
+//! |-----|-------|
+//! | `source` | File of the chunk, relative to the project root |
+//! | `chunk_id` | ID of the chunk |
+//! | `start_line`, `end_line` | Lines of the chunk in `source` |
+//! | `element_type`, `element_name`, `qualified_name`, `visibility` | The chunked element |
+//! | `chunk_strategy`, `part_index`, `part_count` | How the element was split |
+//! | `token_count` | Estimated tokens of the content |
+//! | `complexity` | Cyclomatic complexity, if the element has one |
+//! | `has_documentation`, `documentation_quality` | `excellent`, `good`, `basic` or `missing` |
+//! | `quality_score` | How worth retrieving the chunk is, from 0 to 1 |
+//! | `semantic_category`, `domain_tags`, `intent_tags`, `retrieval_keywords` | Categories and tags |
+//! | `parent_elements`, `child_elements`, `references`, `referenced_by` | IDs of related elements |
+//! | `embedding_strategy` | `combined`, `code_only`, `documentation_only` or a specialized strategy |
+//! | `semantic_hash` | Hash of the content, to find changed chunks |
+
+use crate::rag::{DocumentationQuality, EmbeddingStrategy, RagChunk, RagDocument};
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// A chunk as a LangChain `Document`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LangChainDocument {
+    /// ID of the chunk
+    pub id: String,
+    /// Context-enriched content of the chunk
+    pub page_content: String,
+    /// Metadata of the chunk, as mapped by [`chunk_metadata`]
+    pub metadata: Map<String, Value>,
+    /// Always `Document`
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+impl LangChainDocument {
+    /// The document of `chunk`.
+    pub fn of(chunk: &RagChunk) -> Self {
+        Self {
+            id: chunk.id.clone(),
+            page_content: chunk.content_with_context.clone(),
+            metadata: chunk_metadata(chunk),
+            kind: "Document".to_string(),
+        }
+    }
+}
+
+/// Render the chunks of `document` as a JSON array of LangChain documents.
+pub fn format_as_langchain(document: &RagDocument, pretty: bool) -> Result<String> {
+    let documents: Vec<LangChainDocument> =
+        document.chunks.iter().map(LangChainDocument::of).collect();
+    if pretty {
+        Ok(serde_json::to_string_pretty(&documents)?)
+    } else {
+        Ok(serde_json::to_string(&documents)?)
+    }
+}
+
+/// The metadata of `chunk` under the keys listed in the [module
+/// documentation](self), with lists of strings and no nested objects.
+pub fn chunk_metadata(chunk: &RagChunk) -> Map<String, Value> {
+    let metadata = &chunk.metadata;
+    let Value::Object(mut map) = json!({
+        "source": metadata.file_path,
+        "chunk_id": chunk.id,
+        "start_line": metadata.start_line,
+        "end_line": metadata.end_line,
+        "element_type": metadata.element_type,
+        "element_name": metadata.element_name,
+        "qualified_name": metadata.qualified_name,
+        "visibility": metadata.visibility,
+        "chunk_strategy": metadata.chunk_strategy.name(),
+        "part_index": metadata.part_index,
+        "part_count": metadata.part_count,
+        "token_count": metadata.token_count,
+        "has_documentation": metadata.has_documentation,
+        "documentation_quality": documentation_quality(&metadata.documentation_quality),
+        "quality_score": metadata.quality_score,
+        "semantic_category": metadata.semantic_category,
+        "domain_tags": strings(&metadata.domain_tags),
+        "intent_tags": strings(&metadata.intent_tags),
+        "retrieval_keywords": strings(&metadata.retrieval_keywords),
+        "parent_elements": strings(&metadata.parent_elements),
+        "child_elements": strings(&metadata.child_elements),
+        "references": strings(&metadata.references),
+        "referenced_by": strings(&metadata.referenced_by),
+        "embedding_strategy": embedding_strategy(&metadata.embedding_strategy),
+        "semantic_hash": chunk.semantic_hash,
+    }) else {
+        unreachable!("json! of an object is an object");
+    };
+    if let Some(complexity) = metadata.complexity {
+        map.insert("complexity".to_string(), Value::from(complexity));
+    }
+    map
+}
+
+/// The non-empty strings of `values`.
+fn strings(values: &[String]) -> Vec<&str> {
+    values
+        .iter()
+        .map(String::as_str)
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn documentation_quality(quality: &DocumentationQuality) -> &'static str {
+    match quality {
+        DocumentationQuality::Excellent => "excellent",
+        DocumentationQuality::Good => "good",
+        DocumentationQuality::Basic => "basic",
+        DocumentationQuality::Missing => "missing",
+    }
+}
+
+fn embedding_strategy(strategy: &EmbeddingStrategy) -> &str {
+    match strategy {
+        EmbeddingStrategy::Combined => "combined",
+        EmbeddingStrategy::CodeOnly => "code_only",
+        EmbeddingStrategy::DocumentationOnly => "documentation_only",
+        EmbeddingStrategy::Specialized(name) => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::{RagConfig, RagFormatter};
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    /// The shape of a LangChain `Document`, without other fields.
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Document {
+        id: String,
+        page_content: String,
+        metadata: Map<String, Value>,
+        #[serde(rename = "type")]
+        kind: String,
+    }
+
+    fn rag_document() -> RagDocument {
+        let mut project = MockDataGenerator::project_ast(1, 2);
+        for element in &mut project.files[0].elements {
+            element.attributes.clear();
+        }
+        RagFormatter::new(RagConfig::default())
+            .format(&project)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_documents_deserialize() {
+        let document = rag_document();
+        for pretty in [false, true] {
+            let json = format_as_langchain(&document, pretty).unwrap();
+            let documents: Vec<Document> = serde_json::from_str(&json).unwrap();
+            assert_eq!(documents.len(), document.chunks.len());
+            for (parsed, chunk) in documents.iter().zip(&document.chunks) {
+                assert_eq!(parsed.id, chunk.id);
+                assert_eq!(parsed.kind, "Document");
+                assert_eq!(parsed.page_content, chunk.content_with_context);
+                assert_eq!(parsed.metadata["chunk_id"], chunk.id.as_str());
+                assert_eq!(parsed.metadata["source"], "file_0.rs");
+                assert_eq!(
+                    parsed.metadata["qualified_name"],
+                    chunk.metadata.qualified_name.as_str()
+                );
+                assert_eq!(parsed.metadata["documentation_quality"], "basic");
+                assert_eq!(parsed.metadata["complexity"], 5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_metadata_is_flat() {
+        let document = rag_document();
+        for chunk in &document.chunks {
+            for (key, value) in chunk_metadata(chunk) {
+                let flat = match &value {
+                    Value::Array(values) => values.iter().all(Value::is_string),
+                    value => !value.is_object(),
+                };
+                assert!(flat, "metadata {} is nested: {}", key, value);
+            }
+        }
+    }
+}
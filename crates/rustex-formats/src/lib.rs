@@ -3,20 +3,78 @@
 //! Output formatters for rustex AST extraction, including specialized formats
 //! for LLM training, RAG systems, and various documentation formats.
 
+mod api_surface;
+mod chunking;
+pub mod coverage;
+pub mod csv;
+pub mod ctags;
+pub mod cypher;
+pub mod dedup;
+pub mod dot;
+mod embedding_inputs;
+pub mod embeddings;
+pub mod fine_tuning;
 pub mod formatters;
+mod graphs;
+#[cfg(feature = "graphql-server")]
+pub mod graphql_server;
+pub mod hf_dataset;
+pub mod keywords;
+mod knowledge;
+pub mod langchain;
+pub mod llamaindex;
+pub mod manifest;
+pub mod markdown;
+pub mod mdbook;
+pub mod mermaid;
+pub mod overview;
+mod patterns;
+pub mod protobuf;
+mod quality;
 pub mod rag;
+pub mod rdf;
+pub mod sarif;
+pub mod scip;
+mod semantics;
+pub mod summaries;
+pub mod template;
+mod training;
 
 // Re-export main formatting functions
 pub use formatters::{
-    format_project_ast, format_as_markdown, format_as_graphql_schema,
-    create_rag_formatter, format_as_rag_with_config, format_as_rag_jsonl,
-    format_for_embeddings,
+    format_project_ast, format_as_graphql_schema, create_rag_formatter, format_as_rag_with_config,
+    format_as_rag_jsonl, format_as_file_jsonl, format_for_embeddings, format_as_message_pack,
+    parse_message_pack, output_schema,
 };
 
+pub use coverage::{doc_coverage, format_coverage_badge, format_coverage_markdown, DocCoverage};
+pub use csv::{format_as_csv, format_as_parquet};
+pub use ctags::{format_as_ctags, format_as_etags};
+pub use cypher::format_as_cypher;
+pub use dedup::{ChunkDeduplicator, DuplicateChunk};
+pub use dot::{dot_graph, format_as_dot, format_dependency_graph};
+pub use embeddings::{embedding_provider, EmbeddingError, EmbeddingProvider};
+pub use fine_tuning::{format_as_anthropic_fine_tuning, format_as_openai_fine_tuning};
+pub use hf_dataset::format_as_hf_dataset;
+pub use keywords::KeywordCorpus;
+pub use langchain::{chunk_metadata, format_as_langchain, LangChainDocument};
+pub use llamaindex::{format_as_llamaindex, LlamaIndexNode};
+pub use manifest::{ChunkChanges, ManifestSummary, RagManifest, RemovedChunk};
+pub use markdown::format_as_markdown;
+pub use mdbook::format_as_mdbook;
+pub use mermaid::{format_as_mermaid, mermaid_diagram, mermaid_sections};
+pub use overview::format_as_overview;
+pub use protobuf::{format_as_protobuf, PROTO_SCHEMA};
+pub use rdf::{format_as_ntriples, format_as_turtle};
+pub use sarif::format_as_sarif;
+pub use scip::format_as_scip;
+pub use summaries::{summarizer, Summarizer};
+pub use template::format_with_template;
+
 // Re-export RAG-specific types and functions
 pub use rag::{
     RagDocument, RagFormatter, RagConfig, RagChunk, RagMetadata, RagSemantics,
-    ChunkMetadata, DocumentationQuality, EmbeddingStrategy, SemanticDepth,
-    TrainingExample, TaskType, DifficultyLevel, EmbeddingInput,
+    ChunkMetadata, ChunkStrategy, DocumentationQuality, EmbeddingStrategy, SemanticDepth,
+    TrainingExample, TaskType, DifficultyLevel, EmbeddingGranularity, EmbeddingInput,
     format_as_json, format_as_jsonl,
 };
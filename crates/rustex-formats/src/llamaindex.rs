@@ -0,0 +1,222 @@
+//! RAG chunks as LlamaIndex nodes.
+//!
+//! [`format_as_llamaindex`] writes a JSON array of serialized LlamaIndex
+//! `TextNode`s, which Python loads with
+//! `[TextNode.from_dict(node) for node in json.load(file)]`. Node metadata
+//! has the keys of the LangChain format (see [`chunk_metadata`]). The text
+//! already holds the chunk's context, so metadata is left out of the text
+//! embedded, and only the location of the chunk is shown to the LLM.
+//!
+//! Each node's source is the document of its file, and the chunks of an
+//! element split into parts are linked as previous and next nodes.
+
+use crate::langchain::chunk_metadata;
+use crate::rag::{RagChunk, RagDocument};
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Metadata keys shown to the LLM along with the text of a node.
+const LLM_METADATA_KEYS: &[&str] = &["source", "qualified_name", "start_line", "end_line"];
+
+/// Keys of the relationships of a node, from LlamaIndex's `NodeRelationship`.
+const SOURCE: &str = "1";
+const PREVIOUS: &str = "2";
+const NEXT: &str = "3";
+
+/// Types of related nodes, from LlamaIndex's `ObjectType`.
+const TEXT_NODE: &str = "1";
+const DOCUMENT_NODE: &str = "4";
+
+/// A chunk as a serialized LlamaIndex `TextNode`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LlamaIndexNode {
+    /// ID of the chunk
+    pub id_: String,
+    /// Context-enriched content of the chunk
+    pub text: String,
+    /// Metadata of the chunk, as mapped by [`chunk_metadata`]
+    pub metadata: Map<String, Value>,
+    /// Metadata keys left out of the text embedded
+    pub excluded_embed_metadata_keys: Vec<String>,
+    /// Metadata keys left out of the text given to the LLM
+    pub excluded_llm_metadata_keys: Vec<String>,
+    /// Related nodes, by `NodeRelationship` value
+    pub relationships: BTreeMap<String, RelatedNode>,
+    /// Embedding of the chunk, if it is embedded
+    pub embedding: Option<Vec<f32>>,
+    /// Always `TextNode`
+    pub class_name: String,
+}
+
+/// A serialized LlamaIndex `RelatedNodeInfo`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelatedNode {
+    /// ID of the related node
+    pub node_id: String,
+    /// `ObjectType` value of the related node
+    pub node_type: String,
+    pub metadata: Map<String, Value>,
+    /// Always `RelatedNodeInfo`
+    pub class_name: String,
+}
+
+impl RelatedNode {
+    fn new(node_id: &str, node_type: &str) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            node_type: node_type.to_string(),
+            metadata: Map::new(),
+            class_name: "RelatedNodeInfo".to_string(),
+        }
+    }
+}
+
+/// The nodes of the chunks of `document`.
+pub fn llamaindex_nodes(document: &RagDocument) -> Vec<LlamaIndexNode> {
+    let chunks = &document.chunks;
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let metadata = chunk_metadata(chunk);
+            let mut relationships = BTreeMap::new();
+            relationships.insert(
+                SOURCE.to_string(),
+                RelatedNode::new(&chunk.metadata.file_path, DOCUMENT_NODE),
+            );
+            let previous = index.checked_sub(1).and_then(|index| chunks.get(index));
+            if let Some(previous) = previous.filter(|previous| same_element(previous, chunk)) {
+                relationships.insert(
+                    PREVIOUS.to_string(),
+                    RelatedNode::new(&previous.id, TEXT_NODE),
+                );
+            }
+            let next = chunks.get(index + 1);
+            if let Some(next) = next.filter(|next| same_element(chunk, next)) {
+                relationships.insert(NEXT.to_string(), RelatedNode::new(&next.id, TEXT_NODE));
+            }
+            LlamaIndexNode {
+                id_: chunk.id.clone(),
+                text: chunk.content_with_context.clone(),
+                excluded_embed_metadata_keys: metadata.keys().cloned().collect(),
+                excluded_llm_metadata_keys: metadata
+                    .keys()
+                    .filter(|key| !LLM_METADATA_KEYS.contains(&key.as_str()))
+                    .cloned()
+                    .collect(),
+                metadata,
+                relationships,
+                embedding: chunk.embedding.clone(),
+                class_name: "TextNode".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Whether `next` is the part after `chunk` of the same element.
+fn same_element(chunk: &RagChunk, next: &RagChunk) -> bool {
+    chunk.metadata.file_path == next.metadata.file_path
+        && chunk.metadata.qualified_name == next.metadata.qualified_name
+        && chunk.metadata.part_index + 1 == next.metadata.part_index
+}
+
+/// Render the chunks of `document` as a JSON array of LlamaIndex nodes.
+pub fn format_as_llamaindex(document: &RagDocument, pretty: bool) -> Result<String> {
+    let nodes = llamaindex_nodes(document);
+    if pretty {
+        Ok(serde_json::to_string_pretty(&nodes)?)
+    } else {
+        Ok(serde_json::to_string(&nodes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::{RagConfig, RagFormatter};
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    /// The shape of a serialized LlamaIndex `TextNode`.
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct TextNode {
+        id_: String,
+        text: String,
+        metadata: Map<String, Value>,
+        excluded_embed_metadata_keys: Vec<String>,
+        excluded_llm_metadata_keys: Vec<String>,
+        relationships: BTreeMap<String, RelatedNodeInfo>,
+        embedding: Option<Vec<f32>>,
+        class_name: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct RelatedNodeInfo {
+        node_id: String,
+        node_type: String,
+        metadata: Map<String, Value>,
+        class_name: String,
+    }
+
+    fn rag_document() -> RagDocument {
+        let mut project = MockDataGenerator::project_ast(1, 2);
+        for element in &mut project.files[0].elements {
+            element.attributes.clear();
+        }
+        RagFormatter::new(RagConfig::default())
+            .format(&project)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_nodes_deserialize() {
+        let document = rag_document();
+        let json = format_as_llamaindex(&document, false).unwrap();
+        let nodes: Vec<TextNode> = serde_json::from_str(&json).unwrap();
+        assert_eq!(nodes.len(), document.chunks.len());
+        for (node, chunk) in nodes.iter().zip(&document.chunks) {
+            assert_eq!(node.id_, chunk.id);
+            assert_eq!(node.class_name, "TextNode");
+            assert_eq!(node.text, chunk.content_with_context);
+            assert_eq!(node.metadata["chunk_id"], chunk.id.as_str());
+            assert_eq!(node.metadata["source"], "file_0.rs");
+            assert_eq!(node.excluded_embed_metadata_keys.len(), node.metadata.len());
+            assert!(!node
+                .excluded_llm_metadata_keys
+                .iter()
+                .any(|key| key == "qualified_name"));
+            assert!(node.embedding.is_none());
+
+            let source = &node.relationships[SOURCE];
+            assert_eq!(source.node_id, "file_0.rs");
+            assert_eq!(source.node_type, DOCUMENT_NODE);
+            assert_eq!(source.class_name, "RelatedNodeInfo");
+            assert!(source.metadata.is_empty());
+            assert_eq!(node.relationships.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_parts_are_linked() {
+        let mut document = rag_document();
+        // Two parts of the same element.
+        let first = document.chunks[0].metadata.clone();
+        let second = &mut document.chunks[1].metadata;
+        second.qualified_name = first.qualified_name.clone();
+        second.part_index = first.part_index + 1;
+
+        let nodes = llamaindex_nodes(&document);
+        assert_eq!(nodes[0].relationships[NEXT].node_id, document.chunks[1].id);
+        assert_eq!(nodes[0].relationships[NEXT].node_type, TEXT_NODE);
+        assert_eq!(
+            nodes[1].relationships[PREVIOUS].node_id,
+            document.chunks[0].id
+        );
+        assert!(!nodes[0].relationships.contains_key(PREVIOUS));
+        assert!(!nodes[1].relationships.contains_key(NEXT));
+    }
+}
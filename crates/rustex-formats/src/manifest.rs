@@ -0,0 +1,168 @@
+//! Manifests of RAG output, to update embeddings incrementally.
+//!
+//! A [`RagManifest`] records the semantic hash of each chunk of a run. Chunk
+//! IDs are derived from stable element IDs, so comparing the chunks of a new
+//! run with the manifest of an earlier one tells which chunks were added,
+//! changed or removed since, and only those need to be embedded and indexed
+//! again.
+//!
+//! Manifests also tell ingestion tools what to expect of the output: the
+//! number of chunks, the byte offset of each chunk's line in RAG JSONL
+//! output, to resume or verify reading it, and a hash of the configuration,
+//! to notice runs made with other settings.
+
+use crate::rag::RagChunk;
+use rustex_core::ProjectInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The chunks of a run of the RAG formats, by ID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RagManifest {
+    /// Version of rustex that wrote the manifest
+    pub rustex_version: String,
+    /// When the chunks were generated
+    pub generated_at: String,
+    /// Name of the project the chunks are of
+    #[serde(default)]
+    pub project_name: String,
+    /// Version of the project
+    #[serde(default)]
+    pub project_version: String,
+    /// Hash of the configuration the chunks were made with, set by the
+    /// writer of the manifest; empty if unknown
+    #[serde(default)]
+    pub config_hash: String,
+    /// Number of chunks
+    #[serde(default)]
+    pub chunk_count: usize,
+    #[serde(default)]
+    pub summary: ManifestSummary,
+    /// Semantic hash of each chunk, by chunk ID
+    pub chunks: BTreeMap<String, String>,
+    /// Byte offset of the line of each chunk written to RAG JSONL output,
+    /// before compression, by chunk ID; empty for other outputs
+    #[serde(default)]
+    pub offsets: BTreeMap<String, u64>,
+}
+
+/// Totals of the chunks of a [`RagManifest`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ManifestSummary {
+    /// Number of chunks of each element type
+    pub chunks_by_element_type: BTreeMap<String, usize>,
+    /// Estimated tokens of the contents of all chunks
+    pub total_tokens: usize,
+    /// Number of chunks left out as near-duplicates
+    pub duplicate_chunks: usize,
+}
+
+impl Default for RagManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RagManifest {
+    /// An empty manifest, generated now.
+    pub fn new() -> Self {
+        Self {
+            rustex_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            project_name: String::new(),
+            project_version: String::new(),
+            config_hash: String::new(),
+            chunk_count: 0,
+            summary: ManifestSummary::default(),
+            chunks: BTreeMap::new(),
+            offsets: BTreeMap::new(),
+        }
+    }
+
+    /// An empty manifest of the chunks of `project`, generated now.
+    pub fn for_project(project: &ProjectInfo) -> Self {
+        Self {
+            project_name: project.name.clone(),
+            project_version: project.version.clone(),
+            ..Self::new()
+        }
+    }
+
+    /// Record `chunks` in the manifest.
+    pub fn add(&mut self, chunks: &[RagChunk]) {
+        for chunk in chunks {
+            let previous = self
+                .chunks
+                .insert(chunk.id.clone(), chunk.semantic_hash.clone());
+            if previous.is_some() {
+                continue;
+            }
+            let metadata = &chunk.metadata;
+            let summary = &mut self.summary;
+            *summary
+                .chunks_by_element_type
+                .entry(metadata.element_type.clone())
+                .or_insert(0) += 1;
+            summary.total_tokens += metadata.token_count;
+        }
+        self.chunk_count = self.chunks.len();
+    }
+
+    /// Whether `chunk` was added or changed since this manifest.
+    pub fn is_changed(&self, chunk: &RagChunk) -> bool {
+        self.chunks.get(&chunk.id) != Some(&chunk.semantic_hash)
+    }
+
+    /// The chunks that changed from this manifest to `current`.
+    pub fn changes_to(&self, current: &RagManifest) -> ChunkChanges {
+        let mut changes = ChunkChanges {
+            since: self.generated_at.clone(),
+            ..ChunkChanges::default()
+        };
+        for (id, hash) in &current.chunks {
+            match self.chunks.get(id) {
+                None => changes.added.push(id.clone()),
+                Some(previous) if previous != hash => changes.changed.push(id.clone()),
+                Some(_) => changes.unchanged += 1,
+            }
+        }
+        changes.removed = self
+            .chunks
+            .keys()
+            .filter(|id| !current.chunks.contains_key(*id))
+            .cloned()
+            .collect();
+        changes
+    }
+}
+
+/// Chunks added, changed or removed since an earlier [`RagManifest`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ChunkChanges {
+    /// When the chunks of the earlier manifest were generated
+    pub since: String,
+    /// IDs of the chunks that are new
+    pub added: Vec<String>,
+    /// IDs of the chunks whose semantic hash changed
+    pub changed: Vec<String>,
+    /// IDs of the chunks that no longer exist
+    pub removed: Vec<String>,
+    /// Number of chunks left out because they are unchanged
+    pub unchanged: usize,
+}
+
+impl ChunkChanges {
+    /// Whether any chunk was added, changed or removed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A line of RAG JSONL output for a chunk removed since the manifest the
+/// output was compared with, so indexes can delete it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RemovedChunk {
+    /// ID of the removed chunk
+    pub removed: String,
+}
@@ -0,0 +1,580 @@
+//! Markdown documentation of a project as a single document.
+//!
+//! [`format_as_markdown`] renders a title with the project's version, then
+//! the sections chosen in [`MarkdownConfig`]: metrics, plugin results, tech
+//! debt, diagrams, trait implementations and the elements of each file. Files and elements get
+//! HTML anchors, which the table of contents and cross-links point to, and
+//! locations link to their lines in a repository browser when `repo_url` is
+//! set.
+
+use crate::formatters::code_list;
+use crate::mdbook::{relative_name, sanitize, unique_anchor};
+use crate::mermaid::mermaid_sections;
+use anyhow::Result;
+use rustex_core::{
+    CodeElement, Diagram, ElementType, FileAst, MarkdownConfig, MarkdownSection, ProjectAst,
+    TechDebtReport, TraitImpl,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Render `project_ast` as Markdown laid out by `config`, embedding
+/// `diagrams` as Mermaid.
+pub fn format_as_markdown(
+    project_ast: &ProjectAst,
+    config: &MarkdownConfig,
+    diagrams: &[Diagram],
+) -> Result<String> {
+    let document = Document::new(project_ast, config);
+    let tech_debt = TechDebtReport::from_project(project_ast);
+    let sections: Vec<MarkdownSection> = MarkdownSection::ALL
+        .into_iter()
+        .filter(|&section| config.includes(section))
+        .filter(|&section| match section {
+            MarkdownSection::Diagrams => !diagrams.is_empty(),
+            MarkdownSection::TraitImplementations => !project_ast.impls.is_empty(),
+            MarkdownSection::Files => !project_ast.files.is_empty(),
+            MarkdownSection::PluginResults => !project_ast.plugin_results.is_empty(),
+            MarkdownSection::TechDebt => tech_debt.is_some(),
+            MarkdownSection::Metrics => true,
+        })
+        .collect();
+
+    let project = &project_ast.project;
+    let mut markdown = document.heading(0, &format!("{} AST Analysis", project.name));
+    markdown.push_str(&format!("**Version:** {}\n", project.version));
+    markdown.push_str(&format!("**Rust Edition:** {}\n", project.rust_edition));
+    markdown.push_str(&format!(
+        "**Extracted:** {}\n\n",
+        project_ast.extracted_at.format("%Y-%m-%d %H:%M:%S UTC")
+    ));
+    if let Some(report) = &project_ast.truncation {
+        markdown.push_str(&format!(
+            "**Truncated:** to {} bytes; {} elements stripped, \
+             {} elements and {} files dropped\n\n",
+            report.limit_bytes,
+            report.stripped_elements,
+            report.dropped_elements,
+            report.dropped_files.len()
+        ));
+    }
+
+    if config.table_of_contents && !sections.is_empty() {
+        document.write_contents(&mut markdown, &sections);
+    }
+    for section in sections {
+        markdown.push_str(&document.heading(1, section_title(section)));
+        match section {
+            MarkdownSection::Metrics => document.write_metrics(&mut markdown),
+            MarkdownSection::PluginResults => document.write_plugin_results(&mut markdown),
+            MarkdownSection::TechDebt => {
+                if let Some(report) = &tech_debt {
+                    document.write_tech_debt(&mut markdown, report);
+                }
+            }
+            MarkdownSection::Diagrams => markdown.push_str(&mermaid_sections(
+                project_ast,
+                diagrams,
+                config.heading_level + 2,
+            )),
+            MarkdownSection::TraitImplementations => {
+                for trait_impl in &project_ast.impls {
+                    document.write_trait_impl(&mut markdown, trait_impl);
+                }
+                markdown.push('\n');
+            }
+            MarkdownSection::Files => {
+                for (index, file) in project_ast.files.iter().enumerate() {
+                    document.write_file(&mut markdown, index, file);
+                }
+            }
+        }
+    }
+    Ok(markdown)
+}
+
+struct Document<'a> {
+    project_ast: &'a ProjectAst,
+    config: &'a MarkdownConfig,
+    /// Anchors of the files, in the order of `project_ast.files`
+    file_anchors: Vec<String>,
+    /// Anchor and name of each element by ID, when files are rendered
+    elements: HashMap<&'a str, (String, String)>,
+}
+
+impl<'a> Document<'a> {
+    fn new(project_ast: &'a ProjectAst, config: &'a MarkdownConfig) -> Self {
+        let mut anchors = BTreeSet::new();
+        let mut file_anchors = Vec::new();
+        let mut elements = HashMap::new();
+        for file in &project_ast.files {
+            let path = file.relative_path.display().to_string();
+            file_anchors.push(unique_anchor(
+                &mut anchors,
+                &format!("file.{}", sanitize(&path)),
+            ));
+            if !config.includes(MarkdownSection::Files) {
+                continue;
+            }
+            for element in &file.elements {
+                let name = qualified_name(element);
+                let anchor = format!(
+                    "{}.{}",
+                    element.element_type.filter_name(),
+                    sanitize(&name.replace("::", "."))
+                );
+                let anchor = unique_anchor(&mut anchors, &anchor);
+                elements.insert(element.id.as_str(), (anchor, name));
+            }
+        }
+        Self {
+            project_ast,
+            config,
+            file_anchors,
+            elements,
+        }
+    }
+
+    /// A heading `depth` levels below the title.
+    fn heading(&self, depth: usize, text: &str) -> String {
+        format!(
+            "{} {}\n\n",
+            "#".repeat(self.config.heading_level + depth),
+            text
+        )
+    }
+
+    fn write_contents(&self, markdown: &mut String, sections: &[MarkdownSection]) {
+        markdown.push_str(&self.heading(1, "Contents"));
+        for &section in sections {
+            let title = section_title(section);
+            markdown.push_str(&format!(
+                "- [{}](#{})\n",
+                title,
+                title.to_lowercase().replace(' ', "-")
+            ));
+            if section != MarkdownSection::Files {
+                continue;
+            }
+            for (file, anchor) in self.project_ast.files.iter().zip(&self.file_anchors) {
+                markdown.push_str(&format!(
+                    "  - [{}](#{})\n",
+                    file.relative_path.display(),
+                    anchor
+                ));
+                for element in &file.elements {
+                    let (anchor, _) = &self.elements[element.id.as_str()];
+                    markdown.push_str(&format!(
+                        "    - [{} `{}`](#{})\n",
+                        element_type_name(element),
+                        relative_name(element),
+                        anchor
+                    ));
+                }
+            }
+        }
+        markdown.push('\n');
+    }
+
+    fn write_metrics(&self, markdown: &mut String) {
+        let metrics = &self.project_ast.metrics;
+        markdown.push_str(&format!("- **Total Files:** {}\n", metrics.total_files));
+        markdown.push_str(&format!("- **Total Lines:** {}\n", metrics.total_lines));
+        markdown.push_str(&format!("- **Functions:** {}\n", metrics.total_functions));
+        markdown.push_str(&format!("- **Structs:** {}\n", metrics.total_structs));
+        markdown.push_str(&format!("- **Enums:** {}\n", metrics.total_enums));
+        markdown.push_str(&format!("- **Traits:** {}\n", metrics.total_traits));
+        markdown.push_str(&format!(
+            "- **Average Complexity:** {:.2}\n\n",
+            metrics.complexity_average
+        ));
+    }
+
+    /// The metrics, metadata and messages of each plugin, with the rule and
+    /// location of diagnostics; metadata holding lists or tables is
+    /// summarized by its size.
+    fn write_plugin_results(&self, markdown: &mut String) {
+        for (name, results) in &self.project_ast.plugin_results {
+            markdown.push_str(&self.heading(2, &format!("`{}`", name)));
+            for (metric, value) in &results.metrics {
+                markdown.push_str(&format!("- **{}:** {}\n", metric, value));
+            }
+            for (key, value) in &results.metadata {
+                let value = match value {
+                    serde_json::Value::Array(items) => format!("{} items", items.len()),
+                    serde_json::Value::Object(entries) => format!("{} entries", entries.len()),
+                    serde_json::Value::String(text) => text.clone(),
+                    value => value.to_string(),
+                };
+                markdown.push_str(&format!("- **{}:** {}\n", key, value));
+            }
+            for message in &results.messages {
+                let mut text = message.message.clone();
+                if let Some(rule) = &message.rule {
+                    text = format!("`{}` {}", rule, text);
+                }
+                if let (Some(file), Some(line)) = (&message.file, message.line) {
+                    text.push_str(&format!(" ({})", self.location(file, line, line)));
+                }
+                markdown.push_str(&format!("- *{}:* {}\n", message.level, text));
+            }
+            markdown.push('\n');
+        }
+    }
+
+    /// Counts of the tech debt markers by kind and owner, then a table of
+    /// the markers.
+    fn write_tech_debt(&self, markdown: &mut String, report: &TechDebtReport) {
+        if report.markers.is_empty() {
+            markdown.push_str("*No technical debt markers found*\n\n");
+            return;
+        }
+        let kinds: Vec<String> = report
+            .by_kind
+            .iter()
+            .map(|(kind, count)| format!("{} `{}`", count, kind))
+            .collect();
+        markdown.push_str(&format!(
+            "- **Markers:** {} ({})\n",
+            report.markers.len(),
+            kinds.join(", ")
+        ));
+        let mut owners: Vec<String> = report
+            .by_owner
+            .iter()
+            .map(|(owner, count)| format!("{} ({})", owner, count))
+            .collect();
+        if report.unowned > 0 {
+            owners.push(format!("unowned ({})", report.unowned));
+        }
+        markdown.push_str(&format!("- **Owners:** {}\n", owners.join(", ")));
+        if let Some(oldest) = report.oldest_age_days() {
+            markdown.push_str(&format!("- **Oldest:** {} days\n", oldest));
+        }
+
+        let with_ages = report
+            .markers
+            .iter()
+            .any(|marker| marker.age_days.is_some());
+        markdown.push_str(if with_ages {
+            "\n| Marker | Location | Element | Owner | Age | Note |\n|---|---|---|---|---|---|\n"
+        } else {
+            "\n| Marker | Location | Element | Owner | Note |\n|---|---|---|---|---|\n"
+        });
+        for marker in &report.markers {
+            let age = match (with_ages, marker.age_days) {
+                (true, Some(days)) => format!(" {} days |", days),
+                (true, None) => " |".to_string(),
+                (false, _) => String::new(),
+            };
+            markdown.push_str(&format!(
+                "| `{}` | {} | {} | {} |{} {} |\n",
+                marker.kind,
+                self.location(&marker.file, marker.line, marker.line),
+                marker
+                    .element
+                    .as_deref()
+                    .map(|element| format!("`{}`", element))
+                    .unwrap_or_default(),
+                marker.owner.as_deref().unwrap_or_default(),
+                age,
+                marker.text.replace('|', "\\|")
+            ));
+        }
+        markdown.push('\n');
+    }
+
+    fn write_trait_impl(&self, markdown: &mut String, trait_impl: &TraitImpl) {
+        let file = &trait_impl.location.file_path;
+        let path = file
+            .strip_prefix(&self.project_ast.project.root_path)
+            .unwrap_or(file);
+        let line = trait_impl.location.line_start;
+        markdown.push_str(&format!(
+            "- {} implements {} ({})\n",
+            self.element_link(trait_impl.type_element_id.as_deref(), &trait_impl.self_type),
+            self.element_link(
+                trait_impl.trait_element_id.as_deref(),
+                &trait_impl.trait_path
+            ),
+            self.location(path, line, line)
+        ));
+        if !trait_impl.generics.is_empty() {
+            markdown.push_str(&format!(
+                "  - **Generics:** `<{}>`\n",
+                trait_impl.generics.join(", ")
+            ));
+        }
+        if let Some(where_clause) = &trait_impl.where_clause {
+            markdown.push_str(&format!("  - **Where:** `{}`\n", where_clause));
+        }
+        if !trait_impl.overridden_methods.is_empty() {
+            markdown.push_str(&format!(
+                "  - **Overrides:** {}\n",
+                code_list(&trait_impl.overridden_methods)
+            ));
+        }
+        if trait_impl.is_generated {
+            markdown.push_str("  - *Generated by macro expansion*\n");
+        }
+    }
+
+    fn write_file(&self, markdown: &mut String, index: usize, file: &FileAst) {
+        markdown.push_str(&format!("<a id=\"{}\"></a>\n\n", self.file_anchors[index]));
+        markdown.push_str(&self.heading(2, &file.relative_path.display().to_string()));
+        if file.elements.is_empty() {
+            markdown.push_str("*No extractable elements found*\n\n");
+        }
+        for element in &file.elements {
+            self.write_element(markdown, file, element);
+        }
+    }
+
+    fn write_element(&self, markdown: &mut String, file: &FileAst, element: &CodeElement) {
+        let (anchor, _) = &self.elements[element.id.as_str()];
+        markdown.push_str(&format!("<a id=\"{}\"></a>\n\n", anchor));
+        markdown.push_str(&self.heading(
+            3,
+            &format!(
+                "{} `{}`",
+                element_type_name(element),
+                relative_name(element)
+            ),
+        ));
+
+        if !element.doc_comments.is_empty() {
+            markdown.push_str("**Documentation:**\n");
+            for doc in &element.doc_comments {
+                markdown.push_str(&format!("> {}\n", doc));
+            }
+            markdown.push('\n');
+        }
+        if let Some(signature) = &element.signature {
+            markdown.push_str(&format!("```rust\n{}\n```\n\n", signature));
+        }
+
+        markdown.push_str(&format!(
+            "- **Location:** {}\n",
+            self.location(
+                &file.relative_path,
+                element.location.line_start,
+                element.location.line_end
+            )
+        ));
+        markdown.push_str(&format!("- **Visibility:** `{}`\n", element.visibility));
+        if let Some(complexity) = element.complexity {
+            markdown.push_str(&format!("- **Complexity:** {}\n", complexity));
+        }
+        for (title, methods) in [
+            ("Required Methods", element.required_methods()),
+            ("Provided Methods", element.provided_methods()),
+        ] {
+            if !methods.is_empty() {
+                markdown.push_str(&format!("- **{}:** {}\n", title, code_list(&methods)));
+            }
+        }
+        if self.config.cross_links {
+            let graph = &self.project_ast.reference_graph;
+            for (title, ids) in [
+                ("Uses", graph.references_of(&element.id)),
+                ("Used by", graph.referenced_by(&element.id)),
+            ] {
+                let mut links: Vec<String> = ids
+                    .iter()
+                    .filter_map(|id| {
+                        let (anchor, name) = self.elements.get(id.as_str())?;
+                        Some(format!("[`{}`](#{})", name, anchor))
+                    })
+                    .collect();
+                links.sort();
+                links.dedup();
+                if !links.is_empty() {
+                    markdown.push_str(&format!("- **{}:** {}\n", title, links.join(", ")));
+                }
+            }
+        }
+        markdown.push('\n');
+    }
+
+    /// `name` in code, linked to the element `id` when cross-links are on and
+    /// the element is documented.
+    fn element_link(&self, id: Option<&str>, name: &str) -> String {
+        match id.and_then(|id| self.elements.get(id)) {
+            Some((anchor, _)) if self.config.cross_links => format!("[`{}`](#{})", name, anchor),
+            _ => format!("`{}`", name),
+        }
+    }
+
+    /// `path:start-end`, linked to those lines below `repo_url` when set.
+    fn location(&self, path: &Path, line_start: usize, line_end: usize) -> String {
+        let path = path.display().to_string().replace('\\', "/");
+        let (text, lines) = if line_start == line_end {
+            (
+                format!("{}:{}", path, line_start),
+                format!("L{}", line_start),
+            )
+        } else {
+            (
+                format!("{}:{}-{}", path, line_start, line_end),
+                format!("L{}-L{}", line_start, line_end),
+            )
+        };
+        match &self.config.repo_url {
+            Some(repo_url) => format!(
+                "[{}]({}/{}#{})",
+                text,
+                repo_url.trim_end_matches('/'),
+                path,
+                lines
+            ),
+            None => text,
+        }
+    }
+}
+
+fn section_title(section: MarkdownSection) -> &'static str {
+    match section {
+        MarkdownSection::Metrics => "Project Metrics",
+        MarkdownSection::PluginResults => "Plugin Results",
+        MarkdownSection::TechDebt => "Technical Debt",
+        MarkdownSection::Diagrams => "Diagrams",
+        MarkdownSection::TraitImplementations => "Trait Implementations",
+        MarkdownSection::Files => "Files",
+    }
+}
+
+/// The type of `element` as a heading word, such as `Function`.
+fn element_type_name(element: &CodeElement) -> String {
+    format!("{:?}", element.element_type)
+}
+
+/// Qualified name of `element` without the leading `crate::`, such as
+/// `net::Connection::open`; impl blocks go by their name.
+fn qualified_name(element: &CodeElement) -> String {
+    if element.element_type == ElementType::Impl {
+        return element.name.clone();
+    }
+    let qualified_name = &element.hierarchy.qualified_name;
+    qualified_name
+        .strip_prefix("crate::")
+        .unwrap_or(qualified_name)
+        .replace(" :: ", "::")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{CrossReference, ReferenceContext, ReferenceGraph, ReferenceType};
+
+    /// Two files of two functions, the first calling the last.
+    fn project() -> ProjectAst {
+        let mut project = MockDataGenerator::project_ast(2, 2);
+        for (index, file) in project.files.iter_mut().enumerate() {
+            for element in &mut file.elements {
+                element.id = format!("{}_{}", index, element.id);
+            }
+        }
+        let from = &project.files[0].elements[0];
+        let to = &project.files[1].elements[1];
+        let mut call = CrossReference::new(
+            from.id.clone(),
+            ReferenceType::FunctionCall,
+            "element_1".to_string(),
+            from.location.clone(),
+            ReferenceContext::new(false, String::new()),
+        );
+        call.resolve_to(to.id.clone());
+        project.reference_graph = ReferenceGraph::from_cross_references([&call]);
+        project
+    }
+
+    /// The targets of the `](#anchor)` links of `markdown`.
+    fn link_targets(markdown: &str) -> Vec<&str> {
+        markdown
+            .split("](#")
+            .skip(1)
+            .filter_map(|rest| rest.split_once(')'))
+            .map(|(anchor, _)| anchor)
+            .collect()
+    }
+
+    /// Whether `markdown` has an anchor or a heading `anchor` links to.
+    fn has_target(markdown: &str, anchor: &str) -> bool {
+        markdown.contains(&format!("<a id=\"{}\"></a>", anchor))
+            || markdown
+                .lines()
+                .filter_map(|line| line.trim_start_matches('#').strip_prefix(' '))
+                .any(|heading| heading.to_lowercase().replace(' ', "-") == anchor)
+    }
+
+    #[test]
+    fn test_sections_can_be_left_out() {
+        let project = project();
+        let config = MarkdownConfig {
+            exclude_sections: vec![MarkdownSection::Metrics],
+            ..MarkdownConfig::default()
+        };
+        let markdown = format_as_markdown(&project, &config, &[]).unwrap();
+        assert!(!markdown.contains("Project Metrics"));
+        assert!(markdown.contains("## Files\n"));
+
+        let config = MarkdownConfig {
+            sections: vec![MarkdownSection::Metrics],
+            ..MarkdownConfig::default()
+        };
+        let markdown = format_as_markdown(&project, &config, &[Diagram::CallGraph]).unwrap();
+        assert!(markdown.contains("## Project Metrics\n"));
+        assert!(!markdown.contains("## Files"));
+        assert!(!markdown.contains("## Diagrams"));
+        assert!(!markdown.contains("element_0"));
+    }
+
+    #[test]
+    fn test_contents_links_resolve() {
+        let markdown = format_as_markdown(
+            &project(),
+            &MarkdownConfig::default(),
+            &[Diagram::CallGraph],
+        )
+        .unwrap();
+        let contents = markdown.split("## Project Metrics").next().unwrap();
+        let targets = link_targets(contents);
+        assert_eq!(
+            &targets[..4],
+            ["project-metrics", "diagrams", "files", "file.file_0.rs"]
+        );
+        assert_eq!(targets.len(), 9);
+        for anchor in targets {
+            assert!(has_target(&markdown, anchor), "no target for #{}", anchor);
+        }
+    }
+
+    #[test]
+    fn test_cross_links_resolve() {
+        let project = project();
+        let markdown = format_as_markdown(&project, &MarkdownConfig::default(), &[]).unwrap();
+        let uses: Vec<&str> = markdown
+            .lines()
+            .filter(|line| line.starts_with("- **Uses:**"))
+            .collect();
+        let used_by: Vec<&str> = markdown
+            .lines()
+            .filter(|line| line.starts_with("- **Used by:**"))
+            .collect();
+        assert_eq!(uses.len(), 1);
+        assert_eq!(used_by.len(), 1);
+        for line in uses.iter().chain(&used_by) {
+            for anchor in link_targets(line) {
+                assert!(has_target(&markdown, anchor), "no target for #{}", anchor);
+            }
+        }
+
+        let config = MarkdownConfig {
+            cross_links: false,
+            ..MarkdownConfig::default()
+        };
+        let markdown = format_as_markdown(&project, &config, &[]).unwrap();
+        assert!(!markdown.contains("- **Uses:**"));
+    }
+}
@@ -0,0 +1,612 @@
+//! mdBook output: the Markdown documentation of a project laid out as a book.
+//!
+//! [`format_as_mdbook`] renders a `book.toml`, a `src/SUMMARY.md` and one
+//! chapter per module, so `mdbook build` publishes the extraction directly.
+//! Each chapter starts with its metadata as front matter inside an HTML
+//! comment, which mdBook leaves out of the rendered page. Elements link to
+//! the elements they use and are used by, and intra-doc links such as
+//! ``[`Parser`]`` in doc comments become links to the element's chapter.
+
+use crate::formatters::code_list;
+use anyhow::Result;
+use rustex_core::{CodeElement, ElementType, FileAst, ProjectAst};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+/// Render `project_ast` as an mdBook, as file contents keyed by their path
+/// relative to the book's root directory.
+pub fn format_as_mdbook(project_ast: &ProjectAst) -> Result<BTreeMap<PathBuf, String>> {
+    let book = Book::new(project_ast);
+    let mut files = BTreeMap::new();
+    files.insert(PathBuf::from("book.toml"), book.book_toml()?);
+    files.insert(PathBuf::from("src/SUMMARY.md"), book.summary());
+    files.insert(PathBuf::from("src/README.md"), book.introduction());
+    for (key, chapter) in &book.chapters {
+        let path = PathBuf::from("src").join(&chapter.path);
+        files.insert(path, book.chapter(key, chapter)?);
+    }
+    Ok(files)
+}
+
+/// A module chapter, keyed in [`Book::chapters`] by crate and module path.
+struct Chapter<'a> {
+    /// Path of the chapter relative to the book's `src` directory
+    path: String,
+    /// Title, the module path with `crate` replaced by the crate name
+    title: String,
+    /// Elements of the module, with the file each comes from
+    elements: Vec<(&'a FileAst, &'a CodeElement)>,
+}
+
+/// Where an element is documented.
+struct Target {
+    /// Key of the chapter documenting the element
+    chapter: (String, String),
+    /// Anchor of the element in its chapter; modules link to their chapter
+    anchor: Option<String>,
+}
+
+struct Book<'a> {
+    project_ast: &'a ProjectAst,
+    /// Chapters by crate name and module path, ancestors before descendants
+    chapters: BTreeMap<(String, String), Chapter<'a>>,
+    /// Documentation targets by element ID
+    targets: HashMap<&'a str, Target>,
+    /// Element IDs by crate name and qualified name
+    qualified: HashMap<(&'a str, &'a str), &'a str>,
+}
+
+impl<'a> Book<'a> {
+    fn new(project_ast: &'a ProjectAst) -> Self {
+        let mut chapters: BTreeMap<(String, String), Chapter<'a>> = BTreeMap::new();
+        let elements = project_ast
+            .files
+            .iter()
+            .flat_map(|file| file.elements.iter().map(move |element| (file, element)));
+        for (file, element) in elements {
+            let crate_name = crate_name(element).to_string();
+            let module_path = &element.hierarchy.module_path;
+            // Every module and its ancestors get a chapter, so the summary nests
+            // and `mod` items can link to the chapter of their module.
+            let mut modules = vec![module_path];
+            if element.element_type == ElementType::Module {
+                modules.push(&element.hierarchy.qualified_name);
+            }
+            for module in modules {
+                let mut ancestor = String::new();
+                for segment in module.split("::") {
+                    if !ancestor.is_empty() {
+                        ancestor.push_str("::");
+                    }
+                    ancestor.push_str(segment);
+                    chapters
+                        .entry((crate_name.clone(), ancestor.clone()))
+                        .or_insert_with(|| Chapter {
+                            path: chapter_path(&crate_name, &ancestor),
+                            title: chapter_title(&crate_name, &ancestor),
+                            elements: Vec::new(),
+                        });
+                }
+            }
+            let key = (crate_name, module_path.clone());
+            chapters
+                .get_mut(&key)
+                .expect("chapter was just added")
+                .elements
+                .push((file, element));
+        }
+
+        let mut targets = HashMap::new();
+        let mut qualified = HashMap::new();
+        for (key, chapter) in &chapters {
+            let mut anchors = BTreeSet::new();
+            for &(_, element) in &chapter.elements {
+                let qualified_name = element.hierarchy.qualified_name.as_str();
+                qualified.insert((crate_name(element), qualified_name), element.id.as_str());
+                let target = if element.element_type == ElementType::Module {
+                    Target {
+                        chapter: (key.0.clone(), qualified_name.to_string()),
+                        anchor: None,
+                    }
+                } else {
+                    let anchor = unique_anchor(&mut anchors, &element_anchor(element));
+                    Target {
+                        chapter: key.clone(),
+                        anchor: Some(anchor),
+                    }
+                };
+                targets.insert(element.id.as_str(), target);
+            }
+        }
+        Self {
+            project_ast,
+            chapters,
+            targets,
+            qualified,
+        }
+    }
+
+    fn book_toml(&self) -> Result<String> {
+        let project = &self.project_ast.project;
+        let title = format!("{} {}", project.name, project.version);
+        // JSON strings are valid TOML basic strings.
+        Ok(format!(
+            "[book]\ntitle = {}\nlanguage = \"en\"\nsrc = \"src\"\n",
+            serde_json::to_string(&title)?
+        ))
+    }
+
+    fn summary(&self) -> String {
+        let mut summary = String::from("# Summary\n\n[Introduction](README.md)\n\n");
+        // Sorted by path segment, so submodules follow their parent.
+        let mut chapters: Vec<_> = self.chapters.iter().collect();
+        chapters.sort_by(|((a_crate, a_module), _), ((b_crate, b_module), _)| {
+            a_crate
+                .cmp(b_crate)
+                .then_with(|| a_module.split("::").cmp(b_module.split("::")))
+        });
+        for ((_, module_path), chapter) in chapters {
+            let depth = module_path.matches("::").count();
+            summary.push_str(&format!(
+                "{}- [{}]({})\n",
+                "  ".repeat(depth),
+                chapter.title,
+                chapter.path
+            ));
+        }
+        summary
+    }
+
+    fn introduction(&self) -> String {
+        let project = &self.project_ast.project;
+        let metrics = &self.project_ast.metrics;
+        let mut markdown = format!("# {}\n\n", project.name);
+        markdown.push_str(&format!("**Version:** {}  \n", project.version));
+        markdown.push_str(&format!("**Rust Edition:** {}  \n", project.rust_edition));
+        markdown.push_str(&format!(
+            "**Extracted:** {}\n\n",
+            self.project_ast
+                .extracted_at
+                .format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+        markdown.push_str("## Project Metrics\n\n");
+        markdown.push_str(&format!("- **Total Files:** {}\n", metrics.total_files));
+        markdown.push_str(&format!("- **Total Lines:** {}\n", metrics.total_lines));
+        markdown.push_str(&format!(
+            "- **Total Functions:** {}\n",
+            metrics.total_functions
+        ));
+        markdown.push_str(&format!("- **Total Structs:** {}\n", metrics.total_structs));
+        markdown.push_str(&format!("- **Total Enums:** {}\n", metrics.total_enums));
+        markdown.push_str(&format!("- **Total Traits:** {}\n", metrics.total_traits));
+        markdown.push_str(&format!(
+            "- **Average Complexity:** {:.2}\n",
+            metrics.complexity_average
+        ));
+        markdown
+    }
+
+    fn chapter(&self, key: &(String, String), chapter: &Chapter<'_>) -> Result<String> {
+        let files: BTreeSet<String> = chapter
+            .elements
+            .iter()
+            .map(|(file, _)| file.relative_path.display().to_string())
+            .collect();
+        let mut markdown = String::from("<!--\n---\n");
+        markdown.push_str(&format!(
+            "title: {}\n",
+            serde_json::to_string(&chapter.title)?
+        ));
+        markdown.push_str(&format!("crate: {}\n", serde_json::to_string(&key.0)?));
+        markdown.push_str(&format!("module: {}\n", serde_json::to_string(&key.1)?));
+        markdown.push_str(&format!("files: {}\n", serde_json::to_string(&files)?));
+        markdown.push_str(&format!("elements: {}\n", chapter.elements.len()));
+        markdown.push_str(&format!(
+            "extracted_at: {}\n",
+            self.project_ast.extracted_at.to_rfc3339()
+        ));
+        markdown.push_str("---\n-->\n\n");
+        markdown.push_str(&format!("# {}\n\n", chapter.title));
+
+        if chapter.elements.is_empty() {
+            markdown.push_str("*No elements in this module.*\n");
+        }
+        for (file, element) in &chapter.elements {
+            self.write_element(&mut markdown, key, file, element);
+        }
+        Ok(markdown)
+    }
+
+    fn write_element(
+        &self,
+        markdown: &mut String,
+        key: &(String, String),
+        file: &FileAst,
+        element: &CodeElement,
+    ) {
+        let target = &self.targets[element.id.as_str()];
+        if let Some(anchor) = &target.anchor {
+            markdown.push_str(&format!("<a id=\"{}\"></a>\n\n", anchor));
+        }
+        markdown.push_str(&format!(
+            "## {:?} `{}`\n\n",
+            element.element_type,
+            relative_name(element)
+        ));
+        if target.anchor.is_none() {
+            let link = self.link(key, target);
+            markdown.push_str(&format!(
+                "See [{}]({}).\n\n",
+                self.chapters[&target.chapter].title, link
+            ));
+        }
+
+        if !element.doc_comments.is_empty() {
+            let mut in_code_block = false;
+            for doc in &element.doc_comments {
+                if doc.trim_start().starts_with("```") {
+                    in_code_block = !in_code_block;
+                }
+                if in_code_block {
+                    markdown.push_str(&format!("{}\n", doc));
+                } else {
+                    markdown.push_str(&format!("{}\n", self.link_doc_line(key, element, doc)));
+                }
+            }
+            markdown.push('\n');
+        }
+
+        if let Some(signature) = &element.signature {
+            markdown.push_str(&format!("```rust,ignore\n{}\n```\n\n", signature));
+        }
+
+        markdown.push_str(&format!(
+            "- **Location:** {}:{}-{}\n",
+            file.relative_path.display(),
+            element.location.line_start,
+            element.location.line_end
+        ));
+        markdown.push_str(&format!("- **Visibility:** {:?}\n", element.visibility));
+        if let Some(complexity) = element.complexity {
+            markdown.push_str(&format!("- **Complexity:** {}\n", complexity));
+        }
+        let required = element.required_methods();
+        if !required.is_empty() {
+            markdown.push_str(&format!(
+                "- **Required Methods:** {}\n",
+                code_list(&required)
+            ));
+        }
+        let provided = element.provided_methods();
+        if !provided.is_empty() {
+            markdown.push_str(&format!(
+                "- **Provided Methods:** {}\n",
+                code_list(&provided)
+            ));
+        }
+        let graph = &self.project_ast.reference_graph;
+        for (label, ids) in [
+            ("Uses", graph.references_of(&element.id)),
+            ("Used by", graph.referenced_by(&element.id)),
+        ] {
+            let links = self.element_links(key, ids);
+            if !links.is_empty() {
+                markdown.push_str(&format!("- **{}:** {}\n", label, links.join(", ")));
+            }
+        }
+        markdown.push('\n');
+    }
+
+    /// Links to the documented elements among `ids`, by name.
+    fn element_links(&self, from: &(String, String), ids: &[String]) -> Vec<String> {
+        let mut links: Vec<String> = ids
+            .iter()
+            .filter_map(|id| {
+                let target = self.targets.get(id.as_str())?;
+                let element = self.chapters[&target.chapter]
+                    .elements
+                    .iter()
+                    .map(|(_, element)| *element)
+                    .find(|element| &element.id == id);
+                let name = match element {
+                    Some(element) => relative_name(element),
+                    None => self.chapters[&target.chapter].title.clone(),
+                };
+                Some(format!("[`{}`]({})", name, self.link(from, target)))
+            })
+            .collect();
+        links.sort();
+        links.dedup();
+        links
+    }
+
+    /// Relative link from the chapter `from` to `target`.
+    fn link(&self, from: &(String, String), target: &Target) -> String {
+        let mut link = String::new();
+        if *from != target.chapter {
+            let from: Vec<&str> = self.chapters[from].path.split('/').collect();
+            let to: Vec<&str> = self.chapters[&target.chapter].path.split('/').collect();
+            let common = from
+                .iter()
+                .zip(&to)
+                .take(from.len().min(to.len()) - 1)
+                .take_while(|(from, to)| from == to)
+                .count();
+            link.push_str(&"../".repeat(from.len() - 1 - common));
+            link.push_str(&to[common..].join("/"));
+        }
+        if let Some(anchor) = &target.anchor {
+            link.push('#');
+            link.push_str(anchor);
+        }
+        link
+    }
+
+    /// `line` of the docs of `element` with its intra-doc links, such as
+    /// ``[`Parser`]`` or `[connect](crate::net::connect)`, pointing to the
+    /// elements they name. Path links to unknown items keep only their text.
+    fn link_doc_line(&self, from: &(String, String), element: &CodeElement, line: &str) -> String {
+        let mut output = String::with_capacity(line.len());
+        let mut rest = line;
+        let mut in_code = false;
+        while let Some(index) = rest.find(['[', '`']) {
+            output.push_str(&rest[..index]);
+            rest = &rest[index..];
+            if rest.starts_with('`') {
+                in_code = !in_code;
+                output.push('`');
+                rest = &rest[1..];
+                continue;
+            }
+            let text_end = rest[1..]
+                .find([']', '['])
+                .filter(|&end| !in_code && rest.as_bytes()[end + 1] == b']');
+            let Some(text_end) = text_end else {
+                output.push('[');
+                rest = &rest[1..];
+                continue;
+            };
+            let text = &rest[1..=text_end];
+            let after = &rest[text_end + 2..];
+            let destination = after
+                .strip_prefix('(')
+                .and_then(|after| Some(&after[..after.find(')')?]));
+            let (path, length) = match destination {
+                Some(destination) => (destination, text_end + 4 + destination.len()),
+                None if after.starts_with(['[', ':', '(']) => {
+                    // A reference link, a reference definition or an unclosed link.
+                    output.push('[');
+                    rest = &rest[1..];
+                    continue;
+                }
+                None => (text, text_end + 2),
+            };
+            let is_path = path
+                .trim_matches('`')
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '@' | '!' | '(' | ')'));
+            match self.resolve(from, element, path) {
+                Some(target) => {
+                    output.push_str(&format!("[{}]({})", text, self.link(from, target)))
+                }
+                // Rustdoc paths can't be followed outside rustdoc.
+                None if destination.is_some() && is_path => output.push_str(text),
+                None => output.push_str(&rest[..length]),
+            }
+            rest = &rest[length..];
+        }
+        output.push_str(rest);
+        output
+    }
+
+    /// The element an intra-doc link's path names in the docs of `element`,
+    /// looked up relative to the module `from`, then from the crate root,
+    /// then by unique path suffix.
+    fn resolve(
+        &self,
+        from: &(String, String),
+        element: &CodeElement,
+        path: &str,
+    ) -> Option<&Target> {
+        let path = path.trim().trim_matches('`');
+        let path = path.split_once('@').map_or(path, |(_, path)| path);
+        let path = path.trim_end_matches("()").trim_end_matches('!');
+        if path.is_empty()
+            || !path
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':'))
+        {
+            return None;
+        }
+        let (crate_name, module_path) = (from.0.as_str(), from.1.as_str());
+        let parent_module = module_path
+            .rsplit_once("::")
+            .map_or("crate", |(parent, _)| parent);
+        let mut candidates = Vec::new();
+        if path == "self" {
+            candidates.push(module_path.to_string());
+        } else if path == "Self" {
+            candidates.push(self_path(element).to_string());
+        } else if let Some(path) = path.strip_prefix("Self::") {
+            candidates.push(format!("{}::{}", self_path(element), path));
+        } else if let Some(path) = path.strip_prefix("self::") {
+            candidates.push(format!("{}::{}", module_path, path));
+        } else if let Some(path) = path.strip_prefix("super::") {
+            candidates.push(format!("{}::{}", parent_module, path));
+        } else if path.starts_with("crate::") {
+            candidates.push(path.to_string());
+        } else {
+            candidates.push(format!("{}::{}", module_path, path));
+            candidates.push(format!("crate::{}", path));
+        }
+        let found = candidates
+            .iter()
+            .find_map(|candidate| self.qualified.get(&(crate_name, candidate.as_str())));
+        let id = match found {
+            Some(id) => *id,
+            None => {
+                // Items re-exported elsewhere, such as at the crate root.
+                let unqualified = path.strip_prefix("crate::").unwrap_or(path);
+                let suffix = format!("::{}", unqualified);
+                let mut matches = self
+                    .qualified
+                    .iter()
+                    .filter(|((_, qualified), _)| qualified.ends_with(&suffix));
+                let (_, id) = matches.next()?;
+                if matches.next().is_some() {
+                    return None;
+                }
+                *id
+            }
+        };
+        self.targets.get(id)
+    }
+}
+
+/// Qualified name of the type `Self` stands for in the docs of `element`.
+fn self_path(element: &CodeElement) -> &str {
+    let qualified_name = element.hierarchy.qualified_name.as_str();
+    match element.element_type {
+        ElementType::Struct
+        | ElementType::Enum
+        | ElementType::Union
+        | ElementType::Trait
+        | ElementType::TypeAlias => qualified_name,
+        _ => qualified_name
+            .rsplit_once("::")
+            .map_or(qualified_name, |(parent, _)| parent),
+    }
+}
+
+/// Name of the crate an element belongs to, the namespace of its ID.
+pub(crate) fn crate_name(element: &CodeElement) -> &str {
+    element
+        .id
+        .split_once("::")
+        .map_or("crate", |(crate_name, _)| crate_name)
+}
+
+/// Name of the crate an element belongs to in Rust paths, `my_crate` for
+/// the package `my-crate`.
+pub(crate) fn crate_path_name(element: &CodeElement) -> String {
+    crate_name(element).replace('-', "_")
+}
+
+/// Path of the chapter of `module_path`: `my-crate.md` for the crate root,
+/// `my-crate/net/tcp.md` for `crate::net::tcp`.
+fn chapter_path(crate_name: &str, module_path: &str) -> String {
+    let mut segments = vec![sanitize(crate_name)];
+    segments.extend(module_path.split("::").skip(1).map(sanitize));
+    format!("{}.md", segments.join("/"))
+}
+
+/// `my-crate::net::tcp` for `crate::net::tcp`.
+pub(crate) fn chapter_title(crate_name: &str, module_path: &str) -> String {
+    match module_path.strip_prefix("crate") {
+        Some(rest) if rest.is_empty() || rest.starts_with("::") => {
+            format!("{}{}", crate_name, rest)
+        }
+        _ => module_path.to_string(),
+    }
+}
+
+/// Name of an element within its module, such as `Parser::parse`.
+pub(crate) fn relative_name(element: &CodeElement) -> String {
+    let hierarchy = &element.hierarchy;
+    hierarchy
+        .qualified_name
+        .strip_prefix(&hierarchy.module_path)
+        .and_then(|name| name.strip_prefix("::"))
+        .filter(|_| element.element_type != ElementType::Impl)
+        .unwrap_or(&element.name)
+        .replace(" :: ", "::")
+}
+
+/// Anchor of an element in the style of rustdoc, such as `struct.Parser` or
+/// `function.Parser.parse`.
+fn element_anchor(element: &CodeElement) -> String {
+    let name = relative_name(element).replace("::", ".");
+    format!("{}.{}", element.element_type.filter_name(), sanitize(&name))
+}
+
+/// `anchor`, with `-2`, `-3`... appended if already used in the chapter.
+pub(crate) fn unique_anchor(used: &mut BTreeSet<String>, anchor: &str) -> String {
+    let mut unique = anchor.to_string();
+    let mut suffix = 1;
+    while !used.insert(unique.clone()) {
+        suffix += 1;
+        unique = format!("{}-{}", anchor, suffix);
+    }
+    unique
+}
+
+/// `text` with characters other than letters, digits, `_`, `-` and `.`
+/// replaced by `-`, for use in file names and anchors.
+pub(crate) fn sanitize(text: &str) -> String {
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') {
+            sanitized.push(c);
+        } else if !sanitized.ends_with('-') {
+            sanitized.push('-');
+        }
+    }
+    sanitized.trim_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    /// A project with items in the crate root, `net::tcp` and `util`.
+    fn project() -> ProjectAst {
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        for (name, module_path) in [
+            ("run", "crate"),
+            ("connect", "crate::net::tcp"),
+            ("helper", "crate::util"),
+        ] {
+            let mut element = MockDataGenerator::code_element(name, ElementType::Function);
+            element.hierarchy.module_path = module_path.to_string();
+            element.hierarchy.qualified_name = format!("{}::{}", module_path, name);
+            project.files[0].elements.push(element);
+        }
+        project
+    }
+
+    #[test]
+    fn test_summary_links_every_chapter() {
+        let files = format_as_mdbook(&project()).unwrap();
+        let summary = &files[&PathBuf::from("src/SUMMARY.md")];
+        let links: BTreeSet<PathBuf> = summary
+            .lines()
+            .filter_map(|line| line.split_once("](")?.1.strip_suffix(')'))
+            .map(|link| PathBuf::from("src").join(link))
+            .collect();
+        let written: BTreeSet<PathBuf> = files
+            .keys()
+            .filter(|path| {
+                path.starts_with("src") && path.file_name() != Some("SUMMARY.md".as_ref())
+            })
+            .cloned()
+            .collect();
+        assert_eq!(links, written);
+    }
+
+    #[test]
+    fn test_summary_nests_modules() {
+        let files = format_as_mdbook(&project()).unwrap();
+        assert_eq!(
+            files[&PathBuf::from("src/SUMMARY.md")],
+            "# Summary\n\n\
+             [Introduction](README.md)\n\n\
+             - [crate](crate.md)\n\
+             \x20 - [crate::net](crate/net.md)\n\
+             \x20   - [crate::net::tcp](crate/net/tcp.md)\n\
+             \x20 - [crate::util](crate/util.md)\n"
+        );
+        // Modules without items of their own still get a chapter.
+        assert!(files.contains_key(&PathBuf::from("src/crate/net.md")));
+    }
+}
@@ -0,0 +1,235 @@
+//! Mermaid diagrams of the structure of a project.
+//!
+//! [`format_as_mermaid`] renders the diagrams of [`Diagram`] as fenced
+//! `mermaid` blocks, which GitHub, GitLab and mdBook (with `mdbook-mermaid`)
+//! render in Markdown. [`mermaid_sections`] renders the same blocks for
+//! embedding in other Markdown documents.
+
+use crate::graphs::{build_graph, diagram_title, EdgeKind, Graph, NodeKind};
+use anyhow::Result;
+use rustex_core::{ComplexityLevel, Diagram, ElementType, ProjectAst};
+
+/// Render `diagrams` of `project_ast` as a Markdown document with one section
+/// per diagram, or every diagram if `diagrams` is empty.
+pub fn format_as_mermaid(project_ast: &ProjectAst, diagrams: &[Diagram]) -> Result<String> {
+    let diagrams = if diagrams.is_empty() {
+        &Diagram::ALL[..]
+    } else {
+        diagrams
+    };
+    let mut markdown = format!("# {} Diagrams\n\n", project_ast.project.name);
+    markdown.push_str(&mermaid_sections(project_ast, diagrams, 2));
+    Ok(markdown)
+}
+
+/// `diagrams` of `project_ast` as Markdown sections with headings of `level`,
+/// each holding a fenced `mermaid` block.
+pub fn mermaid_sections(project_ast: &ProjectAst, diagrams: &[Diagram], level: usize) -> String {
+    let mut markdown = String::new();
+    for &diagram in diagrams {
+        markdown.push_str(&format!(
+            "{} {}\n\n",
+            "#".repeat(level),
+            diagram_title(diagram)
+        ));
+        let graph = build_graph(project_ast, diagram);
+        if graph.nodes.is_empty() {
+            markdown.push_str("*Nothing to show.*\n\n");
+        } else {
+            markdown.push_str(&format!("```mermaid\n{}\n```\n\n", render(diagram, &graph)));
+        }
+    }
+    markdown
+}
+
+/// The Mermaid source of `diagram` of `project_ast`, without fences.
+pub fn mermaid_diagram(project_ast: &ProjectAst, diagram: Diagram) -> String {
+    render(diagram, &build_graph(project_ast, diagram))
+}
+
+fn render(diagram: Diagram, graph: &Graph) -> String {
+    match diagram {
+        Diagram::StructRelationships => class_diagram(graph),
+        Diagram::ModuleHierarchy => flowchart(diagram, graph, "TD"),
+        _ => flowchart(diagram, graph, "LR"),
+    }
+}
+
+/// Node IDs of each diagram start with their own letter, which keeps the
+/// source readable and the IDs unique when diagrams are embedded together.
+fn node_id(diagram: Diagram, index: usize) -> String {
+    let prefix = match diagram {
+        Diagram::CrateDependencies => 'c',
+        Diagram::ModuleHierarchy => 'm',
+        Diagram::ModuleImports => 'i',
+        Diagram::StructRelationships => 't',
+        Diagram::CallGraph => 'f',
+    };
+    format!("{}{}", prefix, index)
+}
+
+/// A flowchart, with nodes colored by complexity.
+fn flowchart(diagram: Diagram, graph: &Graph, direction: &str) -> String {
+    let mut lines = vec![format!("flowchart {}", direction)];
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let id = node_id(diagram, index);
+        let label = label(&node.label);
+        lines.push(match node.kind {
+            NodeKind::Dependency => format!("    {}([{}])", id, label),
+            _ => format!("    {}[{}]", id, label),
+        });
+    }
+    for edge in &graph.edges {
+        let arrow = match edge.kind {
+            EdgeKind::DevDepends => "-. dev .->",
+            EdgeKind::BuildDepends => "-. build .->",
+            _ => "-->",
+        };
+        lines.push(format!(
+            "    {} {} {}",
+            node_id(diagram, edge.from),
+            arrow,
+            node_id(diagram, edge.to)
+        ));
+    }
+
+    let levels = [
+        (ComplexityLevel::Medium, "medium", "#fff3bf"),
+        (ComplexityLevel::High, "high", "#ffd8a8"),
+        (ComplexityLevel::VeryHigh, "very_high", "#ffa8a8"),
+    ];
+    for (level, class, color) in levels {
+        let ids: Vec<String> = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.complexity == Some(level))
+            .map(|(index, _)| node_id(diagram, index))
+            .collect();
+        if !ids.is_empty() {
+            lines.push(format!("    classDef {} fill:{}", class, color));
+            lines.push(format!("    class {} {}", ids.join(","), class));
+        }
+    }
+    lines.join("\n")
+}
+
+/// A class diagram of types with their members.
+fn class_diagram(graph: &Graph) -> String {
+    let diagram = Diagram::StructRelationships;
+    let mut lines = vec!["classDiagram".to_string()];
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let id = node_id(diagram, index);
+        lines.push(format!("    class {}[{}]", id, label(&node.label)));
+        match node.kind {
+            NodeKind::Type(ElementType::Enum) => lines.push(format!("    <<enumeration>> {}", id)),
+            NodeKind::Type(ElementType::Trait) => lines.push(format!("    <<trait>> {}", id)),
+            _ => {}
+        }
+        for member in &node.members {
+            lines.push(format!("    {} : +{}", id, member));
+        }
+    }
+    for edge in &graph.edges {
+        let (from, to) = (node_id(diagram, edge.from), node_id(diagram, edge.to));
+        lines.push(match edge.kind {
+            EdgeKind::Implements => format!("    {} <|.. {}", to, from),
+            _ => format!("    {} --> {}", from, to),
+        });
+    }
+    lines.join("\n")
+}
+
+/// `text` as a quoted Mermaid label, with the characters Mermaid would read
+/// as syntax replaced by entity codes.
+fn label(text: &str) -> String {
+    let escaped = text
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{CrossReference, ReferenceContext, ReferenceType};
+
+    /// A project with the generic struct `net::Map<K, V>` and the function
+    /// `net::tcp::connect`, calling `run`.
+    fn project() -> ProjectAst {
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        let elements = &mut project.files[0].elements;
+        for (name, element_type, module_path) in [
+            ("Map<K, V>", ElementType::Struct, "crate::net"),
+            ("connect", ElementType::Function, "crate::net::tcp"),
+            ("run", ElementType::Function, "crate"),
+        ] {
+            let mut element = MockDataGenerator::code_element(name, element_type);
+            element.hierarchy.module_path = module_path.to_string();
+            element.hierarchy.qualified_name = format!("{}::{}", module_path, name);
+            elements.push(element);
+        }
+        let mut call = CrossReference::new(
+            elements[1].id.clone(),
+            ReferenceType::FunctionCall,
+            "run".to_string(),
+            elements[1].location.clone(),
+            ReferenceContext::new(false, String::new()),
+        );
+        call.resolve_to(elements[2].id.clone());
+        project.cross_references = vec![call];
+        project
+    }
+
+    #[test]
+    fn test_labels_are_escaped() {
+        assert_eq!(label("crate::net"), "\"crate::net\"");
+        assert_eq!(label("Map<K, V>"), "\"Map#lt;K, V#gt;\"");
+        assert_eq!(label("say \"hi\""), "\"say #quot;hi#quot;\"");
+    }
+
+    #[test]
+    fn test_node_ids() {
+        // IDs are the diagram's letter and an index, whatever the labels hold.
+        let diagram = mermaid_diagram(&project(), Diagram::StructRelationships);
+        assert_eq!(diagram, "classDiagram\n    class t0[\"Map#lt;K, V#gt;\"]");
+        assert_eq!(node_id(Diagram::CallGraph, 3), "f3");
+        assert_eq!(node_id(Diagram::ModuleImports, 0), "i0");
+    }
+
+    #[test]
+    fn test_module_hierarchy() {
+        assert_eq!(
+            mermaid_diagram(&project(), Diagram::ModuleHierarchy),
+            "flowchart TD\n    m0[\"crate\"]\n    m1[\"net\"]\n    m2[\"tcp\"]\n    \
+             m0 --> m1\n    m1 --> m2"
+        );
+    }
+
+    #[test]
+    fn test_call_graph() {
+        let mut project = project();
+        let connect = &mut project.files[0].elements[1];
+        connect.complexity_metrics.as_mut().unwrap().cyclomatic = 30;
+        assert_eq!(
+            mermaid_diagram(&project, Diagram::CallGraph),
+            "flowchart LR\n    f0[\"crate::net::tcp::connect\"]\n    f1[\"crate::run\"]\n    \
+             f0 --> f1\n    classDef very_high fill:#ffa8a8\n    class f0 very_high"
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let project = project();
+        let markdown = mermaid_sections(&project, &[Diagram::CallGraph], 3);
+        assert!(markdown.starts_with("### "));
+        assert!(markdown.contains("\n\n```mermaid\nflowchart LR\n"));
+        assert!(markdown.ends_with("```\n\n"));
+
+        let empty = MockDataGenerator::project_ast(1, 0);
+        let markdown = mermaid_sections(&empty, &[Diagram::CallGraph], 2);
+        assert!(markdown.ends_with("\n\n*Nothing to show.*\n\n"));
+    }
+}
@@ -0,0 +1,455 @@
+//! A one-page overview of a project, for the system prompt of a language
+//! model asked about the project.
+//!
+//! [`format_as_overview`] renders Markdown in sections:
+//!
+//! - the purpose of the project, from the first paragraph of its README and
+//!   the crate docs (`//!`) of its library and binary
+//! - the crates of a workspace, with their docs and the members they use
+//! - the key modules, those with the most public items
+//! - the main types, those referenced most and with the most methods
+//! - the entry points: `main` functions, items exported from library roots
+//!   and procedural macros
+//! - the direct dependencies, with the purposes of well-known crates
+//! - the metrics of the project and its documentation coverage
+//!
+//! Sections list their most important entries only, so the overview stays
+//! short for large projects.
+
+use crate::api_surface::api_surface;
+use crate::coverage::doc_coverage;
+use crate::formatters::code_list;
+use crate::mdbook::{chapter_title, crate_path_name};
+use anyhow::Result;
+use rustex_core::{CodeElement, ElementType, FileAst, ProjectAst, Visibility};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Most modules, types and entry points listed.
+const MAX_MODULES: usize = 12;
+const MAX_TYPES: usize = 15;
+const MAX_ENTRY_POINTS: usize = 20;
+
+/// README file names, in the order they're looked for.
+const README_NAMES: &[&str] = &["README.md", "README", "readme.md", "Readme.md"];
+
+/// Purposes of well-known crates, highlighted among the dependencies.
+const KNOWN_CRATES: &[(&str, &str)] = &[
+    ("actix-web", "web framework"),
+    ("anyhow", "error handling"),
+    ("async-trait", "async functions in traits"),
+    ("axum", "web framework"),
+    ("bytes", "byte buffers"),
+    ("chrono", "dates and times"),
+    ("clap", "command-line parsing"),
+    ("crossbeam", "concurrency primitives"),
+    ("diesel", "SQL ORM"),
+    ("futures", "async utilities"),
+    ("hyper", "HTTP"),
+    ("log", "logging"),
+    ("rayon", "data parallelism"),
+    ("regex", "regular expressions"),
+    ("reqwest", "HTTP client"),
+    ("serde", "serialization"),
+    ("serde_json", "JSON"),
+    ("sqlx", "SQL database access"),
+    ("syn", "Rust parsing"),
+    ("thiserror", "error types"),
+    ("tokio", "async runtime"),
+    ("toml", "TOML"),
+    ("tonic", "gRPC"),
+    ("tracing", "instrumentation"),
+    ("ureq", "HTTP client"),
+    ("uuid", "UUIDs"),
+];
+
+/// Render the overview of `project_ast` as Markdown.
+pub fn format_as_overview(project_ast: &ProjectAst) -> Result<String> {
+    let project = &project_ast.project;
+    let mut markdown = format!("# {} Overview\n\n", project.name);
+    markdown.push_str(&format!(
+        "**Version:** {} | **Rust Edition:** {}\n\n",
+        project.version, project.rust_edition
+    ));
+
+    let purpose = purpose(project_ast);
+    if !purpose.is_empty() {
+        markdown.push_str("## Purpose\n\n");
+        for paragraph in purpose {
+            markdown.push_str(&format!("{}\n\n", paragraph));
+        }
+    }
+    if let Some(workspace) = &project_ast.workspace {
+        markdown.push_str("## Crates\n\n");
+        for crate_ast in &workspace.crates {
+            let mut line = format!(
+                "- **{}** (`{}`, {} files, {} lines)",
+                crate_ast.name,
+                crate_ast.relative_path.display(),
+                crate_ast.metrics.total_files,
+                crate_ast.metrics.total_lines
+            );
+            if let Some(doc) = crate_docs(project_ast, &crate_ast.relative_path).first() {
+                line = format!("{}: {}", line, first_sentence(doc));
+            }
+            let uses: Vec<&str> = workspace
+                .dependency_edges
+                .iter()
+                .filter(|edge| edge.from == crate_ast.name && edge.kind == "normal")
+                .map(|edge| edge.to.as_str())
+                .collect();
+            if !uses.is_empty() {
+                line = format!("{} Uses {}.", line, code_list(&uses));
+            }
+            markdown.push_str(&format!("{}\n", line));
+        }
+        markdown.push('\n');
+    }
+    write_modules(&mut markdown, project_ast);
+    write_types(&mut markdown, project_ast);
+    write_entry_points(&mut markdown, project_ast);
+    write_dependencies(&mut markdown, project_ast);
+    write_metrics(&mut markdown, project_ast);
+    Ok(markdown)
+}
+
+/// The first paragraph of the README, then those of the crate docs that
+/// the README doesn't repeat.
+fn purpose(project_ast: &ProjectAst) -> Vec<String> {
+    let root = &project_ast.project.root_path;
+    let readme = README_NAMES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(root.join(name)).ok())
+        .and_then(|readme| readme_paragraph(&readme));
+    let mut paragraphs: Vec<String> = readme.into_iter().collect();
+    // A workspace describes its crates in their own section.
+    if project_ast.workspace.is_none() {
+        for doc in crate_docs(project_ast, Path::new("")) {
+            if !paragraphs.contains(&doc) {
+                paragraphs.push(doc);
+            }
+        }
+    }
+    paragraphs
+}
+
+/// The first paragraph of prose in `readme`, skipping headings, badges,
+/// HTML and code blocks.
+fn readme_paragraph(readme: &str) -> Option<String> {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_code = false;
+    for line in readme.lines().map(str::trim) {
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        let is_prose = !in_code
+            && !line.is_empty()
+            && !line.starts_with('#')
+            && !line.starts_with('<')
+            && !line.starts_with("[![")
+            && !line.starts_with("![")
+            && !line.starts_with("---")
+            && !line.starts_with("===");
+        if is_prose {
+            lines.push(line);
+        } else if !lines.is_empty() {
+            break;
+        }
+    }
+    Some(lines.join(" ")).filter(|paragraph| !paragraph.is_empty())
+}
+
+/// The first paragraphs of the crate docs of the library and the binary of
+/// the crate at `crate_root`, relative to the project root.
+fn crate_docs(project_ast: &ProjectAst, crate_root: &Path) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    for root_file in ["src/lib.rs", "src/main.rs"] {
+        let path = crate_root.join(root_file);
+        let Some(file) = project_ast
+            .files
+            .iter()
+            .find(|file| file.relative_path == path)
+        else {
+            continue;
+        };
+        if let Some(paragraph) = inner_docs(file) {
+            paragraphs.push(paragraph);
+        }
+    }
+    paragraphs
+}
+
+/// The first paragraph of the inner docs (`//!`) at the start of `file`.
+fn inner_docs(file: &FileAst) -> Option<String> {
+    let source = std::fs::read_to_string(&file.path).ok()?;
+    let mut lines: Vec<&str> = Vec::new();
+    for line in source.lines().map(str::trim) {
+        let Some(doc) = line.strip_prefix("//!") else {
+            if line.is_empty() && lines.is_empty() {
+                continue;
+            }
+            break;
+        };
+        let doc = doc.trim();
+        // Crate docs often start with the crate's name as their heading.
+        if doc.starts_with('#') && lines.is_empty() {
+            continue;
+        }
+        if doc.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        lines.push(doc);
+    }
+    Some(lines.join(" ")).filter(|paragraph| !paragraph.is_empty())
+}
+
+/// The modules with the most public items, with the first line of their
+/// docs.
+fn write_modules(markdown: &mut String, project_ast: &ProjectAst) {
+    // Public and other items, and docs of each module.
+    let mut modules: BTreeMap<String, (usize, usize, Option<String>)> = BTreeMap::new();
+    for file in &project_ast.files {
+        let file_docs = inner_docs(file);
+        for element in &file.elements {
+            let module_path = &element.hierarchy.module_path;
+            if module_path.ends_with("::tests") {
+                continue;
+            }
+            let title = chapter_title(&crate_path_name(element), module_path);
+            let module = modules.entry(title).or_default();
+            if element.hierarchy.parent_id.is_none() && module.2.is_none() {
+                module.2 = file_docs.clone();
+            }
+            match element.element_type {
+                ElementType::Impl => {}
+                ElementType::Module => {
+                    let title =
+                        chapter_title(&crate_path_name(element), &element.hierarchy.qualified_name);
+                    let module = modules.entry(title).or_default();
+                    if let Some(doc) = first_doc_line(element) {
+                        module.2 = Some(doc.to_string());
+                    }
+                }
+                _ if element.visibility == Visibility::Public => module.0 += 1,
+                _ => module.1 += 1,
+            }
+        }
+    }
+    let mut modules: Vec<_> = modules
+        .into_iter()
+        .filter(|(_, (public, private, _))| public + private > 0)
+        .collect();
+    modules.sort_by(|(a_name, a), (b_name, b)| {
+        (b.0, b.1).cmp(&(a.0, a.1)).then_with(|| a_name.cmp(b_name))
+    });
+    if modules.is_empty() {
+        return;
+    }
+
+    markdown.push_str("## Key Modules\n\n");
+    for (name, (public, private, docs)) in modules.iter().take(MAX_MODULES) {
+        let mut line = format!(
+            "- `{}` ({} items, {} public)",
+            name,
+            public + private,
+            public
+        );
+        if let Some(docs) = docs {
+            line = format!("{}: {}", line, first_sentence(docs));
+        }
+        markdown.push_str(&format!("{}\n", line));
+    }
+    write_more(markdown, modules.len(), MAX_MODULES, "modules");
+}
+
+/// The types referenced most and with the most methods, public types first.
+fn write_types(markdown: &mut String, project_ast: &ProjectAst) {
+    let mut methods: HashMap<&str, usize> = HashMap::new();
+    for type_methods in project_ast.type_index.values() {
+        if let Some(id) = &type_methods.type_element_id {
+            let count = type_methods.inherent_methods.len()
+                + type_methods
+                    .trait_methods
+                    .values()
+                    .map(Vec::len)
+                    .sum::<usize>();
+            *methods.entry(id).or_default() += count;
+        }
+    }
+    let referenced_by = &project_ast.reference_graph.referenced_by;
+
+    // Types with their references and methods.
+    let mut types: Vec<(&CodeElement, usize, usize)> = project_ast
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .filter(|element| {
+            matches!(
+                element.element_type,
+                ElementType::Struct | ElementType::Enum | ElementType::Union | ElementType::Trait
+            )
+        })
+        .map(|element| {
+            let references = referenced_by.get(&element.id).map_or(0, Vec::len);
+            let methods = methods.get(element.id.as_str()).copied().unwrap_or(0);
+            (element, references, methods)
+        })
+        .collect();
+    types.sort_by(
+        |(a, a_references, a_methods), (b, b_references, b_methods)| {
+            let a_public = a.visibility == Visibility::Public;
+            let b_public = b.visibility == Visibility::Public;
+            (b_public, b_references + b_methods)
+                .cmp(&(a_public, a_references + a_methods))
+                .then_with(|| a.hierarchy.qualified_name.cmp(&b.hierarchy.qualified_name))
+        },
+    );
+    if types.is_empty() {
+        return;
+    }
+
+    markdown.push_str("## Main Types\n\n");
+    for (element, references, methods) in types.iter().take(MAX_TYPES) {
+        let name = chapter_title(&crate_path_name(element), &element.hierarchy.qualified_name);
+        let mut line = format!(
+            "- `{}` ({:?}, {} methods, {} references)",
+            name, element.element_type, methods, references
+        );
+        if let Some(doc) = first_doc_line(element) {
+            line = format!("{}: {}", line, first_sentence(doc));
+        }
+        markdown.push_str(&format!("{}\n", line));
+    }
+    write_more(markdown, types.len(), MAX_TYPES, "types");
+}
+
+/// The entry points of the project's crates, `main` functions first.
+fn write_entry_points(markdown: &mut String, project_ast: &ProjectAst) {
+    let mut entry_points = api_surface(project_ast).entry_points;
+    entry_points.sort_by_key(|entry_point| !entry_point.ends_with("::main"));
+    if entry_points.is_empty() {
+        return;
+    }
+    markdown.push_str("## Entry Points\n\n");
+    for entry_point in entry_points.iter().take(MAX_ENTRY_POINTS) {
+        markdown.push_str(&format!("- `{}`\n", entry_point));
+    }
+    write_more(
+        markdown,
+        entry_points.len(),
+        MAX_ENTRY_POINTS,
+        "entry points",
+    );
+}
+
+/// The direct dependencies, well-known crates first with their purposes.
+fn write_dependencies(markdown: &mut String, project_ast: &ProjectAst) {
+    let dependencies = &project_ast.dependencies;
+    if dependencies.direct.is_empty() && dependencies.dev_dependencies.is_empty() {
+        return;
+    }
+    markdown.push_str("## Dependencies\n\n");
+    let (known, others): (Vec<&String>, Vec<&String>) = dependencies
+        .direct
+        .iter()
+        .partition(|name| purpose_of(name).is_some());
+    for name in &known {
+        let purpose = purpose_of(name).unwrap_or_default();
+        markdown.push_str(&format!("- `{}`: {}\n", name, purpose));
+    }
+    if !others.is_empty() {
+        let label = if known.is_empty() { "Direct" } else { "Also" };
+        markdown.push_str(&format!("- {}: {}\n", label, code_list(&others)));
+    }
+    if !dependencies.dev_dependencies.is_empty() {
+        markdown.push_str(&format!(
+            "- Development: {}\n",
+            code_list(&dependencies.dev_dependencies)
+        ));
+    }
+    markdown.push_str(&format!(
+        "- {} transitive dependencies\n\n",
+        dependencies.transitive.len()
+    ));
+}
+
+/// The metrics of the project and the documentation coverage of its public
+/// items.
+fn write_metrics(markdown: &mut String, project_ast: &ProjectAst) {
+    let metrics = &project_ast.metrics;
+    let coverage = doc_coverage(project_ast);
+    markdown.push_str("## Metrics\n\n");
+    markdown.push_str(&format!(
+        "- {} files, {} lines\n",
+        metrics.total_files, metrics.total_lines
+    ));
+    markdown.push_str(&format!(
+        "- {} functions, {} structs, {} enums, {} traits\n",
+        metrics.total_functions, metrics.total_structs, metrics.total_enums, metrics.total_traits
+    ));
+    markdown.push_str(&format!(
+        "- Complexity: {:.2} average, {} maximum\n",
+        metrics.complexity_average, metrics.complexity_max
+    ));
+    markdown.push_str(&format!(
+        "- Documentation: {:.1}% of {} public items\n",
+        coverage.total.coverage_percent, coverage.total.public_items
+    ));
+}
+
+/// A line noting the entries of a section left out after the first `max`.
+fn write_more(markdown: &mut String, count: usize, max: usize, what: &str) {
+    if count > max {
+        markdown.push_str(&format!("- ... and {} more {}\n", count - max, what));
+    }
+    markdown.push('\n');
+}
+
+/// The purpose of the well-known crate `name`.
+fn purpose_of(name: &str) -> Option<&'static str> {
+    KNOWN_CRATES
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, purpose)| *purpose)
+}
+
+/// The first non-empty line of `element`'s docs.
+fn first_doc_line(element: &CodeElement) -> Option<&str> {
+    element
+        .doc_comments
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+}
+
+/// The first sentence of `text`, or all of it without one.
+fn first_sentence(text: &str) -> &str {
+    text.find(". ").map_or(text, |end| &text[..=end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    #[test]
+    fn test_paths_use_crate_identifiers() {
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        let mut config = MockDataGenerator::code_element("Config", ElementType::Struct);
+        config.id = "my-crate::Struct_Config".to_string();
+        config.hierarchy.module_path = "crate::config".to_string();
+        config.hierarchy.qualified_name = "crate::config::Config".to_string();
+        config.hierarchy.namespace.exported_paths = vec!["crate::Config".to_string()];
+        project.files[0].elements.push(config);
+
+        let overview = format_as_overview(&project).unwrap();
+        assert!(overview.contains("- `my_crate::config` (1 items, 1 public)"));
+        assert!(overview.contains("- `my_crate::config::Config` (Struct, 0 methods"));
+        assert!(overview.contains("## Entry Points\n\n- `my_crate::Config`\n"));
+        assert!(!overview.contains("my-crate::"));
+    }
+}
@@ -0,0 +1,685 @@
+//! Detection of Rust design patterns and idioms for RAG documents:
+//!
+//! - Builder: types with a `build` method and methods taking and returning
+//!   `Self`
+//! - Newtype: tuple structs of one field
+//! - Typestate: generic structs with impls for two unit structs or more as
+//!   the state
+//! - Interior mutability: structs with `Cell`, `RefCell`, `Mutex`, `RwLock`
+//!   or atomic fields
+//! - RAII guard: types implementing `Drop`
+//! - Error enum: enums implementing `Error`, or named `*Error`
+//!
+//! Only elements with a chunk in the document count, and each pattern links
+//! to the chunks of its occurrences. Fields of structs are read from their
+//! source, so structs of files changed since extraction are left out.
+
+use crate::rag::{CodePattern, PatternType};
+use crate::semantics::{count, ChunkIds};
+use crate::training::last_segment;
+use rustex_core::{CodeElement, ElementType, ProjectAst};
+use std::collections::{HashMap, HashSet};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+/// Most examples of a pattern.
+const MAX_EXAMPLES: usize = 5;
+
+/// Types whose values can change behind a shared reference.
+const INTERIOR_MUTABILITY_TYPES: &[&str] = &[
+    "Cell",
+    "RefCell",
+    "OnceCell",
+    "UnsafeCell",
+    "Mutex",
+    "RwLock",
+    "OnceLock",
+];
+
+/// An occurrence of a pattern.
+struct Occurrence {
+    /// Chunks of the elements making up the occurrence
+    chunks: Vec<String>,
+    /// A sentence on the occurrence, naming its elements
+    example: String,
+}
+
+/// A struct and its fields, parsed from its source.
+struct StructDefinition<'a> {
+    element: &'a CodeElement,
+    item: syn::ItemStruct,
+    /// The source `item` was parsed from, which its spans point into
+    text: String,
+}
+
+impl StructDefinition<'_> {
+    /// The type parameters of the struct.
+    fn type_parameters(&self) -> Vec<String> {
+        let generics = self.item.generics.type_params();
+        generics
+            .map(|parameter| parameter.ident.to_string())
+            .collect()
+    }
+
+    /// The name of each field, its index for tuple structs, with its type
+    /// as written.
+    fn fields(&self) -> Vec<(String, &syn::Type, &str)> {
+        self.item
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let name = match &field.ident {
+                    Some(ident) => ident.to_string(),
+                    None => index.to_string(),
+                };
+                let text = self.text.get(field.ty.span().byte_range()).unwrap_or("_");
+                (name, &field.ty, text)
+            })
+            .collect()
+    }
+}
+
+/// The patterns occurring in `project_ast`, in the order of the list of
+/// this module.
+pub(crate) fn code_patterns(project_ast: &ProjectAst, chunk_ids: &ChunkIds) -> Vec<CodePattern> {
+    let elements: HashMap<&str, &CodeElement> = project_ast
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .map(|element| (element.id.as_str(), element))
+        .collect();
+    let structs = struct_definitions(project_ast, chunk_ids);
+
+    let detected = [
+        (
+            "builder",
+            "Builder",
+            PatternType::DesignPattern,
+            "Types making a value with `build` after chained methods set it up",
+            builders(project_ast, &elements, chunk_ids),
+        ),
+        (
+            "newtype",
+            "Newtype",
+            PatternType::Idiom,
+            "Tuple structs wrapping a single value in a type of its own",
+            newtypes(&structs, chunk_ids),
+        ),
+        (
+            "typestate",
+            "Typestate",
+            PatternType::DesignPattern,
+            "Generic types whose parameter is their state, with methods only in some states",
+            typestates(project_ast, &elements, &structs, chunk_ids),
+        ),
+        (
+            "interior_mutability",
+            "Interior mutability",
+            PatternType::Idiom,
+            "Structs with fields that can change behind a shared reference",
+            interior_mutability(&structs, chunk_ids),
+        ),
+        (
+            "raii_guard",
+            "RAII guard",
+            PatternType::Idiom,
+            "Types releasing what they hold when they go out of scope, with a `Drop` impl",
+            raii_guards(project_ast, &elements, chunk_ids),
+        ),
+        (
+            "error_enum",
+            "Error enum",
+            PatternType::BestPractice,
+            "Enums with a variant for each way an operation can fail",
+            error_enums(project_ast, chunk_ids),
+        ),
+    ];
+
+    detected
+        .into_iter()
+        .filter(|(.., occurrences)| !occurrences.is_empty())
+        .map(|(id, name, pattern_type, description, occurrences)| {
+            let mut related_chunks: Vec<String> = Vec::new();
+            for chunk in occurrences.iter().flat_map(|occurrence| &occurrence.chunks) {
+                if !related_chunks.contains(chunk) {
+                    related_chunks.push(chunk.clone());
+                }
+            }
+            CodePattern {
+                id: id.to_string(),
+                name: name.to_string(),
+                description: description.to_string(),
+                pattern_type,
+                examples: occurrences
+                    .iter()
+                    .take(MAX_EXAMPLES)
+                    .map(|occurrence| occurrence.example.clone())
+                    .collect(),
+                related_chunks,
+                frequency: occurrences.len(),
+            }
+        })
+        .collect()
+}
+
+/// The structs of `project_ast` with a chunk, parsed from the source of
+/// their files.
+fn struct_definitions<'a>(
+    project_ast: &'a ProjectAst,
+    chunk_ids: &ChunkIds,
+) -> Vec<StructDefinition<'a>> {
+    let mut structs = Vec::new();
+    for file in &project_ast.files {
+        let mut elements = file.elements.iter().filter(|element| {
+            element.element_type == ElementType::Struct
+                && chunk_ids.contains_key(element.id.as_str())
+        });
+        let Some(first) = elements.next() else {
+            continue;
+        };
+        let Ok(source) = std::fs::read_to_string(&file.path) else {
+            continue;
+        };
+        for element in std::iter::once(first).chain(elements) {
+            let location = &element.location;
+            let Some(text) = source.get(location.byte_start..location.byte_end) else {
+                continue;
+            };
+            if let Ok(item) = syn::parse_str::<syn::ItemStruct>(text) {
+                structs.push(StructDefinition {
+                    element,
+                    item,
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+    structs
+}
+
+/// Types with a `build` method taking `self`, and methods taking `self` and
+/// returning `Self` to set them up.
+fn builders(
+    project_ast: &ProjectAst,
+    elements: &HashMap<&str, &CodeElement>,
+    chunk_ids: &ChunkIds,
+) -> Vec<Occurrence> {
+    let mut occurrences = Vec::new();
+    for methods in project_ast.type_index.values() {
+        let Some(type_chunk) = methods
+            .type_element_id
+            .as_deref()
+            .and_then(|id| chunk_ids.get(id))
+        else {
+            continue;
+        };
+        let methods: Vec<(&CodeElement, String)> = methods
+            .inherent_methods
+            .iter()
+            .filter_map(|id| elements.get(id.as_str()))
+            .filter_map(|method| Some((*method, compact(method.signature.as_deref()?))))
+            .filter(|(_, signature)| takes_self(signature))
+            .collect();
+        let build = methods.iter().find(|(method, _)| method.name == "build");
+        let setters: Vec<&str> = methods
+            .iter()
+            .filter(|(_, signature)| {
+                signature.ends_with("->Self") || signature.ends_with("->&mutSelf")
+            })
+            .map(|(method, _)| method.name.as_str())
+            .collect();
+        let (Some((build, signature)), false) = (build, setters.is_empty()) else {
+            continue;
+        };
+        let builder = build
+            .hierarchy
+            .impl_context
+            .as_ref()
+            .map_or("_", |context| last_segment(&context.self_type));
+        let built = signature
+            .rsplit_once("->")
+            .map_or("()", |(_, output)| output);
+        let mut chunks = vec![type_chunk.clone()];
+        chunks.extend(chunk_ids.get(build.id.as_str()).cloned());
+        occurrences.push(Occurrence {
+            chunks,
+            example: format!(
+                "`{}` makes `{}` with `build`, set up by {}",
+                builder,
+                built,
+                code_list(&setters)
+            ),
+        });
+    }
+    occurrences
+}
+
+/// Tuple structs of a single field.
+fn newtypes(structs: &[StructDefinition], chunk_ids: &ChunkIds) -> Vec<Occurrence> {
+    structs
+        .iter()
+        .filter(|definition| matches!(definition.item.fields, syn::Fields::Unnamed(_)))
+        .filter_map(|definition| {
+            let [(_, _, wrapped)] = definition.fields()[..] else {
+                return None;
+            };
+            Some(Occurrence {
+                chunks: vec![chunk_ids[definition.element.id.as_str()].clone()],
+                example: format!("`{}` wraps `{}`", definition.element.name, wrapped),
+            })
+        })
+        .collect()
+}
+
+/// Generic structs with a field of a type parameter, such as
+/// `PhantomData<S>`, and impls for two unit structs or more as that
+/// parameter.
+fn typestates(
+    project_ast: &ProjectAst,
+    elements: &HashMap<&str, &CodeElement>,
+    structs: &[StructDefinition],
+    chunk_ids: &ChunkIds,
+) -> Vec<Occurrence> {
+    let unit_structs: HashSet<&str> = structs
+        .iter()
+        .filter(|definition| matches!(definition.item.fields, syn::Fields::Unit))
+        .map(|definition| definition.element.name.as_str())
+        .collect();
+    let mut occurrences = Vec::new();
+    for definition in structs {
+        let parameters = definition.type_parameters();
+        let has_state = definition
+            .fields()
+            .iter()
+            .any(|(_, ty, _)| type_names(ty).iter().any(|name| parameters.contains(name)));
+        let methods = project_ast
+            .type_index
+            .get(&definition.element.hierarchy.qualified_name);
+        let (true, Some(methods)) = (has_state, methods) else {
+            continue;
+        };
+        // Impls of a state are named like `impl Door < Open >`.
+        let mut states = Vec::new();
+        let mut chunks = vec![chunk_ids[definition.element.id.as_str()].clone()];
+        for impl_element in methods
+            .impl_ids
+            .iter()
+            .filter_map(|id| elements.get(id.as_str()))
+        {
+            let state = impl_element
+                .name
+                .split_once('<')
+                .and_then(|(_, arguments)| arguments.rsplit_once('>'))
+                .map(|(arguments, _)| arguments.trim());
+            let Some(state) = state.filter(|state| unit_structs.contains(state)) else {
+                continue;
+            };
+            if impl_element.generic_params.is_empty() && !states.contains(&state) {
+                states.push(state);
+                chunks.extend(chunk_ids.get(impl_element.id.as_str()).cloned());
+            }
+        }
+        if states.len() >= 2 {
+            occurrences.push(Occurrence {
+                chunks,
+                example: format!(
+                    "`{}` has methods in states {}",
+                    definition.element.name,
+                    code_list(&states)
+                ),
+            });
+        }
+    }
+    occurrences
+}
+
+/// Structs with fields of [`INTERIOR_MUTABILITY_TYPES`] or atomics.
+fn interior_mutability(structs: &[StructDefinition], chunk_ids: &ChunkIds) -> Vec<Occurrence> {
+    let mut occurrences = Vec::new();
+    for definition in structs {
+        let fields: Vec<String> = definition
+            .fields()
+            .into_iter()
+            .filter_map(|(name, ty, _)| {
+                let cell = type_names(ty).into_iter().find(|name| {
+                    INTERIOR_MUTABILITY_TYPES.contains(&name.as_str()) || name.starts_with("Atomic")
+                })?;
+                Some(format!("`{}` in a `{}`", name, cell))
+            })
+            .collect();
+        if !fields.is_empty() {
+            occurrences.push(Occurrence {
+                chunks: vec![chunk_ids[definition.element.id.as_str()].clone()],
+                example: format!("`{}` keeps {}", definition.element.name, fields.join(", ")),
+            });
+        }
+    }
+    occurrences
+}
+
+/// Types implementing `Drop`.
+fn raii_guards(
+    project_ast: &ProjectAst,
+    elements: &HashMap<&str, &CodeElement>,
+    chunk_ids: &ChunkIds,
+) -> Vec<Occurrence> {
+    project_ast
+        .impls
+        .iter()
+        .filter(|trait_impl| last_segment(&trait_impl.trait_path) == "Drop")
+        .filter_map(|trait_impl| {
+            let type_id = trait_impl.type_element_id.as_deref()?;
+            let mut chunks = vec![chunk_ids.get(type_id)?.clone()];
+            chunks.extend(chunk_ids.get(trait_impl.impl_id.as_str()).cloned());
+            Some(Occurrence {
+                chunks,
+                example: format!(
+                    "`{}` releases what it holds in `Drop::drop`",
+                    elements[type_id].name
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Enums implementing or deriving `Error`, or named `*Error`.
+fn error_enums(project_ast: &ProjectAst, chunk_ids: &ChunkIds) -> Vec<Occurrence> {
+    let mut traits: HashMap<&str, Vec<&str>> = HashMap::new();
+    for trait_impl in &project_ast.impls {
+        if let Some(type_id) = &trait_impl.type_element_id {
+            let implemented = traits.entry(type_id.as_str()).or_default();
+            implemented.push(last_segment(&trait_impl.trait_path));
+        }
+    }
+    let mut occurrences = Vec::new();
+    let enums = project_ast
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .filter(|element| element.element_type == ElementType::Enum);
+    for element in enums {
+        let Some(chunk) = chunk_ids.get(element.id.as_str()) else {
+            continue;
+        };
+        let mut implemented = traits.get(element.id.as_str()).cloned().unwrap_or_default();
+        let derived = element
+            .attribute_info
+            .iter()
+            .flat_map(|attribute| &attribute.derived_traits)
+            .map(|derived| last_segment(derived));
+        implemented.extend(derived);
+        let is_error = implemented.contains(&"Error");
+        if !is_error && !element.name.ends_with("Error") {
+            continue;
+        }
+        let variants = element
+            .metadata
+            .get("variants")
+            .and_then(|variants| variants.as_array())
+            .map_or(0, Vec::len);
+        implemented.retain(|name| ["Display", "Error", "From"].contains(name));
+        implemented.sort_unstable();
+        implemented.dedup();
+        let mut example = format!("`{}` with {}", element.name, count(variants, "variant"));
+        if !implemented.is_empty() {
+            example.push_str(&format!(", implementing {}", code_list(&implemented)));
+        }
+        occurrences.push(Occurrence {
+            chunks: vec![chunk.clone()],
+            example,
+        });
+    }
+    occurrences
+}
+
+/// The names of the types in `ty`, such as `Mutex` and `u64` in
+/// `Mutex<u64>`.
+fn type_names(ty: &syn::Type) -> Vec<String> {
+    #[derive(Default)]
+    struct Names(Vec<String>);
+
+    impl<'ast> Visit<'ast> for Names {
+        fn visit_path_segment(&mut self, segment: &'ast syn::PathSegment) {
+            self.0.push(segment.ident.to_string());
+            syn::visit::visit_path_segment(self, segment);
+        }
+    }
+
+    let mut names = Names::default();
+    names.visit_type(ty);
+    names.0
+}
+
+/// `signature` without whitespace, such as `fnurl(mutself,url:&str)->Self`.
+fn compact(signature: &str) -> String {
+    signature.split_whitespace().collect()
+}
+
+/// Whether the compacted `signature` of a method takes `self`, as `self`,
+/// `mutself`, `&self`, `&mutself` or `&'aself`.
+fn takes_self(signature: &str) -> bool {
+    signature
+        .split_once('(')
+        .map(|(_, parameters)| {
+            parameters
+                .split(&[',', ')', ':'])
+                .next()
+                .unwrap_or_default()
+        })
+        .and_then(|receiver| receiver.strip_suffix("self"))
+        .map(|prefix| prefix.strip_suffix("mut").unwrap_or(prefix))
+        .is_some_and(|prefix| prefix.is_empty() || prefix == "&" || prefix.starts_with("&'"))
+}
+
+/// `names` in backticks, as in "`a`, `b` and `c`".
+fn code_list(names: &[&str]) -> String {
+    let names: Vec<String> = names.iter().map(|name| format!("`{}`", name)).collect();
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::{AstExtractor, ExtractorConfig};
+    use std::fs;
+
+    /// One occurrence of each pattern.
+    const PATTERNS: &str = r#"use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::atomic::AtomicU64;
+
+pub struct Request;
+
+pub struct RequestBuilder {
+    url: String,
+}
+
+impl RequestBuilder {
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    pub fn build(self) -> Request {
+        Request
+    }
+}
+
+pub struct Meters(f64);
+
+pub struct Open;
+
+pub struct Closed;
+
+pub struct Door<S> {
+    state: PhantomData<S>,
+}
+
+impl Door<Open> {
+    pub fn close(self) -> Door<Closed> {
+        Door { state: PhantomData }
+    }
+}
+
+impl Door<Closed> {
+    pub fn open(self) -> Door<Open> {
+        Door { state: PhantomData }
+    }
+}
+
+pub struct Cache {
+    entries: RefCell<Vec<u8>>,
+    hits: AtomicU64,
+}
+
+pub struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+
+pub enum ParseError {
+    Empty,
+    Invalid(String),
+}
+"#;
+
+    /// Near misses of each pattern.
+    const NEAR_MISSES: &str = r#"use std::marker::PhantomData;
+
+pub struct Request;
+
+pub struct Factory;
+
+impl Factory {
+    pub fn build(&self) -> Request {
+        Request
+    }
+}
+
+pub struct Point(f64, f64);
+
+pub struct Open;
+
+pub struct Door<S> {
+    state: PhantomData<S>,
+}
+
+impl Door<Open> {
+    pub fn close(self) {}
+}
+
+pub struct Buffer {
+    bytes: Vec<u8>,
+}
+
+pub struct Token;
+
+impl Clone for Token {
+    fn clone(&self) -> Self {
+        Token
+    }
+}
+
+pub enum Direction {
+    Up,
+    Down,
+}
+"#;
+
+    /// The patterns of a crate with `source` as its root, with a chunk for
+    /// each element, named by its ID.
+    fn patterns(source: &str) -> Vec<CodePattern> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), source).unwrap();
+        let config = ExtractorConfig {
+            include_private: true,
+            ..ExtractorConfig::default()
+        };
+        let project = AstExtractor::new(config, root.to_path_buf())
+            .extract_project()
+            .unwrap();
+        let chunk_ids: ChunkIds = project
+            .files
+            .iter()
+            .flat_map(|file| &file.elements)
+            .map(|element| (element.id.as_str(), element.id.clone()))
+            .collect();
+        code_patterns(&project, &chunk_ids)
+    }
+
+    #[test]
+    fn test_patterns() {
+        let patterns = patterns(PATTERNS);
+        let found: Vec<(&str, usize, &str)> = patterns
+            .iter()
+            .map(|pattern| {
+                (
+                    pattern.id.as_str(),
+                    pattern.frequency,
+                    pattern.examples[0].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "builder",
+                    1,
+                    "`RequestBuilder` makes `Request` with `build`, set up by `url`"
+                ),
+                ("newtype", 1, "`Meters` wraps `f64`"),
+                (
+                    "typestate",
+                    1,
+                    "`Door` has methods in states `Open` and `Closed`"
+                ),
+                (
+                    "interior_mutability",
+                    1,
+                    "`Cache` keeps `entries` in a `RefCell`, `hits` in a `AtomicU64`"
+                ),
+                (
+                    "raii_guard",
+                    1,
+                    "`Guard` releases what it holds in `Drop::drop`"
+                ),
+                ("error_enum", 1, "`ParseError` with 2 variants"),
+            ]
+        );
+        // The typestate links to the struct and the impl of each state.
+        assert_eq!(patterns[2].related_chunks.len(), 3);
+        assert_eq!(patterns[4].related_chunks.len(), 2);
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let ids: Vec<String> = patterns(NEAR_MISSES)
+            .into_iter()
+            .map(|pattern| pattern.id)
+            .collect();
+        assert!(ids.is_empty(), "{:?}", ids);
+    }
+
+    #[test]
+    fn test_takes_self() {
+        assert!(takes_self("fnurl(mutself,url:&str)->Self"));
+        assert!(takes_self("fnbuild(self:Box<Self>)->Request"));
+        assert!(takes_self("fnget<'a>(&'amutself)->&'amutSelf"));
+        assert!(!takes_self("fnnew(url:&str)->Self"));
+        assert!(!takes_self("fnnew(myself:u8)->Self"));
+    }
+}
@@ -0,0 +1,462 @@
+//! Protocol Buffers encoding of a project AST.
+//!
+//! [`format_as_protobuf`] writes a `rustex.v1.ProjectAst` message as defined
+//! by [`PROTO_SCHEMA`], the schema shipped in `proto/rustex.proto`. The
+//! message types in [`v1`] are generated from that schema by `prost-build`,
+//! so the encoding always follows it. Readers in any language generate their
+//! types from the same schema with `protoc`; the rules at its top keep them
+//! working with output of later rustex versions.
+
+use anyhow::Result;
+use prost::Message;
+use rustex_core::complexity::{ComplexityMetrics, HalsteadMetrics};
+use rustex_core::{
+    CallKind, CodeElement, CodeLocation, CrossReference, ElementType, FileAst, FileKind,
+    ImportInfo, ProjectAst, ProjectMetrics, ReferenceType, SkipReason, SkippedFile, TraitImpl,
+    Visibility,
+};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The schema of Protobuf output.
+pub const PROTO_SCHEMA: &str = include_str!("../proto/rustex.proto");
+
+/// Messages of the `rustex.v1` package of [`PROTO_SCHEMA`].
+#[allow(missing_docs)]
+pub mod v1 {
+    include!(concat!(env!("OUT_DIR"), "/rustex.v1.rs"));
+}
+
+/// Encode `project_ast` as a `rustex.v1.ProjectAst` message.
+pub fn format_as_protobuf(project_ast: &ProjectAst) -> Result<Vec<u8>> {
+    Ok(project(project_ast)?.encode_to_vec())
+}
+
+fn project(project_ast: &ProjectAst) -> Result<v1::ProjectAst> {
+    let project = &project_ast.project;
+    let dependencies = &project_ast.dependencies;
+    let report = &project_ast.extraction_report;
+    Ok(v1::ProjectAst {
+        project: Some(v1::ProjectInfo {
+            name: project.name.clone(),
+            version: project.version.clone(),
+            rust_edition: project.rust_edition.clone(),
+            root_path: path(&project.root_path),
+        }),
+        files: project_ast
+            .files
+            .iter()
+            .map(file_ast)
+            .collect::<Result<_>>()?,
+        dependencies: Some(v1::DependencyInfo {
+            direct: dependencies.direct.clone(),
+            transitive: dependencies.transitive.clone(),
+            dev_dependencies: dependencies.dev_dependencies.clone(),
+        }),
+        metrics: Some(project_metrics(&project_ast.metrics)),
+        extracted_at: project_ast.extracted_at.to_rfc3339(),
+        cross_references: project_ast
+            .cross_references
+            .iter()
+            .map(cross_reference)
+            .collect(),
+        workspace: project_ast
+            .workspace
+            .as_ref()
+            .map(|workspace| v1::WorkspaceAst {
+                crates: workspace
+                    .crates
+                    .iter()
+                    .map(|krate| v1::CrateAst {
+                        name: krate.name.clone(),
+                        version: krate.version.clone(),
+                        relative_path: path(&krate.relative_path),
+                        files: krate.files.iter().map(|file| path(file)).collect(),
+                        metrics: Some(project_metrics(&krate.metrics)),
+                    })
+                    .collect(),
+                dependency_edges: workspace
+                    .dependency_edges
+                    .iter()
+                    .map(|edge| v1::CrateDependency {
+                        from: edge.from.clone(),
+                        to: edge.to.clone(),
+                        kind: edge.kind.clone(),
+                    })
+                    .collect(),
+            }),
+        impls: project_ast.impls.iter().map(trait_impl).collect(),
+        extraction_report: Some(v1::ExtractionReport {
+            failed: report.failed.iter().map(skipped_file).collect(),
+            incomplete: report.incomplete.iter().map(skipped_file).collect(),
+        }),
+    })
+}
+
+fn file_ast(file: &FileAst) -> Result<v1::FileAst> {
+    let metrics = &file.file_metrics;
+    Ok(v1::FileAst {
+        path: path(&file.path),
+        relative_path: path(&file.relative_path),
+        elements: file
+            .elements
+            .iter()
+            .map(code_element)
+            .collect::<Result<_>>()?,
+        imports: file.imports.iter().map(import_info).collect(),
+        file_metrics: Some(v1::FileMetrics {
+            lines_of_code: metrics.lines_of_code as u64,
+            lines_of_comments: metrics.lines_of_comments as u64,
+            complexity_total: metrics.complexity_total,
+            function_count: metrics.function_count as u64,
+            struct_count: metrics.struct_count as u64,
+            enum_count: metrics.enum_count as u64,
+            trait_count: metrics.trait_count as u64,
+        }),
+        cross_references: file.cross_references.iter().map(cross_reference).collect(),
+        parse_errors: file
+            .parse_errors
+            .iter()
+            .map(|error| v1::ParseErrorSpan {
+                message: error.message.clone(),
+                line_start: error.line_start as u64,
+                line_end: error.line_end as u64,
+            })
+            .collect(),
+        warnings: file.warnings.clone(),
+        kind: file_kind(file.kind) as i32,
+        partially_extracted: file.partially_extracted,
+    })
+}
+
+fn code_element(element: &CodeElement) -> Result<v1::CodeElement> {
+    let (kind, visibility_path) = match &element.visibility {
+        Visibility::Public => (v1::VisibilityKind::Public, ""),
+        Visibility::Crate => (v1::VisibilityKind::Crate, ""),
+        Visibility::Super => (v1::VisibilityKind::Super, ""),
+        Visibility::Restricted(path) => (v1::VisibilityKind::Restricted, path.as_str()),
+        Visibility::Private => (v1::VisibilityKind::Private, ""),
+    };
+    let metadata_json = if element.metadata.is_empty() {
+        String::new()
+    } else {
+        // Sorted by key, so the same element always encodes the same.
+        let metadata: BTreeMap<_, _> = element.metadata.iter().collect();
+        serde_json::to_string(&metadata)?
+    };
+    let hierarchy = &element.hierarchy;
+    Ok(v1::CodeElement {
+        id: element.id.clone(),
+        element_type: element_type(&element.element_type) as i32,
+        name: element.name.clone(),
+        signature: element.signature.clone(),
+        visibility: Some(v1::Visibility {
+            kind: kind as i32,
+            path: visibility_path.to_string(),
+        }),
+        doc_comments: element.doc_comments.clone(),
+        location: Some(code_location(&element.location)),
+        attributes: element.attributes.clone(),
+        deprecation: element
+            .deprecation
+            .as_ref()
+            .map(|deprecation| v1::Deprecation {
+                since: deprecation.since.clone(),
+                note: deprecation.note.clone(),
+            }),
+        complexity: element.complexity,
+        complexity_metrics: element.complexity_metrics.as_ref().map(complexity_metrics),
+        dependencies: element.dependencies.clone(),
+        calls: element
+            .calls
+            .iter()
+            .map(|call| v1::CallSite {
+                name: call.name.clone(),
+                kind: call_kind(call.kind) as i32,
+                line: call.line as u64,
+            })
+            .collect(),
+        generic_params: element.generic_params.clone(),
+        metadata_json,
+        hierarchy: Some(v1::ElementHierarchy {
+            parent_id: hierarchy.parent_id.clone(),
+            children_ids: hierarchy.children_ids.clone(),
+            nesting_level: hierarchy.nesting_level,
+            module_path: hierarchy.module_path.clone(),
+            qualified_name: hierarchy.qualified_name.clone(),
+            canonical_path: hierarchy.namespace.canonical_path.clone(),
+            exported_paths: hierarchy.namespace.exported_paths.clone(),
+            impl_context: hierarchy
+                .impl_context
+                .as_ref()
+                .map(|context| v1::ImplContext {
+                    self_type: context.self_type.clone(),
+                    trait_path: context.trait_path.clone(),
+                    where_clause: context.where_clause.clone(),
+                }),
+            parent_element: hierarchy.parent_element.clone(),
+        }),
+        is_generated: element.is_generated,
+        name_location: element.name_location.as_ref().map(code_location),
+    })
+}
+
+fn code_location(location: &CodeLocation) -> v1::CodeLocation {
+    v1::CodeLocation {
+        line_start: location.line_start as u64,
+        line_end: location.line_end as u64,
+        char_start: location.char_start as u64,
+        char_end: location.char_end as u64,
+        byte_start: location.byte_start as u64,
+        byte_end: location.byte_end as u64,
+        file_path: path(&location.file_path),
+    }
+}
+
+fn import_info(import: &ImportInfo) -> v1::ImportInfo {
+    v1::ImportInfo {
+        module_path: import.module_path.clone(),
+        imported_items: import.imported_items.clone(),
+        is_glob: import.is_glob,
+        alias: import.alias.clone(),
+        is_reexport: import.is_reexport,
+        scope: import.scope.clone(),
+    }
+}
+
+fn complexity_metrics(metrics: &ComplexityMetrics) -> v1::ComplexityMetrics {
+    v1::ComplexityMetrics {
+        cyclomatic: metrics.cyclomatic,
+        cognitive: metrics.cognitive,
+        halstead: Some(halstead_metrics(&metrics.halstead)),
+        nesting_depth: metrics.nesting_depth,
+        lines_of_code: metrics.lines_of_code,
+        parameter_count: metrics.parameter_count,
+        return_count: metrics.return_count,
+        closure_count: metrics.closure_count,
+        async_block_count: metrics.async_block_count,
+    }
+}
+
+fn halstead_metrics(metrics: &HalsteadMetrics) -> v1::HalsteadMetrics {
+    v1::HalsteadMetrics {
+        n1: metrics.n1,
+        n2: metrics.n2,
+        big_n1: metrics.big_n1,
+        big_n2: metrics.big_n2,
+        vocabulary: metrics.vocabulary,
+        length: metrics.length,
+        calculated_length: metrics.calculated_length,
+        volume: metrics.volume,
+        difficulty: metrics.difficulty,
+        effort: metrics.effort,
+    }
+}
+
+fn project_metrics(metrics: &ProjectMetrics) -> v1::ProjectMetrics {
+    v1::ProjectMetrics {
+        total_lines: metrics.total_lines as u64,
+        total_files: metrics.total_files as u64,
+        total_functions: metrics.total_functions as u64,
+        total_structs: metrics.total_structs as u64,
+        total_enums: metrics.total_enums as u64,
+        total_traits: metrics.total_traits as u64,
+        complexity_average: metrics.complexity_average,
+        complexity_max: metrics.complexity_max,
+    }
+}
+
+fn cross_reference(reference: &CrossReference) -> v1::CrossReference {
+    v1::CrossReference {
+        from_element_id: reference.from_element_id.clone(),
+        to_element_id: reference.to_element_id.clone(),
+        reference_type: reference_type(&reference.reference_type) as i32,
+        reference_text: reference.reference_text.clone(),
+        location: Some(code_location(&reference.location)),
+        is_resolved: reference.is_resolved,
+    }
+}
+
+fn trait_impl(trait_impl: &TraitImpl) -> v1::TraitImpl {
+    v1::TraitImpl {
+        impl_id: trait_impl.impl_id.clone(),
+        trait_path: trait_impl.trait_path.clone(),
+        self_type: trait_impl.self_type.clone(),
+        trait_element_id: trait_impl.trait_element_id.clone(),
+        type_element_id: trait_impl.type_element_id.clone(),
+        generics: trait_impl.generics.clone(),
+        where_clause: trait_impl.where_clause.clone(),
+        location: Some(code_location(&trait_impl.location)),
+        is_generated: trait_impl.is_generated,
+        overridden_methods: trait_impl.overridden_methods.clone(),
+        inherited_methods: trait_impl.inherited_methods.clone(),
+    }
+}
+
+fn skipped_file(skipped: &SkippedFile) -> v1::SkippedFile {
+    let reason = match skipped.reason {
+        SkipReason::TooLarge => v1::SkipReason::TooLarge,
+        SkipReason::ParseError => v1::SkipReason::ParseError,
+        SkipReason::InvalidEncoding => v1::SkipReason::InvalidEncoding,
+        SkipReason::IoError => v1::SkipReason::IoError,
+        SkipReason::AccessDenied => v1::SkipReason::AccessDenied,
+    };
+    v1::SkippedFile {
+        file: path(&skipped.file),
+        reason: reason as i32,
+        message: skipped.message.clone(),
+    }
+}
+
+fn path(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn file_kind(kind: FileKind) -> v1::FileKind {
+    match kind {
+        FileKind::Source => v1::FileKind::Source,
+        FileKind::BuildScript => v1::FileKind::BuildScript,
+        FileKind::ProcMacro => v1::FileKind::ProcMacro,
+    }
+}
+
+fn element_type(element_type: &ElementType) -> v1::ElementType {
+    match element_type {
+        ElementType::Function => v1::ElementType::Function,
+        ElementType::Struct => v1::ElementType::Struct,
+        ElementType::Enum => v1::ElementType::Enum,
+        ElementType::Trait => v1::ElementType::Trait,
+        ElementType::Impl => v1::ElementType::Impl,
+        ElementType::Module => v1::ElementType::Module,
+        ElementType::Constant => v1::ElementType::Constant,
+        ElementType::Static => v1::ElementType::Static,
+        ElementType::TypeAlias => v1::ElementType::TypeAlias,
+        ElementType::Macro => v1::ElementType::Macro,
+        ElementType::Union => v1::ElementType::Union,
+        ElementType::Closure => v1::ElementType::Closure,
+        ElementType::AsyncBlock => v1::ElementType::AsyncBlock,
+    }
+}
+
+fn call_kind(kind: CallKind) -> v1::CallKind {
+    match kind {
+        CallKind::Function => v1::CallKind::Function,
+        CallKind::Method => v1::CallKind::Method,
+        CallKind::Macro => v1::CallKind::Macro,
+    }
+}
+
+fn reference_type(reference_type: &ReferenceType) -> v1::ReferenceType {
+    match reference_type {
+        ReferenceType::FunctionCall => v1::ReferenceType::FunctionCall,
+        ReferenceType::TypeUsage => v1::ReferenceType::TypeUsage,
+        ReferenceType::VariableAccess => v1::ReferenceType::VariableAccess,
+        ReferenceType::ModuleReference => v1::ReferenceType::ModuleReference,
+        ReferenceType::TraitImplementation => v1::ReferenceType::TraitImplementation,
+        ReferenceType::GenericUsage => v1::ReferenceType::GenericUsage,
+        ReferenceType::MacroInvocation => v1::ReferenceType::MacroInvocation,
+        ReferenceType::ImportReference => v1::ReferenceType::ImportReference,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    #[test]
+    fn test_prost_round_trip() {
+        let mut project = MockDataGenerator::project_ast(2, 2);
+        let element = &mut project.files[1].elements[1];
+        element.name = "größe".to_string();
+        element.signature = None;
+        element.complexity = Some(0);
+        element.location.line_start = 300;
+        element.location.line_end = 70_000;
+        element
+            .metadata
+            .insert("variants".to_string(), serde_json::json!(["A", "B"]));
+        element.complexity_metrics.as_mut().unwrap().halstead.volume = 12.5;
+        element.is_generated = true;
+        project.metrics.complexity_average = 2.25;
+
+        let bytes = format_as_protobuf(&project).unwrap();
+        let decoded = v1::ProjectAst::decode(bytes.as_slice()).unwrap();
+
+        let info = decoded.project.unwrap();
+        assert_eq!(info.name, project.project.name);
+        assert_eq!(info.version, "0.1.0");
+        assert_eq!(decoded.extracted_at, project.extracted_at.to_rfc3339());
+        let metrics = decoded.metrics.unwrap();
+        assert_eq!(metrics.total_files, 2);
+        assert_eq!(metrics.complexity_average, 2.25);
+        assert_eq!(metrics.complexity_max, 10);
+
+        assert_eq!(decoded.files.len(), 2);
+        let file = &decoded.files[1];
+        assert_eq!(file.relative_path, "file_1.rs");
+        assert_eq!(file.kind(), v1::FileKind::Source);
+        assert_eq!(file.elements.len(), 2);
+
+        let first = &file.elements[0];
+        assert_eq!(first.id, project.files[1].elements[0].id);
+        assert_eq!(first.element_type(), v1::ElementType::Function);
+        assert_eq!(first.signature.as_deref(), Some("fn element_0() -> ()"));
+        assert_eq!(first.doc_comments, ["Documentation for element_0"]);
+        assert_eq!(first.complexity, Some(5));
+        assert_eq!(first.metadata_json, "");
+        assert!(!first.is_generated);
+
+        let second = &file.elements[1];
+        assert_eq!(second.name, "größe");
+        assert_eq!(second.signature, None);
+        assert_eq!(second.complexity, Some(0));
+        let location = second.location.as_ref().unwrap();
+        assert_eq!(
+            (location.line_start, location.line_end, location.char_end),
+            (300, 70_000, 100)
+        );
+        let metrics = second.complexity_metrics.as_ref().unwrap();
+        assert_eq!(metrics.cyclomatic, 3);
+        assert_eq!(metrics.halstead.as_ref().unwrap().volume, 12.5);
+        assert_eq!(second.metadata_json, r#"{"variants":["A","B"]}"#);
+        assert!(second.is_generated);
+    }
+
+    #[test]
+    fn test_empty_messages_are_present() {
+        let mut project = MockDataGenerator::project_ast(0, 0);
+        project.dependencies.direct.clear();
+        project.dependencies.transitive.clear();
+        project.dependencies.dev_dependencies.clear();
+        let bytes = format_as_protobuf(&project).unwrap();
+        let decoded = v1::ProjectAst::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.dependencies, Some(v1::DependencyInfo::default()));
+        assert_eq!(
+            decoded.extraction_report,
+            Some(v1::ExtractionReport::default())
+        );
+        assert_eq!(decoded.workspace, None);
+    }
+
+    #[test]
+    fn test_visibility_and_skip_reasons() {
+        let mut project = MockDataGenerator::project_ast(1, 1);
+        project.files[0].elements[0].visibility = Visibility::Restricted("crate::net".to_string());
+        project.extraction_report.failed.push(SkippedFile {
+            file: "src/huge.rs".into(),
+            reason: SkipReason::TooLarge,
+            message: "file is 20 MB".to_string(),
+        });
+
+        let bytes = format_as_protobuf(&project).unwrap();
+        let decoded = v1::ProjectAst::decode(bytes.as_slice()).unwrap();
+        let element = &decoded.files[0].elements[0];
+        let visibility = element.visibility.as_ref().unwrap();
+        assert_eq!(visibility.kind(), v1::VisibilityKind::Restricted);
+        assert_eq!(visibility.path, "crate::net");
+        let failed = &decoded.extraction_report.unwrap().failed;
+        assert_eq!(failed[0].file, "src/huge.rs");
+        assert_eq!(failed[0].reason(), v1::SkipReason::TooLarge);
+    }
+}
@@ -0,0 +1,303 @@
+//! Quality scores of chunks, from 0 for chunks not worth retrieving or
+//! training on to 1.
+//!
+//! The score of an element's chunks weighs four parts:
+//!
+//! | Part | Weight | 1 for | 0 for |
+//! |------|--------|-------|-------|
+//! | Documentation | 0.35 | Excellent docs | Missing docs |
+//! | Complexity | 0.25 | Cyclomatic complexity of 4 to 15 | - |
+//! | Tests | 0.2 | Functions called by a test of their file | Files without tests |
+//! | Code smells | 0.2 | No smells | Several of them |
+//!
+//! Generated code scores 0, and trivial getters and setters at most 0.2, so
+//! `RagConfig::min_quality` can leave them out.
+
+use crate::rag::DocumentationQuality;
+use crate::training::{is_test, last_segment};
+use rustex_core::{CodeElement, ElementType, FileAst};
+
+const DOCUMENTATION_WEIGHT: f64 = 0.35;
+const COMPLEXITY_WEIGHT: f64 = 0.25;
+const TESTS_WEIGHT: f64 = 0.2;
+const SMELLS_WEIGHT: f64 = 0.2;
+
+/// Highest score of trivial getters and setters.
+const MAX_ACCESSOR_SCORE: f64 = 0.2;
+
+/// Most lines of a trivial getter or setter, besides its docs and
+/// attributes.
+const MAX_ACCESSOR_LINES: usize = 3;
+
+/// The quality score of the chunks of `element`, an element of `file`,
+/// rounded to two decimals.
+pub(crate) fn quality_score(element: &CodeElement, file: &FileAst) -> f64 {
+    if is_generated(element, file) {
+        return 0.0;
+    }
+    let score = DOCUMENTATION_WEIGHT * DocumentationQuality::of(element).weight()
+        + COMPLEXITY_WEIGHT * complexity_value(element)
+        + TESTS_WEIGHT * test_proximity(element, file)
+        + SMELLS_WEIGHT * (1.0 - smell_penalty(element));
+    let score = if is_trivial_accessor(element) {
+        score.min(MAX_ACCESSOR_SCORE)
+    } else {
+        score
+    };
+    (score * 100.0).round() / 100.0
+}
+
+/// Whether `element` was generated, by macro expansion or into a file named
+/// as generated, rather than written by hand.
+fn is_generated(element: &CodeElement, file: &FileAst) -> bool {
+    let path = file.relative_path.to_string_lossy().to_ascii_lowercase();
+    element.is_generated
+        || path.contains("generated")
+        || element
+            .attribute_info
+            .iter()
+            .any(|attribute| attribute.name == "automatically_derived")
+}
+
+/// How much the complexity of `element` is worth retrieving: trivial
+/// functions teach little, and very complex ones are hard to follow.
+/// Other elements don't have a cyclomatic complexity and get a neutral value.
+fn complexity_value(element: &CodeElement) -> f64 {
+    let cyclomatic = match (&element.element_type, &element.complexity_metrics) {
+        (ElementType::Function, Some(metrics)) => metrics.cyclomatic,
+        _ => return 0.7,
+    };
+    match cyclomatic {
+        0..=1 => 0.4,
+        2..=3 => 0.7,
+        4..=15 => 1.0,
+        _ => 0.6,
+    }
+}
+
+/// 1 if a test of `file` calls `element`, 0.5 if `file` has tests that
+/// don't, and 0 if it has none.
+fn test_proximity(element: &CodeElement, file: &FileAst) -> f64 {
+    let mut tests = file
+        .elements
+        .iter()
+        .filter(|other| is_test(other))
+        .peekable();
+    if tests.peek().is_none() {
+        return 0.0;
+    }
+    let called = tests.any(|test| {
+        test.calls
+            .iter()
+            .any(|call| last_segment(&call.name) == element.name)
+    });
+    if called {
+        1.0
+    } else {
+        0.5
+    }
+}
+
+/// A quarter for each code smell of `element`, up to 1: many parameters,
+/// deep nesting, high cognitive complexity, suppressed lints and
+/// deprecation.
+fn smell_penalty(element: &CodeElement) -> f64 {
+    let mut smells = 0;
+    if let Some(metrics) = &element.complexity_metrics {
+        smells += usize::from(metrics.parameter_count > 5);
+        smells += usize::from(metrics.nesting_depth > 4);
+        smells += usize::from(metrics.cognitive > 25);
+    }
+    for attribute in &element.attribute_info {
+        smells += usize::from(attribute.name == "allow");
+        smells += 2 * usize::from(attribute.name == "deprecated");
+    }
+    (smells as f64 * 0.25).min(1.0)
+}
+
+/// Whether `element` is a method returning or setting a field and nothing
+/// else: one of a few lines, without branches and making a call at most,
+/// taking only `self`, or a value too when named `set_*`.
+fn is_trivial_accessor(element: &CodeElement) -> bool {
+    let Some(metrics) = &element.complexity_metrics else {
+        return false;
+    };
+    let location = &element.location;
+    let attributes = element
+        .attribute_info
+        .iter()
+        .filter(|attribute| attribute.name != "doc")
+        .count();
+    let lines = (location.line_end + 1)
+        .saturating_sub(location.line_start)
+        .saturating_sub(element.doc_comments.len() + attributes);
+    let takes_self = element
+        .signature
+        .as_deref()
+        .is_some_and(|signature| signature.contains("self"));
+    let parameters = if element.name.starts_with("set_") {
+        2
+    } else {
+        1
+    };
+    element.element_type == ElementType::Function
+        && element.hierarchy.impl_context.is_some()
+        && takes_self
+        && metrics.parameter_count == parameters
+        && metrics.cyclomatic <= 1
+        && element.calls.len() <= 1
+        && lines <= MAX_ACCESSOR_LINES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keywords::KeywordCorpus;
+    use crate::rag::{RagConfig, RagFormatter};
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{AttributeInfo, CallKind, CallSite, ImplContext};
+    use std::path::PathBuf;
+
+    /// Docs with a summary, arguments and an example.
+    const EXCELLENT_DOCS: [&str; 4] = [
+        "Parses a configuration file into settings, reporting the line of the first error found.",
+        "# Arguments",
+        "* `path` - Path of the file",
+        "```\nlet settings = parse_config(\"rustex.toml\")?;\n```",
+    ];
+
+    fn attribute(name: &str) -> AttributeInfo {
+        AttributeInfo {
+            name: name.to_string(),
+            args: Vec::new(),
+            value: None,
+            is_derive: false,
+            derived_traits: Vec::new(),
+        }
+    }
+
+    /// A function of cyclomatic complexity 5 with `doc_comments`.
+    fn function(name: &str, doc_comments: &[&str]) -> CodeElement {
+        let mut element = MockDataGenerator::code_element(name, ElementType::Function);
+        element.doc_comments = doc_comments.iter().map(|line| line.to_string()).collect();
+        element.complexity_metrics.as_mut().unwrap().cyclomatic = 5;
+        element.attributes.clear();
+        element
+    }
+
+    /// A test calling `callee`.
+    fn test(callee: &str) -> CodeElement {
+        let mut test = function(&format!("test_{}", callee), &[]);
+        test.attribute_info.push(attribute("test"));
+        test.calls.push(CallSite {
+            name: callee.to_string(),
+            kind: CallKind::Function,
+            line: 1,
+        });
+        test
+    }
+
+    fn file(path: &str, elements: Vec<CodeElement>) -> FileAst {
+        let mut file = MockDataGenerator::file_ast(PathBuf::from(path), 0);
+        file.relative_path = PathBuf::from(path);
+        file.elements = elements;
+        file
+    }
+
+    #[test]
+    fn test_documented_and_undocumented_scores() {
+        let documented = function("parse_config", &EXCELLENT_DOCS);
+        let undocumented = function("load", &[]);
+        let tested = file(
+            "src/lib.rs",
+            vec![
+                documented.clone(),
+                undocumented.clone(),
+                test("parse_config"),
+            ],
+        );
+        // Excellent docs, good complexity, a test and no smells.
+        assert_eq!(quality_score(&documented, &tested), 1.0);
+        // No docs, and tests in the file that don't call it.
+        assert_eq!(quality_score(&undocumented, &tested), 0.55);
+
+        let untested = file("src/lib.rs", vec![documented.clone(), undocumented.clone()]);
+        assert_eq!(quality_score(&documented, &untested), 0.8);
+        assert_eq!(quality_score(&undocumented, &untested), 0.45);
+    }
+
+    #[test]
+    fn test_generated_code_accessors_and_smells() {
+        let documented = function("parse_config", &EXCELLENT_DOCS);
+        assert_eq!(
+            quality_score(&documented, &file("src/generated/config.rs", Vec::new())),
+            0.0
+        );
+        let mut expanded = documented.clone();
+        expanded.is_generated = true;
+        assert_eq!(
+            quality_score(&expanded, &file("src/lib.rs", Vec::new())),
+            0.0
+        );
+
+        let mut getter = function("name", &["The name."]);
+        getter.signature = Some("pub fn name(&self) -> &str".to_string());
+        getter.hierarchy.impl_context = Some(ImplContext {
+            self_type: "Config".to_string(),
+            trait_path: None,
+            where_clause: None,
+        });
+        (getter.location.line_start, getter.location.line_end) = (1, 4);
+        let metrics = getter.complexity_metrics.as_mut().unwrap();
+        (metrics.cyclomatic, metrics.parameter_count) = (1, 1);
+        assert_eq!(quality_score(&getter, &file("src/lib.rs", Vec::new())), 0.2);
+
+        // Two smells take half the code smell part off.
+        let mut smelly = documented.clone();
+        smelly.attribute_info.push(attribute("allow"));
+        smelly.complexity_metrics.as_mut().unwrap().parameter_count = 6;
+        assert_eq!(quality_score(&smelly, &file("src/lib.rs", Vec::new())), 0.7);
+        smelly.attribute_info.push(attribute("deprecated"));
+        assert_eq!(quality_score(&smelly, &file("src/lib.rs", Vec::new())), 0.6);
+    }
+
+    #[test]
+    fn test_min_quality_filter() {
+        let documented = function("parse_config", &EXCELLENT_DOCS);
+        let undocumented = function("load", &[]);
+        let file = file(
+            "src/lib.rs",
+            vec![
+                documented.clone(),
+                undocumented.clone(),
+                test("parse_config"),
+            ],
+        );
+        let corpus = KeywordCorpus::new();
+
+        for (min_quality, expected) in [
+            (0.0, vec!["parse_config", "load"]),
+            (0.55, vec!["parse_config", "load"]),
+            (0.56, vec!["parse_config"]),
+            (1.0, vec!["parse_config"]),
+        ] {
+            let formatter = RagFormatter::new(RagConfig {
+                min_quality,
+                ..RagConfig::default()
+            });
+            let mut kept = Vec::new();
+            for element in [&documented, &undocumented] {
+                let chunks = formatter
+                    .chunks_for_element(element, &file, &corpus)
+                    .unwrap();
+                for chunk in &chunks {
+                    assert_eq!(chunk.metadata.quality_score, quality_score(element, &file));
+                }
+                if !chunks.is_empty() {
+                    kept.push(element.name.as_str());
+                }
+            }
+            assert_eq!(kept, expected, "min_quality {}", min_quality);
+        }
+    }
+}
@@ -3,13 +3,30 @@
 //! This module provides specialized output formats optimized for Retrieval-Augmented
 //! Generation (RAG) systems and Large Language Model (LLM) applications.
 
-use rustex_core::{ProjectAst, CodeElement, FileAst, ElementType, Visibility};
+use crate::api_surface::api_surface;
+use crate::chunking::{estimate_tokens, split_element, surrounding_lines, Part};
+use crate::dedup::{ChunkDeduplicator, DuplicateChunk};
+use crate::embedding_inputs::embedding_inputs;
+use crate::embeddings::{Embedder, EmbeddingProvider};
+use crate::keywords::KeywordCorpus;
+use crate::manifest::{ChunkChanges, RagManifest, RemovedChunk};
+use crate::patterns::code_patterns;
+use crate::quality::quality_score;
+use crate::semantics::{self, ChunkIds};
+use crate::summaries::{Summaries, Summarizer};
+use crate::training::TrainingGenerator;
+use rustex_core::{
+    ProjectAst, CodeElement, FileAst, ElementType, ImplContext, Visibility, RagChunkStrategy,
+    serialize_sorted_map, stable_hash,
+};
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use anyhow::Result;
 
 /// RAG-optimized AST representation designed for embedding and retrieval.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RagDocument {
     /// Document metadata for indexing and retrieval
     pub metadata: RagMetadata,
@@ -22,7 +39,7 @@ pub struct RagDocument {
 }
 
 /// Metadata for RAG document indexing and filtering.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RagMetadata {
     /// Project information
     pub project_name: String,
@@ -35,7 +52,9 @@ pub struct RagMetadata {
     pub chunk_size_stats: ChunkSizeStats,
     
     /// Content distribution
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub element_distribution: HashMap<String, usize>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub complexity_distribution: HashMap<String, usize>,
     
     /// Semantic categories
@@ -45,10 +64,30 @@ pub struct RagMetadata {
     pub generated_at: String,
     pub rustex_version: String,
     pub chunk_strategy: String,
+    /// Model of the chunks' embeddings, if they are embedded
+    pub embedding_model: Option<String>,
+    /// Model of the chunks' generated summaries, if they are summarized
+    #[serde(default)]
+    pub summary_model: Option<String>,
+    /// Errors of the summaries that failed, whose chunks are left without a
+    /// generated summary
+    #[serde(default)]
+    pub failed_summaries: Vec<String>,
+    /// Chunks changed since the manifest given to
+    /// [`RagFormatter::with_since`]; the document only has the added and
+    /// changed ones
+    pub changes: Option<ChunkChanges>,
+    /// Chunks left out as near-duplicates of others, when
+    /// `dedup_threshold` is set
+    #[serde(default)]
+    pub duplicate_chunks: Vec<DuplicateChunk>,
+    /// Number of training examples of each task type
+    #[serde(default, serialize_with = "serialize_sorted_map")]
+    pub training_examples_by_task: HashMap<String, usize>,
 }
 
 /// Statistics about chunk sizes for embedding optimization.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ChunkSizeStats {
     pub min_tokens: usize,
     pub max_tokens: usize,
@@ -58,7 +97,7 @@ pub struct ChunkSizeStats {
 }
 
 /// A single text chunk optimized for embedding and retrieval.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RagChunk {
     /// Unique identifier for the chunk
     pub id: String,
@@ -75,12 +114,18 @@ pub struct RagChunk {
     /// Pre-computed embeddings (optional)
     pub embedding: Option<Vec<f32>>,
     
+    /// Summary of the element written by a [`Summarizer`], for complex
+    /// elements without docs; never written by people, unlike the docs in
+    /// `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_summary: Option<String>,
+    
     /// Semantic fingerprint for deduplication
     pub semantic_hash: String,
 }
 
 /// Metadata for individual chunks.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChunkMetadata {
     /// Source location
     pub file_path: String,
@@ -93,11 +138,20 @@ pub struct ChunkMetadata {
     pub qualified_name: String,
     pub visibility: String,
     
+    /// How the element was split, and which of its chunks this is
+    pub chunk_strategy: ChunkStrategy,
+    pub part_index: usize,
+    pub part_count: usize,
+    
     /// Content characteristics
     pub token_count: usize,
     pub complexity: Option<u32>,
     pub has_documentation: bool,
     pub documentation_quality: DocumentationQuality,
+    /// How worth retrieving and training on the chunk is, from 0 to 1,
+    /// combining its docs, complexity, tests and code smells
+    #[serde(default)]
+    pub quality_score: f64,
     
     /// Semantic categorization
     pub semantic_category: String,
@@ -116,7 +170,7 @@ pub struct ChunkMetadata {
 }
 
 /// Documentation quality assessment for ranking.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum DocumentationQuality {
     Excellent,  // Comprehensive docs with examples
     Good,       // Good docs with some details
@@ -124,8 +178,41 @@ pub enum DocumentationQuality {
     Missing,    // No documentation
 }
 
+impl DocumentationQuality {
+    /// Assess the documentation of `element`: examples, details over 100
+    /// characters and parameter sections make it better.
+    pub fn of(element: &CodeElement) -> Self {
+        if element.doc_comments.is_empty() {
+            return DocumentationQuality::Missing;
+        }
+        
+        let doc_text = element.doc_comments.join(" ");
+        let has_examples = doc_text.contains("```") || doc_text.contains("Example");
+        let has_details = doc_text.len() > 100;
+        let has_params = doc_text.contains("# Arguments") || doc_text.contains("Parameters");
+        
+        match (has_examples, has_details, has_params) {
+            (true, true, true) => DocumentationQuality::Excellent,
+            (_, true, true) | (true, true, _) => DocumentationQuality::Good,
+            (_, true, _) | (_, _, true) => DocumentationQuality::Basic,
+            _ => DocumentationQuality::Basic,
+        }
+    }
+
+    /// How much documentation of this quality counts towards weighted
+    /// coverage, from 0 for missing docs to 1 for excellent ones.
+    pub fn weight(&self) -> f64 {
+        match self {
+            DocumentationQuality::Excellent => 1.0,
+            DocumentationQuality::Good => 0.75,
+            DocumentationQuality::Basic => 0.5,
+            DocumentationQuality::Missing => 0.0,
+        }
+    }
+}
+
 /// Strategy for embedding this chunk.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum EmbeddingStrategy {
     /// Embed code and documentation together
     Combined,
@@ -138,7 +225,7 @@ pub enum EmbeddingStrategy {
 }
 
 /// Semantic information for enhanced retrieval.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RagSemantics {
     /// Concept hierarchy extracted from code
     pub concept_hierarchy: Vec<ConceptNode>,
@@ -147,6 +234,7 @@ pub struct RagSemantics {
     pub relationships: Vec<SemanticRelationship>,
     
     /// Domain-specific vocabulary
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub vocabulary: HashMap<String, VocabularyEntry>,
     
     /// Code patterns and idioms
@@ -157,7 +245,7 @@ pub struct RagSemantics {
 }
 
 /// A concept in the semantic hierarchy.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ConceptNode {
     pub id: String,
     pub name: String,
@@ -170,7 +258,7 @@ pub struct ConceptNode {
 }
 
 /// Types of semantic concepts.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum ConceptType {
     Module,
     Trait,
@@ -183,7 +271,7 @@ pub enum ConceptType {
 }
 
 /// Semantic relationship between code elements.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SemanticRelationship {
     pub from_chunk: String,
     pub to_chunk: String,
@@ -193,7 +281,7 @@ pub struct SemanticRelationship {
 }
 
 /// Types of semantic relationships.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum RelationshipType {
     Implements,
     Uses,
@@ -206,7 +294,7 @@ pub enum RelationshipType {
 }
 
 /// Entry in the domain vocabulary.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct VocabularyEntry {
     pub term: String,
     pub definition: String,
@@ -217,7 +305,7 @@ pub struct VocabularyEntry {
 }
 
 /// Identified code pattern or idiom.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CodePattern {
     pub id: String,
     pub name: String,
@@ -229,7 +317,7 @@ pub struct CodePattern {
 }
 
 /// Types of code patterns.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum PatternType {
     DesignPattern,
     Idiom,
@@ -239,7 +327,7 @@ pub enum PatternType {
 }
 
 /// Analysis of the public API surface.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ApiSurface {
     pub public_functions: Vec<ApiElement>,
     pub public_types: Vec<ApiElement>,
@@ -250,10 +338,12 @@ pub struct ApiSurface {
 }
 
 /// A public API element.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ApiElement {
     pub name: String,
     pub qualified_name: String,
+    /// Paths users import the element by, following `pub use` re-exports
+    pub exported_paths: Vec<String>,
     pub element_type: String,
     pub signature: String,
     pub documentation: String,
@@ -263,7 +353,7 @@ pub struct ApiElement {
 }
 
 /// API stability assessment.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum ApiStability {
     Stable,
     Unstable,
@@ -272,7 +362,7 @@ pub enum ApiStability {
 }
 
 /// Metrics about API complexity.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ApiComplexityMetrics {
     pub total_public_items: usize,
     pub avg_parameter_count: f64,
@@ -282,7 +372,7 @@ pub struct ApiComplexityMetrics {
 }
 
 /// Training example for LLM fine-tuning.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TrainingExample {
     pub id: String,
     pub input: String,
@@ -293,7 +383,7 @@ pub struct TrainingExample {
 }
 
 /// Types of training tasks.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum TaskType {
     CodeGeneration,
     CodeExplanation,
@@ -303,10 +393,16 @@ pub enum TaskType {
     CodeSummarization,
     ApiUsage,
     PatternRecognition,
+    /// Write the doc comment of a function
+    DocumentationGeneration,
+    /// Name a function from its code
+    FunctionNaming,
+    /// Write a test of a function
+    TestGeneration,
 }
 
 /// Difficulty levels for training examples.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum DifficultyLevel {
     Beginner,
     Intermediate,
@@ -315,7 +411,7 @@ pub enum DifficultyLevel {
 }
 
 /// Metadata for training examples.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TrainingMetadata {
     pub source_chunks: Vec<String>,
     pub concepts_involved: Vec<String>,
@@ -333,18 +429,39 @@ pub struct RagConfig {
     pub max_chunk_size: usize,
     /// Minimum chunk size in tokens
     pub min_chunk_size: usize,
-    /// Overlap between adjacent chunks
-    pub chunk_overlap: usize,
+    /// Overlap between adjacent chunks of a split element, in tokens
+    #[serde(alias = "chunk_overlap")]
+    pub chunk_overlap_tokens: usize,
+    /// How elements are split into chunks
+    pub chunk_strategy: ChunkStrategy,
+    /// Lines of source before and after each chunk to add to its context
+    pub context_radius_lines: usize,
+    /// Add the headers of the impl, trait or modules enclosing each chunk's
+    /// element to its context
+    pub include_parent_signature: bool,
     
     /// Include pre-computed embeddings
     pub include_embeddings: bool,
     /// Embedding model to use
     pub embedding_model: Option<String>,
+    /// Chunks embedded per request to the embedding provider
+    pub embedding_batch_size: usize,
+    /// Most requests to the embedding provider per minute, unlimited when unset
+    pub embedding_requests_per_minute: Option<u32>,
+    /// Retries of embedding requests that were rate limited or failed on the way
+    pub embedding_max_retries: u32,
+    
+    /// Least cyclomatic complexity of the elements without docs to summarize
+    /// with the summarizer
+    pub summary_min_complexity: u32,
     
     /// Generate training examples
     pub generate_training_examples: bool,
     /// Maximum training examples per chunk
     pub max_training_examples_per_chunk: usize,
+    /// Tasks to generate training examples of, in the order they fill the
+    /// examples of a chunk
+    pub training_tasks: Vec<TaskType>,
     
     /// Semantic analysis depth
     pub semantic_analysis_depth: SemanticDepth,
@@ -354,6 +471,11 @@ pub struct RagConfig {
     pub include_test_code: bool,
     pub min_complexity_for_inclusion: Option<u32>,
     pub min_documentation_quality: DocumentationQuality,
+    /// Lowest quality score of the chunks to output, from 0 to 1
+    pub min_quality: f64,
+    /// Similarity from 0 to 1 above which chunks are left out as
+    /// near-duplicates of earlier ones, none when unset
+    pub dedup_threshold: Option<f64>,
 }
 
 /// Depth of semantic analysis to perform.
@@ -364,22 +486,83 @@ pub enum SemanticDepth {
     Deep,       // Full semantic analysis with patterns
 }
 
+/// How elements are split into chunks.
+///
+/// Strategies other than `PerElement` chunk the source of elements, read
+/// from their files, and keep elements of up to `max_chunk_size` tokens
+/// whole. Elements whose files can't be read are chunked per element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ChunkStrategy {
+    /// One chunk per element, of its docs and signature
+    PerElement,
+    /// Chunks of `target_chunk_size` tokens of source, each starting with
+    /// the last `chunk_overlap_tokens` tokens of the one before
+    TokenBudget,
+    /// Windows of `target_chunk_size` tokens overlapping by `chunk_overlap_tokens`
+    /// over large functions, each starting with the function's signature;
+    /// other elements stay whole
+    SlidingWindow,
+    /// Chunks of whole statements of functions and items of impls, traits
+    /// and modules, up to `target_chunk_size` tokens
+    Semantic,
+}
+
+impl ChunkStrategy {
+    /// Name of the strategy in the RAG metadata.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChunkStrategy::PerElement => "per_element",
+            ChunkStrategy::TokenBudget => "token_budget",
+            ChunkStrategy::SlidingWindow => "sliding_window",
+            ChunkStrategy::Semantic => "semantic",
+        }
+    }
+}
+
+impl From<RagChunkStrategy> for ChunkStrategy {
+    fn from(strategy: RagChunkStrategy) -> Self {
+        match strategy {
+            RagChunkStrategy::PerElement => ChunkStrategy::PerElement,
+            RagChunkStrategy::TokenBudget => ChunkStrategy::TokenBudget,
+            RagChunkStrategy::SlidingWindow => ChunkStrategy::SlidingWindow,
+            RagChunkStrategy::Semantic => ChunkStrategy::Semantic,
+        }
+    }
+}
+
 impl Default for RagConfig {
     fn default() -> Self {
         Self {
             target_chunk_size: 512,
             max_chunk_size: 1024,
             min_chunk_size: 100,
-            chunk_overlap: 50,
+            chunk_overlap_tokens: 50,
+            chunk_strategy: ChunkStrategy::PerElement,
+            context_radius_lines: 0,
+            include_parent_signature: true,
             include_embeddings: false,
             embedding_model: None,
+            embedding_batch_size: 64,
+            embedding_requests_per_minute: None,
+            embedding_max_retries: 3,
+            summary_min_complexity: 5,
             generate_training_examples: true,
-            max_training_examples_per_chunk: 3,
+            max_training_examples_per_chunk: 6,
+            training_tasks: vec![
+                TaskType::ApiUsage,
+                TaskType::TestGeneration,
+                TaskType::DocumentationGeneration,
+                TaskType::CodeCompletion,
+                TaskType::BugFinding,
+                TaskType::FunctionNaming,
+            ],
             semantic_analysis_depth: SemanticDepth::Standard,
             include_private_items: false,
             include_test_code: false,
             min_complexity_for_inclusion: None,
             min_documentation_quality: DocumentationQuality::Missing,
+            min_quality: 0.0,
+            dedup_threshold: None,
         }
     }
 }
@@ -387,31 +570,96 @@ impl Default for RagConfig {
 /// Main RAG formatter that converts ProjectAst to RAG format.
 pub struct RagFormatter {
     config: RagConfig,
+    embedder: Option<Embedder>,
+    summaries: Option<Summaries>,
+    since: Option<RagManifest>,
+}
+
+impl Default for RagFormatter {
+    /// Create a new RAG formatter with default configuration.
+    fn default() -> Self {
+        Self {
+            config: RagConfig::default(),
+            embedder: None,
+            summaries: None,
+            since: None,
+        }
+    }
 }
 
 impl RagFormatter {
     /// Create a new RAG formatter with the given configuration.
     pub fn new(config: RagConfig) -> Self {
-        Self { config }
-    }
-    
-    /// Create a new RAG formatter with default configuration.
-    pub fn default() -> Self {
         Self {
-            config: RagConfig::default(),
+            config,
+            embedder: None,
+            summaries: None,
+            since: None,
         }
     }
     
+    /// Embed the chunks with `provider` when `include_embeddings` is set.
+    pub fn with_embedding_provider(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.embedder = Some(Embedder::new(provider, &self.config));
+        self
+    }
+    
+    /// Summarize the elements without docs of at least
+    /// `summary_min_complexity` with `summarizer`.
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summaries = Some(Summaries::new(summarizer, &self.config));
+        self
+    }
+    
+    /// Only output the chunks added or changed since `manifest`, from an
+    /// earlier run, so only those are embedded again.
+    pub fn with_since(mut self, manifest: RagManifest) -> Self {
+        self.since = Some(manifest);
+        self
+    }
+    
+    /// The manifest given to [`Self::with_since`], if any.
+    pub fn since(&self) -> Option<&RagManifest> {
+        self.since.as_ref()
+    }
+    
     /// Format a ProjectAst into RAG document format.
     pub fn format(&self, project_ast: &ProjectAst) -> Result<RagDocument> {
-        let metadata = self.build_metadata(project_ast)?;
-        let chunks = self.create_chunks(project_ast)?;
-        let semantics = self.analyze_semantics(project_ast, &chunks)?;
+        let (mut chunks, duplicates) = self.unique_chunks(project_ast)?;
+        let chunk_ids = self.chunk_ids_by_element(project_ast, &chunks);
+        self.link_references(project_ast, &mut chunks, &chunk_ids);
+        let changes = match &self.since {
+            Some(since) => {
+                let current = self.manifest_of(project_ast, &chunks, &duplicates);
+                chunks.retain(|chunk| since.is_changed(chunk));
+                Some(since.changes_to(&current))
+            }
+            None => None,
+        };
+        let failed_summaries = match &self.summaries {
+            Some(_) => self.summarize_chunks(&mut chunks, &project_ast.files)?,
+            None => Vec::new(),
+        };
+        if self.config.include_embeddings {
+            self.embed_chunks(&mut chunks)?;
+        }
+        let mut metadata = self.build_metadata(project_ast, &chunks)?;
+        metadata.failed_summaries = failed_summaries
+            .iter()
+            .map(|e| format!("{:#}", e))
+            .collect();
+        metadata.changes = changes;
+        metadata.duplicate_chunks = duplicates;
+        let semantics = self.analyze_semantics(project_ast, &chunk_ids)?;
         let training_examples = if self.config.generate_training_examples {
             self.generate_training_examples(project_ast, &chunks)?
         } else {
             Vec::new()
         };
+        for example in &training_examples {
+            let task = format!("{:?}", example.task_type);
+            *metadata.training_examples_by_task.entry(task).or_insert(0) += 1;
+        }
         
         Ok(RagDocument {
             metadata,
@@ -422,7 +670,7 @@ impl RagFormatter {
     }
     
     /// Build metadata for the RAG document.
-    fn build_metadata(&self, project_ast: &ProjectAst) -> Result<RagMetadata> {
+    fn build_metadata(&self, project_ast: &ProjectAst, chunks: &[RagChunk]) -> Result<RagMetadata> {
         let mut element_distribution = HashMap::new();
         let mut complexity_distribution = HashMap::new();
         let mut total_tokens = 0;
@@ -445,15 +693,14 @@ impl RagFormatter {
                     };
                     *complexity_distribution.entry(complexity_level.to_string()).or_insert(0) += 1;
                 }
-                
-                // Estimate token count
-                let content = self.build_element_content(element);
-                let tokens = self.estimate_token_count(&content);
-                total_tokens += tokens;
-                token_sizes.push(tokens);
             }
         }
         
+        for chunk in chunks {
+            total_tokens += chunk.metadata.token_count;
+            token_sizes.push(chunk.metadata.token_count);
+        }
+        
         // Calculate chunk size statistics
         token_sizes.sort_unstable();
         let chunk_size_stats = ChunkSizeStats {
@@ -493,54 +740,228 @@ impl RagFormatter {
                 "implementation".to_string(),
                 "module_organization".to_string(),
             ],
-            generated_at: chrono::Utc::now().to_rfc3339(),
+            generated_at: project_ast.extracted_at.to_rfc3339(),
             rustex_version: env!("CARGO_PKG_VERSION").to_string(),
-            chunk_strategy: "semantic_boundaries".to_string(),
+            chunk_strategy: self.config.chunk_strategy.name().to_string(),
+            embedding_model: self
+                .embedder
+                .as_ref()
+                .filter(|_| self.config.include_embeddings)
+                .map(|embedder| embedder.model().to_string()),
+            summary_model: self
+                .summaries
+                .as_ref()
+                .map(|summaries| summaries.model().to_string()),
+            failed_summaries: Vec::new(),
+            changes: None,
+            duplicate_chunks: Vec::new(),
+            training_examples_by_task: HashMap::new(),
         })
     }
     
-    /// Create optimized chunks from the project AST.
+    /// The manifest of the chunks of `project_ast`, for a later run to
+    /// compare its chunks with.
+    pub fn manifest(&self, project_ast: &ProjectAst) -> Result<RagManifest> {
+        let (chunks, duplicates) = self.unique_chunks(project_ast)?;
+        Ok(self.manifest_of(project_ast, &chunks, &duplicates))
+    }
+
+    fn manifest_of(
+        &self,
+        project_ast: &ProjectAst,
+        chunks: &[RagChunk],
+        duplicates: &[DuplicateChunk],
+    ) -> RagManifest {
+        let mut manifest = RagManifest {
+            generated_at: project_ast.extracted_at.to_rfc3339(),
+            ..RagManifest::for_project(&project_ast.project)
+        };
+        manifest.add(chunks);
+        manifest.summary.duplicate_chunks = duplicates.len();
+        manifest
+    }
+    
+    /// Set the embeddings of `chunks` with the embedding provider, in
+    /// batches. Chunks from [`Self::chunks_for_file`] are embedded separately,
+    /// so streamed files can be embedded as they come.
+    pub fn embed_chunks(&self, chunks: &mut [RagChunk]) -> Result<()> {
+        let Some(embedder) = &self.embedder else {
+            anyhow::bail!("Embedding RAG chunks needs an embedding provider");
+        };
+        let texts: Vec<String> = chunks.iter().map(embedding_text).collect();
+        for (chunk, embedding) in chunks.iter_mut().zip(embedder.embed(&texts)?) {
+            chunk.embedding = Some(embedding);
+        }
+        Ok(())
+    }
+    
+    /// Set the generated summaries of the chunks of complex elements without
+    /// docs with the summarizer, reading the code of the elements from
+    /// `files`, the files of the chunks. Summaries are generated before
+    /// embedding but kept out of the embedded text. Chunks whose summary
+    /// fails are left without one, and the errors returned.
+    pub fn summarize_chunks(
+        &self,
+        chunks: &mut [RagChunk],
+        files: &[FileAst],
+    ) -> Result<Vec<anyhow::Error>> {
+        let Some(summaries) = &self.summaries else {
+            anyhow::bail!("Summarizing RAG chunks needs a summarizer");
+        };
+        Ok(summaries.summarize(chunks, files))
+    }
+    
+    /// Create optimized chunks from the project AST, with retrieval
+    /// keywords weighed against the docs of the whole project.
     fn create_chunks(&self, project_ast: &ProjectAst) -> Result<Vec<RagChunk>> {
+        let corpus = KeywordCorpus::from_files(&project_ast.files);
         let mut chunks = Vec::new();
-        let mut chunk_id = 0;
         
         for file in &project_ast.files {
-            for element in &file.elements {
-                // Filter elements based on configuration
-                if !self.should_include_element(element) {
-                    continue;
-                }
-                
-                let content = self.build_element_content(element);
-                let content_with_context = self.build_element_content_with_context(element, file);
-                
-                let metadata = self.build_chunk_metadata(element, file, &content)?;
-                
-                // Generate semantic hash for deduplication
-                let semantic_hash = self.generate_semantic_hash(&content);
-                
-                chunk_id += 1;
-                chunks.push(RagChunk {
-                    id: format!("chunk_{}", chunk_id),
-                    content,
-                    content_with_context,
-                    metadata,
-                    embedding: None, // Computed separately if needed
-                    semantic_hash,
-                });
-            }
+            chunks.extend(self.chunks_for_file(file, &corpus)?);
         }
         
         Ok(chunks)
     }
     
-    /// Analyze semantic relationships and concepts.
-    fn analyze_semantics(&self, project_ast: &ProjectAst, chunks: &[RagChunk]) -> Result<RagSemantics> {
-        let concept_hierarchy = self.extract_concept_hierarchy(project_ast)?;
-        let relationships = self.extract_semantic_relationships(project_ast, chunks)?;
-        let vocabulary = self.build_vocabulary(project_ast)?;
-        let patterns = self.identify_code_patterns(project_ast)?;
-        let api_surface = self.analyze_api_surface(project_ast)?;
+    /// The chunks of `project_ast` without near-duplicates, and the
+    /// duplicates left out.
+    fn unique_chunks(
+        &self,
+        project_ast: &ProjectAst,
+    ) -> Result<(Vec<RagChunk>, Vec<DuplicateChunk>)> {
+        let mut chunks = self.create_chunks(project_ast)?;
+        let duplicates = self.deduplicator().retain_unique(&mut chunks);
+        Ok((chunks, duplicates))
+    }
+    
+    /// A deduplicator of chunks at the `dedup_threshold`, to leave the
+    /// near-duplicates out of chunks from [`Self::chunks_for_file`].
+    pub fn deduplicator(&self) -> ChunkDeduplicator {
+        ChunkDeduplicator::new(self.config.dedup_threshold)
+    }
+    
+    /// Create the chunks for a single file's elements, weighing the words
+    /// of their docs for retrieval keywords against `corpus`.
+    ///
+    /// Chunks only depend on their own file and `corpus`, so this can be
+    /// used on files streamed from `AstExtractor::extract_iter`, adding each
+    /// to the corpus first. References between chunks need the whole project,
+    /// so they are only set by [`Self::format`].
+    pub fn chunks_for_file(&self, file: &FileAst, corpus: &KeywordCorpus) -> Result<Vec<RagChunk>> {
+        let source = self.read_source(file);
+        let mut chunks = Vec::new();
+        
+        for element in &file.elements {
+            chunks.extend(self.element_chunks(element, file, source.as_deref(), corpus)?);
+        }
+        
+        Ok(chunks)
+    }
+    
+    /// Create the chunks for one element of `file`, none if the
+    /// configuration excludes the element.
+    pub fn chunks_for_element(
+        &self,
+        element: &CodeElement,
+        file: &FileAst,
+        corpus: &KeywordCorpus,
+    ) -> Result<Vec<RagChunk>> {
+        self.element_chunks(element, file, self.read_source(file).as_deref(), corpus)
+    }
+    
+    /// Read the source of `file` if the chunk strategy splits sources or
+    /// chunks get the lines around them as context.
+    fn read_source(&self, file: &FileAst) -> Option<String> {
+        if self.config.chunk_strategy == ChunkStrategy::PerElement
+            && self.config.context_radius_lines == 0
+        {
+            return None;
+        }
+        std::fs::read_to_string(&file.path).ok()
+    }
+    
+    /// Create the chunks for `element`, splitting `source`, the text of
+    /// `file`, when there is one.
+    fn element_chunks(
+        &self,
+        element: &CodeElement,
+        file: &FileAst,
+        source: Option<&str>,
+        corpus: &KeywordCorpus,
+    ) -> Result<Vec<RagChunk>> {
+        // Filter elements based on configuration
+        if !self.should_include_element(element) {
+            return Ok(Vec::new());
+        }
+        // Low-value elements, such as generated code and trivial getters,
+        // are left out of embeddings and training examples.
+        let quality_score = quality_score(element, file);
+        if quality_score < self.config.min_quality {
+            return Ok(Vec::new());
+        }
+        
+        let parts = source.and_then(|source| split_element(element, source, &self.config));
+        let (chunk_strategy, parts) = match parts {
+            Some(parts) => (self.config.chunk_strategy, parts),
+            None => (
+                ChunkStrategy::PerElement,
+                vec![Part {
+                    line_start: element.location.line_start,
+                    line_end: element.location.line_end,
+                    content: self.build_element_content(element),
+                }],
+            ),
+        };
+        
+        let part_count = parts.len();
+        let mut chunks = Vec::new();
+        for (part_index, part) in parts.into_iter().enumerate() {
+            let content_with_context =
+                self.build_element_content_with_context(element, file, &part, source);
+
+            let mut metadata =
+                self.build_chunk_metadata(element, file, &part.content, quality_score, corpus)?;
+            metadata.start_line = part.line_start as u32;
+            metadata.end_line = part.line_end as u32;
+            metadata.chunk_strategy = chunk_strategy;
+            metadata.part_index = part_index;
+            metadata.part_count = part_count;
+            
+            // Generate semantic hash for deduplication
+            let semantic_hash = self.generate_semantic_hash(&part.content);
+            
+            // Later parts are told apart from the element's first chunk.
+            let id = match part_index {
+                0 => chunk_id(element),
+                _ => format!("{}_part{}", chunk_id(element), part_index + 1),
+            };
+            chunks.push(RagChunk {
+                id,
+                content: part.content,
+                content_with_context,
+                metadata,
+                embedding: None, // Computed separately if needed
+                generated_summary: None,
+                semantic_hash,
+            });
+        }
+        
+        Ok(chunks)
+    }
+    
+    /// Analyze semantic relationships and concepts among the elements with
+    /// a chunk in `chunk_ids`.
+    fn analyze_semantics(
+        &self,
+        project_ast: &ProjectAst,
+        chunk_ids: &ChunkIds,
+    ) -> Result<RagSemantics> {
+        let concept_hierarchy = semantics::concept_hierarchy(project_ast, chunk_ids);
+        let relationships = semantics::relationships(project_ast, chunk_ids);
+        let vocabulary = semantics::vocabulary(project_ast, chunk_ids);
+        let patterns = code_patterns(project_ast, chunk_ids);
+        let api_surface = api_surface(project_ast);
         
         Ok(RagSemantics {
             concept_hierarchy,
@@ -552,34 +973,33 @@ impl RagFormatter {
     }
     
     /// Generate training examples for LLM fine-tuning.
-    fn generate_training_examples(&self, _project_ast: &ProjectAst, chunks: &[RagChunk]) -> Result<Vec<TrainingExample>> {
+    fn generate_training_examples(
+        &self,
+        project_ast: &ProjectAst,
+        chunks: &[RagChunk],
+    ) -> Result<Vec<TrainingExample>> {
+        let mut generator = TrainingGenerator::new(&self.config, project_ast);
         let mut examples = Vec::new();
-        let mut example_id = 0;
         
-        for chunk in chunks {
-            // Limit examples per chunk
-            let mut chunk_examples = 0;
-            
-            // Generate different types of training examples
-            if chunk_examples < self.config.max_training_examples_per_chunk {
-                if let Some(example) = self.create_code_explanation_example(chunk, &mut example_id)? {
-                    examples.push(example);
-                    chunk_examples += 1;
-                }
-            }
-            
-            if chunk_examples < self.config.max_training_examples_per_chunk {
-                if let Some(example) = self.create_code_completion_example(chunk, &mut example_id)? {
-                    examples.push(example);
-                    chunk_examples += 1;
-                }
-            }
-            
-            if chunk_examples < self.config.max_training_examples_per_chunk {
-                if let Some(example) = self.create_api_usage_example(chunk, &mut example_id)? {
-                    examples.push(example);
-                    let _ = chunk_examples + 1; // Acknowledge final increment
-                }
+        // Examples are made for the first chunk of each element, if it is in
+        // the output.
+        let chunks_by_id: HashMap<&str, &RagChunk> = chunks
+            .iter()
+            .map(|chunk| (chunk.id.as_str(), chunk))
+            .collect();
+        for file in &project_ast.files {
+            let mut source = None;
+            let elements = file
+                .elements
+                .iter()
+                .filter(|element| self.should_include_element(element));
+            for element in elements {
+                let Some(chunk) = chunks_by_id.get(chunk_id(element).as_str()).copied() else {
+                    continue;
+                };
+                // Generators read the source of functions, once per file.
+                let source = source.get_or_insert_with(|| std::fs::read_to_string(&file.path).ok());
+                examples.extend(generator.examples(element, chunk, source.as_deref()));
             }
         }
         
@@ -628,33 +1048,71 @@ impl RagFormatter {
         content
     }
     
-    /// Build content with additional context.
-    fn build_element_content_with_context(&self, element: &CodeElement, file: &FileAst) -> String {
+    /// Build content with additional context: the file and module, the
+    /// imports the part uses, the headers of the items enclosing the element
+    /// and the lines of `source` around the part.
+    fn build_element_content_with_context(
+        &self,
+        element: &CodeElement,
+        file: &FileAst,
+        part: &Part,
+        source: Option<&str>,
+    ) -> String {
         let mut content = String::new();
         
         // Add file context
         content.push_str(&format!("// File: {}\n", file.relative_path.display()));
-        content.push_str(&format!("// Module: {}\n\n", element.hierarchy.module_path));
+        content.push_str(&format!("// Module: {}\n", element.hierarchy.module_path));
+        if self.config.include_parent_signature {
+            for header in parent_headers(element, file) {
+                content.push_str(&format!("// In: {}\n", header));
+            }
+        }
+        content.push('\n');
+        
+        let imports = relevant_imports(element, file, &part.content);
+        if !imports.is_empty() {
+            content.push_str(&imports.join("\n"));
+            content.push_str("\n\n");
+        }
         
-        // Add main content
-        content.push_str(&self.build_element_content(element));
+        // Add main content between the lines around it
+        let (before, after) = source
+            .map(|source| surrounding_lines(source, part, self.config.context_radius_lines))
+            .unwrap_or_default();
+        for line in before {
+            content.push_str(line);
+            content.push('\n');
+        }
+        content.push_str(&part.content);
+        for line in after {
+            content.push('\n');
+            content.push_str(line);
+        }
         
         // Add additional context
-        if element.complexity.is_some() {
-            content.push_str(&format!("\n// Complexity: {}", element.complexity.unwrap()));
+        if let Some(complexity) = element.complexity {
+            content.push_str(&format!("\n// Complexity: {}", complexity));
         }
         
         content
     }
     
     /// Build metadata for a chunk.
-    fn build_chunk_metadata(&self, element: &CodeElement, file: &FileAst, content: &str) -> Result<ChunkMetadata> {
-        let documentation_quality = self.assess_documentation_quality(element);
+    fn build_chunk_metadata(
+        &self,
+        element: &CodeElement,
+        file: &FileAst,
+        content: &str,
+        quality_score: f64,
+        corpus: &KeywordCorpus,
+    ) -> Result<ChunkMetadata> {
+        let documentation_quality = DocumentationQuality::of(element);
         let semantic_category = self.categorize_element(element);
         let domain_tags = self.extract_domain_tags(element);
         let intent_tags = self.extract_intent_tags(element);
         let embedding_strategy = self.determine_embedding_strategy(element);
-        let retrieval_keywords = self.extract_retrieval_keywords(element);
+        let retrieval_keywords = corpus.retrieval_keywords(element, file);
         
         Ok(ChunkMetadata {
             file_path: file.relative_path.to_string_lossy().to_string(),
@@ -664,15 +1122,20 @@ impl RagFormatter {
             element_name: element.name.clone(),
             qualified_name: element.hierarchy.qualified_name.clone(),
             visibility: format!("{:?}", element.visibility),
+            chunk_strategy: ChunkStrategy::PerElement,
+            part_index: 0,
+            part_count: 1,
             token_count: self.estimate_token_count(content),
             complexity: element.complexity,
             has_documentation: !element.doc_comments.is_empty(),
             documentation_quality,
+            quality_score,
             semantic_category,
             domain_tags,
             intent_tags,
-            references: Vec::new(), // TODO: Add cross-references when available
-            referenced_by: Vec::new(), // TODO: Add cross-references when available  
+            // Filled in from the project's reference graph by `link_references`
+            references: Vec::new(),
+            referenced_by: Vec::new(),
             parent_elements: vec![element.hierarchy.parent_id.clone().unwrap_or_default()],
             child_elements: element.hierarchy.children_ids.clone(),
             embedding_strategy,
@@ -682,37 +1145,13 @@ impl RagFormatter {
     
     /// Estimate token count for text (rough approximation).
     fn estimate_token_count(&self, text: &str) -> usize {
-        // Simple approximation: ~4 characters per token
-        (text.len() as f64 / 4.0).ceil() as usize
+        estimate_tokens(text)
     }
     
-    /// Generate semantic hash for deduplication.
+    /// Generate semantic hash for deduplication, stable across runs so it
+    /// can be compared with a [`RagManifest`].
     fn generate_semantic_hash(&self, content: &str) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut hasher = DefaultHasher::new();
-        content.hash(&mut hasher);
-        format!("{:x}", hasher.finish())
-    }
-    
-    /// Assess documentation quality.
-    fn assess_documentation_quality(&self, element: &CodeElement) -> DocumentationQuality {
-        if element.doc_comments.is_empty() {
-            return DocumentationQuality::Missing;
-        }
-        
-        let doc_text = element.doc_comments.join(" ");
-        let has_examples = doc_text.contains("```") || doc_text.contains("Example");
-        let has_details = doc_text.len() > 100;
-        let has_params = doc_text.contains("# Arguments") || doc_text.contains("Parameters");
-        
-        match (has_examples, has_details, has_params) {
-            (true, true, true) => DocumentationQuality::Excellent,
-            (_, true, true) | (true, true, _) => DocumentationQuality::Good,
-            (_, true, _) | (_, _, true) => DocumentationQuality::Basic,
-            _ => DocumentationQuality::Basic,
-        }
+        format!("{:016x}", stable_hash(&[content]))
     }
     
     /// Categorize element semantically.
@@ -789,115 +1228,150 @@ impl RagFormatter {
         }
     }
     
-    /// Extract keywords for retrieval.
-    fn extract_retrieval_keywords(&self, element: &CodeElement) -> Vec<String> {
-        let mut keywords = Vec::new();
-        
-        // Add element name and variations
-        keywords.push(element.name.clone());
-        keywords.push(element.hierarchy.qualified_name.clone());
-        
-        // Add words from documentation
-        for doc in &element.doc_comments {
-            let words: Vec<String> = doc
-                .split_whitespace()
-                .filter(|w| w.len() > 3 && !w.starts_with("///"))
-                .map(|w| w.to_lowercase().trim_matches(|c: char| !c.is_alphanumeric()).to_string())
-                .filter(|w| !w.is_empty())
-                .collect();
-            keywords.extend(words);
-        }
-        
-        // Remove duplicates and limit length
-        keywords.sort();
-        keywords.dedup();
-        keywords.truncate(20);
-        
-        keywords
+    /// Elements that get a chunk, in the order their chunks are created.
+    fn included_elements<'a>(
+        &'a self,
+        project_ast: &'a ProjectAst,
+    ) -> impl Iterator<Item = &'a CodeElement> {
+        project_ast
+            .files
+            .iter()
+            .flat_map(|file| &file.elements)
+            .filter(|element| self.should_include_element(element))
     }
     
-    // Placeholder implementations for semantic analysis methods
-    fn extract_concept_hierarchy(&self, _project_ast: &ProjectAst) -> Result<Vec<ConceptNode>> {
-        Ok(Vec::new()) // TODO: Implement concept extraction
+    /// Set the references of `chunks` from the reference graph of
+    /// `project_ast`, as the IDs of the chunks of the referenced and
+    /// referencing elements. Elements without a chunk in `chunk_ids` are
+    /// left out. Every part of a split element gets its element's references.
+    fn link_references(
+        &self,
+        project_ast: &ProjectAst,
+        chunks: &mut [RagChunk],
+        chunk_ids: &ChunkIds,
+    ) {
+        let element_ids: HashMap<String, &str> = self
+            .included_elements(project_ast)
+            .map(|element| (chunk_id(element), element.id.as_str()))
+            .collect();
+        let graph = &project_ast.reference_graph;
+        let to_chunk_ids = |element_ids: &[String]| -> Vec<String> {
+            element_ids
+                .iter()
+                .filter_map(|id| chunk_ids.get(id.as_str()).cloned())
+                .collect()
+        };
+        for chunk in chunks {
+            let suffix = format!("_part{}", chunk.metadata.part_index + 1);
+            let first_id = match chunk.metadata.part_index {
+                0 => chunk.id.as_str(),
+                _ => chunk.id.strip_suffix(&suffix).unwrap_or(&chunk.id),
+            };
+            let Some(element_id) = element_ids.get(first_id) else {
+                continue;
+            };
+            chunk.metadata.references = to_chunk_ids(graph.references_of(element_id));
+            chunk.metadata.referenced_by = to_chunk_ids(graph.referenced_by(element_id));
+        }
     }
     
-    fn extract_semantic_relationships(&self, _project_ast: &ProjectAst, _chunks: &[RagChunk]) -> Result<Vec<SemanticRelationship>> {
-        Ok(Vec::new()) // TODO: Implement relationship extraction
+    /// Map element IDs to the IDs of the chunks created for them, for the
+    /// elements whose chunk is among `chunks`, left after deduplication.
+    fn chunk_ids_by_element<'a>(
+        &'a self,
+        project_ast: &'a ProjectAst,
+        chunks: &[RagChunk],
+    ) -> ChunkIds<'a> {
+        let kept: HashSet<&str> = chunks.iter().map(|chunk| chunk.id.as_str()).collect();
+        self.included_elements(project_ast)
+            .map(|element| (element.id.as_str(), chunk_id(element)))
+            .filter(|(_, chunk_id)| kept.contains(chunk_id.as_str()))
+            .collect()
     }
-    
-    fn build_vocabulary(&self, _project_ast: &ProjectAst) -> Result<HashMap<String, VocabularyEntry>> {
-        Ok(HashMap::new()) // TODO: Implement vocabulary building
+}
+
+/// Headers of the impl, trait and modules enclosing `element` in `file`,
+/// outermost first, such as `mod net` and `impl Display for crate::net::Message`.
+fn parent_headers(element: &CodeElement, file: &FileAst) -> Vec<String> {
+    let mut headers = Vec::new();
+    let mut parent_id = element.hierarchy.parent_id.as_deref();
+    while let Some(parent) = parent_id.and_then(|id| file.elements.iter().find(|e| e.id == id)) {
+        headers.push(
+            match (&parent.element_type, &parent.hierarchy.impl_context) {
+                (ElementType::Impl, Some(context)) => impl_header(context),
+                (ElementType::Module, _) => format!("mod {}", parent.name),
+                (ElementType::Trait, _) => format!("trait {}", parent.name),
+                _ => parent
+                    .signature
+                    .clone()
+                    .unwrap_or_else(|| parent.name.clone()),
+            },
+        );
+        parent_id = parent.hierarchy.parent_id.as_deref();
     }
-    
-    fn identify_code_patterns(&self, _project_ast: &ProjectAst) -> Result<Vec<CodePattern>> {
-        Ok(Vec::new()) // TODO: Implement pattern identification
+    // Methods of impls outside the file still name their impl.
+    if headers.is_empty() && element.element_type != ElementType::Impl {
+        if let Some(context) = &element.hierarchy.impl_context {
+            headers.push(impl_header(context));
+        }
     }
-    
-    fn analyze_api_surface(&self, project_ast: &ProjectAst) -> Result<ApiSurface> {
-        let mut public_functions = Vec::new();
-        let mut public_types = Vec::new();
-        let mut public_traits = Vec::new();
-        let mut modules = Vec::new();
-        
-        for file in &project_ast.files {
-            for element in &file.elements {
-                if element.visibility == Visibility::Public {
-                    let api_element = ApiElement {
-                        name: element.name.clone(),
-                        qualified_name: element.hierarchy.qualified_name.clone(),
-                        element_type: format!("{:?}", element.element_type),
-                        signature: element.signature.clone().unwrap_or_default(),
-                        documentation: element.doc_comments.join("\n"),
-                        chunk_id: format!("chunk_{}", element.name), // TODO: Link to actual chunk
-                        stability: ApiStability::Stable, // TODO: Infer from attributes
-                        usage_examples: Vec::new(), // TODO: Extract from docs
-                    };
-                    
-                    match element.element_type {
-                        ElementType::Function => public_functions.push(api_element),
-                        ElementType::Struct | ElementType::Enum => public_types.push(api_element),
-                        ElementType::Trait => public_traits.push(api_element),
-                        ElementType::Module => modules.push(api_element),
-                        ElementType::Impl => {}, // Skip implementations in API surface
-                        _ => {}, // Skip other types
-                    }
+    headers.reverse();
+    headers
+}
+
+/// The header of the impl block of `context`, without generics.
+fn impl_header(context: &ImplContext) -> String {
+    let mut header = match &context.trait_path {
+        Some(trait_path) => format!("impl {} for {}", trait_path, context.self_type),
+        None => format!("impl {}", context.self_type),
+    };
+    if let Some(where_clause) = &context.where_clause {
+        header.push(' ');
+        header.push_str(where_clause);
+    }
+    header
+}
+
+/// The `use` declarations in the module of `element` that bring in names
+/// `content` uses, and glob imports, whose names are unknown.
+fn relevant_imports(element: &CodeElement, file: &FileAst, content: &str) -> Vec<String> {
+    let words: HashSet<&str> = content
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .collect();
+    let mut imports = Vec::new();
+    for import in &file.imports {
+        if import.scope != element.hierarchy.module_path || import.is_reexport {
+            continue;
+        }
+        let path = |item: &str| match import.module_path.as_str() {
+            "" => item.to_string(),
+            module_path => format!("{}::{}", module_path, item),
+        };
+        if import.is_glob {
+            imports.push(format!("use {};", path("*")));
+            continue;
+        }
+        for item in &import.imported_items {
+            match &import.alias {
+                Some(alias) if words.contains(alias.as_str()) => {
+                    imports.push(format!("use {} as {};", path(item), alias));
                 }
+                None if words.contains(item.as_str()) => {
+                    imports.push(format!("use {};", path(item)))
+                }
+                _ => {}
             }
         }
-        
-        let complexity_metrics = ApiComplexityMetrics {
-            total_public_items: public_functions.len() + public_types.len() + public_traits.len(),
-            avg_parameter_count: 0.0, // TODO: Calculate from signatures
-            max_parameter_count: 0,   // TODO: Calculate from signatures
-            generic_usage_ratio: 0.0, // TODO: Calculate from generics
-            documentation_coverage: 0.0, // TODO: Calculate from docs
-        };
-        
-        Ok(ApiSurface {
-            public_functions,
-            public_types,
-            public_traits,
-            modules,
-            entry_points: vec!["main".to_string()], // TODO: Detect actual entry points
-            complexity_metrics,
-        })
-    }
-    
-    // Placeholder implementations for training example generation
-    fn create_code_explanation_example(&self, _chunk: &RagChunk, example_id: &mut usize) -> Result<Option<TrainingExample>> {
-        *example_id += 1;
-        Ok(None) // TODO: Implement training example generation
-    }
-    
-    fn create_code_completion_example(&self, _chunk: &RagChunk, example_id: &mut usize) -> Result<Option<TrainingExample>> {
-        *example_id += 1;
-        Ok(None) // TODO: Implement training example generation
-    }
-    
-    fn create_api_usage_example(&self, _chunk: &RagChunk, example_id: &mut usize) -> Result<Option<TrainingExample>> {
-        *example_id += 1;
-        Ok(None) // TODO: Implement training example generation
     }
+    // Items used under several imports, e.g. in `cfg` branches, are listed once.
+    let mut seen = HashSet::new();
+    imports.retain(|import| seen.insert(import.clone()));
+    imports
+}
+
+/// ID of the chunk created for `element`, derived from its stable element ID.
+pub(crate) fn chunk_id(element: &CodeElement) -> String {
+    format!("chunk_{}", element.id)
 }
 
 /// Convert RAG document to JSON format.
@@ -909,7 +1383,8 @@ pub fn format_as_json(document: &RagDocument, pretty: bool) -> Result<String> {
     }
 }
 
-/// Convert RAG document to JSONL format (one chunk per line).
+/// Convert RAG document to JSONL format (one chunk per line, after a line of
+/// metadata and before a line per removed chunk).
 pub fn format_as_jsonl(document: &RagDocument) -> Result<String> {
     let mut output = String::new();
     
@@ -923,45 +1398,277 @@ pub fn format_as_jsonl(document: &RagDocument) -> Result<String> {
         output.push('\n');
     }
     
+    // Then each chunk removed since the manifest the chunks were compared with
+    for id in document
+        .metadata
+        .changes
+        .iter()
+        .flat_map(|changes| &changes.removed)
+    {
+        output.push_str(&serde_json::to_string(&RemovedChunk {
+            removed: id.clone(),
+        })?);
+        output.push('\n');
+    }
+    
     Ok(output)
 }
 
-/// Convert RAG document to embedding-optimized format.
+/// Convert RAG document to embedding-optimized format, with inputs for its
+/// files, modules, types and functions linked to each other, coarsest first.
 pub fn format_for_embeddings(document: &RagDocument) -> Result<Vec<EmbeddingInput>> {
-    let mut inputs = Vec::new();
-    
-    for chunk in &document.chunks {
-        let text = match chunk.metadata.embedding_strategy {
-            EmbeddingStrategy::Combined => chunk.content_with_context.clone(),
-            EmbeddingStrategy::CodeOnly => {
-                // Extract just the code part
-                chunk.content.clone()
-            }
-            EmbeddingStrategy::DocumentationOnly => {
-                // Extract just the documentation
-                chunk.content.lines()
-                    .filter(|line| line.starts_with("///") || line.starts_with("//!"))
-                    .map(|line| line.trim_start_matches("///").trim_start_matches("//!").trim())
-                    .collect::<Vec<_>>()
-                    .join(" ")
-            }
-            EmbeddingStrategy::Specialized(_) => chunk.content_with_context.clone(),
-        };
-        
-        inputs.push(EmbeddingInput {
-            id: chunk.id.clone(),
-            text,
-            metadata: chunk.metadata.clone(),
-        });
+    Ok(embedding_inputs(&document.chunks))
+}
+
+/// The text of `chunk` to embed, following its embedding strategy, or its
+/// content when the strategy leaves nothing to embed.
+pub(crate) fn embedding_text(chunk: &RagChunk) -> String {
+    let text = match chunk.metadata.embedding_strategy {
+        EmbeddingStrategy::Combined => chunk.content_with_context.clone(),
+        EmbeddingStrategy::CodeOnly => {
+            // Extract just the code part
+            chunk.content.clone()
+        }
+        EmbeddingStrategy::DocumentationOnly => {
+            // Extract just the documentation
+            chunk
+                .content
+                .lines()
+                .filter(|line| line.starts_with("///") || line.starts_with("//!"))
+                .map(|line| {
+                    line.trim_start_matches("///")
+                        .trim_start_matches("//!")
+                        .trim()
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        EmbeddingStrategy::Specialized(_) => chunk.content_with_context.clone(),
+    };
+    // Providers reject empty texts, as of chunks without docs to embed.
+    if text.trim().is_empty() {
+        chunk.content.clone()
+    } else {
+        text
     }
-    
-    Ok(inputs)
 }
 
 /// Input format for embedding models.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmbeddingInput {
+    /// ID of the chunk of the input, or `file:<path>` and `module:<path>`
+    /// for file and module inputs
     pub id: String,
     pub text: String,
-    pub metadata: ChunkMetadata,
-}
\ No newline at end of file
+    pub granularity: EmbeddingGranularity,
+    /// ID of the input this one is part of, none for files
+    pub parent_id: Option<String>,
+    /// IDs of the inputs that are part of this one
+    pub child_ids: Vec<String>,
+    /// Metadata of the input's chunk; none for files, and for modules
+    /// without a `mod` item in the document
+    pub metadata: Option<ChunkMetadata>,
+}
+
+/// Part of the code an [`EmbeddingInput`] is of, from the coarsest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmbeddingGranularity {
+    File,
+    Module,
+    Type,
+    Function,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{ImportInfo, ReferenceGraph};
+
+    /// A file with a method in an impl of `Display` for `Message` in module
+    /// `net`.
+    fn file() -> FileAst {
+        let mut module = MockDataGenerator::code_element("net", ElementType::Module);
+        module.hierarchy.module_path = "crate".to_string();
+        let mut impl_block = MockDataGenerator::code_element("Message", ElementType::Impl);
+        impl_block.hierarchy.parent_id = Some(module.id.clone());
+        impl_block.hierarchy.impl_context = Some(ImplContext {
+            self_type: "crate::net::Message".to_string(),
+            trait_path: Some("Display".to_string()),
+            where_clause: None,
+        });
+        let mut method = MockDataGenerator::code_element("fmt", ElementType::Function);
+        method.hierarchy.parent_id = Some(impl_block.id.clone());
+        method.hierarchy.module_path = "crate::net".to_string();
+        method.hierarchy.impl_context = impl_block.hierarchy.impl_context.clone();
+
+        let mut file = MockDataGenerator::file_ast("src/net.rs".into(), 0);
+        file.elements = vec![module, impl_block, method];
+        file.imports.clear();
+        file
+    }
+
+    fn import(module_path: &str, item: Option<&str>, alias: Option<&str>) -> ImportInfo {
+        ImportInfo {
+            module_path: module_path.to_string(),
+            imported_items: item.map(str::to_string).into_iter().collect(),
+            is_glob: item.is_none(),
+            alias: alias.map(str::to_string),
+            is_reexport: false,
+            scope: "crate::net".to_string(),
+        }
+    }
+
+    fn part(line_start: usize, line_end: usize, content: &str) -> Part {
+        Part {
+            line_start,
+            line_end,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_radius_lines() {
+        let file = file();
+        let method = &file.elements[2];
+        let source = "// one\n// two\nfn fmt() {\n}\n// five\n// six\n";
+        let part = part(3, 4, "fn fmt() {\n}");
+        let content = |radius| {
+            let formatter = RagFormatter::new(RagConfig {
+                context_radius_lines: radius,
+                include_parent_signature: false,
+                ..RagConfig::default()
+            });
+            let content =
+                formatter.build_element_content_with_context(method, &file, &part, Some(source));
+            content.split_once("\n\n").unwrap().1.to_string()
+        };
+        assert_eq!(content(0), "fn fmt() {\n}\n// Complexity: 5");
+        assert_eq!(
+            content(1),
+            "// two\nfn fmt() {\n}\n// five\n// Complexity: 5"
+        );
+        // The radius stops at the start and end of the file.
+        assert_eq!(
+            content(5),
+            "// one\n// two\nfn fmt() {\n}\n// five\n// six\n// Complexity: 5"
+        );
+    }
+
+    #[test]
+    fn test_parent_headers() {
+        let file = file();
+        let method = &file.elements[2];
+        assert_eq!(
+            parent_headers(method, &file),
+            ["mod net", "impl Display for crate::net::Message"]
+        );
+
+        let formatter = RagFormatter::new(RagConfig::default());
+        let content = formatter.build_element_content_with_context(
+            method,
+            &file,
+            &part(3, 4, "fn fmt() {}"),
+            None,
+        );
+        assert!(content.starts_with(
+            "// File: net.rs\n// Module: crate::net\n// In: mod net\n\
+             // In: impl Display for crate::net::Message\n\n"
+        ));
+        let formatter = RagFormatter::new(RagConfig {
+            include_parent_signature: false,
+            ..RagConfig::default()
+        });
+        let content = formatter.build_element_content_with_context(
+            method,
+            &file,
+            &part(3, 4, "fn fmt() {}"),
+            None,
+        );
+        assert!(!content.contains("// In:"));
+
+        // Methods of impls outside the file name their impl.
+        let mut method = method.clone();
+        method.hierarchy.parent_id = Some("elsewhere".to_string());
+        method.hierarchy.impl_context.as_mut().unwrap().where_clause =
+            Some("where T: Clone".to_string());
+        assert_eq!(
+            parent_headers(&method, &file),
+            ["impl Display for crate::net::Message where T: Clone"]
+        );
+        // Top-level items have no headers.
+        assert!(parent_headers(&file.elements[0], &file).is_empty());
+    }
+
+    #[test]
+    fn test_relevant_imports() {
+        let mut file = file();
+        let mut reexport = import("crate::wire", Some("Frame"), None);
+        reexport.is_reexport = true;
+        let mut other_module = import("std::io", Some("Write"), None);
+        other_module.scope = "crate".to_string();
+        file.imports = vec![
+            import("std::fmt", Some("Display"), None),
+            import("std::fmt", Some("Formatter"), None),
+            import("std::collections", Some("HashMap"), Some("Map")),
+            import("std::collections", Some("BTreeMap"), Some("Sorted")),
+            import("crate::prelude", None, None),
+            import("", Some("serde"), None),
+            reexport,
+            other_module,
+            import("std::fmt", Some("Display"), None),
+        ];
+        let method = &file.elements[2];
+        let content =
+            "fn fmt(&self, f: &mut Formatter, seen: Map<u32, Frame>) -> Write { serde::x() }";
+        assert_eq!(
+            relevant_imports(method, &file, content),
+            [
+                "use std::fmt::Formatter;",
+                "use std::collections::HashMap as Map;",
+                "use crate::prelude::*;",
+                "use serde;",
+            ]
+        );
+
+        // Each import is listed once, however often it is declared.
+        let content = "impl Display for Message {}";
+        assert_eq!(
+            relevant_imports(method, &file, content),
+            ["use std::fmt::Display;", "use crate::prelude::*;"]
+        );
+    }
+
+    #[test]
+    fn test_chunk_references() {
+        let mut project = MockDataGenerator::project_ast(1, 3);
+        for element in &mut project.files[0].elements {
+            element.attributes.clear();
+        }
+        let ids: Vec<String> = project.files[0]
+            .elements
+            .iter()
+            .map(|e| e.id.clone())
+            .collect();
+        // `element_0` calls `element_1` and `element_2`, which has no chunk.
+        project.files[0].elements[2].visibility = Visibility::Private;
+        project.reference_graph = ReferenceGraph {
+            references: [(ids[0].clone(), vec![ids[1].clone(), ids[2].clone()])].into(),
+            referenced_by: [
+                (ids[1].clone(), vec![ids[0].clone()]),
+                (ids[2].clone(), vec![ids[0].clone()]),
+            ]
+            .into(),
+        };
+
+        let document = RagFormatter::new(RagConfig::default())
+            .format(&project)
+            .unwrap();
+        let chunks = &document.chunks;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].metadata.references, [chunks[1].id.clone()]);
+        assert!(chunks[0].metadata.referenced_by.is_empty());
+        assert!(chunks[1].metadata.references.is_empty());
+        assert_eq!(chunks[1].metadata.referenced_by, [chunks[0].id.clone()]);
+    }
+}
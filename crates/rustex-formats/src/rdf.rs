@@ -0,0 +1,446 @@
+//! RDF knowledge graph of a project, in Turtle or N-Triples.
+//!
+//! Files, elements and workspace crates become resources typed by the classes
+//! of the rustex ontology, linked by `contains`, `defines`, `implements`,
+//! `calls`, `uses` and `dependsOn`. The ontology itself is part of the
+//! output, so graph databases such as Neo4j (with n10s) or Stardog load a
+//! self-describing graph.
+//!
+//! Resources of a project are named `urn:rustex:<project>`, with
+//! `:file:<path>`, `:element:<id>` or `:crate:<name>` appended.
+
+use crate::knowledge::{build_knowledge_graph, EntityKind, EntityRef, Value};
+use anyhow::Result;
+use rustex_core::ProjectAst;
+use std::collections::HashMap;
+
+/// Namespace of the rustex ontology.
+pub const ONTOLOGY_NAMESPACE: &str = "https://github.com/jarnura/rustex/ontology#";
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Prefixes of the namespaces in Turtle output.
+const PREFIXES: [(&str, &str); 4] = [
+    ("rx", ONTOLOGY_NAMESPACE),
+    ("rdf", RDF),
+    ("rdfs", RDFS),
+    ("xsd", XSD),
+];
+
+/// Classes of the ontology, with their superclass and description.
+const CLASSES: &[(&str, Option<&str>, &str)] = &[
+    ("Project", None, "A Rust project or Cargo workspace"),
+    ("Crate", None, "A crate of a Cargo workspace"),
+    ("File", None, "A Rust source file"),
+    (
+        "Element",
+        None,
+        "An item extracted from source, such as a function or struct",
+    ),
+    ("Function", Some("Element"), "A function or method"),
+    ("Struct", Some("Element"), "A struct"),
+    ("Enum", Some("Element"), "An enum"),
+    ("Trait", Some("Element"), "A trait"),
+    ("Impl", Some("Element"), "An impl block"),
+    ("Module", Some("Element"), "A module"),
+    ("Constant", Some("Element"), "A constant"),
+    ("Static", Some("Element"), "A static variable"),
+    ("TypeAlias", Some("Element"), "A type alias"),
+    ("Macro", Some("Element"), "A macro definition"),
+    ("Union", Some("Element"), "A union"),
+    (
+        "Closure",
+        Some("Element"),
+        "A closure inside a function body",
+    ),
+    (
+        "AsyncBlock",
+        Some("Element"),
+        "An async block inside a function body",
+    ),
+];
+
+/// Properties linking resources, with their description.
+const OBJECT_PROPERTIES: &[(&str, &str)] = &[
+    (
+        "contains",
+        "A project containing a crate or file, a crate containing a file, or an element \
+         containing a nested element",
+    ),
+    ("defines", "A file defining an element"),
+    (
+        "implements",
+        "A type implementing a trait of the project, or an impl block when its type \
+         wasn't extracted",
+    ),
+    ("calls", "A function calling a function of the project"),
+    ("uses", "An element using a type of the project"),
+    (
+        "dependsOn",
+        "A crate depending on another crate of the workspace",
+    ),
+];
+
+/// Properties with literal values, with their description.
+const DATATYPE_PROPERTIES: &[(&str, &str)] = &[
+    ("name", "Name of a project, crate or element"),
+    ("version", "Version of a project or crate"),
+    ("path", "Path of a file relative to the project root"),
+    ("qualifiedName", "Fully qualified name of an element"),
+    (
+        "visibility",
+        "Visibility of an element as written, or private",
+    ),
+    ("signature", "Signature of a function"),
+    ("documentation", "Documentation comments of an element"),
+    ("lineStart", "First line of an element"),
+    ("lineEnd", "Last line of an element"),
+    ("complexity", "Cyclomatic complexity of an element"),
+    (
+        "linesOfCode",
+        "Lines of code of a file, excluding comments and blank lines",
+    ),
+    (
+        "traitPath",
+        "Path of the trait an impl block implements, as written",
+    ),
+    (
+        "isGenerated",
+        "Whether an element was produced by macro expansion",
+    ),
+];
+
+enum Object {
+    Iri(String),
+    Text(String),
+    Integer(u64),
+    Boolean(bool),
+}
+
+/// Statements grouped by subject, in the order subjects were first seen.
+#[derive(Default)]
+struct Statements {
+    subjects: Vec<String>,
+    by_subject: HashMap<String, Vec<(String, Object)>>,
+}
+
+impl Statements {
+    fn add(&mut self, subject: &str, predicate: String, object: Object) {
+        let statements = self
+            .by_subject
+            .entry(subject.to_string())
+            .or_insert_with(|| {
+                self.subjects.push(subject.to_string());
+                Vec::new()
+            });
+        let duplicate = statements.iter().any(|(existing_predicate, existing)| {
+            *existing_predicate == predicate && same_object(existing, &object)
+        });
+        if !duplicate {
+            statements.push((predicate, object));
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&str, &[(String, Object)])> {
+        self.subjects
+            .iter()
+            .map(|subject| (subject.as_str(), self.by_subject[subject].as_slice()))
+    }
+}
+
+fn same_object(a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (Object::Iri(a), Object::Iri(b)) | (Object::Text(a), Object::Text(b)) => a == b,
+        (Object::Integer(a), Object::Integer(b)) => a == b,
+        (Object::Boolean(a), Object::Boolean(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn rx(name: &str) -> String {
+    format!("{}{}", ONTOLOGY_NAMESPACE, name)
+}
+
+fn rdf_type() -> String {
+    format!("{}type", RDF)
+}
+
+/// Render `project_ast` and the ontology as Turtle.
+pub fn format_as_turtle(project_ast: &ProjectAst) -> Result<String> {
+    let mut turtle = format!(
+        "# Knowledge graph of {}, in the rustex ontology\n\n",
+        project_ast.project.name
+    );
+    for (prefix, namespace) in PREFIXES {
+        turtle.push_str(&format!("@prefix {}: <{}> .\n", prefix, namespace));
+    }
+
+    for (section, statements) in [
+        ("Ontology", ontology()),
+        ("Project", project_statements(project_ast)),
+    ] {
+        turtle.push_str(&format!("\n# {}\n", section));
+        for (subject, predicates) in statements.iter() {
+            turtle.push('\n');
+            turtle.push_str(&turtle_iri(subject));
+            for (index, (predicate, object)) in predicates.iter().enumerate() {
+                let predicate = if *predicate == rdf_type() {
+                    "a".to_string()
+                } else {
+                    turtle_iri(predicate)
+                };
+                let separator = if index == 0 { " " } else { " ;\n    " };
+                turtle.push_str(&format!(
+                    "{}{} {}",
+                    separator,
+                    predicate,
+                    turtle_object(object)
+                ));
+            }
+            turtle.push_str(" .\n");
+        }
+    }
+    Ok(turtle)
+}
+
+/// Render `project_ast` and the ontology as N-Triples, one statement per
+/// line.
+pub fn format_as_ntriples(project_ast: &ProjectAst) -> Result<String> {
+    let mut ntriples = String::new();
+    for statements in [ontology(), project_statements(project_ast)] {
+        for (subject, predicates) in statements.iter() {
+            for (predicate, object) in predicates {
+                ntriples.push_str(&format!(
+                    "{} {} {} .\n",
+                    iri(subject),
+                    iri(predicate),
+                    ntriples_object(object)
+                ));
+            }
+        }
+    }
+    Ok(ntriples)
+}
+
+/// The classes and properties of the ontology, with labels and comments.
+fn ontology() -> Statements {
+    let mut statements = Statements::default();
+    let mut describe = |name: &str, kind: String, comment: &str| {
+        let subject = rx(name);
+        statements.add(&subject, rdf_type(), Object::Iri(kind));
+        statements.add(
+            &subject,
+            format!("{}label", RDFS),
+            Object::Text(name.to_string()),
+        );
+        statements.add(
+            &subject,
+            format!("{}comment", RDFS),
+            Object::Text(comment.to_string()),
+        );
+        subject
+    };
+
+    let mut subclasses = Vec::new();
+    for (name, superclass, comment) in CLASSES {
+        let subject = describe(name, format!("{}Class", RDFS), comment);
+        if let Some(superclass) = superclass {
+            subclasses.push((subject, rx(superclass)));
+        }
+    }
+    for (name, comment) in OBJECT_PROPERTIES {
+        describe(name, format!("{}Property", RDF), comment);
+    }
+    for (name, comment) in DATATYPE_PROPERTIES {
+        describe(name, format!("{}Property", RDF), comment);
+    }
+    for (subject, superclass) in subclasses {
+        statements.add(
+            &subject,
+            format!("{}subClassOf", RDFS),
+            Object::Iri(superclass),
+        );
+    }
+    statements
+}
+
+fn project_statements(project_ast: &ProjectAst) -> Statements {
+    let mut statements = Statements::default();
+    let project = format!("urn:rustex:{}", project_ast.project.name);
+    let resource = |reference: &EntityRef| match reference.kind {
+        EntityKind::Project => project.clone(),
+        EntityKind::Crate => format!("{}:crate:{}", project, reference.id),
+        EntityKind::File => format!("{}:file:{}", project, reference.id),
+        EntityKind::Element => format!("{}:element:{}", project, reference.id),
+    };
+
+    let graph = build_knowledge_graph(project_ast);
+    for entity in &graph.entities {
+        let subject = resource(&entity.reference);
+        statements.add(&subject, rdf_type(), Object::Iri(rx(&entity.class)));
+        for (property, value) in &entity.properties {
+            let object = match value {
+                Value::Text(text) => Object::Text(text.clone()),
+                Value::Integer(value) => Object::Integer(*value),
+                Value::Boolean(value) => Object::Boolean(*value),
+            };
+            statements.add(&subject, rx(property), object);
+        }
+    }
+    for relationship in &graph.relationships {
+        statements.add(
+            &resource(&relationship.from),
+            rx(relationship.kind.name()),
+            Object::Iri(resource(&relationship.to)),
+        );
+    }
+    statements
+}
+
+/// `iri` as an IRI reference, with the characters IRIs can't hold
+/// percent-encoded.
+fn iri(iri: &str) -> String {
+    let mut encoded = String::with_capacity(iri.len() + 2);
+    encoded.push('<');
+    for c in iri.chars() {
+        if c <= ' ' || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\') {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded.push('>');
+    encoded
+}
+
+/// `full` as a prefixed name when it is in a namespace with a prefix and its
+/// local name needs no escaping.
+fn turtle_iri(full: &str) -> String {
+    for (prefix, namespace) in PREFIXES {
+        if let Some(local) = full.strip_prefix(namespace) {
+            let simple = local.starts_with(|c: char| c.is_ascii_alphabetic())
+                && local.chars().all(|c| c.is_ascii_alphanumeric());
+            if simple {
+                return format!("{}:{}", prefix, local);
+            }
+        }
+    }
+    iri(full)
+}
+
+fn turtle_object(object: &Object) -> String {
+    match object {
+        Object::Iri(full) => turtle_iri(full),
+        Object::Text(text) => literal(text),
+        Object::Integer(value) => value.to_string(),
+        Object::Boolean(value) => value.to_string(),
+    }
+}
+
+fn ntriples_object(object: &Object) -> String {
+    match object {
+        Object::Iri(full) => iri(full),
+        Object::Text(text) => literal(text),
+        Object::Integer(value) => format!("\"{}\"^^<{}integer>", value, XSD),
+        Object::Boolean(value) => format!("\"{}\"^^<{}boolean>", value, XSD),
+    }
+}
+
+/// `text` as a quoted string literal.
+fn literal(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+
+    /// A name with a quote, a backslash, a newline, a space and non-ASCII
+    /// characters.
+    const NAME: &str = "say \"hé\"\\\nnow";
+
+    #[test]
+    fn test_iri() {
+        assert_eq!(iri("urn:rustex:demo"), "<urn:rustex:demo>");
+        assert_eq!(
+            iri(&format!("urn:rustex:demo:element:{}", NAME)),
+            "<urn:rustex:demo:element:say%20%22hé%22%5C%0Anow>"
+        );
+        assert_eq!(
+            iri("urn:x:<a>{b}|c^d`e"),
+            "<urn:x:%3Ca%3E%7Bb%7D%7Cc%5Ed%60e>"
+        );
+    }
+
+    #[test]
+    fn test_turtle_iri() {
+        assert_eq!(turtle_iri(&rx("qualifiedName")), "rx:qualifiedName");
+        assert_eq!(turtle_iri(&format!("{}label", RDFS)), "rdfs:label");
+        // Local names needing escapes, and other namespaces, stay full IRIs.
+        assert_eq!(
+            turtle_iri(&rx("a b")),
+            "<https://github.com/jarnura/rustex/ontology#a%20b>"
+        );
+        assert_eq!(
+            turtle_iri(&rx("1st")),
+            "<https://github.com/jarnura/rustex/ontology#1st>"
+        );
+        assert_eq!(turtle_iri("urn:rustex:demo"), "<urn:rustex:demo>");
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(literal(NAME), "\"say \\\"hé\\\"\\\\\\nnow\"");
+        assert_eq!(literal("tab\there\r"), "\"tab\\there\\r\"");
+        assert_eq!(literal("日本"), "\"日本\"");
+    }
+
+    /// A project named `my project` with an element named [`NAME`].
+    fn project() -> ProjectAst {
+        let mut project = MockDataGenerator::project_ast(1, 1);
+        project.project.name = "my project".to_string();
+        let element = &mut project.files[0].elements[0];
+        element.id = "fn é".to_string();
+        element.name = NAME.to_string();
+        project
+    }
+
+    #[test]
+    fn test_turtle_escaping() {
+        let turtle = format_as_turtle(&project()).unwrap();
+        assert!(turtle.starts_with("# Knowledge graph of my project, in the rustex ontology\n"));
+        assert!(turtle.contains("\n<urn:rustex:my%20project:element:fn%20é> a rx:Function ;\n"));
+        assert!(turtle.contains("\n    rx:name \"say \\\"hé\\\"\\\\\\nnow\" ;\n"));
+    }
+
+    #[test]
+    fn test_ntriples_escaping() {
+        let ntriples = format_as_ntriples(&project()).unwrap();
+        let name = format!(
+            "<urn:rustex:my%20project:element:fn%20é> <{}name> \"say \\\"hé\\\"\\\\\\nnow\" .",
+            ONTOLOGY_NAMESPACE
+        );
+        assert!(ntriples.lines().any(|line| line == name));
+        // Every statement stays on its own line.
+        assert!(ntriples.lines().all(|line| line.ends_with(" .")));
+    }
+}
@@ -0,0 +1,86 @@
+//! Plugin diagnostics in SARIF 2.1.0, for code scanning services.
+//!
+//! [`format_as_sarif`] writes one run of the `rustex` tool with a result per
+//! plugin message that reports a rule violation, such as the findings of the
+//! `security-analyzer` plugin; other messages are left out. Results name the
+//! plugin that reported them, and their file URIs are relative to the
+//! project root, the `%SRCROOT%` of the run.
+
+use anyhow::Result;
+use rustex_core::ProjectAst;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Render the diagnostics of the plugins run on `project_ast` as a SARIF log.
+pub fn format_as_sarif(project_ast: &ProjectAst, pretty: bool) -> Result<String> {
+    let mut rules = BTreeMap::new();
+    let mut results = Vec::new();
+    for (plugin, plugin_results) in &project_ast.plugin_results {
+        for message in &plugin_results.messages {
+            let Some(rule) = &message.rule else {
+                continue;
+            };
+            rules
+                .entry(rule.clone())
+                .or_insert_with(|| json!({ "id": rule, "properties": { "plugin": plugin } }));
+            let mut result = json!({
+                "ruleId": rule,
+                "level": sarif_level(&message.level),
+                "message": { "text": message.message },
+                "properties": { "plugin": plugin },
+            });
+            if let Some(file) = &message.file {
+                let uri = file.display().to_string().replace('\\', "/");
+                let mut location = json!({
+                    "artifactLocation": { "uri": uri, "uriBaseId": "%SRCROOT%" },
+                });
+                if let Some(line) = message.line {
+                    location["region"] = json!({ "startLine": line });
+                }
+                result["locations"] = json!([{ "physicalLocation": location }]);
+            }
+            results.push(result);
+        }
+    }
+
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": "rustex",
+                "version": env!("CARGO_PKG_VERSION"),
+                "informationUri": "https://github.com/jarnura/rustex",
+                "rules": rules.into_values().collect::<Vec<Value>>(),
+            },
+        },
+        "results": results,
+    });
+    // Base URIs must be absolute; viewers resolve relative URIs themselves.
+    let root = &project_ast.project.root_path;
+    if let Ok(root) = root.canonicalize() {
+        let root = root.display().to_string().replace('\\', "/");
+        run["originalUriBaseIds"] = json!({
+            "%SRCROOT%": { "uri": format!("file://{}/", root.trim_end_matches('/')) },
+        });
+    }
+    let sarif = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [run],
+    });
+    Ok(if pretty {
+        serde_json::to_string_pretty(&sarif)?
+    } else {
+        serde_json::to_string(&sarif)?
+    })
+}
+
+/// SARIF level of a plugin message level.
+fn sarif_level(level: &str) -> &'static str {
+    match level {
+        "error" => "error",
+        "warning" => "warning",
+        _ => "note",
+    }
+}
@@ -0,0 +1,410 @@
+//! SCIP code intelligence index of a project.
+//!
+//! [`format_as_scip`] writes an `scip.Index` message, as uploaded to
+//! Sourcegraph with `src code-intel upload`, giving hover documentation,
+//! go-to-definition and find-references without running rust-analyzer.
+//! Definitions are the names of extracted elements and references are the
+//! resolved cross-references, so navigation covers items of the project only.
+//!
+//! Symbols are `rustex cargo <crate> <version>` followed by descriptors of
+//! the element's path, such as `net/Connection#open().`.
+
+use crate::mdbook::crate_name;
+use anyhow::Result;
+use prost::Message;
+use rustex_core::{CodeElement, CodeLocation, ElementType, ProjectAst, ReferenceType};
+use std::collections::{HashMap, HashSet};
+
+/// Symbol role of a definition.
+const DEFINITION: i32 = 0x1;
+/// Symbol role of an import.
+const IMPORT: i32 = 0x2;
+/// Position encoding of columns counted in characters.
+const UTF32_OFFSETS: i32 = 3;
+/// Text encoding of the indexed files.
+const UTF8: i32 = 1;
+
+/// Messages of the SCIP schema, as prost generates them from `scip.proto`,
+/// with the fields written.
+mod messages {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Index {
+        #[prost(message, optional, tag = "1")]
+        pub metadata: Option<Metadata>,
+        #[prost(message, repeated, tag = "2")]
+        pub documents: Vec<Document>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Metadata {
+        #[prost(message, optional, tag = "2")]
+        pub tool_info: Option<ToolInfo>,
+        #[prost(string, tag = "3")]
+        pub project_root: String,
+        #[prost(int32, tag = "4")]
+        pub text_document_encoding: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ToolInfo {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub version: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Document {
+        #[prost(string, tag = "1")]
+        pub relative_path: String,
+        #[prost(message, repeated, tag = "2")]
+        pub occurrences: Vec<Occurrence>,
+        #[prost(message, repeated, tag = "3")]
+        pub symbols: Vec<SymbolInformation>,
+        #[prost(string, tag = "4")]
+        pub language: String,
+        #[prost(string, tag = "5")]
+        pub text: String,
+        #[prost(int32, tag = "6")]
+        pub position_encoding: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Occurrence {
+        #[prost(int32, repeated, tag = "1")]
+        pub range: Vec<i32>,
+        #[prost(string, tag = "2")]
+        pub symbol: String,
+        #[prost(int32, tag = "3")]
+        pub symbol_roles: i32,
+        #[prost(int32, repeated, tag = "7")]
+        pub enclosing_range: Vec<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SymbolInformation {
+        #[prost(string, tag = "1")]
+        pub symbol: String,
+        #[prost(string, repeated, tag = "3")]
+        pub documentation: Vec<String>,
+        #[prost(message, repeated, tag = "4")]
+        pub relationships: Vec<Relationship>,
+        #[prost(string, tag = "6")]
+        pub display_name: String,
+        #[prost(message, optional, tag = "7")]
+        pub signature_documentation: Option<Document>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Relationship {
+        #[prost(string, tag = "1")]
+        pub symbol: String,
+        #[prost(bool, tag = "3")]
+        pub is_implementation: bool,
+    }
+}
+
+/// Encode `project_ast` as a SCIP index.
+pub fn format_as_scip(project_ast: &ProjectAst) -> Result<Vec<u8>> {
+    let symbols = element_symbols(project_ast);
+
+    // Traits each type implements, for "find implementations".
+    let mut implemented: HashMap<&str, Vec<&str>> = HashMap::new();
+    for trait_impl in &project_ast.impls {
+        if let (Some(type_id), Some(trait_id)) =
+            (&trait_impl.type_element_id, &trait_impl.trait_element_id)
+        {
+            let traits = implemented.entry(type_id.as_str()).or_default();
+            if !traits.contains(&trait_id.as_str()) {
+                traits.push(trait_id);
+            }
+        }
+    }
+
+    let mut documents = Vec::new();
+    for file in &project_ast.files {
+        let mut occurrences = Vec::new();
+        let mut document_symbols = Vec::new();
+
+        for element in &file.elements {
+            let Some(symbol) = symbols.get(element.id.as_str()) else {
+                continue;
+            };
+            if let Some(name_location) = &element.name_location {
+                occurrences.push(messages::Occurrence {
+                    range: range(name_location),
+                    symbol: symbol.clone(),
+                    symbol_roles: DEFINITION,
+                    enclosing_range: range(&element.location),
+                });
+            }
+
+            let relationships = implemented
+                .get(element.id.as_str())
+                .into_iter()
+                .flatten()
+                .filter_map(|trait_id| symbols.get(trait_id))
+                .map(|trait_symbol| messages::Relationship {
+                    symbol: trait_symbol.clone(),
+                    is_implementation: true,
+                })
+                .collect();
+            let signature_documentation =
+                element
+                    .signature
+                    .as_ref()
+                    .map(|signature| messages::Document {
+                        language: "Rust".to_string(),
+                        text: signature.clone(),
+                        ..Default::default()
+                    });
+            document_symbols.push(messages::SymbolInformation {
+                symbol: symbol.clone(),
+                documentation: if element.doc_comments.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![element.doc_comments.join("\n")]
+                },
+                relationships,
+                display_name: element.name.clone(),
+                signature_documentation,
+            });
+        }
+
+        for reference in &file.cross_references {
+            let symbol = reference
+                .to_element_id
+                .as_deref()
+                .and_then(|id| symbols.get(id));
+            let Some(symbol) = symbol else {
+                continue;
+            };
+            let is_import = reference.reference_type == ReferenceType::ImportReference;
+            occurrences.push(messages::Occurrence {
+                range: range(&reference.location),
+                symbol: symbol.clone(),
+                symbol_roles: if is_import { IMPORT } else { 0 },
+                enclosing_range: Vec::new(),
+            });
+        }
+
+        occurrences.sort_by(|a, b| a.range.cmp(&b.range));
+        let relative_path = file.relative_path.display().to_string();
+        documents.push(messages::Document {
+            relative_path: relative_path.replace('\\', "/"),
+            occurrences,
+            symbols: document_symbols,
+            language: "Rust".to_string(),
+            text: String::new(),
+            position_encoding: UTF32_OFFSETS,
+        });
+    }
+
+    let index = messages::Index {
+        metadata: Some(messages::Metadata {
+            tool_info: Some(messages::ToolInfo {
+                name: "rustex".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }),
+            project_root: project_root(project_ast),
+            text_document_encoding: UTF8,
+        }),
+        documents,
+    };
+    Ok(index.encode_to_vec())
+}
+
+/// Symbols of the elements of `project_ast` that can be referenced, by
+/// element ID. Closures, async blocks and impl blocks have none.
+fn element_symbols(project_ast: &ProjectAst) -> HashMap<&str, String> {
+    let mut symbols = HashMap::new();
+    let mut taken = HashSet::new();
+    for element in project_ast.files.iter().flat_map(|file| &file.elements) {
+        let Some(descriptors) = descriptors(element) else {
+            continue;
+        };
+        let crate_name = crate_name(element);
+        let version = project_ast
+            .workspace
+            .as_ref()
+            .and_then(|workspace| {
+                workspace
+                    .crates
+                    .iter()
+                    .find(|krate| krate.name.replace('-', "_") == crate_name)
+            })
+            .map_or(project_ast.project.version.as_str(), |krate| {
+                krate.version.as_str()
+            });
+        let package = format!(
+            "rustex cargo {} {} ",
+            package_field(crate_name),
+            package_field(version)
+        );
+
+        let mut symbol = format!("{}{}", package, descriptors);
+        // Methods of the same name in several impls of a type are told apart
+        // by a disambiguator, as SCIP does for overloads.
+        if let Some(method) = descriptors.strip_suffix("().") {
+            let mut disambiguator = 1;
+            while taken.contains(&symbol) {
+                symbol = format!("{}{}(+{}).", package, method, disambiguator);
+                disambiguator += 1;
+            }
+        }
+        taken.insert(symbol.clone());
+        symbols.insert(element.id.as_str(), symbol);
+    }
+    symbols
+}
+
+/// Descriptors of the path of `element`: modules are namespaces, types end
+/// with `#`, functions with `().`, values with `.` and macros with `!`.
+fn descriptors(element: &CodeElement) -> Option<String> {
+    let suffix = match element.element_type {
+        ElementType::Module => "/",
+        ElementType::Struct
+        | ElementType::Enum
+        | ElementType::Union
+        | ElementType::Trait
+        | ElementType::TypeAlias => "#",
+        ElementType::Function => "().",
+        ElementType::Constant | ElementType::Static => ".",
+        ElementType::Macro => "!",
+        ElementType::Impl | ElementType::Closure | ElementType::AsyncBlock => return None,
+    };
+    let qualified_name = &element.hierarchy.qualified_name;
+    let mut segments: Vec<&str> = qualified_name.split("::").collect();
+    if segments.first() == Some(&"crate") {
+        segments.remove(0);
+    }
+    let own = segments.pop()?;
+
+    let mut descriptors = String::new();
+    let method = element.hierarchy.impl_context.is_some();
+    for (index, segment) in segments.iter().enumerate() {
+        let is_self_type = method && index + 1 == segments.len();
+        descriptors.push_str(&name(segment));
+        descriptors.push(if is_self_type { '#' } else { '/' });
+    }
+    descriptors.push_str(&name(own));
+    descriptors.push_str(suffix);
+    Some(descriptors)
+}
+
+/// `identifier` as a SCIP name, in backticks unless it is made of ASCII
+/// letters, digits, `_`, `+`, `-` and `$`.
+fn name(identifier: &str) -> String {
+    let simple = !identifier.is_empty()
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '$'));
+    if simple {
+        identifier.to_string()
+    } else {
+        format!("`{}`", identifier.replace('`', "``"))
+    }
+}
+
+/// `text` as the name or version of a package, with spaces doubled and `.`
+/// standing for an empty one.
+fn package_field(text: &str) -> String {
+    if text.is_empty() {
+        ".".to_string()
+    } else {
+        text.replace(' ', "  ")
+    }
+}
+
+/// The SCIP range of `location`, with zero-based lines: start line, start
+/// column and end column, with the end line before the end column when it
+/// differs.
+fn range(location: &CodeLocation) -> Vec<i32> {
+    let start_line = location.line_start.saturating_sub(1) as i32;
+    let end_line = location.line_end.saturating_sub(1) as i32;
+    let (start, end) = (location.char_start as i32, location.char_end as i32);
+    if start_line == end_line {
+        vec![start_line, start, end]
+    } else {
+        vec![start_line, start, end_line, end]
+    }
+}
+
+/// The `file://` URI of the project root, made absolute if it exists.
+fn project_root(project_ast: &ProjectAst) -> String {
+    let root = &project_ast.project.root_path;
+    let root = std::fs::canonicalize(root).unwrap_or_else(|_| root.clone());
+    let path = root.display().to_string().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{CrossReference, ReferenceContext};
+
+    #[test]
+    fn test_prost_round_trip() {
+        let mut project = MockDataGenerator::project_ast(1, 2);
+        let file = &mut project.files[0];
+        let mut name_location = file.elements[0].location.clone();
+        (name_location.line_start, name_location.line_end) = (3, 3);
+        (name_location.char_start, name_location.char_end) = (7, 16);
+        file.elements[0].name_location = Some(name_location.clone());
+        let mut location = name_location;
+        (location.line_start, location.line_end) = (8, 8);
+        (location.char_start, location.char_end) = (4, 13);
+        file.cross_references.push(CrossReference {
+            from_element_id: file.elements[1].id.clone(),
+            to_element_id: Some(file.elements[0].id.clone()),
+            reference_type: ReferenceType::ImportReference,
+            reference_text: "element_0".to_string(),
+            location,
+            is_resolved: true,
+            context: ReferenceContext {
+                is_definition: false,
+                scope: "crate::test".to_string(),
+                metadata: HashMap::new(),
+            },
+        });
+
+        let bytes = format_as_scip(&project).unwrap();
+        let index = messages::Index::decode(bytes.as_slice()).unwrap();
+
+        let metadata = index.metadata.unwrap();
+        assert_eq!(metadata.tool_info.unwrap().name, "rustex");
+        assert_eq!(metadata.text_document_encoding, 1);
+        let document = &index.documents[0];
+        assert_eq!(document.relative_path, "file_0.rs");
+        assert_eq!(document.position_encoding, 3);
+
+        let symbol = "rustex cargo crate 0.1.0 test/element_0().";
+        let occurrences: Vec<(&[i32], &str, i32)> = document
+            .occurrences
+            .iter()
+            .map(|occurrence| {
+                let range = occurrence.range.as_slice();
+                (range, occurrence.symbol.as_str(), occurrence.symbol_roles)
+            })
+            .collect();
+        assert_eq!(
+            occurrences,
+            [
+                (&[2, 7, 16][..], symbol, DEFINITION),
+                (&[7, 4, 13][..], symbol, IMPORT)
+            ]
+        );
+        assert_eq!(document.occurrences[0].enclosing_range, [0, 0, 9, 100]);
+
+        let information = &document.symbols[0];
+        assert_eq!(information.symbol, symbol);
+        assert_eq!(information.documentation, ["Documentation for element_0"]);
+        assert_eq!(information.display_name, "element_0");
+        assert_eq!(document.symbols.len(), 2);
+    }
+}
@@ -0,0 +1,687 @@
+//! Semantic analysis of a project for RAG documents.
+//!
+//! Each pass works on the elements that have a chunk in the document, so
+//! everything it finds points at chunks a retriever can fetch:
+//!
+//! - [`concept_hierarchy`]: a concept for each module, nested as the
+//!   modules are, and for each trait, under its module and with the chunks
+//!   of its implementations
+//! - [`relationships`]: types implementing traits, elements containing
+//!   nested ones, and elements using others by calls or type references,
+//!   from the cross-reference graph
+//! - [`vocabulary`]: the words of identifiers, such as `chunk` and
+//!   `manifest` in `ChunkManifest`, with how often they occur
+
+use crate::mdbook::{chapter_title, crate_name};
+use crate::rag::{
+    ConceptNode, ConceptType, RelationshipType, SemanticRelationship, VocabularyEntry,
+};
+use rustex_core::{CodeElement, ElementType, ProjectAst, ReferenceType};
+use std::collections::{BTreeMap, HashMap};
+
+/// Chunk IDs of the elements that have a chunk in the document, by element
+/// ID.
+pub(crate) type ChunkIds<'a> = HashMap<&'a str, String>;
+
+/// Fewest identifiers a word must occur in to be in the vocabulary.
+const MIN_TERM_FREQUENCY: usize = 2;
+
+/// Most chunks referenced by a vocabulary entry.
+const MAX_TERM_CHUNKS: usize = 20;
+
+/// Most related terms of a vocabulary entry.
+const MAX_RELATED_TERMS: usize = 5;
+
+/// Words of identifiers that say little about a project.
+const STOP_WORDS: &[&str] = &[
+    "and", "for", "from", "get", "impl", "into", "mut", "new", "ref", "self", "set", "the", "with",
+];
+
+/// The elements of `project_ast` that have a chunk, with its ID.
+fn chunked<'a>(
+    project_ast: &'a ProjectAst,
+    chunk_ids: &'a ChunkIds,
+) -> impl Iterator<Item = (&'a CodeElement, &'a String)> {
+    project_ast
+        .files
+        .iter()
+        .flat_map(|file| &file.elements)
+        .filter_map(|element| Some((element, chunk_ids.get(element.id.as_str())?)))
+}
+
+/// The concepts of the modules and traits of `project_ast`, parents before
+/// their children.
+pub(crate) fn concept_hierarchy(
+    project_ast: &ProjectAst,
+    chunk_ids: &ChunkIds,
+) -> Vec<ConceptNode> {
+    let mut modules: BTreeMap<(&str, String), ModuleConcept> = BTreeMap::new();
+    let mut traits: Vec<(String, ConceptNode)> = Vec::new();
+    for (element, chunk_id) in chunked(project_ast, chunk_ids) {
+        let crate_name = crate_name(element);
+        let hierarchy = &element.hierarchy;
+        let module = match element.element_type {
+            ElementType::Module => &hierarchy.qualified_name,
+            _ => &hierarchy.module_path,
+        };
+        add_module(&mut modules, crate_name, module);
+        let concept = modules
+            .get_mut(&(crate_name, module.clone()))
+            .expect("modules were just added");
+        concept.node.related_chunks.push(chunk_id.clone());
+        if element.element_type != ElementType::Module {
+            *concept.kinds.entry(kind_name(element)).or_insert(0) += 1;
+        }
+        match element.element_type {
+            ElementType::Module => concept.node.description = doc_summary(element),
+            ElementType::Trait => {
+                let id = format!(
+                    "trait:{}",
+                    chapter_title(crate_name, &hierarchy.qualified_name)
+                );
+                concept.node.child_concepts.push(id.clone());
+                let node = ConceptNode {
+                    id,
+                    name: element.name.clone(),
+                    concept_type: ConceptType::Trait,
+                    description: doc_summary(element),
+                    related_chunks: vec![chunk_id.clone()],
+                    parent_concepts: vec![concept.node.id.clone()],
+                    child_concepts: Vec::new(),
+                    importance_score: 0.0,
+                };
+                traits.push((element.id.clone(), node));
+            }
+            _ => {}
+        }
+    }
+
+    // Traits relate to the chunks of the types implementing them.
+    for trait_impl in &project_ast.impls {
+        let Some(trait_id) = &trait_impl.trait_element_id else {
+            continue;
+        };
+        let implementor = trait_impl
+            .type_element_id
+            .as_deref()
+            .and_then(|id| chunk_ids.get(id))
+            .or_else(|| chunk_ids.get(trait_impl.impl_id.as_str()));
+        let node = traits.iter_mut().find(|(id, _)| id == trait_id);
+        if let (Some(implementor), Some((_, node))) = (implementor, node) {
+            if !node.related_chunks.contains(implementor) {
+                node.related_chunks.push(implementor.clone());
+            }
+        }
+    }
+
+    // Modules count the chunks of their submodules, added after them.
+    let mut sizes: HashMap<String, usize> = HashMap::new();
+    for concept in modules.values().rev() {
+        let size = concept.node.related_chunks.len()
+            + concept
+                .node
+                .child_concepts
+                .iter()
+                .filter_map(|child| sizes.get(child))
+                .sum::<usize>();
+        sizes.insert(concept.node.id.clone(), size);
+    }
+    let mut nodes: Vec<ConceptNode> = modules
+        .into_values()
+        .map(|mut concept| {
+            if concept.node.description.is_empty() {
+                concept.node.description = concept.describe();
+            }
+            concept.node
+        })
+        .collect();
+    for (_, mut node) in traits {
+        if node.description.is_empty() {
+            let implementations = node.related_chunks.len() - 1;
+            node.description = format!(
+                "Trait `{}` with {}",
+                node.name,
+                count(implementations, "implementation")
+            );
+        }
+        sizes.insert(node.id.clone(), node.related_chunks.len());
+        nodes.push(node);
+    }
+
+    // Importance is the size of a concept relative to the largest one.
+    let largest = sizes.values().copied().max().unwrap_or(0).max(1) as f64;
+    for node in &mut nodes {
+        let size = sizes.get(&node.id).copied().unwrap_or(0) as f64;
+        node.importance_score = (size / largest * 100.0).round() / 100.0;
+    }
+    nodes
+}
+
+/// The concept of a module while it is being built.
+struct ModuleConcept {
+    node: ConceptNode,
+    /// Number of elements of each kind in the module
+    kinds: BTreeMap<&'static str, usize>,
+}
+
+impl ModuleConcept {
+    /// A description of the module by what it holds, for modules without
+    /// docs.
+    fn describe(&self) -> String {
+        let kinds: Vec<String> = self
+            .kinds
+            .iter()
+            .map(|(kind, number)| count(*number, kind))
+            .collect();
+        match kinds.as_slice() {
+            [] => format!("Module `{}`", self.node.name),
+            [kinds @ .., last] if !kinds.is_empty() => {
+                format!(
+                    "Module `{}` with {} and {}",
+                    self.node.name,
+                    kinds.join(", "),
+                    last
+                )
+            }
+            _ => format!("Module `{}` with {}", self.node.name, kinds.join(", ")),
+        }
+    }
+}
+
+/// Add the module at `path` of `crate_name` to `modules`, with its
+/// ancestors.
+fn add_module<'a>(
+    modules: &mut BTreeMap<(&'a str, String), ModuleConcept>,
+    crate_name: &'a str,
+    path: &str,
+) {
+    let segments: Vec<&str> = path.split("::").collect();
+    for depth in 1..=segments.len() {
+        let path = segments[..depth].join("::");
+        if modules.contains_key(&(crate_name, path.clone())) {
+            continue;
+        }
+        let name = chapter_title(crate_name, &path);
+        let id = format!("module:{}", name);
+        let mut parent_concepts = Vec::new();
+        if depth > 1 {
+            let parent = segments[..depth - 1].join("::");
+            if let Some(parent) = modules.get_mut(&(crate_name, parent)) {
+                parent.node.child_concepts.push(id.clone());
+                parent_concepts.push(parent.node.id.clone());
+            }
+        }
+        let node = ConceptNode {
+            id,
+            name,
+            concept_type: ConceptType::Module,
+            description: String::new(),
+            related_chunks: Vec::new(),
+            parent_concepts,
+            child_concepts: Vec::new(),
+            importance_score: 0.0,
+        };
+        let kinds = BTreeMap::new();
+        modules.insert((crate_name, path), ModuleConcept { node, kinds });
+    }
+}
+
+/// The relationships between the chunks of `project_ast`: types
+/// implementing traits, elements containing others, and elements using
+/// others. Traits outside the project are referenced by path.
+pub(crate) fn relationships(
+    project_ast: &ProjectAst,
+    chunk_ids: &ChunkIds,
+) -> Vec<SemanticRelationship> {
+    let mut relationships = Vec::new();
+
+    // The implementing type's chunk (or the impl block's, if the type was not chunked)
+    // points at the trait's chunk; foreign traits are referenced by path.
+    for trait_impl in &project_ast.impls {
+        let from_chunk = trait_impl
+            .type_element_id
+            .as_ref()
+            .and_then(|id| chunk_ids.get(id.as_str()))
+            .or_else(|| chunk_ids.get(trait_impl.impl_id.as_str()));
+        let Some(from_chunk) = from_chunk else {
+            continue;
+        };
+        let to_chunk = trait_impl
+            .trait_element_id
+            .as_ref()
+            .and_then(|id| chunk_ids.get(id.as_str()))
+            .cloned()
+            .unwrap_or_else(|| trait_impl.trait_path.clone());
+        relationships.push(SemanticRelationship {
+            from_chunk: from_chunk.clone(),
+            to_chunk,
+            relationship_type: RelationshipType::Implements,
+            strength: 1.0,
+            description: format!(
+                "`{}` implements `{}`",
+                trait_impl.self_type, trait_impl.trait_path
+            ),
+        });
+    }
+
+    let names: HashMap<&str, &str> = chunked(project_ast, chunk_ids)
+        .map(|(element, _)| {
+            let name = element.hierarchy.qualified_name.as_str();
+            (element.id.as_str(), name)
+        })
+        .collect();
+    for (element, chunk_id) in chunked(project_ast, chunk_ids) {
+        let Some(parent_id) = &element.hierarchy.parent_id else {
+            continue;
+        };
+        if let (Some(parent_chunk), Some(parent)) = (
+            chunk_ids.get(parent_id.as_str()),
+            names.get(parent_id.as_str()),
+        ) {
+            relationships.push(SemanticRelationship {
+                from_chunk: parent_chunk.clone(),
+                to_chunk: chunk_id.clone(),
+                relationship_type: RelationshipType::Contains,
+                strength: 1.0,
+                description: format!(
+                    "`{}` contains `{}`",
+                    parent, element.hierarchy.qualified_name
+                ),
+            });
+        }
+    }
+
+    // Uses are counted per pair of elements, in the order first found.
+    let mut uses: Vec<((&str, &str), Uses)> = Vec::new();
+    let mut positions: HashMap<(&str, &str), usize> = HashMap::new();
+    for reference in &project_ast.cross_references {
+        let Some(to) = reference.to_element_id.as_deref() else {
+            continue;
+        };
+        let from = reference.from_element_id.as_str();
+        let chunked = chunk_ids.contains_key(from) && chunk_ids.contains_key(to);
+        if from == to || !chunked {
+            continue;
+        }
+        let position = *positions.entry((from, to)).or_insert_with(|| {
+            uses.push(((from, to), Uses::default()));
+            uses.len() - 1
+        });
+        let counts = &mut uses[position].1;
+        match reference.reference_type {
+            ReferenceType::FunctionCall => counts.calls += 1,
+            ReferenceType::TypeUsage => counts.type_references += 1,
+            _ => {}
+        }
+    }
+    for ((from, to), counts) in uses {
+        let total = counts.calls + counts.type_references;
+        if total == 0 {
+            continue;
+        }
+        let mut kinds = Vec::new();
+        if counts.calls > 0 {
+            kinds.push(count(counts.calls, "call"));
+        }
+        if counts.type_references > 0 {
+            kinds.push(count(counts.type_references, "type reference"));
+        }
+        relationships.push(SemanticRelationship {
+            from_chunk: chunk_ids[from].clone(),
+            to_chunk: chunk_ids[to].clone(),
+            relationship_type: RelationshipType::Uses,
+            // Each reference halves the distance left to 1.
+            strength: 1.0 - 0.5f64.powi(total.min(16) as i32),
+            description: format!(
+                "`{}` uses `{}`: {}",
+                names.get(from).unwrap_or(&from),
+                names.get(to).unwrap_or(&to),
+                kinds.join(", ")
+            ),
+        });
+    }
+    relationships
+}
+
+/// References from one element to another.
+#[derive(Default)]
+struct Uses {
+    calls: usize,
+    type_references: usize,
+}
+
+/// The words of the identifiers of the elements of `project_ast` occurring
+/// in at least [`MIN_TERM_FREQUENCY`] of them, by word.
+pub(crate) fn vocabulary(
+    project_ast: &ProjectAst,
+    chunk_ids: &ChunkIds,
+) -> HashMap<String, VocabularyEntry> {
+    let mut terms: BTreeMap<String, Term> = BTreeMap::new();
+    for (element, chunk_id) in chunked(project_ast, chunk_ids) {
+        if element.element_type == ElementType::Impl {
+            continue;
+        }
+        let mut words = identifier_words(&element.name);
+        words.sort();
+        words.dedup();
+        for word in &words {
+            let term = terms.entry(word.clone()).or_default();
+            term.frequency += 1;
+            if term.chunks.len() < MAX_TERM_CHUNKS {
+                term.chunks.push(chunk_id.clone());
+            }
+            for other in words.iter().filter(|other| *other != word) {
+                *term.co_occurrences.entry(other.clone()).or_insert(0) += 1;
+            }
+            // A type or function named by the word alone defines it.
+            let is_type = matches!(
+                element.element_type,
+                ElementType::Struct | ElementType::Enum | ElementType::Trait
+            );
+            let named = element.name.to_lowercase() == *word;
+            if named && (term.definition.is_empty() || is_type) {
+                let summary = doc_summary(element);
+                if !summary.is_empty() {
+                    term.definition = summary;
+                }
+            }
+        }
+    }
+
+    terms
+        .into_iter()
+        .filter(|(_, term)| term.frequency >= MIN_TERM_FREQUENCY)
+        .map(|(word, term)| {
+            let mut related: Vec<(String, usize)> = term.co_occurrences.into_iter().collect();
+            related.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let entry = VocabularyEntry {
+                term: word.clone(),
+                definition: term.definition,
+                synonyms: Vec::new(),
+                related_terms: related
+                    .into_iter()
+                    .take(MAX_RELATED_TERMS)
+                    .map(|(word, _)| word)
+                    .collect(),
+                chunk_references: term.chunks,
+                frequency: term.frequency,
+            };
+            (word, entry)
+        })
+        .collect()
+}
+
+/// A word of the vocabulary while it is being counted.
+#[derive(Default)]
+struct Term {
+    frequency: usize,
+    chunks: Vec<String>,
+    /// Number of identifiers each other word shares with this one
+    co_occurrences: BTreeMap<String, usize>,
+    definition: String,
+}
+
+/// The lowercase words of an identifier in snake_case or CamelCase, such as
+/// `http` and `server` in `HTTPServer`, without numbers, short words and
+/// [`STOP_WORDS`].
+pub(crate) fn identifier_words(identifier: &str) -> Vec<String> {
+    let chars: Vec<char> = identifier.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut word));
+            continue;
+        }
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1);
+        let boundary = match previous {
+            // `serverName`, `HTTPServer` and `add0` split before `N`, `S` and `0`.
+            Some(previous) if c.is_uppercase() => {
+                previous.is_lowercase()
+                    || previous.is_ascii_digit()
+                    || (previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+            }
+            Some(previous) => c.is_ascii_digit() != previous.is_ascii_digit(),
+            None => false,
+        };
+        if boundary {
+            words.push(std::mem::take(&mut word));
+        }
+        word.extend(c.to_lowercase());
+    }
+    words.push(word);
+    words.retain(|word| {
+        word.chars().count() >= 3
+            && !word.chars().all(|c| c.is_ascii_digit())
+            && !STOP_WORDS.contains(&word.as_str())
+    });
+    words
+}
+
+/// The first line of the docs of `element`, if it has any.
+fn doc_summary(element: &CodeElement) -> String {
+    element
+        .doc_comments
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The plural name of elements of the type of `element`, as in "3 structs".
+fn kind_name(element: &CodeElement) -> &'static str {
+    match element.element_type {
+        ElementType::Function => "function",
+        ElementType::Struct => "struct",
+        ElementType::Enum => "enum",
+        ElementType::Trait => "trait",
+        ElementType::Impl => "impl",
+        ElementType::Constant => "constant",
+        ElementType::Static => "static",
+        ElementType::TypeAlias => "type alias",
+        ElementType::Macro => "macro",
+        _ => "item",
+    }
+}
+
+/// `number` of `noun`, as in "1 call" or "2 calls".
+pub(crate) fn count(number: usize, noun: &str) -> String {
+    match number {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", number, noun),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{CrossReference, ReferenceContext, TraitImpl};
+
+    /// The module `net` with the struct `TcpServer` and its method
+    /// `start_server`, which uses the trait `Server` of the crate root twice,
+    /// and an impl of `Server` for `TcpServer`. Chunk IDs are `chunk:` and
+    /// the element's name.
+    fn project() -> (ProjectAst, HashMap<String, String>) {
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        let elements = &mut project.files[0].elements;
+        for (name, element_type, module_path, docs) in [
+            ("net", ElementType::Module, "crate", "Networking over TCP."),
+            (
+                "TcpServer",
+                ElementType::Struct,
+                "crate::net",
+                "A server listening on a port.",
+            ),
+            ("start_server", ElementType::Function, "crate::net", ""),
+            ("Server", ElementType::Trait, "crate", ""),
+            ("TcpServer", ElementType::Impl, "crate::net", ""),
+        ] {
+            let mut element = MockDataGenerator::code_element(name, element_type);
+            element.hierarchy.module_path = module_path.to_string();
+            element.hierarchy.qualified_name = format!("{}::{}", module_path, name);
+            element.doc_comments = if docs.is_empty() {
+                Vec::new()
+            } else {
+                vec![docs.to_string()]
+            };
+            elements.push(element);
+        }
+        elements[2].hierarchy.parent_id = Some(elements[1].id.clone());
+        elements[2].hierarchy.qualified_name = "crate::net::TcpServer::start_server".to_string();
+
+        let reference = |reference_type| {
+            let mut reference = CrossReference::new(
+                elements[2].id.clone(),
+                reference_type,
+                "Server".to_string(),
+                elements[2].location.clone(),
+                ReferenceContext::new(false, String::new()),
+            );
+            reference.resolve_to(elements[3].id.clone());
+            reference
+        };
+        project.cross_references = vec![
+            reference(ReferenceType::TypeUsage),
+            reference(ReferenceType::FunctionCall),
+        ];
+        project.impls = vec![TraitImpl {
+            impl_id: elements[4].id.clone(),
+            trait_path: "Server".to_string(),
+            self_type: "TcpServer".to_string(),
+            trait_element_id: Some(elements[3].id.clone()),
+            type_element_id: Some(elements[1].id.clone()),
+            generics: Vec::new(),
+            where_clause: None,
+            location: elements[4].location.clone(),
+            is_generated: false,
+            overridden_methods: Vec::new(),
+            inherited_methods: Vec::new(),
+        }];
+
+        let chunk_ids = elements
+            .iter()
+            .map(|element| (element.id.clone(), format!("chunk:{}", element.name)))
+            .collect();
+        (project, chunk_ids)
+    }
+
+    fn borrowed(chunk_ids: &HashMap<String, String>) -> ChunkIds<'_> {
+        chunk_ids
+            .iter()
+            .map(|(id, chunk)| (id.as_str(), chunk.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_relationships() {
+        let (project, chunk_ids) = project();
+        let relationships = relationships(&project, &borrowed(&chunk_ids));
+        let summary: Vec<(&str, &str, String, &str)> = relationships
+            .iter()
+            .map(|relationship| {
+                (
+                    relationship.from_chunk.as_str(),
+                    relationship.to_chunk.as_str(),
+                    format!("{:?}", relationship.relationship_type),
+                    relationship.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "chunk:TcpServer",
+                    "chunk:Server",
+                    "Implements".to_string(),
+                    "`TcpServer` implements `Server`"
+                ),
+                (
+                    "chunk:TcpServer",
+                    "chunk:start_server",
+                    "Contains".to_string(),
+                    "`crate::net::TcpServer` contains `crate::net::TcpServer::start_server`"
+                ),
+                (
+                    "chunk:start_server",
+                    "chunk:Server",
+                    "Uses".to_string(),
+                    "`crate::net::TcpServer::start_server` uses `crate::Server`: 1 call, \
+                     1 type reference"
+                ),
+            ]
+        );
+        assert_eq!(relationships[2].strength, 0.75);
+
+        // Elements without chunks are left out.
+        let mut chunk_ids = chunk_ids;
+        chunk_ids.retain(|_, chunk| chunk != "chunk:start_server");
+        let relationships = super::relationships(&project, &borrowed(&chunk_ids));
+        assert_eq!(relationships.len(), 1);
+    }
+
+    #[test]
+    fn test_concept_hierarchy() {
+        let (project, chunk_ids) = project();
+        let nodes = concept_hierarchy(&project, &borrowed(&chunk_ids));
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["module:crate", "module:crate::net", "trait:crate::Server"]
+        );
+
+        let (root, net, server) = (&nodes[0], &nodes[1], &nodes[2]);
+        assert_eq!(
+            root.child_concepts,
+            ["module:crate::net", "trait:crate::Server"]
+        );
+        assert_eq!(root.related_chunks, ["chunk:Server"]);
+        assert_eq!(root.description, "Module `crate` with 1 trait");
+        assert_eq!(net.parent_concepts, ["module:crate"]);
+        assert_eq!(
+            net.related_chunks,
+            [
+                "chunk:net",
+                "chunk:TcpServer",
+                "chunk:start_server",
+                "chunk:TcpServer"
+            ]
+        );
+        assert_eq!(net.description, "Networking over TCP.");
+        assert_eq!(server.parent_concepts, ["module:crate"]);
+        assert_eq!(server.related_chunks, ["chunk:Server", "chunk:TcpServer"]);
+        assert_eq!(server.description, "Trait `Server` with 1 implementation");
+        // The root holds its own chunk and those of `net`.
+        assert_eq!(root.importance_score, 1.0);
+        assert_eq!(net.importance_score, 0.8);
+        assert_eq!(server.importance_score, 0.4);
+    }
+
+    #[test]
+    fn test_vocabulary() {
+        let (project, chunk_ids) = project();
+        let vocabulary = vocabulary(&project, &borrowed(&chunk_ids));
+        let mut terms: Vec<&str> = vocabulary.keys().map(String::as_str).collect();
+        terms.sort();
+        assert_eq!(terms, ["server"]);
+
+        let server = &vocabulary["server"];
+        assert_eq!(server.frequency, 3);
+        assert_eq!(
+            server.chunk_references,
+            ["chunk:TcpServer", "chunk:start_server", "chunk:Server"]
+        );
+        assert_eq!(server.related_terms, ["start", "tcp"]);
+        assert!(server.definition.is_empty());
+    }
+
+    #[test]
+    fn test_identifier_words() {
+        assert_eq!(identifier_words("HTTPServer"), ["http", "server"]);
+        assert_eq!(identifier_words("parse_config_v2"), ["parse", "config"]);
+        assert_eq!(identifier_words("getNewValue"), ["value"]);
+    }
+}
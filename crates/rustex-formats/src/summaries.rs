@@ -0,0 +1,272 @@
+//! Generated summaries of RAG chunks.
+//!
+//! A [`Summarizer`] writes a one-paragraph summary of the element of a
+//! chunk, usually with a language model. The [`RagFormatter`] asks its
+//! summarizer about the elements without docs whose cyclomatic complexity is
+//! at least `summary_min_complexity`, those a reader is most likely to need
+//! explained, and stores the summaries in the chunks' `generated_summary`,
+//! apart from the docs written by people. A summarizer for OpenAI-compatible
+//! chat APIs ([`OpenAiSummarizer`], feature `summaries-http`) is included,
+//! and [`summarizer`] picks it from the `[summaries]` configuration.
+//! Summaries are cached, so formatting the same chunks again, as when
+//! fitting output to a budget, doesn't repeat requests. Chunks whose summary
+//! fails are left without one, so an unreachable model doesn't stop the
+//! output.
+//!
+//! [`RagFormatter`]: crate::rag::RagFormatter
+
+#[cfg(feature = "summaries-http")]
+mod openai;
+
+#[cfg(feature = "summaries-http")]
+pub use openai::OpenAiSummarizer;
+
+use crate::rag::{chunk_id, RagChunk, RagConfig};
+use anyhow::Result;
+use rustex_core::{FileAst, SummaryConfig};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A model writing summaries of the elements of chunks.
+pub trait Summarizer: Send + Sync {
+    /// Name of the model, recorded in the RAG metadata.
+    fn model(&self) -> &str;
+
+    /// A one-paragraph summary of what the element of `chunk` does, given
+    /// its `code`: the element's source, or the chunk's content when the
+    /// source can't be read.
+    fn summarize(&self, chunk: &RagChunk, code: &str) -> Result<String>;
+}
+
+/// The summarizer configured by `config`, of the OpenAI-compatible API at
+/// `url`.
+pub fn summarizer(config: &SummaryConfig) -> Result<Arc<dyn Summarizer>> {
+    #[cfg(feature = "summaries-http")]
+    {
+        let api_key = std::env::var(&config.api_key_env)
+            .ok()
+            .filter(|key| !key.is_empty());
+        Ok(Arc::new(OpenAiSummarizer::new(
+            &config.url,
+            &config.model,
+            api_key,
+        )))
+    }
+    #[cfg(not(feature = "summaries-http"))]
+    anyhow::bail!(
+        "Summarizing with the API at {} needs the summaries-http feature",
+        config.url
+    )
+}
+
+/// Asks a summarizer about the chunks that need a summary.
+pub(crate) struct Summaries {
+    summarizer: Arc<dyn Summarizer>,
+    min_complexity: u32,
+    /// Summaries of the code summarized so far
+    cache: Mutex<HashMap<String, String>>,
+}
+
+impl Summaries {
+    /// Summarize with `summarizer`, following the summary settings of
+    /// `config`.
+    pub(crate) fn new(summarizer: Arc<dyn Summarizer>, config: &RagConfig) -> Self {
+        Self {
+            summarizer,
+            min_complexity: config.summary_min_complexity,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Name of the summarizer's model.
+    pub(crate) fn model(&self) -> &str {
+        self.summarizer.model()
+    }
+
+    /// Set the generated summary of each of `chunks` that needs one, with
+    /// the code of their elements in `files`, returning the errors of the
+    /// summaries that failed.
+    pub(crate) fn summarize(
+        &self,
+        chunks: &mut [RagChunk],
+        files: &[FileAst],
+    ) -> Vec<anyhow::Error> {
+        let mut failures = Vec::new();
+        let mut pending: HashMap<String, &mut RagChunk> = chunks
+            .iter_mut()
+            .filter(|chunk| self.needs_summary(chunk))
+            .map(|chunk| (chunk.id.clone(), chunk))
+            .collect();
+        for file in files {
+            // Sources are read once per file, and only when needed.
+            let mut source = None;
+            for element in &file.elements {
+                let Some(chunk) = pending.remove(&chunk_id(element)) else {
+                    continue;
+                };
+                let source = source.get_or_insert_with(|| std::fs::read_to_string(&file.path).ok());
+                let location = &element.location;
+                let code = source
+                    .as_deref()
+                    .and_then(|source| source.get(location.byte_start..location.byte_end))
+                    .filter(|code| !code.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| chunk.content.clone());
+                failures.extend(self.summarize_chunk(chunk, &code).err());
+            }
+        }
+        for chunk in pending.into_values() {
+            let code = chunk.content.clone();
+            failures.extend(self.summarize_chunk(chunk, &code).err());
+        }
+        failures
+    }
+
+    /// Set the generated summary of `chunk`, whose element's code is `code`.
+    /// Failed summaries aren't cached, so later runs of the same code try
+    /// again.
+    fn summarize_chunk(&self, chunk: &mut RagChunk, code: &str) -> Result<()> {
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let summary = match cache.get(code) {
+            Some(summary) => summary.clone(),
+            None => {
+                let summary = self
+                    .summarizer
+                    .summarize(chunk, code)
+                    .map_err(|e| e.context(format!("Failed to summarize chunk {}", chunk.id)))?;
+                // Models may answer in several paragraphs or lines.
+                let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+                cache.insert(code.to_string(), summary.clone());
+                summary
+            }
+        };
+        chunk.generated_summary = Some(summary).filter(|summary| !summary.is_empty());
+        Ok(())
+    }
+
+    /// Whether `chunk` is the first chunk of an element without docs that is
+    /// complex enough to summarize.
+    fn needs_summary(&self, chunk: &RagChunk) -> bool {
+        let metadata = &chunk.metadata;
+        metadata.part_index == 0
+            && !metadata.has_documentation
+            && metadata
+                .complexity
+                .is_some_and(|complexity| complexity >= self.min_complexity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::RagFormatter;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::{ElementType, ProjectAst};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Summaries naming the element over two lines, failing for `broken`.
+    #[derive(Default)]
+    struct StubSummarizer {
+        calls: AtomicUsize,
+    }
+
+    impl Summarizer for StubSummarizer {
+        fn model(&self) -> &str {
+            "stub"
+        }
+
+        fn summarize(&self, chunk: &RagChunk, _code: &str) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let name = &chunk.metadata.element_name;
+            if name == "broken" {
+                anyhow::bail!("model unavailable");
+            }
+            Ok(format!("Summary of\n\n{}.", name))
+        }
+    }
+
+    /// Functions without docs of complexity 5, `parse` and `broken`, one of
+    /// complexity 1, `tiny`, and one with docs, `documented`.
+    fn project() -> ProjectAst {
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        for name in ["parse", "broken", "tiny", "documented"] {
+            let mut element = MockDataGenerator::code_element(name, ElementType::Function);
+            element.attributes.clear();
+            if name != "documented" {
+                element.doc_comments.clear();
+            }
+            if name == "tiny" {
+                element.complexity = Some(1);
+            }
+            project.files[0].elements.push(element);
+        }
+        project
+    }
+
+    fn summaries(document: &crate::rag::RagDocument) -> Vec<(&str, Option<&str>)> {
+        document
+            .chunks
+            .iter()
+            .map(|chunk| {
+                let name = chunk.metadata.element_name.as_str();
+                (name, chunk.generated_summary.as_deref())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_summaries() {
+        let summarizer = Arc::new(StubSummarizer::default());
+        let formatter = RagFormatter::new(RagConfig::default()).with_summarizer(summarizer.clone());
+        let document = formatter.format(&project()).unwrap();
+
+        assert_eq!(
+            summaries(&document),
+            [
+                ("parse", Some("Summary of parse.")),
+                ("broken", None),
+                ("tiny", None),
+                ("documented", None),
+            ]
+        );
+        assert_eq!(document.metadata.summary_model.as_deref(), Some("stub"));
+        assert_eq!(
+            document.metadata.failed_summaries,
+            ["Failed to summarize chunk chunk_Function_broken_1: model unavailable"]
+        );
+
+        // Summaries have a field of their own, apart from the content.
+        let chunk = serde_json::to_value(&document.chunks[0]).unwrap();
+        assert_eq!(chunk["generated_summary"], "Summary of parse.");
+        assert!(!chunk["content"].as_str().unwrap().contains("Summary of"));
+        let chunk = serde_json::to_value(&document.chunks[2]).unwrap();
+        assert!(chunk["generated_summary"].is_null());
+    }
+
+    #[test]
+    fn test_summaries_are_cached() {
+        let summarizer = Arc::new(StubSummarizer::default());
+        let formatter = RagFormatter::new(RagConfig::default()).with_summarizer(summarizer.clone());
+        let project = project();
+        formatter.format(&project).unwrap();
+        assert_eq!(summarizer.calls.load(Ordering::SeqCst), 2);
+
+        // Only the failed summary is asked for again.
+        let document = formatter.format(&project).unwrap();
+        assert_eq!(summarizer.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            document.chunks[0].generated_summary.as_deref(),
+            Some("Summary of parse.")
+        );
+    }
+
+    #[test]
+    fn test_summarize_chunks_needs_summarizer() {
+        let formatter = RagFormatter::new(RagConfig::default());
+        let error = formatter.summarize_chunks(&mut [], &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Summarizing RAG chunks needs a summarizer"
+        );
+    }
+}
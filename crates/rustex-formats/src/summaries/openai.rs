@@ -0,0 +1,217 @@
+//! Summaries from OpenAI-compatible HTTP chat APIs.
+
+use super::Summarizer;
+use crate::rag::RagChunk;
+use anyhow::Result;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Instructions the model summarizes with.
+const SYSTEM_PROMPT: &str = "You summarize Rust code for a search index. Given an item, \
+    write one short paragraph in plain English on what it does and when to use it. \
+    Don't repeat the code, and don't guess beyond it.";
+
+/// Most tokens of a summary.
+const MAX_TOKENS: u32 = 200;
+
+/// Summaries from the `/chat/completions` endpoint of an OpenAI-compatible
+/// API, such as those of OpenAI, Azure OpenAI, Ollama or vLLM.
+pub struct OpenAiSummarizer {
+    agent: ureq::Agent,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+}
+
+impl OpenAiSummarizer {
+    /// Summaries by `model` from the API at `base_url`, such as
+    /// `https://api.openai.com/v1`, authenticated with `api_key` if given.
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(120))
+                .build(),
+            endpoint: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+            model: model.to_string(),
+            api_key,
+        }
+    }
+}
+
+impl Summarizer for OpenAiSummarizer {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn summarize(&self, chunk: &RagChunk, code: &str) -> Result<String> {
+        let metadata = &chunk.metadata;
+        let prompt = format!(
+            "{} `{}` in {}:\n\n```rust\n{}\n```",
+            metadata.element_type, metadata.qualified_name, metadata.file_path, code
+        );
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": prompt },
+            ],
+            "max_tokens": MAX_TOKENS,
+            "temperature": 0.2,
+        });
+        let mut request = self.agent.post(&self.endpoint);
+        if let Some(api_key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+        let response = match request.send_json(body) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => anyhow::bail!(
+                "Summary request to {} failed with status {}: {}",
+                self.endpoint,
+                status,
+                response.into_string().unwrap_or_default().trim()
+            ),
+            Err(error) => {
+                anyhow::bail!("Summary request to {} failed: {}", self.endpoint, error)
+            }
+        };
+
+        let response: ChatResponse = response.into_json().map_err(|e| {
+            anyhow::anyhow!("Invalid summary response from {}: {}", self.endpoint, e)
+        })?;
+        Ok(response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::{RagConfig, RagFormatter};
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use rustex_core::ElementType;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// A server answering one request with `status` and `body`, at the
+    /// returned base URL; the thread returns the request's head and body.
+    fn serve(status: &str, body: &str) -> (String, JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .strip_prefix("content-length: ")?
+                        .parse()
+                        .ok()
+                })
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            (head, String::from_utf8(body).unwrap())
+        });
+        (url, server)
+    }
+
+    /// The chunk of a function `parse`.
+    fn chunk() -> RagChunk {
+        let mut project = MockDataGenerator::project_ast(1, 0);
+        let mut element = MockDataGenerator::code_element("parse", ElementType::Function);
+        element.attributes.clear();
+        project.files[0].elements.push(element);
+        let document = RagFormatter::new(RagConfig::default())
+            .format(&project)
+            .unwrap();
+        document.chunks.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_summarize() {
+        let (url, server) = serve(
+            "200 OK",
+            r#"{"choices":[{"message":{"role":"assistant","content":"Parses the input."}}]}"#,
+        );
+        let summarizer =
+            OpenAiSummarizer::new(&format!("{}/", url), "gpt-test", Some("key".into()));
+        let summary = summarizer.summarize(&chunk(), "fn parse() {}").unwrap();
+        assert_eq!(summary, "Parses the input.");
+
+        let (head, body) = server.join().unwrap();
+        assert!(
+            head.starts_with("POST /v1/chat/completions HTTP/1.1\r\n"),
+            "{}",
+            head
+        );
+        assert!(head.contains("Authorization: Bearer key\r\n"), "{}", head);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["model"], "gpt-test");
+        assert_eq!(body["messages"][0]["content"], SYSTEM_PROMPT);
+        let prompt = body["messages"][1]["content"].as_str().unwrap();
+        assert!(
+            prompt.starts_with("Function `crate::test::parse` in "),
+            "{}",
+            prompt
+        );
+        assert!(
+            prompt.ends_with("```rust\nfn parse() {}\n```"),
+            "{}",
+            prompt
+        );
+    }
+
+    #[test]
+    fn test_failed_request() {
+        let (url, server) = serve("503 Service Unavailable", "overloaded");
+        let summarizer = OpenAiSummarizer::new(&url, "gpt-test", None);
+        let error = summarizer.summarize(&chunk(), "fn parse() {}").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Summary request to {}/chat/completions failed with status 503: overloaded",
+                url
+            )
+        );
+        let (head, _) = server.join().unwrap();
+        assert!(!head.contains("Authorization"), "{}", head);
+    }
+}
@@ -0,0 +1,171 @@
+//! Output rendered through a user-provided Tera template.
+//!
+//! [`format_with_template`] renders a template with the project AST as its
+//! context, as serialized in JSON output: `project`, `files`, `metrics`,
+//! `impls` and so on. Every element of the project is also in `elements`,
+//! with the path of its file relative to the project root as `file`.
+//!
+//! Templates can select elements with the `elements` function and group them
+//! with the `group_elements` filter:
+//!
+//! ```text
+//! {% for group in elements(type="Function", visibility="pub") | group_elements(by="module") %}
+//! ## {{ group.name }}
+//! {% for function in group.elements %}- `{{ function.signature }}`
+//! {% endfor %}{% endfor %}
+//! ```
+
+use anyhow::Result;
+use rustex_core::{ProjectAst, Visibility};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tera::{Context, Tera};
+
+/// Name of the template in errors.
+const TEMPLATE_NAME: &str = "template";
+
+/// Render `template`, the source of a Tera template, with `project_ast` as
+/// its context.
+pub fn format_with_template(project_ast: &ProjectAst, template: &str) -> Result<String> {
+    let mut elements = Vec::new();
+    for file in &project_ast.files {
+        let path = file.relative_path.display().to_string();
+        for element in &file.elements {
+            let mut value = serde_json::to_value(element)?;
+            value["file"] = Value::String(path.clone());
+            elements.push((element.visibility.clone(), value));
+        }
+    }
+    let elements = Arc::new(elements);
+
+    let mut tera = Tera::default();
+    tera.add_raw_template(TEMPLATE_NAME, template)
+        .map_err(describe)?;
+    let all_elements = Arc::clone(&elements);
+    tera.register_function("elements", move |args: &HashMap<String, Value>| {
+        select_elements(&all_elements, args)
+    });
+    tera.register_filter("group_elements", group_elements);
+
+    let mut context = Context::from_serialize(project_ast).map_err(describe)?;
+    let values: Vec<&Value> = elements.iter().map(|(_, value)| value).collect();
+    context.insert("elements", &values);
+    tera.render(TEMPLATE_NAME, &context).map_err(describe)
+}
+
+/// The `elements` function: elements of the project, optionally only those
+/// of a `type` such as `Function`, a `visibility` filter such as
+/// `pub(crate)`, a `module` such as `crate::net` or a `file`. Each argument
+/// takes one value or a list of values.
+fn select_elements(
+    elements: &[(Visibility, Value)],
+    args: &HashMap<String, Value>,
+) -> tera::Result<Value> {
+    for name in args.keys() {
+        if !matches!(name.as_str(), "type" | "visibility" | "module" | "file") {
+            return Err(tera::Error::msg(format!(
+                "Function `elements` has no argument `{}`, only type, visibility, module and file",
+                name
+            )));
+        }
+    }
+    let argument = |name: &str| -> Option<Vec<String>> {
+        match args.get(name)? {
+            Value::Array(values) => Some(values.iter().map(text).collect()),
+            value => Some(vec![text(value)]),
+        }
+    };
+    let (types, visibilities) = (argument("type"), argument("visibility"));
+    let (modules, files) = (argument("module"), argument("file"));
+    let matches = |filter: &Option<Vec<String>>, value: &Value| {
+        filter.as_ref().is_none_or(|accepted| {
+            accepted
+                .iter()
+                .any(|accepted| accepted.eq_ignore_ascii_case(&text(value)))
+        })
+    };
+
+    let selected = elements
+        .iter()
+        .filter(|(visibility, _)| {
+            visibilities.as_ref().is_none_or(|filters| {
+                filters
+                    .iter()
+                    .any(|filter| visibility.matches_filter(filter))
+            })
+        })
+        .map(|(_, element)| element)
+        .filter(|element| matches(&types, &element["element_type"]))
+        .filter(|element| matches(&modules, &element["hierarchy"]["module_path"]))
+        .filter(|element| matches(&files, &element["file"]))
+        .cloned()
+        .collect();
+    Ok(Value::Array(selected))
+}
+
+/// The `group_elements` filter: a list of elements grouped `by` their
+/// `module`, `file` or `type`, as a list of groups with a `name` and their
+/// `elements`, in the order the groups first appear.
+fn group_elements(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let Value::Array(elements) = value else {
+        return Err(tera::Error::msg(
+            "Filter `group_elements` expects a list of elements",
+        ));
+    };
+    let by = args
+        .get("by")
+        .map(text)
+        .unwrap_or_else(|| "module".to_string());
+    let key = |element: &Value| -> tera::Result<String> {
+        Ok(match by.as_str() {
+            "module" => text(&element["hierarchy"]["module_path"]),
+            "file" => match &element["file"] {
+                Value::Null => text(&element["location"]["file_path"]),
+                file => text(file),
+            },
+            "type" => text(&element["element_type"]),
+            other => {
+                return Err(tera::Error::msg(format!(
+                    "Filter `group_elements` can't group by `{}` (expected module, file or type)",
+                    other
+                )))
+            }
+        })
+    };
+
+    let mut groups: Vec<(String, Vec<Value>)> = Vec::new();
+    for element in elements {
+        let name = key(element)?;
+        match groups.iter_mut().find(|(group, _)| *group == name) {
+            Some((_, members)) => members.push(element.clone()),
+            None => groups.push((name, vec![element.clone()])),
+        }
+    }
+    Ok(Value::Array(
+        groups
+            .into_iter()
+            .map(|(name, elements)| serde_json::json!({ "name": name, "elements": elements }))
+            .collect(),
+    ))
+}
+
+/// `value` as text: strings without quotes, anything else as JSON.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// `error` with the errors that caused it, which carry the position of
+/// syntax errors and the name of missing variables.
+fn describe(error: tera::Error) -> anyhow::Error {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(&error);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    anyhow::anyhow!(message)
+}
@@ -0,0 +1,761 @@
+//! Generation of training examples from the elements of a project.
+//!
+//! Each task type of [`RagConfig::training_tasks`] has a generator, and the
+//! generators run in that order for each element until the element has
+//! `max_training_examples_per_chunk` examples. Generators other than API
+//! usage work on the source of functions, read back from their files, and
+//! leave out functions whose examples would teach little:
+//!
+//! | Task | Input | Output | Made for |
+//! |------|-------|--------|----------|
+//! | `ApiUsage` | Signature | Doc example | Rust doc examples that aren't `compile_fail` |
+//! | `TestGeneration` | Code | Test | Functions called by a test named after them |
+//! | `DocumentationGeneration` | Code | Doc comment | Functions with 6 words or more of docs outside code blocks |
+//! | `CodeCompletion` | Docs and signature | Body | Bodies of 2 lines or more, up to `max_chunk_size` tokens, not `todo!()` |
+//! | `BugFinding` | Code | Bug-prone patterns | Functions with such patterns, or a cyclomatic complexity of 3 or more |
+//! | `FunctionNaming` | Code, unnamed | Name | Functions with bodies of 2 lines or more, not named by a trait |
+//!
+//! Tests are only found among the extracted elements, so test generation
+//! needs private items and test files to be extracted.
+
+use crate::chunking::estimate_tokens;
+use crate::rag::{
+    DifficultyLevel, RagChunk, RagConfig, TaskType, TrainingExample, TrainingMetadata,
+};
+use rustex_core::{CodeElement, ElementType, ProjectAst};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+/// Fewest words of the docs of a documentation generation example, outside
+/// code blocks.
+const MIN_DOC_WORDS: usize = 6;
+
+/// Fewest lines of the bodies of code completion and function naming examples.
+const MIN_BODY_LINES: usize = 2;
+
+/// Lowest cyclomatic complexity of the functions without bug-prone patterns
+/// that get a bug finding example.
+const MIN_CLEAN_COMPLEXITY: u32 = 3;
+
+/// Longest snippet of code quoted by a bug finding example, in characters.
+const MAX_SNIPPET_CHARS: usize = 60;
+
+/// Bug-prone patterns of bug finding examples, and what they do.
+const BUG_PATTERNS: &[(&str, &str)] = &[
+    (
+        "unwrap_or_expect",
+        "`unwrap` or `expect`, which panic on `None` or `Err`",
+    ),
+    (
+        "explicit_panic",
+        "`panic!`, `todo!`, `unimplemented!` or `unreachable!`",
+    ),
+    ("indexing", "indexing, which panics out of bounds"),
+    (
+        "numeric_cast",
+        "numeric `as` casts, which truncate or wrap silently",
+    ),
+    ("unsafe_block", "`unsafe` blocks"),
+];
+
+/// Generator of the training examples of a project.
+pub(crate) struct TrainingGenerator<'a> {
+    config: &'a RagConfig,
+    tests: Vec<TestFunction>,
+    last_id: usize,
+}
+
+/// A test function of the project.
+struct TestFunction {
+    name: String,
+    /// Last path segments of the functions and methods the test calls
+    calls: Vec<String>,
+    code: String,
+}
+
+impl<'a> TrainingGenerator<'a> {
+    /// A generator for the elements of `project_ast`, matching functions with
+    /// its tests.
+    pub(crate) fn new(config: &'a RagConfig, project_ast: &ProjectAst) -> Self {
+        let mut tests = Vec::new();
+        for file in &project_ast.files {
+            let test_elements: Vec<&CodeElement> = file
+                .elements
+                .iter()
+                .filter(|element| is_test(element))
+                .collect();
+            if test_elements.is_empty() {
+                continue;
+            }
+            let Ok(source) = std::fs::read_to_string(&file.path) else {
+                continue;
+            };
+            for element in test_elements {
+                if let Some(function) = FunctionSource::of(element, &source) {
+                    tests.push(TestFunction {
+                        name: element.name.clone(),
+                        calls: element
+                            .calls
+                            .iter()
+                            .map(|call| last_segment(&call.name).to_string())
+                            .collect(),
+                        code: function.code,
+                    });
+                }
+            }
+        }
+        Self {
+            config,
+            tests,
+            last_id: 0,
+        }
+    }
+
+    /// The training examples of `element`, whose first chunk is `chunk`,
+    /// with `source`, the text of its file, when it could be read.
+    pub(crate) fn examples(
+        &mut self,
+        element: &CodeElement,
+        chunk: &RagChunk,
+        source: Option<&str>,
+    ) -> Vec<TrainingExample> {
+        let function = source.and_then(|source| FunctionSource::of(element, source));
+        let config = self.config;
+        let limit = config.max_training_examples_per_chunk;
+        let mut examples = Vec::new();
+        for task in &config.training_tasks {
+            if examples.len() >= limit {
+                break;
+            }
+            let made = match (task, &function) {
+                (TaskType::ApiUsage, _) => self.api_usage(element, chunk),
+                (TaskType::TestGeneration, Some(function)) => {
+                    self.test_generation(element, chunk, function)
+                }
+                (TaskType::DocumentationGeneration, Some(function)) => {
+                    self.documentation_generation(element, chunk, function)
+                }
+                (TaskType::CodeCompletion, Some(function)) => {
+                    self.code_completion(element, chunk, function)
+                }
+                (TaskType::BugFinding, Some(function)) => {
+                    self.bug_finding(element, chunk, function)
+                }
+                (TaskType::FunctionNaming, Some(function)) => {
+                    self.function_naming(element, chunk, function)
+                }
+                _ => Vec::new(),
+            };
+            for mut example in made.into_iter().take(limit - examples.len()) {
+                self.last_id += 1;
+                example.id = format!("example_{}", self.last_id);
+                examples.push(example);
+            }
+        }
+        examples
+    }
+
+    /// Turn the Rust doc examples of an element into API usage examples.
+    ///
+    /// `compile_fail` examples show misuse rather than usage, so they are skipped.
+    fn api_usage(&self, element: &CodeElement, chunk: &RagChunk) -> Vec<TrainingExample> {
+        element
+            .doc_examples
+            .iter()
+            .filter(|example| example.language == "rust" && !example.compile_fail)
+            .map(|example| {
+                let mut input = format!("Show how to use `{}`", chunk.metadata.qualified_name);
+                if let Some(signature) = &element.signature {
+                    input.push_str(&format!(":\n\n{}", signature));
+                }
+                if example.should_panic {
+                    input.push_str("\n\nThe example is expected to panic.");
+                }
+                let objective = format!("Use `{}`", element.name);
+                self.example(
+                    TaskType::ApiUsage,
+                    chunk,
+                    input,
+                    example.code.clone(),
+                    objective,
+                )
+            })
+            .collect()
+    }
+
+    /// A test of the function from the project: one that calls it and has
+    /// its name in the test's name.
+    fn test_generation(
+        &self,
+        element: &CodeElement,
+        chunk: &RagChunk,
+        function: &FunctionSource,
+    ) -> Vec<TrainingExample> {
+        let Some(test) = self
+            .tests
+            .iter()
+            .find(|test| test.name.contains(&element.name) && test.calls.contains(&element.name))
+        else {
+            return Vec::new();
+        };
+        let input = format!(
+            "Write a test of this function:\n\n{}",
+            rust_block(&function.code)
+        );
+        let output = test.code.clone();
+        let objective = format!("Test `{}`", element.name);
+        vec![self.example(TaskType::TestGeneration, chunk, input, output, objective)]
+    }
+
+    /// The doc comment of the function, from its code.
+    fn documentation_generation(
+        &self,
+        element: &CodeElement,
+        chunk: &RagChunk,
+        function: &FunctionSource,
+    ) -> Vec<TrainingExample> {
+        if prose_words(&element.doc_comments) < MIN_DOC_WORDS {
+            return Vec::new();
+        }
+        let input = format!(
+            "Write the documentation comment of this function:\n\n{}",
+            rust_block(&function.code)
+        );
+        let output = doc_comment(element);
+        let objective = format!("Document `{}`", element.name);
+        vec![self.example(
+            TaskType::DocumentationGeneration,
+            chunk,
+            input,
+            output,
+            objective,
+        )]
+    }
+
+    /// The body of the function, from its docs and signature.
+    fn code_completion(
+        &self,
+        element: &CodeElement,
+        chunk: &RagChunk,
+        function: &FunctionSource,
+    ) -> Vec<TrainingExample> {
+        let body = &function.body;
+        let unfinished = body.contains("todo!") || body.contains("unimplemented!");
+        if body.lines().count() < MIN_BODY_LINES
+            || estimate_tokens(body) > self.config.max_chunk_size
+            || unfinished
+        {
+            return Vec::new();
+        }
+        let mut code = doc_comment(element);
+        if !code.is_empty() {
+            code.push('\n');
+        }
+        code.push_str(&function.signature);
+        code.push_str(" {");
+        let input = format!(
+            "Complete the body of this function:\n\n{}",
+            rust_block(&code)
+        );
+        let objective = format!("Implement `{}`", element.name);
+        vec![self.example(
+            TaskType::CodeCompletion,
+            chunk,
+            input,
+            body.clone(),
+            objective,
+        )]
+    }
+
+    /// The bug-prone patterns of the function, or that it has none.
+    fn bug_finding(
+        &self,
+        element: &CodeElement,
+        chunk: &RagChunk,
+        function: &FunctionSource,
+    ) -> Vec<TrainingExample> {
+        let patterns = &function.bug_patterns;
+        let cyclomatic = element
+            .complexity_metrics
+            .as_ref()
+            .map_or(1, |metrics| metrics.cyclomatic);
+        if patterns.is_empty() && cyclomatic < MIN_CLEAN_COMPLEXITY {
+            return Vec::new();
+        }
+        let mut input = String::from("Classify the bug-prone patterns of this function as:\n\n");
+        for (label, description) in BUG_PATTERNS {
+            input.push_str(&format!("- {}: {}\n", label, description));
+        }
+        input.push_str(&format!("\n{}", rust_block(&function.code)));
+        let output = if patterns.is_empty() {
+            "No bug-prone patterns.".to_string()
+        } else {
+            patterns
+                .iter()
+                .map(|(label, snippet)| format!("- {}: `{}`", label, snippet))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let objective = "Spot code that can panic or misbehave".to_string();
+        vec![self.example(TaskType::BugFinding, chunk, input, output, objective)]
+    }
+
+    /// The name of the function, from its code with the name left out.
+    fn function_naming(
+        &self,
+        element: &CodeElement,
+        chunk: &RagChunk,
+        function: &FunctionSource,
+    ) -> Vec<TrainingExample> {
+        let trait_method = element
+            .hierarchy
+            .impl_context
+            .as_ref()
+            .is_some_and(|context| context.trait_path.is_some());
+        if trait_method
+            || matches!(element.name.as_str(), "main" | "new")
+            || function.body.lines().count() < MIN_BODY_LINES
+        {
+            return Vec::new();
+        }
+        let code = replace_word(&function.code, &element.name, "_");
+        let input = format!(
+            "Name this function, whose name is replaced by `_`:\n\n{}",
+            rust_block(&code)
+        );
+        let objective = "Name functions after what they do".to_string();
+        vec![self.example(
+            TaskType::FunctionNaming,
+            chunk,
+            input,
+            element.name.clone(),
+            objective,
+        )]
+    }
+
+    /// An example of `task_type` made from `chunk`, without an ID yet.
+    fn example(
+        &self,
+        task_type: TaskType,
+        chunk: &RagChunk,
+        input: String,
+        output: String,
+        objective: String,
+    ) -> TrainingExample {
+        let estimated_token_count = estimate_tokens(&input) + estimate_tokens(&output);
+        TrainingExample {
+            id: String::new(),
+            input,
+            output,
+            task_type,
+            difficulty: match chunk.metadata.complexity.unwrap_or(0) {
+                0..=2 => DifficultyLevel::Beginner,
+                3..=5 => DifficultyLevel::Intermediate,
+                6..=10 => DifficultyLevel::Advanced,
+                _ => DifficultyLevel::Expert,
+            },
+            metadata: TrainingMetadata {
+                source_chunks: vec![chunk.id.clone()],
+                concepts_involved: chunk.metadata.domain_tags.clone(),
+                required_knowledge: Vec::new(),
+                learning_objectives: vec![objective],
+                estimated_token_count,
+            },
+        }
+    }
+}
+
+/// The source of a function, dedented and without its doc comment.
+struct FunctionSource {
+    /// Attributes, signature and body
+    code: String,
+    /// Attributes and signature
+    signature: String,
+    /// Statements of the body, without its braces
+    body: String,
+    /// Labels of the bug-prone patterns of the body, with the code of each
+    bug_patterns: Vec<(&'static str, String)>,
+}
+
+impl FunctionSource {
+    /// The source of `element` in `source`, the text of its file, if it is a
+    /// function with a body that parses.
+    fn of(element: &CodeElement, source: &str) -> Option<Self> {
+        if element.element_type != ElementType::Function {
+            return None;
+        }
+        let location = &element.location;
+        let text = source.get(location.byte_start..location.byte_end)?;
+        let code: Vec<&str> = dedent_item(text)
+            .into_iter()
+            .filter(|line| {
+                let line = line.trim_start();
+                !(line.starts_with("///") || line.starts_with("#[doc"))
+            })
+            .collect();
+        let code = code.join("\n");
+
+        let function: syn::ImplItemFn = syn::parse_str(&code).ok()?;
+        let block = function.block.span().byte_range();
+        let signature = code.get(..block.start)?.trim_end().to_string();
+        let body = code.get(block.start + 1..block.end - 1)?;
+        let mut patterns = BugPatterns {
+            code: &code,
+            found: Vec::new(),
+        };
+        patterns.visit_block(&function.block);
+        let bug_patterns = patterns.found;
+        Some(Self {
+            body: dedent(body),
+            signature,
+            bug_patterns,
+            code,
+        })
+    }
+}
+
+/// Visitor collecting the bug-prone patterns of a function.
+struct BugPatterns<'a> {
+    /// The code the spans of the function point into
+    code: &'a str,
+    found: Vec<(&'static str, String)>,
+}
+
+impl BugPatterns<'_> {
+    fn add(&mut self, label: &'static str, node: &impl Spanned) {
+        let snippet = self
+            .code
+            .get(node.span().byte_range())
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let snippet = if snippet.chars().count() > MAX_SNIPPET_CHARS {
+            let start: String = snippet.chars().take(MAX_SNIPPET_CHARS - 3).collect();
+            format!("{}...", start)
+        } else {
+            snippet
+        };
+        self.found.push((label, snippet));
+    }
+}
+
+impl<'ast> Visit<'ast> for BugPatterns<'_> {
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if call.method == "unwrap" || call.method == "expect" {
+            self.add("unwrap_or_expect", call);
+        }
+        syn::visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let name = mac
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string());
+        if let Some("panic" | "todo" | "unimplemented" | "unreachable") = name.as_deref() {
+            self.add("explicit_panic", mac);
+        }
+        syn::visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_index(&mut self, index: &'ast syn::ExprIndex) {
+        self.add("indexing", index);
+        syn::visit::visit_expr_index(self, index);
+    }
+
+    fn visit_expr_cast(&mut self, cast: &'ast syn::ExprCast) {
+        let numeric = match &*cast.ty {
+            syn::Type::Path(path) => path.path.get_ident().is_some_and(|ident| {
+                matches!(
+                    ident.to_string().as_str(),
+                    "u8" | "u16"
+                        | "u32"
+                        | "u64"
+                        | "u128"
+                        | "usize"
+                        | "i8"
+                        | "i16"
+                        | "i32"
+                        | "i64"
+                        | "i128"
+                        | "isize"
+                        | "f32"
+                        | "f64"
+                )
+            }),
+            _ => false,
+        };
+        if numeric {
+            self.add("numeric_cast", cast);
+        }
+        syn::visit::visit_expr_cast(self, cast);
+    }
+
+    fn visit_expr_unsafe(&mut self, block: &'ast syn::ExprUnsafe) {
+        self.add("unsafe_block", &block.unsafe_token);
+        syn::visit::visit_expr_unsafe(self, block);
+    }
+}
+
+/// Whether `element` is a test function.
+pub(crate) fn is_test(element: &CodeElement) -> bool {
+    element.element_type == ElementType::Function
+        && element
+            .attribute_info
+            .iter()
+            .any(|attribute| last_segment(&attribute.name) == "test")
+}
+
+/// The last segment of a path such as `Vec::new`.
+pub(crate) fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// The docs of `element` as a doc comment.
+fn doc_comment(element: &CodeElement) -> String {
+    element
+        .doc_comments
+        .iter()
+        .map(|line| match line.trim_end() {
+            "" => "///".to_string(),
+            line => format!("/// {}", line.strip_prefix(' ').unwrap_or(line)),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The number of words of `docs` outside code blocks.
+fn prose_words(docs: &[String]) -> usize {
+    let mut in_code = false;
+    let mut words = 0;
+    for line in docs {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        } else if !in_code {
+            words += line.split_whitespace().count();
+        }
+    }
+    words
+}
+
+fn rust_block(code: &str) -> String {
+    format!("```rust\n{}\n```", code)
+}
+
+/// The lines of `text`, the source of an item from its first character,
+/// with the indentation of its last line removed from the others.
+fn dedent_item(text: &str) -> Vec<&str> {
+    let last = text.lines().last().unwrap_or_default();
+    let indent = last.len() - last.trim_start().len();
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| match index {
+            0 => line,
+            _ => strip_indent(line, indent),
+        })
+        .collect()
+}
+
+/// The lines of `text` without their common indentation, and without the
+/// blank lines around them.
+fn dedent(text: &str) -> String {
+    let text = text.trim_matches(|c: char| c == '\n' || c == '\r');
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| strip_indent(line, indent))
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// `line` without up to `indent` bytes of leading whitespace.
+fn strip_indent(line: &str, indent: usize) -> &str {
+    let whitespace = line.len() - line.trim_start().len();
+    line.get(whitespace.min(indent)..).unwrap_or(line)
+}
+
+/// `text` with the identifier `word` replaced by `replacement`.
+fn replace_word(text: &str, word: &str, replacement: &str) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::new();
+    let mut copied = 0;
+    for (start, _) in text.match_indices(word) {
+        let end = start + word.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        if start < copied || before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+            continue;
+        }
+        result.push_str(&text[copied..start]);
+        result.push_str(replacement);
+        copied = end;
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::RagFormatter;
+    use rustex_core::{AstExtractor, ExtractorConfig};
+    use std::fs;
+
+    const LIB: &str = r#"/// Parses a port number from the text given on the command line.
+///
+/// ```
+/// assert_eq!(demo::parse_port("80"), Some(80));
+/// ```
+pub fn parse_port(text: &str) -> Option<u16> {
+    let port = text.trim().parse::<u32>().ok()?;
+    Some(port as u16)
+}
+
+/// First value.
+pub fn first(values: &[u8]) -> u8 {
+    values[0]
+}
+
+pub fn unfinished() -> u8 {
+    let value = 1;
+    todo!()
+}
+
+#[test]
+fn parse_port_reads_numbers() {
+    assert_eq!(parse_port("80"), Some(80));
+}
+"#;
+
+    /// The examples of `task` for the public functions of a sample crate.
+    fn examples(task: TaskType) -> Vec<TrainingExample> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), LIB).unwrap();
+        let config = ExtractorConfig {
+            include_private: true,
+            ..ExtractorConfig::default()
+        };
+        let project = AstExtractor::new(config, root.to_path_buf())
+            .extract_project()
+            .unwrap();
+
+        let formatter = RagFormatter::new(RagConfig {
+            training_tasks: vec![task],
+            ..RagConfig::default()
+        });
+        let examples = formatter.format(&project).unwrap().training_examples;
+        assert!(examples.iter().all(|example| example.task_type == task));
+        examples
+    }
+
+    #[test]
+    fn test_api_usage() {
+        let examples = examples(TaskType::ApiUsage);
+        assert_eq!(examples.len(), 1);
+        assert!(examples[0]
+            .input
+            .starts_with("Show how to use `crate::parse_port`:\n\n"));
+        assert_eq!(
+            examples[0].output,
+            "assert_eq!(demo::parse_port(\"80\"), Some(80));"
+        );
+        assert_eq!(
+            examples[0].metadata.learning_objectives,
+            ["Use `parse_port`"]
+        );
+    }
+
+    #[test]
+    fn test_test_generation() {
+        let examples = examples(TaskType::TestGeneration);
+        assert_eq!(examples.len(), 1);
+        assert!(examples[0]
+            .input
+            .starts_with("Write a test of this function:\n\n```rust\n"));
+        assert!(examples[0].input.contains("pub fn parse_port"));
+        assert_eq!(
+            examples[0].output,
+            "#[test]\nfn parse_port_reads_numbers() {\n    assert_eq!(parse_port(\"80\"), Some(80));\n}"
+        );
+    }
+
+    #[test]
+    fn test_documentation_generation() {
+        // `first` has too few words of docs.
+        let examples = examples(TaskType::DocumentationGeneration);
+        assert_eq!(examples.len(), 1);
+        assert!(!examples[0].input.contains("///"));
+        assert_eq!(
+            examples[0].output,
+            "/// Parses a port number from the text given on the command line.\n/// ```\n\
+             /// assert_eq!(demo::parse_port(\"80\"), Some(80));\n/// ```"
+        );
+    }
+
+    #[test]
+    fn test_code_completion() {
+        // `first` has a one-line body and `unfinished` isn't implemented.
+        let examples = examples(TaskType::CodeCompletion);
+        assert_eq!(examples.len(), 1);
+        assert!(examples[0]
+            .input
+            .ends_with("pub fn parse_port(text: &str) -> Option<u16> {\n```"));
+        assert_eq!(
+            examples[0].output,
+            "let port = text.trim().parse::<u32>().ok()?;\nSome(port as u16)"
+        );
+    }
+
+    #[test]
+    fn test_bug_finding() {
+        let examples = examples(TaskType::BugFinding);
+        let outputs: Vec<&str> = examples
+            .iter()
+            .map(|example| example.output.as_str())
+            .collect();
+        assert_eq!(
+            outputs,
+            [
+                "- numeric_cast: `port as u16`",
+                "- indexing: `values[0]`",
+                "- explicit_panic: `todo!()`",
+            ]
+        );
+        assert!(examples[0]
+            .input
+            .contains("- unsafe_block: `unsafe` blocks\n"));
+    }
+
+    #[test]
+    fn test_function_naming() {
+        let examples = examples(TaskType::FunctionNaming);
+        let outputs: Vec<&str> = examples
+            .iter()
+            .map(|example| example.output.as_str())
+            .collect();
+        assert_eq!(outputs, ["parse_port", "unfinished"]);
+        assert!(examples[0].input.contains("pub fn _(text: &str)"));
+        assert!(!examples[0].input.contains("parse_port"));
+    }
+
+    #[test]
+    fn test_replace_word() {
+        let code = "fn run() { run_all(); run() }";
+        assert_eq!(replace_word(code, "run", "_"), "fn _() { run_all(); _() }");
+        assert_eq!(dedent("\n    a\n      b\n"), "a\n  b");
+    }
+}
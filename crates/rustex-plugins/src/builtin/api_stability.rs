@@ -0,0 +1,211 @@
+//! API stability and churn plugin.
+
+use crate::core::{Plugin, PluginContext, PluginError, PluginInfo, PluginOutput, PluginPhase};
+use crate::plugin_info;
+use rustex_core::api_compat::{api_churn, check_compat, ApiStatus, API_STATUS_METADATA_KEY};
+use rustex_core::ProjectAst;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Configuration for the API stability plugin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct ApiStabilityConfig {
+    /// JSON extraction to compare against: a path, relative to the project
+    /// root, or an `http://` or `https://` URL
+    pub baseline: Option<String>,
+}
+
+/// Whether a baseline `location` is a URL rather than a path.
+pub fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Read the JSON extraction at `location`, a path or a URL downloaded with
+/// `curl`.
+pub fn load_baseline(location: &str) -> Result<ProjectAst, PluginError> {
+    let content = if is_url(location) {
+        let output = Command::new("curl")
+            .args(["--fail", "--silent", "--show-error", "--location", location])
+            .output()
+            .map_err(|e| PluginError::ProcessingFailed(format!("Failed to run curl: {}", e)))?;
+        if !output.status.success() {
+            return Err(PluginError::ProcessingFailed(format!(
+                "Failed to download baseline {}: {}",
+                location,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        output.stdout
+    } else {
+        fs::read(location).map_err(|e| {
+            PluginError::IoError(format!("Failed to read baseline {}: {}", location, e))
+        })?
+    };
+    serde_json::from_slice(&content).map_err(|e| {
+        PluginError::ProcessingFailed(format!("{} is not a JSON extraction: {}", location, e))
+    })
+}
+
+/// API stability plugin, giving each public element a status relative to a
+/// baseline extraction: `new`, `unchanged`, `signature_changed` or
+/// `removed_soon` for deprecated elements.
+///
+/// Statuses go in the `api_status` metadata of elements, which the RAG
+/// output's API stability classification reads. The plugin's metrics count
+/// the elements of each status and the removed ones, with the churn rate,
+/// and its metadata has the version bump the changes require, as the semver
+/// check finds.
+#[derive(Default)]
+pub struct ApiStabilityAnalyzer {
+    config: ApiStabilityConfig,
+}
+
+impl ApiStabilityAnalyzer {
+    /// Create a new API stability analyzer with custom configuration.
+    pub fn with_config(config: ApiStabilityConfig) -> Self {
+        Self { config }
+    }
+
+    /// Compare `project` with `baseline`, annotating its public elements.
+    fn analyze(&self, baseline: &ProjectAst, project: &ProjectAst) -> PluginOutput {
+        let churn = api_churn(baseline, project);
+        let mut output = PluginOutput::new();
+        for element in project.files.iter().flat_map(|file| &file.elements) {
+            if let Some(status) = churn.statuses.get(&element.id) {
+                let mut element = element.clone();
+                element.metadata.insert(
+                    API_STATUS_METADATA_KEY.to_string(),
+                    serde_json::to_value(status).unwrap_or_default(),
+                );
+                output.add_element(element);
+            }
+        }
+
+        for status in ApiStatus::ALL {
+            output.add_metric(format!("{}_elements", status), churn.count(status) as f64);
+        }
+        output.add_metric("removed_elements", churn.removed.len() as f64);
+        output.add_metric("baseline_elements", churn.baseline_items as f64);
+        output.add_metric("api_churn", churn.churn_rate());
+        output.add_metadata("removed", churn.removed);
+        output.add_metadata(
+            "required_bump",
+            check_compat(baseline, project).required_bump().to_string(),
+        );
+        output
+    }
+}
+
+impl Plugin for ApiStabilityAnalyzer {
+    fn info(&self) -> PluginInfo {
+        plugin_info!(
+            "api-stability",
+            "0.1.0",
+            "Status of public elements relative to a baseline extraction, with API churn",
+            phases: [PluginPhase::PostProject]
+        )
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        if !config.is_null() {
+            self.config = serde_json::from_value(config.clone())
+                .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(ApiStabilityConfig)).ok()
+    }
+
+    fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
+        let project = context.project_ast().ok_or_else(|| {
+            PluginError::InvalidContext("api-stability needs the project AST".to_string())
+        })?;
+        let Some(location) = &self.config.baseline else {
+            return Err(PluginError::ConfigurationError(
+                "api-stability needs a baseline extraction; set `baseline`".to_string(),
+            ));
+        };
+        let baseline = if is_url(location) || Path::new(location).is_absolute() {
+            load_baseline(location)?
+        } else {
+            let path = project.project.root_path.join(location);
+            load_baseline(&path.to_string_lossy())?
+        };
+        Ok(self.analyze(&baseline, project))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::{AstExtractor, ExtractorConfig};
+
+    fn extract(source: &str) -> ProjectAst {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"api\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), source).unwrap();
+        AstExtractor::new(ExtractorConfig::default(), root.to_path_buf())
+            .extract_project()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_analyze() {
+        let baseline = extract("pub fn stable() {}\npub fn widened(x: u32) {}\npub fn gone() {}\n");
+        let project = extract(concat!(
+            "pub fn stable() {}\npub fn widened(x: u64) {}\n",
+            "#[deprecated]\npub fn added() {}\n",
+        ));
+        let output = ApiStabilityAnalyzer::default().analyze(&baseline, &project);
+
+        let statuses: Vec<(&str, ApiStatus)> = output
+            .modified_elements
+            .iter()
+            .map(|element| (element.name.as_str(), ApiStatus::of(element).unwrap()))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("stable", ApiStatus::Unchanged),
+                ("widened", ApiStatus::SignatureChanged),
+                ("added", ApiStatus::RemovedSoon),
+            ]
+        );
+        assert_eq!(output.metrics["removed_soon_elements"], 1.0);
+        assert_eq!(output.metrics["removed_elements"], 1.0);
+        assert_eq!(output.metrics["api_churn"], 2.0 / 3.0);
+        assert_eq!(
+            output.additional_metadata["removed"],
+            serde_json::json!(["crate::gone"])
+        );
+        assert_eq!(output.additional_metadata["required_bump"], "major");
+    }
+
+    #[test]
+    fn test_load_baseline() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("baseline.json");
+        let project = extract("pub fn stable() {}\n");
+        fs::write(&path, serde_json::to_vec(&project).unwrap()).unwrap();
+        let baseline = load_baseline(&path.to_string_lossy()).unwrap();
+        assert_eq!(baseline.files.len(), project.files.len());
+
+        fs::write(&path, "not json").unwrap();
+        let error = load_baseline(&path.to_string_lossy()).unwrap_err();
+        assert!(error.to_string().contains("is not a JSON extraction"));
+        assert!(load_baseline("/nonexistent/baseline.json").is_err());
+    }
+}
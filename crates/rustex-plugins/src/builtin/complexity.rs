@@ -1,12 +1,15 @@
 //! Advanced complexity analysis plugin.
 
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use rustex_core::{ElementType, ComplexityLevel};
 use crate::core::{Plugin, PluginInfo, PluginPhase, PluginContext, PluginOutput, PluginError};
 use crate::plugin_info;
 
 /// Configuration for the complexity analyzer plugin.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct ComplexityConfig {
     /// Whether to include detailed complexity breakdown
     pub include_breakdown: bool,
@@ -37,17 +40,11 @@ impl Default for ComplexityConfig {
 }
 
 /// Advanced complexity analysis plugin.
+#[derive(Default)]
 pub struct ComplexityAnalyzer {
     config: ComplexityConfig,
 }
 
-impl Default for ComplexityAnalyzer {
-    fn default() -> Self {
-        Self {
-            config: ComplexityConfig::default(),
-        }
-    }
-}
 
 impl ComplexityAnalyzer {
     /// Create a new complexity analyzer with custom configuration.
@@ -135,7 +132,7 @@ impl ComplexityAnalyzer {
             }
             
             for (level, count) in complexity_distribution {
-                output.add_metric(&format!("complexity_{}", level), count as f64);
+                output.add_metric(format!("complexity_{}", level), count as f64);
             }
         }
         
@@ -306,6 +303,10 @@ impl Plugin for ComplexityAnalyzer {
         Ok(())
     }
     
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(ComplexityConfig)).ok()
+    }
+    
     fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
         self.analyze_project_complexity(context)
     }
@@ -329,6 +330,7 @@ mod tests {
             signature: Some(format!("fn {}()", name)),
             visibility: Visibility::Public,
             doc_comments: vec![],
+            doc_examples: vec![],
             inline_comments: vec![],
             location: CodeLocation {
                 line_start: 1,
@@ -336,7 +338,10 @@ mod tests {
                 char_start: 0,
                 char_end: 100,
                 file_path: PathBuf::from("test.rs"),
+                byte_start: 0,
+                byte_end: 0,
             },
+            name_location: None,
             attributes: vec![],
             complexity: Some(complexity_score),
             complexity_metrics: Some(ComplexityMetrics {
@@ -347,6 +352,8 @@ mod tests {
                 lines_of_code: 50,
                 parameter_count: 3,
                 return_count: 1,
+                closure_count: 0,
+                async_block_count: 0,
             }),
             dependencies: vec![],
             generic_params: vec![],
@@ -360,6 +367,11 @@ mod tests {
                     &Visibility::Public,
                 ),
             ),
+            is_generated: false,
+            cfg_predicates: vec![],
+            attribute_info: vec![],
+            deprecation: None,
+            calls: vec![],
         }
     }
 
@@ -1,12 +1,15 @@
 //! Documentation enhancement plugin.
 
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use rustex_core::ElementType;
 use crate::core::{Plugin, PluginInfo, PluginPhase, PluginContext, PluginOutput, PluginError};
 use crate::plugin_info;
 
 /// Configuration for the documentation enhancer plugin.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct DocEnhancerConfig {
     /// Whether to generate missing documentation
     pub generate_missing_docs: bool,
@@ -28,7 +31,7 @@ pub struct DocEnhancerConfig {
 }
 
 /// Documentation style options.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub enum DocStyle {
     /// Standard Rust documentation style
     #[default]
@@ -186,7 +189,9 @@ impl DocEnhancer {
                 // Prioritize public items
                 let priority = match element.visibility {
                     rustex_core::Visibility::Public => "high",
-                    rustex_core::Visibility::Restricted(_) => "medium",
+                    rustex_core::Visibility::Crate
+                    | rustex_core::Visibility::Super
+                    | rustex_core::Visibility::Restricted(_) => "medium",
                     rustex_core::Visibility::Private => "low",
                 };
                 
@@ -432,6 +437,35 @@ impl DocEnhancer {
         suggestions
     }
     
+    /// Annotate an element's metadata with the quality of its documentation,
+    /// and suggestions for missing or weak documentation.
+    fn annotate_element(&self, element: &mut rustex_core::CodeElement) -> PluginOutput {
+        let mut output = PluginOutput::new();
+        if element.doc_comments.is_empty() {
+            if self.config.generate_missing_docs {
+                let suggestion = self.generate_documentation_suggestion(element);
+                element
+                    .metadata
+                    .insert("doc_suggestion".to_string(), suggestion.into());
+                output.has_modifications = true;
+            }
+            return output;
+        }
+        
+        let quality = self.calculate_documentation_quality(element);
+        element
+            .metadata
+            .insert("doc_quality".to_string(), quality.into());
+        if self.config.enhance_existing_docs && quality < self.config.min_quality_score {
+            let suggestions = self.generate_enhancement_suggestions(element, quality);
+            element
+                .metadata
+                .insert("doc_enhancements".to_string(), suggestions.into());
+        }
+        output.has_modifications = true;
+        output
+    }
+    
     /// Generate usage example for an element.
     fn generate_usage_example(&self, element: &rustex_core::CodeElement) -> String {
         match element.element_type {
@@ -461,7 +495,7 @@ impl Plugin for DocEnhancer {
             "doc-enhancer",
             "0.1.0",
             "Enhance and analyze code documentation quality",
-            phases: [PluginPhase::PostProject]
+            phases: [PluginPhase::PostElement, PluginPhase::PostProject]
         )
     }
     
@@ -473,6 +507,18 @@ impl Plugin for DocEnhancer {
         Ok(())
     }
     
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(DocEnhancerConfig)).ok()
+    }
+    
+    fn post_element(
+        &self,
+        element: &mut rustex_core::CodeElement,
+        _context: &PluginContext,
+    ) -> Result<PluginOutput, PluginError> {
+        Ok(self.annotate_element(element))
+    }
+    
     fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
         self.enhance_documentation(context)
     }
@@ -493,6 +539,7 @@ mod tests {
             signature: Some(format!("fn {}()", name)),
             visibility: Visibility::Public,
             doc_comments: docs,
+            doc_examples: vec![],
             inline_comments: vec![],
             location: CodeLocation {
                 line_start: 1,
@@ -500,7 +547,10 @@ mod tests {
                 char_start: 0,
                 char_end: 50,
                 file_path: PathBuf::from("test.rs"),
+                byte_start: 0,
+                byte_end: 0,
             },
+            name_location: None,
             attributes: vec![],
             complexity: Some(1),
             complexity_metrics: None,
@@ -516,6 +566,11 @@ mod tests {
                     &Visibility::Public,
                 ),
             ),
+            is_generated: false,
+            cfg_predicates: vec![],
+            attribute_info: vec![],
+            deprecation: None,
+            calls: vec![],
         }
     }
 
@@ -570,4 +625,33 @@ mod tests {
         let poor_quality = enhancer.calculate_documentation_quality(&poor_element);
         assert!(poor_quality < quality);
     }
+
+    #[test]
+    fn test_element_annotation() {
+        let enhancer = DocEnhancer::default();
+        let project_info = ProjectInfo {
+            name: "test".to_string(),
+            version: "0.1.0".to_string(),
+            rust_edition: "2021".to_string(),
+            root_path: PathBuf::from("/test"),
+        };
+        let config = ExtractorConfig::default();
+        let mut metadata = HashMap::new();
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        
+        let mut undocumented = create_test_element_with_docs("undocumented", vec![]);
+        let output = enhancer.post_element(&mut undocumented, &context).unwrap();
+        assert!(output.has_modifications);
+        assert!(undocumented.metadata["doc_suggestion"]
+            .as_str()
+            .unwrap()
+            .contains("undocumented"));
+        
+        let mut poor = create_test_element_with_docs("poor", vec!["/// Bad docs".to_string()]);
+        enhancer.post_element(&mut poor, &context).unwrap();
+        assert!(poor.metadata["doc_quality"].as_f64().unwrap() < 0.7);
+        assert!(poor.metadata["doc_enhancements"]
+            .as_array()
+            .is_some_and(|s| !s.is_empty()));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,632 @@
+//! License header and SPDX identifier plugin.
+
+use crate::core::plugin::MessageLevel;
+use crate::core::{Plugin, PluginContext, PluginError, PluginInfo, PluginOutput, PluginPhase};
+use crate::plugin_info;
+use rustex_core::dependencies::{registry_source_dirs_in, CargoSettings};
+use rustex_core::workspace::discover_workspace;
+use rustex_core::{DependencyGraph, DependencyKind, PackageRole};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Key of the plugin metadata holding the license inventory of each crate.
+pub const LICENSE_INVENTORY_METADATA_KEY: &str = "license_inventory";
+
+/// License of dependencies whose manifests aren't in the local cargo registry.
+const UNKNOWN_LICENSE: &str = "unknown";
+
+/// Configuration for the license checker plugin.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct LicenseCheckerConfig {
+    /// SPDX license expression files must declare, by default the `license`
+    /// of their crate's `Cargo.toml`
+    pub license: Option<String>,
+
+    /// Whether files must have an `SPDX-License-Identifier` comment
+    pub require_spdx: bool,
+
+    /// Text the leading comments of files must contain, such as a copyright
+    /// notice; comment markers and line breaks don't matter
+    pub header: Option<String>,
+
+    /// Whether to list the licenses of each crate's dependencies, read from
+    /// their manifests in the local cargo registry
+    pub dependencies: bool,
+}
+
+impl Default for LicenseCheckerConfig {
+    fn default() -> Self {
+        Self {
+            license: None,
+            require_spdx: true,
+            header: None,
+            dependencies: true,
+        }
+    }
+}
+
+/// The licenses of a crate: declared, in its files and of its dependencies.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateLicenses {
+    /// Crate name
+    pub name: String,
+
+    /// License declared in the crate's `Cargo.toml`
+    pub license: Option<String>,
+
+    /// Number of files checked
+    pub files: usize,
+
+    /// Number of files by the SPDX expression they declare, normalized
+    pub file_licenses: BTreeMap<String, usize>,
+
+    /// Number of files declaring no SPDX expression
+    pub files_without_spdx: usize,
+
+    /// Packages the crate depends on, directly or not, by normalized license,
+    /// leaving out dev and build dependencies
+    pub dependency_licenses: BTreeMap<String, Vec<String>>,
+}
+
+/// A license problem of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LicenseRule {
+    /// No `SPDX-License-Identifier` comment
+    MissingSpdx,
+    /// An SPDX expression other than the expected license
+    LicenseMismatch,
+    /// Leading comments without the configured header
+    MissingHeader,
+}
+
+impl LicenseRule {
+    /// ID of the rule in diagnostics.
+    pub fn id(&self) -> &'static str {
+        match self {
+            LicenseRule::MissingSpdx => "missing-spdx",
+            LicenseRule::LicenseMismatch => "license-mismatch",
+            LicenseRule::MissingHeader => "missing-header",
+        }
+    }
+
+    /// Level of the rule's diagnostics.
+    pub fn level(&self) -> MessageLevel {
+        match self {
+            LicenseRule::LicenseMismatch => MessageLevel::Error,
+            LicenseRule::MissingSpdx | LicenseRule::MissingHeader => MessageLevel::Warning,
+        }
+    }
+}
+
+/// License checker plugin, checking that files declare the expected license
+/// in an `SPDX-License-Identifier` comment and carry the configured header.
+///
+/// Problems are reported as diagnostics on the files, which SARIF output
+/// lists. The `license_inventory` metadata lists the licenses of each crate:
+/// declared, found in its files and of its dependencies.
+#[derive(Default)]
+pub struct LicenseChecker {
+    config: LicenseCheckerConfig,
+    /// Cargo home whose registry holds the manifests of dependencies
+    cargo: CargoSettings,
+}
+
+/// A crate of the project being checked.
+struct CrateEntry {
+    /// Crate directory, relative to the project root
+    relative_path: PathBuf,
+    licenses: CrateLicenses,
+}
+
+impl LicenseChecker {
+    /// Create a new license checker with custom configuration.
+    pub fn with_config(config: LicenseCheckerConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Read the licenses of dependencies from the registry of the Cargo home
+    /// of `cargo`, instead of the environment's.
+    pub fn with_cargo(mut self, cargo: CargoSettings) -> Self {
+        self.cargo = cargo;
+        self
+    }
+
+    /// Check the leading comments of `source`, the contents of `file`,
+    /// against `expected`, the license of its crate, adding problems to the
+    /// diagnostics of `output`. Returns the declared SPDX expression.
+    fn check_file(
+        &self,
+        file: &Path,
+        source: &str,
+        expected: Option<&str>,
+        output: &mut PluginOutput,
+    ) -> Option<String> {
+        let comments = leading_comments(source);
+        let spdx = comments.iter().find_map(|(line, text)| {
+            let (_, expression) = text.split_once("SPDX-License-Identifier:")?;
+            Some((*line, expression.trim().to_string()))
+        });
+        let mut problem = |rule: LicenseRule, message: String, line: usize| {
+            output.add_diagnostic(rule.level(), rule.id(), message, file, line);
+        };
+
+        let expected = self.config.license.as_deref().or(expected);
+        match (&spdx, expected) {
+            (None, _) if self.config.require_spdx => problem(
+                LicenseRule::MissingSpdx,
+                "No SPDX-License-Identifier comment".to_string(),
+                1,
+            ),
+            (Some((line, expression)), Some(expected))
+                if !normalize_expression(expression)
+                    .eq_ignore_ascii_case(&normalize_expression(expected)) =>
+            {
+                problem(
+                    LicenseRule::LicenseMismatch,
+                    format!("License `{}` isn't the expected `{}`", expression, expected),
+                    *line,
+                );
+            }
+            _ => {}
+        }
+
+        if let Some(header) = &self.config.header {
+            let text: Vec<&str> = comments.iter().map(|(_, text)| text.as_str()).collect();
+            if !collapse_whitespace(&text.join(" ")).contains(&collapse_whitespace(header)) {
+                problem(
+                    LicenseRule::MissingHeader,
+                    "No license header".to_string(),
+                    1,
+                );
+            }
+        }
+
+        spdx.map(|(_, expression)| expression)
+    }
+}
+
+/// The text of the comments at the start of `source`, by line number, before
+/// the first line of code.
+fn leading_comments(source: &str) -> Vec<(usize, String)> {
+    let mut comments = Vec::new();
+    let mut in_block = false;
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        let is_comment = in_block || line.starts_with("//") || line.starts_with("/*");
+        if !is_comment {
+            let is_shebang = index == 0 && line.starts_with("#!") && !line.starts_with("#![");
+            if line.is_empty() || is_shebang {
+                continue;
+            }
+            break;
+        }
+        if line.starts_with("/*") {
+            in_block = true;
+        }
+        if line.ends_with("*/") {
+            in_block = false;
+        }
+
+        let text = ["//!", "///", "//", "/*!", "/**", "/*"]
+            .iter()
+            .find_map(|marker| line.strip_prefix(marker))
+            .unwrap_or(line);
+        let text = text.trim_end_matches("*/").trim_start_matches('*');
+        comments.push((index + 1, text.trim().to_string()));
+    }
+    comments
+}
+
+/// `text` with runs of whitespace replaced by a single space.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// An SPDX expression in a form equal, ignoring case, for equivalent
+/// expressions: with uppercase operators, the legacy `/` separator as `OR`
+/// and the licenses of a plain `OR` list sorted.
+fn normalize_expression(expression: &str) -> String {
+    let expression = expression.replace('/', " OR ");
+    let tokens: Vec<String> = expression
+        .split_whitespace()
+        .map(|token| match token.to_uppercase().as_str() {
+            operator @ ("OR" | "AND" | "WITH") => operator.to_string(),
+            _ => token.to_string(),
+        })
+        .collect();
+    let expression = tokens.join(" ");
+    if expression.contains('(') || expression.contains(" AND ") || expression.contains(" WITH ") {
+        return expression;
+    }
+    let mut licenses: Vec<&str> = expression.split(" OR ").collect();
+    licenses.sort_by_key(|license| license.to_lowercase());
+    licenses.join(" OR ")
+}
+
+/// The `license` of a `Cargo.toml`, or of the workspace root's
+/// `[workspace.package]` when the crate inherits it, or its `license-file`.
+fn manifest_license(manifest_path: &Path, workspace_root: &Path) -> Option<String> {
+    let manifest: toml::Value = toml::from_str(&fs::read_to_string(manifest_path).ok()?).ok()?;
+    let package = manifest.get("package")?;
+    match package.get("license") {
+        Some(toml::Value::String(license)) => Some(license.clone()),
+        Some(license) if license.get("workspace").and_then(toml::Value::as_bool) == Some(true) => {
+            let root = workspace_root.join("Cargo.toml");
+            let root: toml::Value = toml::from_str(&fs::read_to_string(root).ok()?).ok()?;
+            let license = root.get("workspace")?.get("package")?.get("license")?;
+            license.as_str().map(String::from)
+        }
+        _ => package
+            .get("license-file")
+            .and_then(|file| file.as_str())
+            .map(|file| format!("license file {}", file)),
+    }
+}
+
+/// The license of the package `name` at `version`, from its manifest in one
+/// of the registry source directories `registries`.
+fn dependency_license(registries: &[PathBuf], name: &str, version: &str) -> Option<String> {
+    let directory = format!("{}-{}", name, version);
+    registries
+        .iter()
+        .map(|registry| registry.join(&directory))
+        .find(|path| path.join("Cargo.toml").exists())
+        .and_then(|path| manifest_license(&path.join("Cargo.toml"), &path))
+}
+
+/// Fill in the licenses of the dependencies of each crate in `crates` from
+/// the project's `Cargo.lock`, with the manifests in the registry source
+/// directories `registries`.
+fn add_dependency_licenses(
+    graph: &DependencyGraph,
+    registries: &[PathBuf],
+    crates: &mut [CrateEntry],
+) {
+    let mut licenses: HashMap<&str, String> = HashMap::new();
+    for package in &graph.packages {
+        if package.role != PackageRole::Root {
+            let license = dependency_license(registries, &package.name, &package.version);
+            let license = license.map_or_else(
+                || UNKNOWN_LICENSE.to_string(),
+                |license| normalize_expression(&license),
+            );
+            licenses.insert(&package.id, license);
+        }
+    }
+
+    for entry in crates {
+        let Some(root) = graph.packages.iter().find(|package| {
+            package.role == PackageRole::Root && package.name == entry.licenses.name
+        }) else {
+            continue;
+        };
+        // Only edges leaving a root have a kind besides normal.
+        let mut seen = BTreeSet::new();
+        let mut queue = VecDeque::from([root.id.as_str()]);
+        while let Some(id) = queue.pop_front() {
+            let dependencies = graph
+                .edges
+                .iter()
+                .filter(|edge| edge.from == id && edge.kind == DependencyKind::Normal);
+            for edge in dependencies {
+                if licenses.contains_key(edge.to.as_str()) && seen.insert(edge.to.as_str()) {
+                    queue.push_back(&edge.to);
+                }
+            }
+        }
+        for id in seen {
+            entry
+                .licenses
+                .dependency_licenses
+                .entry(licenses[id].clone())
+                .or_default()
+                .push(id.to_string());
+        }
+    }
+}
+
+impl Plugin for LicenseChecker {
+    fn info(&self) -> PluginInfo {
+        plugin_info!(
+            "license-checker",
+            "0.1.0",
+            "Check of license headers and SPDX identifiers, with a license inventory",
+            phases: [PluginPhase::PostProject]
+        )
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        if !config.is_null() {
+            self.config = serde_json::from_value(config.clone())
+                .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(LicenseCheckerConfig)).ok()
+    }
+
+    fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
+        let project = context.project_ast().ok_or_else(|| {
+            PluginError::InvalidContext("license-checker needs the project AST".to_string())
+        })?;
+        let root = &project.project.root_path;
+
+        // The root package, then the workspace members.
+        let mut crates = vec![CrateEntry {
+            relative_path: PathBuf::new(),
+            licenses: CrateLicenses {
+                name: project.project.name.clone(),
+                license: manifest_license(&root.join("Cargo.toml"), root),
+                ..CrateLicenses::default()
+            },
+        }];
+        let members = discover_workspace(root)
+            .map_err(|e| PluginError::ProcessingFailed(e.to_string()))?
+            .unwrap_or_default();
+        for member in members {
+            let manifest_path = root.join(&member.relative_path).join("Cargo.toml");
+            crates.push(CrateEntry {
+                licenses: CrateLicenses {
+                    name: member.name,
+                    license: manifest_license(&manifest_path, root),
+                    ..CrateLicenses::default()
+                },
+                relative_path: member.relative_path,
+            });
+        }
+
+        let mut output = PluginOutput::new();
+        for file in &project.files {
+            let Ok(source) = fs::read_to_string(&file.path) else {
+                continue;
+            };
+            let entry = crates
+                .iter_mut()
+                .filter(|entry| file.relative_path.starts_with(&entry.relative_path))
+                .max_by_key(|entry| entry.relative_path.components().count())
+                .expect("the root crate contains every file");
+            let expected = entry.licenses.license.as_deref();
+            let spdx = self.check_file(&file.relative_path, &source, expected, &mut output);
+            entry.licenses.files += 1;
+            match spdx {
+                Some(expression) => {
+                    let expression = normalize_expression(&expression);
+                    *entry.licenses.file_licenses.entry(expression).or_default() += 1;
+                }
+                None => entry.licenses.files_without_spdx += 1,
+            }
+        }
+
+        if self.config.dependencies {
+            match DependencyGraph::from_project(root) {
+                Ok(graph) => {
+                    let registries = registry_source_dirs_in(&self.cargo);
+                    add_dependency_licenses(&graph, &registries, &mut crates)
+                }
+                Err(e) => tracing::debug!("No dependency licenses: {}", e),
+            }
+        }
+
+        // Virtual workspace roots have neither a package nor files.
+        let inventory: Vec<CrateLicenses> = crates
+            .into_iter()
+            .map(|entry| entry.licenses)
+            .filter(|licenses| licenses.files > 0 || licenses.license.is_some())
+            .collect();
+        let count = |rule: LicenseRule| {
+            let rule = Some(rule.id());
+            output
+                .messages
+                .iter()
+                .filter(|message| message.rule.as_deref() == rule)
+                .count()
+        };
+        let dependency_licenses: BTreeMap<&str, BTreeSet<&str>> = inventory
+            .iter()
+            .flat_map(|licenses| &licenses.dependency_licenses)
+            .fold(BTreeMap::new(), |mut all, (license, packages)| {
+                all.entry(license.as_str())
+                    .or_insert_with(BTreeSet::new)
+                    .extend(packages.iter().map(String::as_str));
+                all
+            });
+        let metrics = [
+            (
+                "files_checked",
+                inventory
+                    .iter()
+                    .map(|licenses| licenses.files)
+                    .sum::<usize>(),
+            ),
+            ("files_without_spdx", count(LicenseRule::MissingSpdx)),
+            ("license_mismatches", count(LicenseRule::LicenseMismatch)),
+            ("missing_headers", count(LicenseRule::MissingHeader)),
+            ("dependency_licenses", dependency_licenses.len()),
+            (
+                "unknown_dependency_licenses",
+                dependency_licenses
+                    .get(UNKNOWN_LICENSE)
+                    .map_or(0, BTreeSet::len),
+            ),
+        ];
+        for (name, value) in metrics {
+            output.add_metric(name, value as f64);
+        }
+        output.add_metadata(
+            LICENSE_INVENTORY_METADATA_KEY,
+            serde_json::to_value(&inventory).unwrap_or_default(),
+        );
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap as Map;
+
+    const SOURCE: &str = "#!/usr/bin/env rust-script
+// Copyright (c) 2024 Example Corp.
+// All rights reserved.
+/*
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Crate docs.
+
+// SPDX-License-Identifier: GPL-3.0
+fn main() {}
+";
+
+    fn rules(output: &PluginOutput) -> Vec<(&str, Option<usize>)> {
+        output
+            .messages
+            .iter()
+            .map(|message| (message.rule.as_deref().unwrap(), message.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_leading_comments() {
+        let comments = leading_comments(SOURCE);
+        assert_eq!(
+            comments[0],
+            (2, "Copyright (c) 2024 Example Corp.".to_string())
+        );
+        assert_eq!(comments[3], (5, "SPDX-License-Identifier: MIT".to_string()));
+        assert_eq!(comments.last().unwrap().0, 10);
+        assert!(leading_comments("use std::fs;\n// SPDX-License-Identifier: MIT\n").is_empty());
+
+        assert_eq!(normalize_expression("MIT/Apache-2.0"), "Apache-2.0 OR MIT");
+        assert_eq!(
+            normalize_expression("mit or  Apache-2.0"),
+            "Apache-2.0 OR mit"
+        );
+        let expression = normalize_expression("(MIT OR Apache-2.0) and Unicode-3.0");
+        assert_eq!(expression, "(MIT OR Apache-2.0) AND Unicode-3.0");
+        assert_ne!(
+            normalize_expression("MIT AND Apache-2.0"),
+            normalize_expression("MIT")
+        );
+    }
+
+    #[test]
+    fn test_check_file() {
+        let file = Path::new("src/main.rs");
+        let checker = LicenseChecker::default();
+        let mut output = PluginOutput::new();
+        let spdx = checker.check_file(file, SOURCE, Some("Apache-2.0 OR MIT"), &mut output);
+        assert_eq!(spdx.as_deref(), Some("MIT"));
+        assert_eq!(rules(&output), [("license-mismatch", Some(5))]);
+        let message = &output.messages[0].message;
+        assert_eq!(
+            message,
+            "License `MIT` isn't the expected `Apache-2.0 OR MIT`"
+        );
+
+        let mut output = PluginOutput::new();
+        checker.check_file(file, SOURCE, Some("MIT"), &mut output);
+        checker.check_file(file, SOURCE, None, &mut output);
+        assert!(output.messages.is_empty());
+
+        let config = LicenseCheckerConfig {
+            license: Some("MIT".to_string()),
+            header: Some("Copyright (c) 2024 Example Corp. All rights reserved.".to_string()),
+            ..LicenseCheckerConfig::default()
+        };
+        let checker = LicenseChecker::with_config(config);
+        let mut output = PluginOutput::new();
+        checker.check_file(file, SOURCE, Some("GPL-3.0"), &mut output);
+        assert!(output.messages.is_empty());
+        checker.check_file(file, "fn main() {}\n", None, &mut output);
+        assert_eq!(
+            rules(&output),
+            [("missing-spdx", Some(1)), ("missing-header", Some(1))]
+        );
+    }
+
+    #[test]
+    fn test_dependency_licenses() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let registry = temp_dir.path().to_path_buf();
+        let manifest = |name: &str, license: &str| {
+            let directory = registry.join(name);
+            fs::create_dir_all(&directory).unwrap();
+            let content = format!(
+                "[package]\nname = \"x\"\nversion = \"1.0.0\"\n{}\n",
+                license
+            );
+            fs::write(directory.join("Cargo.toml"), content).unwrap();
+        };
+        manifest("serde-1.0.0", "license = \"MIT OR Apache-2.0\"");
+        manifest("odd-0.1.0", "license-file = \"LICENSE.txt\"");
+
+        let registries = [PathBuf::from("/nonexistent"), registry.clone()];
+        let license = |name, version| dependency_license(&registries, name, version);
+        assert_eq!(
+            license("serde", "1.0.0").as_deref(),
+            Some("MIT OR Apache-2.0")
+        );
+        assert_eq!(
+            license("odd", "0.1.0").as_deref(),
+            Some("license file LICENSE.txt")
+        );
+        assert_eq!(license("serde", "2.0.0"), None);
+
+        // Members inherit the workspace license.
+        let workspace = registry.join("workspace");
+        fs::create_dir_all(workspace.join("member")).unwrap();
+        let root_manifest = "[workspace.package]\nlicense = \"MIT\"\n";
+        fs::write(workspace.join("Cargo.toml"), root_manifest).unwrap();
+        fs::write(
+            workspace.join("member/Cargo.toml"),
+            "[package]\nname = \"member\"\nlicense.workspace = true\n",
+        )
+        .unwrap();
+        let license = manifest_license(&workspace.join("member/Cargo.toml"), &workspace);
+        assert_eq!(license.as_deref(), Some("MIT"));
+
+        let lockfile = "version = 3\n\n[[package]]\nname = \"app\"\nversion = \"0.1.0\"\n\
+                        dependencies = [\"serde\", \"tempfile\"]\n\n[[package]]\nname = \"serde\"\n\
+                        version = \"1.0.0\"\nsource = \"registry+https://example.com\"\n\n\
+                        [[package]]\nname = \"tempfile\"\nversion = \"3.0.0\"\n\
+                        source = \"registry+https://example.com\"\n";
+        let kinds = Map::from([("tempfile".to_string(), DependencyKind::Dev)]);
+        let roots = [("app".to_string(), kinds)].into_iter().collect();
+        let graph = DependencyGraph::from_lockfile(lockfile, &roots).unwrap();
+        let mut crates = vec![CrateEntry {
+            relative_path: PathBuf::new(),
+            licenses: CrateLicenses {
+                name: "app".to_string(),
+                ..CrateLicenses::default()
+            },
+        }];
+        // Manifests are read from the registry of the configured Cargo home.
+        let cargo_home = temp_dir.path().join("cargo");
+        let source = cargo_home.join("registry/src/example.com-1");
+        fs::create_dir_all(&source).unwrap();
+        fs::rename(registry.join("serde-1.0.0"), source.join("serde-1.0.0")).unwrap();
+        let cargo = CargoSettings {
+            home: Some(cargo_home),
+            ..CargoSettings::default()
+        };
+        let checker = LicenseChecker::default().with_cargo(cargo);
+        let registries = registry_source_dirs_in(&checker.cargo);
+        assert_eq!(registries, [source]);
+        add_dependency_licenses(&graph, &registries, &mut crates);
+        // Dev dependencies are left out.
+        let licenses = &crates[0].licenses.dependency_licenses;
+        assert_eq!(licenses.len(), 1);
+        assert_eq!(licenses["Apache-2.0 OR MIT"], ["serde 1.0.0"]);
+    }
+}
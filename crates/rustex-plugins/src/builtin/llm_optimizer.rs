@@ -1,12 +1,15 @@
 //! LLM output optimization plugin.
 
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use rustex_core::ElementType;
 use crate::core::{Plugin, PluginInfo, PluginPhase, PluginContext, PluginOutput, PluginError};
 use crate::plugin_info;
 
 /// Configuration for the LLM optimizer plugin.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct LlmOptimizerConfig {
     /// Target LLM model for optimization
     pub target_model: String,
@@ -234,7 +237,7 @@ impl LlmOptimizer {
         let content_length = element.name.len() + 
             element.signature.as_ref().map(|s| s.len()).unwrap_or(0) +
             element.doc_comments.iter().map(|c| c.len()).sum::<usize>() +
-            element.inline_comments.iter().map(|c| c.len()).sum::<usize>();
+            element.inline_comments.iter().map(|c| c.text.len()).sum::<usize>();
             
         (content_length / 4).max(10) // Minimum 10 tokens per element
     }
@@ -400,6 +403,10 @@ impl Plugin for LlmOptimizer {
         Ok(())
     }
     
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(LlmOptimizerConfig)).ok()
+    }
+    
     fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
         self.optimize_for_llm(context)
     }
@@ -455,6 +462,7 @@ mod tests {
             signature: Some("fn test_function() -> i32".to_string()),
             visibility: Visibility::Public,
             doc_comments: vec!["A test function".to_string()],
+            doc_examples: vec![],
             inline_comments: vec![],
             location: CodeLocation {
                 line_start: 1,
@@ -462,7 +470,10 @@ mod tests {
                 char_start: 0,
                 char_end: 50,
                 file_path: PathBuf::from("test.rs"),
+                byte_start: 0,
+                byte_end: 0,
             },
+            name_location: None,
             attributes: vec![],
             complexity: Some(1),
             complexity_metrics: None,
@@ -478,6 +489,11 @@ mod tests {
                     &Visibility::Public,
                 ),
             ),
+            is_generated: false,
+            cfg_predicates: vec![],
+            attribute_info: vec![],
+            deprecation: None,
+            calls: vec![],
         };
         
         let tokens = optimizer.estimate_tokens(&element);
@@ -1,12 +1,15 @@
 //! Comprehensive metrics collection plugin.
 
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use rustex_core::ElementType;
 use crate::core::{Plugin, PluginInfo, PluginPhase, PluginContext, PluginOutput, PluginError};
 use crate::plugin_info;
 
 /// Configuration for the metrics collector plugin.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct MetricsConfig {
     /// Whether to collect detailed metrics
     pub detailed_metrics: bool,
@@ -223,7 +226,9 @@ impl MetricsCollector {
             let visibility_key = match &element.visibility {
                 rustex_core::Visibility::Public => "public",
                 rustex_core::Visibility::Private => "private",
-                rustex_core::Visibility::Restricted(_) => "restricted",
+                rustex_core::Visibility::Crate
+                | rustex_core::Visibility::Super
+                | rustex_core::Visibility::Restricted(_) => "restricted",
             };
             
             *visibility_counts.entry(visibility_key).or_insert(0) += 1;
@@ -479,6 +484,10 @@ impl Plugin for MetricsCollector {
         Ok(())
     }
     
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(MetricsConfig)).ok()
+    }
+    
     fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
         self.collect_metrics(context)
     }
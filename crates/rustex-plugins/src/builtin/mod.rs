@@ -3,16 +3,30 @@
 //! This module contains plugins that ship with RustEx by default,
 //! providing common functionality for AST analysis and enhancement.
 
+pub mod api_stability;
 pub mod complexity;
 pub mod llm_optimizer;
 pub mod doc_enhancer;
+pub mod license;
 pub mod metrics;
+pub mod naming;
+pub mod rustsec;
+pub mod security;
+pub mod tech_debt;
+pub mod test_mapping;
 
 // Re-export built-in plugins
+pub use api_stability::ApiStabilityAnalyzer;
 pub use complexity::ComplexityAnalyzer;
 pub use llm_optimizer::LlmOptimizer;
 pub use doc_enhancer::DocEnhancer;
+pub use license::LicenseChecker;
 pub use metrics::MetricsCollector;
+pub use naming::NamingLinter;
+pub use rustsec::RustSecAuditor;
+pub use security::SecurityAnalyzer;
+pub use tech_debt::TechDebtCollector;
+pub use test_mapping::TestMapper;
 
 /// Register all built-in plugins with a plugin manager.
 pub fn register_builtin_plugins(
@@ -22,6 +36,28 @@ pub fn register_builtin_plugins(
     manager.register_plugin(Box::new(LlmOptimizer::default()))?;
     manager.register_plugin(Box::new(DocEnhancer::default()))?;
     manager.register_plugin(Box::new(MetricsCollector::default()))?;
+    manager.register_plugin(Box::new(SecurityAnalyzer::default()))?;
+    manager.register_plugin(Box::new(RustSecAuditor::default()))?;
+    manager.register_plugin(Box::new(TechDebtCollector::default()))?;
+    manager.register_plugin(Box::new(LicenseChecker::default()))?;
+    manager.register_plugin(Box::new(TestMapper::default()))?;
+    manager.register_plugin(Box::new(ApiStabilityAnalyzer::default()))?;
+    manager.register_plugin(Box::new(NamingLinter::default()))?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PluginManager;
+    use rustex_core::config_diagnostics::BUILTIN_PLUGINS;
+
+    #[test]
+    fn test_builtin_plugin_names() {
+        let mut manager = PluginManager::new();
+        register_builtin_plugins(&mut manager).unwrap();
+        // Config validation and `rustex plugins` tell built-ins apart by this list.
+        assert_eq!(manager.list_plugins(), BUILTIN_PLUGINS);
+    }
+}
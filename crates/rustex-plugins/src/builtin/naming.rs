@@ -0,0 +1,459 @@
+//! Naming convention plugin.
+
+use crate::core::plugin::MessageLevel;
+use crate::core::{Plugin, PluginContext, PluginError, PluginInfo, PluginOutput, PluginPhase};
+use crate::plugin_info;
+use rustex_core::{CodeElement, ElementType, FileAst};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Configuration for the naming linter plugin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct NamingLinterConfig {
+    /// Rules not to check, by ID (see [`NamingRule::id`])
+    pub disabled_rules: Vec<String>,
+
+    /// Prefix the names of each kind of element must start with, by kind:
+    /// `module`, `function`, `struct`, `enum`, `trait`, `type_alias`,
+    /// `union`, `constant`, `static` or `macro`
+    pub prefixes: BTreeMap<String, String>,
+
+    /// Names not to check, such as those of FFI bindings
+    pub allowed_names: Vec<String>,
+}
+
+/// A naming convention the naming linter checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamingRule {
+    /// Modules are `snake_case`
+    ModuleSnakeCase,
+    /// Functions, methods and macros are `snake_case`
+    FunctionSnakeCase,
+    /// Structs, enums, traits, unions and type aliases are `CamelCase`
+    TypeCamelCase,
+    /// Constants and statics are `SCREAMING_SNAKE_CASE`
+    ConstScreamingSnakeCase,
+    /// Names start with the prefix configured for their kind
+    MissingPrefix,
+}
+
+impl NamingRule {
+    /// All rules, in the order they're checked.
+    pub const ALL: [NamingRule; 5] = [
+        NamingRule::ModuleSnakeCase,
+        NamingRule::FunctionSnakeCase,
+        NamingRule::TypeCamelCase,
+        NamingRule::ConstScreamingSnakeCase,
+        NamingRule::MissingPrefix,
+    ];
+
+    /// ID of the rule in diagnostics and `disabled_rules`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            NamingRule::ModuleSnakeCase => "module-snake-case",
+            NamingRule::FunctionSnakeCase => "function-snake-case",
+            NamingRule::TypeCamelCase => "type-camel-case",
+            NamingRule::ConstScreamingSnakeCase => "const-screaming-snake-case",
+            NamingRule::MissingPrefix => "missing-prefix",
+        }
+    }
+
+    /// The case rule for elements of `kind`, with the `allow` lint that
+    /// silences rustc's own check of it.
+    fn for_kind(kind: &str) -> Option<(NamingRule, &'static str)> {
+        match kind {
+            "module" => Some((NamingRule::ModuleSnakeCase, "non_snake_case")),
+            "function" | "macro" => Some((NamingRule::FunctionSnakeCase, "non_snake_case")),
+            "struct" | "enum" | "trait" | "type_alias" | "union" => {
+                Some((NamingRule::TypeCamelCase, "non_camel_case_types"))
+            }
+            "constant" | "static" => Some((
+                NamingRule::ConstScreamingSnakeCase,
+                "non_upper_case_globals",
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Kinds of elements in `prefixes`, by element type.
+const KINDS: [(ElementType, &str); 10] = [
+    (ElementType::Module, "module"),
+    (ElementType::Function, "function"),
+    (ElementType::Struct, "struct"),
+    (ElementType::Enum, "enum"),
+    (ElementType::Trait, "trait"),
+    (ElementType::TypeAlias, "type_alias"),
+    (ElementType::Union, "union"),
+    (ElementType::Constant, "constant"),
+    (ElementType::Static, "static"),
+    (ElementType::Macro, "macro"),
+];
+
+/// Naming linter plugin, checking the case of the names of modules, types,
+/// functions and constants as rustc's naming lints do, and the prefixes
+/// configured for each kind of element.
+///
+/// Violations are reported as warnings on the lines of the names, which
+/// SARIF output lists. Generated elements, trait impl methods, whose names
+/// come from the trait, and elements with an `#[allow]` of the matching
+/// rustc lint are skipped.
+#[derive(Default)]
+pub struct NamingLinter {
+    config: NamingLinterConfig,
+}
+
+impl NamingLinter {
+    /// Create a new naming linter with custom configuration.
+    pub fn with_config(config: NamingLinterConfig) -> Result<Self, PluginError> {
+        let kinds: Vec<&str> = KINDS.iter().map(|(_, kind)| *kind).collect();
+        if let Some(kind) = config
+            .prefixes
+            .keys()
+            .find(|kind| !kinds.contains(&kind.as_str()))
+        {
+            return Err(PluginError::ConfigurationError(format!(
+                "Unknown element kind `{}` in prefixes; expected one of {}",
+                kind,
+                kinds.join(", ")
+            )));
+        }
+        Ok(Self { config })
+    }
+
+    /// Whether `rule` is checked.
+    fn is_enabled(&self, rule: NamingRule) -> bool {
+        !self.config.disabled_rules.iter().any(|id| id == rule.id())
+    }
+
+    /// Check the names of the elements of `file`, adding violations to the
+    /// diagnostics of `output`. Returns the number of names checked.
+    fn check_file(&self, file: &FileAst, output: &mut PluginOutput) -> usize {
+        let mut checked = 0;
+        for element in &file.elements {
+            let Some((_, kind)) = KINDS
+                .iter()
+                .find(|(element_type, _)| *element_type == element.element_type)
+            else {
+                continue;
+            };
+            let name = element.name.strip_prefix("r#").unwrap_or(&element.name);
+            let in_trait_impl = element
+                .hierarchy
+                .impl_context
+                .as_ref()
+                .is_some_and(|context| context.trait_path.is_some());
+            if element.is_generated
+                || in_trait_impl
+                || self
+                    .config
+                    .allowed_names
+                    .iter()
+                    .any(|allowed| allowed == name)
+            {
+                continue;
+            }
+            checked += 1;
+
+            let line = element
+                .name_location
+                .as_ref()
+                .unwrap_or(&element.location)
+                .line_start;
+            let mut report = |rule: NamingRule, message: String| {
+                let path = &file.relative_path;
+                output.add_diagnostic(MessageLevel::Warning, rule.id(), message, path, line);
+            };
+
+            if let Some((rule, lint)) = NamingRule::for_kind(kind) {
+                let (is_valid, convention, suggestion) = match rule {
+                    NamingRule::TypeCamelCase => {
+                        (is_camel_case(name), "CamelCase", to_camel_case(name))
+                    }
+                    NamingRule::ConstScreamingSnakeCase => (
+                        !name.chars().any(char::is_lowercase),
+                        "SCREAMING_SNAKE_CASE",
+                        to_snake_case(name).to_uppercase(),
+                    ),
+                    _ => (
+                        !name.chars().any(char::is_uppercase),
+                        "snake_case",
+                        to_snake_case(name),
+                    ),
+                };
+                if !is_valid && self.is_enabled(rule) && !allows(element, lint) {
+                    let message = format!(
+                        "{} `{}` should be {}, as `{}`",
+                        kind, name, convention, suggestion
+                    );
+                    report(rule, message);
+                }
+            }
+
+            if let Some(prefix) = self.config.prefixes.get(*kind) {
+                let is_enabled = self.is_enabled(NamingRule::MissingPrefix);
+                if is_enabled && !name.starts_with(prefix.as_str()) {
+                    report(
+                        NamingRule::MissingPrefix,
+                        format!("{} `{}` should start with `{}`", kind, name, prefix),
+                    );
+                }
+            }
+        }
+        checked
+    }
+}
+
+/// Whether `element` has an `#[allow]` of the rustc lint `lint`.
+fn allows(element: &CodeElement, lint: &str) -> bool {
+    element.attribute_info.iter().any(|attribute| {
+        attribute.name == "allow" && attribute.args.iter().any(|arg| arg.name == lint)
+    })
+}
+
+/// Whether `name` is `CamelCase`, ignoring leading and trailing underscores:
+/// starting with an uppercase letter and without underscores.
+fn is_camel_case(name: &str) -> bool {
+    let name = name.trim_matches('_');
+    !name.starts_with(|c: char| c.is_lowercase()) && !name.contains('_')
+}
+
+/// The words of an identifier in any case, as in `HTTPServer` or `http_server`.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (index, &c) in chars.iter().enumerate() {
+            // A word starts at an uppercase letter after a lowercase one, or
+            // before a lowercase one at the end of an acronym.
+            let previous = index.checked_sub(1).map(|index| chars[index]);
+            let next = chars.get(index + 1);
+            let starts_word = c.is_uppercase()
+                && (previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                    || (previous.is_some_and(char::is_uppercase)
+                        && next.is_some_and(|n| n.is_lowercase())));
+            if starts_word && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(c);
+        }
+        words.push(word);
+    }
+    words
+}
+
+/// `name` in `snake_case`.
+fn to_snake_case(name: &str) -> String {
+    let words: Vec<String> = words(name).iter().map(|word| word.to_lowercase()).collect();
+    words.join("_")
+}
+
+/// `name` in `CamelCase`.
+fn to_camel_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars
+                .next()
+                .map(|c| c.to_uppercase().to_string())
+                .unwrap_or_default();
+            first + &chars.as_str().to_lowercase()
+        })
+        .collect()
+}
+
+impl Plugin for NamingLinter {
+    fn info(&self) -> PluginInfo {
+        plugin_info!(
+            "naming-linter",
+            "0.1.0",
+            "Check of naming conventions: case of names and required prefixes",
+            phases: [PluginPhase::PostProject]
+        )
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        if !config.is_null() {
+            let config = serde_json::from_value(config.clone())
+                .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+            *self = Self::with_config(config)?;
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(NamingLinterConfig)).ok()
+    }
+
+    fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
+        let project = context.project_ast().ok_or_else(|| {
+            PluginError::InvalidContext("naming-linter needs the project AST".to_string())
+        })?;
+        let mut output = PluginOutput::new();
+        let checked: usize = project
+            .files
+            .iter()
+            .map(|file| self.check_file(file, &mut output))
+            .sum();
+
+        let mut counts: BTreeMap<&str, usize> =
+            NamingRule::ALL.iter().map(|rule| (rule.id(), 0)).collect();
+        for message in &output.messages {
+            if let Some(count) = message
+                .rule
+                .as_deref()
+                .and_then(|rule| counts.get_mut(rule))
+            {
+                *count += 1;
+            }
+        }
+        output.add_metric("checked_names", checked as f64);
+        output.add_metric("naming_violations", output.messages.len() as f64);
+        for (rule, count) in &counts {
+            output.add_metric(
+                format!("{}_violations", rule.replace('-', "_")),
+                *count as f64,
+            );
+        }
+        output.add_metadata(
+            "violations_by_rule",
+            serde_json::to_value(&counts).unwrap_or_default(),
+        );
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::{AstExtractor, ExtractorConfig, ProjectAst};
+    use std::fs;
+
+    const SOURCE: &str = r#"pub mod Parsing {}
+
+pub struct http_server;
+
+pub trait AcmeHandler {
+    fn handle(&self);
+}
+
+impl AcmeHandler for http_server {
+    fn handle(&self) {}
+}
+
+impl http_server {
+    fn Handle(&self) {}
+}
+
+#[allow(non_snake_case)]
+pub fn ParseAll() {}
+
+pub fn loadHTTPConfig() {}
+
+const bad_name: u8 = 0;
+
+pub static MAX_RETRIES: u32 = 3;
+"#;
+
+    fn extract() -> ProjectAst {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"naming\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), SOURCE).unwrap();
+        let config = ExtractorConfig {
+            include_private: true,
+            ..ExtractorConfig::default()
+        };
+        AstExtractor::new(config, root.to_path_buf())
+            .extract_project()
+            .unwrap()
+    }
+
+    fn check(linter: &NamingLinter, file: &FileAst) -> Vec<(String, usize, String)> {
+        let mut output = PluginOutput::new();
+        linter.check_file(file, &mut output);
+        output
+            .messages
+            .into_iter()
+            .map(|message| {
+                (
+                    message.rule.unwrap(),
+                    message.line.unwrap(),
+                    message.message,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_case_rules() {
+        let file = extract().files.remove(0);
+        let violations = check(&NamingLinter::default(), &file);
+        let found: Vec<(&str, usize)> = violations
+            .iter()
+            .map(|(rule, line, _)| (rule.as_str(), *line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("module-snake-case", 1),
+                ("type-camel-case", 3),
+                ("function-snake-case", 14),
+                ("function-snake-case", 20),
+                ("const-screaming-snake-case", 22),
+            ]
+        );
+        assert_eq!(
+            violations[1].2,
+            "struct `http_server` should be CamelCase, as `HttpServer`"
+        );
+        assert_eq!(
+            violations[3].2,
+            "function `loadHTTPConfig` should be snake_case, as `load_http_config`"
+        );
+        assert_eq!(
+            violations[4].2,
+            "constant `bad_name` should be SCREAMING_SNAKE_CASE, as `BAD_NAME`"
+        );
+    }
+
+    #[test]
+    fn test_prefixes_and_configuration() {
+        let config = NamingLinterConfig {
+            disabled_rules: NamingRule::ALL[..4]
+                .iter()
+                .map(|rule| rule.id().to_string())
+                .collect(),
+            prefixes: BTreeMap::from([
+                ("trait".to_string(), "Acme".to_string()),
+                ("struct".to_string(), "Acme".to_string()),
+            ]),
+            allowed_names: vec!["Parsing".to_string()],
+        };
+        let violations = check(
+            &NamingLinter::with_config(config).unwrap(),
+            &extract().files[0],
+        );
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].2,
+            "struct `http_server` should start with `Acme`"
+        );
+
+        let config = NamingLinterConfig {
+            prefixes: BTreeMap::from([("class".to_string(), "C".to_string())]),
+            ..NamingLinterConfig::default()
+        };
+        assert!(NamingLinter::with_config(config).is_err());
+    }
+}
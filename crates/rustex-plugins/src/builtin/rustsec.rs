@@ -0,0 +1,638 @@
+//! RustSec advisory audit plugin.
+
+use crate::core::plugin::MessageLevel;
+use crate::core::{Plugin, PluginContext, PluginError, PluginInfo, PluginOutput, PluginPhase};
+use crate::plugin_info;
+use rustex_core::{DependencyGraph, PackageRole};
+use schemars::JsonSchema;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Git repository of the RustSec advisory database.
+pub const RUSTSEC_REPOSITORY: &str = "https://github.com/RustSec/advisory-db.git";
+
+/// Key of the plugin metadata listing the packages advisories apply to.
+pub const VULNERABILITIES_METADATA_KEY: &str = "vulnerabilities";
+
+/// Configuration for the RustSec audit plugin.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct RustSecAuditConfig {
+    /// Directory of a checkout of the advisory database, by default
+    /// `advisory-db` in the cargo home directory, where `cargo audit` keeps it
+    pub database: Option<PathBuf>,
+
+    /// Whether to clone the database, or pull it if it's cloned already,
+    /// before auditing
+    pub fetch: bool,
+
+    /// Git URL the database is fetched from
+    pub repository: String,
+
+    /// Advisories not to report, by ID such as `RUSTSEC-2020-0071`
+    pub ignore: Vec<String>,
+}
+
+impl Default for RustSecAuditConfig {
+    fn default() -> Self {
+        Self {
+            database: None,
+            fetch: false,
+            repository: RUSTSEC_REPOSITORY.to_string(),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+/// An advisory of the RustSec database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advisory {
+    /// ID, such as `RUSTSEC-2020-0071`
+    pub id: String,
+
+    /// Name of the crate the advisory is about
+    pub package: String,
+
+    /// Title of the advisory
+    pub title: String,
+
+    /// Date the advisory was reported
+    pub date: String,
+
+    /// URL of the issue or fix
+    pub url: Option<String>,
+
+    /// Other IDs of the vulnerability, such as CVE IDs
+    pub aliases: Vec<String>,
+
+    /// Kind of informational advisory, such as `unmaintained` or `unsound`,
+    /// for advisories that aren't about a vulnerability
+    pub informational: Option<String>,
+
+    /// Date the advisory was withdrawn, if it was
+    pub withdrawn: Option<String>,
+
+    /// Versions the issue is fixed in
+    pub patched: Vec<VersionReq>,
+
+    /// Versions the issue never affected
+    pub unaffected: Vec<VersionReq>,
+}
+
+/// TOML front matter of an advisory file.
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisorySection,
+    #[serde(default)]
+    versions: VersionsSection,
+}
+
+#[derive(Deserialize)]
+struct AdvisorySection {
+    id: String,
+    package: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    date: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    informational: Option<String>,
+    #[serde(default)]
+    withdrawn: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct VersionsSection {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+impl Advisory {
+    /// Parse an advisory file of the database: TOML front matter in a
+    /// ```` ```toml ```` block, followed by Markdown whose first heading is
+    /// the title. Files of plain TOML with a `title` are read too.
+    pub fn parse(content: &str) -> Result<Self, PluginError> {
+        let (front_matter, markdown) = match content.trim_start().strip_prefix("```toml") {
+            Some(rest) => rest.split_once("\n```").ok_or_else(|| {
+                PluginError::ProcessingFailed("Unterminated TOML front matter".to_string())
+            })?,
+            None => (content, ""),
+        };
+        let AdvisoryFile { advisory, versions } = toml::from_str(front_matter)
+            .map_err(|e| PluginError::ProcessingFailed(e.to_string()))?;
+
+        let requirements = |ranges: &[String]| {
+            ranges
+                .iter()
+                .map(|range| {
+                    VersionReq::parse(range).map_err(|e| {
+                        PluginError::ProcessingFailed(format!(
+                            "{}: invalid version range '{}': {}",
+                            advisory.id, range, e
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let patched = requirements(&versions.patched)?;
+        let unaffected = requirements(&versions.unaffected)?;
+        let title = markdown
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .map(|title| title.trim().to_string())
+            .or(advisory.title)
+            .unwrap_or_default();
+
+        Ok(Self {
+            id: advisory.id,
+            package: advisory.package,
+            title,
+            date: advisory.date,
+            url: advisory.url,
+            aliases: advisory.aliases,
+            informational: advisory.informational,
+            withdrawn: advisory.withdrawn,
+            patched,
+            unaffected,
+        })
+    }
+
+    /// Whether the advisory applies to `version`, which is neither patched nor
+    /// unaffected.
+    pub fn affects(&self, version: &Version) -> bool {
+        !self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .any(|range| range.matches(version))
+    }
+}
+
+/// A package resolved at a version an advisory applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vulnerability {
+    /// Package name
+    pub package: String,
+
+    /// Resolved version
+    pub version: String,
+
+    /// Position of the package relative to the project
+    pub role: PackageRole,
+
+    /// ID of the advisory
+    pub advisory: String,
+
+    /// Title of the advisory
+    pub title: String,
+
+    /// URL of the issue or fix
+    pub url: Option<String>,
+
+    /// Other IDs of the vulnerability, such as CVE IDs
+    pub aliases: Vec<String>,
+
+    /// Kind of informational advisory, for advisories that aren't about a
+    /// vulnerability
+    pub informational: Option<String>,
+
+    /// Versions the issue is fixed in; none if there's no fix
+    pub patched: Vec<String>,
+}
+
+/// The advisories of a RustSec database, by package name.
+#[derive(Debug, Clone, Default)]
+pub struct AdvisoryDatabase {
+    advisories: BTreeMap<String, Vec<Advisory>>,
+}
+
+impl AdvisoryDatabase {
+    /// Load the advisories in the `crates` directory of a checkout of the
+    /// database, leaving out withdrawn ones.
+    pub fn load(path: &Path) -> Result<Self, PluginError> {
+        let packages = fs::read_dir(path.join("crates")).map_err(|_| {
+            PluginError::ConfigurationError(format!(
+                "No RustSec advisory database at {}; clone {} there or set `fetch = true`",
+                path.display(),
+                RUSTSEC_REPOSITORY
+            ))
+        })?;
+
+        let mut database = Self::default();
+        for package in packages {
+            for entry in fs::read_dir(package?.path())? {
+                let file = entry?.path();
+                let extension = file.extension().and_then(|extension| extension.to_str());
+                if !matches!(extension, Some("md" | "toml")) {
+                    continue;
+                }
+                let advisory = Advisory::parse(&fs::read_to_string(&file)?).map_err(|e| {
+                    PluginError::ProcessingFailed(format!("{}: {}", file.display(), e))
+                })?;
+                database.add(advisory);
+            }
+        }
+        Ok(database)
+    }
+
+    /// Clone the database from `repository` into `path`, or pull it if it's
+    /// cloned already.
+    pub fn fetch(repository: &str, path: &Path) -> Result<(), PluginError> {
+        let mut command = Command::new("git");
+        if path.join(".git").exists() {
+            command
+                .arg("-C")
+                .arg(path)
+                .args(["pull", "--ff-only", "--quiet"]);
+        } else {
+            command
+                .args(["clone", "--depth", "1", "--quiet", repository])
+                .arg(path);
+        }
+        let output = command
+            .output()
+            .map_err(|e| PluginError::ProcessingFailed(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(PluginError::ProcessingFailed(format!(
+                "Failed to fetch the RustSec advisory database from {}: {}",
+                repository,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Add an advisory, unless it's withdrawn.
+    pub fn add(&mut self, advisory: Advisory) {
+        if advisory.withdrawn.is_none() {
+            self.advisories
+                .entry(advisory.package.clone())
+                .or_default()
+                .push(advisory);
+        }
+    }
+
+    /// Number of advisories.
+    pub fn len(&self) -> usize {
+        self.advisories.values().map(Vec::len).sum()
+    }
+
+    /// Whether there are no advisories.
+    pub fn is_empty(&self) -> bool {
+        self.advisories.is_empty()
+    }
+
+    /// The packages of `graph` from crates.io at versions advisories apply
+    /// to, leaving out the advisories in `ignore`.
+    pub fn vulnerabilities(
+        &self,
+        graph: &DependencyGraph,
+        ignore: &[String],
+    ) -> Vec<Vulnerability> {
+        let mut vulnerabilities = Vec::new();
+        for package in &graph.packages {
+            let from_crates_io = package.source.as_deref().is_some_and(|source| {
+                source.starts_with("registry+") || source.starts_with("sparse+")
+            });
+            let Some(advisories) = self
+                .advisories
+                .get(&package.name)
+                .filter(|_| from_crates_io)
+            else {
+                continue;
+            };
+            let Ok(version) = Version::parse(&package.version) else {
+                continue;
+            };
+
+            let applying = advisories
+                .iter()
+                .filter(|advisory| !ignore.contains(&advisory.id))
+                .filter(|advisory| advisory.affects(&version));
+            for advisory in applying {
+                vulnerabilities.push(Vulnerability {
+                    package: package.name.clone(),
+                    version: package.version.clone(),
+                    role: package.role,
+                    advisory: advisory.id.clone(),
+                    title: advisory.title.clone(),
+                    url: advisory.url.clone(),
+                    aliases: advisory.aliases.clone(),
+                    informational: advisory.informational.clone(),
+                    patched: advisory.patched.iter().map(ToString::to_string).collect(),
+                });
+            }
+        }
+        vulnerabilities
+    }
+}
+
+/// RustSec audit plugin, cross-referencing the packages of `Cargo.lock`
+/// against the RustSec advisory database.
+///
+/// Each package at a version an advisory applies to is listed in the
+/// `vulnerabilities` metadata and reported as a diagnostic on `Cargo.lock`:
+/// an error for vulnerabilities and a warning for informational advisories,
+/// such as unmaintained crates. The `vulnerabilities` metric is what the
+/// `max_vulnerabilities` threshold of `rustex check` limits.
+#[derive(Default)]
+pub struct RustSecAuditor {
+    config: RustSecAuditConfig,
+}
+
+impl RustSecAuditor {
+    /// Create a new RustSec auditor with custom configuration.
+    pub fn with_config(config: RustSecAuditConfig) -> Self {
+        Self { config }
+    }
+
+    /// Directory of the advisory database.
+    pub fn database_path(&self) -> PathBuf {
+        self.config.database.clone().unwrap_or_else(|| {
+            std::env::var_os("CARGO_HOME")
+                .map(PathBuf::from)
+                .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+                .unwrap_or_else(|| PathBuf::from(".cargo"))
+                .join("advisory-db")
+        })
+    }
+
+    /// The packages of `graph` advisories apply to, fetching the database
+    /// first if configured to.
+    pub fn audit(&self, graph: &DependencyGraph) -> Result<Vec<Vulnerability>, PluginError> {
+        let path = self.database_path();
+        if self.config.fetch {
+            AdvisoryDatabase::fetch(&self.config.repository, &path)?;
+        }
+        let database = AdvisoryDatabase::load(&path)?;
+        tracing::debug!(
+            "Loaded {} RustSec advisories from {}",
+            database.len(),
+            path.display()
+        );
+        Ok(database.vulnerabilities(graph, &self.config.ignore))
+    }
+}
+
+/// Output listing `vulnerabilities`, with diagnostics on their packages in
+/// `lockfile`.
+fn report(vulnerabilities: &[Vulnerability], lockfile: &str) -> PluginOutput {
+    let mut output = PluginOutput::new();
+    for vulnerability in vulnerabilities {
+        let Vulnerability {
+            package, version, ..
+        } = vulnerability;
+        let (level, issue) = match &vulnerability.informational {
+            Some(kind) => (
+                MessageLevel::Warning,
+                format!("{} {} is {}", package, version, kind),
+            ),
+            None => (
+                MessageLevel::Error,
+                format!("{} {} is vulnerable", package, version),
+            ),
+        };
+        let fix = if vulnerability.patched.is_empty() {
+            "no patched version".to_string()
+        } else {
+            format!("upgrade to {}", vulnerability.patched.join(" or "))
+        };
+        output.add_diagnostic(
+            level,
+            &vulnerability.advisory,
+            format!("{}: {} ({})", issue, vulnerability.title, fix),
+            Path::new("Cargo.lock"),
+            lockfile_line(lockfile, package, version).unwrap_or(1),
+        );
+    }
+
+    let vulnerable: Vec<&Vulnerability> = vulnerabilities
+        .iter()
+        .filter(|vulnerability| vulnerability.informational.is_none())
+        .collect();
+    let packages: BTreeSet<(&str, &str)> = vulnerable
+        .iter()
+        .map(|vulnerability| {
+            (
+                vulnerability.package.as_str(),
+                vulnerability.version.as_str(),
+            )
+        })
+        .collect();
+    output.add_metric("vulnerabilities", vulnerable.len() as f64);
+    output.add_metric("vulnerable_packages", packages.len() as f64);
+    output.add_metric(
+        "informational_advisories",
+        (vulnerabilities.len() - vulnerable.len()) as f64,
+    );
+    output.add_metadata(
+        VULNERABILITIES_METADATA_KEY,
+        serde_json::to_value(vulnerabilities).unwrap_or_default(),
+    );
+    output
+}
+
+/// Line of the `name` of the package entry of `package` at `version` in a
+/// `Cargo.lock`.
+fn lockfile_line(lockfile: &str, package: &str, version: &str) -> Option<usize> {
+    let name = format!("name = \"{}\"", package);
+    let version = format!("version = \"{}\"", version);
+    let lines: Vec<&str> = lockfile.lines().map(str::trim).collect();
+    lines
+        .windows(2)
+        .position(|pair| pair[0] == name && pair[1] == version)
+        .map(|index| index + 1)
+}
+
+impl Plugin for RustSecAuditor {
+    fn info(&self) -> PluginInfo {
+        plugin_info!(
+            "rustsec-audit",
+            "0.1.0",
+            "Audit of Cargo.lock against the RustSec advisory database",
+            phases: [PluginPhase::PostProject]
+        )
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        if !config.is_null() {
+            self.config = serde_json::from_value(config.clone())
+                .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(RustSecAuditConfig)).ok()
+    }
+
+    fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
+        let root = &context.project_info.root_path;
+        let graph = DependencyGraph::from_project(root)
+            .map_err(|e| PluginError::ProcessingFailed(e.to_string()))?;
+        let vulnerabilities = self.audit(&graph)?;
+        let lockfile = fs::read_to_string(root.join("Cargo.lock"))?;
+        Ok(report(&vulnerabilities, &lockfile))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2020-0071"
+package = "time"
+date = "2020-11-18"
+url = "https://github.com/time-rs/time/issues/293"
+aliases = ["CVE-2020-26235"]
+
+[versions]
+patched = [">=0.2.23"]
+unaffected = ["=0.2.0", "=0.2.1", "=0.2.2", "=0.2.3", "=0.2.4", "=0.2.5", "=0.2.6"]
+```
+
+# Potential segfault in the time crate
+
+Unix-like operating systems may segfault due to dereferencing a dangling pointer.
+"#;
+
+    const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "ansi_term",
+ "time",
+]
+
+[[package]]
+name = "ansi_term"
+version = "0.12.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "time"
+version = "0.1.45"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    fn database() -> AdvisoryDatabase {
+        let mut database = AdvisoryDatabase::default();
+        database.add(Advisory::parse(ADVISORY).unwrap());
+        let unmaintained = r#"
+[advisory]
+id = "RUSTSEC-2021-0139"
+package = "ansi_term"
+title = "ansi_term is Unmaintained"
+informational = "unmaintained"
+"#;
+        database.add(Advisory::parse(unmaintained).unwrap());
+        let withdrawn = "[advisory]\nid = \"RUSTSEC-2099-0001\"\npackage = \"time\"\n\
+                         withdrawn = \"2099-01-02\"\n";
+        database.add(Advisory::parse(withdrawn).unwrap());
+        database
+    }
+
+    fn graph() -> DependencyGraph {
+        let roots = [("app".to_string(), HashMap::new())].into_iter().collect();
+        DependencyGraph::from_lockfile(LOCKFILE, &roots).unwrap()
+    }
+
+    #[test]
+    fn test_parse_advisory() {
+        let advisory = Advisory::parse(ADVISORY).unwrap();
+        assert_eq!(advisory.id, "RUSTSEC-2020-0071");
+        assert_eq!(advisory.title, "Potential segfault in the time crate");
+        assert_eq!(advisory.aliases, ["CVE-2020-26235"]);
+
+        let affected = |version: &str| advisory.affects(&Version::parse(version).unwrap());
+        assert!(affected("0.1.45"));
+        assert!(affected("0.2.7"));
+        assert!(!affected("0.2.3"));
+        assert!(!affected("0.2.23"));
+        assert!(!affected("0.3.36"));
+
+        let invalid =
+            "[advisory]\nid = \"X\"\npackage = \"y\"\n[versions]\npatched = [\"latest\"]\n";
+        assert!(Advisory::parse(invalid).is_err());
+        assert!(Advisory::parse("```toml\n[advisory]\n").is_err());
+    }
+
+    #[test]
+    fn test_vulnerabilities() {
+        let database = database();
+        assert_eq!(database.len(), 2);
+        let vulnerabilities = database.vulnerabilities(&graph(), &[]);
+        let found: Vec<(&str, &str)> = vulnerabilities
+            .iter()
+            .map(|vulnerability| {
+                (
+                    vulnerability.package.as_str(),
+                    vulnerability.advisory.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("ansi_term", "RUSTSEC-2021-0139"),
+                ("time", "RUSTSEC-2020-0071")
+            ]
+        );
+        assert_eq!(vulnerabilities[1].role, PackageRole::Direct);
+        assert_eq!(vulnerabilities[1].patched, [">=0.2.23"]);
+
+        let ignored = database.vulnerabilities(&graph(), &["RUSTSEC-2020-0071".to_string()]);
+        assert_eq!(ignored.len(), 1);
+
+        let output = report(&vulnerabilities, LOCKFILE);
+        assert_eq!(output.metrics["vulnerabilities"], 1.0);
+        assert_eq!(output.metrics["informational_advisories"], 1.0);
+        let error = &output.messages[1];
+        assert!(matches!(error.level, MessageLevel::Error));
+        assert_eq!(error.rule.as_deref(), Some("RUSTSEC-2020-0071"));
+        assert_eq!(error.line, Some(17));
+        assert_eq!(
+            error.message,
+            "time 0.1.45 is vulnerable: Potential segfault in the time crate (upgrade to >=0.2.23)"
+        );
+        assert!(matches!(output.messages[0].level, MessageLevel::Warning));
+    }
+
+    #[test]
+    fn test_load_database() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = RustSecAuditConfig {
+            database: Some(temp_dir.path().to_path_buf()),
+            ..RustSecAuditConfig::default()
+        };
+        let auditor = RustSecAuditor::with_config(config);
+        assert!(matches!(
+            auditor.audit(&graph()),
+            Err(PluginError::ConfigurationError(_))
+        ));
+
+        let crate_dir = temp_dir.path().join("crates/time");
+        fs::create_dir_all(&crate_dir).unwrap();
+        fs::write(crate_dir.join("RUSTSEC-2020-0071.md"), ADVISORY).unwrap();
+        fs::write(crate_dir.join("README.txt"), "not an advisory").unwrap();
+        let vulnerabilities = auditor.audit(&graph()).unwrap();
+        assert_eq!(vulnerabilities.len(), 1);
+        assert_eq!(vulnerabilities[0].version, "0.1.45");
+    }
+}
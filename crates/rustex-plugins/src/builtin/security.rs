@@ -0,0 +1,631 @@
+//! Security audit plugin.
+
+use crate::core::plugin::MessageLevel;
+use crate::core::{Plugin, PluginContext, PluginError, PluginInfo, PluginOutput, PluginPhase};
+use crate::plugin_info;
+use regex::Regex;
+use rustex_core::{CfgPredicate, CodeElement, FileAst};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::{Component, Path};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+/// Key of the element `metadata` entry listing the element's security findings.
+pub const SECURITY_FINDINGS_METADATA_KEY: &str = "security_findings";
+
+/// Regular expressions of hard-coded secrets checked by default: AWS access
+/// keys, private keys, and GitHub and Slack tokens.
+const DEFAULT_SECRET_PATTERNS: [&str; 4] = [
+    r"\bAKIA[0-9A-Z]{16}\b",
+    r"-----BEGIN (RSA |EC |DSA |OPENSSH )?PRIVATE KEY-----",
+    r"\bgh[pousr]_[A-Za-z0-9]{36}\b",
+    r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+];
+
+/// A string literal assigned to a name like `password` or `api_key`, with
+/// the name and the literal captured.
+const SECRET_ASSIGNMENT_PATTERN: &str = concat!(
+    r"(?i)\b(\w*(?:password|passwd|secret|api_?key|access_?key|auth_?token)\w*)\b",
+    r#"[^=\n]*[:=]\s*"([^"\s]{8,})""#,
+);
+
+/// Configuration for the security analyzer plugin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct SecurityAnalyzerConfig {
+    /// Rules not to check, by ID (see [`SecurityRule::id`])
+    pub disabled_rules: Vec<String>,
+
+    /// Regular expressions of hard-coded secrets, checked besides the
+    /// default ones
+    pub secret_patterns: Vec<String>,
+
+    /// Whether to check tests: files in `tests` and `benches` directories,
+    /// and `#[test]` and `#[cfg(test)]` items
+    pub include_tests: bool,
+}
+
+/// A pattern the security analyzer flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecurityRule {
+    /// An `unsafe` block
+    UnsafeBlock,
+    /// A `std::process::Command` built from user input
+    CommandInjection,
+    /// `unwrap` or `expect` on external input, which panics on bad input
+    UnwrapExternalInput,
+    /// A hard-coded secret
+    HardcodedSecret,
+    /// An `#[allow(...)]` lint suppression
+    LintSuppression,
+}
+
+impl SecurityRule {
+    /// All rules, in the order they're checked.
+    pub const ALL: [SecurityRule; 5] = [
+        SecurityRule::UnsafeBlock,
+        SecurityRule::CommandInjection,
+        SecurityRule::UnwrapExternalInput,
+        SecurityRule::HardcodedSecret,
+        SecurityRule::LintSuppression,
+    ];
+
+    /// ID of the rule in diagnostics and `disabled_rules`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            SecurityRule::UnsafeBlock => "unsafe-block",
+            SecurityRule::CommandInjection => "command-injection",
+            SecurityRule::UnwrapExternalInput => "unwrap-external-input",
+            SecurityRule::HardcodedSecret => "hardcoded-secret",
+            SecurityRule::LintSuppression => "lint-suppression",
+        }
+    }
+
+    /// Level of the rule's diagnostics.
+    pub fn level(&self) -> MessageLevel {
+        match self {
+            SecurityRule::CommandInjection | SecurityRule::HardcodedSecret => MessageLevel::Error,
+            SecurityRule::UnsafeBlock | SecurityRule::UnwrapExternalInput => MessageLevel::Warning,
+            SecurityRule::LintSuppression => MessageLevel::Info,
+        }
+    }
+}
+
+/// A finding of the security analyzer, as listed in element metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecurityFinding {
+    /// ID of the rule
+    pub rule: String,
+
+    /// Line of the finding (1-based)
+    pub line: usize,
+
+    /// Description of the finding
+    pub message: String,
+}
+
+/// Compiled patterns of the rules.
+struct Patterns {
+    unsafe_block: Regex,
+    command: Regex,
+    command_argument: Regex,
+    user_input: Regex,
+    unwrap: Regex,
+    external_input: Regex,
+    allow: Regex,
+    secrets: Vec<Regex>,
+    secret_assignment: Regex,
+    env_var_name: Regex,
+}
+
+impl Patterns {
+    fn new(secret_patterns: &[String]) -> Result<Self, regex::Error> {
+        let secrets = DEFAULT_SECRET_PATTERNS
+            .iter()
+            .copied()
+            .chain(secret_patterns.iter().map(String::as_str))
+            .map(Regex::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            unsafe_block: Regex::new(r"\bunsafe\s*\{")?,
+            command: Regex::new(r"\bCommand::new\s*\(")?,
+            command_argument: Regex::new(r"\.(arg|args|env|current_dir)\s*\(")?,
+            user_input: Regex::new(concat!(
+                r"\b(env::args(_os)?|env::var(_os)?|stdin|read_line",
+                r"|user_input|input|request|query|params)\b",
+            ))?,
+            unwrap: Regex::new(r"\.(unwrap|expect)\s*\(")?,
+            external_input: Regex::new(concat!(
+                r"\b(env::args|env::var(_os)?|stdin|read_line|read_to_string|File::open|fs::read",
+                r"|parse|from_str|from_slice|from_reader|TcpStream|UdpSocket|recv)\b",
+            ))?,
+            allow: Regex::new(r"#!?\[allow\(([^)]*)\)\]")?,
+            secrets,
+            secret_assignment: Regex::new(SECRET_ASSIGNMENT_PATTERN)?,
+            env_var_name: Regex::new(r"^[A-Z][A-Z0-9]*(_[A-Z0-9]+)+$")?,
+        })
+    }
+
+    /// Whether `code` has a hard-coded secret. Names of environment variables
+    /// holding a secret, such as `api_key_env = "OPENAI_API_KEY"`, aren't one.
+    fn has_secret(&self, code: &str) -> bool {
+        self.secrets.iter().any(|secret| secret.is_match(code))
+            || self
+                .secret_assignment
+                .captures_iter(code)
+                .any(|assignment| {
+                    !assignment[1].to_lowercase().ends_with("_env")
+                        && !self.env_var_name.is_match(&assignment[2])
+                })
+    }
+}
+
+/// Security audit plugin, flagging `unsafe` blocks, commands built from
+/// user input, `unwrap` on external input, hard-coded secrets and lint
+/// suppressions.
+///
+/// Findings go in the `security_findings` metadata of the innermost element
+/// they're in, and in the plugin's diagnostics, which SARIF output lists.
+/// Source lines are matched against patterns, so findings are heuristic.
+pub struct SecurityAnalyzer {
+    config: SecurityAnalyzerConfig,
+    patterns: Patterns,
+}
+
+impl Default for SecurityAnalyzer {
+    fn default() -> Self {
+        Self {
+            config: SecurityAnalyzerConfig::default(),
+            patterns: Patterns::new(&[]).expect("default security patterns are valid"),
+        }
+    }
+}
+
+impl SecurityAnalyzer {
+    /// Create a new security analyzer with custom configuration.
+    pub fn with_config(config: SecurityAnalyzerConfig) -> Result<Self, PluginError> {
+        let patterns = Patterns::new(&config.secret_patterns)
+            .map_err(|e| PluginError::ConfigurationError(format!("secret_patterns: {}", e)))?;
+        Ok(Self { config, patterns })
+    }
+
+    /// Find the security issues in the lines of `source`, skipping comments.
+    pub fn scan(&self, source: &str) -> Vec<(SecurityRule, usize, String)> {
+        let runs_commands = self.patterns.command.is_match(source);
+        let mut findings = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let code = line.trim_start();
+            if code.starts_with("//") {
+                continue;
+            }
+            let line_number = index + 1;
+            let mut found = |rule: SecurityRule, message: String| {
+                if !self.config.disabled_rules.iter().any(|id| id == rule.id()) {
+                    findings.push((rule, line_number, message));
+                }
+            };
+
+            if self.patterns.unsafe_block.is_match(code) {
+                found(SecurityRule::UnsafeBlock, "unsafe block".to_string());
+            }
+
+            let builds_command = self.patterns.command.is_match(code)
+                || (runs_commands && self.patterns.command_argument.is_match(code));
+            if builds_command {
+                if let Some(input) = self.patterns.user_input.find(code) {
+                    found(
+                        SecurityRule::CommandInjection,
+                        format!("Command built from user input (`{}`)", input.as_str()),
+                    );
+                }
+            }
+
+            if self.patterns.unwrap.is_match(code) {
+                if let Some(input) = self.patterns.external_input.find(code) {
+                    found(
+                        SecurityRule::UnwrapExternalInput,
+                        format!("`unwrap` on external input (`{}`)", input.as_str()),
+                    );
+                }
+            }
+
+            // The secret itself is left out of the finding.
+            if self.patterns.has_secret(code) {
+                found(
+                    SecurityRule::HardcodedSecret,
+                    "Possible hard-coded secret".to_string(),
+                );
+            }
+
+            for allow in self.patterns.allow.captures_iter(code) {
+                found(
+                    SecurityRule::LintSuppression,
+                    format!("Lint suppression `allow({})`", allow[1].trim()),
+                );
+            }
+        }
+
+        findings
+    }
+
+    /// Scan a file, adding its findings to the metadata of the elements they're
+    /// in and to the diagnostics of `output`.
+    fn audit_file(&self, file: &mut FileAst, source: &str, output: &mut PluginOutput) {
+        // Private test modules and functions aren't extracted by default, so
+        // test code is found in the source as well as among the elements.
+        let test_lines = if self.config.include_tests {
+            Vec::new()
+        } else {
+            test_lines(source)
+        };
+        for (rule, line, message) in self.scan(source) {
+            if test_lines.iter().any(|lines| lines.contains(&line)) {
+                continue;
+            }
+            let element = innermost_element(&mut file.elements, line);
+            if !self.config.include_tests && element.as_deref().is_some_and(is_test_element) {
+                continue;
+            }
+
+            let path = &file.relative_path;
+            output.add_diagnostic(rule.level(), rule.id(), message.clone(), path, line);
+            if let Some(element) = element {
+                let finding = SecurityFinding {
+                    rule: rule.id().to_string(),
+                    line,
+                    message,
+                };
+                let findings = element
+                    .metadata
+                    .entry(SECURITY_FINDINGS_METADATA_KEY.to_string())
+                    .or_insert_with(|| serde_json::Value::Array(vec![]));
+                if let serde_json::Value::Array(findings) = findings {
+                    findings.push(serde_json::to_value(finding).unwrap_or_default());
+                }
+                output.has_modifications = true;
+            }
+        }
+    }
+
+    /// Count the findings in the project's elements, by rule.
+    fn summarize(&self, context: &PluginContext) -> PluginOutput {
+        let mut output = PluginOutput::new();
+        let mut counts: BTreeMap<&str, usize> = SecurityRule::ALL
+            .iter()
+            .map(|rule| (rule.id(), 0))
+            .collect();
+        let mut flagged_elements = 0;
+
+        for element in context.all_elements() {
+            let Some(serde_json::Value::Array(findings)) =
+                element.metadata.get(SECURITY_FINDINGS_METADATA_KEY)
+            else {
+                continue;
+            };
+            flagged_elements += 1;
+            for finding in findings {
+                let rule = finding.get("rule").and_then(|rule| rule.as_str());
+                if let Some(count) = rule.and_then(|rule| counts.get_mut(rule)) {
+                    *count += 1;
+                }
+            }
+        }
+
+        let total: usize = counts.values().sum();
+        output.add_metric("security_findings", total as f64);
+        output.add_metric("flagged_elements", flagged_elements as f64);
+        for (rule, count) in &counts {
+            output.add_metric(
+                format!("{}_findings", rule.replace('-', "_")),
+                *count as f64,
+            );
+        }
+        output.add_metadata(
+            "findings_by_rule",
+            serde_json::to_value(&counts).unwrap_or_default(),
+        );
+        output
+    }
+}
+
+/// The element spanning `line` with the fewest lines, skipping generated ones.
+fn innermost_element(elements: &mut [CodeElement], line: usize) -> Option<&mut CodeElement> {
+    elements
+        .iter_mut()
+        .filter(|element| !element.is_generated)
+        .filter(|element| element.location.line_start <= line && line <= element.location.line_end)
+        .min_by_key(|element| element.location.line_end - element.location.line_start)
+}
+
+/// Whether the element is a test or compiled only for tests.
+pub(crate) fn is_test_element(element: &CodeElement) -> bool {
+    let cfg_test = element.cfg_predicates.iter().any(|predicate| {
+        matches!(predicate, CfgPredicate::Option { name, value: None } if name == "test")
+    });
+    cfg_test
+        || element
+            .attributes
+            .iter()
+            .any(|attr| attr.trim_start_matches("#[").starts_with("test"))
+}
+
+/// Lines of the items of `source` that are tests or compiled only for tests,
+/// or none when it doesn't parse.
+pub(crate) fn test_lines(source: &str) -> Vec<RangeInclusive<usize>> {
+    let Ok(file) = syn::parse_file(source) else {
+        return Vec::new();
+    };
+    let mut finder = TestItemFinder::default();
+    finder.visit_file(&file);
+    finder.lines
+}
+
+/// Collects the lines of test items, without looking inside them.
+#[derive(Default)]
+struct TestItemFinder {
+    lines: Vec<RangeInclusive<usize>>,
+}
+
+impl TestItemFinder {
+    /// Record `node` when `attrs` mark it as test code, returning whether they did.
+    fn found(&mut self, attrs: &[syn::Attribute], node: &impl Spanned) -> bool {
+        if !attrs.iter().any(is_test_attribute) {
+            return false;
+        }
+        let span = node.span();
+        self.lines.push(span.start().line..=span.end().line);
+        true
+    }
+}
+
+impl<'ast> Visit<'ast> for TestItemFinder {
+    fn visit_item(&mut self, item: &'ast syn::Item) {
+        let attrs = match item {
+            syn::Item::Const(item) => &item.attrs,
+            syn::Item::Enum(item) => &item.attrs,
+            syn::Item::Fn(item) => &item.attrs,
+            syn::Item::Impl(item) => &item.attrs,
+            syn::Item::Macro(item) => &item.attrs,
+            syn::Item::Mod(item) => &item.attrs,
+            syn::Item::Static(item) => &item.attrs,
+            syn::Item::Struct(item) => &item.attrs,
+            syn::Item::Trait(item) => &item.attrs,
+            syn::Item::Use(item) => &item.attrs,
+            _ => return syn::visit::visit_item(self, item),
+        };
+        if !self.found(attrs, item) {
+            syn::visit::visit_item(self, item);
+        }
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        if !self.found(&item.attrs, item) {
+            syn::visit::visit_impl_item_fn(self, item);
+        }
+    }
+}
+
+/// Whether an attribute is `#[cfg(test)]` or a test attribute like
+/// `#[test]` or `#[tokio::test]`.
+fn is_test_attribute(attr: &syn::Attribute) -> bool {
+    if attr.path().is_ident("cfg") {
+        return attr
+            .parse_args::<syn::Ident>()
+            .is_ok_and(|predicate| predicate == "test");
+    }
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "test")
+}
+
+/// Whether a file is in a `tests` or `benches` directory.
+pub(crate) fn is_test_file(relative_path: &Path) -> bool {
+    relative_path.components().any(|component| {
+        matches!(component, Component::Normal(name) if name == "tests" || name == "benches")
+    })
+}
+
+impl Plugin for SecurityAnalyzer {
+    fn info(&self) -> PluginInfo {
+        plugin_info!(
+            "security-analyzer",
+            "0.1.0",
+            "Security audit of unsafe code, commands, unwraps, secrets and lint suppressions",
+            phases: [PluginPhase::PostFile, PluginPhase::PostProject]
+        )
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        if !config.is_null() {
+            let config = serde_json::from_value(config.clone())
+                .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+            *self = Self::with_config(config)?;
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(SecurityAnalyzerConfig)).ok()
+    }
+
+    fn post_file(
+        &self,
+        file: &mut FileAst,
+        _context: &PluginContext,
+    ) -> Result<PluginOutput, PluginError> {
+        let mut output = PluginOutput::new();
+        if !self.config.include_tests && is_test_file(&file.relative_path) {
+            return Ok(output);
+        }
+
+        let source = std::fs::read_to_string(&file.path)?;
+        self.audit_file(file, &source, &mut output);
+        Ok(output)
+    }
+
+    fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
+        Ok(self.summarize(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use std::path::PathBuf;
+
+    const SOURCE: &str = r#"use std::process::Command;
+
+fn run(input: &str) {
+    // unsafe { comments are skipped }
+    let port: u16 = std::env::var("PORT").unwrap().parse().unwrap();
+    Command::new("sh").arg("-c").arg(input).status().unwrap();
+    let ptr = unsafe { std::ptr::null::<u8>().read() };
+}
+
+#[allow(dead_code, clippy::all)]
+const API_KEY: &str = "sk-live-1234567890abcdef";
+"#;
+
+    fn rules(findings: &[(SecurityRule, usize, String)]) -> Vec<(&'static str, usize)> {
+        findings
+            .iter()
+            .map(|(rule, line, _)| (rule.id(), *line))
+            .collect()
+    }
+
+    #[test]
+    fn test_scan() {
+        let analyzer = SecurityAnalyzer::default();
+        let findings = analyzer.scan(SOURCE);
+        assert_eq!(
+            rules(&findings),
+            vec![
+                ("unwrap-external-input", 5),
+                ("command-injection", 6),
+                ("unsafe-block", 7),
+                ("lint-suppression", 10),
+                ("hardcoded-secret", 11),
+            ]
+        );
+        assert_eq!(
+            findings[3].2,
+            "Lint suppression `allow(dead_code, clippy::all)`"
+        );
+        assert!(!findings[4].2.contains("sk-live"));
+
+        let config = SecurityAnalyzerConfig {
+            disabled_rules: vec!["lint-suppression".to_string()],
+            secret_patterns: vec![r"hunter\d".to_string()],
+            ..SecurityAnalyzerConfig::default()
+        };
+        let analyzer = SecurityAnalyzer::with_config(config).unwrap();
+        let findings = analyzer.scan("let password = hunter2;\n#[allow(unused)]\n");
+        assert_eq!(rules(&findings), vec![("hardcoded-secret", 1)]);
+
+        let config = SecurityAnalyzerConfig {
+            secret_patterns: vec!["(".to_string()],
+            ..SecurityAnalyzerConfig::default()
+        };
+        assert!(SecurityAnalyzer::with_config(config).is_err());
+    }
+
+    #[test]
+    fn test_env_var_names_are_not_secrets() {
+        let analyzer = SecurityAnalyzer::default();
+        let source = concat!(
+            "api_key_env = \"OPENAI_API_KEY\"\n",
+            "let api_key = env::var(\"OPENAI_API_KEY\");\n",
+            "let api_key = \"OPENAI_API_KEY\";\n",
+            "auth_token_env: \"sk-live-1234567890abcdef\",\n",
+            "auth_token: \"sk-live-1234567890abcdef\",\n",
+        );
+        assert_eq!(rules(&analyzer.scan(source)), vec![("hardcoded-secret", 5)]);
+    }
+
+    #[test]
+    fn test_audit_file() {
+        let analyzer = SecurityAnalyzer::default();
+        let mut file = MockDataGenerator::file_ast(PathBuf::from("/test/src/lib.rs"), 2);
+        file.relative_path = PathBuf::from("src/lib.rs");
+        for (element, (line_start, line_end)) in file.elements.iter_mut().zip([(3, 8), (1, 11)]) {
+            element.location.line_start = line_start;
+            element.location.line_end = line_end;
+            element.attributes.clear();
+        }
+
+        let mut output = PluginOutput::new();
+        analyzer.audit_file(&mut file, SOURCE, &mut output);
+
+        // Findings go to the innermost element, and into diagnostics.
+        let findings = &file.elements[0].metadata[SECURITY_FINDINGS_METADATA_KEY];
+        assert_eq!(findings.as_array().unwrap().len(), 3);
+        assert_eq!(findings[2]["rule"], "unsafe-block");
+        assert_eq!(
+            file.elements[1].metadata[SECURITY_FINDINGS_METADATA_KEY][0]["line"],
+            10
+        );
+        assert_eq!(output.messages.len(), 5);
+        let secret = &output.messages[4];
+        assert_eq!(secret.rule.as_deref(), Some("hardcoded-secret"));
+        assert_eq!(secret.file.as_deref(), Some(Path::new("src/lib.rs")));
+        assert_eq!(secret.line, Some(11));
+
+        // Tests are skipped by default.
+        let mut file = MockDataGenerator::file_ast(PathBuf::from("/test/src/lib.rs"), 1);
+        file.elements[0].location.line_start = 1;
+        file.elements[0].location.line_end = 11;
+        let mut output = PluginOutput::new();
+        analyzer.audit_file(&mut file, SOURCE, &mut output);
+        assert!(output.messages.is_empty());
+        assert!(is_test_file(Path::new("crates/core/tests/integration.rs")));
+    }
+
+    #[test]
+    fn test_skips_unextracted_test_code() {
+        let source = r#"pub fn port() -> u16 {
+    std::env::var("PORT").unwrap().parse().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn reads_port() {
+        std::env::var("PORT").unwrap();
+    }
+}
+
+impl Config {
+    #[tokio::test]
+    async fn loads() {
+        let password = "hunter2hunter2";
+    }
+}
+"#;
+        assert_eq!(test_lines(source), vec![5..=11, 14..=17]);
+        assert!(test_lines("fn broken( {").is_empty());
+
+        // Private test code has no element, so it's found in the source.
+        let mut file = MockDataGenerator::file_ast(PathBuf::from("/test/src/lib.rs"), 0);
+        file.relative_path = PathBuf::from("src/lib.rs");
+        let mut output = PluginOutput::new();
+        SecurityAnalyzer::default().audit_file(&mut file, source, &mut output);
+        let lines: Vec<_> = output.messages.iter().map(|message| message.line).collect();
+        assert_eq!(lines, vec![Some(2)]);
+
+        let config = SecurityAnalyzerConfig {
+            include_tests: true,
+            ..Default::default()
+        };
+        let analyzer = SecurityAnalyzer::with_config(config).unwrap();
+        let mut output = PluginOutput::new();
+        analyzer.audit_file(&mut file, source, &mut output);
+        assert_eq!(output.messages.len(), 3);
+    }
+}
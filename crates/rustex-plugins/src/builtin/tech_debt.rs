@@ -0,0 +1,434 @@
+//! Technical debt plugin.
+
+use crate::core::{Plugin, PluginContext, PluginError, PluginInfo, PluginOutput, PluginPhase};
+use crate::plugin_info;
+use regex::Regex;
+use rustex_core::{
+    CodeElement, FileAst, TechDebtKind, TechDebtMarker, TechDebtReport, TECH_DEBT_METADATA_KEY,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Seconds in a day, for the age of markers.
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Configuration for the tech debt plugin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct TechDebtConfig {
+    /// Whether to find the age of each marker with `git blame`, which runs
+    /// git once for each file with markers
+    pub blame: bool,
+
+    /// Kinds of markers not to collect
+    pub disabled_kinds: Vec<TechDebtKind>,
+}
+
+/// Tech debt plugin, collecting `TODO`, `FIXME` and `HACK` comments and
+/// `#[allow(dead_code)]` attributes.
+///
+/// A marker counts when it starts the text of a comment other than a doc
+/// comment, so docs and prose mentioning markers aren't collected.
+///
+/// Markers name their owner as in `TODO(alice): ...`. The report of the
+/// markers goes in the plugin's `tech_debt` metadata, which the Markdown
+/// output renders as its tech debt section.
+pub struct TechDebtCollector {
+    config: TechDebtConfig,
+    comment_marker: Regex,
+    allow_dead_code: Regex,
+}
+
+impl Default for TechDebtCollector {
+    fn default() -> Self {
+        Self::with_config(TechDebtConfig::default())
+    }
+}
+
+impl TechDebtCollector {
+    /// Create a new tech debt collector with custom configuration.
+    pub fn with_config(config: TechDebtConfig) -> Self {
+        Self {
+            config,
+            comment_marker: Regex::new(r"^(TODO|FIXME|HACK)\b(?:\(([^)]*)\))?:?\s*(.*)")
+                .expect("comment marker pattern is valid"),
+            allow_dead_code: Regex::new(r"#!?\[allow\([^)]*\bdead_code\b[^)]*\)\]")
+                .expect("dead code pattern is valid"),
+        }
+    }
+
+    /// Find the markers in the lines of `source`, the contents of `file`.
+    pub fn scan(&self, file: &Path, source: &str) -> Vec<TechDebtMarker> {
+        let mut markers = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let marker = |kind: TechDebtKind, text: &str, owner: Option<&str>| TechDebtMarker {
+                kind,
+                file: file.to_path_buf(),
+                line: index + 1,
+                text: text.trim().trim_end_matches("*/").trim_end().to_string(),
+                owner: owner
+                    .map(str::trim)
+                    .filter(|owner| !owner.is_empty())
+                    .map(String::from),
+                element: None,
+                age_days: None,
+            };
+
+            let (code, comment) = split_comment(line);
+            let captures = comment
+                .and_then(comment_text)
+                .and_then(|text| self.comment_marker.captures(text));
+            if let Some(captures) = captures {
+                let kind = match &captures[1] {
+                    "TODO" => TechDebtKind::Todo,
+                    "FIXME" => TechDebtKind::Fixme,
+                    _ => TechDebtKind::Hack,
+                };
+                let owner = captures.get(2).map(|owner| owner.as_str());
+                markers.push(marker(kind, &captures[3], owner));
+            }
+
+            if let Some(allow) = self.allow_dead_code.find(&code) {
+                markers.push(marker(TechDebtKind::AllowDeadCode, allow.as_str(), None));
+            }
+        }
+
+        markers.retain(|marker| !self.config.disabled_kinds.contains(&marker.kind));
+        markers
+    }
+
+    /// The markers of a file, with the elements they're in, or the element
+    /// they're on for attributes.
+    fn collect_file(&self, file: &FileAst, source: &str) -> Vec<TechDebtMarker> {
+        let mut markers = self.scan(&file.relative_path, source);
+        for marker in &mut markers {
+            let line = marker.line;
+            let element = innermost_element(&file.elements, line).or_else(|| {
+                // Attributes can come before the lines of the item they're on.
+                (marker.kind == TechDebtKind::AllowDeadCode)
+                    .then(|| {
+                        file.elements
+                            .iter()
+                            .filter(|element| element.location.line_start > line)
+                            .min_by_key(|element| element.location.line_start)
+                    })
+                    .flatten()
+            });
+            marker.element = element.map(|element| element.hierarchy.qualified_name.clone());
+        }
+        markers
+    }
+}
+
+/// Split a line into its code, without the contents of string literals, and its
+/// comment: what follows `//` or `/*` outside strings, or the line of a
+/// block comment starting with `*`.
+fn split_comment(line: &str) -> (String, Option<&str>) {
+    if line.trim_start().starts_with('*') {
+        return (String::new(), Some(line.trim_start()));
+    }
+    let mut code = String::new();
+    let mut in_string = false;
+    let mut chars = line.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '\'' if line[index..].starts_with("'\"'") => {
+                chars.next();
+                chars.next();
+            }
+            '/' if matches!(chars.peek(), Some((_, '/' | '*'))) => {
+                return (code, Some(&line[index..]));
+            }
+            _ => {}
+        }
+        code.push(c);
+    }
+    (code, None)
+}
+
+/// The text of a comment without its opening, or `None` for doc comments.
+fn comment_text(comment: &str) -> Option<&str> {
+    let is_doc = (comment.starts_with("///") && !comment.starts_with("////"))
+        || comment.starts_with("//!")
+        || (comment.starts_with("/**") && !comment.starts_with("/***") && comment != "/**/")
+        || comment.starts_with("/*!");
+    if is_doc {
+        return None;
+    }
+    let text = comment
+        .strip_prefix("//")
+        .or_else(|| comment.strip_prefix("/*"))
+        .unwrap_or(comment);
+    Some(text.trim_start_matches(['/', '*']).trim_start())
+}
+
+/// The element spanning `line` with the fewest lines, skipping generated ones.
+fn innermost_element(elements: &[CodeElement], line: usize) -> Option<&CodeElement> {
+    elements
+        .iter()
+        .filter(|element| !element.is_generated)
+        .filter(|element| element.location.line_start <= line && line <= element.location.line_end)
+        .min_by_key(|element| element.location.line_end - element.location.line_start)
+}
+
+/// When each line of the file at `path` was last changed, as a Unix time by
+/// line number, from `git blame`; `None` outside a git repository.
+fn blame(path: &Path) -> Option<HashMap<usize, i64>> {
+    let directory = path.parent()?;
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(path.file_name()?)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(parse_blame(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The `author-time` of each line of `git blame --line-porcelain` output.
+fn parse_blame(porcelain: &str) -> HashMap<usize, i64> {
+    let mut times = HashMap::new();
+    let mut line = None;
+    for entry in porcelain.lines() {
+        if let Some(time) = entry.strip_prefix("author-time ") {
+            if let (Some(line), Ok(time)) = (line, time.parse()) {
+                times.insert(line, time);
+            }
+        } else if !entry.starts_with('\t') {
+            // Headers are `<commit> <original line> <final line> [<lines>]`.
+            let mut fields = entry.split(' ');
+            let commit = fields.next().unwrap_or_default();
+            if commit.len() == 40 && commit.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                line = fields.nth(1).and_then(|line| line.parse().ok());
+            }
+        }
+    }
+    times
+}
+
+impl Plugin for TechDebtCollector {
+    fn info(&self) -> PluginInfo {
+        plugin_info!(
+            "tech-debt",
+            "0.1.0",
+            "Report of TODO, FIXME and HACK comments and allow(dead_code) attributes",
+            phases: [PluginPhase::PostProject]
+        )
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        if !config.is_null() {
+            let config = serde_json::from_value(config.clone())
+                .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+            *self = Self::with_config(config);
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(TechDebtConfig)).ok()
+    }
+
+    fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
+        let project = context.project_ast().ok_or_else(|| {
+            PluginError::InvalidContext("tech-debt needs the project AST".to_string())
+        })?;
+        let now = chrono::Utc::now().timestamp();
+
+        let mut markers = Vec::new();
+        for file in &project.files {
+            let Ok(source) = std::fs::read_to_string(&file.path) else {
+                continue;
+            };
+            let mut file_markers = self.collect_file(file, &source);
+            if self.config.blame && !file_markers.is_empty() {
+                if let Some(times) = blame(&file.path) {
+                    for marker in &mut file_markers {
+                        marker.age_days = times
+                            .get(&marker.line)
+                            .map(|time| ((now - time).max(0) / SECONDS_PER_DAY) as u64);
+                    }
+                }
+            }
+            markers.extend(file_markers);
+        }
+
+        let report = TechDebtReport::new(markers);
+        let mut output = PluginOutput::new();
+        output.add_metric("tech_debt_markers", report.markers.len() as f64);
+        for kind in TechDebtKind::ALL {
+            let count = report.by_kind.get(&kind).copied().unwrap_or_default();
+            let name = serde_json::to_value(kind).unwrap_or_default();
+            output.add_metric(
+                format!("{}_markers", name.as_str().unwrap_or_default()),
+                count as f64,
+            );
+        }
+        output.add_metric(
+            "owned_markers",
+            (report.markers.len() - report.unowned) as f64,
+        );
+        if let Some(oldest) = report.oldest_age_days() {
+            output.add_metric("oldest_marker_days", oldest as f64);
+        }
+        output.add_metadata(
+            TECH_DEBT_METADATA_KEY,
+            serde_json::to_value(&report).unwrap_or_default(),
+        );
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::test_fixtures::MockDataGenerator;
+    use std::path::PathBuf;
+
+    const SOURCE: &str = r#"// TODO(alice): split this module
+fn parse() {
+    let url = "https://example.com"; // FIXME: configurable
+    /* HACK work around the borrow checker */
+    let todo_list = Vec::new(); // no marker: TODOs in names don't count
+}
+
+#[allow(unused, dead_code)]
+struct Legacy;
+"#;
+
+    #[test]
+    fn test_scan() {
+        let collector = TechDebtCollector::default();
+        let markers = collector.scan(Path::new("src/lib.rs"), SOURCE);
+        let found: Vec<(TechDebtKind, usize, &str, Option<&str>)> = markers
+            .iter()
+            .map(|marker| {
+                (
+                    marker.kind,
+                    marker.line,
+                    marker.text.as_str(),
+                    marker.owner.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (TechDebtKind::Todo, 1, "split this module", Some("alice")),
+                (TechDebtKind::Fixme, 3, "configurable", None),
+                (
+                    TechDebtKind::Hack,
+                    4,
+                    "work around the borrow checker",
+                    None
+                ),
+                (
+                    TechDebtKind::AllowDeadCode,
+                    8,
+                    "#[allow(unused, dead_code)]",
+                    None
+                ),
+            ]
+        );
+
+        let config = TechDebtConfig {
+            disabled_kinds: vec![TechDebtKind::AllowDeadCode, TechDebtKind::Hack],
+            ..TechDebtConfig::default()
+        };
+        let collector = TechDebtCollector::with_config(config);
+        assert_eq!(collector.scan(Path::new("src/lib.rs"), SOURCE).len(), 2);
+    }
+
+    #[test]
+    fn test_scan_skips_prose() {
+        let source = r##"//! Collects `TODO` and `#[allow(dead_code)]` markers.
+/// TODO: doc comments aren't debt
+/** FIXME: nor are block doc comments */
+// See the TODO in the parser
+let marker = "// TODO: in a string"; // HACK: after a string
+let pattern = "#[allow(dead_code)]";
+fn f() {} // #[allow(dead_code)] in a comment
+//// TODO: four slashes make a plain comment
+ * FIXME: in a block comment
+    #[allow(dead_code)] // TODO
+"##;
+        let collector = TechDebtCollector::default();
+        let found: Vec<(TechDebtKind, usize)> = collector
+            .scan(Path::new("src/lib.rs"), source)
+            .iter()
+            .map(|marker| (marker.kind, marker.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (TechDebtKind::Hack, 5),
+                (TechDebtKind::Todo, 8),
+                (TechDebtKind::Fixme, 9),
+                (TechDebtKind::Todo, 10),
+                (TechDebtKind::AllowDeadCode, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_file() {
+        let collector = TechDebtCollector::default();
+        let mut file = MockDataGenerator::file_ast(PathBuf::from("/test/src/lib.rs"), 2);
+        file.relative_path = PathBuf::from("src/lib.rs");
+        for (element, (line_start, line_end)) in file.elements.iter_mut().zip([(2, 6), (9, 9)]) {
+            element.location.line_start = line_start;
+            element.location.line_end = line_end;
+        }
+
+        let markers = collector.collect_file(&file, SOURCE);
+        let elements: Vec<Option<&str>> = markers
+            .iter()
+            .map(|marker| marker.element.as_deref())
+            .collect();
+        let parse = file.elements[0].hierarchy.qualified_name.as_str();
+        let legacy = file.elements[1].hierarchy.qualified_name.as_str();
+        assert_eq!(elements, [None, Some(parse), Some(parse), Some(legacy)]);
+
+        let report = TechDebtReport::new(markers);
+        assert_eq!(report.by_kind[&TechDebtKind::Todo], 1);
+        assert_eq!(report.by_owner["alice"], 1);
+        assert_eq!(report.unowned, 3);
+    }
+
+    #[test]
+    fn test_parse_blame() {
+        let porcelain = "\
+4b825dc642cb6eb9a060e54bf8d69288fbee4904 1 1 2
+author Alice
+author-time 1700000000
+\t// TODO(alice): split this module
+4b825dc642cb6eb9a060e54bf8d69288fbee4904 2 2
+author Alice
+author-time 1700086400
+\tfn parse() {
+";
+        let times = parse_blame(porcelain);
+        assert_eq!(times[&1], 1_700_000_000);
+        assert_eq!(times[&2], 1_700_086_400);
+        assert_eq!(times.len(), 2);
+    }
+}
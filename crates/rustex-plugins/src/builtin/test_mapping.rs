@@ -0,0 +1,628 @@
+//! Test-to-code mapping plugin.
+
+use super::security::{is_test_element, is_test_file};
+use crate::core::plugin::MessageLevel;
+use crate::core::{Plugin, PluginContext, PluginError, PluginInfo, PluginOutput, PluginPhase};
+use crate::plugin_info;
+use crate::utils::StringHelpers;
+use rustex_core::{
+    CallKind, CodeElement, ElementType, ExtractorConfig, FileAst, ProjectAst, Visibility,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Key of the element `metadata` entry listing the tests of the element.
+pub const TESTED_BY_METADATA_KEY: &str = "tested_by";
+
+/// Key of the plugin metadata holding the coverage of each module.
+pub const TEST_COVERAGE_METADATA_KEY: &str = "test_coverage";
+
+/// Types of public elements tests are linked to.
+const TESTED_TYPES: [ElementType; 5] = [
+    ElementType::Function,
+    ElementType::Struct,
+    ElementType::Enum,
+    ElementType::Trait,
+    ElementType::Union,
+];
+
+/// Configuration for the test mapping plugin.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct TestMappingConfig {
+    /// Whether to also link tests to the elements their names mention, as
+    /// `test_parser_new` mentions `Parser::new`, besides those they call
+    pub name_heuristics: bool,
+}
+
+impl Default for TestMappingConfig {
+    fn default() -> Self {
+        Self {
+            name_heuristics: true,
+        }
+    }
+}
+
+/// Approximate test coverage of the public functions of a module.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleCoverage {
+    /// Module path, such as `crate::parser`
+    pub module: String,
+    /// Number of public functions and methods
+    pub public_functions: usize,
+    /// Number of those with tests
+    pub tested_functions: usize,
+    /// Share of the public functions with tests, from 0 to 1
+    pub coverage: f64,
+}
+
+/// The tests of the public elements of a project.
+#[derive(Debug, Clone, Default)]
+pub struct TestMap {
+    /// Number of tests
+    pub tests: usize,
+    /// Qualified names of the tests of each public element with tests, by
+    /// element ID
+    pub tested_by: BTreeMap<String, BTreeSet<String>>,
+    /// Coverage of each module with public functions, by module path
+    pub modules: Vec<ModuleCoverage>,
+}
+
+impl TestMap {
+    /// Public functions and methods in all modules.
+    pub fn public_functions(&self) -> usize {
+        self.modules
+            .iter()
+            .map(|module| module.public_functions)
+            .sum()
+    }
+
+    /// Public functions and methods with tests in all modules.
+    pub fn tested_functions(&self) -> usize {
+        self.modules
+            .iter()
+            .map(|module| module.tested_functions)
+            .sum()
+    }
+}
+
+/// Test mapping plugin, linking test functions to the public elements they
+/// exercise.
+///
+/// A test exercises the elements it calls, from the project's reference
+/// graph and, for calls it doesn't resolve, by name; with `name_heuristics`
+/// also those its name mentions, in its own file for unit tests. Types are
+/// tested by the tests of their methods. Each public element with tests gets
+/// their qualified names in its `tested_by` metadata, and the share of public
+/// functions with tests in each module is a proxy for its test coverage.
+///
+/// Tests are only extracted with `include_private = true`, and integration
+/// tests only when the filters include `tests/**/*.rs` and drop the default
+/// `tests/**` exclude pattern.
+#[derive(Default)]
+pub struct TestMapper {
+    config: TestMappingConfig,
+}
+
+impl TestMapper {
+    /// Create a new test mapper with custom configuration.
+    pub fn with_config(config: TestMappingConfig) -> Self {
+        Self { config }
+    }
+
+    /// Link the tests of `project` to its public elements.
+    pub fn map(&self, project: &ProjectAst) -> TestMap {
+        let mut tests: Vec<(&FileAst, &CodeElement)> = Vec::new();
+        let mut public: Vec<(&FileAst, &CodeElement)> = Vec::new();
+        for file in &project.files {
+            let test_file = is_test_file(&file.relative_path);
+            for element in &file.elements {
+                if is_test_function(element) {
+                    tests.push((file, element));
+                } else if !test_file
+                    && !element.is_generated
+                    && !is_test_element(element)
+                    && element.visibility == Visibility::Public
+                    && TESTED_TYPES.contains(&element.element_type)
+                {
+                    public.push((file, element));
+                }
+            }
+        }
+        let public_ids: HashMap<&str, &CodeElement> = public
+            .iter()
+            .map(|(_, element)| (element.id.as_str(), *element))
+            .collect();
+        let mut functions_by_name: HashMap<&str, Vec<&CodeElement>> = HashMap::new();
+        for (_, element) in &public {
+            if element.element_type == ElementType::Function {
+                functions_by_name
+                    .entry(element.name.as_str())
+                    .or_default()
+                    .push(element);
+            }
+        }
+
+        let mut tested_by: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (file, test) in &tests {
+            let mut exercised: BTreeSet<&str> = BTreeSet::new();
+            let references = project.reference_graph.references.get(&test.id);
+            exercised.extend(
+                references
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .filter(|id| public_ids.contains_key(id)),
+            );
+
+            // Calls the reference graph couldn't resolve, by name: qualified
+            // calls by the end of the element's qualified name, others only
+            // when one public function has the name.
+            for call in test
+                .calls
+                .iter()
+                .filter(|call| call.kind != CallKind::Macro)
+            {
+                let name = call.name.rsplit("::").next().unwrap_or(&call.name);
+                let candidates = functions_by_name
+                    .get(name)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                if call.name.contains("::") {
+                    let suffix = format!("::{}", call.name);
+                    exercised.extend(
+                        candidates
+                            .iter()
+                            .filter(|element| element.hierarchy.qualified_name.ends_with(&suffix))
+                            .map(|element| element.id.as_str()),
+                    );
+                } else if let [element] = candidates {
+                    exercised.insert(&element.id);
+                }
+            }
+
+            if self.config.name_heuristics {
+                let unit_test = !is_test_file(&file.relative_path);
+                let words = name_words(&test.name);
+                let mentioned = public
+                    .iter()
+                    .filter(|(element_file, _)| !unit_test || element_file.path == file.path)
+                    .filter(|(_, element)| mentions(&words, element))
+                    .map(|(_, element)| element.id.as_str());
+                exercised.extend(mentioned);
+            }
+
+            for id in exercised {
+                tested_by
+                    .entry(id.to_string())
+                    .or_default()
+                    .insert(test.hierarchy.qualified_name.clone());
+            }
+        }
+
+        // Types are tested by the tests of their methods.
+        for (_, element) in &public {
+            let Some(parent) = &element.hierarchy.parent_element else {
+                continue;
+            };
+            if !public_ids.contains_key(parent.as_str()) {
+                continue;
+            }
+            if let Some(tests) = tested_by.get(&element.id).cloned() {
+                tested_by.entry(parent.clone()).or_default().extend(tests);
+            }
+        }
+
+        let mut modules: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for (_, element) in &public {
+            if element.element_type == ElementType::Function {
+                let counts = modules.entry(&element.hierarchy.module_path).or_default();
+                counts.0 += 1;
+                if tested_by.contains_key(&element.id) {
+                    counts.1 += 1;
+                }
+            }
+        }
+        let modules = modules
+            .into_iter()
+            .map(
+                |(module, (public_functions, tested_functions))| ModuleCoverage {
+                    module: module.to_string(),
+                    public_functions,
+                    tested_functions,
+                    coverage: tested_functions as f64 / public_functions as f64,
+                },
+            )
+            .collect();
+
+        TestMap {
+            tests: tests.len(),
+            tested_by,
+            modules,
+        }
+    }
+}
+
+/// Whether `element` is a test function, marked `#[test]` or with a test
+/// attribute of another crate, such as `#[tokio::test]`.
+fn is_test_function(element: &CodeElement) -> bool {
+    element.element_type == ElementType::Function
+        && element
+            .attribute_info
+            .iter()
+            .any(|attribute| attribute.name.rsplit("::").next() == Some("test"))
+}
+
+/// The words of a test name, without a `test` prefix or suffix.
+fn name_words(test_name: &str) -> Vec<&str> {
+    let name = test_name.strip_prefix("test_").unwrap_or(test_name);
+    let name = name.strip_suffix("_test").unwrap_or(name);
+    name.split('_').filter(|word| !word.is_empty()).collect()
+}
+
+/// Whether the test name `words` mention `element`, with the words of its
+/// name in a row, and for methods also those of their type.
+fn mentions(words: &[&str], element: &CodeElement) -> bool {
+    let contains = |name: &str| {
+        let name = StringHelpers::camel_to_snake(name);
+        let name: Vec<&str> = name.split('_').filter(|word| !word.is_empty()).collect();
+        !name.is_empty()
+            && words
+                .windows(name.len())
+                .any(|window| window == name.as_slice())
+    };
+    if !contains(&element.name) {
+        return false;
+    }
+    match &element.hierarchy.impl_context {
+        Some(context) if element.element_type == ElementType::Function => {
+            let self_type = context
+                .self_type
+                .rsplit("::")
+                .next()
+                .unwrap_or(&context.self_type);
+            contains(self_type.split('<').next().unwrap_or(self_type))
+        }
+        _ => true,
+    }
+}
+
+/// The warning when no tests were extracted, naming the settings of `config`
+/// that keep them out.
+fn no_tests_message(config: &ExtractorConfig) -> String {
+    let mut hints = Vec::new();
+    if !config.include_private {
+        hints.push("set include_private = true to extract test functions".to_string());
+    }
+    let filters = &config.filters;
+    let mut changes: Vec<String> = filters
+        .exclude
+        .iter()
+        .filter(|pattern| pattern.starts_with("tests/"))
+        .map(|pattern| format!("remove the exclude pattern `{}`", pattern))
+        .collect();
+    if !filters
+        .include
+        .iter()
+        .any(|pattern| pattern.starts_with("tests/"))
+    {
+        changes.push("add the include pattern `tests/**/*.rs`".to_string());
+    }
+    if !changes.is_empty() {
+        hints.push(format!(
+            "{} to extract integration tests",
+            changes.join(" and ")
+        ));
+    }
+    let mut message = "No tests were extracted".to_string();
+    if !hints.is_empty() {
+        message.push_str("; ");
+        message.push_str(&hints.join("; "));
+    }
+    message
+}
+
+impl Plugin for TestMapper {
+    fn info(&self) -> PluginInfo {
+        plugin_info!(
+            "test-mapping",
+            "0.1.0",
+            "Links tests to the public elements they exercise, with a test coverage proxy",
+            phases: [PluginPhase::PostProject]
+        )
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        if !config.is_null() {
+            self.config = serde_json::from_value(config.clone())
+                .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(schemars::schema_for!(TestMappingConfig)).ok()
+    }
+
+    fn post_project(&self, context: &PluginContext) -> Result<PluginOutput, PluginError> {
+        let project = context.project_ast().ok_or_else(|| {
+            PluginError::InvalidContext("test-mapping needs the project AST".to_string())
+        })?;
+        let map = self.map(project);
+
+        let mut output = PluginOutput::new();
+        if map.tests == 0 {
+            output.add_message(MessageLevel::Warning, no_tests_message(context.config));
+        }
+        let elements = project.files.iter().flat_map(|file| &file.elements);
+        for element in elements {
+            if let Some(tests) = map.tested_by.get(&element.id) {
+                let mut element = element.clone();
+                element.metadata.insert(
+                    TESTED_BY_METADATA_KEY.to_string(),
+                    serde_json::to_value(tests).unwrap_or_default(),
+                );
+                output.add_element(element);
+            }
+        }
+
+        let public_functions = map.public_functions();
+        let tested_functions = map.tested_functions();
+        output.add_metric("tests", map.tests as f64);
+        output.add_metric("public_functions", public_functions as f64);
+        output.add_metric("tested_functions", tested_functions as f64);
+        if public_functions > 0 {
+            output.add_metric(
+                "test_coverage",
+                tested_functions as f64 / public_functions as f64,
+            );
+        }
+        for module in &map.modules {
+            output.add_metric(format!("test_coverage:{}", module.module), module.coverage);
+        }
+        output.add_metadata(
+            TEST_COVERAGE_METADATA_KEY,
+            serde_json::to_value(&map.modules).unwrap_or_default(),
+        );
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustex_core::{AstExtractor, ExtractorConfig};
+    use std::fs;
+
+    const LIB: &str = r#"pub mod parser;
+
+pub fn add(a: i32, b: i32) -> i32 { a + b }
+
+pub fn unused() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        assert_eq!(add(1, 2), 3);
+    }
+
+    #[test]
+    fn unused_is_callable() {}
+}
+"#;
+
+    const PARSER: &str = r#"pub struct Parser { pub depth: usize }
+
+impl Parser {
+    pub fn new() -> Self { Parser { depth: 0 } }
+    pub fn parse(&self, input: &str) -> usize { input.len() + self.depth }
+    pub fn reset(&mut self) {}
+}
+
+pub fn tokenize(input: &str) -> Vec<&str> { input.split(' ').collect() }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_input() {
+        let parser = Parser::new();
+        assert_eq!(parser.parse("ab"), 2);
+    }
+
+    #[test]
+    fn test_parser_reset() {}
+}
+"#;
+
+    const INTEGRATION: &str = r#"use sample::parser::tokenize;
+
+#[tokio::test]
+async fn splits_words() {
+    assert_eq!(tokenize("a b").len(), 2);
+}
+"#;
+
+    fn extract(include_private: bool) -> ProjectAst {
+        let mut config = ExtractorConfig {
+            include_private,
+            ..ExtractorConfig::default()
+        };
+        include_integration_tests(&mut config);
+        extract_with(&config, true)
+    }
+
+    fn include_integration_tests(config: &mut ExtractorConfig) {
+        config.filters.include.push("tests/**/*.rs".to_string());
+        config
+            .filters
+            .exclude
+            .retain(|pattern| pattern != "tests/**");
+    }
+
+    /// Extract the sample project, with or without its unit tests.
+    fn extract_with(config: &ExtractorConfig, unit_tests: bool) -> ProjectAst {
+        let source = |source: &'static str| {
+            if unit_tests {
+                source
+            } else {
+                source.split("#[cfg(test)]").next().unwrap()
+            }
+        };
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("tests")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"sample\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), source(LIB)).unwrap();
+        fs::write(root.join("src/parser.rs"), source(PARSER)).unwrap();
+        fs::write(root.join("tests/words.rs"), INTEGRATION).unwrap();
+
+        AstExtractor::new(config.clone(), root.to_path_buf())
+            .extract_project()
+            .unwrap()
+    }
+
+    fn tests_of<'a>(project: &ProjectAst, map: &'a TestMap, name: &str) -> Vec<&'a str> {
+        let element = project
+            .files
+            .iter()
+            .flat_map(|file| &file.elements)
+            .find(|element| element.hierarchy.qualified_name == name)
+            .unwrap();
+        map.tested_by
+            .get(&element.id)
+            .map(|tests| tests.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_map() {
+        let project = extract(true);
+        let map = TestMapper::default().map(&project);
+        assert_eq!(map.tests, 5);
+
+        let tests = |name| tests_of(&project, &map, name);
+        assert_eq!(tests("crate::add"), ["crate::tests::test_add"]);
+        assert_eq!(tests("crate::unused"), ["crate::tests::unused_is_callable"]);
+        assert_eq!(
+            tests("crate::parser::Parser::new"),
+            ["crate::parser::tests::parses_input"]
+        );
+        let reset = tests("crate::parser::Parser::reset");
+        assert_eq!(reset, ["crate::parser::tests::test_parser_reset"]);
+        assert_eq!(
+            tests("crate::parser::Parser"),
+            [
+                "crate::parser::tests::parses_input",
+                "crate::parser::tests::test_parser_reset"
+            ]
+        );
+        assert_eq!(
+            tests("crate::parser::tokenize"),
+            ["crate::words::splits_words"]
+        );
+
+        let modules: Vec<(&str, usize, usize)> = map
+            .modules
+            .iter()
+            .map(|coverage| {
+                (
+                    coverage.module.as_str(),
+                    coverage.public_functions,
+                    coverage.tested_functions,
+                )
+            })
+            .collect();
+        assert_eq!(modules, [("crate", 2, 2), ("crate::parser", 4, 4)]);
+
+        // Without name heuristics, only calls link tests.
+        let mapper = TestMapper::with_config(TestMappingConfig {
+            name_heuristics: false,
+        });
+        let map = mapper.map(&project);
+        assert!(tests_of(&project, &map, "crate::unused").is_empty());
+        assert_eq!(map.tested_functions(), 4);
+    }
+
+    #[test]
+    fn test_post_project() {
+        let mut project = extract(true);
+        let project_info = project.project.clone();
+        let config = ExtractorConfig::default();
+        let mut metadata = HashMap::new();
+        let context =
+            PluginContext::new_project_process(&project_info, &mut project, &config, &mut metadata);
+        let output = TestMapper::default().post_project(&context).unwrap();
+
+        let add = output
+            .modified_elements
+            .iter()
+            .find(|element| element.name == "add")
+            .unwrap();
+        let tested_by = &add.metadata[TESTED_BY_METADATA_KEY];
+        assert_eq!(tested_by, &serde_json::json!(["crate::tests::test_add"]));
+        assert_eq!(output.metrics["test_coverage"], 1.0);
+        assert_eq!(output.metrics["test_coverage:crate::parser"], 1.0);
+        assert!(output.messages.is_empty());
+
+        // Tests are private, so aren't extracted by default.
+        let mut project = extract(false);
+        let context =
+            PluginContext::new_project_process(&project_info, &mut project, &config, &mut metadata);
+        let output = TestMapper::default().post_project(&context).unwrap();
+        assert_eq!(output.metrics["test_coverage"], 0.0);
+        assert!(matches!(output.messages[0].level, MessageLevel::Warning));
+        assert!(output.messages[0]
+            .message
+            .contains("include_private = true"));
+    }
+
+    #[test]
+    fn test_integration_tests_only() {
+        // Integration tests are excluded by default even with private items.
+        let mut config = ExtractorConfig {
+            include_private: true,
+            ..ExtractorConfig::default()
+        };
+        let mut project = extract_with(&config, false);
+        let project_info = project.project.clone();
+        let mut metadata = HashMap::new();
+        let context =
+            PluginContext::new_project_process(&project_info, &mut project, &config, &mut metadata);
+        let output = TestMapper::default().post_project(&context).unwrap();
+        assert_eq!(
+            output.messages[0].message,
+            "No tests were extracted; remove the exclude pattern `tests/**` and add the include \
+             pattern `tests/**/*.rs` to extract integration tests"
+        );
+
+        include_integration_tests(&mut config);
+        let mut project = extract_with(&config, false);
+        let context =
+            PluginContext::new_project_process(&project_info, &mut project, &config, &mut metadata);
+        let output = TestMapper::default().post_project(&context).unwrap();
+        assert!(output.messages.is_empty());
+        assert_eq!(output.metrics["tests"], 1.0);
+        let tokenize = output
+            .modified_elements
+            .iter()
+            .find(|element| element.name == "tokenize")
+            .unwrap();
+        let tested_by = &tokenize.metadata[TESTED_BY_METADATA_KEY];
+        assert_eq!(
+            tested_by,
+            &serde_json::json!(["crate::words::splits_words"])
+        );
+    }
+}
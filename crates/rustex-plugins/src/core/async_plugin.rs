@@ -0,0 +1,58 @@
+//! Asynchronous plugin trait, for plugins waiting on I/O.
+
+use super::context::PluginContext;
+use super::errors::PluginResult;
+use super::plugin::{PluginInfo, PluginOutput, PluginPhase};
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Plugin trait for plugins whose phases wait on I/O, such as calls to
+/// external services.
+///
+/// The plugin manager runs the async plugins of a phase concurrently, except
+/// that a plugin runs after the plugins it depends on, and stops waiting for
+/// a plugin after its timeout. Async plugins can support the phases with a
+/// context, `PreProcess`, `PostProject`, `PreFormat` and `PostFormat`, but
+/// only run in phases run with
+/// [`PluginManager::execute_phase_async`](super::PluginManager::execute_phase_async).
+/// [`PluginManager::run_on_project`](super::PluginManager::run_on_project)
+/// runs `PostProject` that way; `execute_phase` fails them.
+#[async_trait]
+pub trait AsyncPlugin: Send + Sync {
+    /// Get plugin information.
+    fn info(&self) -> PluginInfo;
+
+    /// Get plugin name (convenience method).
+    fn name(&self) -> String {
+        self.info().name
+    }
+
+    /// Initialize the plugin with configuration.
+    fn initialize(&mut self, config: &serde_json::Value) -> PluginResult<()> {
+        let _ = config;
+        Ok(())
+    }
+
+    /// JSON schema of the plugin's settings in `[plugins.config.<name>]`.
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// How long a phase of the plugin may take, unless the manager's
+    /// configuration sets a timeout for the plugin.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Execute the plugin for a specific phase.
+    async fn execute(
+        &self,
+        phase: PluginPhase,
+        context: &PluginContext<'_>,
+    ) -> PluginResult<PluginOutput>;
+
+    /// Clean up plugin resources.
+    fn cleanup(&mut self) -> PluginResult<()> {
+        Ok(())
+    }
+}
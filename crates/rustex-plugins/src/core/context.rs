@@ -1,7 +1,9 @@
 //! Plugin execution context types.
 
 use std::collections::HashMap;
+use serde::de::DeserializeOwned;
 use rustex_core::{ProjectInfo, FileAst, ProjectAst, ExtractorConfig, CodeElement};
+use super::errors::{PluginError, PluginResult};
 
 /// Main plugin execution context.
 #[derive(Debug)]
@@ -111,22 +113,22 @@ impl<'a> PluginContext<'a> {
     
     /// Get the current file being processed (if any).
     pub fn current_file(&self) -> Option<&FileAst> {
-        self.current_file.as_ref().map(|f| &**f)
+        self.current_file.as_deref()
     }
     
     /// Get a mutable reference to the current file (if any).
     pub fn current_file_mut(&mut self) -> Option<&mut FileAst> {
-        self.current_file.as_mut().map(|f| &mut **f)
+        self.current_file.as_deref_mut()
     }
     
     /// Get the project AST (if available).
     pub fn project_ast(&self) -> Option<&ProjectAst> {
-        self.project_ast.as_ref().map(|p| &**p)
+        self.project_ast.as_deref()
     }
     
     /// Get a mutable reference to the project AST (if available).
     pub fn project_ast_mut(&mut self) -> Option<&mut ProjectAst> {
-        self.project_ast.as_mut().map(|p| &mut **p)
+        self.project_ast.as_deref_mut()
     }
     
     /// Get all code elements from current context.
@@ -165,6 +167,24 @@ impl<'a> PluginContext<'a> {
         self.metadata.insert(key.to_string(), value.into());
     }
     
+    /// Get the settings of a plugin in `[plugins.config.<name>]` (if any).
+    pub fn plugin_config(&self, name: &str) -> Option<&serde_json::Value> {
+        self.config.plugins.config.get(name)
+    }
+    
+    /// Get the settings of a plugin as `T` (if any).
+    pub fn plugin_config_as<T>(&self, name: &str) -> PluginResult<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.plugin_config(name)
+            .map(|config| T::deserialize(config))
+            .transpose()
+            .map_err(|e| {
+                PluginError::ConfigurationError(format!("[plugins.config.{}] {}", name, e))
+            })
+    }
+    
     /// Check if this is a file processing context.
     pub fn is_file_context(&self) -> bool {
         matches!(self.context_type, ContextType::FileProcess)
@@ -254,6 +274,7 @@ impl<'a> PostProcessContext<'a> {
 mod tests {
     use super::*;
     use rustex_core::ProjectInfo;
+    use crate::builtin::metrics::MetricsConfig;
     use std::path::PathBuf;
     // use chrono::Utc; // Unused import removed
 
@@ -296,4 +317,26 @@ mod tests {
             Some(&serde_json::Value::String("test_value".to_string()))
         );
     }
+
+    #[test]
+    fn test_plugin_config() {
+        let project_info = create_test_project_info();
+        let config = ExtractorConfig::from_toml_str(
+            "[plugins.config.metrics-collector]\ndetailed_metrics = false\n",
+        )
+        .unwrap();
+        let mut metadata = HashMap::new();
+        
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        assert!(context.plugin_config("llm-optimizer").is_none());
+        
+        let metrics: MetricsConfig = context
+            .plugin_config_as("metrics-collector")
+            .unwrap()
+            .unwrap();
+        assert!(!metrics.detailed_metrics);
+        assert!(context
+            .plugin_config_as::<u32>("metrics-collector")
+            .is_err());
+    }
 }
\ No newline at end of file
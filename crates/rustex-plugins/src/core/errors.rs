@@ -33,6 +33,9 @@ pub enum PluginError {
     /// Serialization/deserialization error
     SerializationError(String),
     
+    /// Plugin took longer than its timeout
+    Timeout(String),
+    
     /// Generic plugin error with custom message
     Custom(String),
 }
@@ -64,6 +67,9 @@ impl fmt::Display for PluginError {
             PluginError::SerializationError(msg) => {
                 write!(f, "Plugin serialization error: {}", msg)
             }
+            PluginError::Timeout(msg) => {
+                write!(f, "Plugin timed out: {}", msg)
+            }
             PluginError::Custom(msg) => {
                 write!(f, "Plugin error: {}", msg)
             }
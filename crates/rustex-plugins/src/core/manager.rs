@@ -1,15 +1,19 @@
 //! Plugin manager for registering and executing plugins.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
 use tracing::{debug, warn, error, info};
 use serde::{Deserialize, Serialize};
-use super::{Plugin, PluginPhase, PluginContext, PluginError};
-use super::plugin::PluginOutput;
+use rustex_core::{CodeElement, ExtractorConfig, FileAst, PluginDiagnostic, ProjectAst};
+use super::{AsyncPlugin, Plugin, PluginPhase, PluginContext, PluginError};
+use super::plugin::{MessageLevel, PluginInfo, PluginOutput};
+use super::profile::{Measurement, PluginProfile, Probe};
 
 /// Configuration for the plugin manager.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginManagerConfig {
-    /// List of enabled plugin names
+    /// List of enabled plugin names; plugins in neither list are enabled
+    /// if their info says so by default
     pub enabled_plugins: Vec<String>,
     
     /// List of disabled plugin names (takes precedence over enabled)
@@ -23,6 +27,12 @@ pub struct PluginManagerConfig {
     
     /// Maximum number of plugins to run concurrently
     pub max_concurrent: usize,
+    
+    /// Timeout of the async plugins without a timeout of their own
+    pub default_timeout: Option<Duration>,
+    
+    /// Timeouts of async plugins by name, overriding their own
+    pub plugin_timeouts: HashMap<String, Duration>,
 }
 
 impl Default for PluginManagerConfig {
@@ -33,6 +43,21 @@ impl Default for PluginManagerConfig {
             plugin_configs: HashMap::new(),
             continue_on_error: true,
             max_concurrent: 4,
+            default_timeout: None,
+            plugin_timeouts: HashMap::new(),
+        }
+    }
+}
+
+impl From<&ExtractorConfig> for PluginManagerConfig {
+    /// The `plugins` and `disabled_plugins` settings of a configuration,
+    /// with the settings of plugins in `plugins.config`.
+    fn from(config: &ExtractorConfig) -> Self {
+        Self {
+            enabled_plugins: config.plugins.enabled.clone(),
+            disabled_plugins: config.disabled_plugins.clone(),
+            plugin_configs: config.plugins.config.clone().into_iter().collect(),
+            ..Self::default()
         }
     }
 }
@@ -40,7 +65,7 @@ impl Default for PluginManagerConfig {
 /// Manages plugin registration and execution.
 pub struct PluginManager {
     /// Registered plugins
-    plugins: HashMap<String, Box<dyn Plugin>>,
+    plugins: HashMap<String, RegisteredPlugin>,
     
     /// Plugin execution order by phase
     execution_order: HashMap<PluginPhase, Vec<String>>,
@@ -50,6 +75,52 @@ pub struct PluginManager {
     
     /// Plugin execution statistics
     stats: PluginStats,
+    
+    /// Outputs of the plugins run since their last merge, by plugin name
+    outputs: BTreeMap<String, PluginOutput>,
+}
+
+/// A registered plugin, synchronous or asynchronous.
+enum RegisteredPlugin {
+    Sync(Box<dyn Plugin>),
+    Async(Box<dyn AsyncPlugin>),
+}
+
+impl RegisteredPlugin {
+    fn info(&self) -> PluginInfo {
+        match self {
+            Self::Sync(plugin) => plugin.info(),
+            Self::Async(plugin) => plugin.info(),
+        }
+    }
+    
+    fn initialize(&mut self, config: &serde_json::Value) -> Result<(), PluginError> {
+        match self {
+            Self::Sync(plugin) => plugin.initialize(config),
+            Self::Async(plugin) => plugin.initialize(config),
+        }
+    }
+    
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Sync(plugin) => plugin.config_schema(),
+            Self::Async(plugin) => plugin.config_schema(),
+        }
+    }
+    
+    fn cleanup(&mut self) -> Result<(), PluginError> {
+        match self {
+            Self::Sync(plugin) => plugin.cleanup(),
+            Self::Async(plugin) => plugin.cleanup(),
+        }
+    }
+}
+
+/// Numbers of plugins executed and failed in a phase.
+#[derive(Default)]
+struct PhaseTally {
+    executed: usize,
+    failed: usize,
 }
 
 /// Plugin execution statistics.
@@ -64,6 +135,9 @@ pub struct PluginStats {
     /// Total execution time per plugin (in milliseconds)
     pub execution_times: HashMap<String, u64>,
     
+    /// Execution profile per plugin
+    pub profiles: HashMap<String, PluginProfile>,
+    
     /// Total number of successful executions
     pub total_successes: usize,
     
@@ -84,12 +158,39 @@ impl PluginManager {
             execution_order: HashMap::new(),
             config,
             stats: PluginStats::default(),
+            outputs: BTreeMap::new(),
         }
     }
     
     /// Register a plugin with the manager.
-    pub fn register_plugin(&mut self, mut plugin: Box<dyn Plugin>) -> Result<(), PluginError> {
-        let plugin_name = plugin.name();
+    pub fn register_plugin(&mut self, plugin: Box<dyn Plugin>) -> Result<(), PluginError> {
+        self.register(RegisteredPlugin::Sync(plugin))
+    }
+    
+    /// Register an async plugin with the manager.
+    ///
+    /// Async plugins can't support the `PostFile` and `PostElement` phases,
+    /// which run synchronously on each file.
+    pub fn register_async_plugin(
+        &mut self,
+        plugin: Box<dyn AsyncPlugin>,
+    ) -> Result<(), PluginError> {
+        let info = plugin.info();
+        let file_phase = info
+            .supported_phases
+            .iter()
+            .find(|phase| matches!(phase, PluginPhase::PostFile | PluginPhase::PostElement));
+        if let Some(phase) = file_phase {
+            return Err(PluginError::ConfigurationError(format!(
+                "Async plugin '{}' can't support phase {:?}",
+                info.name, phase
+            )));
+        }
+        self.register(RegisteredPlugin::Async(plugin))
+    }
+    
+    fn register(&mut self, mut plugin: RegisteredPlugin) -> Result<(), PluginError> {
+        let plugin_name = plugin.info().name;
         
         debug!("Registering plugin: {}", plugin_name);
         
@@ -101,18 +202,24 @@ impl PluginManager {
         }
         
         // Initialize plugin with its specific configuration
-        if let Some(plugin_config) = self.config.plugin_configs.get(&plugin_name) {
-            plugin.initialize(plugin_config)?;
-        } else {
-            plugin.initialize(&serde_json::Value::Null)?;
-        }
+        let plugin_config = self
+            .config
+            .plugin_configs
+            .get(&plugin_name)
+            .unwrap_or(&serde_json::Value::Null);
+        plugin.initialize(plugin_config).map_err(|e| match e {
+            PluginError::ConfigurationError(msg) => {
+                PluginError::ConfigurationError(format!("[plugins.config.{}] {}", plugin_name, msg))
+            }
+            e => e,
+        })?;
         
         // Update execution order for supported phases
         let supported_phases = plugin.info().supported_phases.clone();
         for phase in supported_phases {
             self.execution_order
                 .entry(phase)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(plugin_name.clone());
         }
         
@@ -143,64 +250,271 @@ impl PluginManager {
     }
     
     /// Execute all plugins for a specific phase.
+    ///
+    /// Async plugins can't run here and fail; phases with async plugins run
+    /// with [`PluginManager::execute_phase_async`].
     pub fn execute_phase(
         &mut self, 
         phase: PluginPhase, 
         context: &PluginContext
     ) -> Result<PluginOutput, PluginError> {
-        let start_time = std::time::Instant::now();
+        self.run_phase(phase, |plugin| plugin.execute(phase, context))
+    }
+    
+    /// Execute the plugins of the `PostFile` phase on an extracted file, then
+    /// those of the `PostElement` phase on each of its elements.
+    ///
+    /// `context` is the context of the project; plugins get the file or
+    /// element they annotate separately.
+    pub fn execute_file_phases(
+        &mut self,
+        file: &mut FileAst,
+        context: &PluginContext,
+    ) -> Result<PluginOutput, PluginError> {
+        let mut output = self.run_phase(PluginPhase::PostFile, |plugin| {
+            plugin.post_file(file, context)
+        })?;
+        let element_output = self.run_phase(PluginPhase::PostElement, |plugin| {
+            let mut output = PluginOutput::new();
+            for element in &mut file.elements {
+                output.merge(plugin.post_element(element, context)?);
+            }
+            Ok(output)
+        })?;
+        output.merge(element_output);
+        Ok(output)
+    }
+    
+    /// Execute all plugins for a phase, running the plugins of each
+    /// dependency level concurrently, at most `max_concurrent` at a time.
+    ///
+    /// Async plugins stop after their timeout, and fail with
+    /// [`PluginError::Timeout`]. Sync plugins run as in
+    /// [`PluginManager::execute_phase`], except that their
+    /// [blocking jobs](Plugin::blocking_job) run on blocking threads.
+    pub async fn execute_phase_async(
+        &mut self,
+        phase: PluginPhase,
+        context: &PluginContext<'_>,
+    ) -> Result<PluginOutput, PluginError> {
+        let start_time = Instant::now();
         
         debug!("Executing plugins for phase: {:?}", phase);
         
         let mut combined_output = PluginOutput::new();
-        let plugin_names = self.get_enabled_plugins_for_phase(phase);
+        let (levels, unresolved) = self.execution_levels(phase);
         
-        if plugin_names.is_empty() {
+        if levels.is_empty() && unresolved.is_empty() {
             debug!("No plugins enabled for phase: {:?}", phase);
             return Ok(combined_output);
         }
         
-        let mut executed = 0;
-        let mut failed = 0;
+        let mut tally = PhaseTally::default();
+        for (plugin_name, e) in unresolved {
+            let measurement = Measurement::default();
+            self.record(
+                &plugin_name,
+                Err(e),
+                measurement,
+                &mut combined_output,
+                &mut tally,
+            )?;
+        }
         
-        for plugin_name in plugin_names {
-            if let Some(plugin) = self.plugins.get(&plugin_name) {
-                let plugin_start = std::time::Instant::now();
+        for level in levels {
+            for batch in level.chunks(self.config.max_concurrent.max(1)) {
+                let runs = batch
+                    .iter()
+                    .map(|name| self.execute_plugin_async(name, phase, context));
+                let results = futures::future::join_all(runs).await;
                 
-                match plugin.execute(phase, context) {
-                    Ok(output) => {
-                        debug!("Plugin '{}' executed successfully", plugin_name);
-                        combined_output.merge(output);
-                        executed += 1;
-                        
-                        // Record execution time
-                        let elapsed = plugin_start.elapsed().as_millis() as u64;
-                        self.stats.execution_times
-                            .entry(plugin_name.clone())
-                            .and_modify(|time| *time += elapsed)
-                            .or_insert(elapsed);
-                    }
-                    Err(e) => {
-                        failed += 1;
-                        error!("Plugin '{}' failed: {}", plugin_name, e);
-                        
-                        if !self.config.continue_on_error {
-                            return Err(e);
-                        }
-                        
-                        // Add error message to output
-                        combined_output.add_message(
-                            super::plugin::MessageLevel::Error,
-                            format!("Plugin '{}' failed: {}", plugin_name, e)
-                        );
-                    }
+                for (plugin_name, (result, measurement)) in batch.iter().zip(results) {
+                    let output = &mut combined_output;
+                    self.record(plugin_name, result, measurement, output, &mut tally)?;
                 }
-            } else {
-                warn!("Plugin '{}' not found in registry", plugin_name);
             }
         }
         
-        // Update statistics
+        self.finish_phase(phase, tally, start_time);
+        Ok(combined_output)
+    }
+    
+    /// Execute one plugin of a phase, within its timeout if it's async,
+    /// returning its result and measurement.
+    async fn execute_plugin_async(
+        &self,
+        plugin_name: &str,
+        phase: PluginPhase,
+        context: &PluginContext<'_>,
+    ) -> (Result<PluginOutput, PluginError>, Measurement) {
+        let probe = Probe::start();
+        let result = match self.plugins.get(plugin_name) {
+            Some(RegisteredPlugin::Sync(plugin)) => match plugin.blocking_job(phase, context) {
+                // Blocking jobs run on tokio's blocking threads, so those of
+                // a batch run concurrently.
+                Ok(Some(job)) => tokio::task::spawn_blocking(job).await.unwrap_or_else(|e| {
+                    Err(PluginError::ProcessingFailed(format!(
+                        "'{}' panicked: {}",
+                        plugin_name, e
+                    )))
+                }),
+                Ok(None) => plugin.execute(phase, context),
+                Err(e) => Err(e),
+            },
+            Some(RegisteredPlugin::Async(plugin)) => {
+                match self.timeout(plugin_name, plugin.as_ref()) {
+                    Some(timeout) => tokio::time::timeout(timeout, plugin.execute(phase, context))
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(PluginError::Timeout(format!(
+                                "'{}' took longer than {:?}",
+                                plugin_name, timeout
+                            )))
+                        }),
+                    None => plugin.execute(phase, context).await,
+                }
+            }
+            None => Err(PluginError::ConfigurationError(format!(
+                "Plugin '{}' is not registered",
+                plugin_name
+            ))),
+        };
+        (result, probe.finish())
+    }
+    
+    /// Timeout of an async plugin: the one configured for it, else its own,
+    /// else the default one.
+    fn timeout(&self, plugin_name: &str, plugin: &dyn AsyncPlugin) -> Option<Duration> {
+        self.config
+            .plugin_timeouts
+            .get(plugin_name)
+            .copied()
+            .or_else(|| plugin.timeout())
+            .or(self.config.default_timeout)
+    }
+    
+    /// Run `run` for each enabled sync plugin of `phase` in dependency
+    /// order, combining their outputs and recording statistics.
+    fn run_phase<F>(&mut self, phase: PluginPhase, mut run: F) -> Result<PluginOutput, PluginError>
+    where
+        F: FnMut(&dyn Plugin) -> Result<PluginOutput, PluginError>,
+    {
+        let start_time = Instant::now();
+        
+        debug!("Executing plugins for phase: {:?}", phase);
+        
+        let mut combined_output = PluginOutput::new();
+        let (levels, unresolved) = self.execution_levels(phase);
+        
+        if levels.is_empty() && unresolved.is_empty() {
+            debug!("No plugins enabled for phase: {:?}", phase);
+            return Ok(combined_output);
+        }
+        
+        let mut tally = PhaseTally::default();
+        for (plugin_name, e) in unresolved {
+            let measurement = Measurement::default();
+            self.record(
+                &plugin_name,
+                Err(e),
+                measurement,
+                &mut combined_output,
+                &mut tally,
+            )?;
+        }
+        
+        for plugin_name in levels.into_iter().flatten() {
+            let probe = Probe::start();
+            let result = match self.plugins.get(&plugin_name) {
+                Some(RegisteredPlugin::Sync(plugin)) => run(plugin.as_ref()),
+                Some(RegisteredPlugin::Async(_)) => Err(PluginError::InvalidContext(format!(
+                    "'{}' is async, run phase {:?} with execute_phase_async",
+                    plugin_name, phase
+                ))),
+                None => {
+                    warn!("Plugin '{}' not found in registry", plugin_name);
+                    continue;
+                }
+            };
+            let measurement = probe.finish();
+            self.record(
+                &plugin_name,
+                result,
+                measurement,
+                &mut combined_output,
+                &mut tally,
+            )?;
+        }
+        
+        self.finish_phase(phase, tally, start_time);
+        Ok(combined_output)
+    }
+    
+    /// Record the result of a plugin in a phase: its output, or its failure
+    /// unless `continue_on_error` is off, and its profile.
+    fn record(
+        &mut self,
+        plugin_name: &str,
+        result: Result<PluginOutput, PluginError>,
+        measurement: Measurement,
+        combined_output: &mut PluginOutput,
+        tally: &mut PhaseTally,
+    ) -> Result<(), PluginError> {
+        let output_bytes = result
+            .as_ref()
+            .ok()
+            .map(|output| serde_json::to_vec(output).map_or(0, |json| json.len()));
+        self.stats
+            .profiles
+            .entry(plugin_name.to_string())
+            .or_insert_with(|| PluginProfile {
+                name: plugin_name.to_string(),
+                ..PluginProfile::default()
+            })
+            .add(measurement, output_bytes);
+        
+        match result {
+            Ok(output) => {
+                debug!("Plugin '{}' executed successfully", plugin_name);
+                self.outputs
+                    .entry(plugin_name.to_string())
+                    .or_default()
+                    .merge(output.clone());
+                combined_output.merge(output);
+                tally.executed += 1;
+                
+                // Record execution time
+                let elapsed = measurement.elapsed.as_millis() as u64;
+                self.stats
+                    .execution_times
+                    .entry(plugin_name.to_string())
+                    .and_modify(|time| *time += elapsed)
+                    .or_insert(elapsed);
+            }
+            Err(e) => {
+                tally.failed += 1;
+                error!("Plugin '{}' failed: {}", plugin_name, e);
+                
+                if !self.config.continue_on_error {
+                    return Err(e);
+                }
+                
+                // Add error message to output
+                let message = format!("Plugin '{}' failed: {}", plugin_name, e);
+                self.outputs
+                    .entry(plugin_name.to_string())
+                    .or_default()
+                    .add_message(MessageLevel::Error, message.clone());
+                combined_output.add_message(MessageLevel::Error, message);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Update the statistics with the plugins executed in a phase.
+    fn finish_phase(&mut self, phase: PluginPhase, tally: PhaseTally, start_time: Instant) {
+        let PhaseTally { executed, failed } = tally;
+        
         self.stats.executions_per_phase
             .entry(phase)
             .and_modify(|count| *count += executed)
@@ -215,18 +529,179 @@ impl PluginManager {
         self.stats.total_failures += failed;
         
         let total_time = start_time.elapsed();
-        info!(
+        // File phases run once per file, so they're only logged when debugging.
+        let message = format!(
             "Phase {:?} completed: {} plugins executed, {} failed in {:?}",
             phase, executed, failed, total_time
         );
+        match phase {
+            PluginPhase::PostFile | PluginPhase::PostElement => debug!("{}", message),
+            _ => info!("{}", message),
+        }
+    }
+    
+    /// Run the enabled plugins on an extracted project: the `PostFile` and
+    /// `PostElement` phases on each of its files, then the `PostProject`
+    /// phase, and merge their outputs into the project.
+    pub async fn run_on_project(
+        &mut self,
+        project: &mut ProjectAst,
+        config: &ExtractorConfig,
+    ) -> Result<(), PluginError> {
+        let project_info = project.project.clone();
+        let mut metadata = HashMap::new();
+        for file in &mut project.files {
+            let context = PluginContext::new_pre_process(&project_info, config, &mut metadata);
+            self.execute_file_phases(file, &context)?;
+        }
+        let context =
+            PluginContext::new_project_process(&project_info, project, config, &mut metadata);
+        self.execute_phase_async(PluginPhase::PostProject, &context)
+            .await?;
+        self.merge_results(project);
+        Ok(())
+    }
+    
+    /// Merge the outputs of the plugins run since the last merge into
+    /// `project`, so every output format includes them.
+    ///
+    /// Elements the plugins output replace the elements of the same ID, and
+    /// the metadata, metrics and messages of each plugin go in the project's
+    /// `plugin_results` under the plugin's name.
+    pub fn merge_results(&mut self, project: &mut ProjectAst) {
+        for (name, output) in std::mem::take(&mut self.outputs) {
+            let PluginOutput {
+                modified_elements,
+                additional_metadata,
+                metrics,
+                messages,
+                ..
+            } = output;
+            
+            if !modified_elements.is_empty() {
+                let mut modified: HashMap<String, CodeElement> = modified_elements
+                    .into_iter()
+                    .map(|element| (element.id.clone(), element))
+                    .collect();
+                let elements = project.files.iter_mut().flat_map(|file| &mut file.elements);
+                for element in elements {
+                    if let Some(replacement) = modified.remove(&element.id) {
+                        *element = replacement;
+                    }
+                }
+                if !modified.is_empty() {
+                    warn!(
+                        "Plugin '{}' modified {} unknown elements",
+                        name,
+                        modified.len()
+                    );
+                }
+            }
+            
+            if additional_metadata.is_empty() && metrics.is_empty() && messages.is_empty() {
+                continue;
+            }
+            let results = project.plugin_results.entry(name).or_default();
+            results.metadata.extend(additional_metadata);
+            results.metrics.extend(metrics);
+            results
+                .messages
+                .extend(messages.into_iter().map(|message| PluginDiagnostic {
+                    level: message.level.as_str().to_string(),
+                    message: message.message,
+                    rule: message.rule,
+                    file: message.file,
+                    line: message.line,
+                }));
+        }
+    }
+    
+    /// Group the enabled plugins of a phase into levels, each running after
+    /// the plugins it depends on in earlier levels, in registration order.
+    ///
+    /// Plugins that can't run because a dependency is missing, disabled or
+    /// can't run itself are returned separately with their error.
+    fn execution_levels(
+        &self,
+        phase: PluginPhase,
+    ) -> (Vec<Vec<String>>, Vec<(String, PluginError)>) {
+        let plugin_names = self.get_enabled_plugins_for_phase(phase);
+        let mut unresolved = Vec::new();
+        let mut pending = Vec::new();
         
-        Ok(combined_output)
+        for plugin_name in &plugin_names {
+            let Some(plugin) = self.plugins.get(plugin_name) else {
+                warn!("Plugin '{}' not found in registry", plugin_name);
+                continue;
+            };
+            let dependencies = plugin.info().dependencies;
+            let missing = dependencies.iter().find(|dependency| {
+                !self.plugins.contains_key(*dependency) || !self.is_plugin_enabled(dependency)
+            });
+            match missing {
+                Some(dependency) => unresolved.push((
+                    plugin_name.clone(),
+                    PluginError::DependencyMissing(format!(
+                        "'{}' needs '{}', which isn't registered and enabled",
+                        plugin_name, dependency
+                    )),
+                )),
+                // Dependencies outside the phase ran in their own phases.
+                None => pending.push((
+                    plugin_name.clone(),
+                    dependencies
+                        .into_iter()
+                        .filter(|dependency| plugin_names.contains(dependency))
+                        .collect::<Vec<_>>(),
+                )),
+            }
+        }
+        
+        let mut levels = Vec::new();
+        let mut done = HashSet::new();
+        while !pending.is_empty() {
+            let (ready, waiting): (Vec<_>, Vec<_>) =
+                pending.into_iter().partition(|(_, dependencies)| {
+                    dependencies
+                        .iter()
+                        .all(|dependency| done.contains(dependency))
+                });
+            
+            if ready.is_empty() {
+                // The rest depend on each other, or on plugins that can't run.
+                unresolved.extend(waiting.into_iter().map(|(plugin_name, dependencies)| {
+                    let blocking: Vec<String> = dependencies
+                        .into_iter()
+                        .filter(|dependency| !done.contains(dependency))
+                        .collect();
+                    let error = PluginError::DependencyMissing(format!(
+                        "'{}' needs {}, which can't run first",
+                        plugin_name,
+                        blocking.join(", ")
+                    ));
+                    (plugin_name, error)
+                }));
+                break;
+            }
+            
+            let level: Vec<String> = ready
+                .into_iter()
+                .map(|(plugin_name, _)| plugin_name)
+                .collect();
+            done.extend(level.iter().cloned());
+            levels.push(level);
+            pending = waiting;
+        }
+        
+        (levels, unresolved)
     }
     
     /// Get list of enabled plugins for a specific phase.
     fn get_enabled_plugins_for_phase(&self, phase: PluginPhase) -> Vec<String> {
-        let phase_plugins = self.execution_order.get(&phase)
-            .map(|plugins| plugins.clone())
+        let phase_plugins = self
+            .execution_order
+            .get(&phase)
+            .cloned()
             .unwrap_or_default();
             
         phase_plugins.into_iter()
@@ -235,36 +710,55 @@ impl PluginManager {
     }
     
     /// Check if a plugin is enabled.
-    fn is_plugin_enabled(&self, plugin_name: &str) -> bool {
+    pub fn is_plugin_enabled(&self, plugin_name: &str) -> bool {
         // Disabled list takes precedence
         if self.config.disabled_plugins.contains(&plugin_name.to_string()) {
             return false;
         }
         
-        // If enabled list is empty, all plugins are enabled by default
-        if self.config.enabled_plugins.is_empty() {
+        if self.config.enabled_plugins.contains(&plugin_name.to_string()) {
             return true;
         }
         
-        // Check if plugin is in enabled list
-        self.config.enabled_plugins.contains(&plugin_name.to_string())
+        // Plugins in neither list keep their default
+        self.plugins
+            .get(plugin_name)
+            .is_some_and(|plugin| plugin.info().default_enabled)
     }
     
-    /// Get list of all registered plugins.
+    /// Get list of all registered plugins, sorted by name.
     pub fn list_plugins(&self) -> Vec<String> {
-        self.plugins.keys().cloned().collect()
+        let mut names: Vec<String> = self.plugins.keys().cloned().collect();
+        names.sort();
+        names
     }
     
     /// Get plugin information by name.
-    pub fn get_plugin_info(&self, plugin_name: &str) -> Option<super::plugin::PluginInfo> {
+    pub fn get_plugin_info(&self, plugin_name: &str) -> Option<PluginInfo> {
         self.plugins.get(plugin_name).map(|plugin| plugin.info())
     }
     
+    /// Get the JSON schema of a plugin's settings (if it has one).
+    pub fn get_config_schema(&self, plugin_name: &str) -> Option<serde_json::Value> {
+        self.plugins.get(plugin_name)?.config_schema()
+    }
+    
     /// Get plugin execution statistics.
     pub fn get_stats(&self) -> &PluginStats {
         &self.stats
     }
     
+    /// Get the execution profiles of the plugins run, slowest first.
+    pub fn get_profiles(&self) -> Vec<PluginProfile> {
+        let mut profiles: Vec<PluginProfile> = self.stats.profiles.values().cloned().collect();
+        profiles.sort_by(|a, b| {
+            b.wall_time_ms
+                .total_cmp(&a.wall_time_ms)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        profiles
+    }
+    
     /// Reset plugin execution statistics.
     pub fn reset_stats(&mut self) {
         self.stats = PluginStats::default();
@@ -318,7 +812,9 @@ mod tests {
     use super::*;
     use crate::core::plugin::{PluginInfo, PluginPhase};
     use rustex_core::{ProjectInfo, ExtractorConfig};
+    use rustex_core::test_fixtures::MockDataGenerator;
     use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
 
     struct TestPlugin {
         name: String,
@@ -365,6 +861,56 @@ mod tests {
         }
     }
 
+    /// Async plugin that sleeps, logging when it starts and ends.
+    struct SleepingPlugin {
+        name: String,
+        dependencies: Vec<String>,
+        phase: PluginPhase,
+        sleep: Duration,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl SleepingPlugin {
+        fn new(name: &str, dependencies: &[&str], log: &Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                name: name.to_string(),
+                dependencies: dependencies.iter().map(|name| name.to_string()).collect(),
+                phase: PluginPhase::PostProject,
+                sleep: Duration::from_millis(20),
+                log: log.clone(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl AsyncPlugin for SleepingPlugin {
+        fn info(&self) -> PluginInfo {
+            PluginInfo {
+                name: self.name.clone(),
+                version: "0.1.0".to_string(),
+                description: "Sleeping test plugin".to_string(),
+                author: None,
+                supported_phases: vec![self.phase],
+                dependencies: self.dependencies.clone(),
+                default_enabled: true,
+            }
+        }
+        
+        async fn execute(
+            &self,
+            _phase: PluginPhase,
+            _context: &PluginContext<'_>,
+        ) -> Result<PluginOutput, PluginError> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("start {}", self.name));
+            tokio::time::sleep(self.sleep).await;
+            self.log.lock().unwrap().push(format!("end {}", self.name));
+            Ok(PluginOutput::new())
+        }
+    }
+
     fn create_test_context() -> (ProjectInfo, ExtractorConfig, HashMap<String, serde_json::Value>) {
         let project_info = ProjectInfo {
             name: "test".to_string(),
@@ -433,6 +979,43 @@ mod tests {
         assert_eq!(stats.total_failures, 1);
     }
 
+    #[test]
+    fn test_plugin_profiles() {
+        let mut manager = PluginManager::new();
+        manager
+            .register_plugin(Box::new(TestPlugin::new("test")))
+            .unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin::with_failure("failing")))
+            .unwrap();
+
+        let (project_info, config, mut metadata) = create_test_context();
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        manager
+            .execute_phase(PluginPhase::PostProject, &context)
+            .unwrap();
+        manager
+            .execute_phase(PluginPhase::PostProject, &context)
+            .unwrap();
+
+        let profiles = manager.get_profiles();
+        assert_eq!(profiles.len(), 2);
+        let test = profiles
+            .iter()
+            .find(|profile| profile.name == "test")
+            .unwrap();
+        assert_eq!((test.executions, test.failures), (2, 0));
+        assert!(test.output_bytes > 0);
+        let failing = profiles
+            .iter()
+            .find(|profile| profile.name == "failing")
+            .unwrap();
+        assert_eq!(
+            (failing.executions, failing.failures, failing.output_bytes),
+            (2, 2, 0)
+        );
+    }
+
     #[test]
     fn test_plugin_enable_disable() {
         let mut manager = PluginManager::new();
@@ -451,4 +1034,190 @@ mod tests {
         manager.enable_plugin("test");
         assert!(manager.is_plugin_enabled("test"));
     }
+    #[test]
+    fn test_plugin_defaults_and_extractor_config() {
+        let config = ExtractorConfig {
+            plugins: vec!["doc-enhancer".to_string()].into(),
+            disabled_plugins: vec!["test".to_string()],
+            ..ExtractorConfig::default()
+        };
+        let mut manager = PluginManager::with_config(PluginManagerConfig::from(&config));
+        crate::builtin::register_builtin_plugins(&mut manager).unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin::new("test")))
+            .unwrap();
+
+        // Built-in plugins are disabled unless enabled in the config
+        assert!(manager.is_plugin_enabled("doc-enhancer"));
+        assert!(!manager.is_plugin_enabled("llm-optimizer"));
+        
+        // The config overrides plugins enabled by default
+        assert!(!manager.is_plugin_enabled("test"));
+        assert!(!manager.is_plugin_enabled("missing"));
+        
+        assert_eq!(manager.list_plugins()[0], "api-stability");
+    }
+
+    #[test]
+    fn test_file_phases() {
+        let config = PluginManagerConfig {
+            enabled_plugins: vec!["doc-enhancer".to_string()],
+            ..PluginManagerConfig::default()
+        };
+        let mut manager = PluginManager::with_config(config);
+        crate::builtin::register_builtin_plugins(&mut manager).unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin::new("test")))
+            .unwrap();
+
+        let (project_info, config, mut metadata) = create_test_context();
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        let mut file = MockDataGenerator::file_ast(PathBuf::from("/test/src/lib.rs"), 2);
+        
+        let result = manager.execute_file_phases(&mut file, &context).unwrap();
+        assert!(result.has_modifications);
+        assert!(file.elements.iter().all(|element| {
+            element.metadata.contains_key("doc_quality")
+                || element.metadata.contains_key("doc_suggestion")
+        }));
+        
+        // Only the plugins of the file phases run.
+        let stats = manager.get_stats();
+        assert_eq!(
+            stats.executions_per_phase.get(&PluginPhase::PostElement),
+            Some(&1)
+        );
+        assert_eq!(stats.total_successes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_async_plugins_concurrency_and_dependencies() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = PluginManager::new();
+        manager
+            .register_async_plugin(Box::new(SleepingPlugin::new("a", &[], &log)))
+            .unwrap();
+        manager
+            .register_async_plugin(Box::new(SleepingPlugin::new("b", &[], &log)))
+            .unwrap();
+        manager
+            .register_async_plugin(Box::new(SleepingPlugin::new("c", &["a"], &log)))
+            .unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin::new("test")))
+            .unwrap();
+
+        let (project_info, config, mut metadata) = create_test_context();
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        let output = manager
+            .execute_phase_async(PluginPhase::PostProject, &context)
+            .await
+            .unwrap();
+        assert_eq!(output.metrics.get("test_metric"), Some(&1.0));
+        assert_eq!(manager.get_stats().total_successes, 4);
+        
+        // Independent plugins overlap, and "c" waits for "a".
+        let log = log.lock().unwrap();
+        let position = |event: &str| log.iter().position(|entry| entry == event).unwrap();
+        assert!(position("start b") < position("end a"));
+        assert!(position("end a") < position("start c"));
+    }
+
+    #[tokio::test]
+    async fn test_async_plugin_limits() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut slow = SleepingPlugin::new("slow", &[], &log);
+        slow.sleep = Duration::from_secs(10);
+        let config = PluginManagerConfig {
+            plugin_timeouts: HashMap::from([("slow".to_string(), Duration::from_millis(20))]),
+            ..PluginManagerConfig::default()
+        };
+        let mut manager = PluginManager::with_config(config);
+        manager.register_async_plugin(Box::new(slow)).unwrap();
+        manager
+            .register_async_plugin(Box::new(SleepingPlugin::new("x", &["y"], &log)))
+            .unwrap();
+        manager
+            .register_async_plugin(Box::new(SleepingPlugin::new("y", &["x"], &log)))
+            .unwrap();
+        manager
+            .register_async_plugin(Box::new(SleepingPlugin::new("z", &["gone"], &log)))
+            .unwrap();
+
+        let (project_info, config, mut metadata) = create_test_context();
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        let output = manager
+            .execute_phase_async(PluginPhase::PostProject, &context)
+            .await
+            .unwrap();
+        let messages: Vec<&str> = output.messages.iter().map(|m| m.message.as_str()).collect();
+        assert_eq!(messages.len(), 4);
+        assert!(messages.iter().any(|message| message.contains("timed out")));
+        assert!(messages
+            .iter()
+            .any(|message| message.contains("'z' needs 'gone'")));
+        assert!(messages
+            .iter()
+            .any(|message| message.contains("'x' needs y")));
+
+        // The sync path doesn't run async plugins.
+        let output = manager
+            .execute_phase(PluginPhase::PostProject, &context)
+            .unwrap();
+        assert!(output
+            .messages
+            .iter()
+            .any(|m| m.message.contains("is async")));
+
+        // Async plugins can't run on each file.
+        let mut element_plugin = SleepingPlugin::new("element", &[], &log);
+        element_plugin.phase = PluginPhase::PostElement;
+        assert!(manager
+            .register_async_plugin(Box::new(element_plugin))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_on_project() {
+        let config = ExtractorConfig {
+            plugins: vec!["doc-enhancer".to_string()].into(),
+            ..ExtractorConfig::default()
+        };
+        let mut manager = PluginManager::with_config(PluginManagerConfig::from(&config));
+        crate::builtin::register_builtin_plugins(&mut manager).unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin::new("test")))
+            .unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin::with_failure("failing")))
+            .unwrap();
+
+        let mut project = MockDataGenerator::project_ast(2, 2);
+        manager.run_on_project(&mut project, &config).await.unwrap();
+        
+        // Element annotations stay on the elements, and project-level output
+        // goes in the results of each plugin.
+        let elements = project.files.iter().flat_map(|file| &file.elements);
+        assert!(elements.into_iter().all(|element| {
+            element.metadata.contains_key("doc_quality")
+                || element.metadata.contains_key("doc_suggestion")
+        }));
+        let results = &project.plugin_results;
+        assert_eq!(results["test"].metrics.get("test_metric"), Some(&1.0));
+        assert!(results["doc-enhancer"]
+            .metadata
+            .contains_key("documentation_analysis"));
+        assert_eq!(results["failing"].messages[0].level, "error");
+        assert!(!results.contains_key("llm-optimizer"));
+        
+        // Outputs are merged once.
+        manager.merge_results(&mut project);
+        assert_eq!(project.plugin_results["failing"].messages.len(), 1);
+        
+        let json = serde_json::to_value(&project).unwrap();
+        assert_eq!(
+            json["plugin_results"]["test"]["metrics"]["test_metric"],
+            1.0
+        );
+    }
 }
\ No newline at end of file
@@ -4,12 +4,16 @@
 //! for the plugin system architecture.
 
 pub mod plugin;
+pub mod async_plugin;
 pub mod manager;
 pub mod context;
 pub mod errors;
+pub mod profile;
 
 // Re-export core types
-pub use plugin::{Plugin, PluginPhase, PluginInfo, PluginOutput};
+pub use plugin::{BlockingJob, Plugin, PluginPhase, PluginInfo, PluginOutput};
+pub use async_plugin::AsyncPlugin;
 pub use manager::PluginManager;
 pub use context::{PluginContext, PreProcessContext, PostProcessContext};
-pub use errors::PluginError;
\ No newline at end of file
+pub use errors::PluginError;
+pub use profile::{CountingAllocator, PluginProfile};
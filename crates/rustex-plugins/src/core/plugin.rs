@@ -1,8 +1,9 @@
 //! Core plugin trait and related types.
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
-use rustex_core::CodeElement;
+use rustex_core::{CodeElement, FileAst};
 use super::errors::{PluginError, PluginResult};
 use super::context::PluginContext;
 
@@ -12,8 +13,14 @@ pub enum PluginPhase {
     /// Before file discovery and processing begins
     PreProcess,
     
-    /// After a single file's AST is extracted but before project assembly
-    PostFileExtract,
+    /// After a single file's AST is extracted but before project assembly,
+    /// with mutable access to the file
+    #[serde(alias = "PostFileExtract")]
+    PostFile,
+    
+    /// After a single file's AST is extracted, once for each of its
+    /// elements, with mutable access to the element
+    PostElement,
     
     /// After all files are processed and project AST is assembled
     PostProject,
@@ -30,7 +37,8 @@ impl PluginPhase {
     pub fn all() -> Vec<PluginPhase> {
         vec![
             PluginPhase::PreProcess,
-            PluginPhase::PostFileExtract,
+            PluginPhase::PostFile,
+            PluginPhase::PostElement,
             PluginPhase::PostProject,
             PluginPhase::PreFormat,
             PluginPhase::PostFormat,
@@ -41,7 +49,8 @@ impl PluginPhase {
     pub fn description(&self) -> &'static str {
         match self {
             PluginPhase::PreProcess => "Before file discovery and processing",
-            PluginPhase::PostFileExtract => "After individual file extraction",
+            PluginPhase::PostFile => "After individual file extraction",
+            PluginPhase::PostElement => "For each element of an extracted file",
             PluginPhase::PostProject => "After project assembly",
             PluginPhase::PreFormat => "Before output formatting",
             PluginPhase::PostFormat => "After output formatting",
@@ -51,6 +60,7 @@ impl PluginPhase {
 
 /// Output produced by a plugin during execution.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PluginOutput {
     /// Modified or additional code elements
     pub modified_elements: Vec<CodeElement>,
@@ -101,7 +111,32 @@ impl PluginOutput {
     
     /// Add a log message to the output.
     pub fn add_message(&mut self, level: MessageLevel, message: String) {
-        self.messages.push(PluginMessage { level, message });
+        self.messages.push(PluginMessage {
+            level,
+            message,
+            rule: None,
+            file: None,
+            line: None,
+        });
+    }
+    
+    /// Add a diagnostic: a message reporting a violation of `rule` at a line
+    /// of a file, relative to the project root.
+    pub fn add_diagnostic(
+        &mut self,
+        level: MessageLevel,
+        rule: &str,
+        message: String,
+        file: &Path,
+        line: usize,
+    ) {
+        self.messages.push(PluginMessage {
+            level,
+            message,
+            rule: Some(rule.to_string()),
+            file: Some(file.to_path_buf()),
+            line: Some(line),
+        });
     }
     
     /// Merge another plugin output into this one.
@@ -119,6 +154,18 @@ impl PluginOutput {
 pub struct PluginMessage {
     pub level: MessageLevel,
     pub message: String,
+    
+    /// Rule a diagnostic reports a violation of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    
+    /// File of a diagnostic, relative to the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    
+    /// Line of a diagnostic (1-based)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
 }
 
 /// Message severity levels.
@@ -130,6 +177,18 @@ pub enum MessageLevel {
     Error,
 }
 
+impl MessageLevel {
+    /// Lowercase name of the level.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageLevel::Debug => "debug",
+            MessageLevel::Info => "info",
+            MessageLevel::Warning => "warning",
+            MessageLevel::Error => "error",
+        }
+    }
+}
+
 /// Plugin metadata and information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginInfo {
@@ -155,6 +214,9 @@ pub struct PluginInfo {
     pub default_enabled: bool,
 }
 
+/// A plugin phase run apart from its context, see [`Plugin::blocking_job`].
+pub type BlockingJob = Box<dyn FnOnce() -> PluginResult<PluginOutput> + Send>;
+
 /// Core plugin trait that all plugins must implement.
 pub trait Plugin: Send + Sync {
     /// Get plugin information.
@@ -181,12 +243,22 @@ pub trait Plugin: Send + Sync {
         Ok(())
     }
     
+    /// JSON schema of the plugin's settings in `[plugins.config.<name>]`,
+    /// which `rustex config validate` checks them against.
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+    
     /// Check if the plugin supports a specific phase.
     fn supports_phase(&self, phase: PluginPhase) -> bool {
         self.info().supported_phases.contains(&phase)
     }
     
     /// Execute the plugin for a specific phase.
+    ///
+    /// The `PostFile` and `PostElement` phases run on a file or element
+    /// rather than a context, see [`Plugin::post_file`] and
+    /// [`Plugin::post_element`].
     fn execute(&self, phase: PluginPhase, context: &PluginContext) -> PluginResult<PluginOutput> {
         if !self.supports_phase(phase) {
             return Err(PluginError::InvalidContext(
@@ -196,13 +268,31 @@ pub trait Plugin: Send + Sync {
         
         match phase {
             PluginPhase::PreProcess => self.pre_process(context),
-            PluginPhase::PostFileExtract => self.post_file_extract(context),
+            PluginPhase::PostFile | PluginPhase::PostElement => Err(PluginError::InvalidContext(
+                format!("Phase {:?} runs on a file or element, not a context", phase),
+            )),
             PluginPhase::PostProject => self.post_project(context),
             PluginPhase::PreFormat => self.pre_format(context),
             PluginPhase::PostFormat => self.post_format(context),
         }
     }
     
+    /// A job running the plugin for `phase` without borrowing `context`,
+    /// for plugins that block on I/O, such as external processes.
+    ///
+    /// [`PluginManager::execute_phase_async`](super::PluginManager::execute_phase_async)
+    /// runs jobs on tokio's blocking threads, so they run concurrently.
+    /// Without a job, which is the default, the plugin is executed on the
+    /// calling task.
+    fn blocking_job(
+        &self,
+        phase: PluginPhase,
+        context: &PluginContext,
+    ) -> PluginResult<Option<BlockingJob>> {
+        let _ = (phase, context);
+        Ok(None)
+    }
+    
     // Phase-specific hooks (default implementations do nothing)
     
     /// Execute during pre-processing phase.
@@ -210,8 +300,22 @@ pub trait Plugin: Send + Sync {
         Ok(PluginOutput::new())
     }
     
-    /// Execute after file extraction phase.
-    fn post_file_extract(&self, _context: &PluginContext) -> PluginResult<PluginOutput> {
+    /// Execute after a file's extraction, with the file to annotate.
+    fn post_file(
+        &self,
+        _file: &mut FileAst,
+        _context: &PluginContext,
+    ) -> PluginResult<PluginOutput> {
+        Ok(PluginOutput::new())
+    }
+    
+    /// Execute after a file's extraction for each of its elements, with the
+    /// element to annotate.
+    fn post_element(
+        &self,
+        _element: &mut CodeElement,
+        _context: &PluginContext,
+    ) -> PluginResult<PluginOutput> {
         Ok(PluginOutput::new())
     }
     
@@ -287,7 +391,7 @@ mod tests {
     #[test]
     fn test_plugin_phases() {
         let phases = PluginPhase::all();
-        assert_eq!(phases.len(), 5);
+        assert_eq!(phases.len(), 6);
         assert!(phases.contains(&PluginPhase::PreProcess));
         assert!(phases.contains(&PluginPhase::PostProject));
     }
@@ -0,0 +1,149 @@
+//! Plugin execution profiling: time, allocations and output size per plugin.
+
+use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator counting the allocations of the process, which plugin
+/// profiles attribute to the plugins running while they happen.
+///
+/// Binaries install it with `#[global_allocator]` and call
+/// [`CountingAllocator::enable`] when profiles are wanted; until then it only
+/// forwards to the system allocator, and profiles report no allocations.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    /// Start counting allocations.
+    pub fn enable() {
+        COUNTING.store(true, Ordering::Relaxed);
+    }
+
+    fn count(bytes: usize) {
+        if COUNTING.load(Ordering::Relaxed) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Measures the time and allocations of one plugin execution.
+pub(crate) struct Probe {
+    start: Instant,
+    allocations: u64,
+    allocated_bytes: u64,
+}
+
+/// Time and allocations of one plugin execution.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Measurement {
+    pub elapsed: Duration,
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+}
+
+impl Probe {
+    pub fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Measure since the start; allocations by other threads or concurrent
+    /// plugins in the meantime count too.
+    pub fn finish(&self) -> Measurement {
+        Measurement {
+            elapsed: self.start.elapsed(),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed) - self.allocations,
+            allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - self.allocated_bytes,
+        }
+    }
+}
+
+/// Execution profile of a plugin, summed over its executions.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginProfile {
+    /// Plugin name
+    pub name: String,
+
+    /// Number of executions, one per phase, or per file in the file phases
+    pub executions: usize,
+
+    /// Number of failed executions
+    pub failures: usize,
+
+    /// Wall time of the executions, in milliseconds
+    pub wall_time_ms: f64,
+
+    /// Approximate number of allocations, see [`CountingAllocator`]
+    pub allocations: u64,
+
+    /// Approximate number of bytes allocated
+    pub allocated_bytes: u64,
+
+    /// Size of the plugin's outputs as JSON, in bytes
+    pub output_bytes: usize,
+}
+
+impl PluginProfile {
+    pub(crate) fn add(&mut self, measurement: Measurement, output_bytes: Option<usize>) {
+        self.executions += 1;
+        self.wall_time_ms += measurement.elapsed.as_secs_f64() * 1000.0;
+        self.allocations += measurement.allocations;
+        self.allocated_bytes += measurement.allocated_bytes;
+        match output_bytes {
+            Some(bytes) => self.output_bytes += bytes,
+            None => self.failures += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn test_probe_counts_allocations() {
+        CountingAllocator::enable();
+        let probe = Probe::start();
+        let buffer = std::hint::black_box(vec![0u8; 4096]);
+        let measurement = probe.finish();
+        drop(buffer);
+
+        assert!(measurement.allocations >= 1);
+        assert!(measurement.allocated_bytes >= 4096);
+
+        let mut profile = PluginProfile::default();
+        profile.add(measurement, Some(10));
+        profile.add(Measurement::default(), None);
+        assert_eq!(
+            (profile.executions, profile.failures, profile.output_bytes),
+            (2, 1, 10)
+        );
+    }
+}
@@ -0,0 +1,644 @@
+//! External plugins, shipped by third parties without rebuilding rustex.
+//!
+//! An external plugin is a directory with a `rustex-plugin.toml` manifest
+//! declaring the plugin and the command that runs it:
+//!
+//! ```toml
+//! name = "todo-finder"
+//! version = "0.1.0"
+//! description = "Reports functions with TODO comments"
+//! api_version = 1
+//! phases = ["PostProject"]
+//! command = "python3"
+//! args = ["todo_finder.py"]
+//! ```
+//!
+//! The command runs in the plugin's directory once per phase, with the phase
+//! as its last argument. It reads a [`PluginRequest`] as JSON from stdin and
+//! writes a [`PluginOutput`] as JSON to stdout; fields it leaves out of the
+//! output are empty. Exiting with a non-zero status fails the plugin, with its
+//! stderr as the error.
+//!
+//! In the `PostFile` and `PostElement` phases, the command runs once per file
+//! or element, and the elements it outputs replace those of the same ID.
+//!
+//! The plugin's settings in `[plugins.config.<name>]` are sent as the
+//! request's `plugin_config`. A manifest can name a JSON schema file for them
+//! as `config_schema`, which `rustex config validate` checks them against.
+//!
+//! The JSON of requests and outputs is versioned by [`PLUGIN_API_VERSION`].
+//! Plugins declare the version they were written for as `api_version`, and
+//! plugins for another version aren't loaded, since they may misread
+//! requests.
+//!
+//! `rustex plugins install <DIR>` copies a plugin directory to the
+//! [plugin directory](rustex_core::plugin_dir), where
+//! [`register_installed_plugins`] loads it from.
+
+use crate::core::errors::PluginResult;
+use crate::core::{
+    BlockingJob, Plugin, PluginContext, PluginError, PluginInfo, PluginManager, PluginOutput,
+    PluginPhase,
+};
+use rustex_core::{
+    CodeElement, ExtractorConfig, FileAst, ProjectAst, ProjectInfo, PLUGIN_MANIFEST,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{debug, warn};
+
+/// Version of the JSON protocol between rustex and external plugins.
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// The manifest of an external plugin, `rustex-plugin.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// Plugin name, also the name of its installed directory
+    pub name: String,
+
+    /// Plugin version
+    pub version: String,
+
+    /// Plugin description
+    #[serde(default)]
+    pub description: String,
+
+    /// Plugin author
+    #[serde(default)]
+    pub author: Option<String>,
+
+    /// Version of the plugin protocol the plugin was written for
+    pub api_version: u32,
+
+    /// Phases the plugin runs in
+    pub phases: Vec<PluginPhase>,
+
+    /// Program running the plugin: a path relative to the plugin's directory,
+    /// or a program on the `PATH`
+    pub command: String,
+
+    /// Arguments of the program, before the phase
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Plugin dependencies
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+
+    /// Whether the plugin is enabled by default
+    #[serde(default)]
+    pub default_enabled: bool,
+
+    /// JSON schema file of the plugin's settings, relative to the plugin's
+    /// directory
+    #[serde(default)]
+    pub config_schema: Option<PathBuf>,
+}
+
+impl PluginManifest {
+    /// Read the manifest of the plugin in `dir`.
+    pub fn load(dir: &Path) -> PluginResult<Self> {
+        let path = dir.join(PLUGIN_MANIFEST);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| PluginError::IoError(format!("{}: {}", path.display(), e)))?;
+        let manifest: Self = toml::from_str(&content)
+            .map_err(|e| PluginError::ConfigurationError(format!("{}: {}", path.display(), e)))?;
+        if manifest.api_version != PLUGIN_API_VERSION {
+            return Err(PluginError::VersionIncompatible {
+                required: format!("plugin API version {}", PLUGIN_API_VERSION),
+                found: format!("{} for API version {}", manifest.name, manifest.api_version),
+            });
+        }
+        Ok(manifest)
+    }
+}
+
+/// The request an external plugin reads from stdin.
+#[derive(Debug, Serialize)]
+pub struct PluginRequest<'a> {
+    /// Version of the plugin protocol, [`PLUGIN_API_VERSION`]
+    pub api_version: u32,
+
+    /// Phase the plugin runs in
+    pub phase: PluginPhase,
+
+    /// The plugin's settings, `null` without any
+    pub plugin_config: &'a serde_json::Value,
+
+    /// Project information
+    pub project_info: &'a ProjectInfo,
+
+    /// Extractor configuration
+    pub config: &'a ExtractorConfig,
+
+    /// File being processed, in the `PostFile` phase
+    pub current_file: Option<&'a FileAst>,
+
+    /// Element being processed, in the `PostElement` phase
+    pub current_element: Option<&'a CodeElement>,
+
+    /// Project AST, from the `PostProject` phase on
+    pub project_ast: Option<&'a ProjectAst>,
+
+    /// Metadata of the plugins run so far
+    pub metadata: &'a HashMap<String, serde_json::Value>,
+}
+
+/// A plugin run by a program declared in a manifest.
+#[derive(Debug)]
+pub struct ExternalPlugin {
+    manifest: PluginManifest,
+    /// Directory of the plugin, where its command runs
+    dir: PathBuf,
+    /// Settings passed to the plugin at initialization
+    config: serde_json::Value,
+}
+
+impl ExternalPlugin {
+    /// Load the plugin in `dir`.
+    pub fn load(dir: &Path) -> PluginResult<Self> {
+        Ok(Self {
+            manifest: PluginManifest::load(dir)?,
+            dir: dir.to_path_buf(),
+            config: serde_json::Value::Null,
+        })
+    }
+
+    /// The plugin's manifest.
+    pub fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    /// Directory of the plugin.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Run the plugin's command for `phase`, sending it `context` and the
+    /// file or element it runs on.
+    fn run(
+        &self,
+        phase: PluginPhase,
+        context: &PluginContext,
+        file: Option<&FileAst>,
+        element: Option<&CodeElement>,
+    ) -> PluginResult<PluginOutput> {
+        let request = self.request(phase, context, file, element)?;
+        self.process(phase).run(request)
+    }
+
+    /// The JSON request of `phase`, with `context` and the file or element
+    /// the plugin runs on.
+    fn request(
+        &self,
+        phase: PluginPhase,
+        context: &PluginContext,
+        file: Option<&FileAst>,
+        element: Option<&CodeElement>,
+    ) -> PluginResult<Vec<u8>> {
+        let request = PluginRequest {
+            api_version: PLUGIN_API_VERSION,
+            phase,
+            plugin_config: &self.config,
+            project_info: context.project_info,
+            config: context.config,
+            current_file: file.or(context.current_file()),
+            current_element: element,
+            project_ast: context.project_ast(),
+            metadata: &*context.metadata,
+        };
+        Ok(serde_json::to_vec(&request)?)
+    }
+
+    /// The process running the plugin's command for `phase`.
+    fn process(&self, phase: PluginPhase) -> PluginProcess {
+        // Commands in the plugin's directory win over those on the `PATH`.
+        let local = self.dir.join(&self.manifest.command);
+        let program = if local.is_file() {
+            local.into_os_string()
+        } else {
+            self.manifest.command.clone().into()
+        };
+        PluginProcess {
+            name: self.manifest.name.clone(),
+            program,
+            args: self.manifest.args.clone(),
+            phase,
+            dir: self.dir.clone(),
+        }
+    }
+}
+
+/// The command of an external plugin for a phase, owning what it needs to
+/// run on another thread.
+struct PluginProcess {
+    name: String,
+    program: OsString,
+    args: Vec<String>,
+    phase: PluginPhase,
+    dir: PathBuf,
+}
+
+impl PluginProcess {
+    /// Run the command, writing `request` to its stdin and reading its
+    /// output from its stdout.
+    fn run(self, request: Vec<u8>) -> PluginResult<PluginOutput> {
+        debug!("Running plugin '{}': {:?}", self.name, self.program);
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .arg(format!("{:?}", self.phase))
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                PluginError::InitializationFailed(format!(
+                    "failed to run {:?} for plugin '{}': {}",
+                    self.program, self.name, e
+                ))
+            })?;
+        // The request is written while the output is read, so plugins
+        // answering before they read all of it don't block on a full pipe.
+        let writer = child
+            .stdin
+            .take()
+            .map(|mut stdin| std::thread::spawn(move || stdin.write_all(&request)));
+        let output = child.wait_with_output()?;
+        if let Some(Ok(Err(e))) = writer.map(|writer| writer.join()) {
+            // Plugins may exit without reading their request.
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+        if !output.status.success() {
+            return Err(PluginError::ProcessingFailed(format!(
+                "plugin '{}' exited with {}: {}",
+                self.name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        if output.stdout.iter().all(u8::is_ascii_whitespace) {
+            return Ok(PluginOutput::new());
+        }
+        serde_json::from_slice(&output.stdout).map_err(|e| {
+            PluginError::SerializationError(format!(
+                "invalid output of plugin '{}': {}",
+                self.name, e
+            ))
+        })
+    }
+}
+
+impl Plugin for ExternalPlugin {
+    fn info(&self) -> PluginInfo {
+        let manifest = &self.manifest;
+        PluginInfo {
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            description: manifest.description.clone(),
+            author: manifest.author.clone(),
+            supported_phases: manifest.phases.clone(),
+            dependencies: manifest.dependencies.clone(),
+            default_enabled: manifest.default_enabled,
+        }
+    }
+
+    fn initialize(&mut self, config: &serde_json::Value) -> PluginResult<()> {
+        self.config = config.clone();
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        let path = self.dir.join(self.manifest.config_schema.as_ref()?);
+        match std::fs::read_to_string(&path).map(|content| serde_json::from_str(&content)) {
+            Ok(Ok(schema)) => Some(schema),
+            Ok(Err(e)) => {
+                warn!("Invalid config schema {}: {}", path.display(), e);
+                None
+            }
+            Err(e) => {
+                warn!("Cannot read config schema {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn execute(&self, phase: PluginPhase, context: &PluginContext) -> PluginResult<PluginOutput> {
+        if !self.supports_phase(phase) {
+            return Err(PluginError::InvalidContext(format!(
+                "Plugin {} does not support phase {:?}",
+                self.manifest.name, phase
+            )));
+        }
+        self.run(phase, context, None, None)
+    }
+
+    fn blocking_job(
+        &self,
+        phase: PluginPhase,
+        context: &PluginContext,
+    ) -> PluginResult<Option<BlockingJob>> {
+        if !self.supports_phase(phase) {
+            return Ok(None);
+        }
+        let request = self.request(phase, context, None, None)?;
+        let process = self.process(phase);
+        Ok(Some(Box::new(move || process.run(request))))
+    }
+
+    fn post_file(&self, file: &mut FileAst, context: &PluginContext) -> PluginResult<PluginOutput> {
+        let output = self.run(PluginPhase::PostFile, context, Some(file), None)?;
+        replace_elements(&mut file.elements, &output.modified_elements);
+        Ok(output)
+    }
+
+    fn post_element(
+        &self,
+        element: &mut CodeElement,
+        context: &PluginContext,
+    ) -> PluginResult<PluginOutput> {
+        let output = self.run(PluginPhase::PostElement, context, None, Some(element))?;
+        replace_elements(std::slice::from_mut(element), &output.modified_elements);
+        Ok(output)
+    }
+}
+
+/// Replace the elements of `elements` with the `modified` elements of the
+/// same ID, which is how external plugins annotate files and elements.
+fn replace_elements(elements: &mut [CodeElement], modified: &[CodeElement]) {
+    for element in modified {
+        if let Some(target) = elements.iter_mut().find(|target| target.id == element.id) {
+            *target = element.clone();
+        }
+    }
+}
+
+/// Load the plugins installed in `dir`, one per subdirectory with a manifest,
+/// in the order of their names.
+pub fn installed_plugins(dir: &Path) -> Vec<PluginResult<ExternalPlugin>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join(PLUGIN_MANIFEST).is_file())
+        .collect();
+    dirs.sort();
+    dirs.iter().map(|dir| ExternalPlugin::load(dir)).collect()
+}
+
+/// Register the plugins installed in the [plugin
+/// directory](rustex_core::plugin_dir) with `manager`. Plugins that can't be
+/// loaded, or whose names are taken, are skipped with a warning.
+pub fn register_installed_plugins(manager: &mut PluginManager) {
+    let Some(dir) = rustex_core::plugin_dir() else {
+        return;
+    };
+    for plugin in installed_plugins(&dir) {
+        let registered = plugin.and_then(|plugin| manager.register_plugin(Box::new(plugin)));
+        if let Err(e) = registered {
+            warn!("Skipping installed plugin: {}", e);
+        }
+    }
+}
+
+/// Install the plugin in `source` to the plugin directory `dir`, replacing an
+/// installed plugin of the same name if `force` is set, and return its
+/// manifest.
+pub fn install_plugin(source: &Path, dir: &Path, force: bool) -> PluginResult<PluginManifest> {
+    let manifest = PluginManifest::load(source)?;
+    validate_plugin_name(&manifest.name)?;
+    if rustex_core::config_diagnostics::BUILTIN_PLUGINS.contains(&manifest.name.as_str()) {
+        return Err(PluginError::ConfigurationError(format!(
+            "'{}' is the name of a built-in plugin",
+            manifest.name
+        )));
+    }
+    let target = dir.join(&manifest.name);
+    if target.exists() {
+        if !force {
+            return Err(PluginError::ConfigurationError(format!(
+                "plugin '{}' is already installed in {}",
+                manifest.name,
+                target.display()
+            )));
+        }
+        // Reinstalling a plugin from its installed copy would delete it.
+        if std::fs::canonicalize(source)? == std::fs::canonicalize(&target)? {
+            return Ok(manifest);
+        }
+        std::fs::remove_dir_all(&target)?;
+    }
+    copy_dir(source, &target)?;
+    Ok(manifest)
+}
+
+/// Remove the plugin `name` from the plugin directory `dir`.
+pub fn uninstall_plugin(name: &str, dir: &Path) -> PluginResult<()> {
+    validate_plugin_name(name)?;
+    let target = dir.join(name);
+    if !target.join(PLUGIN_MANIFEST).is_file() {
+        return Err(PluginError::ConfigurationError(format!(
+            "plugin '{}' isn't installed in {}",
+            name,
+            dir.display()
+        )));
+    }
+    std::fs::remove_dir_all(&target)?;
+    Ok(())
+}
+
+/// Check that `name` can name a directory in the plugin directory: names
+/// can't be empty, start with `.` or contain path separators, so `..` and
+/// `a/b` can't reach outside of it.
+fn validate_plugin_name(name: &str) -> PluginResult<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(PluginError::ConfigurationError(format!(
+            "invalid plugin name '{}'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Copy the directory `source` and its contents to `target`.
+fn copy_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(target)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let path = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &path)?;
+        } else {
+            // Copies keep permissions, so commands stay executable.
+            std::fs::copy(entry.path(), &path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_plugin(dir: &Path, api_version: u32, script: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        let manifest = format!(
+            "name = \"echo\"\nversion = \"0.2.0\"\ndescription = \"Test plugin\"\n\
+             api_version = {}\nphases = [\"PostProject\"]\ncommand = \"sh\"\n\
+             args = [\"plugin.sh\"]\n",
+            api_version
+        );
+        std::fs::write(dir.join(PLUGIN_MANIFEST), manifest).unwrap();
+        std::fs::write(dir.join("plugin.sh"), script).unwrap();
+    }
+
+    fn project_info() -> ProjectInfo {
+        ProjectInfo {
+            name: "test".to_string(),
+            version: "0.1.0".to_string(),
+            rust_edition: "2021".to_string(),
+            root_path: PathBuf::from("/test"),
+        }
+    }
+
+    #[test]
+    fn test_manifest_api_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write_plugin(temp_dir.path(), PLUGIN_API_VERSION + 1, "");
+
+        match PluginManifest::load(temp_dir.path()) {
+            Err(PluginError::VersionIncompatible { .. }) => {}
+            other => panic!("Expected VersionIncompatible, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_plugin_execution() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let script = "read -r request\n\
+                      case \"$request\" in *'\"phase\":\"PostProject\"'*) ;; *) exit 1 ;; esac\n\
+                      echo \"$1\" >&2\n\
+                      echo '{\"metrics\": {\"answer\": 42.0}, \"has_modifications\": true}'\n";
+        write_plugin(temp_dir.path(), PLUGIN_API_VERSION, script);
+
+        let mut plugin = ExternalPlugin::load(temp_dir.path()).unwrap();
+        plugin
+            .initialize(&serde_json::json!({ "threshold": 3 }))
+            .unwrap();
+        assert_eq!(plugin.name(), "echo");
+        assert!(plugin.supports_phase(PluginPhase::PostProject));
+
+        let (project_info, config) = (project_info(), ExtractorConfig::default());
+        let mut metadata = HashMap::new();
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        let output = plugin.execute(PluginPhase::PostProject, &context).unwrap();
+        assert_eq!(output.metrics.get("answer"), Some(&42.0));
+        assert!(output.modified_elements.is_empty());
+        assert!(plugin.execute(PluginPhase::PreProcess, &context).is_err());
+
+        std::fs::write(
+            temp_dir.path().join("plugin.sh"),
+            "echo broken >&2\nexit 3\n",
+        )
+        .unwrap();
+        let error = plugin
+            .execute(PluginPhase::PostProject, &context)
+            .unwrap_err();
+        assert!(error.to_string().contains("broken"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_external_plugins_run_concurrently() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut manager = PluginManager::new();
+        for name in ["first", "second"] {
+            let dir = temp_dir.path().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            let manifest = format!(
+                "name = \"{}\"\nversion = \"0.1.0\"\napi_version = {}\n\
+                 phases = [\"PostProject\"]\ncommand = \"sh\"\nargs = [\"-c\", \"sleep 0.5\"]\n\
+                 default_enabled = true\n",
+                name, PLUGIN_API_VERSION
+            );
+            std::fs::write(dir.join(PLUGIN_MANIFEST), manifest).unwrap();
+            manager
+                .register_plugin(Box::new(ExternalPlugin::load(&dir).unwrap()))
+                .unwrap();
+        }
+
+        let (project_info, config) = (project_info(), ExtractorConfig::default());
+        let mut metadata = HashMap::new();
+        let context = PluginContext::new_pre_process(&project_info, &config, &mut metadata);
+        let start = std::time::Instant::now();
+        manager
+            .execute_phase_async(PluginPhase::PostProject, &context)
+            .await
+            .unwrap();
+        assert_eq!(manager.get_stats().total_successes, 2);
+        assert!(start.elapsed() < std::time::Duration::from_millis(900));
+    }
+
+    #[test]
+    fn test_install_and_uninstall_plugin() {
+        let source = tempfile::tempdir().unwrap();
+        let plugins = tempfile::tempdir().unwrap();
+        write_plugin(source.path(), PLUGIN_API_VERSION, "");
+
+        let manifest = install_plugin(source.path(), plugins.path(), false).unwrap();
+        assert_eq!(manifest.version, "0.2.0");
+        assert!(plugins.path().join("echo").join("plugin.sh").is_file());
+        assert!(install_plugin(source.path(), plugins.path(), false).is_err());
+        assert!(install_plugin(source.path(), plugins.path(), true).is_ok());
+        let installed_copy = plugins.path().join("echo");
+        assert!(install_plugin(&installed_copy, plugins.path(), true).is_ok());
+        assert!(installed_copy.join("plugin.sh").is_file());
+
+        let installed = installed_plugins(plugins.path());
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].as_ref().unwrap().name(), "echo");
+
+        uninstall_plugin("echo", plugins.path()).unwrap();
+        assert!(installed_plugins(plugins.path()).is_empty());
+        assert!(uninstall_plugin("echo", plugins.path()).is_err());
+    }
+
+    #[test]
+    fn test_plugin_names_stay_in_the_plugin_directory() {
+        // `..` of a plugin directory holding a manifest would be removed.
+        let parent = tempfile::tempdir().unwrap();
+        write_plugin(parent.path(), PLUGIN_API_VERSION, "");
+        let plugins = parent.path().join("plugins");
+        write_plugin(&plugins, PLUGIN_API_VERSION, "");
+
+        for name in ["..", ".", "", ".hidden", "a/b", "a\\b"] {
+            match uninstall_plugin(name, &plugins) {
+                Err(PluginError::ConfigurationError(message)) => {
+                    assert!(message.contains("invalid plugin name"), "{}", message)
+                }
+                other => panic!("Expected invalid name for {:?}, got {:?}", name, other),
+            }
+        }
+        assert!(parent.path().join(PLUGIN_MANIFEST).is_file());
+        assert!(plugins.join(PLUGIN_MANIFEST).is_file());
+
+        let source = tempfile::tempdir().unwrap();
+        write_plugin(source.path(), PLUGIN_API_VERSION, "");
+        let manifest = std::fs::read_to_string(source.path().join(PLUGIN_MANIFEST)).unwrap();
+        std::fs::write(
+            source.path().join(PLUGIN_MANIFEST),
+            manifest.replace("name = \"echo\"", "name = \"..\""),
+        )
+        .unwrap();
+        assert!(install_plugin(source.path(), &plugins, true).is_err());
+    }
+}
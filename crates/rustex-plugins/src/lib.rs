@@ -42,13 +42,21 @@
 
 pub mod core;
 pub mod builtin;
+pub mod external;
 pub mod utils;
 
 // Re-export core types for convenience
 pub use core::{
-    Plugin, PluginInfo, PluginContext, PluginOutput, PluginError, PluginPhase,
-    PluginManager, PreProcessContext, PostProcessContext
+    Plugin, AsyncPlugin, BlockingJob, PluginInfo, PluginContext, PluginOutput, PluginError,
+    PluginPhase, PluginManager, PreProcessContext, PostProcessContext, CountingAllocator,
+    PluginProfile,
 };
 
 // Re-export built-in plugins
 pub use builtin::*;
+
+// Re-export external plugin loading
+pub use external::{
+    install_plugin, register_installed_plugins, uninstall_plugin, ExternalPlugin, PluginManifest,
+    PLUGIN_API_VERSION,
+};
@@ -152,7 +152,7 @@ impl MetricHelpers {
         values.sort_by(|a, b| a.partial_cmp(b).unwrap());
         let len = values.len();
         
-        if len % 2 == 0 {
+        if len.is_multiple_of(2) {
             (values[len / 2 - 1] + values[len / 2]) / 2.0
         } else {
             values[len / 2]
@@ -318,6 +318,7 @@ mod tests {
             signature: Some(format!("fn {}()", name)),
             visibility: Visibility::Public,
             doc_comments: vec![],
+            doc_examples: vec![],
             inline_comments: vec![],
             location: CodeLocation {
                 line_start: 1,
@@ -325,7 +326,10 @@ mod tests {
                 char_start: 0,
                 char_end: 100,
                 file_path: PathBuf::from("test.rs"),
+                byte_start: 0,
+                byte_end: 0,
             },
+            name_location: None,
             attributes: vec![],
             complexity,
             complexity_metrics: None,
@@ -341,16 +345,20 @@ mod tests {
                     &Visibility::Public,
                 ),
             ),
+            is_generated: false,
+            cfg_predicates: vec![],
+            attribute_info: vec![],
+            deprecation: None,
+            calls: vec![],
         }
     }
 
     #[test]
     fn test_element_filtering() {
-        let elements = vec![
+        let elements = [
             create_test_element("func1", ElementType::Function, Some(5)),
             create_test_element("struct1", ElementType::Struct, Some(2)),
-            create_test_element("func2", ElementType::Function, Some(15)),
-        ];
+            create_test_element("func2", ElementType::Function, Some(15))];
         
         let element_refs: Vec<&CodeElement> = elements.iter().collect();
         
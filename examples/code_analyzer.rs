@@ -95,8 +95,8 @@ fn perform_code_analysis(project_ast: &rustex_core::ProjectAst) -> CodeAnalysis
                 // Quality issues
                 if complexity > 15 {
                     quality_issues.push(format!(
-                        "Very high complexity in {}: {} ({})",
-                        element.name, complexity, format!("{:?}", element.element_type)
+                        "Very high complexity in {}: {} ({:?})",
+                        element.name, complexity, element.element_type
                     ));
                 }
             }
@@ -381,8 +381,8 @@ fn generate_analysis_report(
     // Detailed Metrics
     report.push_str("## Detailed Metrics\n\n");
     report.push_str("### Project Statistics\n\n");
-    report.push_str(&format!("| Metric | Value |\n"));
-    report.push_str(&format!("|--------|-------|\n"));
+    report.push_str("| Metric | Value |\n");
+    report.push_str("|--------|-------|\n");
     report.push_str(&format!("| Files | {} |\n", project_ast.metrics.total_files));
     report.push_str(&format!("| Lines of Code | {} |\n", project_ast.metrics.total_lines));
     report.push_str(&format!("| Functions | {} |\n", project_ast.metrics.total_functions));
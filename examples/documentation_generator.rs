@@ -159,8 +159,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             doc.push_str(&format!("### {} (Private)\n\n", file_path.display()));
             
             for element in items {
-                doc.push_str(&format!("- **{}** `{}` ", 
-                    format!("{:?}", element.element_type), element.name));
+                doc.push_str(&format!("- **{:?}** `{}` ", 
+                    element.element_type, element.name));
                 
                 if let Some(complexity) = element.complexity {
                     doc.push_str(&format!("*(complexity: {})*", complexity));
@@ -214,8 +214,8 @@ fn create_function_example(element: &rustex_core::CodeElement) -> (String, Strin
                 element.signature.as_ref().unwrap_or(&element.name))
     };
     
-    let output = if !element.doc_comments.is_empty() && element.signature.is_some() {
-        element.signature.as_ref().unwrap().clone()
+    let output = if let (false, Some(signature)) = (element.doc_comments.is_empty(), &element.signature) {
+        signature.clone()
     } else if !element.doc_comments.is_empty() {
         element.doc_comments.join("\n")
     } else {
@@ -302,11 +302,11 @@ fn create_code_chunk(
     };
     
     let context = format!(
-        "File: {}, Module: {}, Element: {} ({})",
+        "File: {}, Module: {}, Element: {} ({:?})",
         file.relative_path.display(),
         element.hierarchy.module_path,
         element.name,
-        format!("{:?}", element.element_type)
+        element.element_type
     );
     
     let complexity_range = if let Some(complexity) = element.complexity {
@@ -404,13 +404,13 @@ fn create_qa_pairs(
     }
     
     // 3. What are the parameters/fields? (for functions/structs)
-    if element.element_type == ElementType::Function && element.signature.is_some() {
+    if let (ElementType::Function, Some(signature)) = (&element.element_type, &element.signature) {
         *id += 1;
         qa_pairs.push(QAPair {
             id: format!("qa_{}", id),
             question: format!("What are the parameters of the function `{}`?", element.name),
             answer: format!("Function signature: {}\n\n{}", 
-                           element.signature.as_ref().unwrap(), 
+                           signature, 
                            documentation),
             context: context.clone(),
             difficulty: "intermediate".to_string(),
@@ -530,7 +530,7 @@ fn print_statistics(dataset: &TrainingDataset) {
     
     println!("\n🧠 Complexity Distribution:");
     for (level, count) in &dataset.metadata.complexity_distribution {
-        let bar = "█".repeat((*count / 10).max(1).min(50));
+        let bar = "█".repeat((*count / 10).clamp(1, 50));
         println!("  {}: {} {}", level, count, bar);
     }
     
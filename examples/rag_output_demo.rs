@@ -74,7 +74,7 @@ fn demonstrate_custom_rag_config(project_ast: &rustex_core::ProjectAst) -> Resul
         target_chunk_size: 256,  // Smaller chunks for faster embedding
         max_chunk_size: 512,
         min_chunk_size: 50,
-        chunk_overlap: 25,
+        chunk_overlap_tokens: 25,
         generate_training_examples: true,
         max_training_examples_per_chunk: 2,
         semantic_analysis_depth: SemanticDepth::Deep,
@@ -163,8 +163,11 @@ fn demonstrate_embedding_format(project_ast: &rustex_core::ProjectAst) -> Result
     
     // Analyze embedding strategies
     let mut strategy_counts = std::collections::HashMap::new();
-    for input in &embedding_inputs {
-        let strategy_name = match input.metadata.embedding_strategy {
+    for metadata in embedding_inputs
+        .iter()
+        .filter_map(|input| input.metadata.as_ref())
+    {
+        let strategy_name = match metadata.embedding_strategy {
             rustex_formats::EmbeddingStrategy::Combined => "Combined",
             rustex_formats::EmbeddingStrategy::CodeOnly => "Code Only",
             rustex_formats::EmbeddingStrategy::DocumentationOnly => "Documentation Only",
@@ -183,12 +186,16 @@ fn demonstrate_embedding_format(project_ast: &rustex_core::ProjectAst) -> Result
     std::fs::write("rag-embeddings.json", embedding_json)?;
     println!("📄 Saved to: rag-embeddings.json");
     
-    // Show sample embedding input
-    if let Some(first_input) = embedding_inputs.first() {
+    // Show sample embedding input, of the first chunk
+    if let Some((first_input, metadata)) = embedding_inputs
+        .iter()
+        .find_map(|input| Some((input, input.metadata.as_ref()?)))
+    {
         println!("\n📝 Sample Embedding Input:");
         println!("  ID: {}", first_input.id);
-        println!("  Strategy: {:?}", first_input.metadata.embedding_strategy);
-        println!("  Keywords: {:?}", first_input.metadata.retrieval_keywords.iter().take(5).collect::<Vec<_>>());
+        println!("  Granularity: {:?}", first_input.granularity);
+        println!("  Strategy: {:?}", metadata.embedding_strategy);
+        println!("  Keywords: {:?}", metadata.retrieval_keywords.iter().take(5).collect::<Vec<_>>());
         println!("  Text preview: {}...", 
                  first_input.text.chars().take(150).collect::<String>());
     }